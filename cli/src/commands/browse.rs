@@ -0,0 +1,286 @@
+#![warn(clippy::all)]
+
+//! Browsing the databases: recent and random titles matching filters, the known genres, and
+//! exports of the titles for other tools.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::commands::timed_query;
+use crate::context::{create_output_printer, Context};
+use crate::error::Error;
+use crate::export::{self, ExportFormat, ExportKind};
+use crate::opts::{parse_genre, GeneralOpts, SearchOpts, Status};
+use crate::print::{Error as PrintError, Printer};
+use crate::query::Query;
+
+use tvrank::imdb::{Imdb, ImdbFilter, ImdbGenre, ImdbQuery};
+use tvrank::utils::sample::Sampler;
+
+use reqwest::Url;
+
+#[derive(Debug, clap::Args)]
+pub struct NewArgs {
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+
+  #[clap(flatten)]
+  search_opts: SearchOpts,
+}
+
+impl NewArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { general_opts, search_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let query = context.query(recent(search_opts))?;
+      let printer = create_output_printer(&query.opts().output, &context.general_opts);
+      let matches = timed_query(|| imdb_new(&context.service, &context.imdb_url, &query, printer))?;
+      Ok(Some(matches))
+    })
+  }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct RandomArgs {
+  /// Number of movies and of series to pick
+  #[clap(short = 'n', long, value_name = "N", default_value = "1")]
+  count: usize,
+
+  /// Seed to pick the same titles again, e.g. to share a selection (random by default)
+  #[clap(long, value_name = "SEED")]
+  seed: Option<u64>,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+
+  #[clap(flatten)]
+  search_opts: SearchOpts,
+}
+
+impl RandomArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { count, seed, general_opts, search_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let query = context.query(search_opts)?;
+      let printer = create_output_printer(&query.opts().output, &context.general_opts);
+      let matches =
+        timed_query(|| imdb_random(&context.service, &context.imdb_url, &query, count, seed, printer))?;
+      Ok(Some(matches))
+    })
+  }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct GenresArgs {
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+}
+
+impl GenresArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    Context::new(self.general_opts, globals).run(|context| {
+      print_genres(&context.service);
+      Ok(None)
+    })
+  }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ExportArgs {
+  /// Set export format
+  #[clap(long, value_enum, default_value = "jsonl")]
+  format: ExportFormat,
+
+  /// Write the titles to FILE instead of the standard output
+  #[clap(long, value_name = "FILE")]
+  out: Option<PathBuf>,
+
+  /// Only export movies or series
+  #[clap(long, value_enum)]
+  kind: Option<ExportKind>,
+
+  /// Only export titles released in or after YEAR
+  #[clap(long, value_name = "YEAR", conflicts_with = "last_n_years")]
+  released_since: Option<u16>,
+
+  /// Only export titles released in the last N years, including the current one
+  #[clap(long, value_name = "YEARS")]
+  last_n_years: Option<u16>,
+
+  /// Only export titles of the given genre (can be specified multiple times)
+  #[clap(short, long, value_parser = parse_genre)]
+  genre: Vec<ImdbGenre>,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+}
+
+impl ExportArgs {
+  fn filter(&self) -> ImdbFilter {
+    let mut filter = ImdbFilter::new();
+    if let Some(year) = self.released_since {
+      filter = filter.released_since(year);
+    }
+    if let Some(years) = self.last_n_years {
+      filter = filter.last_n_years(years);
+    }
+    for &genre in &self.genre {
+      filter = filter.genre(genre);
+    }
+    filter
+  }
+
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let filter = self.filter();
+    let Self { format, out, kind, general_opts, .. } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let count = imdb_export(&context.service, &filter, kind, format, out.as_deref())?;
+      if !context.general_opts.quiet {
+        eprintln!("Exported {count} titles");
+      }
+      Ok(Some(count))
+    })
+  }
+}
+
+/// Number of titles listed by the `new` command unless `--top` is given.
+const NEW_DEFAULT_TOP: usize = 25;
+
+/// The search options of the `new` command, which lists the last year's titles unless
+/// release years are given, and only the top ones unless `--top` is given.
+fn recent(mut search_opts: SearchOpts) -> SearchOpts {
+  search_opts.top = search_opts.top.or(Some(NEW_DEFAULT_TOP));
+  let has_years = search_opts.released_since.is_some()
+    || search_opts.last_n_years.is_some()
+    || search_opts.decade.is_some()
+    || search_opts.era.is_some();
+  if !has_years {
+    search_opts.last_n_years = Some(1);
+  }
+
+  search_opts
+}
+
+fn imdb_new(
+  imdb: &Imdb,
+  imdb_url: &Url,
+  query: &Query,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<usize, Error> {
+  let filter = query.opts().filter();
+
+  let mut movies_results = query.new_results(query.opts().top);
+  let mut series_results = query.new_results(query.opts().top);
+  movies_results.extend(imdb.iter_by_filter(&filter, ImdbQuery::Movies));
+  series_results.extend(imdb.iter_by_filter(&filter, ImdbQuery::Series));
+
+  let search_terms = describe_filters(query.opts(), &filter);
+  let matches = printer.print(Some(movies_results), Some(series_results), imdb_url, Some(&search_terms))?;
+  Ok(matches)
+}
+
+/// Description of the filters of a query, e.g. "titles released since 2020 in Drama".
+fn describe_filters(search_opts: &SearchOpts, filter: &ImdbFilter) -> String {
+  let mut search_terms = String::from("titles");
+  match (filter.get_released_since(), filter.get_released_until()) {
+    (Some(start), Some(end)) => search_terms.push_str(&format!(" released between {start} and {end}")),
+    (Some(start), None) => search_terms.push_str(&format!(" released since {start}")),
+    (None, Some(end)) => search_terms.push_str(&format!(" released until {end}")),
+    (None, None) => {}
+  }
+  if !search_opts.genre.is_empty() {
+    let genres: Vec<_> = search_opts.genre.iter().map(ToString::to_string).collect();
+    search_terms.push_str(&format!(" in {}", genres.join(", ")));
+  }
+  match (filter.get_min_votes(), filter.get_max_votes()) {
+    (Some(min), Some(max)) => search_terms.push_str(&format!(" with {min} to {max} votes")),
+    (Some(min), None) => search_terms.push_str(&format!(" with at least {min} votes")),
+    (None, Some(max)) => search_terms.push_str(&format!(" with at most {max} votes")),
+    (None, None) => {}
+  }
+  match search_opts.status {
+    Some(Status::Running) => search_terms.push_str(" still running"),
+    Some(Status::Ended) => search_terms.push_str(" that have ended"),
+    None => {}
+  }
+  search_terms
+}
+
+pub fn imdb_random(
+  imdb: &Imdb,
+  imdb_url: &Url,
+  query: &Query,
+  count: usize,
+  seed: Option<u64>,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<usize, Error> {
+  let seed = seed.unwrap_or_else(Sampler::random_seed);
+  let mut sampler = Sampler::new(seed);
+  let filter = query.opts().filter();
+
+  // Hidden titles are only known after sampling, so all the titles are drawn in random
+  // order and the first ones that are not hidden are kept.
+  let exclusion = query.exclusion();
+  let draw = if exclusion.is_some() {
+    usize::MAX
+  } else {
+    count
+  };
+  let mut sample = |query| {
+    let titles = imdb.sample(&filter, query, draw, &mut sampler).into_iter();
+    titles
+      .filter(|title| !exclusion.as_ref().is_some_and(|exclusion| exclusion(title)))
+      .take(count)
+  };
+
+  let mut movies_results = query.new_results(None);
+  let mut series_results = query.new_results(None);
+  movies_results.extend(sample(ImdbQuery::Movies));
+  series_results.extend(sample(ImdbQuery::Series));
+
+  let search_terms = format!("random {} (seed {seed})", describe_filters(query.opts(), &filter));
+  let matches = printer.print(Some(movies_results), Some(series_results), imdb_url, Some(&search_terms))?;
+  Ok(matches)
+}
+
+fn imdb_export<'a>(
+  imdb: &'a Imdb,
+  filter: &'a ImdbFilter,
+  kind: Option<ExportKind>,
+  format: ExportFormat,
+  out: Option<&Path>,
+) -> Result<usize, Error> {
+  let titles = ExportKind::queries(kind)
+    .iter()
+    .flat_map(move |&query| imdb.iter_by_filter(filter, query));
+
+  let count = match out {
+    Some(out) => export::write_titles(titles, format, io::BufWriter::new(fs::File::create(out)?))?,
+    None => export::write_titles(titles, format, io::BufWriter::new(io::stdout().lock()))?,
+  };
+
+  Ok(count)
+}
+
+/// Print the known genres with the number of movies and series of each.
+fn print_genres(imdb: &Imdb) {
+  let mut counts: HashMap<ImdbGenre, [usize; 2]> = HashMap::new();
+  for (i, query) in [ImdbQuery::Movies, ImdbQuery::Series].into_iter().enumerate() {
+    for title in imdb.titles(query) {
+      for genre in title.genres().iter() {
+        counts.entry(genre).or_default()[i] += 1;
+      }
+    }
+  }
+
+  println!("{:<14} {:>10} {:>10}", "Genre", "Movies", "Series");
+  for genre in ImdbGenre::all() {
+    let [movies, series] = counts.get(&genre).copied().unwrap_or_default();
+    println!("{:<14} {movies:>10} {series:>10}", genre.to_string());
+  }
+}
@@ -0,0 +1,186 @@
+#![warn(clippy::all)]
+
+//! Managing the internal databases and listing where they and the runtime state are.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::context::{
+  create_cache_dir, create_imdb_service, get_paths, record_database_update, run_without_service,
+  update_imdb_service, with_download_progress, Context,
+};
+use crate::error::Error;
+use crate::opts::{merge_general_opts, GeneralOpts};
+use crate::ui::ProgressFormat;
+
+use tvrank::imdb::{Imdb, ImdbColumnValue, ImdbQuery, ImdbUpdatePolicy};
+use tvrank::paths::Paths;
+
+use reqwest::Url;
+use tracing::error;
+
+#[derive(Debug, clap::Subcommand)]
+pub enum DbCommand {
+  /// Download pre-built databases instead of building them from the IMDB dumps
+  FetchPrebuilt {
+    /// URL of a bundle written by `db publish`
+    #[clap(name = "URL")]
+    url: Url,
+
+    #[clap(flatten)]
+    general_opts: GeneralOpts,
+  },
+
+  /// Describe the databases
+  Info {
+    /// Also display an estimate of the memory used by the loaded databases, per shard
+    #[clap(long)]
+    memory: bool,
+
+    #[clap(flatten)]
+    general_opts: GeneralOpts,
+  },
+
+  /// Write the databases as a bundle that can be installed with `db fetch-prebuilt`
+  Publish {
+    /// File to write the bundle to
+    #[clap(name = "FILE")]
+    file: PathBuf,
+
+    #[clap(flatten)]
+    general_opts: GeneralOpts,
+  },
+
+  /// Update the databases if they are outdated, unless they are already being updated
+  Update {
+    #[clap(flatten)]
+    general_opts: GeneralOpts,
+  },
+}
+
+impl DbCommand {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    match self {
+      Self::FetchPrebuilt { url, general_opts } => {
+        run_without_service(general_opts, globals, |general_opts| {
+          db_fetch_prebuilt(url, general_opts)?;
+          Ok(None)
+        })
+      }
+      Self::Info { memory, general_opts } => Context::new(general_opts, globals).run(|context| {
+        db_info(&context.service, memory);
+        Ok(None)
+      }),
+      Self::Publish { file, general_opts } => Context::new(general_opts, globals).run(|context| {
+        db_publish(&file, &context.general_opts)?;
+        Ok(None)
+      }),
+      Self::Update { general_opts } => run_without_service(general_opts, globals, |general_opts| {
+        let paths = get_paths(general_opts)?;
+        if let Some(imdb) = update_imdb_service(&paths, general_opts, general_opts.force_update)? {
+          std::mem::forget(imdb);
+        }
+        Ok(None)
+      }),
+    }
+  }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct PathsArgs {
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+}
+
+impl PathsArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let general_opts = merge_general_opts(self.general_opts, globals);
+    let paths = fail!(false, get_paths(&general_opts));
+    print_paths(&paths);
+    (general_opts.quiet, None)
+  }
+}
+
+fn print_paths(paths: &Paths) {
+  println!("Cache:          {}", paths.cache_dir().display());
+  println!("State:          {}", paths.state_dir().display());
+  if let Some(profile) = paths.profile() {
+    println!("Profile:        {} ({})", profile, paths.profile_dir().display());
+  }
+  println!("Rating history: {}", paths.history_dir().display());
+  println!("Retired IDs:    {}", paths.tombstones_dir().display());
+  println!("Enrichment:     {}", paths.enrichment_dir().display());
+  println!("Trakt:          {}", paths.trakt_dir().display());
+  println!("Watchlist:      {}", paths.watchlist_file().display());
+  println!("Log file:       {}", paths.log_file().display());
+  println!("Cache version:  {}", Imdb::cache_version());
+}
+
+fn db_fetch_prebuilt(url: Url, general_opts: &GeneralOpts) -> Result<(), Error> {
+  let paths = get_paths(general_opts)?;
+  let app_cache_dir = create_cache_dir(&paths)?;
+  let progress_format = general_opts.progress.unwrap_or(ProgressFormat::Bar);
+
+  with_download_progress(progress_format, "Downloading pre-built IMDB databases...", |progress_fn| {
+    Imdb::fetch_prebuilt(app_cache_dir, url, progress_fn)
+  })?;
+
+  // The installed databases replace a rebuild, so their ratings are recorded the same way.
+  let imdb = create_imdb_service(&paths, ImdbUpdatePolicy::Never, false, general_opts)?;
+  record_database_update(&imdb, &paths);
+  std::mem::forget(imdb);
+
+  if !general_opts.quiet {
+    eprintln!("Installed pre-built IMDB databases into {}", app_cache_dir.display());
+  }
+
+  Ok(())
+}
+
+fn format_bytes(bytes: usize) -> String {
+  ImdbColumnValue::Bytes(bytes as u64).to_string()
+}
+
+fn db_info(imdb: &Imdb, memory: bool) {
+  println!("Built at:       {}", humantime::format_rfc3339_seconds(imdb.built_at()));
+  println!("Cache version:  {}", Imdb::cache_version());
+  println!("Movies:         {}", imdb.n_titles(ImdbQuery::Movies));
+  println!("Series:         {}", imdb.n_titles(ImdbQuery::Series));
+
+  if !memory {
+    return;
+  }
+
+  let stats = imdb.memory_stats();
+  let shards = stats.shards_total();
+  println!();
+  println!("Estimated memory usage:");
+  println!("  {:<8} {:>12} {:>12} {:>12} {:>12}", "Shard", "Titles", "By ID", "By title", "Total");
+
+  let rows = stats.shards().iter().enumerate().map(|(i, shard)| (i.to_string(), shard));
+  for (name, shard) in rows.chain([(String::from("All"), &shards)]) {
+    println!(
+      "  {name:<8} {:>12} {:>12} {:>12} {:>12}",
+      format_bytes(shard.titles()),
+      format_bytes(shard.by_id()),
+      format_bytes(shard.by_title()),
+      format_bytes(shard.total()),
+    );
+  }
+
+  println!("  Database files: {}", format_bytes(stats.storage()));
+  println!("  Total:          {}", format_bytes(stats.total()));
+}
+
+fn db_publish(file: &Path, general_opts: &GeneralOpts) -> Result<(), Error> {
+  let paths = get_paths(general_opts)?;
+  let writer = io::BufWriter::new(fs::File::create(file)?);
+  Imdb::publish_prebuilt(paths.cache_dir(), writer)?;
+
+  if !general_opts.quiet {
+    eprintln!("Published pre-built IMDB databases to {}", file.display());
+  }
+
+  Ok(())
+}
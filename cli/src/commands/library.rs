@@ -0,0 +1,622 @@
+#![warn(clippy::all)]
+
+//! Managing a library: marking directories with their titles, checking them, finding
+//! duplicates, summarizing them and prefetching data of other providers for them.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::commands::scan::{resolve_scan_dir, ScanDir};
+use crate::commands::{display_title_and_year, parse_title_and_year, timed_query};
+use crate::context::{create_enricher, create_output_printer, Context};
+use crate::error::Error;
+use crate::opts::GeneralOpts;
+use crate::print::{Error as PrintError, OutputFormat, Printer};
+use crate::usage::dir_size;
+
+use tvrank::enrich::{Enricher, Lookup, Provider as ImdbEnrichmentProvider};
+use tvrank::imdb::{Imdb, ImdbColumnValue, ImdbQuery, ImdbRetired, ImdbStats, ImdbTitle, ImdbTitleId};
+use tvrank::provider::TitleProvider;
+use tvrank::title_info::TitleInfo;
+use tvrank::utils::search::{SearchOptions, SearchString};
+
+use reqwest::Url;
+use tracing::{debug, warn};
+use walkdir::WalkDir;
+
+#[derive(Debug, clap::Args)]
+pub struct MarkArgs {
+  /// Directory of title in "TITLE [(YYYY)]"
+  #[clap(name = "DIR")]
+  dir: PathBuf,
+
+  /// The unique IMDB ID ("ttXXXXX" which can be found in the URL)
+  #[clap(name = "IMDB-ID")]
+  id: String,
+
+  /// Force overwriting of the title information (tvrank.json) file
+  #[clap(long)]
+  force: bool,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+}
+
+impl MarkArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { dir, id, force, general_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      timed_query(|| imdb_mark(&dir, &id, &context.service, force))?;
+      Ok(None)
+    })
+  }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct MarkBulkArgs {
+  /// The mapping file, e.g. written from the ambiguous entries of a scan report (`-` reads
+  /// from stdin)
+  #[clap(name = "FILE")]
+  file: PathBuf,
+
+  /// Force overwriting of existing title information (tvrank.json) files
+  #[clap(long)]
+  force: bool,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+}
+
+impl MarkBulkArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { file, force, general_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      timed_query(|| imdb_mark_bulk(&file, &context.service, force))?;
+      Ok(None)
+    })
+  }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct VerifyArgs {
+  /// Directory to recursively check
+  #[clap(value_name = "DIR")]
+  dir: PathBuf,
+
+  /// Re-resolve directories whose IMDB ID was retired by their title and year, and update
+  /// their title information files
+  #[clap(long)]
+  fix_retired: bool,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+}
+
+impl VerifyArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { dir, fix_retired, general_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let retired = context.tombstones.load()?;
+      imdb_verify(&dir, &context.service, &retired, fix_retired)?;
+      Ok(None)
+    })
+  }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct DedupeArgs {
+  /// Directory to recursively check
+  #[clap(value_name = "DIR")]
+  dir: PathBuf,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+}
+
+impl DedupeArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { dir, general_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let search = context.service.with_options(SearchOptions::default());
+      imdb_dedupe(&dir, &context.providers(&search))?;
+      Ok(None)
+    })
+  }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ReportArgs {
+  /// Directory to recursively scan
+  #[clap(value_name = "DIR")]
+  dir: PathBuf,
+
+  /// Number of best and worst rated titles to display
+  #[clap(short = 'n', long, name = "N", default_value = "10")]
+  top: usize,
+
+  /// Set output format
+  #[clap(short, long, value_enum, default_value = "table")]
+  output: OutputFormat,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+}
+
+impl ReportArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { dir, top, output, general_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let search = context.service.with_options(SearchOptions::default());
+      let printer = create_output_printer(&output, &context.general_opts);
+      let matches = imdb_library_report(&dir, &context.providers(&search), &context.imdb_url, top, printer)?;
+      Ok(Some(matches))
+    })
+  }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct EnrichArgs {
+  /// Library directory of movie and series folders, resolved like in scans
+  #[clap(long, value_name = "DIR")]
+  library: PathBuf,
+
+  /// Providers to fetch from, separated by commas (API keys are read from the
+  /// TVRANK_TMDB_API_KEY and TVRANK_OMDB_API_KEY environment variables)
+  #[clap(long, value_name = "PROVIDERS", value_delimiter = ',', default_value = "tmdb,omdb")]
+  providers: Vec<ImdbEnrichmentProvider>,
+
+  /// Minimum delay between requests (e.g. "250ms" or "1s")
+  #[clap(long, value_name = "DURATION", default_value = "250ms", value_parser = humantime::parse_duration)]
+  delay: Duration,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+}
+
+impl EnrichArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { library, providers, delay, general_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let enricher = create_enricher(&context.paths, &providers, delay)?;
+      imdb_enrich(&library, &context.service, enricher, &providers)?;
+      Ok(None)
+    })
+  }
+}
+
+fn imdb_mark(dir: &Path, id: &str, imdb: &Imdb, force: bool) -> Result<(), Error> {
+  // TODO: Check if the directory follows the naming convention.
+  // TODO: Check if the imdb id matches the title and year of the directory name.
+
+  let title_id = ImdbTitleId::try_from(id)?;
+
+  if !dir.is_dir() {
+    return Err(Error::NotDir(dir.to_owned()));
+  }
+
+  if imdb.by_id_any(&title_id).is_none() {
+    return Err(Error::UnknownImdbId(id.to_owned()));
+  }
+
+  write_title_info(dir, title_id, force)
+}
+
+/// Parse a "DIR,IMDB-ID" row of a mapping file. Directories may contain commas and may be
+/// quoted.
+fn parse_mapping_row(row: &str) -> Option<(&str, &str)> {
+  let (dir, id) = row.rsplit_once(',')?;
+  let dir = dir.trim();
+  let dir = dir.strip_prefix('"').and_then(|dir| dir.strip_suffix('"')).unwrap_or(dir);
+  Some((dir, id.trim()))
+}
+
+fn imdb_mark_bulk(file: &Path, imdb: &Imdb, force: bool) -> Result<(), Error> {
+  let reader: Box<dyn BufRead> = if file == Path::new("-") {
+    Box::new(io::stdin().lock())
+  } else {
+    Box::new(BufReader::new(fs::File::open(file)?))
+  };
+
+  let mut rows = 0;
+  let mut failed = 0;
+
+  for (index, line) in reader.lines().enumerate() {
+    let line = line?;
+    let row = line.trim();
+    let number = index + 1;
+
+    if row.is_empty() || row.starts_with('#') {
+      continue;
+    }
+
+    let Some((dir, id)) = parse_mapping_row(row) else {
+      println!("Row {number}: expected DIR,IMDB-ID but got `{row}`");
+      rows += 1;
+      failed += 1;
+      continue;
+    };
+
+    // Skip a header row such as "path,imdb_id".
+    if number == 1 && !id.starts_with("tt") {
+      continue;
+    }
+
+    rows += 1;
+
+    if let Err(e) = imdb_mark(Path::new(dir), id, imdb, force) {
+      println!("Row {number}: `{dir}`: {e}");
+      failed += 1;
+    }
+  }
+
+  println!("Marked {} of {rows} directories, {failed} failed", rows - failed);
+
+  if failed > 0 {
+    return Err(Error::MarkBulk(failed, rows));
+  }
+
+  Ok(())
+}
+
+fn write_title_info(dir: &Path, title_id: ImdbTitleId, force: bool) -> Result<(), Error> {
+  let title_info = TitleInfo::new(title_id);
+
+  let title_info_path = dir.join("tvrank.json");
+  let mut file = OpenOptions::new()
+    .create(true)
+    .truncate(true)
+    .write(true)
+    .create_new(!force)
+    .open(title_info_path)?;
+  file.write_all(serde_json::to_string_pretty(&title_info)?.as_bytes())?;
+
+  Ok(())
+}
+
+/// Find the titles a directory refers to by its name, either "TITLE (YYYY)" for movies and
+/// series or "TITLE" for series.
+fn resolve_dir<'a>(dir: &Path, imdb: &'a Imdb) -> Result<Vec<&'a ImdbTitle<'a>>, Error> {
+  let Some(filename) = dir.file_name() else {
+    return Ok(Vec::new());
+  };
+
+  let filename = filename.to_string_lossy();
+
+  if let Some((title, year)) = parse_title_and_year(&filename) {
+    let search_string = SearchString::try_from(title)?;
+    let mut titles = imdb.by_title_and_year(&search_string, year, ImdbQuery::Movies);
+    titles.extend(imdb.by_title_and_year(&search_string, year, ImdbQuery::Series));
+    Ok(titles)
+  } else {
+    Ok(imdb.by_title(&SearchString::try_from(filename.as_ref())?, ImdbQuery::Series))
+  }
+}
+
+fn imdb_verify(dir: &Path, imdb: &Imdb, retired: &ImdbRetired, fix_retired: bool) -> Result<(), Error> {
+  let mut checked = 0;
+  let mut problems = 0;
+  let mut fixed = 0;
+  let mut dirs_by_id: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+
+  for entry in WalkDir::new(dir).sort_by_file_name() {
+    let entry = entry?;
+
+    if !entry.file_type().is_dir() {
+      continue;
+    }
+
+    let entry_path = entry.path();
+    let Ok(title_info) = TitleInfo::from_path(entry_path) else {
+      continue;
+    };
+
+    checked += 1;
+    let id = title_info.imdb().id();
+    let path = entry_path.display();
+    dirs_by_id.entry(id.to_string()).or_default().push(entry_path.to_owned());
+
+    if let Some((title, _)) = imdb.by_id_any(id) {
+      if let Some(disagreement) = name_disagreement(&entry.file_name().to_string_lossy(), title) {
+        let name = match title.start_year() {
+          Some(year) => display_title_and_year(title.primary_title(), year),
+          None => title.primary_title().to_owned(),
+        };
+        println!("`{path}`: IMDB ID `{id}` is `{name}`, {disagreement}");
+        problems += 1;
+      }
+      continue;
+    }
+
+    let Some(retired_at) = retired.retired_at(id) else {
+      println!("`{path}`: unknown IMDB ID `{id}`");
+      problems += 1;
+      continue;
+    };
+
+    let retired_at = humantime::format_rfc3339_seconds(retired_at);
+
+    if !fix_retired {
+      println!("`{path}`: IMDB ID `{id}` was retired on {retired_at}");
+      problems += 1;
+      continue;
+    }
+
+    match resolve_dir(entry_path, imdb)?.as_slice() {
+      [title] => {
+        write_title_info(entry_path, *title.title_id(), true)?;
+        println!("`{path}`: IMDB ID `{id}` was retired, updated to `{}`", title.title_id());
+        fixed += 1;
+      }
+      [] => {
+        println!("`{path}`: IMDB ID `{id}` was retired, and no title matches the directory name");
+        problems += 1;
+      }
+      titles => {
+        println!(
+          "`{path}`: IMDB ID `{id}` was retired, and {} titles match the directory name",
+          titles.len()
+        );
+        problems += 1;
+      }
+    }
+  }
+
+  for (id, dirs) in dirs_by_id.iter().filter(|(_, dirs)| dirs.len() > 1) {
+    println!("IMDB ID `{id}` is used by {} directories:", dirs.len());
+    for dir in dirs {
+      println!("  `{}`", dir.display());
+    }
+    problems += 1;
+  }
+
+  println!("Checked {checked} title information files: {fixed} fixed, {problems} problems");
+
+  if problems > 0 {
+    return Err(Error::VerifyProblems(problems));
+  }
+
+  Ok(())
+}
+
+/// The directories under `dir` that resolve to exactly one title, like `scan` resolves them,
+/// along with their titles.
+fn scan_titles<'a>(
+  dir: &Path,
+  provider: &'a dyn TitleProvider,
+) -> Result<Vec<(PathBuf, &'a ImdbTitle<'a>)>, Error> {
+  if !dir.is_dir() {
+    return Err(Error::NotDir(dir.to_owned()));
+  }
+
+  let mut titles = Vec::new();
+  let mut walkdir = WalkDir::new(dir).sort_by_file_name().min_depth(1).into_iter();
+
+  while let Some(entry) = walkdir.next() {
+    let entry = entry?;
+
+    if !entry.file_type().is_dir() {
+      continue;
+    }
+
+    let title = match resolve_scan_dir(entry.path(), provider)? {
+      ScanDir::Container => continue,
+      ScanDir::Skipped => None,
+      ScanDir::Identified(title, _) => Some(title),
+      ScanDir::Named { movies, series, .. } => {
+        match movies.iter().chain(&series).flatten().collect::<Vec<_>>()[..] {
+          [title] => Some(*title),
+          _ => None,
+        }
+      }
+    };
+
+    walkdir.skip_current_dir();
+    if let Some(title) = title {
+      titles.push((entry.into_path(), title));
+    }
+  }
+
+  Ok(titles)
+}
+
+/// Find the titles that several directories under `dir` resolve to, like `scan` resolves
+/// them, and list their directories with their sizes.
+fn imdb_dedupe(dir: &Path, provider: &dyn TitleProvider) -> Result<(), Error> {
+  let titles = scan_titles(dir, provider)?;
+  let mut dirs_by_id: BTreeMap<String, (&ImdbTitle, Vec<PathBuf>)> = BTreeMap::new();
+
+  for (dir, title) in &titles {
+    dirs_by_id
+      .entry(title.title_id().to_string())
+      .or_insert_with(|| (title, Vec::new()))
+      .1
+      .push(dir.clone());
+  }
+
+  let mut duplicates = 0;
+  for (id, (title, dirs)) in dirs_by_id.iter().filter(|(_, (_, dirs))| dirs.len() > 1) {
+    let name = match title.start_year() {
+      Some(year) => display_title_and_year(title.primary_title(), year),
+      None => title.primary_title().to_owned(),
+    };
+    println!("{name} [{id}] is in {} directories:", dirs.len());
+
+    for dir in dirs {
+      let size = ImdbColumnValue::Bytes(dir_size(dir)?).to_string();
+      println!("  {size:>10}  {}", dir.display());
+    }
+
+    duplicates += 1;
+  }
+
+  println!("Resolved {} directories: {duplicates} titles are in several directories", titles.len());
+
+  Ok(())
+}
+
+fn imdb_library_report(
+  dir: &Path,
+  provider: &dyn TitleProvider,
+  imdb_url: &Url,
+  top: usize,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<usize, Error> {
+  let titles = scan_titles(dir, provider)?;
+  let stats = ImdbStats::new(titles.iter().map(|(_, title)| *title), top);
+  Ok(printer.print_stats(&stats, imdb_url)?)
+}
+
+/// Lowercase alphanumeric words of a title, for comparing titles regardless of punctuation.
+fn title_words(title: &str) -> HashSet<String> {
+  title
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|word| !word.is_empty())
+    .map(str::to_lowercase)
+    .collect()
+}
+
+/// How the name of a directory disagrees with the title its title information refers to:
+/// when the release years are more than a year apart, or when the directory name has no
+/// word in common with the primary and original titles.
+fn name_disagreement(dir_name: &str, title: &ImdbTitle) -> Option<String> {
+  let (name, year) = match parse_title_and_year(dir_name) {
+    Some((name, year)) => (name, Some(year)),
+    None => (dir_name, None),
+  };
+
+  if let (Some(year), Some(start_year)) = (year, title.start_year()) {
+    if year.abs_diff(start_year) > 1 {
+      return Some(format!("but the directory name says {year}"));
+    }
+  }
+
+  let name_words = title_words(name);
+  let mut words = title_words(title.primary_title());
+  words.extend(title.original_title().map(title_words).unwrap_or_default());
+
+  if !name_words.is_empty() && name_words.is_disjoint(&words) {
+    return Some("which does not match the directory name".to_owned());
+  }
+
+  None
+}
+
+/// Find the titles of a library: directories with a title information file or media center
+/// metadata, top-level directories named "TITLE (YYYY)" or "TITLE", and nested directories
+/// named "TITLE (YYYY)" that match exactly one title. Directories of titles are not descended
+/// into.
+fn library_titles<'a>(library: &Path, imdb: &'a Imdb) -> Result<Vec<&'a ImdbTitle<'a>>, Error> {
+  let mut titles = Vec::new();
+  let mut seen = HashSet::new();
+  let mut walkdir = WalkDir::new(library).sort_by_file_name().min_depth(1).into_iter();
+
+  while let Some(entry) = walkdir.next() {
+    let entry = entry?;
+
+    if !entry.file_type().is_dir() {
+      continue;
+    }
+
+    let path = entry.path();
+    let resolved = if let Ok((title_info, _)) = TitleInfo::discover(path) {
+      let title = imdb.by_id_any(title_info.imdb().id()).map(|(title, _)| title);
+      if title.is_none() {
+        warn!("`{}`: unknown IMDB ID `{}`", path.display(), title_info.imdb().id());
+      }
+      title
+    } else if entry.depth() == 1 || parse_title_and_year(&entry.file_name().to_string_lossy()).is_some() {
+      match resolve_dir(path, imdb)?.as_slice() {
+        [title] => Some(*title),
+        [] => None,
+        matches => {
+          debug!("`{}`: {} titles match the directory name, skipping", path.display(), matches.len());
+          None
+        }
+      }
+    } else {
+      None
+    };
+
+    if let Some(title) = resolved {
+      walkdir.skip_current_dir();
+      if seen.insert(*title.title_id()) {
+        titles.push(title);
+      }
+    }
+  }
+
+  Ok(titles)
+}
+
+fn imdb_enrich(
+  library: &Path,
+  imdb: &Imdb,
+  mut enricher: Enricher,
+  providers: &[ImdbEnrichmentProvider],
+) -> Result<(), Error> {
+  if !library.is_dir() {
+    return Err(Error::NotDir(library.to_owned()));
+  }
+
+  let titles = library_titles(library, imdb)?;
+  let providers: Vec<_> = providers.iter().copied().filter(|&p| enricher.has_api_key(p)).collect();
+
+  let mut fetched = 0;
+  let mut cached = 0;
+  let mut missing = 0;
+  let mut failed = 0;
+  let mut posters = 0;
+
+  for title in &titles {
+    let id = title.title_id();
+    let mut poster_url = None;
+
+    for &provider in &providers {
+      match enricher.enrich(provider, id) {
+        Ok(lookup) => {
+          match &lookup {
+            Lookup::Fetched(_) => fetched += 1,
+            Lookup::Cached(_) => cached += 1,
+          }
+
+          match lookup.enrichment() {
+            Some(enrichment) => poster_url = poster_url.or(enrichment.poster_url().map(str::to_owned)),
+            None => missing += 1,
+          }
+        }
+        Err(e) => {
+          warn!("Could not fetch {provider} data for `{}` ({id}): {e}", title.primary_title());
+          failed += 1;
+        }
+      }
+    }
+
+    if let Some(poster_url) = poster_url {
+      match enricher.fetch_poster(id, &poster_url) {
+        Ok(true) => posters += 1,
+        Ok(false) => {}
+        Err(e) => {
+          warn!("Could not fetch the poster of `{}` ({id}): {e}", title.primary_title());
+          failed += 1;
+        }
+      }
+    }
+  }
+
+  println!(
+    "Enriched {} titles: {fetched} fetched, {cached} cached, {missing} unknown to providers, \
+     {posters} posters downloaded, {failed} failures",
+    titles.len()
+  );
+
+  Ok(())
+}
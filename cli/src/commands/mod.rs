@@ -0,0 +1,79 @@
+#![warn(clippy::all)]
+
+//! The sub-commands, each with its arguments and how it is run.
+
+pub mod browse;
+pub mod db;
+pub mod library;
+pub mod profile;
+pub mod scan;
+pub mod search;
+pub mod serve;
+pub mod sync;
+pub mod title;
+pub mod watch;
+pub mod watchlist;
+
+use std::time::Instant;
+
+use atoi::atoi;
+use humantime::format_duration;
+use regex::Regex;
+use tracing::{debug, info_span, warn};
+
+pub fn parse_title_and_year(input: &str) -> Option<(&str, u16)> {
+  let regex = match Regex::new(r"^(.+)\s+\((\d{4})\)$") {
+    Ok(regex) => regex,
+    Err(e) => {
+      warn!("Could not parse input `{}` as TITLE (YYYY): {}", input, e);
+      return None;
+    }
+  };
+
+  let captures = match regex.captures(input) {
+    Some(captures) => captures,
+    None => {
+      debug!("Could not parse title and year from `{}`", input);
+      return None;
+    }
+  };
+
+  let title_match = match captures.get(1) {
+    Some(title_match) => title_match,
+    None => {
+      debug!("Could not parse title from `{}`", input);
+      return None;
+    }
+  };
+
+  let year_match = match captures.get(2) {
+    Some(year_match) => year_match,
+    None => {
+      debug!("Could not parse year from `{}`", input);
+      return None;
+    }
+  };
+
+  let year_val = match atoi::<u16>(year_match.as_str().as_bytes()) {
+    Some(year_val) => year_val,
+    None => {
+      warn!("Could not parse year `{}`", year_match.as_str());
+      return None;
+    }
+  };
+
+  Some((title_match.as_str(), year_val))
+}
+
+pub fn display_title_and_year(title: &str, year: u16) -> String {
+  format!("{title} ({year})")
+}
+
+/// Run a query of the databases in the `query` span and log how long it took.
+pub fn timed_query<T>(query: impl FnOnce() -> T) -> T {
+  let _query = info_span!("query").entered();
+  let start_time = Instant::now();
+  let result = query();
+  debug!("IMDB query took {}", format_duration(Instant::now().duration_since(start_time)));
+  result
+}
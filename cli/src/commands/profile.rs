@@ -0,0 +1,95 @@
+#![warn(clippy::all)]
+
+//! Profiles, which keep personal state separate on shared machines.
+
+use std::fs;
+
+use crate::context::run_without_service;
+use crate::error::Error;
+use crate::opts::GeneralOpts;
+
+use tvrank::paths::Paths;
+
+use tracing::debug;
+
+#[derive(Debug, clap::Subcommand)]
+pub enum ProfileCommand {
+  /// List the existing profiles
+  List {
+    #[clap(flatten)]
+    general_opts: GeneralOpts,
+  },
+
+  /// Create a profile
+  Create {
+    /// Name of the profile, made of letters, digits, "-" and "_"
+    #[clap(value_name = "NAME")]
+    name: String,
+
+    #[clap(flatten)]
+    general_opts: GeneralOpts,
+  },
+
+  /// Remove a profile along with all its state
+  Remove {
+    /// Name of the profile
+    #[clap(value_name = "NAME")]
+    name: String,
+
+    #[clap(flatten)]
+    general_opts: GeneralOpts,
+  },
+}
+
+impl ProfileCommand {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    match self {
+      Self::List { general_opts } => run_without_service(general_opts, globals, |_| {
+        profile_list(&Paths::new()?)?;
+        Ok(None)
+      }),
+      Self::Create { name, general_opts } => run_without_service(general_opts, globals, |_| {
+        profile_create(Paths::new()?, &name)?;
+        Ok(None)
+      }),
+      Self::Remove { name, general_opts } => run_without_service(general_opts, globals, |_| {
+        profile_remove(Paths::new()?, &name)?;
+        Ok(None)
+      }),
+    }
+  }
+}
+
+fn profile_list(paths: &Paths) -> Result<(), Error> {
+  for profile in paths.profiles()? {
+    println!("{profile}");
+  }
+
+  Ok(())
+}
+
+fn profile_create(paths: Paths, name: &str) -> Result<(), Error> {
+  let paths = paths.with_profile(name)?;
+  let profile_dir = paths.profile_dir();
+
+  if profile_dir.exists() {
+    return Err(Error::ProfileExists(name.to_owned()));
+  }
+
+  fs::create_dir_all(&profile_dir)?;
+  debug!("Created profile directory: {}", profile_dir.display());
+  Ok(())
+}
+
+fn profile_remove(paths: Paths, name: &str) -> Result<(), Error> {
+  let paths = paths.with_profile(name)?;
+  let profile_dir = paths.profile_dir();
+
+  if !profile_dir.is_dir() {
+    return Err(Error::UnknownProfile(name.to_owned()));
+  }
+
+  fs::remove_dir_all(&profile_dir)?;
+  debug!("Removed profile directory: {}", profile_dir.display());
+  Ok(())
+}
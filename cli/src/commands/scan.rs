@@ -0,0 +1,816 @@
+#![warn(clippy::all)]
+
+//! Looking up the titles of the directories and files of a library, by their title
+//! information files, media center metadata or names.
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::commands::{display_title_and_year, parse_title_and_year, timed_query};
+use crate::context::{create_cache_dir, create_imdb_episodes, create_output_printer, Context};
+use crate::error::Error;
+use crate::nfo::NfoWriter;
+use crate::opts::{GeneralOpts, RenameOpts, ScanOpts, SearchOpts};
+use crate::print::{Error as PrintError, OutputFormat, Printer};
+use crate::query::Query;
+use crate::releases::{is_episode_name, is_media_file, is_season_dir_name, parse_release_name};
+use crate::renames::RenamePlan;
+use crate::report::ScanReport;
+use crate::search::SearchRes;
+use crate::usage::StorageUsage;
+
+use tvrank::imdb::{ImdbColumn, ImdbEpisodes, ImdbQuery, ImdbTitle};
+use tvrank::provider::TitleProvider;
+use tvrank::title_info::TitleInfo;
+use tvrank::utils::search::SearchString;
+
+use rayon::prelude::*;
+use reqwest::Url;
+use tracing::warn;
+use walkdir::WalkDir;
+
+#[derive(Debug, clap::Args)]
+pub struct ScanMoviesArgs {
+  /// Directory of movie folders named "TITLE (YYYY)"
+  #[clap(name = "DIR")]
+  dir: PathBuf,
+
+  /// Lookup movie files instead of folders, named "TITLE (YYYY)" or like release names
+  /// such as "Title.YYYY.1080p.BluRay.x264-GROUP"
+  #[clap(long, conflicts_with_all = ["suggest_renames", "write_nfo"])]
+  files: bool,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+
+  #[clap(flatten)]
+  search_opts: SearchOpts,
+
+  #[clap(flatten)]
+  scan_opts: ScanOpts,
+
+  #[clap(flatten)]
+  rename_opts: RenameOpts,
+}
+
+impl ScanMoviesArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { dir, files, general_opts, search_opts, scan_opts, rename_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let query = context.query(search_opts)?;
+      let search = context.service.with_options(query.opts().search_options());
+      let scan = Scan::new(scan_opts);
+      let printer = create_output_printer(&query.opts().output, &context.general_opts);
+
+      let matches = timed_query(|| {
+        let providers = context.providers(&search);
+        if files {
+          imdb_movie_files(&dir, &providers, &context.imdb_url, &query, &scan, printer)
+        } else {
+          imdb_movies_dir(&dir, &providers, &context.imdb_url, &query, &scan, &rename_opts, printer)
+        }
+      })?;
+
+      Ok(Some(matches))
+    })
+  }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ScanArgs {
+  /// Directory of movie and series folders named "TITLE (YYYY)", series folders may also be
+  /// named "TITLE"
+  #[clap(name = "DIR")]
+  dir: PathBuf,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+
+  #[clap(flatten)]
+  search_opts: SearchOpts,
+
+  #[clap(flatten)]
+  scan_opts: ScanOpts,
+}
+
+impl ScanArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { dir, general_opts, search_opts, scan_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let query = context.query(search_opts)?;
+      let search = context.service.with_options(query.opts().search_options());
+      let scan = Scan::new(scan_opts).with_episodes(context)?;
+      let printer = create_output_printer(&query.opts().output, &context.general_opts);
+
+      let matches = timed_query(|| {
+        imdb_scan_dir(&dir, &context.providers(&search), &context.imdb_url, &query, &scan, printer)
+      })?;
+
+      Ok(Some(matches))
+    })
+  }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ScanSeriesArgs {
+  /// Directory of series folders named "TITLE [(YYYY)]"
+  #[clap(name = "DIR")]
+  dir: PathBuf,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+
+  #[clap(flatten)]
+  search_opts: SearchOpts,
+
+  #[clap(flatten)]
+  scan_opts: ScanOpts,
+}
+
+impl ScanSeriesArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { dir, general_opts, search_opts, scan_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let query = context.query(search_opts)?;
+      let search = context.service.with_options(query.opts().search_options());
+      let scan = Scan::new(scan_opts).with_episodes(context)?;
+      let printer = create_output_printer(&query.opts().output, &context.general_opts);
+
+      let matches = timed_query(|| {
+        imdb_series_dir(&dir, &context.providers(&search), &context.imdb_url, &query, &scan, printer)
+      })?;
+
+      Ok(Some(matches))
+    })
+  }
+}
+
+/// Name of the column with the average episode rating of series.
+const EPISODE_RATING_COLUMN: &str = "Episode Rating";
+
+/// A scan along with the episode columns of series, when the episodes database is available.
+pub struct Scan {
+  opts: ScanOpts,
+  /// Number of seasons and episodes and average episode rating of series, when the episodes
+  /// database was downloaded (see `tvrank show`).
+  episode_columns: Vec<ImdbColumn>,
+}
+
+impl Scan {
+  pub fn new(opts: ScanOpts) -> Self {
+    Self { opts, episode_columns: Vec::new() }
+  }
+
+  fn storage_usage(&self) -> Option<StorageUsage> {
+    (self.opts.sizes || self.opts.sort_by_size).then(StorageUsage::new)
+  }
+
+  fn nfo_writer(&self) -> Option<NfoWriter> {
+    self.opts.write_nfo.then(|| NfoWriter::new(self.opts.overwrite_nfo))
+  }
+
+  fn write_report(&self, report: &ScanReport) -> Result<(), Error> {
+    if let Some(path) = &self.opts.report {
+      report.write(path)?;
+      eprintln!("Wrote scan report to {}", path.display());
+    }
+
+    Ok(())
+  }
+
+  /// Load the episodes database for the episode columns of series if it was downloaded, or
+  /// download it if series are sorted by their episodes.
+  fn with_episodes(mut self, context: &Context) -> Result<Self, Error> {
+    let episodes = if self.opts.sort_by_episodes {
+      Some(create_imdb_episodes(&context.paths, &context.general_opts)?)
+    } else {
+      ImdbEpisodes::load(create_cache_dir(&context.paths)?)?
+    };
+
+    if let Some(episodes) = episodes {
+      self.episode_columns = ImdbEpisodes::columns(Arc::new(episodes));
+    }
+
+    Ok(self)
+  }
+
+  fn new_results<'a, 'storage>(
+    &self,
+    query: &Query,
+    top: Option<usize>,
+    usage: Option<&StorageUsage>,
+  ) -> SearchRes<'a, 'storage> {
+    let mut results = query.new_results(top);
+
+    for column in &self.episode_columns {
+      results = results.with_column(column.clone());
+
+      if self.opts.sort_by_episodes && column.name() == EPISODE_RATING_COLUMN {
+        results = results.with_sort_column(column.clone());
+      }
+    }
+
+    if let Some(usage) = usage {
+      results = results.with_column(usage.column());
+
+      if self.opts.sort_by_size {
+        results = results.with_sort_column(usage.column());
+      }
+    }
+
+    results
+  }
+}
+
+/// How a directory of a movie scan was resolved.
+enum MovieDir<'a> {
+  /// The title of the directory's title information.
+  Identified(&'a ImdbTitle<'a>),
+  /// The titles matching the "TITLE (YYYY)" name of the directory.
+  Named { title: String, year: u16, titles: Vec<&'a ImdbTitle<'a>> },
+  /// The directory has no title information and its name does not follow the "TITLE (YYYY)"
+  /// format.
+  Unnamed,
+}
+
+/// Resolve a directory of a movie scan, counting its disk usage if requested. Warnings are
+/// returned instead of logged so that they can be reported in a deterministic order.
+fn resolve_movie_dir<'a>(
+  entry_path: &Path,
+  provider: &'a dyn TitleProvider,
+  usage: Option<&StorageUsage>,
+) -> Result<(MovieDir<'a>, Vec<String>), Error> {
+  let mut warnings = Vec::new();
+
+  if let Ok((title_info, source)) = TitleInfo::discover(entry_path) {
+    if let Some(result) = provider.by_id(title_info.imdb().id(), ImdbQuery::Movies) {
+      if let Some(usage) = usage {
+        usage.add(entry_path, [result])?;
+      }
+      return Ok((MovieDir::Identified(result), warnings));
+    } else {
+      let id = title_info.imdb().id();
+      let path = entry_path.display();
+      warnings.push(format!("Could not find title ID `{id}` for `{path}`, ignoring {source}"));
+    }
+  }
+
+  let Some(filename) = entry_path.file_name() else {
+    return Ok((MovieDir::Unnamed, warnings));
+  };
+
+  let filename = filename.to_string_lossy();
+
+  let Some((title, year)) = parse_title_and_year(&filename) else {
+    warnings.push(format!(
+      "Skipping `{}` because `{}` does not follow the TITLE (YYYY) format",
+      entry_path.display(),
+      filename,
+    ));
+    return Ok((MovieDir::Unnamed, warnings));
+  };
+
+  let search_string = SearchString::try_from(title)?;
+  let titles = provider.by_title_and_year(&search_string, year, ImdbQuery::Movies);
+  if let Some(usage) = usage {
+    usage.add(entry_path, titles.iter().copied())?;
+  }
+
+  Ok((MovieDir::Named { title: title.to_owned(), year, titles }, warnings))
+}
+
+pub fn imdb_movies_dir(
+  dir: &Path,
+  provider: &dyn TitleProvider,
+  imdb_url: &Url,
+  query: &Query,
+  scan: &Scan,
+  rename_opts: &RenameOpts,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<usize, Error> {
+  let mut renames = rename_opts.suggest_renames.then(RenamePlan::new);
+  let mut nfos = scan.nfo_writer();
+  let mut report = ScanReport::new();
+  let mut matches = 0;
+  let mut at_least_one = false;
+  let mut at_least_one_matched = false;
+  let usage = scan.storage_usage();
+  let mut results = scan.new_results(query, query.opts().top, usage.as_ref());
+  // Resolving directories reads title information files and queries the providers, which is
+  // slow on large libraries over network mounts, so directories are resolved in parallel and
+  // then handled in the order they were walked in.
+  let mut dirs = Vec::new();
+  for entry in WalkDir::new(dir).sort_by_file_name().min_depth(1) {
+    let entry = entry?;
+    if entry.file_type().is_dir() {
+      dirs.push(entry.into_path());
+    }
+  }
+
+  let resolved = dirs
+    .par_iter()
+    .map(|entry_path| resolve_movie_dir(entry_path, provider, usage.as_ref()))
+    .collect::<Result<Vec<_>, Error>>()?;
+
+  for (entry_path, (resolution, warnings)) in dirs.iter().zip(resolved) {
+    for warning in warnings {
+      warn!("{warning}");
+    }
+
+    match resolution {
+      MovieDir::Identified(result) => {
+        at_least_one = true;
+        at_least_one_matched = true;
+        if let Some(renames) = &mut renames {
+          renames.add(entry_path, result);
+        }
+        if let Some(nfos) = &mut nfos {
+          nfos.add(entry_path, result);
+        }
+        results.push(result);
+      }
+      MovieDir::Named { title, year, titles } => {
+        at_least_one = true;
+
+        let mut local_results = scan.new_results(query, None, usage.as_ref());
+        report.add(entry_path, &titles);
+        if let (Some(renames), [title]) = (&mut renames, titles.as_slice()) {
+          renames.add(entry_path, title);
+        }
+        if let (Some(nfos), [title]) = (&mut nfos, titles.as_slice()) {
+          nfos.add(entry_path, title);
+        }
+        local_results.extend(titles);
+
+        if local_results.is_empty() || local_results.len() > 1 {
+          if local_results.len() > 1 {
+            at_least_one_matched = true;
+          }
+
+          if renames.is_some() {
+            let path = entry_path.display();
+            warn!("Not renaming `{path}`, {} titles match its name", local_results.len());
+          } else if matches!(printer.get_format(), OutputFormat::Table) {
+            matches += printer.print(
+              Some(local_results),
+              None,
+              imdb_url,
+              Some(&display_title_and_year(&title, year)),
+            )?;
+          } else {
+            results.extend(local_results);
+          }
+        } else {
+          at_least_one_matched = true;
+          results.extend(local_results);
+        }
+      }
+      MovieDir::Unnamed => report.skip(entry_path),
+    }
+  }
+
+  scan.write_report(&report)?;
+
+  if !at_least_one {
+    eprintln!("No valid directory names");
+    return Ok(0);
+  }
+
+  if !at_least_one_matched {
+    eprintln!("None of the directories matched any titles");
+    return Ok(0);
+  }
+
+  if let Some(nfos) = &nfos {
+    nfos.print_summary();
+  }
+
+  if let Some(mut renames) = renames {
+    renames.print(dir);
+
+    if rename_opts.apply {
+      let renamed = renames.apply()?;
+      println!("Renamed {renamed} of {} directories", renames.len());
+    }
+
+    return Ok(results.total_len());
+  }
+
+  matches += printer.print(Some(results), None, imdb_url, None)?;
+
+  if let Some(usage) = &usage {
+    printer.print_storage_usage(&usage.summary())?;
+  }
+
+  Ok(matches)
+}
+
+fn imdb_movie_files(
+  dir: &Path,
+  provider: &dyn TitleProvider,
+  imdb_url: &Url,
+  query: &Query,
+  scan: &Scan,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<usize, Error> {
+  let mut report = ScanReport::new();
+  let mut matches = 0;
+  let mut at_least_one = false;
+  let mut at_least_one_matched = false;
+  let usage = scan.storage_usage();
+  let mut results = scan.new_results(query, query.opts().top, usage.as_ref());
+  let walkdir = WalkDir::new(dir).sort_by_file_name().min_depth(1);
+
+  for entry in walkdir {
+    let entry = entry?;
+    let entry_path = entry.path();
+
+    if !entry.file_type().is_file() || !is_media_file(entry_path) {
+      continue;
+    }
+
+    let Some(stem) = entry_path.file_stem() else {
+      continue;
+    };
+
+    let stem = stem.to_string_lossy();
+    let Some((title, year)) = parse_release_name(&stem) else {
+      report.skip(entry_path);
+      warn!("Skipping `{}` because the title and year could not be found in its name", entry_path.display());
+      continue;
+    };
+
+    at_least_one = true;
+
+    let mut local_results = scan.new_results(query, None, usage.as_ref());
+    let search_string = SearchString::try_from(title.as_str())?;
+    let titles = provider.by_title_and_year(&search_string, year, ImdbQuery::Movies);
+    if let Some(usage) = &usage {
+      usage.add(entry_path, titles.iter().copied())?;
+    }
+    report.add(entry_path, &titles);
+    local_results.extend(titles);
+
+    if local_results.is_empty() || local_results.len() > 1 {
+      if local_results.len() > 1 {
+        at_least_one_matched = true;
+      }
+
+      if matches!(printer.get_format(), OutputFormat::Table) {
+        let search_terms = display_title_and_year(&title, year);
+        matches += printer.print(Some(local_results), None, imdb_url, Some(&search_terms))?;
+      } else {
+        results.extend(local_results);
+      }
+    } else {
+      at_least_one_matched = true;
+      results.extend(local_results);
+    }
+  }
+
+  scan.write_report(&report)?;
+
+  if !at_least_one {
+    eprintln!("No valid file names");
+    return Ok(0);
+  }
+
+  if !at_least_one_matched {
+    eprintln!("None of the files matched any titles");
+    return Ok(0);
+  }
+
+  matches += printer.print(Some(results), None, imdb_url, None)?;
+
+  if let Some(usage) = &usage {
+    printer.print_storage_usage(&usage.summary())?;
+  }
+
+  Ok(matches)
+}
+
+/// Detect whether a directory holds a series, by its season folders or episode files, or a
+/// movie, by its video files. Directories with neither, e.g. ones grouping other titles, are
+/// undetected.
+fn detect_title_kind(dir: &Path) -> Result<Option<ImdbQuery>, Error> {
+  let mut has_media = false;
+
+  for entry in fs::read_dir(dir)? {
+    let entry = entry?;
+    let path = entry.path();
+    let name = entry.file_name();
+    let name = name.to_string_lossy();
+
+    if path.is_dir() {
+      if is_season_dir_name(&name) {
+        return Ok(Some(ImdbQuery::Series));
+      }
+    } else if is_media_file(&path) {
+      if is_episode_name(&name) {
+        return Ok(Some(ImdbQuery::Series));
+      }
+      has_media = true;
+    }
+  }
+
+  Ok(has_media.then_some(ImdbQuery::Movies))
+}
+
+/// How a directory of a mixed scan was resolved.
+pub enum ScanDir<'a> {
+  /// The title of the directory's title information or media center metadata.
+  Identified(&'a ImdbTitle<'a>, ImdbQuery),
+  /// The movies and series matching the name of the directory, `None` for the kinds of titles
+  /// the directory was not looked up as.
+  Named {
+    search_terms: String,
+    movies: Option<Vec<&'a ImdbTitle<'a>>>,
+    series: Option<Vec<&'a ImdbTitle<'a>>>,
+  },
+  /// A movie directory whose name does not follow the "TITLE (YYYY)" format.
+  Skipped,
+  /// A directory that is neither detected as a movie nor as a series, and may contain more
+  /// titles.
+  Container,
+}
+
+/// Resolve a directory of a mixed scan by its title information or media center metadata,
+/// otherwise by its name as a movie or series, depending on its detected kind.
+pub fn resolve_scan_dir<'a>(dir: &Path, provider: &'a dyn TitleProvider) -> Result<ScanDir<'a>, Error> {
+  if let Ok((title_info, source)) = TitleInfo::discover(dir) {
+    if let Some((result, query)) = provider.by_id_any(title_info.imdb().id()) {
+      return Ok(ScanDir::Identified(result, query));
+    } else {
+      let id = title_info.imdb().id();
+      let path = dir.display();
+      warn!("Could not find title ID `{id}` for `{path}`, ignoring {source}");
+    }
+  }
+
+  let Some(filename) = dir.file_name() else {
+    return Ok(ScanDir::Container);
+  };
+
+  let filename = filename.to_string_lossy();
+  let title_and_year = parse_title_and_year(&filename);
+
+  // Directories that are neither detected as movies nor as series are looked up as both
+  // when they are named "TITLE (YYYY)", and otherwise searched for more titles.
+  let (query_movies, query_series) = match (detect_title_kind(dir)?, title_and_year) {
+    (Some(ImdbQuery::Series), _) => (false, true),
+    (Some(ImdbQuery::Movies), Some(_)) => (true, false),
+    (Some(ImdbQuery::Movies), None) => {
+      warn!("Skipping `{}` because `{}` does not follow the TITLE (YYYY) format", dir.display(), filename);
+      return Ok(ScanDir::Skipped);
+    }
+    (None, Some(_)) => (true, true),
+    (None, None) => return Ok(ScanDir::Container),
+  };
+
+  let (search_string, search_terms) = match title_and_year {
+    Some((title, year)) => (SearchString::try_from(title)?, display_title_and_year(title, year)),
+    None => (SearchString::try_from(filename.as_ref())?, filename.to_string()),
+  };
+  let lookup = |query| match title_and_year {
+    Some((_, year)) => provider.by_title_and_year(&search_string, year, query),
+    None => provider.by_title(&search_string, query),
+  };
+
+  Ok(ScanDir::Named {
+    search_terms,
+    movies: query_movies.then(|| lookup(ImdbQuery::Movies)),
+    series: query_series.then(|| lookup(ImdbQuery::Series)),
+  })
+}
+
+fn imdb_scan_dir(
+  dir: &Path,
+  provider: &dyn TitleProvider,
+  imdb_url: &Url,
+  query: &Query,
+  scan: &Scan,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<usize, Error> {
+  let mut nfos = scan.nfo_writer();
+  let mut report = ScanReport::new();
+  let mut matches = 0;
+  let mut at_least_one = false;
+  let mut at_least_one_matched = false;
+  let usage = scan.storage_usage();
+  let mut movies = scan.new_results(query, query.opts().top, usage.as_ref());
+  let mut series = scan.new_results(query, query.opts().top, usage.as_ref());
+  let mut walkdir = WalkDir::new(dir).sort_by_file_name().min_depth(1).into_iter();
+
+  while let Some(entry) = walkdir.next() {
+    let entry = entry?;
+
+    if !entry.file_type().is_dir() {
+      continue;
+    }
+
+    let entry_path = entry.path();
+
+    let (search_terms, found_movies, found_series) = match resolve_scan_dir(entry_path, provider)? {
+      ScanDir::Container => continue,
+      ScanDir::Skipped => {
+        report.skip(entry_path);
+        walkdir.skip_current_dir();
+        continue;
+      }
+      ScanDir::Identified(result, query) => {
+        walkdir.skip_current_dir();
+        at_least_one = true;
+        at_least_one_matched = true;
+        if let Some(usage) = &usage {
+          usage.add(entry_path, [result])?;
+        }
+        if let Some(nfos) = &mut nfos {
+          nfos.add(entry_path, result);
+        }
+        match query {
+          ImdbQuery::Movies => movies.push(result),
+          ImdbQuery::Series => series.push(result),
+        }
+        continue;
+      }
+      ScanDir::Named { search_terms, movies, series } => (search_terms, movies, series),
+    };
+
+    walkdir.skip_current_dir();
+    at_least_one = true;
+
+    let titles = found_movies.iter().chain(&found_series).flatten().copied().collect::<Vec<_>>();
+    if let Some(usage) = &usage {
+      usage.add(entry_path, titles.iter().copied())?;
+    }
+    if let (Some(nfos), [title]) = (&mut nfos, titles.as_slice()) {
+      nfos.add(entry_path, title);
+    }
+    report.add(entry_path, &titles);
+
+    if titles.len() == 1 {
+      at_least_one_matched = true;
+      movies.extend(found_movies.unwrap_or_default());
+      series.extend(found_series.unwrap_or_default());
+      continue;
+    }
+
+    if titles.len() > 1 {
+      at_least_one_matched = true;
+    }
+
+    if matches!(printer.get_format(), OutputFormat::Table) {
+      let local_results = |found: Vec<_>| {
+        let mut local_results = scan.new_results(query, None, usage.as_ref());
+        local_results.extend(found);
+        local_results
+      };
+      let local_movies = found_movies.map(local_results);
+      let local_series = found_series.map(local_results);
+      matches += printer.print(local_movies, local_series, imdb_url, Some(&search_terms))?;
+    } else {
+      movies.extend(found_movies.unwrap_or_default());
+      series.extend(found_series.unwrap_or_default());
+    }
+  }
+
+  scan.write_report(&report)?;
+
+  if !at_least_one {
+    eprintln!("No valid directory names");
+    return Ok(0);
+  }
+
+  if !at_least_one_matched {
+    eprintln!("None of the directories matched any titles");
+    return Ok(0);
+  }
+
+  if let Some(nfos) = &nfos {
+    nfos.print_summary();
+  }
+
+  let movies = (!movies.is_empty()).then_some(movies);
+  let series = (!series.is_empty()).then_some(series);
+  matches += printer.print(movies, series, imdb_url, None)?;
+
+  if let Some(usage) = &usage {
+    printer.print_storage_usage(&usage.summary())?;
+  }
+
+  Ok(matches)
+}
+
+pub fn imdb_series_dir(
+  dir: &Path,
+  provider: &dyn TitleProvider,
+  imdb_url: &Url,
+  query: &Query,
+  scan: &Scan,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<usize, Error> {
+  let mut nfos = scan.nfo_writer();
+  let mut report = ScanReport::new();
+  let mut matches = 0;
+  let mut at_least_one = false;
+  let mut at_least_one_matched = false;
+  let usage = scan.storage_usage();
+  let mut results = scan.new_results(query, query.opts().top, usage.as_ref());
+  let walkdir = WalkDir::new(dir).sort_by_file_name().min_depth(1).max_depth(1);
+
+  for entry in walkdir {
+    let entry = entry?;
+
+    if entry.file_type().is_dir() {
+      let entry_path = entry.path();
+
+      if let Ok((title_info, source)) = TitleInfo::discover(entry_path) {
+        if let Some(result) = provider.by_id(title_info.imdb().id(), ImdbQuery::Series) {
+          at_least_one = true;
+          at_least_one_matched = true;
+          if let Some(usage) = &usage {
+            usage.add(entry_path, [result])?;
+          }
+          if let Some(nfos) = &mut nfos {
+            nfos.add(entry_path, result);
+          }
+          results.push(result);
+          continue;
+        } else {
+          let id = title_info.imdb().id();
+          let path = entry_path.display();
+          warn!("Could not find title ID `{id}` for `{path}`, ignoring {source}");
+        }
+      }
+
+      if let Some(filename) = entry_path.file_name() {
+        at_least_one = true;
+
+        let filename = filename.to_string_lossy();
+        let mut local_results = scan.new_results(query, None, usage.as_ref());
+
+        let (titles, search_terms) = if let Some((title, year)) = parse_title_and_year(&filename) {
+          let search_string = SearchString::try_from(title)?;
+          let titles = provider.by_title_and_year(&search_string, year, ImdbQuery::Series);
+          (titles, Cow::from(display_title_and_year(title, year)))
+        } else {
+          let titles = provider.by_title(&SearchString::try_from(filename.as_ref())?, ImdbQuery::Series);
+          (titles, filename)
+        };
+
+        if let Some(usage) = &usage {
+          usage.add(entry_path, titles.iter().copied())?;
+        }
+        if let (Some(nfos), [title]) = (&mut nfos, titles.as_slice()) {
+          nfos.add(entry_path, title);
+        }
+        report.add(entry_path, &titles);
+        local_results.extend(titles);
+
+        if local_results.is_empty() || local_results.len() > 1 {
+          if local_results.len() > 1 {
+            at_least_one_matched = true;
+          }
+
+          if matches!(printer.get_format(), OutputFormat::Table) {
+            matches += printer.print(None, Some(local_results), imdb_url, Some(&search_terms))?;
+          } else {
+            results.extend(local_results);
+          }
+        } else {
+          at_least_one_matched = true;
+          results.extend(local_results);
+        }
+      }
+    }
+  }
+
+  scan.write_report(&report)?;
+
+  if !at_least_one {
+    eprintln!("No valid directory names");
+    return Ok(0);
+  }
+
+  if !at_least_one_matched {
+    eprintln!("None of the directories matched any titles");
+    return Ok(0);
+  }
+
+  if let Some(nfos) = &nfos {
+    nfos.print_summary();
+  }
+
+  matches += printer.print(None, Some(results), imdb_url, None)?;
+
+  if let Some(usage) = &usage {
+    printer.print_storage_usage(&usage.summary())?;
+  }
+
+  Ok(matches)
+}
@@ -0,0 +1,517 @@
+#![warn(clippy::all)]
+
+//! Searching titles by keywords, exact names, IMDB IDs or regular expressions, one search at a
+//! time or a whole file of them.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::commands::{display_title_and_year, parse_title_and_year, timed_query};
+use crate::context::{create_output_printer, Context};
+use crate::diff::{DiffPrinter, PreviousResults};
+use crate::error::Error;
+use crate::opts::{parse_genre, GeneralOpts, SearchOpts, Status};
+use crate::print::{Error as PrintError, Printer};
+use crate::query::Query;
+use crate::search::{Exclusion, SearchGroup, SearchRes};
+
+use tvrank::imdb::{ImdbFilter, ImdbQuery, ImdbSearch, ImdbSuggestion, ImdbTitleId};
+use tvrank::provider::TitleProvider;
+use tvrank::utils::search::{SearchString, SearchStringError};
+
+use regex::RegexBuilder;
+use reqwest::Url;
+use tracing::{debug, enabled, warn, Level};
+
+#[derive(Debug, clap::Args)]
+pub struct SearchArgs {
+  /// Search terms, as "KEYWORDS", "TITLE (YYYY)" or an IMDB ID ("ttXXXXX")
+  #[clap(name = "TITLE")]
+  title: String,
+
+  /// Match the given title exactly
+  #[clap(short, long)]
+  exact: bool,
+
+  /// Match titles with TITLE as a case-insensitive regular expression (slow, since every
+  /// title is matched)
+  #[clap(long, conflicts_with = "exact")]
+  regex: bool,
+
+  /// Search IMDB online when nothing matches locally, results are unranked
+  #[clap(long)]
+  fallback_online: bool,
+
+  /// After displaying the results, refine them from prompts without querying the databases
+  /// again, with keywords, "genre:GENRE", "since:YEAR" or "status:running|ended" (an empty
+  /// line quits)
+  #[clap(short, long)]
+  interactive: bool,
+
+  /// Compare the results against previous results saved from `--output json`, and report
+  /// new and removed titles and rating changes on the standard error
+  #[clap(long, value_name = "FILE", conflicts_with = "interactive")]
+  diff: Option<PathBuf>,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+
+  #[clap(flatten)]
+  search_opts: SearchOpts,
+}
+
+impl SearchArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { title, exact, regex, fallback_online, interactive, diff, general_opts, search_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let query = context.query(search_opts)?;
+      let search = context.service.with_options(query.opts().search_options());
+      let mut printer = create_output_printer(&query.opts().output, &context.general_opts);
+      if let Some(diff) = diff.filter(|_| !context.general_opts.quiet) {
+        printer = Box::new(DiffPrinter::new(printer, PreviousResults::load(&diff)?));
+      }
+
+      let matches = timed_query(|| {
+        let imdb_url = &context.imdb_url;
+        if regex {
+          imdb_title_regex(&title, &search, imdb_url, &query, interactive, printer)
+        } else if interactive {
+          imdb_title_interactive(&title, &context.providers(&search), imdb_url, &query, exact, printer)
+        } else {
+          let providers = context.providers(&search);
+          imdb_title(&title, &providers, imdb_url, &query, exact, fallback_online, printer)
+        }
+      })?;
+
+      Ok(Some(matches))
+    })
+  }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct BatchArgs {
+  /// File with one search per line, or "-" to read from standard input
+  #[clap(long, name = "FILE")]
+  file: PathBuf,
+
+  /// Match the given titles exactly
+  #[clap(short, long)]
+  exact: bool,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+
+  #[clap(flatten)]
+  search_opts: SearchOpts,
+}
+
+impl BatchArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { file, exact, general_opts, search_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let query = context.query(search_opts)?;
+      let search = context.service.with_options(query.opts().search_options());
+      let printer = create_output_printer(&query.opts().output, &context.general_opts);
+      let quiet = context.general_opts.quiet;
+
+      let matches = timed_query(|| {
+        imdb_batch(&file, &context.providers(&search), &context.imdb_url, &query, exact, quiet, printer)
+      })?;
+
+      Ok(Some(matches))
+    })
+  }
+}
+
+fn display_keywords(keywords: &[SearchString]) -> String {
+  keywords
+    .iter()
+    .map(|kw| {
+      let keyword = match kw.as_str() {
+        phrase if phrase.contains(' ') => format!("\"{phrase}\""),
+        keyword => keyword.to_owned(),
+      };
+
+      if kw.is_excluded() {
+        format!("-{keyword}")
+      } else {
+        keyword
+      }
+    })
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// Split a search string into keywords, where quoted phrases are single keywords made of
+/// their words separated by single spaces. An unterminated quote extends to the end.
+/// Keywords starting with `-` are kept as such, to be excluded.
+fn split_keywords(title: &str) -> Vec<String> {
+  let mut keywords = Vec::new();
+
+  for (i, part) in title.split('"').enumerate() {
+    if i % 2 == 1 {
+      let phrase = part.split_whitespace().collect::<Vec<_>>().join(" ");
+      if !phrase.is_empty() {
+        keywords.push(phrase);
+      }
+    } else {
+      keywords.extend(part.split_whitespace().map(String::from));
+    }
+  }
+
+  keywords
+}
+
+fn create_keywords_set(title: &str, stem: bool) -> Result<Vec<SearchString>, Error> {
+  debug!("Going to use `{}` as keywords for search query", title);
+
+  let set: HashSet<_> = split_keywords(title).into_iter().collect();
+  let set: HashSet<_> = if set.is_empty() {
+    return Err(Error::EmptyKeywords);
+  } else if set.len() > 1 {
+    set.into_iter().filter(|kw| kw.len() > 1).collect()
+  } else {
+    set
+  };
+
+  let keywords = set
+    .iter()
+    .map(|kw| match kw.strip_prefix('-').filter(|kw| !kw.is_empty()) {
+      Some(kw) => SearchString::excluded(kw),
+      None => SearchString::try_from(kw.as_str()),
+    })
+    .map(|keyword| {
+      if stem {
+        keyword.map(SearchString::stemmed)
+      } else {
+        keyword
+      }
+    })
+    .collect::<Result<Vec<_>, SearchStringError>>()?;
+
+  if keywords.iter().all(SearchString::is_excluded) {
+    return Err(Error::EmptyKeywords);
+  }
+
+  if enabled!(Level::DEBUG) {
+    debug!("Keywords: {}", display_keywords(&keywords));
+  }
+
+  Ok(keywords)
+}
+
+/// Movies and series matching a search, together with a description of the search terms.
+pub type TitleResults<'a> = (SearchRes<'a, 'a>, SearchRes<'a, 'a>, String);
+
+pub fn search_title<'a>(
+  title: &str,
+  provider: &'a dyn TitleProvider,
+  query: &Query,
+  exact: bool,
+) -> Result<TitleResults<'a>, Error> {
+  let mut movies_results = query.new_results(query.opts().top);
+  let mut series_results = query.new_results(query.opts().top);
+
+  let search_terms = if let Ok(id) = ImdbTitleId::try_from(title.trim()) {
+    match provider.by_id_any(&id) {
+      Some((title, ImdbQuery::Movies)) => movies_results.push(title),
+      Some((title, ImdbQuery::Series)) => series_results.push(title),
+      None => {}
+    }
+
+    id.to_string()
+  } else if let Some((title, year)) = parse_title_and_year(title) {
+    if exact {
+      let search_string = SearchString::try_from(title)?;
+      movies_results.extend(provider.by_title_and_year(&search_string, year, ImdbQuery::Movies));
+      series_results.extend(provider.by_title_and_year(&search_string, year, ImdbQuery::Series));
+
+      if movies_results.is_empty() && series_results.is_empty() {
+        movies_results.extend(provider.by_title_and_year_normalized(&search_string, year, ImdbQuery::Movies));
+        series_results.extend(provider.by_title_and_year_normalized(&search_string, year, ImdbQuery::Series));
+        movies_results.mark_normalized();
+        series_results.mark_normalized();
+      }
+    } else {
+      let keywords = create_keywords_set(title, query.opts().stem)?;
+      (movies_results, series_results) = query.by_relevance(movies_results, series_results, &keywords);
+      match query.limit() {
+        Some(limit) => {
+          movies_results.extend(provider.by_keywords_and_year_limited(
+            &keywords,
+            year,
+            limit,
+            ImdbQuery::Movies,
+          ));
+          series_results.extend(provider.by_keywords_and_year_limited(
+            &keywords,
+            year,
+            limit,
+            ImdbQuery::Series,
+          ));
+        }
+        None => {
+          movies_results.extend(provider.by_keywords_and_year(&keywords, year, ImdbQuery::Movies));
+          series_results.extend(provider.by_keywords_and_year(&keywords, year, ImdbQuery::Series));
+        }
+      }
+    }
+
+    display_title_and_year(title, year)
+  } else if exact {
+    let search_string = SearchString::try_from(title)?;
+    movies_results.extend(provider.by_title(&search_string, ImdbQuery::Movies));
+    series_results.extend(provider.by_title(&search_string, ImdbQuery::Series));
+
+    if movies_results.is_empty() && series_results.is_empty() {
+      movies_results.extend(provider.by_title_normalized(&search_string, ImdbQuery::Movies));
+      series_results.extend(provider.by_title_normalized(&search_string, ImdbQuery::Series));
+      movies_results.mark_normalized();
+      series_results.mark_normalized();
+    }
+
+    search_string.into()
+  } else {
+    let keywords = create_keywords_set(title, query.opts().stem)?;
+    (movies_results, series_results) = query.by_relevance(movies_results, series_results, &keywords);
+    match query.limit() {
+      Some(limit) => {
+        movies_results.extend(provider.by_keywords_limited(&keywords, limit, ImdbQuery::Movies));
+        series_results.extend(provider.by_keywords_limited(&keywords, limit, ImdbQuery::Series));
+      }
+      None => {
+        movies_results.extend(provider.by_keywords(&keywords, ImdbQuery::Movies));
+        series_results.extend(provider.by_keywords(&keywords, ImdbQuery::Series));
+      }
+    }
+    display_keywords(&keywords)
+  };
+
+  Ok((movies_results, series_results, search_terms))
+}
+
+fn imdb_title(
+  title: &str,
+  provider: &dyn TitleProvider,
+  imdb_url: &Url,
+  query: &Query,
+  exact: bool,
+  fallback_online: bool,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<usize, Error> {
+  let (movies_results, series_results, search_terms) = search_title(title, provider, query, exact)?;
+
+  if fallback_online && movies_results.is_empty() && series_results.is_empty() {
+    if let Some(remote) = fetch_suggestions(title) {
+      return Ok(printer.print_remote(&remote, imdb_url, Some(&search_terms))?);
+    }
+  }
+  let matches = printer.print(Some(movies_results), Some(series_results), imdb_url, Some(&search_terms))?;
+  Ok(matches)
+}
+
+/// Search titles like `imdb_title`, then refine the results interactively.
+fn imdb_title_interactive(
+  title: &str,
+  provider: &dyn TitleProvider,
+  imdb_url: &Url,
+  query: &Query,
+  exact: bool,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<usize, Error> {
+  let (movies_results, series_results, search_terms) = search_title(title, provider, query, exact)?;
+  refine_interactively(movies_results, series_results, search_terms, imdb_url, printer)
+}
+
+/// Search titles whose names match a case-insensitive regular expression.
+fn imdb_title_regex(
+  pattern: &str,
+  imdb: &ImdbSearch,
+  imdb_url: &Url,
+  query: &Query,
+  interactive: bool,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<usize, Error> {
+  let regex = RegexBuilder::new(pattern).case_insensitive(true).build()?;
+
+  let mut movies_results = query.new_results(query.opts().top);
+  let mut series_results = query.new_results(query.opts().top);
+  movies_results.extend(imdb.by_title_regex(&regex, ImdbQuery::Movies));
+  series_results.extend(imdb.by_title_regex(&regex, ImdbQuery::Series));
+
+  let search_terms = format!("/{pattern}/");
+  if interactive {
+    return refine_interactively(movies_results, series_results, search_terms, imdb_url, printer);
+  }
+
+  let matches = printer.print(Some(movies_results), Some(series_results), imdb_url, Some(&search_terms))?;
+  Ok(matches)
+}
+
+/// Display results, then refine them with the refinements read from the standard input one
+/// line at a time until an empty line or the end of the input, and return the number of
+/// results that were displayed last.
+fn refine_interactively(
+  mut movies_results: SearchRes,
+  mut series_results: SearchRes,
+  mut search_terms: String,
+  imdb_url: &Url,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<usize, Error> {
+  let mut matches = printer.print(
+    Some(movies_results.clone()),
+    Some(series_results.clone()),
+    imdb_url,
+    Some(&search_terms),
+  )?;
+
+  let mut stdin = io::stdin().lock();
+  loop {
+    eprint!("refine> ");
+    io::stderr().flush()?;
+
+    let mut line = String::new();
+    if stdin.read_line(&mut line)? == 0 || line.trim().is_empty() {
+      break;
+    }
+
+    let (filter, exclusion) = match parse_refinement(line.trim()) {
+      Ok(refinement) => refinement,
+      Err(e) => {
+        eprintln!("{e}");
+        continue;
+      }
+    };
+
+    movies_results = movies_results.refine(filter, exclusion.clone());
+    series_results = series_results.refine(filter, exclusion);
+    search_terms = format!("{search_terms} {}", line.trim());
+    matches = printer.print(
+      Some(movies_results.clone()),
+      Some(series_results.clone()),
+      imdb_url,
+      Some(&search_terms),
+    )?;
+  }
+
+  Ok(matches)
+}
+
+/// Parse a refinement of results, made of keywords the names of titles have to contain,
+/// "genre:GENRE", "since:YEAR" and "status:running|ended" filters, into a filter and an
+/// exclusion of the titles not matching the keywords.
+fn parse_refinement(line: &str) -> Result<(ImdbFilter, Option<Exclusion>), String> {
+  let mut filter = ImdbFilter::new();
+  let mut keywords = Vec::new();
+
+  for token in line.split_whitespace() {
+    match token.split_once(':') {
+      Some(("genre", genre)) => filter = filter.genre(parse_genre(genre)?),
+      Some(("since", year)) => {
+        filter = filter.released_since(year.parse().map_err(|_| format!("invalid year `{year}`"))?)
+      }
+      Some(("status", status)) => {
+        filter = filter.status(<Status as clap::ValueEnum>::from_str(status, true)?.into())
+      }
+      _ => keywords.push(token.to_lowercase()),
+    }
+  }
+
+  if keywords.is_empty() {
+    return Ok((filter, None));
+  }
+
+  let exclusion: Exclusion = Arc::new(move |title| {
+    let primary = title.primary_title().to_lowercase();
+    let original = title.original_title().map(str::to_lowercase).unwrap_or_default();
+    !keywords
+      .iter()
+      .all(|kw| primary.contains(kw.as_str()) || original.contains(kw.as_str()))
+  });
+
+  Ok((filter, Some(exclusion)))
+}
+
+/// Fetch unranked suggestions from IMDB, returns `None` if they could not be fetched.
+fn fetch_suggestions(title: &str) -> Option<Vec<ImdbSuggestion>> {
+  let title = title.trim();
+
+  let result = match parse_title_and_year(title) {
+    Some((title, year)) => ImdbSuggestion::fetch(title)
+      .map(|suggestions| suggestions.into_iter().filter(|s| s.start_year() == Some(year)).collect()),
+    None => ImdbSuggestion::fetch(title),
+  };
+
+  match result {
+    Ok(suggestions) => Some(suggestions),
+    Err(e) => {
+      eprintln!("Could not fetch suggestions from IMDB: {e}");
+      None
+    }
+  }
+}
+
+fn imdb_batch(
+  file: &Path,
+  provider: &dyn TitleProvider,
+  imdb_url: &Url,
+  query: &Query,
+  exact: bool,
+  quiet: bool,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<usize, Error> {
+  let reader: Box<dyn BufRead> = if file == Path::new("-") {
+    Box::new(io::stdin().lock())
+  } else {
+    Box::new(BufReader::new(fs::File::open(file)?))
+  };
+
+  let mut total_lines = 0;
+  let mut groups = Vec::new();
+  let mut unmatched = Vec::new();
+
+  for line in reader.lines() {
+    let line = line?;
+    let input = line.trim();
+
+    if input.is_empty() || input.starts_with('#') {
+      continue;
+    }
+
+    let index = total_lines;
+    total_lines += 1;
+
+    let (movies_results, series_results, search_terms) = match search_title(input, provider, query, exact) {
+      Ok(results) => results,
+      Err(e) => {
+        warn!("Ignoring `{input}`: {e}");
+        unmatched.push(line);
+        continue;
+      }
+    };
+
+    let group = SearchGroup::new(index, search_terms, Some(movies_results), Some(series_results));
+
+    if group.total_len() == 0 {
+      unmatched.push(line);
+    }
+
+    groups.push(group);
+  }
+
+  let matches = printer.print_groups(groups, imdb_url)?;
+
+  if !quiet && !unmatched.is_empty() {
+    eprintln!("{} of {total_lines} lines did not match any titles:", unmatched.len());
+    for line in unmatched {
+      eprintln!("  {line}");
+    }
+  }
+
+  Ok(matches)
+}
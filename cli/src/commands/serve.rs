@@ -0,0 +1,42 @@
+#![warn(clippy::all)]
+
+//! Serving queries over HTTP.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use crate::context::{update_imdb_service, Context};
+use crate::opts::GeneralOpts;
+use crate::server;
+
+#[derive(Debug, clap::Args)]
+pub struct ServeArgs {
+  /// Address and port to listen on
+  #[clap(short, long, default_value = "127.0.0.1:8080")]
+  listen: SocketAddr,
+
+  /// Only let `/scan` read directories under DIR (can be repeated), otherwise it can read
+  /// any directory when listening on a loopback address and none at all otherwise
+  #[clap(long, value_name = "DIR")]
+  scan_root: Vec<PathBuf>,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+}
+
+impl ServeArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { listen, scan_root, general_opts } = self;
+
+    Context::with_service(general_opts, globals).run(|context| {
+      let update_fn = || update_imdb_service(&context.paths, &context.general_opts, false);
+      let update_fn: Option<&server::UpdateFn<'_>> = if context.general_opts.background_update {
+        Some(&update_fn)
+      } else {
+        None
+      };
+      server::serve(listen, &context.service, &context.imdb_url, &scan_root, update_fn)?;
+      Ok(None)
+    })
+  }
+}
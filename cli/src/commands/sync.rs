@@ -0,0 +1,83 @@
+#![warn(clippy::all)]
+
+//! Pulling personal data from other services.
+
+use std::env;
+use std::path::PathBuf;
+
+use crate::context::{get_paths, run_without_service};
+use crate::error::Error;
+use crate::opts::GeneralOpts;
+
+use tvrank::paths::Paths;
+use tvrank::trakt::{Trakt, TraktToken};
+
+use tracing::debug;
+
+#[derive(Debug, clap::Subcommand)]
+pub enum SyncCommand {
+  /// Pull the watched history and ratings of a Trakt account, linking it on first use (the
+  /// application credentials are read from the TVRANK_TRAKT_CLIENT_ID and
+  /// TVRANK_TRAKT_CLIENT_SECRET environment variables)
+  Trakt {
+    #[clap(flatten)]
+    general_opts: GeneralOpts,
+  },
+}
+
+impl SyncCommand {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    match self {
+      Self::Trakt { general_opts } => run_without_service(general_opts, globals, |general_opts| {
+        sync_trakt(&get_paths(general_opts)?)?;
+        Ok(None)
+      }),
+    }
+  }
+}
+
+pub const TRAKT_CLIENT_ID_VAR: &str = "TVRANK_TRAKT_CLIENT_ID";
+pub const TRAKT_CLIENT_SECRET_VAR: &str = "TVRANK_TRAKT_CLIENT_SECRET";
+
+fn trakt_token_file(paths: &Paths) -> PathBuf {
+  paths.trakt_dir().join("token.json")
+}
+
+pub fn trakt_watched_file(paths: &Paths) -> PathBuf {
+  paths.trakt_dir().join("watched.json")
+}
+
+/// Pull the watched titles of the Trakt account linked to the profile, linking an account
+/// first if none is.
+fn sync_trakt(paths: &Paths) -> Result<(), Error> {
+  let client_id = env::var(TRAKT_CLIENT_ID_VAR).map_err(|_| Error::NoTraktClient)?;
+  let client_secret = env::var(TRAKT_CLIENT_SECRET_VAR).map_err(|_| Error::NoTraktClient)?;
+  let trakt = Trakt::new(&client_id, &client_secret)?;
+
+  let token_file = trakt_token_file(paths);
+  debug!("Trakt token file: {}", token_file.display());
+
+  let token = match TraktToken::load(&token_file)? {
+    Some(token) if token.is_expired() => {
+      debug!("Refreshing the Trakt token");
+      trakt.refresh(&token)?
+    }
+    Some(token) => token,
+    None => {
+      let code = trakt.device_code()?;
+      eprintln!(
+        "To link your Trakt account, go to {} and enter the code {}",
+        code.verification_url(),
+        code.user_code()
+      );
+      trakt.wait_for_token(&code)?
+    }
+  };
+  token.save(&token_file)?;
+
+  let watched = trakt.watched(&token)?;
+  watched.save(&trakt_watched_file(paths))?;
+  println!("Synced {} watched titles, {} of them rated, from Trakt", watched.len(), watched.rated());
+
+  Ok(())
+}
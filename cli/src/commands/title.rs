@@ -0,0 +1,239 @@
+#![warn(clippy::all)]
+
+//! Displaying single titles: all their fields, the ratings of the episodes of series, and
+//! how ratings and votes evolved across database updates.
+
+use std::time::Duration;
+
+use crate::commands::{display_title_and_year, timed_query};
+use crate::context::{create_imdb_episodes, create_output_printer, Context};
+use crate::error::Error;
+use crate::opts::GeneralOpts;
+use crate::print::{Error as PrintError, OutputFormat, Printer};
+
+use tvrank::imdb::{Imdb, ImdbHistory, ImdbQuery, ImdbTitleId};
+use tvrank::paths::Paths;
+
+use humantime::format_duration;
+use reqwest::Url;
+
+#[derive(Debug, clap::Args)]
+pub struct InfoArgs {
+  /// The IMDB ID of the title ("ttXXXXX")
+  #[clap(name = "IMDB-ID")]
+  id: String,
+
+  /// Set output format
+  #[clap(short, long, value_enum, default_value = "table")]
+  output: OutputFormat,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+}
+
+impl InfoArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { id, output, general_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let printer = create_output_printer(&output, &context.general_opts);
+      Ok(Some(imdb_info(&id, &context.service, &context.imdb_url, printer)?))
+    })
+  }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ShowArgs {
+  /// The IMDB ID of the series ("ttXXXXX")
+  #[clap(name = "IMDB-ID")]
+  id: String,
+
+  /// Set output format
+  #[clap(short, long, value_enum, default_value = "table")]
+  output: OutputFormat,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+}
+
+impl ShowArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { id, output, general_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let printer = create_output_printer(&output, &context.general_opts);
+      let matches = timed_query(|| {
+        imdb_show(&id, &context.service, &context.paths, &context.general_opts, &context.imdb_url, printer)
+      })?;
+      Ok(Some(matches))
+    })
+  }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct TrendingArgs {
+  /// Only display the top N titles
+  #[clap(short = 'n', long, name = "N", default_value = "25")]
+  count: usize,
+
+  /// Time window to compare against (e.g. "30d" or "2weeks")
+  #[clap(short, long, default_value = "30d", value_parser = humantime::parse_duration)]
+  window: Duration,
+
+  /// Set output format
+  #[clap(short, long, value_enum, default_value = "table")]
+  output: OutputFormat,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+}
+
+impl TrendingArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { count, window, output, general_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let printer = create_output_printer(&output, &context.general_opts);
+      let matches = timed_query(|| {
+        imdb_trending(&context.service, &context.history, &context.imdb_url, count, window, printer)
+      })?;
+      Ok(Some(matches))
+    })
+  }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct HistoryArgs {
+  /// The IMDB ID of the title ("ttXXXXX")
+  #[clap(name = "IMDB-ID")]
+  id: String,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+}
+
+impl HistoryArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { id, general_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let quiet = context.general_opts.quiet;
+      Ok(Some(imdb_history(&id, &context.service, &context.history, quiet)?))
+    })
+  }
+}
+
+fn imdb_info(
+  id: &str,
+  imdb: &Imdb,
+  imdb_url: &Url,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<usize, Error> {
+  let id = ImdbTitleId::try_from(id.trim())?;
+  let Some((title, _)) = imdb.by_id_any(&id) else {
+    return Err(Error::UnknownImdbId(id.to_string()));
+  };
+
+  Ok(printer.print_info(title, imdb_url)?)
+}
+
+fn imdb_show(
+  id: &str,
+  imdb: &Imdb,
+  paths: &Paths,
+  general_opts: &GeneralOpts,
+  imdb_url: &Url,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<usize, Error> {
+  let id = ImdbTitleId::try_from(id.trim())?;
+  let series = match imdb.by_id_any(&id) {
+    Some((series, ImdbQuery::Series)) => series,
+    Some((_, ImdbQuery::Movies)) => return Err(Error::NotASeries(id.to_string())),
+    None => return Err(Error::UnknownImdbId(id.to_string())),
+  };
+
+  let episodes = create_imdb_episodes(paths, general_opts)?;
+  let episodes = episodes.of_series(&id).unwrap_or_default();
+  Ok(printer.print_episodes(series, &episodes, imdb_url)?)
+}
+
+fn imdb_trending(
+  imdb: &Imdb,
+  history: &ImdbHistory,
+  imdb_url: &Url,
+  count: usize,
+  window: Duration,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<usize, Error> {
+  let mut movies = history.trending(imdb, window, ImdbQuery::Movies)?;
+  let mut series = history.trending(imdb, window, ImdbQuery::Series)?;
+  movies.truncate(count);
+  series.truncate(count);
+
+  let matches = printer.print_trending(&movies, &series, imdb_url, &format_duration(window).to_string())?;
+
+  Ok(matches)
+}
+
+fn imdb_history(id: &str, imdb: &Imdb, history: &ImdbHistory, quiet: bool) -> Result<usize, Error> {
+  let id = ImdbTitleId::try_from(id.trim())?;
+  let Some((title, _)) = imdb.by_id_any(&id) else {
+    return Err(Error::UnknownImdbId(id.to_string()));
+  };
+
+  let points = history.title_history(&id)?;
+  if quiet {
+    return Ok(points.len());
+  }
+
+  match title.start_year() {
+    Some(year) => println!("{} {}", id, display_title_and_year(title.primary_title(), year)),
+    None => println!("{} {}", id, title.primary_title()),
+  }
+
+  let (Some(first), Some(last)) = (points.first(), points.last()) else {
+    println!("No rating history yet, it is recorded each time the database is updated");
+    return Ok(0);
+  };
+
+  let ratings: Vec<u8> = points.iter().map(|point| point.rating().rating()).collect();
+  println!(
+    "Rating: {} {} -> {}",
+    sparkline(&ratings),
+    display_rating(first.rating().rating()),
+    display_rating(last.rating().rating())
+  );
+
+  let mut previous_votes = None;
+  for point in &points {
+    let date = humantime::format_rfc3339_seconds(point.taken()).to_string();
+    let rating = point.rating();
+    let gained = previous_votes
+      .map_or_else(String::new, |votes| format!(" ({:+})", i64::from(rating.votes()) - i64::from(votes)));
+    println!("  {}  {}  {} votes{}", &date[..10], display_rating(rating.rating()), rating.votes(), gained);
+    previous_votes = Some(rating.votes());
+  }
+
+  Ok(points.len())
+}
+
+/// Display a rating out of 100 as it is displayed by IMDB, e.g. "7.5".
+pub fn display_rating(rating: u8) -> String {
+  format!("{}.{}", rating / 10, rating % 10)
+}
+
+/// A line of block characters, one per value, whose heights follow the values.
+fn sparkline(values: &[u8]) -> String {
+  const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+  let min = values.iter().copied().min().unwrap_or(0);
+  let max = values.iter().copied().max().unwrap_or(0);
+
+  values
+    .iter()
+    .map(|&value| match max - min {
+      0 => BARS[BARS.len() / 2],
+      range => BARS[usize::from(value - min) * (BARS.len() - 1) / usize::from(range)],
+    })
+    .collect()
+}
@@ -0,0 +1,245 @@
+#![warn(clippy::all)]
+
+//! Watching a directory for new movie and series folders, and looking them up once their
+//! contents settle.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::commands::scan::{resolve_scan_dir, ScanDir};
+use crate::context::{create_output_printer, Context};
+use crate::error::Error;
+use crate::opts::{GeneralOpts, SearchOpts};
+use crate::print::{Error as PrintError, Printer};
+use crate::query::Query;
+
+use tvrank::imdb::{ImdbQuery, ImdbTitle};
+use tvrank::provider::TitleProvider;
+
+use notify::{RecursiveMode, Watcher};
+use reqwest::Url;
+use serde::Serialize;
+use tracing::{debug, warn};
+use walkdir::WalkDir;
+
+#[derive(Debug, clap::Args)]
+pub struct WatchArgs {
+  /// Directory to watch
+  #[clap(name = "DIR")]
+  dir: PathBuf,
+
+  /// How long the contents of a new folder must stay unchanged before it is looked up
+  /// (e.g. "10s" or "1m")
+  #[clap(long, default_value = "10s", value_parser = humantime::parse_duration)]
+  settle: Duration,
+
+  /// Also append every lookup as a line of JSON to FILE
+  #[clap(long, value_name = "FILE")]
+  log: Option<PathBuf>,
+
+  #[clap(flatten)]
+  general_opts: GeneralOpts,
+
+  #[clap(flatten)]
+  search_opts: SearchOpts,
+}
+
+impl WatchArgs {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    let Self { dir, settle, log, general_opts, search_opts } = self;
+
+    Context::new(general_opts, globals).run(|context| {
+      let query = context.query(search_opts)?;
+      let search = context.service.with_options(query.opts().search_options());
+      let printer = create_output_printer(&query.opts().output, &context.general_opts);
+      imdb_watch(
+        &dir,
+        &context.providers(&search),
+        &context.imdb_url,
+        &query,
+        settle,
+        log.as_deref(),
+        printer,
+      )?;
+      Ok(None)
+    })
+  }
+}
+
+/// A lookup of a folder that appeared in a watched directory, as written to the log.
+#[derive(Serialize)]
+struct WatchEvent<'a> {
+  time: String,
+  path: &'a Path,
+  movies: &'a [&'a ImdbTitle<'a>],
+  series: &'a [&'a ImdbTitle<'a>],
+}
+
+/// The folders directly under a directory.
+fn child_dirs(dir: &Path) -> Result<HashSet<PathBuf>, Error> {
+  let mut dirs = HashSet::new();
+
+  for entry in fs::read_dir(dir)? {
+    let path = entry?.path();
+    if path.is_dir() {
+      dirs.insert(path);
+    }
+  }
+
+  Ok(dirs)
+}
+
+/// Whether there is at least one file under a directory.
+fn has_files(dir: &Path) -> Result<bool, Error> {
+  for entry in WalkDir::new(dir) {
+    if entry?.file_type().is_file() {
+      return Ok(true);
+    }
+  }
+
+  Ok(false)
+}
+
+/// The folder directly under `dir` that contains `path`, if any.
+///
+/// # Arguments
+///
+/// * `dir` - The watched directory.
+/// * `path` - Path of a file system event under it.
+fn watched_child(dir: &Path, path: &Path) -> Option<PathBuf> {
+  let child = path.strip_prefix(dir).ok()?.components().next()?;
+  Some(dir.join(child))
+}
+
+/// Look up a folder that appeared in a watched directory, print its titles and append them
+/// to the log.
+fn watch_lookup(
+  dir: &Path,
+  provider: &dyn TitleProvider,
+  imdb_url: &Url,
+  query: &Query,
+  log: Option<&mut fs::File>,
+  printer: &dyn Printer<Error = PrintError>,
+) -> Result<(), Error> {
+  let (search_terms, movies, series) = match resolve_scan_dir(dir, provider)? {
+    ScanDir::Identified(title, ImdbQuery::Movies) => (None, Some(vec![title]), None),
+    ScanDir::Identified(title, ImdbQuery::Series) => (None, None, Some(vec![title])),
+    ScanDir::Named { search_terms, movies, series } => (Some(search_terms), movies, series),
+    ScanDir::Skipped => return Ok(()),
+    ScanDir::Container => {
+      warn!("No movie or series detected in `{}`", dir.display());
+      return Ok(());
+    }
+  };
+
+  if let Some(log) = log {
+    let event = WatchEvent {
+      time: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+      path: dir,
+      movies: movies.as_deref().unwrap_or_default(),
+      series: series.as_deref().unwrap_or_default(),
+    };
+    writeln!(log, "{}", serde_json::to_string(&event)?)?;
+  }
+
+  let results = |found: Vec<_>| {
+    let mut results = query.new_results(query.opts().top);
+    results.extend(found);
+    results
+  };
+  let search_terms = search_terms.unwrap_or_else(|| dir.display().to_string());
+  printer.print(movies.map(results), series.map(results), imdb_url, Some(&search_terms))?;
+
+  Ok(())
+}
+
+fn imdb_watch(
+  dir: &Path,
+  provider: &dyn TitleProvider,
+  imdb_url: &Url,
+  query: &Query,
+  settle: Duration,
+  log: Option<&Path>,
+  printer: Box<dyn Printer<Error = PrintError>>,
+) -> Result<(), Error> {
+  if !dir.is_dir() {
+    return Err(Error::NotDir(dir.to_owned()));
+  }
+
+  // Events are reported with the path the directory was watched with.
+  let dir = fs::canonicalize(dir)?;
+
+  let mut log = match log {
+    Some(path) => Some(OpenOptions::new().create(true).append(true).open(path)?),
+    None => None,
+  };
+
+  let (sender, events) = mpsc::channel();
+  let mut watcher = notify::recommended_watcher(sender)?;
+  watcher.watch(&dir, RecursiveMode::Recursive)?;
+
+  // Folders that already exist are not looked up, new ones are looked up once nothing
+  // changed in them for `settle`.
+  let mut known = child_dirs(&dir)?;
+  let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+
+  eprintln!("Watching {} for new folders", dir.display());
+
+  loop {
+    let timeout = pending
+      .values()
+      .map(|changed| settle.saturating_sub(changed.elapsed()))
+      .min()
+      .unwrap_or(settle);
+
+    match events.recv_timeout(timeout) {
+      Ok(event) => {
+        for path in event?.paths {
+          let Some(child) = watched_child(&dir, &path) else { continue };
+
+          if !child.is_dir() {
+            known.remove(&child);
+            pending.remove(&child);
+          } else if !known.contains(&child) && pending.insert(child.clone(), Instant::now()).is_none() {
+            debug!("New folder `{}`", child.display());
+          }
+        }
+      }
+      Err(RecvTimeoutError::Timeout) => {}
+      Err(RecvTimeoutError::Disconnected) => return Ok(()),
+    }
+
+    let mut settled: Vec<_> = pending
+      .iter()
+      .filter(|(_, changed)| changed.elapsed() >= settle)
+      .map(|(path, _)| path.clone())
+      .collect();
+
+    settled.sort();
+    for path in settled {
+      match has_files(&path) {
+        Ok(true) => {}
+        // Folders are only looked up once they have contents, wait for more changes.
+        Ok(false) => {
+          pending.insert(path, Instant::now());
+          continue;
+        }
+        Err(e) => {
+          warn!("Could not check `{}`: {e}", path.display());
+          pending.insert(path, Instant::now());
+          continue;
+        }
+      }
+
+      pending.remove(&path);
+      known.insert(path.clone());
+      if let Err(e) = watch_lookup(&path, provider, imdb_url, query, log.as_mut(), printer.as_ref()) {
+        warn!("Could not look up `{}`: {e}", path.display());
+      }
+    }
+  }
+}
@@ -0,0 +1,156 @@
+#![warn(clippy::all)]
+
+//! The watchlist, whose rating changes are reported after database updates.
+
+use std::{env, process};
+
+use crate::commands::title::display_rating;
+use crate::context::{get_paths, run_without_service, Context};
+use crate::error::Error;
+use crate::opts::GeneralOpts;
+
+use tvrank::imdb::{Imdb, ImdbTitleId, ImdbWatchlist};
+use tvrank::paths::Paths;
+
+use tracing::{info, warn};
+
+#[derive(Debug, clap::Subcommand)]
+pub enum WatchlistCommand {
+  /// Add a title to the watchlist
+  Add {
+    /// The IMDB ID of the title ("ttXXXXX")
+    #[clap(name = "IMDB-ID")]
+    id: String,
+
+    #[clap(flatten)]
+    general_opts: GeneralOpts,
+  },
+
+  /// Remove a title from the watchlist
+  Remove {
+    /// The IMDB ID of the title ("ttXXXXX")
+    #[clap(name = "IMDB-ID")]
+    id: String,
+
+    #[clap(flatten)]
+    general_opts: GeneralOpts,
+  },
+
+  /// List the titles of the watchlist
+  List {
+    #[clap(flatten)]
+    general_opts: GeneralOpts,
+  },
+}
+
+impl WatchlistCommand {
+  pub fn run(self, globals: GeneralOpts) -> (bool, Option<usize>) {
+    match self {
+      Self::Add { id, general_opts } => Context::new(general_opts, globals).run(|context| {
+        watchlist_add(&id, &context.service, &context.paths)?;
+        Ok(None)
+      }),
+      Self::Remove { id, general_opts } => run_without_service(general_opts, globals, |general_opts| {
+        watchlist_remove(&id, &get_paths(general_opts)?)?;
+        Ok(None)
+      }),
+      Self::List { general_opts } => Context::new(general_opts, globals).run(|context| {
+        let titles = watchlist_list(&context.service, &context.paths)?;
+        Ok(Some(titles))
+      }),
+    }
+  }
+}
+
+/// Environment variable holding a shell command run when the ratings of titles of the
+/// watchlist changed after a database update, with the changes in `NOTIFY_CHANGES_VAR`.
+const NOTIFY_VAR: &str = "TVRANK_NOTIFY";
+const NOTIFY_CHANGES_VAR: &str = "TVRANK_CHANGES";
+
+fn watchlist_add(id: &str, imdb: &Imdb, paths: &Paths) -> Result<(), Error> {
+  let id = ImdbTitleId::try_from(id.trim())?;
+  let Some((title, _)) = imdb.by_id_any(&id) else {
+    return Err(Error::UnknownImdbId(id.to_string()));
+  };
+
+  let mut watchlist = ImdbWatchlist::load(&paths.watchlist_file())?;
+  if watchlist.add(&id, title.rating()) {
+    watchlist.save()?;
+    println!("Added {} {} to the watchlist", id, title.primary_title());
+  } else {
+    println!("{} {} is already in the watchlist", id, title.primary_title());
+  }
+
+  Ok(())
+}
+
+fn watchlist_remove(id: &str, paths: &Paths) -> Result<(), Error> {
+  let id = ImdbTitleId::try_from(id.trim())?;
+  let mut watchlist = ImdbWatchlist::load(&paths.watchlist_file())?;
+  if watchlist.remove(&id) {
+    watchlist.save()?;
+    println!("Removed {id} from the watchlist");
+  } else {
+    println!("{id} is not in the watchlist");
+  }
+
+  Ok(())
+}
+
+fn watchlist_list(imdb: &Imdb, paths: &Paths) -> Result<usize, Error> {
+  let watchlist = ImdbWatchlist::load(&paths.watchlist_file())?;
+
+  for id in watchlist.ids() {
+    match ImdbTitleId::try_from(id).ok().and_then(|id| imdb.by_id_any(&id)) {
+      Some((title, _)) => match title.rating() {
+        Some(rating) => println!(
+          "{id} {} {} ({} votes)",
+          title.primary_title(),
+          display_rating(rating.rating()),
+          rating.votes()
+        ),
+        None => println!("{id} {}", title.primary_title()),
+      },
+      None => println!("{id} (not in the database)"),
+    }
+  }
+
+  Ok(watchlist.len())
+}
+
+/// Report the changes in the ratings of the titles of the watchlist since they were last
+/// seen, and run the command in `NOTIFY_VAR` with a summary of them, if any.
+pub fn notify_watchlist_changes(imdb: &Imdb, paths: &Paths) -> Result<(), Error> {
+  let mut watchlist = ImdbWatchlist::load(&paths.watchlist_file())?;
+  let changes = watchlist.update(imdb);
+  if changes.is_empty() {
+    return Ok(());
+  }
+
+  watchlist.save()?;
+
+  let summary: Vec<String> = changes.iter().map(ToString::to_string).collect();
+  let summary = summary.join("\n");
+  info!("Ratings of the watchlist changed:\n{summary}");
+
+  let Ok(notify) = env::var(NOTIFY_VAR) else {
+    return Ok(());
+  };
+
+  let mut command = if cfg!(windows) {
+    let mut command = process::Command::new("cmd");
+    command.arg("/C");
+    command
+  } else {
+    let mut command = process::Command::new("sh");
+    command.arg("-c");
+    command
+  };
+
+  let status = command.arg(&notify).env(NOTIFY_CHANGES_VAR, &summary).status()?;
+  if !status.success() {
+    warn!("Notification command `{notify}` failed: {status}");
+  }
+
+  Ok(())
+}
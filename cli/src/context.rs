@@ -0,0 +1,390 @@
+#![warn(clippy::all)]
+
+//! The IMDB database and the locations of personal state that commands run with, and how
+//! they are set up from the general options.
+
+use std::cell::Cell;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::commands::watchlist::notify_watchlist_changes;
+use crate::error::Error;
+use crate::logging;
+use crate::opts::{merge_general_opts, GeneralOpts, SearchOpts};
+use crate::print::{JsonPrinter, OutputFormat, Printer, QuietPrinter, TablePrinter, YamlPrinter};
+use crate::query::Query;
+use crate::ui::{PhaseProgress, ProgressFormat};
+use crate::update::spawn_update_process;
+
+use tvrank::enrich::{Enricher, Provider as ImdbEnrichmentProvider};
+use tvrank::imdb::{
+  Imdb, ImdbEpisodes, ImdbError, ImdbHistory, ImdbSearch, ImdbTombstones, ImdbUpdatePolicy,
+};
+use tvrank::paths::Paths;
+use tvrank::provider::TitleProviders;
+use tvrank::utils::cancel::Cancellation;
+
+use humantime::format_duration;
+use reqwest::Url;
+use tracing::{debug, error, info_span, warn, Level};
+
+pub fn create_cache_dir(paths: &Paths) -> Result<&Path, Error> {
+  let app_cache_dir = paths.cache_dir();
+  fs::create_dir_all(app_cache_dir)?;
+  debug!("Cache directory: {}", app_cache_dir.display());
+  Ok(app_cache_dir)
+}
+
+fn create_history(paths: &Paths) -> ImdbHistory {
+  let app_history_dir = paths.history_dir();
+  debug!("Rating history directory: {}", app_history_dir.display());
+  ImdbHistory::new(&app_history_dir)
+}
+
+fn create_tombstones(paths: &Paths) -> ImdbTombstones {
+  let app_tombstones_dir = paths.tombstones_dir();
+  debug!("Retired title IDs directory: {}", app_tombstones_dir.display());
+  ImdbTombstones::new(&app_tombstones_dir)
+}
+
+/// Environment variable holding the API key of an enrichment provider.
+fn api_key_var(provider: ImdbEnrichmentProvider) -> &'static str {
+  match provider {
+    ImdbEnrichmentProvider::Tmdb => "TVRANK_TMDB_API_KEY",
+    ImdbEnrichmentProvider::Omdb => "TVRANK_OMDB_API_KEY",
+  }
+}
+
+/// An enricher with the API keys of the requested providers, skipping providers whose key
+/// is not set.
+pub fn create_enricher(
+  paths: &Paths,
+  providers: &[ImdbEnrichmentProvider],
+  delay: Duration,
+) -> Result<Enricher, Error> {
+  let enrichment_dir = paths.enrichment_dir();
+  debug!("Enrichment directory: {}", enrichment_dir.display());
+  let mut enricher = Enricher::new(&enrichment_dir, delay)?;
+
+  for &provider in providers {
+    match env::var(api_key_var(provider)) {
+      Ok(api_key) if !api_key.is_empty() => enricher = enricher.with_api_key(provider, api_key),
+      _ => warn!("{} is not set, skipping {provider}", api_key_var(provider)),
+    }
+  }
+
+  if !providers.iter().any(|&provider| enricher.has_api_key(provider)) {
+    let vars: Vec<_> = providers.iter().map(|&provider| api_key_var(provider)).collect();
+    return Err(Error::NoApiKeys(vars.join(" or ")));
+  }
+
+  Ok(enricher)
+}
+
+fn get_imdb_url() -> Result<Url, Error> {
+  const IMDB: &str = "https://www.imdb.com/title/";
+  let imdb_url = Url::parse(IMDB)?;
+  Ok(imdb_url)
+}
+
+pub fn create_output_printer(
+  output_format: &OutputFormat,
+  general_opts: &GeneralOpts,
+) -> Box<dyn Printer<Error = crate::print::Error>> {
+  if general_opts.quiet {
+    return Box::new(QuietPrinter::new(output_format.clone()));
+  }
+
+  match output_format {
+    OutputFormat::Json => Box::new(JsonPrinter::new()),
+    OutputFormat::Table => Box::new(TablePrinter::new(general_opts.color)),
+    OutputFormat::Yaml => Box::new(YamlPrinter::new()),
+  }
+}
+
+fn imdb_update_policy(general_opts: &GeneralOpts) -> ImdbUpdatePolicy {
+  if general_opts.force_update {
+    ImdbUpdatePolicy::Always
+  } else if general_opts.background_update {
+    ImdbUpdatePolicy::InBackground
+  } else {
+    ImdbUpdatePolicy::IfOutdated
+  }
+}
+
+/// Load the IMDB database, updating it according to `update_policy`, and record the ratings
+/// and the retired IDs of the database if this process rebuilt it.
+pub fn create_imdb_service(
+  paths: &Paths,
+  update_policy: ImdbUpdatePolicy,
+  wait_for_update: bool,
+  general_opts: &GeneralOpts,
+) -> Result<Imdb, Error> {
+  let app_cache_dir = create_cache_dir(paths)?;
+  let progress_format = general_opts.progress.unwrap_or(ProgressFormat::Bar);
+  // Progress is only reported while the databases are rebuilt.
+  let rebuilt = Cell::new(false);
+  let build = |wait_for_update| {
+    let import = PhaseProgress::new(progress_format, "import", "Building IMDB databases...");
+    let result = Imdb::builder()
+      .cache_dir(app_cache_dir)
+      .update_policy(update_policy)
+      .wait_for_update(wait_for_update)
+      .progress(|len, delta| {
+        rebuilt.set(true);
+        import.report(len, delta);
+      })
+      .compression(general_opts.cache_compression.map(Into::into).unwrap_or_default())
+      .name_index(general_opts.name_index)
+      .lazy(true)
+      .build();
+    import.finish();
+    result
+  };
+
+  let _load = info_span!("load").entered();
+  let start_time = Instant::now();
+  let imdb = match build(false) {
+    Err(ImdbError::UpdateInProgress) if wait_for_update => {
+      if !general_opts.quiet {
+        eprintln!("Another tvrank process is updating the database, waiting for it to finish...");
+      }
+      build(true)
+    }
+    result => result,
+  }
+  .map_err(|e| match e {
+    ImdbError::CorruptCache(_) => Error::CorruptCache(e),
+    e => Error::Imdb(e),
+  })?;
+  debug!("Loaded IMDB database in {}", format_duration(Instant::now().duration_since(start_time)));
+
+  if rebuilt.get() {
+    record_database_update(&imdb, paths);
+  }
+
+  Ok(imdb)
+}
+
+/// Record the ratings of an updated IMDB database in the rating history, reporting the
+/// changes of the watchlist, and the IDs retired since the previous database.
+pub fn record_database_update(imdb: &Imdb, paths: &Paths) {
+  match create_history(paths).record(imdb) {
+    Ok(false) => {}
+    Ok(true) => {
+      if let Err(e) = notify_watchlist_changes(imdb, paths) {
+        warn!("Could not report watchlist changes: {e}");
+      }
+    }
+    Err(e) => warn!("Could not record rating history: {e}"),
+  }
+
+  match create_tombstones(paths).record(imdb) {
+    Ok(0) => {}
+    Ok(retired) => debug!("{retired} IMDB IDs were retired since the previous database"),
+    Err(e) => warn!("Could not record retired title IDs: {e}"),
+  }
+}
+
+/// Update the IMDB database if it is outdated, or regardless of its age with `force_update`,
+/// and load it, or return `None` if another process is already updating it.
+pub fn update_imdb_service(
+  paths: &Paths,
+  general_opts: &GeneralOpts,
+  force_update: bool,
+) -> Result<Option<Imdb>, Error> {
+  let update_policy = if force_update {
+    ImdbUpdatePolicy::Always
+  } else {
+    ImdbUpdatePolicy::IfOutdated
+  };
+
+  match create_imdb_service(paths, update_policy, false, general_opts) {
+    Ok(imdb) => Ok(Some(imdb)),
+    Err(Error::Imdb(ImdbError::UpdateInProgress)) => {
+      debug!("IMDB database is already being updated by another process");
+      Ok(None)
+    }
+    Err(e) => Err(e),
+  }
+}
+
+pub fn create_imdb_episodes(paths: &Paths, general_opts: &GeneralOpts) -> Result<ImdbEpisodes, Error> {
+  let app_cache_dir = create_cache_dir(paths)?;
+  let progress_format = general_opts.progress.unwrap_or(ProgressFormat::Bar);
+  let compression = general_opts.cache_compression.map(Into::into).unwrap_or_default();
+
+  let start_time = Instant::now();
+  let episodes = with_download_progress(progress_format, "Downloading IMDB episodes...", |progress_fn| {
+    let cancellation = Cancellation::new();
+    ImdbEpisodes::new_cancellable(
+      app_cache_dir,
+      general_opts.force_update,
+      progress_fn,
+      &cancellation,
+      compression,
+    )
+  })?;
+  debug!("Loaded IMDB episodes in {}", format_duration(Instant::now().duration_since(start_time)));
+  Ok(episodes)
+}
+
+/// Call `f` with a download progress callback, which starts reporting progress on its first
+/// call.
+pub fn with_download_progress<T>(
+  progress_format: ProgressFormat,
+  msg: &str,
+  f: impl FnOnce(&dyn Fn(Option<u64>, u64)) -> T,
+) -> T {
+  let progress = PhaseProgress::new(progress_format, "download", msg);
+  let result = f(&|content_len, delta| progress.report(content_len, delta));
+  progress.finish();
+  result
+}
+
+/// The standard locations, with the personal state of the selected profile if any.
+pub fn get_paths(general_opts: &GeneralOpts) -> Result<Paths, Error> {
+  let paths = Paths::new()?;
+
+  let Some(profile) = &general_opts.profile else {
+    return Ok(paths);
+  };
+
+  let paths = paths.with_profile(profile)?;
+  if !paths.profile_dir().is_dir() {
+    return Err(Error::UnknownProfile(profile.clone()));
+  }
+
+  Ok(paths)
+}
+
+fn get_log_level(verbose: u8) -> Option<Level> {
+  match verbose {
+    0 => None,
+    1 => Some(Level::ERROR),
+    2 => Some(Level::WARN),
+    3 => Some(Level::INFO),
+    4 => Some(Level::DEBUG),
+    _ => Some(Level::TRACE),
+  }
+}
+
+fn init_logger(general_opts: &GeneralOpts) -> bool {
+  let log_level = get_log_level(general_opts.verbose);
+  let logger = logging::init(log_level, general_opts.log_format.unwrap_or_default());
+  if let Err(e) = &logger {
+    eprintln!("Error initializing logger: {e}");
+  }
+  logger.is_err()
+}
+
+pub struct Context {
+  pub general_opts: GeneralOpts,
+  pub have_logger: bool,
+  pub imdb_url: Url,
+  pub service: Imdb,
+  pub history: ImdbHistory,
+  pub tombstones: ImdbTombstones,
+  pub paths: Paths,
+}
+
+impl Context {
+  pub fn new(locals: GeneralOpts, globals: GeneralOpts) -> Self {
+    let context = Self::with_service(locals, globals);
+
+    if context.service.is_outdated() {
+      if let Err(e) = spawn_update_process(&context.general_opts) {
+        warn!("Could not update the IMDB database in the background: {e}");
+      }
+    }
+
+    context
+  }
+
+  /// Create a context whose outdated IMDB database is not updated in another process, for
+  /// commands that update it themselves.
+  pub fn with_service(locals: GeneralOpts, globals: GeneralOpts) -> Self {
+    let general_opts = merge_general_opts(locals, globals);
+    let have_logger = init_logger(&general_opts);
+
+    // error!("Error output enabled.");
+    // warn!("Warning output enabled.");
+    // info!("Info output enabled.");
+    // debug!("Debug output enabled.");
+    // trace!("Trace output enabled.");
+
+    let paths = fail!(have_logger, get_paths(&general_opts));
+    let imdb_url = fail!(have_logger, get_imdb_url());
+    let service = fail!(
+      have_logger,
+      create_imdb_service(
+        &paths,
+        imdb_update_policy(&general_opts),
+        !general_opts.fail_if_updating,
+        &general_opts
+      )
+    );
+
+    let history = create_history(&paths);
+    let tombstones = create_tombstones(&paths);
+
+    Self { general_opts, have_logger, imdb_url, service, history, tombstones, paths }
+  }
+
+  /// The providers searched by title, the IMDB database first, queried with the given
+  /// search options.
+  pub fn providers<'p>(&self, search: &'p ImdbSearch<'p>) -> TitleProviders<'p> {
+    TitleProviders::new().with(search)
+  }
+
+  /// A query of the IMDB database with the given search options, connected to the other data
+  /// sources they request.
+  pub fn query(&self, search_opts: SearchOpts) -> Result<Query, Error> {
+    Query::new(search_opts, &self.service).with_sources(&self.paths)
+  }
+
+  /// Run a command, exiting with its error if it fails.
+  ///
+  /// Returns whether output is quiet, along with the number of titles the command matched if
+  /// it reports them through the exit code.
+  ///
+  /// # Arguments
+  ///
+  /// * `command` - The command, run with this context.
+  pub fn run(self, command: impl FnOnce(&Self) -> Result<Option<usize>, Error>) -> (bool, Option<usize>) {
+    let result = command(&self);
+    let matches = fail!(self.have_logger, result => {
+      self.destroy();
+    });
+    let quiet = self.general_opts.quiet;
+    self.destroy();
+    (quiet, matches)
+  }
+
+  fn destroy(self) {
+    std::mem::forget(self)
+  }
+}
+
+/// Run a command that does not load the IMDB database, exiting with its error if it fails.
+///
+/// Returns whether output is quiet, along with the number of titles the command matched if it
+/// reports them through the exit code.
+///
+/// # Arguments
+///
+/// * `locals` - Options given after the sub-command.
+/// * `globals` - Options given before the sub-command.
+/// * `command` - The command, run with the merged options.
+pub fn run_without_service(
+  locals: GeneralOpts,
+  globals: GeneralOpts,
+  command: impl FnOnce(&GeneralOpts) -> Result<Option<usize>, Error>,
+) -> (bool, Option<usize>) {
+  let general_opts = merge_general_opts(locals, globals);
+  let have_logger = init_logger(&general_opts);
+  let matches = fail!(have_logger, command(&general_opts));
+  (general_opts.quiet, matches)
+}
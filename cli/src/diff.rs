@@ -0,0 +1,271 @@
+#![warn(clippy::all)]
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::print::{Error as PrintError, OutputFormat, Printer};
+use crate::search::{SearchGroup, SearchRes};
+use crate::usage::UsageSummary;
+
+use tvrank::imdb::{ImdbSeriesEpisodes, ImdbStats, ImdbSuggestion, ImdbTitle, ImdbTrending};
+
+use reqwest::Url;
+use serde::Deserialize;
+
+#[derive(Debug, thiserror::Error)]
+#[error("Previous results error")]
+pub enum Error {
+  #[error("IO error: {0}")]
+  Io(#[from] std::io::Error),
+  #[error("Not a JSON output of a search: {0}")]
+  Json(#[from] serde_json::Error),
+}
+
+/// Rating of a title in the JSON output of a search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+struct PreviousRating {
+  rating: u8,
+  votes: u32,
+}
+
+/// A title in the JSON output of a search, with only the fields needed for comparisons.
+#[derive(Debug, Clone, Deserialize)]
+struct PreviousTitle {
+  title_id: String,
+  primary_title: String,
+  start_year: Option<u16>,
+  rating: Option<PreviousRating>,
+}
+
+/// The results of a previous search, as saved from its JSON output.
+#[derive(Debug, Default, Deserialize)]
+pub struct PreviousResults {
+  #[serde(default)]
+  movies: Option<Vec<PreviousTitle>>,
+  #[serde(default)]
+  series: Option<Vec<PreviousTitle>>,
+}
+
+impl PreviousResults {
+  /// Load the results saved from the JSON output of a search (`--output json`).
+  pub fn load(path: &Path) -> Result<Self, Error> {
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+  }
+
+  fn titles(&self) -> impl Iterator<Item = &PreviousTitle> {
+    self.movies.iter().chain(self.series.iter()).flatten()
+  }
+}
+
+/// Display a rating out of 100 along with its votes, e.g. "7.5 (1234 votes)".
+fn display_rating(rating: Option<PreviousRating>) -> String {
+  match rating {
+    Some(rating) => format!("{}.{} ({} votes)", rating.rating / 10, rating.rating % 10, rating.votes),
+    None => String::from("no rating"),
+  }
+}
+
+/// A title of either the previous or the current results.
+struct Entry {
+  title_id: String,
+  primary_title: String,
+  start_year: Option<u16>,
+  rating: Option<PreviousRating>,
+}
+
+impl Entry {
+  fn from_title(title: &ImdbTitle) -> Self {
+    Self {
+      title_id: title.title_id().to_string(),
+      primary_title: title.primary_title().to_owned(),
+      start_year: title.start_year(),
+      rating: title
+        .rating()
+        .map(|rating| PreviousRating { rating: rating.rating(), votes: rating.votes() }),
+    }
+  }
+
+  fn from_previous(title: &PreviousTitle) -> Self {
+    Self {
+      title_id: title.title_id.clone(),
+      primary_title: title.primary_title.clone(),
+      start_year: title.start_year,
+      rating: title.rating,
+    }
+  }
+}
+
+impl fmt::Display for Entry {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} {}", self.title_id, self.primary_title)?;
+
+    if let Some(year) = self.start_year {
+      write!(f, " ({year})")?;
+    }
+
+    Ok(())
+  }
+}
+
+/// The differences between previous and current results: titles that are new, titles that
+/// are gone, and titles whose rating changed.
+pub struct ResultsDiff {
+  added: Vec<Entry>,
+  removed: Vec<Entry>,
+  changed: Vec<(Entry, Option<PreviousRating>)>,
+}
+
+impl ResultsDiff {
+  /// Compare the current results, as displayed, against the previous ones.
+  ///
+  /// # Arguments
+  ///
+  /// * `previous` - The previous results.
+  /// * `current` - The titles of the current results.
+  fn new<'a, 'storage: 'a>(
+    previous: &PreviousResults,
+    current: impl Iterator<Item = &'a ImdbTitle<'storage>>,
+  ) -> Self {
+    let mut previous: HashMap<&str, &PreviousTitle> =
+      previous.titles().map(|title| (title.title_id.as_str(), title)).collect();
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+
+    for title in current {
+      let entry = Entry::from_title(title);
+
+      match previous.remove(entry.title_id.as_str()) {
+        None => added.push(entry),
+        Some(before) if before.rating.map(|r| r.rating) != entry.rating.map(|r| r.rating) => {
+          changed.push((entry, before.rating))
+        }
+        Some(_) => {}
+      }
+    }
+
+    let mut removed: Vec<_> = previous.into_values().map(Entry::from_previous).collect();
+    removed.sort_by(|a, b| a.title_id.cmp(&b.title_id));
+
+    Self { added, removed, changed }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+  }
+}
+
+impl fmt::Display for ResultsDiff {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.is_empty() {
+      return writeln!(f, "No changes since the previous results");
+    }
+
+    writeln!(
+      f,
+      "Since the previous results: {} new, {} removed, {} with a changed rating",
+      self.added.len(),
+      self.removed.len(),
+      self.changed.len()
+    )?;
+
+    for entry in &self.added {
+      writeln!(f, "+ {entry}: {}", display_rating(entry.rating))?;
+    }
+
+    for entry in &self.removed {
+      writeln!(f, "- {entry}: {}", display_rating(entry.rating))?;
+    }
+
+    for (entry, before) in &self.changed {
+      writeln!(f, "~ {entry}: {} -> {}", display_rating(*before), display_rating(entry.rating))?;
+    }
+
+    Ok(())
+  }
+}
+
+/// A printer comparing the results it prints against previous results, and reporting the
+/// differences on the standard error so that the output can be saved for the next comparison.
+pub struct DiffPrinter {
+  printer: Box<dyn Printer<Error = PrintError>>,
+  previous: PreviousResults,
+}
+
+impl DiffPrinter {
+  #[must_use]
+  pub fn new(printer: Box<dyn Printer<Error = PrintError>>, previous: PreviousResults) -> Self {
+    Self { printer, previous }
+  }
+}
+
+impl Printer for DiffPrinter {
+  type Error = PrintError;
+
+  fn get_format(&self) -> OutputFormat {
+    self.printer.get_format()
+  }
+
+  fn print(
+    &self,
+    movies: Option<SearchRes>,
+    series: Option<SearchRes>,
+    imdb_url: &Url,
+    search_terms: Option<&str>,
+  ) -> Result<usize, Self::Error> {
+    let mut current: Vec<SearchRes> = movies.iter().chain(series.iter()).cloned().collect();
+    let diff = ResultsDiff::new(
+      &self.previous,
+      current.iter_mut().flat_map(|results| results.top_sorted_results().to_vec()),
+    );
+
+    let matches = self.printer.print(movies, series, imdb_url, search_terms)?;
+    eprint!("{diff}");
+    Ok(matches)
+  }
+
+  fn print_groups(&self, groups: Vec<SearchGroup>, imdb_url: &Url) -> Result<usize, Self::Error> {
+    self.printer.print_groups(groups, imdb_url)
+  }
+
+  fn print_trending(
+    &self,
+    movies: &[ImdbTrending],
+    series: &[ImdbTrending],
+    imdb_url: &Url,
+    window: &str,
+  ) -> Result<usize, Self::Error> {
+    self.printer.print_trending(movies, series, imdb_url, window)
+  }
+
+  fn print_remote(
+    &self,
+    remote: &[ImdbSuggestion],
+    imdb_url: &Url,
+    search_terms: Option<&str>,
+  ) -> Result<usize, Self::Error> {
+    self.printer.print_remote(remote, imdb_url, search_terms)
+  }
+
+  fn print_episodes(
+    &self,
+    series: &ImdbTitle,
+    episodes: &ImdbSeriesEpisodes,
+    imdb_url: &Url,
+  ) -> Result<usize, Self::Error> {
+    self.printer.print_episodes(series, episodes, imdb_url)
+  }
+
+  fn print_stats(&self, stats: &ImdbStats, imdb_url: &Url) -> Result<usize, Self::Error> {
+    self.printer.print_stats(stats, imdb_url)
+  }
+
+  fn print_info(&self, title: &ImdbTitle, imdb_url: &Url) -> Result<usize, Self::Error> {
+    self.printer.print_info(title, imdb_url)
+  }
+
+  fn print_storage_usage(&self, usage: &UsageSummary) -> Result<(), Self::Error> {
+    self.printer.print_storage_usage(usage)
+  }
+}
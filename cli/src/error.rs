@@ -0,0 +1,91 @@
+#![warn(clippy::all)]
+
+//! Errors of the sub-commands.
+
+use std::io;
+use std::path::PathBuf;
+
+use crate::commands::sync::{TRAKT_CLIENT_ID_VAR, TRAKT_CLIENT_SECRET_VAR};
+use crate::{diff, print, server};
+
+use tvrank::enrich::Error as EnrichmentError;
+use tvrank::imdb::{
+  ImdbEpisodesError, ImdbError, ImdbHistoryError, ImdbTitleIdError, ImdbTombstonesError, ImdbWatchlistError,
+};
+use tvrank::paths::Error as PathsError;
+use tvrank::scores::Error as ScoresError;
+use tvrank::trakt::{TraktError, TraktWatchedError};
+use tvrank::utils::search::SearchStringError;
+
+#[derive(Debug, thiserror::Error)]
+#[error("TVrank error")]
+pub enum Error {
+  #[error("Empty set of keywords")]
+  EmptyKeywords,
+  #[error("Invalid search string `{0}`")]
+  SearchString(#[from] SearchStringError),
+  #[error("Invalid regular expression: {0}")]
+  Regex(#[from] regex::Error),
+  #[error("Output error: {0}")]
+  Print(#[from] print::Error),
+  #[error("Server error: {0}")]
+  Server(#[from] server::Error),
+  #[error("Previous results error: {0}")]
+  Diff(#[from] diff::Error),
+  #[error("Directory error: {0}")]
+  Walkdir(#[from] walkdir::Error),
+  #[error("IMDB title ID error: {0}")]
+  ImdbTitleId(#[from] ImdbTitleIdError),
+  #[error("`{}` is not a directory", .0.display())]
+  NotDir(PathBuf),
+  #[error("Unknown IMDB ID `{0}`")]
+  UnknownImdbId(String),
+  #[error("`{0}` is a movie, not a series")]
+  NotASeries(String),
+  #[error("IMDB episodes error: {0}")]
+  Episodes(#[from] ImdbEpisodesError),
+  #[error("IO error: {0}")]
+  Io(#[from] io::Error),
+  #[error("Error watching directory: {0}")]
+  Watch(#[from] notify::Error),
+  #[error("Error writing title information file: {0}")]
+  Json(#[from] serde_json::Error),
+  #[error("Profile `{0}` does not exist, create it with `tvrank profile create {0}`")]
+  UnknownProfile(String),
+  #[error("Profile `{0}` already exists")]
+  ProfileExists(String),
+  #[error("Cannot determine standard locations: {0}")]
+  Paths(#[from] PathsError),
+  #[error("URL parse error: {0}")]
+  Url(#[from] url::ParseError),
+  #[error("IMDB service error: {0}")]
+  Imdb(#[from] ImdbError),
+  #[error("{0}, run with --force-update to rebuild it")]
+  CorruptCache(ImdbError),
+  #[error("Rating history error: {0}")]
+  History(#[from] ImdbHistoryError),
+  #[error("Retired title IDs error: {0}")]
+  Tombstones(#[from] ImdbTombstonesError),
+  #[error("Enrichment error: {0}")]
+  Enrichment(#[from] EnrichmentError),
+  #[error("No API key for any of the requested providers, set {0}")]
+  NoApiKeys(String),
+  #[error("Scores error: {0}")]
+  Scores(#[from] ScoresError),
+  #[error("Trakt error: {0}")]
+  Trakt(#[from] TraktError),
+  #[error("Watchlist error: {0}")]
+  Watchlist(#[from] ImdbWatchlistError),
+  #[error("Watched titles error: {0}")]
+  TraktWatched(#[from] TraktWatchedError),
+  #[error(
+    "Set {TRAKT_CLIENT_ID_VAR} and {TRAKT_CLIENT_SECRET_VAR} to the credentials of a Trakt application"
+  )]
+  NoTraktClient,
+  #[error("No watched titles, run `tvrank sync trakt` first")]
+  NoWatchedTitles,
+  #[error("{0} of {1} rows of the mapping file could not be applied")]
+  MarkBulk(usize, usize),
+  #[error("Found {0} problems with title information files")]
+  VerifyProblems(usize),
+}
@@ -0,0 +1,105 @@
+#![warn(clippy::all)]
+
+use std::io::{self, Write};
+
+use tvrank::imdb::{ImdbQuery, ImdbTitle};
+
+/// Formats the titles of the databases can be exported in.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum ExportFormat {
+  /// One JSON object per line and title
+  Jsonl,
+  /// Tab separated values with a header line, like the IMDB dumps
+  Tsv,
+}
+
+/// Kinds of titles that can be exported.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum ExportKind {
+  /// Only movies
+  Movies,
+  /// Only series
+  Series,
+}
+
+impl ExportKind {
+  /// The databases to export titles from, both of them when no kind is given.
+  pub fn queries(kind: Option<Self>) -> &'static [ImdbQuery] {
+    match kind {
+      None => &[ImdbQuery::Movies, ImdbQuery::Series],
+      Some(ExportKind::Movies) => &[ImdbQuery::Movies],
+      Some(ExportKind::Series) => &[ImdbQuery::Series],
+    }
+  }
+}
+
+/// Header line of TSV exports.
+const TSV_HEADER: &str =
+  "tconst\ttitleType\tprimaryTitle\toriginalTitle\tstartYear\tendYear\truntimeMinutes\tgenres\taverageRating\tnumVotes";
+
+/// Value of TSV columns that are not known, as in the IMDB dumps.
+const TSV_NONE: &str = "\\N";
+
+/// Write titles to `writer` in the given format, returning the number of titles written.
+///
+/// # Arguments
+///
+/// * `titles` - Titles to be written.
+/// * `format` - Format to write the titles in.
+/// * `writer` - Where the titles are written to.
+pub fn write_titles<'a, 'storage: 'a>(
+  titles: impl Iterator<Item = &'a ImdbTitle<'storage>>,
+  format: ExportFormat,
+  mut writer: impl Write,
+) -> io::Result<usize> {
+  let mut count = 0;
+
+  if let ExportFormat::Tsv = format {
+    writeln!(writer, "{TSV_HEADER}")?;
+  }
+
+  for title in titles {
+    match format {
+      ExportFormat::Jsonl => {
+        serde_json::to_writer(&mut writer, title)?;
+        writeln!(writer)?;
+      }
+      ExportFormat::Tsv => write_tsv(title, &mut writer)?,
+    }
+
+    count += 1;
+  }
+
+  writer.flush()?;
+  Ok(count)
+}
+
+/// Write a title as a line of tab separated values.
+fn write_tsv(title: &ImdbTitle, writer: &mut impl Write) -> io::Result<()> {
+  fn or_none(value: Option<impl ToString>) -> String {
+    value.map_or_else(|| TSV_NONE.to_owned(), |value| value.to_string())
+  }
+
+  let genres: Vec<String> = title.genres().iter().map(|genre| genre.to_string()).collect();
+  let genres = if genres.is_empty() {
+    TSV_NONE.to_owned()
+  } else {
+    genres.join(",")
+  };
+  let rating = title.rating();
+
+  writeln!(
+    writer,
+    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+    title.title_id(),
+    title.title_type(),
+    title.primary_title(),
+    title.original_title().unwrap_or(TSV_NONE),
+    or_none(title.start_year()),
+    or_none(title.end_year()),
+    or_none(title.runtime().map(|runtime| runtime.as_secs() / 60)),
+    genres,
+    or_none(rating.map(|rating| format!("{:.1}", f32::from(rating.rating()) / 10.0))),
+    or_none(rating.map(|rating| rating.votes())),
+  )
+}
@@ -0,0 +1,43 @@
+#![warn(clippy::all)]
+
+//! Log output on standard error, either as text lines or as JSON objects for observability
+//! tooling, with the spans (download, import, query, request...) that each event happened in.
+//!
+//! Events of the `tracing` crate, which `tvrank` uses, and records of the `log` crate, which
+//! some dependencies use, are written in the same format.
+
+use std::io;
+
+use tracing::Level;
+
+/// How log output is formatted.
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+pub enum LogFormat {
+  /// Human-readable lines
+  #[default]
+  Text,
+  /// Newline-delimited JSON objects
+  Json,
+}
+
+/// Install a subscriber for both `tracing` events and `log` records.
+///
+/// # Arguments
+///
+/// * `max_level` - The most verbose level written, or `None` to write nothing.
+/// * `format` - How the output is formatted.
+pub fn init(
+  max_level: Option<Level>,
+  format: LogFormat,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+  let Some(max_level) = max_level else {
+    return Ok(());
+  };
+
+  let builder = tracing_subscriber::fmt().with_max_level(max_level).with_writer(io::stderr);
+
+  match format {
+    LogFormat::Text => builder.try_init(),
+    LogFormat::Json => builder.json().with_current_span(false).with_span_list(true).try_init(),
+  }
+}
@@ -0,0 +1,200 @@
+#![warn(clippy::all)]
+
+//! Man pages in roff format for the command-line interface and all of its sub-commands,
+//! generated from the clap definitions.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use clap::{Arg, Command};
+
+/// Write a man page for a command and for each of its sub-commands, recursively, named after
+/// the command and its sub-commands (e.g. `tvrank-db-update.1`).
+///
+/// # Arguments
+///
+/// * `command` - The command-line interface.
+/// * `dir` - Directory to write the man pages to, which is created if it does not exist.
+pub fn write_man_pages(mut command: Command, dir: &Path) -> io::Result<usize> {
+  fs::create_dir_all(dir)?;
+  command.build();
+  write_pages(&mut command, dir)
+}
+
+fn write_pages(command: &mut Command, dir: &Path) -> io::Result<usize> {
+  let page = page_name(command);
+  fs::write(dir.join(format!("{page}.1")), render_page(command))?;
+
+  let mut n_pages = 1;
+  for subcommand in command
+    .get_subcommands_mut()
+    .filter(|c| !c.is_hide_set() && c.get_name() != "help")
+  {
+    n_pages += write_pages(subcommand, dir)?;
+  }
+  Ok(n_pages)
+}
+
+/// The name of the man page of a command, e.g. `tvrank-db-update` for `tvrank db update`.
+fn page_name(command: &Command) -> String {
+  command.get_bin_name().unwrap_or(command.get_name()).replace(' ', "-")
+}
+
+fn render_page(command: &mut Command) -> String {
+  let page = page_name(command);
+  let version = command.get_version().map(String::from);
+  let about = command.get_about().map(|about| about.to_string());
+  let long_about = command.get_long_about().map(|about| about.to_string());
+  let usage = command.render_usage().to_string();
+
+  let mut out = String::new();
+  let _ = writeln!(
+    out,
+    ".TH {} 1 \"\" \"{}\"",
+    escape(&page.to_uppercase()),
+    escape(&format!("{} {}", env!("CARGO_BIN_NAME"), env!("CARGO_PKG_VERSION")))
+  );
+
+  let _ = writeln!(out, ".SH NAME");
+  match &about {
+    Some(about) => {
+      let _ = writeln!(out, "{} \\- {}", escape(&page), escape(about));
+    }
+    None => {
+      let _ = writeln!(out, "{}", escape(&page));
+    }
+  }
+
+  let _ = writeln!(out, ".SH SYNOPSIS");
+  let _ = writeln!(out, "{}", escape(usage.trim_start_matches("Usage:").trim()));
+
+  if let Some(description) = long_about.or(about) {
+    let _ = writeln!(out, ".SH DESCRIPTION");
+    write_text(&mut out, &description);
+  }
+
+  let args: Vec<&Arg> = command.get_arguments().filter(|arg| !arg.is_hide_set()).collect();
+
+  let positionals: Vec<&Arg> = args.iter().copied().filter(|arg| arg.is_positional()).collect();
+  if !positionals.is_empty() {
+    let _ = writeln!(out, ".SH ARGUMENTS");
+    for arg in positionals {
+      write_arg(&mut out, arg);
+    }
+  }
+
+  let options: Vec<&Arg> = args.iter().copied().filter(|arg| !arg.is_positional()).collect();
+  if !options.is_empty() {
+    let _ = writeln!(out, ".SH OPTIONS");
+    for arg in options {
+      write_arg(&mut out, arg);
+    }
+  }
+
+  let subcommands: Vec<&Command> = command
+    .get_subcommands()
+    .filter(|c| !c.is_hide_set() && c.get_name() != "help")
+    .collect();
+  if !subcommands.is_empty() {
+    let _ = writeln!(out, ".SH SUBCOMMANDS");
+    for subcommand in subcommands {
+      let _ = writeln!(out, ".TP");
+      let _ = writeln!(out, "\\fB{}\\fR(1)", escape(&page_name(subcommand)));
+      if let Some(about) = subcommand.get_about() {
+        write_text(&mut out, &about.to_string());
+      }
+    }
+  }
+
+  if let Some(version) = version {
+    let _ = writeln!(out, ".SH VERSION");
+    let _ = writeln!(out, "v{}", escape(&version));
+  }
+
+  out
+}
+
+/// Write the description of an argument: its flags or value name, help, default values and
+/// possible values.
+fn write_arg(out: &mut String, arg: &Arg) {
+  let value_names = arg
+    .get_value_names()
+    .map(|names| names.iter().map(|name| format!("<{name}>")).collect::<Vec<_>>().join(" "))
+    .filter(|_| arg.get_action().takes_values());
+
+  let mut header = Vec::new();
+  if let Some(short) = arg.get_short() {
+    header.push(format!("\\fB\\-{}\\fR", escape(&short.to_string())));
+  }
+  if let Some(long) = arg.get_long() {
+    header.push(format!("\\fB\\-\\-{}\\fR", escape(long)));
+  }
+  let mut header = header.join(", ");
+  match value_names {
+    Some(value_names) if header.is_empty() => header = escape(&value_names),
+    Some(value_names) => header = format!("{header} {}", escape(&value_names)),
+    None if header.is_empty() => header = escape(&format!("<{}>", arg.get_id())),
+    None => {}
+  }
+
+  let _ = writeln!(out, ".TP");
+  let _ = writeln!(out, "{header}");
+
+  if let Some(help) = arg.get_long_help().or(arg.get_help()) {
+    write_text(out, &help.to_string());
+  }
+
+  let defaults: Vec<_> = arg.get_default_values().iter().map(|value| value.to_string_lossy()).collect();
+  if !defaults.is_empty() && arg.get_action().takes_values() {
+    let _ = writeln!(out, ".br");
+    let _ = writeln!(out, "[default: {}]", escape(&defaults.join(", ")));
+  }
+
+  let values: Vec<_> = arg
+    .get_possible_values()
+    .into_iter()
+    .filter(|value| !value.is_hide_set())
+    .collect();
+  if !values.is_empty() && arg.get_action().takes_values() {
+    let _ = writeln!(out, ".br");
+    let _ = writeln!(out, "Possible values:");
+    let _ = writeln!(out, ".RS");
+    for value in values {
+      let _ = writeln!(out, ".IP \\(bu 2");
+      match value.get_help() {
+        Some(help) => {
+          let _ = writeln!(out, "{}: {}", escape(value.get_name()), escape(&help.to_string()));
+        }
+        None => {
+          let _ = writeln!(out, "{}", escape(value.get_name()));
+        }
+      }
+    }
+    let _ = writeln!(out, ".RE");
+  }
+}
+
+/// Write text as paragraphs separated by blank lines.
+fn write_text(out: &mut String, text: &str) {
+  for (i, paragraph) in text.split("\n\n").enumerate() {
+    if i > 0 {
+      let _ = writeln!(out, ".PP");
+    }
+    for line in paragraph.lines() {
+      let _ = writeln!(out, "{}", escape(line));
+    }
+  }
+}
+
+/// Escape text for roff: backslashes and dashes, and control characters at the start of a
+/// line.
+fn escape(text: &str) -> String {
+  let text = text.replace('\\', "\\e").replace('-', "\\-");
+  if text.starts_with('.') || text.starts_with('\'') {
+    format!("\\&{text}")
+  } else {
+    text
+  }
+}
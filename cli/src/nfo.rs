@@ -0,0 +1,50 @@
+#![warn(clippy::all)]
+
+use std::path::Path;
+
+use tvrank::imdb::ImdbTitle;
+use tvrank::nfo::{self, Written};
+
+use tracing::warn;
+
+/// Writes the NFO files of matched directories during a scan and keeps count of them.
+#[derive(Debug, Default)]
+pub struct NfoWriter {
+  overwrite: bool,
+  created: usize,
+  overwritten: usize,
+  skipped: usize,
+  failed: usize,
+}
+
+impl NfoWriter {
+  pub fn new(overwrite: bool) -> Self {
+    Self { overwrite, ..Self::default() }
+  }
+
+  /// Write the NFO file of `title` into `dir`. Failures are logged as warnings so that one
+  /// read-only directory does not abort the whole scan.
+  pub fn add(&mut self, dir: &Path, title: &ImdbTitle) {
+    match nfo::write(dir, title, self.overwrite) {
+      Ok(Written::Created) => self.created += 1,
+      Ok(Written::Overwritten) => self.overwritten += 1,
+      Ok(Written::Skipped) => self.skipped += 1,
+      Err(e) => {
+        warn!("{e}");
+        self.failed += 1;
+      }
+    }
+  }
+
+  /// Print how many NFO files were written and skipped.
+  pub fn print_summary(&self) {
+    eprintln!(
+      "NFO files: {} created, {} overwritten, {} skipped (already exist), {} failed",
+      self.created, self.overwritten, self.skipped, self.failed
+    );
+
+    if self.skipped > 0 && !self.overwrite {
+      eprintln!("Use --overwrite-nfo to replace existing NFO files");
+    }
+  }
+}
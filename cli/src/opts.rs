@@ -0,0 +1,572 @@
+#![warn(clippy::all)]
+
+//! Options shared by sub-commands, as they are parsed from the command line.
+
+use std::env;
+use std::path::PathBuf;
+
+use crate::logging::LogFormat;
+use crate::print::OutputFormat;
+use crate::sources::Source;
+use crate::ui::ProgressFormat;
+
+use tvrank::imdb::{
+  ImdbCacheCompression, ImdbColumn, ImdbColumnValue, ImdbColumns, ImdbFilter, ImdbGenre, ImdbPopularity,
+  ImdbSeriesStatus, ImdbWeightedRating,
+};
+use tvrank::utils::search::{KeywordMatchKind, Language, SearchOptions, SearchScope, StopWords};
+
+#[derive(Debug, clap::Args)]
+pub struct GeneralOpts {
+  /// Force updating internal databases
+  #[clap(short, long)]
+  pub force_update: bool,
+
+  /// Display colors even when the NO_COLOR environment variable is set
+  #[clap(short, long)]
+  pub color: bool,
+
+  /// Verbose output (can be specified multiple times)
+  #[clap(short, long, action = clap::ArgAction::Count)]
+  pub verbose: u8,
+
+  /// Set how verbose output is formatted [default: text]
+  #[clap(long, value_enum)]
+  pub log_format: Option<LogFormat>,
+
+  /// Do not print results, only report whether there were matches through the exit code
+  #[clap(short, long)]
+  pub quiet: bool,
+
+  /// Set how download progress is reported [default: bar]
+  #[clap(long, value_enum)]
+  pub progress: Option<ProgressFormat>,
+
+  /// Set how internal databases are compressed when they are built [default: none]
+  #[clap(long, value_enum)]
+  pub cache_compression: Option<CacheCompression>,
+
+  /// Keep an index of titles by name alongside the internal databases to start faster
+  #[clap(long)]
+  pub name_index: bool,
+
+  /// Answer from outdated internal databases right away and update them in the background
+  #[clap(long)]
+  pub background_update: bool,
+
+  /// Fail instead of waiting when another tvrank process is updating the internal databases
+  #[clap(long)]
+  pub fail_if_updating: bool,
+
+  /// Keep personal state, like the rating history, in the given profile
+  #[clap(long, value_name = "NAME")]
+  pub profile: Option<String>,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct SearchOpts {
+  /// Sort by year/rating/title instead of rating/year/title
+  #[clap(short = 'y', long)]
+  pub sort_by_year: bool,
+
+  /// Only display the top N results
+  #[clap(short, long, name = "N")]
+  pub top: Option<usize>,
+
+  /// Set output format
+  #[clap(short, long, value_enum, default_value = "table")]
+  pub output: OutputFormat,
+
+  /// Set how keywords are matched against titles
+  #[clap(long, value_enum, default_value = "overlapping")]
+  pub match_kind: MatchKind,
+
+  /// Set which names of titles are searched
+  #[clap(long, value_enum, default_value = "all")]
+  pub scope: Scope,
+
+  /// Set the languages whose leading articles are ignored when exact titles do not match
+  /// (e.g. "Matrix" matches "The Matrix"), defaults to all of them
+  #[clap(long, value_name = "LANGUAGES", value_enum, value_delimiter = ',')]
+  pub articles: Vec<Articles>,
+
+  /// Also match the singular, plural, "-ing" and "-ed" forms of English keywords (e.g.
+  /// "alien" also finds "Aliens" with `--match-kind word-boundary`, "aliens" finds "Alien")
+  #[clap(long)]
+  pub stem: bool,
+
+  /// Only include titles released in or after YEAR
+  #[clap(long, value_name = "YEAR", conflicts_with = "last_n_years")]
+  pub released_since: Option<u16>,
+
+  /// Only include titles released in the last N years, including the current one
+  #[clap(long, value_name = "YEARS")]
+  pub last_n_years: Option<u16>,
+
+  /// Only include titles released in the given decade, e.g. 1990 or 1990s
+  #[clap(
+    long,
+    value_name = "DECADE",
+    value_parser = parse_decade,
+    conflicts_with_all = ["released_since", "last_n_years", "era"]
+  )]
+  pub decade: Option<u16>,
+
+  /// Only include titles released between two years (inclusive), e.g. 1960-1975, or before or
+  /// after a year with -1975 or 1960-
+  #[clap(
+    long,
+    value_name = "START-END",
+    value_parser = parse_era,
+    allow_hyphen_values = true,
+    conflicts_with_all = ["released_since", "last_n_years"]
+  )]
+  pub era: Option<Era>,
+
+  /// Only include titles of the given genre (can be specified multiple times)
+  #[clap(short, long, value_parser = parse_genre)]
+  pub genre: Vec<ImdbGenre>,
+
+  /// Only include series that are still running or that have ended
+  #[clap(long, value_enum)]
+  pub status: Option<Status>,
+
+  /// Only include titles with at least N votes
+  #[clap(long, value_name = "N", conflicts_with = "popularity")]
+  pub min_votes: Option<u32>,
+
+  /// Only include titles with at most N votes, e.g. to find hidden gems
+  #[clap(long, value_name = "N", conflicts_with = "popularity")]
+  pub max_votes: Option<u32>,
+
+  /// Only include titles whose number of votes is in the given band
+  #[clap(long, value_enum)]
+  pub popularity: Option<Popularity>,
+
+  /// Stop after N matches and ask to refine the query (0 for no limit)
+  #[clap(long, value_name = "N", default_value_t = DEFAULT_MAX_RESULTS)]
+  pub max_results: usize,
+
+  /// Display a derived column: age, votes-per-year (can be specified multiple times)
+  #[clap(long, value_name = "COLUMN", value_parser = parse_column)]
+  pub column: Vec<ImdbColumn>,
+
+  /// Sort by a derived column, highest values first, before the other sort keys
+  #[clap(long, value_name = "COLUMN", value_parser = parse_column)]
+  pub sort_by_column: Option<ImdbColumn>,
+
+  /// Sort keyword searches by relevance, exact title matches first, before the other sort
+  /// keys
+  #[clap(long, conflicts_with = "sort_by_column")]
+  pub sort_by_relevance: bool,
+
+  /// Rank titles by their average rating, or by an IMDB-style weighted rating that pulls
+  /// the ratings of titles with few votes towards a prior mean
+  #[clap(long, value_enum, default_value_t = Rank::Average)]
+  pub rank: Rank,
+
+  /// Prior mean rating (out of 100) of `--rank bayesian`, defaults to the average rating of
+  /// all titles
+  #[clap(long, value_name = "RATING", value_parser = parse_prior_mean)]
+  pub prior_mean: Option<f64>,
+
+  /// Number of votes at which the rating of a title and the prior mean weigh the same with
+  /// `--rank bayesian`
+  #[clap(long, value_name = "N", default_value_t = ImdbWeightedRating::DEFAULT_MIN_VOTES)]
+  pub prior_votes: u32,
+
+  /// Data sources to display results from, separated by commas (TMDB and OMDb add plots
+  /// and posters, and read their API keys from TVRANK_TMDB_API_KEY and TVRANK_OMDB_API_KEY)
+  #[clap(long, value_name = "SOURCES", value_enum, value_delimiter = ',', default_value = "imdb")]
+  pub source: Vec<Source>,
+
+  /// Only fetch data from sources other than IMDB for the top N results, the others only
+  /// display data that is already cached
+  #[clap(long, value_name = "N", default_value_t = DEFAULT_ENRICH_TOP)]
+  pub enrich_top: usize,
+
+  /// Display how the rating and votes of titles compare to the medians of their genre and
+  /// decade
+  #[clap(long)]
+  pub baseline: bool,
+
+  /// Display Rotten Tomatoes and Metacritic scores from a CSV or JSON file with an
+  /// "imdb_id" and "rotten_tomatoes" and/or "metacritic" scores for each title
+  #[clap(long, value_name = "FILE")]
+  pub scores: Option<PathBuf>,
+
+  /// Display when titles were watched and how they were rated, as synced from Trakt
+  #[clap(long)]
+  pub watched: bool,
+
+  /// Only include titles that were not watched or rated, as synced from Trakt
+  #[clap(long)]
+  pub unwatched: bool,
+}
+
+/// Default number of matches after which a query stops and is reported as overflowed.
+pub const DEFAULT_MAX_RESULTS: usize = 50_000;
+
+/// Default number of results data is fetched for from sources other than IMDB.
+pub const DEFAULT_ENRICH_TOP: usize = 10;
+
+impl SearchOpts {
+  /// How titles are searched: the keyword match kind, the names searched, and the leading
+  /// articles ignored when exact titles do not match, of all languages unless some were
+  /// requested.
+  pub fn search_options(&self) -> SearchOptions {
+    let stop_words = if self.articles.is_empty() {
+      StopWords::default()
+    } else {
+      let languages: Vec<Language> = self.articles.iter().copied().map(Language::from).collect();
+      StopWords::new(&languages)
+    };
+
+    SearchOptions::new()
+      .match_kind(self.match_kind.into())
+      .scope(self.scope.into())
+      .stop_words(stop_words)
+  }
+
+  pub fn filter(&self) -> ImdbFilter {
+    let mut filter = ImdbFilter::new();
+
+    if let Some(year) = self.released_since {
+      filter = filter.released_since(year);
+    }
+
+    if let Some(years) = self.last_n_years {
+      filter = filter.last_n_years(years);
+    }
+
+    if let Some(decade) = self.decade {
+      filter = filter.decade(decade);
+    }
+
+    if let Some(era) = self.era {
+      if let Some(start) = era.start {
+        filter = filter.released_since(start);
+      }
+      if let Some(end) = era.end {
+        filter = filter.released_until(end);
+      }
+    }
+
+    for &genre in &self.genre {
+      filter = filter.genre(genre);
+    }
+
+    if let Some(status) = self.status {
+      filter = filter.status(status.into());
+    }
+
+    if let Some(votes) = self.min_votes {
+      filter = filter.min_votes(votes);
+    }
+
+    if let Some(votes) = self.max_votes {
+      filter = filter.max_votes(votes);
+    }
+
+    if let Some(popularity) = self.popularity {
+      filter = filter.popularity(popularity.into());
+    }
+
+    filter
+  }
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct ScanOpts {
+  /// Display the disk usage of each title and a summary per genre, counting hard links once
+  #[clap(long)]
+  pub sizes: bool,
+
+  /// Sort by disk usage, largest first, before the other sort keys (implies --sizes)
+  #[clap(long)]
+  pub sort_by_size: bool,
+
+  /// Sort series by the average rating of their episodes, best first, before the other sort
+  /// keys (downloads the episodes database if needed)
+  #[clap(long, conflicts_with = "sort_by_size")]
+  pub sort_by_episodes: bool,
+
+  /// Write `movie.nfo` or `tvshow.nfo` metadata files for Kodi and Jellyfin into every
+  /// directory that matches exactly one title, keeping existing ones
+  #[clap(long)]
+  pub write_nfo: bool,
+
+  /// Replace existing NFO files when writing them
+  #[clap(long, requires = "write_nfo")]
+  pub overwrite_nfo: bool,
+
+  /// Write a JSON report of the unmatched and ambiguous entries, with the IDs of their
+  /// candidate titles, and of the entries skipped because of their names to FILE
+  #[clap(long, value_name = "FILE")]
+  pub report: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Clone, clap::Args)]
+pub struct RenameOpts {
+  /// Suggest renaming matched directories into the canonical "TITLE (YYYY)" form instead of
+  /// listing the titles
+  #[clap(long)]
+  pub suggest_renames: bool,
+
+  /// Execute the suggested renames, skipping the ones that collide with other directories
+  #[clap(long, requires = "suggest_renames")]
+  pub apply: bool,
+}
+
+pub fn parse_genre(name: &str) -> Result<ImdbGenre, String> {
+  ImdbGenre::from_name(name).ok_or_else(|| match ImdbGenre::closest(name) {
+    Some(genre) => format!("unknown genre `{name}`, did you mean `{genre}`?"),
+    None => format!("unknown genre `{name}`, see `tvrank genres` for the known genres"),
+  })
+}
+
+/// Parse a decade as its first year followed by an optional "s", e.g. "1990" or "1990s".
+pub fn parse_decade(decade: &str) -> Result<u16, String> {
+  let year: u16 = decade
+    .strip_suffix('s')
+    .unwrap_or(decade)
+    .parse()
+    .map_err(|_| format!("invalid decade `{decade}`, expected e.g. 1990 or 1990s"))?;
+
+  if !year.is_multiple_of(10) {
+    return Err(format!("`{year}` is not the first year of a decade, did you mean {}?", year - year % 10));
+  }
+
+  Ok(year)
+}
+
+/// Parse a prior mean rating out of 100.
+pub fn parse_prior_mean(mean: &str) -> Result<f64, String> {
+  let mean: f64 = mean.parse().map_err(|_| format!("invalid rating `{mean}`, expected e.g. 70"))?;
+
+  if !(0.0..=100.0).contains(&mean) {
+    return Err(format!("the rating `{mean}` is not between 0 and 100"));
+  }
+
+  Ok(mean)
+}
+
+/// A range of release years, either of which can be open.
+#[derive(Debug, Clone, Copy)]
+pub struct Era {
+  pub start: Option<u16>,
+  pub end: Option<u16>,
+}
+
+/// Parse a range of years as "START-END", where either year can be left out for an open
+/// range (e.g. "-1975"), and ".." or an en dash can separate them instead.
+pub fn parse_era(era: &str) -> Result<Era, String> {
+  let invalid = || format!("invalid era `{era}`, expected e.g. 1960-1975, 1960- or -1975");
+
+  let (start, end) = ["..", "-", "–"]
+    .into_iter()
+    .find_map(|separator| era.split_once(separator))
+    .ok_or_else(invalid)?;
+
+  let year = |year: &str| match year.trim() {
+    "" => Ok(None),
+    year => year.parse::<u16>().map(Some).map_err(|_| invalid()),
+  };
+
+  let (start, end) = (year(start)?, year(end)?);
+  match (start, end) {
+    (None, None) => Err(invalid()),
+    (Some(start), Some(end)) if start > end => Err(format!("the era `{era}` ends before it starts")),
+    _ => Ok(Era { start, end }),
+  }
+}
+
+/// Columns computed from titles that can be displayed and sorted by.
+pub fn derived_columns() -> ImdbColumns {
+  let mut columns = ImdbColumns::new();
+  let age = ImdbColumn::age();
+  columns.register(age.clone());
+  columns.register(ImdbColumn::new("Votes-Per-Year", move |title| {
+    let votes = title.rating()?.votes();
+
+    match age.value(title)? {
+      ImdbColumnValue::Integer(age) => Some(ImdbColumnValue::Float(f64::from(votes) / (age + 1) as f64)),
+      _ => None,
+    }
+  }));
+  columns
+}
+
+pub fn parse_column(name: &str) -> Result<ImdbColumn, String> {
+  derived_columns()
+    .get(name)
+    .cloned()
+    .ok_or_else(|| format!("unknown column `{name}`"))
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum CacheCompression {
+  /// Uncompressed, fastest to load
+  None,
+  /// Zstandard-compressed, several times smaller on disk
+  Zstd,
+}
+
+impl From<CacheCompression> for ImdbCacheCompression {
+  fn from(compression: CacheCompression) -> Self {
+    match compression {
+      CacheCompression::None => ImdbCacheCompression::None,
+      CacheCompression::Zstd => ImdbCacheCompression::Zstd,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum MatchKind {
+  /// Keywords may overlap each other (e.g. "man" and "mandala" both match "Mandala")
+  Overlapping,
+  /// Keywords may not overlap, the first given keyword wins
+  LeftmostFirst,
+  /// Keywords may not overlap, the longest keyword wins
+  LeftmostLongest,
+  /// Keywords only match whole words (e.g. "up" matches "Up" but not "Supernova")
+  WordBoundary,
+}
+
+impl From<MatchKind> for KeywordMatchKind {
+  fn from(kind: MatchKind) -> Self {
+    match kind {
+      MatchKind::Overlapping => KeywordMatchKind::Overlapping,
+      MatchKind::LeftmostFirst => KeywordMatchKind::LeftmostFirst,
+      MatchKind::LeftmostLongest => KeywordMatchKind::LeftmostLongest,
+      MatchKind::WordBoundary => KeywordMatchKind::WordBoundary,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Status {
+  /// Series that are still running
+  Running,
+  /// Series that have ended
+  Ended,
+}
+
+impl From<Status> for ImdbSeriesStatus {
+  fn from(status: Status) -> Self {
+    match status {
+      Status::Running => ImdbSeriesStatus::Running,
+      Status::Ended => ImdbSeriesStatus::Ended,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Rank {
+  /// Average rating of titles
+  Average,
+  /// Weighted rating of titles, as for the IMDB Top 250
+  Bayesian,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Popularity {
+  /// Fewer than 1,000 votes
+  Obscure,
+  /// 1,000 to 9,999 votes
+  Niche,
+  /// 10,000 to 99,999 votes
+  Popular,
+  /// 100,000 votes or more
+  Blockbuster,
+}
+
+impl From<Popularity> for ImdbPopularity {
+  fn from(popularity: Popularity) -> Self {
+    match popularity {
+      Popularity::Obscure => ImdbPopularity::Obscure,
+      Popularity::Niche => ImdbPopularity::Niche,
+      Popularity::Popular => ImdbPopularity::Popular,
+      Popularity::Blockbuster => ImdbPopularity::Blockbuster,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Scope {
+  /// Search both primary and original names of titles
+  All,
+  /// Only search primary names, usually the English ones (e.g. "Leaving the Factory")
+  Primary,
+  /// Only search original names, in their native language (e.g. "La sortie de l'usine")
+  Original,
+}
+
+impl From<Scope> for SearchScope {
+  fn from(scope: Scope) -> Self {
+    match scope {
+      Scope::All => SearchScope::All,
+      Scope::Primary => SearchScope::PrimaryTitles,
+      Scope::Original => SearchScope::OriginalTitles,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Articles {
+  /// "the", "a", "an"
+  English,
+  /// "le", "la", "les", "l'", "un", "une"
+  French,
+  /// "der", "die", "das", "ein", "eine"
+  German,
+  /// "el", "la", "los", "las", "un", "una"
+  Spanish,
+  /// "il", "lo", "la", "i", "gli", "le", "l'", "un", "uno", "una"
+  Italian,
+  /// "o", "a", "os", "as", "um", "uma"
+  Portuguese,
+  /// "de", "het", "een"
+  Dutch,
+}
+
+impl From<Articles> for Language {
+  fn from(articles: Articles) -> Self {
+    match articles {
+      Articles::English => Language::English,
+      Articles::French => Language::French,
+      Articles::German => Language::German,
+      Articles::Spanish => Language::Spanish,
+      Articles::Italian => Language::Italian,
+      Articles::Portuguese => Language::Portuguese,
+      Articles::Dutch => Language::Dutch,
+    }
+  }
+}
+
+fn is_no_color_env_set() -> bool {
+  match env::var("NO_COLOR") {
+    Ok(val) => val != "0",
+    Err(_) => false,
+  }
+}
+
+pub fn merge_general_opts(locals: GeneralOpts, globals: GeneralOpts) -> GeneralOpts {
+  GeneralOpts {
+    force_update: locals.force_update || globals.force_update,
+    color: !is_no_color_env_set() || locals.color || globals.color,
+    verbose: if locals.verbose > 0 {
+      locals.verbose
+    } else {
+      globals.verbose
+    },
+    log_format: locals.log_format.or(globals.log_format),
+    quiet: locals.quiet || globals.quiet,
+    progress: locals.progress.or(globals.progress),
+    cache_compression: locals.cache_compression.or(globals.cache_compression),
+    name_index: locals.name_index || globals.name_index,
+    background_update: locals.background_update || globals.background_update,
+    fail_if_updating: locals.fail_if_updating || globals.fail_if_updating,
+    profile: locals.profile.or(globals.profile),
+  }
+}
@@ -1,8 +1,16 @@
 #![warn(clippy::all)]
 
-use crate::search::SearchRes;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
 
-use tvrank::imdb::{ImdbQuery, ImdbTitle};
+use crate::search::{SearchGroup, SearchRes};
+use crate::usage::UsageSummary;
+
+use tvrank::imdb::{
+  ImdbColumnValue, ImdbColumnValues, ImdbEpisode, ImdbQuery, ImdbSeason, ImdbSeriesEpisodes, ImdbStats,
+  ImdbSuggestion, ImdbTitle, ImdbTrending,
+};
 
 use humantime::format_duration;
 use prettytable::{color, format, Attr, Cell, Row, Table};
@@ -28,21 +36,160 @@ pub enum OutputFormat {
   Yaml,
 }
 
+/// A title along with the values of the derived columns displayed with it.
+#[derive(Serialize)]
+struct TitleOutput<'a, 'storage, 'columns> {
+  #[serde(flatten)]
+  title: &'a ImdbTitle<'storage>,
+  #[serde(skip_serializing_if = "ImdbColumnValues::is_empty")]
+  columns: ImdbColumnValues<'columns>,
+}
+
+/// Sort the results and attach the values of their derived columns for serialization.
+fn title_outputs<'search_res, 'a, 'storage>(
+  results: &'search_res mut SearchRes<'a, 'storage>,
+) -> Vec<TitleOutput<'a, 'storage, 'search_res>> {
+  let titles = results.top_sorted_results().to_vec();
+  let columns = results.columns();
+  titles
+    .into_iter()
+    .map(|title| TitleOutput { title, columns: columns.values(title) })
+    .collect()
+}
+
 #[derive(Serialize)]
 struct OutputWrapper<'search_res, 'a, 'storage> {
-  movies: Option<&'search_res [&'a ImdbTitle<'storage>]>,
-  series: Option<&'search_res [&'a ImdbTitle<'storage>]>,
+  movies: Option<Vec<TitleOutput<'a, 'storage, 'search_res>>>,
+  series: Option<Vec<TitleOutput<'a, 'storage, 'search_res>>>,
+  /// Whether there were more matches than the results cap.
+  truncated: bool,
+  /// Whether the titles only matched once leading articles were ignored.
+  #[serde(skip_serializing_if = "std::ops::Not::not")]
+  normalized: bool,
 }
 
 impl<'search_res, 'a, 'storage> OutputWrapper<'search_res, 'a, 'storage> {
   fn new(
-    movies: Option<&'search_res [&'a ImdbTitle<'storage>]>,
-    series: Option<&'search_res [&'a ImdbTitle<'storage>]>,
+    movies: Option<Vec<TitleOutput<'a, 'storage, 'search_res>>>,
+    series: Option<Vec<TitleOutput<'a, 'storage, 'search_res>>>,
+    truncated: bool,
+    normalized: bool,
   ) -> Self {
-    Self { movies, series }
+    Self { movies, series, truncated, normalized }
+  }
+}
+
+#[derive(Serialize)]
+struct GroupOutputWrapper<'search_res, 'a, 'storage> {
+  index: usize,
+  query: &'search_res str,
+  movies: Option<Vec<TitleOutput<'a, 'storage, 'search_res>>>,
+  series: Option<Vec<TitleOutput<'a, 'storage, 'search_res>>>,
+  truncated: bool,
+}
+
+/// Sort groups by their position in the input and prepare them for serialization.
+fn group_outputs<'search_res, 'a, 'storage>(
+  groups: &'search_res mut [SearchGroup<'a, 'storage>],
+) -> Vec<GroupOutputWrapper<'search_res, 'a, 'storage>> {
+  groups.sort_by_key(|group| group.index);
+  groups
+    .iter_mut()
+    .map(|group| GroupOutputWrapper {
+      truncated: group.is_overflowed(),
+      index: group.index,
+      query: &group.search_terms,
+      movies: group.movies.as_mut().map(title_outputs),
+      series: group.series.as_mut().map(title_outputs),
+    })
+    .collect()
+}
+
+#[derive(Serialize)]
+struct TrendingOutputWrapper<'trending, 'a> {
+  movies: &'trending [ImdbTrending<'a>],
+  series: &'trending [ImdbTrending<'a>],
+}
+
+/// Remote suggestions, with empty local results for consistency with the other outputs.
+#[derive(Serialize)]
+struct RemoteOutputWrapper<'remote> {
+  movies: [(); 0],
+  series: [(); 0],
+  truncated: bool,
+  remote: &'remote [ImdbSuggestion],
+}
+
+impl<'remote> RemoteOutputWrapper<'remote> {
+  fn new(remote: &'remote [ImdbSuggestion]) -> Self {
+    Self { movies: [], series: [], truncated: false, remote }
+  }
+}
+
+/// A title with all its known fields, including the ones that are not part of results.
+#[derive(Serialize)]
+struct InfoOutput<'a, 'storage> {
+  #[serde(flatten)]
+  title: &'a ImdbTitle<'storage>,
+  is_adult: bool,
+  imdb_link: String,
+}
+
+impl<'a, 'storage> InfoOutput<'a, 'storage> {
+  fn new(title: &'a ImdbTitle<'storage>, imdb_url: &Url) -> Result<Self, Error> {
+    let imdb_link = imdb_url.join(&title.title_id().to_string())?.to_string();
+    Ok(Self { title, is_adult: title.is_adult(), imdb_link })
   }
 }
 
+/// A season along with the average rating of its episodes.
+#[derive(Serialize)]
+struct SeasonOutput<'episodes> {
+  #[serde(flatten)]
+  season: &'episodes ImdbSeason,
+  average: Option<f64>,
+}
+
+/// A series with its episodes, grouped by season.
+#[derive(Serialize)]
+struct EpisodesOutputWrapper<'episodes, 'a, 'storage> {
+  series: &'a ImdbTitle<'storage>,
+  seasons: Vec<SeasonOutput<'episodes>>,
+  average: Option<f64>,
+  best: Option<&'episodes ImdbEpisode>,
+  worst: Option<&'episodes ImdbEpisode>,
+}
+
+impl<'episodes, 'a, 'storage> EpisodesOutputWrapper<'episodes, 'a, 'storage> {
+  fn new(series: &'a ImdbTitle<'storage>, episodes: &'episodes ImdbSeriesEpisodes) -> Self {
+    let seasons = episodes
+      .seasons()
+      .iter()
+      .map(|season| SeasonOutput { season, average: season.average() })
+      .collect();
+
+    Self { series, seasons, average: episodes.average(), best: episodes.best(), worst: episodes.worst() }
+  }
+}
+
+fn total_matches(movies: &Option<SearchRes>, series: &Option<SearchRes>) -> usize {
+  let movies = movies.as_ref().map_or(0, |movies| movies.total_len());
+  let series = series.as_ref().map_or(0, |series| series.total_len());
+  movies + series
+}
+
+fn is_overflowed(movies: &Option<SearchRes>, series: &Option<SearchRes>) -> bool {
+  let movies = movies.as_ref().is_some_and(|movies| movies.is_overflowed());
+  let series = series.as_ref().is_some_and(|series| series.is_overflowed());
+  movies || series
+}
+
+fn is_normalized(movies: &Option<SearchRes>, series: &Option<SearchRes>) -> bool {
+  let movies = movies.as_ref().is_some_and(|movies| movies.is_normalized());
+  let series = series.as_ref().is_some_and(|series| series.is_normalized());
+  movies || series
+}
+
 pub trait Printer {
   type Error;
 
@@ -54,15 +201,79 @@ pub trait Printer {
     series: Option<SearchRes>,
     imdb_url: &Url,
     search_terms: Option<&str>,
-  ) -> Result<(), Self::Error>;
+  ) -> Result<usize, Self::Error>;
+
+  /// Print the results of several queries in input order, regardless of the order in which
+  /// the groups are given.
+  fn print_groups(&self, mut groups: Vec<SearchGroup>, imdb_url: &Url) -> Result<usize, Self::Error> {
+    groups.sort_by_key(|group| group.index);
+
+    let mut matches = 0;
+    for group in groups {
+      matches += self.print(group.movies, group.series, imdb_url, Some(&group.search_terms))?;
+    }
+
+    Ok(matches)
+  }
+
+  fn print_trending(
+    &self,
+    movies: &[ImdbTrending],
+    series: &[ImdbTrending],
+    imdb_url: &Url,
+    window: &str,
+  ) -> Result<usize, Self::Error>;
+
+  /// Print unranked suggestions fetched from IMDB when nothing matched locally.
+  fn print_remote(
+    &self,
+    remote: &[ImdbSuggestion],
+    imdb_url: &Url,
+    search_terms: Option<&str>,
+  ) -> Result<usize, Self::Error>;
+
+  /// Print the episodes of a series season by season, returns the number of episodes.
+  fn print_episodes(
+    &self,
+    series: &ImdbTitle,
+    episodes: &ImdbSeriesEpisodes,
+    imdb_url: &Url,
+  ) -> Result<usize, Self::Error>;
+
+  /// Print the statistics of the titles of a library, returns the number of titles.
+  fn print_stats(&self, stats: &ImdbStats, imdb_url: &Url) -> Result<usize, Self::Error>;
+
+  /// Print all the known fields of a single title.
+  fn print_info(&self, title: &ImdbTitle, imdb_url: &Url) -> Result<usize, Self::Error>;
+
+  /// Print the disk usage per genre of scanned titles. The disk usage of each title is
+  /// already part of the results, so only tables display the summary.
+  fn print_storage_usage(&self, _usage: &UsageSummary) -> Result<(), Self::Error> {
+    Ok(())
+  }
 }
 
-pub struct JsonPrinter;
+pub struct JsonPrinter {
+  buffer: Option<Rc<RefCell<String>>>,
+}
 
 impl JsonPrinter {
   #[must_use]
   pub fn new() -> Self {
-    Self
+    Self { buffer: None }
+  }
+
+  /// Create a printer that appends its output to `buffer` instead of standard output.
+  #[must_use]
+  pub fn with_buffer(buffer: Rc<RefCell<String>>) -> Self {
+    Self { buffer: Some(buffer) }
+  }
+
+  fn output(&self, json: String) {
+    match &self.buffer {
+      Some(buffer) => buffer.borrow_mut().push_str(&json),
+      None => println!("{json}"),
+    }
   }
 }
 
@@ -79,11 +290,74 @@ impl Printer for JsonPrinter {
     mut series: Option<SearchRes>,
     _imdb_url: &Url,
     _search_terms: Option<&str>,
-  ) -> Result<(), Self::Error> {
-    let movie_results = movies.as_mut().map(|movies| movies.top_sorted_results());
-    let series_results = series.as_mut().map(|series| series.top_sorted_results());
-    println!("{}", serde_json::to_string_pretty(&OutputWrapper::new(movie_results, series_results))?);
-    Ok(())
+  ) -> Result<usize, Self::Error> {
+    let matches = total_matches(&movies, &series);
+    let truncated = is_overflowed(&movies, &series);
+    let normalized = is_normalized(&movies, &series);
+    let movie_results = movies.as_mut().map(title_outputs);
+    let series_results = series.as_mut().map(title_outputs);
+    self.output(serde_json::to_string_pretty(&OutputWrapper::new(
+      movie_results,
+      series_results,
+      truncated,
+      normalized,
+    ))?);
+    Ok(matches)
+  }
+
+  /// Print one compact JSON object per group and line (NDJSON), so that the results of
+  /// long lists can be processed line by line.
+  fn print_groups(&self, mut groups: Vec<SearchGroup>, _imdb_url: &Url) -> Result<usize, Self::Error> {
+    let matches = groups.iter().map(SearchGroup::total_len).sum();
+    let lines = group_outputs(&mut groups)
+      .iter()
+      .map(serde_json::to_string)
+      .collect::<Result<Vec<_>, _>>()?;
+    if !lines.is_empty() {
+      self.output(lines.join("\n"));
+    }
+    Ok(matches)
+  }
+
+  fn print_trending(
+    &self,
+    movies: &[ImdbTrending],
+    series: &[ImdbTrending],
+    _imdb_url: &Url,
+    _window: &str,
+  ) -> Result<usize, Self::Error> {
+    self.output(serde_json::to_string_pretty(&TrendingOutputWrapper { movies, series })?);
+    Ok(movies.len() + series.len())
+  }
+
+  fn print_remote(
+    &self,
+    remote: &[ImdbSuggestion],
+    _imdb_url: &Url,
+    _search_terms: Option<&str>,
+  ) -> Result<usize, Self::Error> {
+    self.output(serde_json::to_string_pretty(&RemoteOutputWrapper::new(remote))?);
+    Ok(remote.len())
+  }
+
+  fn print_episodes(
+    &self,
+    series: &ImdbTitle,
+    episodes: &ImdbSeriesEpisodes,
+    _imdb_url: &Url,
+  ) -> Result<usize, Self::Error> {
+    self.output(serde_json::to_string_pretty(&EpisodesOutputWrapper::new(series, episodes))?);
+    Ok(episodes.len())
+  }
+
+  fn print_stats(&self, stats: &ImdbStats, _imdb_url: &Url) -> Result<usize, Self::Error> {
+    self.output(serde_json::to_string_pretty(stats)?);
+    Ok(stats.titles())
+  }
+
+  fn print_info(&self, title: &ImdbTitle, imdb_url: &Url) -> Result<usize, Self::Error> {
+    self.output(serde_json::to_string_pretty(&InfoOutput::new(title, imdb_url)?)?);
+    Ok(1)
   }
 }
 
@@ -109,11 +383,129 @@ impl Printer for YamlPrinter {
     mut series: Option<SearchRes>,
     _imdb_url: &Url,
     _search_terms: Option<&str>,
-  ) -> Result<(), Self::Error> {
-    let movie_results = movies.as_mut().map(|movies| movies.top_sorted_results());
-    let series_results = series.as_mut().map(|series| series.top_sorted_results());
-    println!("{}", serde_yaml::to_string(&OutputWrapper::new(movie_results, series_results,))?);
-    Ok(())
+  ) -> Result<usize, Self::Error> {
+    let matches = total_matches(&movies, &series);
+    let truncated = is_overflowed(&movies, &series);
+    let normalized = is_normalized(&movies, &series);
+    let movie_results = movies.as_mut().map(title_outputs);
+    let series_results = series.as_mut().map(title_outputs);
+    println!(
+      "{}",
+      serde_yaml::to_string(&OutputWrapper::new(movie_results, series_results, truncated, normalized))?
+    );
+    Ok(matches)
+  }
+
+  fn print_groups(&self, mut groups: Vec<SearchGroup>, _imdb_url: &Url) -> Result<usize, Self::Error> {
+    let matches = groups.iter().map(SearchGroup::total_len).sum();
+    println!("{}", serde_yaml::to_string(&group_outputs(&mut groups))?);
+    Ok(matches)
+  }
+
+  fn print_trending(
+    &self,
+    movies: &[ImdbTrending],
+    series: &[ImdbTrending],
+    _imdb_url: &Url,
+    _window: &str,
+  ) -> Result<usize, Self::Error> {
+    println!("{}", serde_yaml::to_string(&TrendingOutputWrapper { movies, series })?);
+    Ok(movies.len() + series.len())
+  }
+
+  fn print_remote(
+    &self,
+    remote: &[ImdbSuggestion],
+    _imdb_url: &Url,
+    _search_terms: Option<&str>,
+  ) -> Result<usize, Self::Error> {
+    println!("{}", serde_yaml::to_string(&RemoteOutputWrapper::new(remote))?);
+    Ok(remote.len())
+  }
+
+  fn print_episodes(
+    &self,
+    series: &ImdbTitle,
+    episodes: &ImdbSeriesEpisodes,
+    _imdb_url: &Url,
+  ) -> Result<usize, Self::Error> {
+    println!("{}", serde_yaml::to_string(&EpisodesOutputWrapper::new(series, episodes))?);
+    Ok(episodes.len())
+  }
+
+  fn print_stats(&self, stats: &ImdbStats, _imdb_url: &Url) -> Result<usize, Self::Error> {
+    println!("{}", serde_yaml::to_string(stats)?);
+    Ok(stats.titles())
+  }
+
+  fn print_info(&self, title: &ImdbTitle, imdb_url: &Url) -> Result<usize, Self::Error> {
+    println!("{}", serde_yaml::to_string(&InfoOutput::new(title, imdb_url)?)?);
+    Ok(1)
+  }
+}
+
+pub struct QuietPrinter {
+  format: OutputFormat,
+}
+
+impl QuietPrinter {
+  #[must_use]
+  pub fn new(format: OutputFormat) -> Self {
+    Self { format }
+  }
+}
+
+impl Printer for QuietPrinter {
+  type Error = Error;
+
+  fn get_format(&self) -> OutputFormat {
+    self.format.clone()
+  }
+
+  fn print(
+    &self,
+    movies: Option<SearchRes>,
+    series: Option<SearchRes>,
+    _imdb_url: &Url,
+    _search_terms: Option<&str>,
+  ) -> Result<usize, Self::Error> {
+    Ok(total_matches(&movies, &series))
+  }
+
+  fn print_trending(
+    &self,
+    movies: &[ImdbTrending],
+    series: &[ImdbTrending],
+    _imdb_url: &Url,
+    _window: &str,
+  ) -> Result<usize, Self::Error> {
+    Ok(movies.len() + series.len())
+  }
+
+  fn print_remote(
+    &self,
+    remote: &[ImdbSuggestion],
+    _imdb_url: &Url,
+    _search_terms: Option<&str>,
+  ) -> Result<usize, Self::Error> {
+    Ok(remote.len())
+  }
+
+  fn print_episodes(
+    &self,
+    _series: &ImdbTitle,
+    episodes: &ImdbSeriesEpisodes,
+    _imdb_url: &Url,
+  ) -> Result<usize, Self::Error> {
+    Ok(episodes.len())
+  }
+
+  fn print_stats(&self, stats: &ImdbStats, _imdb_url: &Url) -> Result<usize, Self::Error> {
+    Ok(stats.titles())
+  }
+
+  fn print_info(&self, _title: &ImdbTitle, _imdb_url: &Url) -> Result<usize, Self::Error> {
+    Ok(1)
   }
 }
 
@@ -135,13 +527,267 @@ impl Printer for TablePrinter {
     series: Option<SearchRes>,
     imdb_url: &Url,
     search_terms: Option<&str>,
-  ) -> Result<(), Self::Error> {
+  ) -> Result<usize, Self::Error> {
+    let mut matches = 0;
     if let Some(movies) = movies {
-      self.print_results(movies, imdb_url, ImdbQuery::Movies, search_terms)?;
+      matches += self.print_results(movies, imdb_url, ImdbQuery::Movies, search_terms)?;
     }
     if let Some(series) = series {
-      self.print_results(series, imdb_url, ImdbQuery::Series, search_terms)?;
+      matches += self.print_results(series, imdb_url, ImdbQuery::Series, search_terms)?;
+    }
+
+    Ok(matches)
+  }
+
+  fn print_trending(
+    &self,
+    movies: &[ImdbTrending],
+    series: &[ImdbTrending],
+    imdb_url: &Url,
+    window: &str,
+  ) -> Result<usize, Self::Error> {
+    self.print_trending_results(movies, imdb_url, ImdbQuery::Movies, window)?;
+    self.print_trending_results(series, imdb_url, ImdbQuery::Series, window)?;
+    Ok(movies.len() + series.len())
+  }
+
+  fn print_remote(
+    &self,
+    remote: &[ImdbSuggestion],
+    imdb_url: &Url,
+    search_terms: Option<&str>,
+  ) -> Result<usize, Self::Error> {
+    let search_terms = search_terms
+      .map(|search_terms| format!(" for `{search_terms}`"))
+      .unwrap_or_default();
+
+    if remote.is_empty() {
+      eprintln!("No local or remote matches found{search_terms}");
+      return Ok(0);
+    }
+
+    println!("No local matches found{search_terms}, remote IMDB suggestions (unranked):");
+
+    let mut table = create_table_with_header(self.color, &["Title", "Year", "Type", "IMDB ID", "IMDB Link"]);
+
+    for suggestion in remote {
+      let mut row = Row::new(vec![]);
+      row.add_cell(Cell::new(&Truncatable::from(suggestion.primary_title()).truncate(50)));
+
+      if let Some(year) = suggestion.start_year() {
+        row.add_cell(Cell::new(&format!("{year}")));
+      } else {
+        row.add_cell(Cell::new(""));
+      }
+
+      if let Some(title_type) = suggestion.title_type() {
+        row.add_cell(Cell::new(&format!("{title_type}")));
+      } else {
+        row.add_cell(Cell::new(""));
+      }
+
+      row.add_cell(Cell::new(suggestion.title_id()));
+      row.add_cell(Cell::new(imdb_url.join(suggestion.title_id())?.as_str()));
+      table.add_row(row);
+    }
+    table.printstd();
+    println!();
+
+    Ok(remote.len())
+  }
+
+  fn print_episodes(
+    &self,
+    series: &ImdbTitle,
+    episodes: &ImdbSeriesEpisodes,
+    imdb_url: &Url,
+  ) -> Result<usize, Self::Error> {
+    let mut table = create_table(self.color, &[], &[]);
+    table.add_row(self.create_table_row(series, imdb_url)?);
+    table.printstd();
+    println!();
+
+    if episodes.is_empty() {
+      eprintln!("No episodes found for `{}`", series.primary_title());
+      return Ok(0);
+    }
+
+    println!("Episode ratings per season:");
+
+    let seasons = episodes.seasons();
+    let header: Vec<_> = seasons.iter().map(|season| format!("S{}", season.number())).collect();
+    let header: Vec<_> = ["Episode"].into_iter().chain(header.iter().map(String::as_str)).collect();
+    let mut table = create_table_with_header(self.color, &header);
+
+    let max_episode = episodes.episodes().map(ImdbEpisode::episode).max().unwrap_or_default();
+    for number in 1..=max_episode {
+      let mut row = Row::new(vec![Cell::new(&format!("E{number}"))]);
+      for season in seasons {
+        let episode = season.episodes().iter().find(|episode| episode.episode() == number);
+        let rating = episode.and_then(ImdbEpisode::rating).map(|rating| rating.rating());
+        row.add_cell(self.rating_cell(rating.map(f64::from), |rating| format!("{rating}")));
+      }
+      table.add_row(row);
+    }
+
+    let mut row = Row::new(vec![Cell::new("Average")]);
+    for season in seasons {
+      row.add_cell(self.rating_cell(season.average(), |average| format!("{average:.1}")));
+    }
+    table.add_row(row);
+    table.printstd();
+
+    if let Some(average) = episodes.average() {
+      println!("Average episode rating: {average:.1}/100 over {} episodes", episodes.len());
+    }
+    for (name, episode) in [("Best", episodes.best()), ("Worst", episodes.worst())] {
+      if let (Some(episode), Some(rating)) = (episode, episode.and_then(ImdbEpisode::rating)) {
+        println!(
+          "{name} episode: S{}E{} rated {}/100 with {} votes ({})",
+          episode.season(),
+          episode.episode(),
+          rating.rating(),
+          rating.votes(),
+          imdb_url.join(&episode.id())?,
+        );
+      }
+    }
+    println!();
+
+    Ok(episodes.len())
+  }
+
+  fn print_stats(&self, stats: &ImdbStats, imdb_url: &Url) -> Result<usize, Self::Error> {
+    if stats.titles() == 0 {
+      eprintln!("No titles found");
+      return Ok(0);
+    }
+
+    let runtime = format_duration(Duration::from_secs(stats.runtime_minutes() * 60));
+    println!("Titles: {} ({} rated)", stats.titles(), stats.rated());
+    if let Some(average) = stats.average_rating() {
+      println!("Average rating: {average:.1}/100");
+    }
+    println!("Total runtime: {runtime}");
+    println!();
+
+    let max = stats.ratings().iter().copied().max().unwrap_or_default();
+    let mut table = create_table_with_header(self.color, &["Rating", "Titles", ""]);
+    for (point, &count) in stats.ratings().iter().enumerate().rev() {
+      let range = if point == 9 {
+        String::from("90-100")
+      } else {
+        format!("{}-{}", point * 10, point * 10 + 9)
+      };
+      table.add_row(Row::new(vec![Cell::new(&range), Cell::new(&count.to_string()), bar_cell(count, max)]));
+    }
+    println!("Rating distribution:");
+    table.printstd();
+    println!();
+
+    let max = stats.decades().values().copied().max().unwrap_or_default();
+    let mut table = create_table_with_header(self.color, &["Decade", "Titles", ""]);
+    for (decade, &count) in stats.decades() {
+      table.add_row(Row::new(vec![
+        Cell::new(&format!("{decade}s")),
+        Cell::new(&count.to_string()),
+        bar_cell(count, max),
+      ]));
+    }
+    println!("Titles per decade:");
+    table.printstd();
+    println!();
+
+    let mut table = create_table_with_header(self.color, &["Genre", "Titles"]);
+    for (genre, count) in stats.genres() {
+      table.add_row(Row::new(vec![Cell::new(&genre.to_string()), Cell::new(&count.to_string())]));
+    }
+    println!("Titles per genre:");
+    table.printstd();
+    println!();
+
+    for (name, titles) in [("Best", stats.best()), ("Worst", stats.worst())] {
+      if titles.is_empty() {
+        continue;
+      }
+
+      let mut table = create_table(self.color, &[], &[]);
+      for title in titles {
+        table.add_row(self.create_table_row(title, imdb_url)?);
+      }
+      println!("{name} rated titles:");
+      table.printstd();
+      println!();
+    }
+
+    Ok(stats.titles())
+  }
+
+  fn print_info(&self, title: &ImdbTitle, imdb_url: &Url) -> Result<usize, Self::Error> {
+    let mut table = create_table_with_header(self.color, &["Field", "Value"]);
+    let mut add_row = |name: &str, value: Cell| {
+      table.add_row(Row::new(vec![Cell::new(name), value]));
+    };
+
+    add_row("Title", Cell::new(title.primary_title()));
+    add_row("Original title", Cell::new(title.original_title().unwrap_or("")));
+    add_row("Type", Cell::new(&title.title_type().to_string()));
+    add_row("Year", Cell::new(&years(title)));
+
+    match title.rating() {
+      Some(rating) => {
+        let mut rating_cell = Cell::new(&format!("{}/100", rating.rating()));
+        if self.color {
+          rating_cell = rating_cell.with_style(rating_style(f64::from(rating.rating())));
+        }
+        add_row("Rating", rating_cell);
+        add_row("Votes", Cell::new(&rating.votes().to_string()));
+      }
+      None => {
+        add_row("Rating", Cell::new(""));
+        add_row("Votes", Cell::new(""));
+      }
+    }
+
+    let runtime = title.runtime().map(|runtime| format_duration(runtime).to_string());
+    add_row("Runtime", Cell::new(runtime.as_deref().unwrap_or("")));
+    add_row("Genres", Cell::new(&title.genres().to_string()));
+    add_row(
+      "Adult",
+      Cell::new(if title.is_adult() {
+        "Yes"
+      } else {
+        "No"
+      }),
+    );
+    add_row("IMDB ID", Cell::new(&title.title_id().to_string()));
+    add_row("IMDB link", Cell::new(&InfoOutput::new(title, imdb_url)?.imdb_link));
+
+    table.printstd();
+    println!();
+
+    Ok(1)
+  }
+
+  fn print_storage_usage(&self, usage: &UsageSummary) -> Result<(), Self::Error> {
+    if usage.total.titles == 0 {
+      return Ok(());
+    }
+
+    println!("Disk usage per genre:");
+
+    let mut table = create_table_with_header(self.color, &["Genre", "Titles", "Size"]);
+
+    let rows = usage.genres.iter().map(|(genre, usage)| (genre.to_string(), usage));
+    for (name, usage) in rows.chain([(String::from("Total"), &usage.total)]) {
+      let mut row = Row::new(vec![]);
+      row.add_cell(Cell::new(&name));
+      row.add_cell(Cell::new(&usage.titles.to_string()));
+      row.add_cell(Cell::new(&ImdbColumnValue::Bytes(usage.bytes).to_string()));
+      table.add_row(row);
     }
+    table.printstd();
+    println!();
 
     Ok(())
   }
@@ -159,7 +805,7 @@ impl TablePrinter {
     imdb_url: &Url,
     query: ImdbQuery,
     search_terms: Option<&str>,
-  ) -> Result<(), Error> {
+  ) -> Result<usize, Error> {
     if results.is_empty() {
       if let Some(search_terms) = search_terms {
         eprintln!("No {query} matches found for `{search_terms}`");
@@ -186,24 +832,75 @@ impl TablePrinter {
         println!("Found {num} {query} {matches}:");
       }
 
-      let mut table = create_table(self.color);
+      if results.is_normalized() {
+        println!("No exact {query} match, these match when leading articles are ignored.");
+      }
+
+      let columns = results.columns().clone();
+      let column_names: Vec<_> = columns.iter().map(|column| column.name()).collect();
+      let mut table = create_table(self.color, &[], &column_names);
 
       for res in results.top_sorted_results() {
-        let row = self.create_table_row(res, imdb_url)?;
+        let mut row = self.create_table_row(res, imdb_url)?;
+        for (_, value) in columns.values(res).iter() {
+          row.add_cell(Cell::new(&value.map(ToString::to_string).unwrap_or_default()));
+        }
         table.add_row(row);
       }
       table.printstd();
+
+      if results.is_overflowed() {
+        println!("More than {num} {query} matches found, stopped searching. Please refine your query.");
+      }
+
       println!();
     }
 
+    Ok(results.total_len())
+  }
+
+  fn print_trending_results(
+    &self,
+    results: &[ImdbTrending],
+    imdb_url: &Url,
+    query: ImdbQuery,
+    window: &str,
+  ) -> Result<(), Error> {
+    if results.is_empty() {
+      eprintln!("No {query} gained votes over the last {window}");
+      return Ok(());
+    }
+
+    println!("Trending {query} titles over the last {window}:");
+
+    let mut table = create_table(self.color, &["Votes/Day"], &[]);
+
+    for res in results {
+      let mut row = self.create_table_row(res.title(), imdb_url)?;
+      row.insert_cell(0, Cell::new(&format!("{:.1}", res.votes_per_day())));
+      table.add_row(row);
+    }
+    table.printstd();
+    println!();
+
     Ok(())
   }
 
-  fn create_table_row(&self, title: &ImdbTitle, imdb_url: &Url) -> Result<Row, Error> {
-    static GREEN: Attr = Attr::ForegroundColor(color::GREEN);
-    static YELLOW: Attr = Attr::ForegroundColor(color::YELLOW);
-    static RED: Attr = Attr::ForegroundColor(color::RED);
+  /// A cell with a rating out of 100, colored like the ratings of titles.
+  fn rating_cell(&self, rating: Option<f64>, format: impl Fn(f64) -> String) -> Cell {
+    let Some(rating) = rating else {
+      return Cell::new("");
+    };
 
+    let cell = Cell::new(&format(rating));
+    if self.color {
+      cell.with_style(rating_style(rating))
+    } else {
+      cell
+    }
+  }
+
+  fn create_table_row(&self, title: &ImdbTitle, imdb_url: &Url) -> Result<Row, Error> {
     let mut row = Row::new(vec![]);
 
     row.add_cell(Cell::new(&Truncatable::from(title.primary_title()).truncate(50)));
@@ -214,22 +911,14 @@ impl TablePrinter {
       row.add_cell(Cell::new(""));
     }
 
-    if let Some(year) = title.start_year() {
-      row.add_cell(Cell::new(&format!("{year}")));
-    } else {
-      row.add_cell(Cell::new(""));
-    }
+    row.add_cell(Cell::new(&years(title)));
 
     if let Some(rating) = title.rating() {
       let rating_text = &format!("{}/100", rating.rating());
 
       let mut rating_cell = Cell::new(rating_text);
       if self.color {
-        rating_cell = rating_cell.with_style(match rating {
-          rating if rating.rating() >= 70 => GREEN,
-          rating if (60..70).contains(&rating.rating()) => YELLOW,
-          _ => RED,
-        });
+        rating_cell = rating_cell.with_style(rating_style(f64::from(rating.rating())));
       }
 
       row.add_cell(rating_cell);
@@ -258,7 +947,58 @@ impl TablePrinter {
   }
 }
 
-fn create_table(color: bool) -> Table {
+/// The years of a title: its release year for movies, and a range like "2008–2013" for
+/// series, which is open-ended like "2008–" while they are still running.
+fn years(title: &ImdbTitle) -> String {
+  match (title.start_year(), title.end_year()) {
+    (None, _) => String::new(),
+    (Some(start), Some(end)) => format!("{start}–{end}"),
+    (Some(start), None) if title.title_type().is_series() => format!("{start}–"),
+    (Some(start), None) => format!("{start}"),
+  }
+}
+
+/// The color of a rating out of 100: green when it is good, yellow when it is average and
+/// red otherwise.
+fn rating_style(rating: f64) -> Attr {
+  if rating >= 70.0 {
+    Attr::ForegroundColor(color::GREEN)
+  } else if rating >= 60.0 {
+    Attr::ForegroundColor(color::YELLOW)
+  } else {
+    Attr::ForegroundColor(color::RED)
+  }
+}
+
+/// A cell with a horizontal bar of up to 40 characters, proportional to `value` out of
+/// `max`.
+fn bar_cell(value: usize, max: usize) -> Cell {
+  let width = if max == 0 {
+    0
+  } else {
+    (value * 40).div_ceil(max)
+  };
+  Cell::new(&"█".repeat(width))
+}
+
+fn create_table(color: bool, leading_columns: &[&str], trailing_columns: &[&str]) -> Table {
+  let columns = [
+    "Primary Title",
+    "Original Title",
+    "Year",
+    "Rating",
+    "Votes",
+    "Runtime",
+    "Genres",
+    "Type",
+    "IMDB ID",
+    "IMDB Link",
+  ];
+
+  create_table_with_header(color, &[leading_columns, &columns, trailing_columns].concat())
+}
+
+fn create_table_with_header(color: bool, columns: &[&str]) -> Table {
   let mut table = Table::new();
 
   let table_format = format::FormatBuilder::new()
@@ -269,7 +1009,6 @@ fn create_table(color: bool) -> Table {
 
   table.set_format(table_format);
 
-  #[macro_export]
   macro_rules! make_bold {
     ($title: expr, $color: expr) => {
       match $color {
@@ -279,18 +1018,6 @@ fn create_table(color: bool) -> Table {
     };
   }
 
-  table.add_row(Row::new(vec![
-    make_bold!("Primary Title", color),
-    make_bold!("Original Title", color),
-    make_bold!("Year", color),
-    make_bold!("Rating", color),
-    make_bold!("Votes", color),
-    make_bold!("Runtime", color),
-    make_bold!("Genres", color),
-    make_bold!("Type", color),
-    make_bold!("IMDB ID", color),
-    make_bold!("IMDB Link", color),
-  ]));
-
+  table.add_row(Row::new(columns.iter().map(|&column| make_bold!(column, color)).collect()));
   table
 }
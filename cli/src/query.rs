@@ -0,0 +1,198 @@
+#![warn(clippy::all)]
+
+//! The state of a search that is computed from its options, such as the ranking of titles
+//! and the columns of other data sources, and the results it starts from.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::commands::sync::trakt_watched_file;
+use crate::context::create_enricher;
+use crate::error::Error;
+use crate::opts::{Rank, SearchOpts};
+use crate::search::{Exclusion, SearchRes};
+use crate::sources::EnrichmentSource;
+
+use tvrank::imdb::{Imdb, ImdbBaselines, ImdbColumn, ImdbColumnValue, ImdbColumns, ImdbWeightedRating};
+use tvrank::paths::Paths;
+use tvrank::scores::{self, ScoreDataset};
+use tvrank::trakt::TraktWatched;
+use tvrank::utils::search::SearchString;
+
+use tracing::debug;
+
+/// Minimum delay between requests to other sources when displaying results, within their
+/// rate limits.
+const SOURCE_DELAY: Duration = Duration::from_millis(50);
+
+/// A search along with what its options require from the IMDB database and other sources.
+#[derive(Debug, Clone)]
+pub struct Query {
+  opts: SearchOpts,
+  weighted_rating: Option<ImdbWeightedRating>,
+  baselines: Option<ImdbBaselines>,
+  score_columns: Vec<ImdbColumn>,
+  enrichment: Option<EnrichmentSource>,
+  watched_titles: Option<Arc<TraktWatched>>,
+}
+
+impl Query {
+  /// Create a query ranking titles as requested, with the rating baselines of the database if
+  /// they are requested.
+  ///
+  /// # Arguments
+  ///
+  /// * `opts` - The search options.
+  /// * `imdb` - The IMDB database, whose average rating is the prior mean of `--rank
+  ///   bayesian` unless one is given.
+  pub fn new(opts: SearchOpts, imdb: &Imdb) -> Self {
+    let weighted_rating = match opts.rank {
+      Rank::Average => None,
+      Rank::Bayesian => Some(match opts.prior_mean {
+        Some(mean) => ImdbWeightedRating::new(mean, opts.prior_votes),
+        None => imdb.weighted_rating(opts.prior_votes),
+      }),
+    };
+
+    let baselines = opts.baseline.then(|| imdb.baselines().clone());
+
+    Self {
+      opts,
+      weighted_rating,
+      baselines,
+      score_columns: Vec::new(),
+      enrichment: None,
+      watched_titles: None,
+    }
+  }
+
+  /// Connect to the requested data sources other than IMDB, and load the scores and watched
+  /// titles if they are requested.
+  ///
+  /// # Arguments
+  ///
+  /// * `paths` - Locations of the cached data of other sources and of the watched titles.
+  pub fn with_sources(mut self, paths: &Paths) -> Result<Self, Error> {
+    let providers: Vec<_> = self.opts.source.iter().filter_map(|source| source.provider()).collect();
+
+    if !providers.is_empty() {
+      let enricher = create_enricher(paths, &providers, SOURCE_DELAY)?;
+      let providers = providers
+        .into_iter()
+        .filter(|&provider| enricher.has_api_key(provider))
+        .collect();
+      self.enrichment = Some(EnrichmentSource::new(enricher, providers, self.opts.enrich_top));
+    }
+
+    if let Some(file) = &self.opts.scores {
+      let dataset = ScoreDataset::load(file)?;
+      debug!("Loaded the scores of {} titles from {}", dataset.len(), file.display());
+      self.score_columns = scores::columns(Arc::new(dataset));
+    }
+
+    if self.opts.watched || self.opts.unwatched {
+      let watched = TraktWatched::load(&trakt_watched_file(paths))?;
+      self.watched_titles = Some(Arc::new(watched.ok_or(Error::NoWatchedTitles)?));
+    }
+
+    Ok(self)
+  }
+
+  /// The search options the query was created from.
+  pub fn opts(&self) -> &SearchOpts {
+    &self.opts
+  }
+
+  /// Titles to hide from the results, if any.
+  pub fn exclusion(&self) -> Option<Exclusion> {
+    let watched = self.watched_titles.clone().filter(|_| self.opts.unwatched)?;
+    Some(Arc::new(move |title| watched.contains(title.title_id())))
+  }
+
+  /// The number of titles keyword searches can stop at, one more than the maximum number of
+  /// results so that overflowing is still noticed. There is none when results are filtered,
+  /// since the filtered out titles would count towards it.
+  pub fn limit(&self) -> Option<usize> {
+    if self.opts.max_results == 0 || !self.opts.filter().is_empty() || self.exclusion().is_some() {
+      return None;
+    }
+
+    Some(self.opts.max_results + 1)
+  }
+
+  /// Sort the results of a keyword search by relevance to the keywords, if requested.
+  pub fn by_relevance<'a, 'storage>(
+    &self,
+    movies_results: SearchRes<'a, 'storage>,
+    series_results: SearchRes<'a, 'storage>,
+    keywords: &[SearchString],
+  ) -> (SearchRes<'a, 'storage>, SearchRes<'a, 'storage>) {
+    if !self.opts.sort_by_relevance {
+      return (movies_results, series_results);
+    }
+
+    let relevance = ImdbColumn::relevance(keywords);
+    (movies_results.with_sort_column(relevance.clone()), series_results.with_sort_column(relevance))
+  }
+
+  pub fn new_results<'a, 'storage>(&self, top: Option<usize>) -> SearchRes<'a, 'storage> {
+    let opts = &self.opts;
+    let mut results = SearchRes::new(opts.sort_by_year, top, opts.filter());
+
+    if opts.max_results > 0 {
+      results = results.with_cap(opts.max_results);
+    }
+
+    if !opts.column.is_empty() {
+      let mut columns = ImdbColumns::new();
+      for column in &opts.column {
+        columns.register(column.clone());
+      }
+      results = results.with_columns(columns);
+    }
+
+    if let Some(column) = &opts.sort_by_column {
+      results = results.with_sort_column(column.clone());
+    }
+
+    if let Some(weighted_rating) = self.weighted_rating {
+      results = results.with_weighted_rating(weighted_rating);
+      results = results.with_column(ImdbColumn::new("Weighted-Rating", move |title| {
+        Some(ImdbColumnValue::Float(weighted_rating.score(&title.rating()?)))
+      }));
+    }
+
+    if let Some(exclusion) = self.exclusion() {
+      results = results.with_exclusion(exclusion);
+    }
+
+    if let Some(baselines) = &self.baselines {
+      let baselines = baselines.clone();
+      results = results.with_column(ImdbColumn::new("Baseline", move |title| {
+        baselines
+          .compare(title)
+          .map(|comparison| ImdbColumnValue::Text(comparison.to_string()))
+      }));
+    }
+
+    for column in &self.score_columns {
+      results = results.with_column(column.clone());
+    }
+
+    if let Some(enrichment) = &self.enrichment {
+      for column in enrichment.columns() {
+        results = results.with_column(column);
+      }
+    }
+
+    if let Some(watched) = self.watched_titles.clone().filter(|_| opts.watched) {
+      results = results.with_column(ImdbColumn::new("Watched", move |title| {
+        watched
+          .get(title.title_id())
+          .map(|watched| ImdbColumnValue::Text(watched.to_string()))
+      }));
+    }
+
+    results
+  }
+}
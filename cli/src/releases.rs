@@ -0,0 +1,114 @@
+#![warn(clippy::all)]
+
+use std::path::Path;
+
+/// Extensions of the video files that are considered when scanning files.
+const MEDIA_EXTENSIONS: [&str; 12] =
+  ["avi", "flv", "m2ts", "m4v", "mkv", "mov", "mp4", "mpeg", "mpg", "ts", "webm", "wmv"];
+
+/// Words of release names that describe the release rather than the title, e.g. its source,
+/// codecs or audio. Only words that are unlikely to be part of a title are listed.
+const NOISE: [&str; 29] = [
+  "4k", "uhd", "hdr", "hdr10", "bluray", "blu-ray", "bdrip", "brrip", "bdremux", "remux", "webrip", "web-dl",
+  "webdl", "hdtv", "dvdrip", "dvdscr", "hdrip", "x264", "x265", "h264", "h265", "hevc", "xvid", "divx",
+  "10bit", "aac", "ac3", "dts", "truehd",
+];
+
+/// Whether `path` is a video file, by its extension.
+pub fn is_media_file(path: &Path) -> bool {
+  path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .is_some_and(|ext| MEDIA_EXTENSIONS.iter().any(|media| media.eq_ignore_ascii_case(ext)))
+}
+
+fn is_noise(word: &str) -> bool {
+  let word = word.to_ascii_lowercase();
+
+  // Release groups are commonly appended to the last word, e.g. "x264-GROUP".
+  let words = [word.as_str(), word.split('-').next().unwrap_or_default()];
+
+  words.iter().any(|word| {
+    NOISE.contains(word)
+      || word
+        .strip_suffix(['p', 'i'])
+        .is_some_and(|resolution| matches!(resolution, "480" | "576" | "720" | "1080" | "2160"))
+  })
+}
+
+fn parse_year(word: &str) -> Option<u16> {
+  let word = word.trim_matches(['(', ')', '[', ']']);
+  if word.len() != 4 {
+    return None;
+  }
+
+  word.parse().ok().filter(|year| (1870..=2100).contains(year))
+}
+
+/// Parse the title and year of a movie from the name of its file without the extension,
+/// either "TITLE (YYYY)" or a release name like "Title.Of.Movie.1995.1080p.BluRay.x264-GROUP".
+///
+/// Dots and underscores separate words, a leading "[GROUP]" tag is ignored, and the year is
+/// the last year before the first word describing the release, so that years in titles like
+/// "Blade Runner 2049 (2017)" are kept.
+pub fn parse_release_name(name: &str) -> Option<(String, u16)> {
+  let name = match name.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+    Some((_, rest)) => rest,
+    None => name,
+  };
+
+  let name = name.replace(['.', '_'], " ");
+  let words = name.split_whitespace().collect::<Vec<_>>();
+  let end = words
+    .iter()
+    .skip(1)
+    .position(|word| is_noise(word))
+    .map_or(words.len(), |pos| pos + 1);
+  let (index, year) = words[..end]
+    .iter()
+    .enumerate()
+    .skip(1)
+    .rev()
+    .find_map(|(index, word)| parse_year(word).map(|year| (index, year)))?;
+
+  let title = words[..index].join(" ");
+  let title = title.trim_end_matches([' ', '-']);
+
+  if title.is_empty() {
+    None
+  } else {
+    Some((title.to_owned(), year))
+  }
+}
+
+/// Whether `name` is the name of a season directory, e.g. "Season 1", "S01" or "Specials".
+pub fn is_season_dir_name(name: &str) -> bool {
+  let name = name.trim().to_ascii_lowercase();
+  let number = name
+    .strip_prefix("season")
+    .map(str::trim_start)
+    .or_else(|| name.strip_prefix('s'));
+
+  name == "specials"
+    || number.is_some_and(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Whether `name` is the name of an episode file, containing an episode number like "S01E02"
+/// or "1x02".
+pub fn is_episode_name(name: &str) -> bool {
+  fn is_number(text: &str, max_digits: usize) -> bool {
+    (1..=max_digits).contains(&text.len()) && text.bytes().all(|b| b.is_ascii_digit())
+  }
+
+  let name = name.to_ascii_lowercase();
+  name.split(|c: char| !c.is_ascii_alphanumeric()).any(|word| {
+    if let Some((season, episodes)) = word.strip_prefix('s').and_then(|rest| rest.split_once('e')) {
+      // Multi-episode files are named like "S01E01E02".
+      is_number(season, 2) && episodes.split('e').all(|episode| is_number(episode, 3))
+    } else if let Some((season, episode)) = word.split_once('x') {
+      is_number(season, 2) && episode.len() >= 2 && is_number(episode, 3)
+    } else {
+      false
+    }
+  })
+}
@@ -0,0 +1,149 @@
+#![warn(clippy::all)]
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tvrank::imdb::ImdbTitle;
+
+/// The name of a title's directory in the canonical "Primary Title (Year)" form, or `None`
+/// if the title has no release year.
+///
+/// Characters that are not allowed in file names on common filesystems are replaced, e.g.
+/// "Mission: Impossible (1996)" becomes "Mission - Impossible (1996)".
+pub fn canonical_name(title: &ImdbTitle) -> Option<String> {
+  let year = title.start_year()?;
+  let mut name = String::with_capacity(title.primary_title().len());
+
+  for c in title.primary_title().chars() {
+    match c {
+      ':' => name.push_str(" -"),
+      '/' | '\\' | '|' => name.push('-'),
+      '*' | '?' | '"' | '<' | '>' => {}
+      c if c.is_control() => {}
+      c => name.push(c),
+    }
+  }
+
+  let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+  let name = name.trim_end_matches('.');
+  Some(format!("{name} ({year})"))
+}
+
+/// Why a directory cannot be renamed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Collision {
+  /// Another directory already has the new name.
+  Exists,
+  /// Several directories would be renamed to the same name.
+  Duplicate,
+}
+
+#[derive(Debug)]
+struct Rename {
+  from: PathBuf,
+  to: PathBuf,
+  collision: Option<Collision>,
+}
+
+/// Renames of scanned directories into the canonical form of the titles they matched.
+#[derive(Debug, Default)]
+pub struct RenamePlan {
+  renames: Vec<Rename>,
+}
+
+impl RenamePlan {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Suggest renaming `dir` after the title it matched, if its name is not canonical yet.
+  pub fn add(&mut self, dir: &Path, title: &ImdbTitle) {
+    let (Some(name), Some(parent)) = (canonical_name(title), dir.parent()) else {
+      return;
+    };
+
+    if dir.file_name().is_some_and(|current| current == name.as_str()) {
+      return;
+    }
+
+    let to = parent.join(name);
+    let collision = if to.exists() && !is_same_dir(dir, &to) {
+      Some(Collision::Exists)
+    } else {
+      None
+    };
+
+    self.renames.push(Rename { from: dir.to_owned(), to, collision });
+  }
+
+  /// Flag the renames that would give several directories the same name.
+  fn detect_duplicates(&mut self) {
+    let mut targets: HashMap<PathBuf, usize> = HashMap::new();
+    for rename in &self.renames {
+      *targets.entry(rename.to.clone()).or_default() += 1;
+    }
+
+    for rename in &mut self.renames {
+      if rename.collision.is_none() && targets[&rename.to] > 1 {
+        rename.collision = Some(Collision::Duplicate);
+      }
+    }
+  }
+
+  /// Number of suggested renames.
+  pub fn len(&self) -> usize {
+    self.renames.len()
+  }
+
+  /// Print the renames as a diff of directory names relative to `dir`, along with the ones
+  /// that are skipped because of collisions.
+  pub fn print(&mut self, dir: &Path) {
+    self.detect_duplicates();
+
+    if self.renames.is_empty() {
+      println!("All matched directories already have canonical names");
+      return;
+    }
+
+    println!("Suggested renames under {}:", dir.display());
+    for rename in &self.renames {
+      let from = rename.from.strip_prefix(dir).unwrap_or(&rename.from);
+      let to = rename.to.strip_prefix(dir).unwrap_or(&rename.to);
+
+      println!("- {}", from.display());
+      match rename.collision {
+        None => println!("+ {}", to.display()),
+        Some(Collision::Exists) => println!("! {} already exists, skipping", to.display()),
+        Some(Collision::Duplicate) => {
+          println!("! {} is suggested for several directories, skipping", to.display())
+        }
+      }
+    }
+    println!();
+  }
+
+  /// Rename the directories, except the ones with collisions. Returns the number of renamed
+  /// directories.
+  pub fn apply(&mut self) -> Result<usize, io::Error> {
+    self.detect_duplicates();
+
+    let mut renamed = 0;
+    for rename in self.renames.iter().filter(|rename| rename.collision.is_none()) {
+      fs::rename(&rename.from, &rename.to)?;
+      renamed += 1;
+    }
+
+    Ok(renamed)
+  }
+}
+
+/// Whether two paths are the same directory, e.g. names that only differ in case on a
+/// case-insensitive filesystem.
+fn is_same_dir(a: &Path, b: &Path) -> bool {
+  match (fs::canonicalize(a), fs::canonicalize(b)) {
+    (Ok(a), Ok(b)) => a == b,
+    _ => false,
+  }
+}
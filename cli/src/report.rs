@@ -0,0 +1,56 @@
+#![warn(clippy::all)]
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use tvrank::imdb::ImdbTitle;
+
+use serde::Serialize;
+
+/// A directory or file whose name matches several titles.
+#[derive(Debug, Serialize)]
+struct Ambiguous {
+  path: PathBuf,
+  candidates: Vec<String>,
+}
+
+/// The directories or files of a scan that need attention: the ones that matched no title,
+/// several titles, or were skipped because of their names.
+#[derive(Debug, Default, Serialize)]
+pub struct ScanReport {
+  unmatched: Vec<PathBuf>,
+  ambiguous: Vec<Ambiguous>,
+  skipped: Vec<PathBuf>,
+}
+
+impl ScanReport {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Record the titles that `path` matched, if it matched none or several of them.
+  pub fn add(&mut self, path: &Path, titles: &[&ImdbTitle]) {
+    match titles {
+      [] => self.unmatched.push(path.to_owned()),
+      [_] => {}
+      titles => self.ambiguous.push(Ambiguous {
+        path: path.to_owned(),
+        candidates: titles.iter().map(|title| title.title_id().to_string()).collect(),
+      }),
+    }
+  }
+
+  /// Record that `path` was skipped because of its name.
+  pub fn skip(&mut self, path: &Path) {
+    self.skipped.push(path.to_owned());
+  }
+
+  /// Write the report as JSON to `path`.
+  pub fn write(&self, path: &Path) -> Result<(), io::Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, self)?;
+    writeln!(writer)?;
+    writer.flush()
+  }
+}
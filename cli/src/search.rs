@@ -1,58 +1,199 @@
 #![warn(clippy::all)]
 
 use std::cmp::Ordering;
-use std::ops::{Deref, DerefMut};
-use tvrank::imdb::ImdbTitle;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+use tvrank::imdb::{ImdbColumn, ImdbColumns, ImdbFilter, ImdbTitle, ImdbWeightedRating};
 
-pub struct SearchRes<'a, 'storage> {
-  results: Vec<&'a ImdbTitle<'storage>>,
-  sort_by_year: bool,
-  top: Option<usize>,
+/// Compare the ratings of two titles, by their weighted ratings if any.
+fn compare_ratings(a: &ImdbTitle, b: &ImdbTitle, weighted_rating: Option<&ImdbWeightedRating>) -> Ordering {
+  match weighted_rating {
+    Some(weighted_rating) => weighted_rating.compare(a.rating().as_ref(), b.rating().as_ref()),
+    None => a.rating().cmp(&b.rating()),
+  }
 }
 
-impl<'a, 'storage> AsRef<[&'a ImdbTitle<'storage>]> for SearchRes<'a, 'storage> {
-  fn as_ref(&self) -> &[&'a ImdbTitle<'storage>] {
-    self.results.as_ref()
+/// Compare two titles by their position in the results, with better titles first.
+fn compare_titles(
+  a: &ImdbTitle,
+  b: &ImdbTitle,
+  sort_by_year: bool,
+  sort_column: Option<&ImdbColumn>,
+  weighted_rating: Option<&ImdbWeightedRating>,
+) -> Ordering {
+  if let Some(column) = sort_column {
+    match column.compare(b, a) {
+      Ordering::Equal => {}
+      ord => return ord,
+    }
+  }
+
+  if sort_by_year {
+    match b.start_year().cmp(&a.start_year()) {
+      Ordering::Equal => {}
+      ord => return ord,
+    }
+
+    match compare_ratings(b, a, weighted_rating) {
+      Ordering::Equal => {}
+      ord => return ord,
+    }
+  } else {
+    match compare_ratings(b, a, weighted_rating) {
+      Ordering::Equal => {}
+      ord => return ord,
+    }
+
+    match b.start_year().cmp(&a.start_year()) {
+      Ordering::Equal => {}
+      ord => return ord,
+    }
   }
+
+  b.primary_title().cmp(a.primary_title())
 }
 
-impl<'a, 'storage> AsMut<[&'a ImdbTitle<'storage>]> for SearchRes<'a, 'storage> {
-  fn as_mut(&mut self) -> &mut [&'a ImdbTitle<'storage>] {
-    self.results.as_mut()
+/// A title ordered by its position in the results.
+///
+/// The greatest element is the one that would be displayed last, so a `BinaryHeap` of
+/// these always has the worst of the retained titles at its top.
+#[derive(Clone)]
+struct Ranked<'a, 'storage> {
+  title: &'a ImdbTitle<'storage>,
+  sort_by_year: bool,
+  sort_column: Option<ImdbColumn>,
+  weighted_rating: Option<ImdbWeightedRating>,
+}
+
+impl PartialEq for Ranked<'_, '_> {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
   }
 }
 
-impl<'a, 'storage> Deref for SearchRes<'a, 'storage> {
-  type Target = Vec<&'a ImdbTitle<'storage>>;
+impl Eq for Ranked<'_, '_> {}
 
-  fn deref(&self) -> &Self::Target {
-    &self.results
+impl PartialOrd for Ranked<'_, '_> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
   }
 }
 
-impl<'a, 'storage> DerefMut for SearchRes<'a, 'storage> {
-  fn deref_mut(&mut self) -> &mut Self::Target {
-    &mut self.results
+impl Ord for Ranked<'_, '_> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    compare_titles(
+      self.title,
+      other.title,
+      self.sort_by_year,
+      self.sort_column.as_ref(),
+      self.weighted_rating.as_ref(),
+    )
   }
 }
 
+/// A predicate telling which titles to hide from the results.
+pub type Exclusion = Arc<dyn Fn(&ImdbTitle) -> bool + Send + Sync>;
+
+#[derive(Clone)]
+pub struct SearchRes<'a, 'storage> {
+  /// Results when all of them are kept, or the retained results once sorted.
+  results: Vec<&'a ImdbTitle<'storage>>,
+  /// The best `top` results seen so far when only the top N results are displayed.
+  bounded: BinaryHeap<Ranked<'a, 'storage>>,
+  /// The number of results seen so far, including the ones that were not retained.
+  total: usize,
+  sort_by_year: bool,
+  top: Option<usize>,
+  /// Results not matching the filter are discarded without being counted.
+  filter: ImdbFilter,
+  /// Results matching the exclusion are discarded without being counted.
+  exclusion: Option<Exclusion>,
+  /// Results beyond the cap are discarded and the results are marked as overflowed.
+  cap: Option<usize>,
+  overflowed: bool,
+  /// Whether the results only matched once leading articles were ignored.
+  normalized: bool,
+  /// Derived columns displayed along with the results.
+  columns: ImdbColumns,
+  /// Derived column to sort by before the other sort keys.
+  sort_column: Option<ImdbColumn>,
+  /// Weighted rating to sort by instead of the average rating.
+  weighted_rating: Option<ImdbWeightedRating>,
+}
+
 impl<'a, 'storage> IntoIterator for SearchRes<'a, 'storage> {
   type Item = &'a ImdbTitle<'storage>;
 
   type IntoIter = std::vec::IntoIter<Self::Item>;
 
   fn into_iter(self) -> Self::IntoIter {
-    self.results.into_iter()
+    let mut results = self.results;
+    results.extend(self.bounded.into_iter().map(|ranked| ranked.title));
+    results.into_iter()
   }
 }
 
 impl<'a, 'storage> SearchRes<'a, 'storage> {
-  pub fn new(sort_by_year: bool, top: Option<usize>) -> Self {
-    Self { results: Vec::new(), sort_by_year, top }
+  pub fn new(sort_by_year: bool, top: Option<usize>, filter: ImdbFilter) -> Self {
+    Self {
+      results: Vec::new(),
+      bounded: BinaryHeap::new(),
+      total: 0,
+      sort_by_year,
+      top,
+      filter,
+      exclusion: None,
+      cap: None,
+      overflowed: false,
+      normalized: false,
+      columns: ImdbColumns::new(),
+      sort_column: None,
+      weighted_rating: None,
+    }
+  }
+
+  /// Stop accepting results once `cap` results were seen.
+  pub fn with_cap(mut self, cap: usize) -> Self {
+    self.cap = Some(cap);
+    self
+  }
+
+  /// Discard the results matching the given exclusion, like the filter.
+  pub fn with_exclusion(mut self, exclusion: Exclusion) -> Self {
+    self.exclusion = Some(exclusion);
+    self
+  }
+
+  /// Display the given derived columns along with the results.
+  pub fn with_columns(mut self, columns: ImdbColumns) -> Self {
+    self.columns = columns;
+    self
+  }
+
+  /// Display the given derived column along with the results, after the other columns.
+  pub fn with_column(mut self, column: ImdbColumn) -> Self {
+    self.columns.register(column);
+    self
+  }
+
+  /// Sort by the given derived column, highest values first, before the other sort keys.
+  pub fn with_sort_column(mut self, column: ImdbColumn) -> Self {
+    self.sort_column = Some(column);
+    self
+  }
+
+  /// Sort by the given weighted rating instead of the average rating.
+  pub fn with_weighted_rating(mut self, weighted_rating: ImdbWeightedRating) -> Self {
+    self.weighted_rating = Some(weighted_rating);
+    self
+  }
+
+  pub fn columns(&self) -> &ImdbColumns {
+    &self.columns
   }
 
   pub fn total_len(&self) -> usize {
-    self.results.len()
+    self.total
   }
 
   pub fn len(&self) -> usize {
@@ -62,6 +203,25 @@ impl<'a, 'storage> SearchRes<'a, 'storage> {
     }
   }
 
+  pub fn is_empty(&self) -> bool {
+    self.total == 0
+  }
+
+  /// Whether results were discarded because there were more than the cap.
+  pub fn is_overflowed(&self) -> bool {
+    self.overflowed
+  }
+
+  /// Mark the results as only matching once leading articles were ignored.
+  pub fn mark_normalized(&mut self) {
+    self.normalized = true;
+  }
+
+  /// Whether the results only matched once leading articles were ignored.
+  pub fn is_normalized(&self) -> bool {
+    self.normalized
+  }
+
   pub fn is_truncated(&self) -> bool {
     match self.top {
       Some(top) => top <= self.total_len(),
@@ -69,44 +229,129 @@ impl<'a, 'storage> SearchRes<'a, 'storage> {
     }
   }
 
+  /// Add a result, discarding the worst retained result if there are more than `top`.
+  pub fn push(&mut self, title: &'a ImdbTitle<'storage>) {
+    if !self.filter.matches(title) || self.exclusion.as_ref().is_some_and(|exclusion| exclusion(title)) {
+      return;
+    }
+
+    if self.cap.is_some_and(|cap| self.total >= cap) {
+      self.overflowed = true;
+      return;
+    }
+
+    self.total += 1;
+
+    match self.top {
+      Some(top) => {
+        if top == 0 {
+          return;
+        }
+
+        let ranked = Ranked {
+          title,
+          sort_by_year: self.sort_by_year,
+          sort_column: self.sort_column.clone(),
+          weighted_rating: self.weighted_rating,
+        };
+
+        if self.bounded.len() < top {
+          self.bounded.push(ranked);
+        } else if let Some(mut worst) = self.bounded.peek_mut() {
+          if ranked < *worst {
+            *worst = ranked;
+          }
+        }
+      }
+      None => self.results.push(title),
+    }
+  }
+
   pub fn extend(&mut self, iter: impl IntoIterator<Item = &'a ImdbTitle<'storage>>) {
-    self.results.extend(iter)
+    for title in iter {
+      self.push(title);
+
+      if self.overflowed {
+        break;
+      }
+    }
+  }
+
+  /// Narrow down the results to the ones matching `filter` and not matching `exclusion`,
+  /// without querying the databases again. The new exclusion replaces the previous one,
+  /// which the results already passed. Only the retained results are refined, so with `top`
+  /// the titles that did not make it into the best `top` are not considered.
+  pub fn refine(&self, filter: ImdbFilter, exclusion: Option<Exclusion>) -> Self {
+    let mut refined = Self {
+      results: Vec::new(),
+      bounded: BinaryHeap::new(),
+      total: 0,
+      sort_by_year: self.sort_by_year,
+      top: self.top,
+      filter,
+      exclusion,
+      cap: self.cap,
+      overflowed: false,
+      normalized: self.normalized,
+      columns: self.columns.clone(),
+      sort_column: self.sort_column.clone(),
+      weighted_rating: self.weighted_rating,
+    };
+
+    refined.extend(
+      self
+        .results
+        .iter()
+        .copied()
+        .chain(self.bounded.iter().map(|ranked| ranked.title)),
+    );
+    refined
   }
 
   pub fn top_sorted_results(&mut self) -> &[&'a ImdbTitle<'storage>] {
+    self.results.extend(self.bounded.drain().map(|ranked| ranked.title));
     self.sort_results();
-    &self.results[0..self.len()]
+    &self.results[0..self.len().min(self.results.len())]
   }
 
   fn sort_results(&mut self) {
-    if self.sort_by_year {
-      self.results.sort_unstable_by(|a, b| {
-        match b.start_year().cmp(&a.start_year()) {
-          Ordering::Equal => {}
-          ord => return ord,
-        }
+    let sort_by_year = self.sort_by_year;
+    let sort_column = self.sort_column.as_ref();
+    let weighted_rating = self.weighted_rating.as_ref();
+    self
+      .results
+      .sort_unstable_by(|a, b| compare_titles(a, b, sort_by_year, sort_column, weighted_rating));
+  }
+}
 
-        match b.rating().cmp(&a.rating()) {
-          Ordering::Equal => {}
-          ord => return ord,
-        }
+/// Results of one of several queries, tagged with the position of the query in the input so
+/// that groups can be displayed in input order regardless of the order they completed in.
+pub struct SearchGroup<'a, 'storage> {
+  pub index: usize,
+  pub search_terms: String,
+  pub movies: Option<SearchRes<'a, 'storage>>,
+  pub series: Option<SearchRes<'a, 'storage>>,
+}
 
-        b.primary_title().cmp(a.primary_title())
-      })
-    } else {
-      self.results.sort_unstable_by(|a, b| {
-        match b.rating().cmp(&a.rating()) {
-          Ordering::Equal => {}
-          ord => return ord,
-        }
+impl<'a, 'storage> SearchGroup<'a, 'storage> {
+  pub fn new(
+    index: usize,
+    search_terms: String,
+    movies: Option<SearchRes<'a, 'storage>>,
+    series: Option<SearchRes<'a, 'storage>>,
+  ) -> Self {
+    Self { index, search_terms, movies, series }
+  }
 
-        match b.start_year().cmp(&a.start_year()) {
-          Ordering::Equal => {}
-          ord => return ord,
-        }
+  pub fn total_len(&self) -> usize {
+    let movies = self.movies.as_ref().map_or(0, |movies| movies.total_len());
+    let series = self.series.as_ref().map_or(0, |series| series.total_len());
+    movies + series
+  }
 
-        b.primary_title().cmp(a.primary_title())
-      })
-    }
+  pub fn is_overflowed(&self) -> bool {
+    let movies = self.movies.as_ref().is_some_and(|movies| movies.is_overflowed());
+    let series = self.series.as_ref().is_some_and(|series| series.is_overflowed());
+    movies || series
   }
 }
@@ -0,0 +1,742 @@
+#![warn(clippy::all)]
+
+//! A small HTTP server that keeps the IMDB database in memory and answers JSON queries.
+//!
+//! Supported endpoints (all `GET`):
+//!
+//! * `/search?q=TERMS[&exact=true]`
+//! * `/title/{IMDB-ID}`
+//! * `/scan?dir=PATH[&kind=movies|series]`
+//! * `/random[?count=N&seed=SEED]`
+//! * `/metrics`
+//!
+//! `/search`, `/scan` and `/random` also accept `top=N`, `sort_by_year=true`, `released_since=YEAR`,
+//! `last_n_years=N`, `decade=DECADE`, `era=START-END`, comma-separated `genre=GENRE,...` and
+//! `column=COLUMN,...`, `status=running|ended`, `min_votes=N`, `max_votes=N`,
+//! `popularity=obscure|niche|popular|blockbuster`, `rank=average|bayesian`, `prior_mean=RATING`,
+//! `prior_votes=N`, `sort_by_column=COLUMN`, `sort_by_relevance=true`,
+//! `match_kind=overlapping|leftmost-first|leftmost-longest|word-boundary`,
+//! `scope=all|primary|original`, comma-separated `articles=LANGUAGE,...`, `stem=true` and
+//! `baseline=true` parameters.
+//!
+//! `/scan` only reads directories under the roots given with `--scan-root`, or any
+//! directory when none were given and the server only listens on a loopback address.
+//!
+//! `/title` includes how the rating of the title compares to the median of its genre and
+//! decade in a `baseline` field.
+//!
+//! `/metrics` exposes the number of requests and their latencies by endpoint, the age of the
+//! IMDB database and its number of titles in the Prometheus text format.
+//!
+//! With `--background-update`, the server answers from an outdated IMDB database while it is
+//! updated, then switches to the updated one for the requests that follow.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::commands::browse::imdb_random;
+use crate::commands::scan::{imdb_movies_dir, imdb_series_dir, Scan};
+use crate::commands::search::search_title;
+use crate::error::Error as CommandError;
+use crate::opts::{
+  parse_column, parse_decade, parse_era, parse_genre, parse_prior_mean, Articles, MatchKind, Popularity,
+  Rank, RenameOpts, ScanOpts, Scope, SearchOpts, Status, DEFAULT_MAX_RESULTS,
+};
+use crate::print::{JsonPrinter, OutputFormat, Printer};
+use crate::query::Query;
+use crate::sources::Source;
+
+use tvrank::imdb::{Imdb, ImdbQuery, ImdbTitle, ImdbTitleId, ImdbWeightedRating};
+
+use reqwest::Url;
+use serde::Serialize;
+use tracing::{debug, info, info_span, warn};
+
+/// Upper bound on the size of a request line and its headers.
+const MAX_REQUEST_HEAD_LEN: usize = 8 * 1024;
+
+/// Upper bound on the number of connections answered at the same time, each by its own
+/// thread. Connections beyond it are answered with a 503 right away.
+const MAX_CONNECTIONS: usize = 64;
+
+/// How long reading a request or writing a response may block, so that slow or idle
+/// clients do not hold on to their connection.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the server checks whether the IMDB database is outdated, when it updates it.
+const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Upper bounds, in seconds, of the buckets of the request latency histogram.
+const LATENCY_BUCKETS: [f64; 9] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0, 5.0];
+
+/// Updates the IMDB database and loads it, or returns `None` if another process is already
+/// updating it.
+pub type UpdateFn<'a> = dyn Fn() -> Result<Option<Imdb>, CommandError> + Sync + 'a;
+
+#[derive(Debug, thiserror::Error)]
+#[error("Server error")]
+pub enum Error {
+  #[error("IO error: {0}")]
+  Io(#[from] io::Error),
+  #[error("Malformed HTTP request: {0}")]
+  Http(#[from] httparse::Error),
+  #[error("Incomplete HTTP request")]
+  Incomplete,
+  #[error("HTTP request is larger than {MAX_REQUEST_HEAD_LEN} bytes")]
+  TooLarge,
+  #[error("Cannot use `{0}` as a scan root: {1}")]
+  ScanRoot(PathBuf, io::Error),
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+  error: &'a str,
+}
+
+struct Response {
+  status: u16,
+  reason: &'static str,
+  content_type: &'static str,
+  body: String,
+}
+
+impl Response {
+  fn ok(body: String) -> Self {
+    Self { status: 200, reason: "OK", content_type: "application/json", body }
+  }
+
+  fn metrics(body: String) -> Self {
+    Self { status: 200, reason: "OK", content_type: "text/plain; version=0.0.4", body }
+  }
+
+  fn error(status: u16, reason: &'static str, message: &str) -> Self {
+    let body =
+      serde_json::to_string_pretty(&ErrorBody { error: message }).unwrap_or_else(|_| String::from("{}"));
+    Self { status, reason, content_type: "application/json", body }
+  }
+
+  fn from_error(e: &CommandError) -> Self {
+    use crate::error::Error as E;
+
+    match e {
+      E::EmptyKeywords
+      | E::SearchString(_)
+      | E::Regex(_)
+      | E::ImdbTitleId(_)
+      | E::Walkdir(_)
+      | E::NotDir(_) => Self::error(400, "Bad Request", &e.to_string()),
+      _ => Self::error(500, "Internal Server Error", &e.to_string()),
+    }
+  }
+
+  fn write_to(&self, mut stream: impl Write) -> Result<(), Error> {
+    write!(
+      stream,
+      "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+      self.status,
+      self.reason,
+      self.content_type,
+      self.body.len(),
+      self.body
+    )?;
+    stream.flush()?;
+    Ok(())
+  }
+}
+
+/// Latencies of the requests to an endpoint.
+#[derive(Default)]
+struct Latencies {
+  /// Number of requests at or below each of [LATENCY_BUCKETS].
+  buckets: [u64; LATENCY_BUCKETS.len()],
+  count: u64,
+  sum: f64,
+}
+
+/// Counts and latencies of the requests answered by the server.
+#[derive(Default)]
+struct Metrics {
+  requests: Mutex<BTreeMap<(&'static str, u16), u64>>,
+  latencies: Mutex<BTreeMap<&'static str, Latencies>>,
+}
+
+impl Metrics {
+  /// Count a request that was answered.
+  ///
+  /// # Arguments
+  ///
+  /// * `endpoint` - The endpoint of the request.
+  /// * `status` - The status of the response.
+  /// * `latency` - How long it took to answer the request.
+  fn record(&self, endpoint: &'static str, status: u16, latency: Duration) {
+    *self
+      .requests
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .entry((endpoint, status))
+      .or_default() += 1;
+
+    let latency = latency.as_secs_f64();
+    let mut latencies = self.latencies.lock().unwrap_or_else(PoisonError::into_inner);
+    let latencies = latencies.entry(endpoint).or_default();
+    for (bucket, bound) in latencies.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+      if latency <= bound {
+        *bucket += 1;
+      }
+    }
+    latencies.count += 1;
+    latencies.sum += latency;
+  }
+
+  /// Render the metrics of the requests and of the IMDB database in the Prometheus text
+  /// format.
+  ///
+  /// # Arguments
+  ///
+  /// * `imdb` - The IMDB database that answers the requests.
+  fn render(&self, imdb: &Imdb) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::new();
+
+    let _ = writeln!(out, "# HELP tvrank_requests_total Requests answered by endpoint and status.");
+    let _ = writeln!(out, "# TYPE tvrank_requests_total counter");
+    for ((endpoint, status), count) in self.requests.lock().unwrap_or_else(PoisonError::into_inner).iter() {
+      let _ = writeln!(out, "tvrank_requests_total{{endpoint=\"{endpoint}\",status=\"{status}\"}} {count}");
+    }
+
+    let _ =
+      writeln!(out, "# HELP tvrank_request_duration_seconds Time taken to answer requests by endpoint.");
+    let _ = writeln!(out, "# TYPE tvrank_request_duration_seconds histogram");
+    for (endpoint, latencies) in self.latencies.lock().unwrap_or_else(PoisonError::into_inner).iter() {
+      for (bucket, bound) in latencies.buckets.iter().zip(LATENCY_BUCKETS) {
+        let _ = writeln!(
+          out,
+          "tvrank_request_duration_seconds_bucket{{endpoint=\"{endpoint}\",le=\"{bound}\"}} {bucket}"
+        );
+      }
+      let count = latencies.count;
+      let _ = writeln!(
+        out,
+        "tvrank_request_duration_seconds_bucket{{endpoint=\"{endpoint}\",le=\"+Inf\"}} {count}"
+      );
+      let _ =
+        writeln!(out, "tvrank_request_duration_seconds_sum{{endpoint=\"{endpoint}\"}} {}", latencies.sum);
+      let _ = writeln!(out, "tvrank_request_duration_seconds_count{{endpoint=\"{endpoint}\"}} {count}");
+    }
+
+    let age = SystemTime::now().duration_since(imdb.built_at()).unwrap_or_default();
+    let _ = writeln!(out, "# HELP tvrank_imdb_database_age_seconds Time since the IMDB database was built.");
+    let _ = writeln!(out, "# TYPE tvrank_imdb_database_age_seconds gauge");
+    let _ = writeln!(out, "tvrank_imdb_database_age_seconds {}", age.as_secs());
+
+    let _ = writeln!(out, "# HELP tvrank_imdb_titles Titles in the IMDB database by kind.");
+    let _ = writeln!(out, "# TYPE tvrank_imdb_titles gauge");
+    for (kind, query) in [("movies", ImdbQuery::Movies), ("series", ImdbQuery::Series)] {
+      let _ = writeln!(out, "tvrank_imdb_titles{{kind=\"{kind}\"}} {}", imdb.n_titles(query));
+    }
+
+    out
+  }
+}
+
+/// The endpoint a request target is counted under in the metrics, without the IMDB IDs of
+/// `/title` and with unknown endpoints grouped together.
+fn endpoint(target: &str) -> &'static str {
+  let path = target.split_once('?').map_or(target, |(path, _)| path);
+
+  match path {
+    "/search" => "/search",
+    "/scan" => "/scan",
+    "/random" => "/random",
+    "/metrics" => "/metrics",
+    _ if path.starts_with("/title/") => "/title",
+    _ => "other",
+  }
+}
+
+/// A connection counted against [MAX_CONNECTIONS] until it is dropped.
+struct ConnectionSlot<'a>(&'a AtomicUsize);
+
+impl<'a> ConnectionSlot<'a> {
+  /// Count a new connection, or return `None` if there are already [MAX_CONNECTIONS].
+  ///
+  /// # Arguments
+  ///
+  /// * `connections` - The number of connections being answered.
+  fn acquire(connections: &'a AtomicUsize) -> Option<Self> {
+    connections
+      .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+        (count < MAX_CONNECTIONS).then_some(count + 1)
+      })
+      .ok()
+      .map(|_| Self(connections))
+  }
+}
+
+impl Drop for ConnectionSlot<'_> {
+  fn drop(&mut self) {
+    self.0.fetch_sub(1, Ordering::AcqRel);
+  }
+}
+
+/// The directories `/scan` may read, canonicalized, or `None` if it may read any directory
+/// because no roots were given and the server only listens on a loopback address.
+///
+/// # Arguments
+///
+/// * `listen` - Address and port the server listens on.
+/// * `roots` - The directories given with `--scan-root`.
+fn scan_roots(listen: SocketAddr, roots: &[PathBuf]) -> Result<Option<Vec<PathBuf>>, Error> {
+  if roots.is_empty() {
+    if listen.ip().is_loopback() {
+      return Ok(None);
+    }
+    warn!(
+      "Disabling `/scan` since the server does not only listen on a loopback address and no \
+       scan roots were given"
+    );
+  }
+
+  let roots = roots
+    .iter()
+    .map(|root| fs::canonicalize(root).map_err(|e| Error::ScanRoot(root.clone(), e)));
+  roots.collect::<Result<_, _>>().map(Some)
+}
+
+/// Listen on the given address and answer queries until the process is terminated.
+///
+/// # Arguments
+///
+/// * `listen` - Address and port to listen on.
+/// * `imdb` - The IMDB service to query.
+/// * `imdb_url` - Base URL of IMDB title pages.
+/// * `scan_roots` - The directories `/scan` may read, see [scan_roots].
+/// * `update_fn` - Updates the database when it is outdated, if the server does so.
+pub fn serve(
+  listen: SocketAddr,
+  imdb: &Imdb,
+  imdb_url: &Url,
+  scan_roots: &[PathBuf],
+  update_fn: Option<&UpdateFn<'_>>,
+) -> Result<(), Error> {
+  let scan_roots = self::scan_roots(listen, scan_roots)?;
+  let listener = TcpListener::bind(listen)?;
+  eprintln!("Listening on http://{}", listener.local_addr()?);
+
+  let updated = RwLock::new(None);
+  let metrics = Metrics::default();
+  let connections = AtomicUsize::new(0);
+
+  thread::scope(|scope| {
+    if let Some(update_fn) = update_fn {
+      scope.spawn(|| update_in_background(imdb, &updated, update_fn));
+    }
+
+    for stream in listener.incoming() {
+      match stream {
+        Ok(stream) => {
+          let Some(slot) = ConnectionSlot::acquire(&connections) else {
+            warn!("Refusing a connection since {MAX_CONNECTIONS} are already being answered");
+            let response = Response::error(503, "Service Unavailable", "Too many connections");
+            if let Err(e) = stream.set_write_timeout(Some(CONNECTION_TIMEOUT)).map_err(Error::from) {
+              warn!("Error refusing connection: {e}");
+            } else if let Err(e) = response.write_to(&stream) {
+              warn!("Error refusing connection: {e}");
+            }
+            continue;
+          };
+
+          let updated = updated.read().unwrap_or_else(PoisonError::into_inner).clone();
+          let metrics = &metrics;
+          let scan_roots = scan_roots.as_deref();
+          scope.spawn(move || {
+            let _slot = slot;
+            let imdb = updated.as_deref().unwrap_or(imdb);
+            if let Err(e) = handle_connection(stream, imdb, imdb_url, scan_roots, metrics) {
+              warn!("Error handling request: {e}");
+            }
+          });
+        }
+        Err(e) => warn!("Error accepting connection: {e}"),
+      }
+    }
+  });
+
+  Ok(())
+}
+
+/// Update the IMDB database whenever it is outdated, and switch the requests that follow to
+/// the updated one.
+///
+/// A database that was switched from is freed once the last request still using it is
+/// answered.
+///
+/// # Arguments
+///
+/// * `imdb` - The database the server started with.
+/// * `updated` - The latest updated database, if any.
+/// * `update_fn` - Updates the database.
+fn update_in_background(imdb: &Imdb, updated: &RwLock<Option<Arc<Imdb>>>, update_fn: &UpdateFn<'_>) {
+  loop {
+    let outdated = match &*updated.read().unwrap_or_else(PoisonError::into_inner) {
+      Some(updated) => updated.is_outdated(),
+      None => imdb.is_outdated(),
+    };
+
+    if outdated {
+      info!("Updating the outdated IMDB database in the background");
+      match update_fn() {
+        Ok(Some(imdb)) => {
+          *updated.write().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(imdb));
+          info!("Switched to the updated IMDB database");
+        }
+        Ok(None) => debug!("IMDB database is already being updated by another process"),
+        Err(e) => warn!("Could not update the IMDB database: {e}"),
+      }
+    }
+
+    thread::sleep(UPDATE_CHECK_INTERVAL);
+  }
+}
+
+fn handle_connection(
+  stream: TcpStream,
+  imdb: &Imdb,
+  imdb_url: &Url,
+  scan_roots: Option<&[PathBuf]>,
+  metrics: &Metrics,
+) -> Result<(), Error> {
+  stream.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+  stream.set_write_timeout(Some(CONNECTION_TIMEOUT))?;
+
+  let mut reader = BufReader::new((&stream).take(MAX_REQUEST_HEAD_LEN as u64));
+  let mut head = Vec::new();
+
+  while !head.ends_with(b"\r\n\r\n") && !head.ends_with(b"\n\n") {
+    if reader.read_until(b'\n', &mut head)? == 0 {
+      if head.len() < MAX_REQUEST_HEAD_LEN {
+        return Err(Error::Incomplete);
+      }
+
+      Response::error(431, "Request Header Fields Too Large", "Request is too large").write_to(&stream)?;
+      return Err(Error::TooLarge);
+    }
+  }
+
+  let mut headers = [httparse::EMPTY_HEADER; 32];
+  let mut request = httparse::Request::new(&mut headers);
+
+  let response = match request.parse(&head)? {
+    httparse::Status::Complete(_) => match (request.method, request.path) {
+      (Some("GET"), Some(target)) => {
+        let _span = info_span!("request", target).entered();
+        debug!("GET {target}");
+        let start = Instant::now();
+        let response = route(target, imdb, imdb_url, scan_roots, metrics);
+        metrics.record(endpoint(target), response.status, start.elapsed());
+        response
+      }
+      _ => Response::error(405, "Method Not Allowed", "Only GET requests are supported"),
+    },
+    httparse::Status::Partial => return Err(Error::Incomplete),
+  };
+
+  response.write_to(&stream)
+}
+
+fn route(
+  target: &str,
+  imdb: &Imdb,
+  imdb_url: &Url,
+  scan_roots: Option<&[PathBuf]>,
+  metrics: &Metrics,
+) -> Response {
+  let url = match Url::parse("http://localhost").and_then(|base| base.join(target)) {
+    Ok(url) => url,
+    Err(e) => return Response::error(400, "Bad Request", &e.to_string()),
+  };
+
+  let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+
+  let result = match url.path() {
+    "/search" => search(&params, imdb, imdb_url),
+    "/scan" => scan(&params, imdb, imdb_url, scan_roots),
+    "/random" => random(&params, imdb, imdb_url),
+    "/metrics" => Ok(Response::metrics(metrics.render(imdb))),
+    path => match path.strip_prefix("/title/") {
+      Some(id) => title(id, imdb),
+      None => Ok(Response::error(404, "Not Found", &format!("Unknown endpoint `{path}`"))),
+    },
+  };
+
+  result.unwrap_or_else(|e| Response::from_error(&e))
+}
+
+fn flag(params: &HashMap<String, String>, name: &str) -> bool {
+  matches!(params.get(name).map(String::as_str), Some("true" | "1"))
+}
+
+fn number<T: FromStr<Err = ParseIntError>>(
+  params: &HashMap<String, String>,
+  name: &str,
+) -> Result<Option<T>, Response> {
+  match params.get(name).map(|value| value.parse::<T>()) {
+    Some(Ok(value)) => Ok(Some(value)),
+    Some(Err(e)) => Err(Response::error(400, "Bad Request", &format!("Invalid `{name}` parameter: {e}"))),
+    None => Ok(None),
+  }
+}
+
+fn search_opts(params: &HashMap<String, String>) -> Result<SearchOpts, Response> {
+  let genre = match params.get("genre") {
+    Some(genres) => genres
+      .split(',')
+      .map(parse_genre)
+      .collect::<Result<_, _>>()
+      .map_err(|e| Response::error(400, "Bad Request", &format!("Invalid `genre` parameter: {e}")))?,
+    None => Vec::new(),
+  };
+
+  let column = match params.get("column") {
+    Some(columns) => columns
+      .split(',')
+      .map(parse_column)
+      .collect::<Result<_, _>>()
+      .map_err(|e| Response::error(400, "Bad Request", &format!("Invalid `column` parameter: {e}")))?,
+    None => Vec::new(),
+  };
+
+  let sort_by_column = match params.get("sort_by_column") {
+    Some(column) => Some(parse_column(column).map_err(|e| {
+      Response::error(400, "Bad Request", &format!("Invalid `sort_by_column` parameter: {e}"))
+    })?),
+    None => None,
+  };
+
+  let decade = match params.get("decade") {
+    Some(decade) => Some(
+      parse_decade(decade)
+        .map_err(|e| Response::error(400, "Bad Request", &format!("Invalid `decade` parameter: {e}")))?,
+    ),
+    None => None,
+  };
+
+  let era = match params.get("era") {
+    Some(era) => Some(
+      parse_era(era)
+        .map_err(|e| Response::error(400, "Bad Request", &format!("Invalid `era` parameter: {e}")))?,
+    ),
+    None => None,
+  };
+
+  let popularity = match params.get("popularity") {
+    Some(popularity) => Some(
+      <Popularity as clap::ValueEnum>::from_str(popularity, true)
+        .map_err(|e| Response::error(400, "Bad Request", &format!("Invalid `popularity` parameter: {e}")))?,
+    ),
+    None => None,
+  };
+
+  let rank = match params.get("rank") {
+    Some(rank) => <Rank as clap::ValueEnum>::from_str(rank, true)
+      .map_err(|e| Response::error(400, "Bad Request", &format!("Invalid `rank` parameter: {e}")))?,
+    None => Rank::Average,
+  };
+
+  let prior_mean = match params.get("prior_mean") {
+    Some(mean) => Some(
+      parse_prior_mean(mean)
+        .map_err(|e| Response::error(400, "Bad Request", &format!("Invalid `prior_mean` parameter: {e}")))?,
+    ),
+    None => None,
+  };
+
+  let match_kind = match params.get("match_kind") {
+    Some(match_kind) => <MatchKind as clap::ValueEnum>::from_str(match_kind, true)
+      .map_err(|e| Response::error(400, "Bad Request", &format!("Invalid `match_kind` parameter: {e}")))?,
+    None => MatchKind::Overlapping,
+  };
+
+  let scope = match params.get("scope") {
+    Some(scope) => <Scope as clap::ValueEnum>::from_str(scope, true)
+      .map_err(|e| Response::error(400, "Bad Request", &format!("Invalid `scope` parameter: {e}")))?,
+    None => Scope::All,
+  };
+
+  let articles = match params.get("articles") {
+    Some(articles) => articles
+      .split(',')
+      .map(|language| <Articles as clap::ValueEnum>::from_str(language, true))
+      .collect::<Result<_, _>>()
+      .map_err(|e| Response::error(400, "Bad Request", &format!("Invalid `articles` parameter: {e}")))?,
+    None => Vec::new(),
+  };
+
+  let status = match params.get("status") {
+    Some(status) => Some(
+      <Status as clap::ValueEnum>::from_str(status, true)
+        .map_err(|e| Response::error(400, "Bad Request", &format!("Invalid `status` parameter: {e}")))?,
+    ),
+    None => None,
+  };
+
+  Ok(SearchOpts {
+    sort_by_year: flag(params, "sort_by_year"),
+    top: number(params, "top")?,
+    output: OutputFormat::Json,
+    match_kind,
+    scope,
+    articles,
+    stem: flag(params, "stem"),
+    released_since: number(params, "released_since")?,
+    last_n_years: number(params, "last_n_years")?,
+    decade,
+    era,
+    min_votes: number(params, "min_votes")?,
+    max_votes: number(params, "max_votes")?,
+    popularity,
+    genre,
+    status,
+    max_results: DEFAULT_MAX_RESULTS,
+    column,
+    sort_by_column,
+    sort_by_relevance: flag(params, "sort_by_relevance"),
+    rank,
+    prior_mean,
+    prior_votes: number(params, "prior_votes")?.unwrap_or(ImdbWeightedRating::DEFAULT_MIN_VOTES),
+    source: vec![Source::Imdb],
+    enrich_top: 0,
+    baseline: flag(params, "baseline"),
+    scores: None,
+    watched: false,
+    unwatched: false,
+  })
+}
+
+fn search(params: &HashMap<String, String>, imdb: &Imdb, imdb_url: &Url) -> Result<Response, CommandError> {
+  let Some(terms) = params.get("q") else {
+    return Ok(Response::error(400, "Bad Request", "Missing `q` parameter"));
+  };
+
+  let query = match search_opts(params) {
+    Ok(search_opts) => Query::new(search_opts, imdb),
+    Err(response) => return Ok(response),
+  };
+
+  let search = imdb.with_options(query.opts().search_options());
+  let (movies, series, search_terms) = search_title(terms, &search, &query, flag(params, "exact"))?;
+
+  let buffer = Rc::new(RefCell::new(String::new()));
+  JsonPrinter::with_buffer(buffer.clone()).print(
+    Some(movies),
+    Some(series),
+    imdb_url,
+    Some(&search_terms),
+  )?;
+
+  Ok(Response::ok(buffer.take()))
+}
+
+fn random(params: &HashMap<String, String>, imdb: &Imdb, imdb_url: &Url) -> Result<Response, CommandError> {
+  let query = match search_opts(params) {
+    Ok(search_opts) => Query::new(search_opts, imdb),
+    Err(response) => return Ok(response),
+  };
+
+  let (count, seed) = match (number(params, "count"), number(params, "seed")) {
+    (Ok(count), Ok(seed)) => (count.unwrap_or(1), seed),
+    (Err(response), _) | (_, Err(response)) => return Ok(response),
+  };
+
+  let buffer = Rc::new(RefCell::new(String::new()));
+  let printer = Box::new(JsonPrinter::with_buffer(buffer.clone()));
+  imdb_random(imdb, imdb_url, &query, count, seed, printer)?;
+
+  Ok(Response::ok(buffer.take()))
+}
+
+/// A title along with how it compares to its genre and decade.
+#[derive(Serialize)]
+struct TitleDetails<'a, 'storage> {
+  #[serde(flatten)]
+  title: &'a ImdbTitle<'storage>,
+  baseline: Option<String>,
+}
+
+fn title(id: &str, imdb: &Imdb) -> Result<Response, CommandError> {
+  let id = ImdbTitleId::try_from(id)?;
+
+  match imdb.by_id_any(&id) {
+    Some((title, _)) => {
+      let baseline = imdb.baselines().compare(title).map(|comparison| comparison.to_string());
+      Ok(Response::ok(serde_json::to_string_pretty(&TitleDetails { title, baseline })?))
+    }
+    None => Ok(Response::error(404, "Not Found", &format!("Unknown IMDB ID `{id}`"))),
+  }
+}
+
+fn scan(
+  params: &HashMap<String, String>,
+  imdb: &Imdb,
+  imdb_url: &Url,
+  scan_roots: Option<&[PathBuf]>,
+) -> Result<Response, CommandError> {
+  let Some(dir) = params.get("dir") else {
+    return Ok(Response::error(400, "Bad Request", "Missing `dir` parameter"));
+  };
+
+  if let Some(roots) = scan_roots {
+    let allowed = fs::canonicalize(dir).is_ok_and(|dir| roots.iter().any(|root| dir.starts_with(root)));
+    if !allowed {
+      return Ok(Response::error(403, "Forbidden", &format!("`{dir}` is not under a scan root")));
+    }
+  }
+
+  let query = match search_opts(params) {
+    Ok(search_opts) => Query::new(search_opts, imdb),
+    Err(response) => return Ok(response),
+  };
+
+  let scan = Scan::new(ScanOpts {
+    sizes: flag(params, "sizes"),
+    sort_by_size: flag(params, "sort_by_size"),
+    sort_by_episodes: false,
+    write_nfo: false,
+    overwrite_nfo: false,
+    report: None,
+  });
+
+  let search = imdb.with_options(query.opts().search_options());
+  let buffer = Rc::new(RefCell::new(String::new()));
+  let printer = Box::new(JsonPrinter::with_buffer(buffer.clone()));
+
+  match params.get("kind").map(String::as_str) {
+    None | Some("movies") => {
+      imdb_movies_dir(Path::new(dir), &search, imdb_url, &query, &scan, &RenameOpts::default(), printer)?
+    }
+    Some("series") => imdb_series_dir(Path::new(dir), &search, imdb_url, &query, &scan, printer)?,
+    Some(kind) => {
+      return Ok(Response::error(400, "Bad Request", &format!("Invalid `kind` parameter `{kind}`")));
+    }
+  };
+
+  let body = buffer.take();
+
+  if body.is_empty() {
+    Ok(Response::error(404, "Not Found", "None of the directories matched any titles"))
+  } else {
+    Ok(Response::ok(body))
+  }
+}
@@ -0,0 +1,145 @@
+#![warn(clippy::all)]
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use tvrank::enrich::{Enricher, Enrichment, Provider};
+use tvrank::imdb::{ImdbColumn, ImdbColumnValue, ImdbTitle};
+
+use tracing::warn;
+
+/// Sources of the data displayed with results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Source {
+  /// The IMDB dataset, which is always used
+  Imdb,
+  /// The Movie Database, for plots, posters and popularity
+  Tmdb,
+  /// The Open Movie Database, for plots, posters and Metacritic scores
+  Omdb,
+}
+
+impl Source {
+  /// The enrichment provider of the source, if it is not IMDB.
+  pub fn provider(self) -> Option<Provider> {
+    match self {
+      Source::Imdb => None,
+      Source::Tmdb => Some(Provider::Tmdb),
+      Source::Omdb => Some(Provider::Omdb),
+    }
+  }
+}
+
+struct Titles {
+  /// Enrichments of titles by their ID, in the order of the providers.
+  enrichments: HashMap<String, Vec<Enrichment>>,
+  /// How many titles data was fetched for, including cached data.
+  fetched: usize,
+}
+
+/// Data about titles from other providers than IMDB, fetched the first time a title is
+/// displayed and cached on disk.
+///
+/// Since results are displayed best first, only the first `limit` titles are fetched, the
+/// others only use data that is already cached. Clones share the same data, so titles are
+/// only fetched once even if several columns display them.
+#[derive(Clone)]
+pub struct EnrichmentSource {
+  enricher: Arc<Mutex<Enricher>>,
+  providers: Vec<Provider>,
+  limit: usize,
+  titles: Arc<Mutex<Titles>>,
+}
+
+impl fmt::Debug for EnrichmentSource {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("EnrichmentSource")
+      .field("providers", &self.providers)
+      .field("limit", &self.limit)
+      .finish_non_exhaustive()
+  }
+}
+
+impl EnrichmentSource {
+  pub fn new(enricher: Enricher, providers: Vec<Provider>, limit: usize) -> Self {
+    let titles = Titles { enrichments: HashMap::new(), fetched: 0 };
+    Self { enricher: Arc::new(Mutex::new(enricher)), providers, limit, titles: Arc::new(Mutex::new(titles)) }
+  }
+
+  fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Data of a title from each provider that knows it, in the order of the providers.
+  fn title(&self, title: &ImdbTitle) -> Vec<Enrichment> {
+    let id = title.title_id();
+    let mut titles = Self::lock(&self.titles);
+
+    if let Some(enrichments) = titles.enrichments.get(&id.to_string()) {
+      return enrichments.clone();
+    }
+
+    let fetch = titles.fetched < self.limit;
+    if fetch {
+      titles.fetched += 1;
+    }
+
+    let mut enricher = Self::lock(&self.enricher);
+    let enrichments: Vec<_> = self
+      .providers
+      .iter()
+      .filter_map(|&provider| {
+        let lookup = if fetch {
+          enricher.enrich(provider, id).map(Some)
+        } else {
+          enricher.cached(provider, id)
+        };
+
+        match lookup {
+          Ok(lookup) => lookup?.enrichment().cloned(),
+          Err(e) => {
+            warn!("Could not fetch {provider} data for `{}` ({id}): {e}", title.primary_title());
+            None
+          }
+        }
+      })
+      .collect();
+
+    titles.enrichments.insert(id.to_string(), enrichments.clone());
+    enrichments
+  }
+
+  /// The first value of a field of the data of a title from the providers.
+  fn first<T>(&self, title: &ImdbTitle, field: impl Fn(&Enrichment) -> Option<T>) -> Option<T> {
+    self.title(title).iter().find_map(field)
+  }
+
+  fn column(
+    &self,
+    name: &str,
+    value: impl Fn(&Enrichment) -> Option<ImdbColumnValue> + Send + Sync + 'static,
+  ) -> ImdbColumn {
+    let source = self.clone();
+    ImdbColumn::new(name, move |title| source.first(title, &value))
+  }
+
+  /// Columns with the data of titles from the providers.
+  pub fn columns(&self) -> Vec<ImdbColumn> {
+    let mut columns = Vec::new();
+
+    if self.providers.contains(&Provider::Tmdb) {
+      columns.push(self.column("Popularity", |e| e.popularity().map(ImdbColumnValue::Float)));
+    }
+
+    if self.providers.contains(&Provider::Omdb) {
+      columns.push(
+        self.column("Metacritic", |e| e.metacritic().map(|score| ImdbColumnValue::Integer(i64::from(score)))),
+      );
+    }
+
+    columns.push(self.column("Plot", |e| e.plot().map(|plot| ImdbColumnValue::Text(plot.to_owned()))));
+    columns.push(self.column("Poster", |e| e.poster_url().map(|url| ImdbColumnValue::Text(url.to_owned()))));
+    columns
+  }
+}
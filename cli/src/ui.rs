@@ -1,6 +1,20 @@
 #![warn(clippy::all)]
 
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
 use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+
+/// How progress of long operations is reported on standard error.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum ProgressFormat {
+  /// Interactive progress bars
+  Bar,
+  /// Newline-delimited JSON progress events
+  Json,
+}
 
 pub fn create_progress_bar(msg: String, len: u64) -> ProgressBar {
   let bar = ProgressBar::new(len).with_style(ProgressStyle::default_bar().template(
@@ -25,6 +39,174 @@ pub fn create_progress_spinner(msg: String) -> ProgressBar {
   bar
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ProgressEventKind {
+  Progress,
+  Finish,
+}
+
+#[derive(Serialize)]
+struct ProgressEvent<'a> {
+  event: ProgressEventKind,
+  phase: &'a str,
+  bytes: u64,
+  total: Option<u64>,
+  elapsed_secs: f64,
+  bytes_per_sec: f64,
+  eta_secs: Option<f64>,
+}
+
+/// Reports progress as newline-delimited JSON events on standard error.
+pub struct JsonProgress {
+  phase: &'static str,
+  total: Option<u64>,
+  bytes: u64,
+  start: Instant,
+  last_event: Option<Instant>,
+}
+
+impl JsonProgress {
+  /// Events are emitted at most this often, apart from the final one.
+  const INTERVAL: Duration = Duration::from_millis(500);
+
+  pub fn new(phase: &'static str, total: Option<u64>) -> Self {
+    Self { phase, total, bytes: 0, start: Instant::now(), last_event: None }
+  }
+
+  pub fn inc(&mut self, delta: u64) {
+    self.bytes += delta;
+
+    let now = Instant::now();
+    if self
+      .last_event
+      .is_none_or(|last_event| now.duration_since(last_event) >= Self::INTERVAL)
+    {
+      self.last_event = Some(now);
+      self.emit(ProgressEventKind::Progress);
+    }
+  }
+
+  pub fn finish(&self) {
+    self.emit(ProgressEventKind::Finish);
+  }
+
+  fn emit(&self, event: ProgressEventKind) {
+    let elapsed_secs = self.start.elapsed().as_secs_f64();
+    let bytes_per_sec = if elapsed_secs > 0.0 {
+      self.bytes as f64 / elapsed_secs
+    } else {
+      0.0
+    };
+
+    let eta_secs = match self.total {
+      Some(total) if bytes_per_sec > 0.0 => Some(total.saturating_sub(self.bytes) as f64 / bytes_per_sec),
+      _ => None,
+    };
+
+    let event = ProgressEvent {
+      event,
+      phase: self.phase,
+      bytes: self.bytes,
+      total: self.total,
+      elapsed_secs,
+      bytes_per_sec,
+      eta_secs,
+    };
+
+    if let Ok(line) = serde_json::to_string(&event) {
+      let _ = writeln!(io::stderr().lock(), "{line}");
+    }
+  }
+}
+
+/// Progress reporting in one of the supported formats.
+pub enum Progress {
+  Bar(ProgressBar),
+  Json(JsonProgress),
+}
+
+impl Progress {
+  /// Create a progress reporter for a phase of work.
+  ///
+  /// # Arguments
+  ///
+  /// * `format` - How progress is reported.
+  /// * `phase` - Machine-readable name of the phase, used in JSON events.
+  /// * `msg` - Human-readable description of the phase, used in progress bars.
+  /// * `len` - Total amount of work, if known.
+  pub fn new(format: ProgressFormat, phase: &'static str, msg: String, len: Option<u64>) -> Self {
+    match format {
+      ProgressFormat::Bar => Self::Bar(match len {
+        Some(len) => create_progress_bar(msg, len),
+        None => create_progress_spinner(msg),
+      }),
+      ProgressFormat::Json => Self::Json(JsonProgress::new(phase, len)),
+    }
+  }
+
+  pub fn inc(&mut self, delta: u64) {
+    match self {
+      Self::Bar(bar) => bar.inc(delta),
+      Self::Json(json) => json.inc(delta),
+    }
+  }
+
+  pub fn finish(&self) {
+    match self {
+      Self::Bar(bar) => bar.finish_and_clear(),
+      Self::Json(json) => json.finish(),
+    }
+  }
+}
+
+/// Progress reporting of a phase of work that starts once the phase reports its progress
+/// for the first time, as it does through the progress functions of the library.
+pub struct PhaseProgress {
+  format: ProgressFormat,
+  phase: &'static str,
+  msg: String,
+  progress: RefCell<Option<Progress>>,
+}
+
+impl PhaseProgress {
+  /// Create a progress reporter for a phase of work that may not happen.
+  ///
+  /// # Arguments
+  ///
+  /// * `format` - How progress is reported.
+  /// * `phase` - Machine-readable name of the phase, used in JSON events.
+  /// * `msg` - Human-readable description of the phase, used in progress bars.
+  pub fn new(format: ProgressFormat, phase: &'static str, msg: &str) -> Self {
+    Self { format, phase, msg: msg.to_string(), progress: RefCell::new(None) }
+  }
+
+  /// Report the progress of the phase, starting it if needed. A new total amount of work
+  /// starts the phase again, as when the next of several downloads starts.
+  ///
+  /// # Arguments
+  ///
+  /// * `len` - Total amount of work, if known, when the phase starts.
+  /// * `delta` - Amount of work done since the last report.
+  pub fn report(&self, len: Option<u64>, delta: u64) {
+    if len.is_some() {
+      self.finish();
+    }
+
+    let mut progress = self.progress.borrow_mut();
+    progress
+      .get_or_insert_with(|| Progress::new(self.format, self.phase, self.msg.clone(), len))
+      .inc(delta);
+  }
+
+  /// Finish the phase if it started, so that it can start again with the next report.
+  pub fn finish(&self) {
+    if let Some(progress) = self.progress.borrow_mut().take() {
+      progress.finish();
+    }
+  }
+}
+
 fn leak_string(s: String) -> &'static str {
   Box::leak(s.into_boxed_str())
 }
@@ -0,0 +1,41 @@
+#![warn(clippy::all)]
+
+//! Updating the internal databases in the background, while queries are answered from the
+//! outdated ones.
+
+use std::env;
+use std::io;
+use std::process::{Command, Stdio};
+
+use crate::opts::GeneralOpts;
+
+use clap::ValueEnum;
+use tracing::debug;
+
+/// Start `db update` in a detached process, which keeps running once this one exits.
+///
+/// # Arguments
+///
+/// * `general_opts` - Options of the internal databases, passed on to the process.
+pub fn spawn_update_process(general_opts: &GeneralOpts) -> io::Result<()> {
+  let mut command = Command::new(env::current_exe()?);
+  command.args(["db", "update"]);
+
+  if let Some(value) = general_opts.cache_compression.and_then(|c| c.to_possible_value()) {
+    command.args(["--cache-compression", value.get_name()]);
+  }
+  if general_opts.name_index {
+    command.arg("--name-index");
+  }
+  if let Some(profile) = &general_opts.profile {
+    command.args(["--profile", profile]);
+  }
+
+  let child = command
+    .stdin(Stdio::null())
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .spawn()?;
+  debug!("Updating internal databases in process {}", child.id());
+  Ok(())
+}
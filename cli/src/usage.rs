@@ -0,0 +1,157 @@
+#![warn(clippy::all)]
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use tvrank::imdb::{ImdbColumn, ImdbColumnValue, ImdbGenre, ImdbGenres, ImdbTitle};
+
+use walkdir::WalkDir;
+
+/// Identifies a file on disk, so that a file with several hard links is only counted once.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum FileId {
+  #[cfg(unix)]
+  Inode(u64, u64),
+  #[cfg(not(unix))]
+  Path(std::path::PathBuf),
+}
+
+/// Sizes of files by their identity.
+type Files = HashMap<FileId, u64>;
+
+fn files_size(files: &Files) -> u64 {
+  files.values().sum()
+}
+
+/// Sizes of all the files under a directory.
+fn dir_files(dir: &Path) -> Result<Files, walkdir::Error> {
+  let mut files = Files::new();
+
+  for entry in WalkDir::new(dir) {
+    let entry = entry?;
+
+    if entry.file_type().is_file() {
+      let metadata = entry.metadata()?;
+
+      #[cfg(unix)]
+      let id = {
+        use std::os::unix::fs::MetadataExt;
+        FileId::Inode(metadata.dev(), metadata.ino())
+      };
+
+      #[cfg(not(unix))]
+      let id = FileId::Path(entry.path().to_owned());
+
+      files.insert(id, metadata.len());
+    }
+  }
+
+  Ok(files)
+}
+
+/// Disk usage of the files under a directory, counting hard links once.
+pub fn dir_size(dir: &Path) -> Result<u64, walkdir::Error> {
+  dir_files(dir).map(|files| files_size(&files))
+}
+
+struct TitleUsage {
+  genres: ImdbGenres,
+  files: Files,
+}
+
+/// How many titles there are and how much disk space their files use.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+  pub titles: usize,
+  pub bytes: u64,
+}
+
+/// Disk usage per genre, largest first, and of all titles.
+#[derive(Debug)]
+pub struct UsageSummary {
+  pub genres: Vec<(ImdbGenre, Usage)>,
+  pub total: Usage,
+}
+
+/// Disk usage of the directories of scanned titles, counting files with several hard links
+/// once per title, genre and in the total.
+///
+/// Clones share the same titles, so the column of a usage also covers titles that were
+/// added after the column was created.
+#[derive(Clone, Default)]
+pub struct StorageUsage {
+  titles: Arc<Mutex<HashMap<String, TitleUsage>>>,
+}
+
+impl StorageUsage {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  fn titles(&self) -> MutexGuard<'_, HashMap<String, TitleUsage>> {
+    self.titles.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Count the files under `dir` as belonging to each of the given titles.
+  pub fn add<'a, 'storage: 'a>(
+    &self,
+    dir: &Path,
+    titles: impl IntoIterator<Item = &'a ImdbTitle<'storage>>,
+  ) -> Result<(), walkdir::Error> {
+    let files = dir_files(dir)?;
+    let mut usage = self.titles();
+
+    for title in titles {
+      usage
+        .entry(title.title_id().to_string())
+        .or_insert_with(|| TitleUsage { genres: title.genres(), files: Files::new() })
+        .files
+        .extend(files.iter().map(|(id, &size)| (id.clone(), size)));
+    }
+
+    Ok(())
+  }
+
+  /// Disk usage of the files of a title, if it was scanned.
+  pub fn size(&self, title: &ImdbTitle) -> Option<u64> {
+    self
+      .titles()
+      .get(&title.title_id().to_string())
+      .map(|usage| files_size(&usage.files))
+  }
+
+  /// Column with the disk usage of titles.
+  pub fn column(&self) -> ImdbColumn {
+    let usage = self.clone();
+    ImdbColumn::new("Size", move |title| usage.size(title).map(ImdbColumnValue::Bytes))
+  }
+
+  pub fn summary(&self) -> UsageSummary {
+    let titles = self.titles();
+    let mut genres: HashMap<ImdbGenre, (usize, Files)> = HashMap::new();
+    let mut total = Files::new();
+
+    for usage in titles.values() {
+      for genre in usage.genres.iter() {
+        let (count, files) = genres.entry(genre).or_default();
+        *count += 1;
+        files.extend(usage.files.iter().map(|(id, &size)| (id.clone(), size)));
+      }
+
+      total.extend(usage.files.iter().map(|(id, &size)| (id.clone(), size)));
+    }
+
+    let mut genres: Vec<_> = genres
+      .into_iter()
+      .map(|(genre, (titles, files))| (genre, Usage { titles, bytes: files_size(&files) }))
+      .collect();
+    genres.sort_by(|(a_genre, a), (b_genre, b)| {
+      b.bytes
+        .cmp(&a.bytes)
+        .then_with(|| a_genre.to_string().cmp(&b_genre.to_string()))
+    });
+
+    UsageSummary { genres, total: Usage { titles: titles.len(), bytes: files_size(&total) } }
+  }
+}
@@ -0,0 +1,56 @@
+#![warn(clippy::all)]
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use tvrank::imdb::{Imdb, ImdbCacheCompression};
+
+const BASICS: &str = "\
+tconst\ttitleType\tprimaryTitle\toriginalTitle\tisAdult\tstartYear\tendYear\truntimeMinutes\tgenres
+tt0000001\tshort\tCarmencita\tCarmencita\t0\t1894\t\\N\t1\tDocumentary,Short
+tt0000010\tmovie\tLeaving the Factory\tLa sortie de l'usine Lumière à Lyon\t0\t1895\t\\N\t1\tDocumentary
+";
+
+const RATINGS: &str = "\
+tconst\taverageRating\tnumVotes
+tt0000001\t5.7\t1845
+tt0000010\t6.9\t6636
+";
+
+/// Run tvrank with its databases and state under `home`, built from small dumps.
+fn tvrank(home: &Path, args: &[&str]) -> Option<i32> {
+  let cache_dir = home.join("cache").join("tvrank");
+  if !cache_dir.exists() {
+    fs::create_dir_all(&cache_dir).unwrap();
+    let basics = home.join("title.basics.tsv");
+    let ratings = home.join("title.ratings.tsv");
+    fs::write(&basics, BASICS).unwrap();
+    fs::write(&ratings, RATINGS).unwrap();
+    Imdb::build_from_files(&basics, &ratings, &cache_dir, |_, _| {}, ImdbCacheCompression::None).unwrap();
+  }
+
+  Command::new(env!("CARGO_BIN_EXE_tvrank"))
+    .args(args)
+    .env("HOME", home)
+    .env("XDG_CACHE_HOME", home.join("cache"))
+    .env("XDG_STATE_HOME", home.join("state"))
+    .env_remove("TVRANK_PROFILE")
+    .output()
+    .unwrap()
+    .status
+    .code()
+}
+
+#[test]
+fn exit_codes() {
+  let home = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+  let home = home.path();
+
+  assert_eq!(tvrank(home, &["search", "-q", "Leaving the Factory (1895)"]), Some(0));
+  assert_eq!(tvrank(home, &["search", "-q", "Leaving the Factory (1999)"]), Some(2));
+  assert_eq!(tvrank(home, &["search", "-q", "--bogus", "Leaving the Factory (1895)"]), Some(1));
+  assert_eq!(tvrank(home, &["search"]), Some(1));
+  assert_eq!(tvrank(home, &["--help"]), Some(0));
+  assert_eq!(tvrank(home, &["--version"]), Some(0));
+}
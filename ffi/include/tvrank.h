@@ -0,0 +1,185 @@
+#ifndef TVRANK_H
+#define TVRANK_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Ownership of strings: strings returned as `const char *` are borrowed from the object they
+ * were read from (e.g. a title, until its result is freed), while strings returned as
+ * `char *` are owned by the caller and have to be freed with `tvrank_string_free`.
+ */
+
+/*
+ * Status returned by the functions of the C API. Failing calls also record a message for
+ * `tvrank_last_error_message`.
+ */
+typedef enum TvrankStatus {
+  /* The call succeeded. */
+  TVRANK_STATUS_OK = 0,
+  /* An argument is NULL, not valid UTF-8, or otherwise invalid. */
+  TVRANK_STATUS_INVALID_ARGUMENT = 1,
+  /* The IMDB dumps could not be downloaded. */
+  TVRANK_STATUS_NETWORK = 2,
+  /* The databases could not be read or written, or are corrupted. */
+  TVRANK_STATUS_CACHE = 3,
+  /* The IMDB dumps could not be parsed. */
+  TVRANK_STATUS_PARSE = 4,
+  /* A file could not be read or written. */
+  TVRANK_STATUS_IO = 5,
+  /* The call panicked. */
+  TVRANK_STATUS_PANIC = 6,
+  /* Another process has been updating the databases for too long. */
+  TVRANK_STATUS_UPDATE_IN_PROGRESS = 7,
+} TvrankStatus;
+
+/* The kind of titles to search for. */
+typedef enum TvrankQuery {
+  /* Search the movies database. */
+  TVRANK_QUERY_MOVIES = 0,
+  /* Search the series database. */
+  TVRANK_QUERY_SERIES = 1,
+} TvrankQuery;
+
+/* Opaque handle to the loaded databases. */
+typedef struct TvrankService TvrankService;
+
+/* Opaque handle to the titles found by a search. */
+typedef struct TvrankResult TvrankResult;
+
+/* A title of a search result, owned by the result. */
+typedef struct TvrankTitle TvrankTitle;
+
+/* Callback reporting how many bytes of the dumps were read so far, out of their total size. */
+typedef void (*TvrankProgressCallback)(uint64_t bytes_read, uint64_t total_bytes);
+
+/*
+ * The message of the last failed call on the calling thread, or NULL if no call failed yet.
+ * The message is only replaced by later failing calls, and is valid until the next failing
+ * call on the same thread.
+ */
+const char *tvrank_last_error_message(void);
+
+/*
+ * Build the binary databases in `out_dir` from IMDB dumps that were already downloaded.
+ *
+ * The dumps can either be gzip-compressed, as IMDB publishes them, if their names end with
+ * `.gz`, or uncompressed. `out_dir` has to exist. `progress_cb` can be NULL.
+ */
+TvrankStatus tvrank_db_build_from_files(const char *basics_path,
+                                        const char *ratings_path,
+                                        const char *out_dir,
+                                        TvrankProgressCallback progress_cb);
+
+/*
+ * Load the databases from `cache_dir` into a new service, which has to be freed with
+ * `tvrank_service_free`. The databases are fetched and built first if they are missing or
+ * older than a month. If another process is already rebuilding them, the call waits for it
+ * for up to an hour before failing with `TVRANK_STATUS_UPDATE_IN_PROGRESS`. `*out` is set
+ * to NULL on failure.
+ */
+TvrankStatus tvrank_service_new(const char *cache_dir, TvrankService **out);
+
+/* Free a service. Results of its searches stay valid. `service` can be NULL. */
+void tvrank_service_free(TvrankService *service);
+
+/*
+ * Search titles by their exact name. `year` is the release year of the titles, or 0 for any
+ * year. `query` is one of the values of `TvrankQuery`, anything else is an invalid argument.
+ * The result has to be freed with `tvrank_result_free`.
+ */
+TvrankStatus tvrank_search_by_title(const TvrankService *service,
+                                    const char *title,
+                                    uint16_t year,
+                                    uint32_t query,
+                                    TvrankResult **out);
+
+/*
+ * Search titles by keywords separated by whitespace. `year` is the release year of the
+ * titles, or 0 for any year. `query` is one of the values of `TvrankQuery`, anything else is
+ * an invalid argument. The result has to be freed with `tvrank_result_free`.
+ */
+TvrankStatus tvrank_search_by_keywords(const TvrankService *service,
+                                       const char *keywords,
+                                       uint16_t year,
+                                       uint32_t query,
+                                       TvrankResult **out);
+
+/*
+ * Look up a title by its IMDB ID (e.g. "tt0133093") in both the movies and series databases.
+ * The result is empty if there is no such title, and has to be freed with
+ * `tvrank_result_free`.
+ */
+TvrankStatus tvrank_title_by_id(const TvrankService *service,
+                                const char *id,
+                                TvrankResult **out);
+
+/* The number of titles of a result, or 0 if `result` is NULL. */
+size_t tvrank_result_count(const TvrankResult *result);
+
+/*
+ * The title at `index` of a result, or NULL if `index` is out of bounds. The title is only
+ * valid until the result is freed.
+ */
+const TvrankTitle *tvrank_result_title_at(const TvrankResult *result, size_t index);
+
+/* Free a result. `result` can be NULL. */
+void tvrank_result_free(TvrankResult *result);
+
+/*
+ * The accessors of titles return NULL, 0 or -1 when `title` is NULL, e.g. when it comes from
+ * `tvrank_result_title_at` with an index out of bounds, and record a message for
+ * `tvrank_last_error_message`.
+ */
+
+/* The IMDB ID of a title, valid until the result is freed. */
+const char *tvrank_title_id_str(const TvrankTitle *title);
+
+/*
+ * Whether a title is a movie or a series, as a value of `TvrankQuery`, or -1 if `title` is
+ * NULL.
+ */
+int32_t tvrank_title_kind(const TvrankTitle *title);
+
+/* The primary name of a title, valid until the result is freed. */
+const char *tvrank_title_primary_title(const TvrankTitle *title);
+
+/*
+ * The original name of a title, or NULL if it is the same as the primary name. The name is
+ * valid until the result is freed.
+ */
+const char *tvrank_title_original_title(const TvrankTitle *title);
+
+/* The release year of a title, or 0 if it is unknown. */
+uint16_t tvrank_title_year(const TvrankTitle *title);
+
+/* The runtime of a title in minutes, or 0 if it is unknown. */
+uint32_t tvrank_title_runtime_minutes(const TvrankTitle *title);
+
+/* The rating of a title out of 100, or -1 if it is not rated. */
+int32_t tvrank_title_rating(const TvrankTitle *title);
+
+/* The number of votes of a title, or 0 if it is not rated. */
+uint32_t tvrank_title_votes(const TvrankTitle *title);
+
+/*
+ * The type of a title, e.g. "Movie" or "TV Mini-Series", to be freed with
+ * `tvrank_string_free`.
+ */
+char *tvrank_title_type(const TvrankTitle *title);
+
+/* The genres of a title, separated by commas, to be freed with `tvrank_string_free`. */
+char *tvrank_title_genres(const TvrankTitle *title);
+
+/* Free a string returned as `char *`. `text` can be NULL. */
+void tvrank_string_free(char *text);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TVRANK_H */
@@ -0,0 +1,934 @@
+#![warn(clippy::all)]
+#![warn(missing_docs)]
+
+//! C API of TVrank, for applications that embed it.
+//!
+//! The declarations of the exported functions are in `include/tvrank.h`.
+//!
+//! Strings returned as `*const c_char` are borrowed from the object they were read from and
+//! stay valid as long as that object, while strings returned as `*mut c_char` are owned by
+//! the caller and have to be freed with [tvrank_string_free].
+//!
+//! Failing calls return a [TvrankStatus] describing the kind of the failure, and record a
+//! message for [tvrank_last_error_message]. The accessors of titles, which cannot return a
+//! status, return null, 0 or -1 when given a null title, e.g. from [tvrank_result_title_at]
+//! with an index out of bounds, and record a message as well.
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::ptr;
+
+use tvrank::imdb::{
+  Imdb, ImdbCacheCompression, ImdbError, ImdbErrorKind, ImdbGenres, ImdbQuery, ImdbTitle, ImdbTitleId,
+  ImdbTitleType,
+};
+use tvrank::utils::search::SearchString;
+
+/// Status returned by the functions of the C API.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TvrankStatus {
+  /// The call succeeded.
+  Ok = 0,
+  /// An argument is null, not valid UTF-8, or otherwise invalid.
+  InvalidArgument = 1,
+  /// The IMDB dumps could not be downloaded.
+  Network = 2,
+  /// The databases could not be read or written, or are corrupted.
+  Cache = 3,
+  /// The IMDB dumps could not be parsed.
+  Parse = 4,
+  /// A file could not be read or written.
+  Io = 5,
+  /// The call panicked.
+  Panic = 6,
+  /// Another process has been updating the databases for longer than the call waits for it.
+  UpdateInProgress = 7,
+}
+
+impl From<&ImdbError> for TvrankStatus {
+  fn from(e: &ImdbError) -> Self {
+    match e.kind() {
+      ImdbErrorKind::Network => TvrankStatus::Network,
+      ImdbErrorKind::Cache => TvrankStatus::Cache,
+      ImdbErrorKind::Parse => TvrankStatus::Parse,
+      // The C API does not cancel operations.
+      ImdbErrorKind::Io | ImdbErrorKind::Cancelled => TvrankStatus::Io,
+      ImdbErrorKind::InvalidInput => TvrankStatus::InvalidArgument,
+      ImdbErrorKind::UpdateInProgress => TvrankStatus::UpdateInProgress,
+    }
+  }
+}
+
+thread_local! {
+  /// The message of the last failed call on this thread.
+  static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Record `message` as the last error of this thread.
+///
+/// # Arguments
+///
+/// * `status` - The status returned by the failed call.
+/// * `message` - Description of the failure.
+fn fail(status: TvrankStatus, message: impl Into<String>) -> TvrankStatus {
+  let message = CString::new(message.into()).unwrap_or_default();
+  LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+  status
+}
+
+/// Describe that the argument `name` is null or not valid UTF-8.
+fn invalid_argument_message(name: &str) -> String {
+  format!("`{name}` is null or not valid UTF-8")
+}
+
+/// Record that the argument `name` is null or not valid UTF-8.
+fn invalid_argument(name: &str) -> TvrankStatus {
+  fail(TvrankStatus::InvalidArgument, invalid_argument_message(name))
+}
+
+/// Record a panic caught at the boundary of the C API.
+fn panicked(payload: Box<dyn Any + Send>) -> TvrankStatus {
+  let message = if let Some(message) = payload.downcast_ref::<&str>() {
+    message
+  } else if let Some(message) = payload.downcast_ref::<String>() {
+    message.as_str()
+  } else {
+    "unknown cause"
+  };
+
+  fail(TvrankStatus::Panic, format!("Panicked: {message}"))
+}
+
+/// The kind of titles to search for.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TvrankQuery {
+  /// Search the movies database.
+  Movies = 0,
+  /// Search the series database.
+  Series = 1,
+}
+
+impl From<TvrankQuery> for ImdbQuery {
+  fn from(query: TvrankQuery) -> Self {
+    match query {
+      TvrankQuery::Movies => ImdbQuery::Movies,
+      TvrankQuery::Series => ImdbQuery::Series,
+    }
+  }
+}
+
+impl TryFrom<u32> for TvrankQuery {
+  type Error = String;
+
+  fn try_from(query: u32) -> Result<Self, Self::Error> {
+    match query {
+      0 => Ok(TvrankQuery::Movies),
+      1 => Ok(TvrankQuery::Series),
+      _ => Err(format!("Invalid query `{query}`")),
+    }
+  }
+}
+
+impl From<ImdbQuery> for TvrankQuery {
+  fn from(query: ImdbQuery) -> Self {
+    match query {
+      ImdbQuery::Movies => TvrankQuery::Movies,
+      ImdbQuery::Series => TvrankQuery::Series,
+    }
+  }
+}
+
+/// Opaque handle to the loaded databases.
+pub struct TvrankService {
+  imdb: Imdb,
+}
+
+/// A title of a search result, which owns copies of the fields of the title so that it can
+/// outlive the service it was found with.
+pub struct TvrankTitle {
+  id: CString,
+  kind: TvrankQuery,
+  title_type: ImdbTitleType,
+  primary_title: CString,
+  original_title: Option<CString>,
+  year: u16,
+  runtime_minutes: u32,
+  rating: Option<(u8, u32)>,
+  genres: ImdbGenres,
+}
+
+impl TvrankTitle {
+  fn new(title: &ImdbTitle, kind: ImdbQuery) -> Self {
+    let c_string = |text: String| CString::new(text).unwrap_or_default();
+
+    Self {
+      id: c_string(title.title_id().to_string()),
+      kind: kind.into(),
+      title_type: title.title_type(),
+      primary_title: c_string(title.primary_title().to_owned()),
+      original_title: title.original_title().map(|original_title| c_string(original_title.to_owned())),
+      year: title.start_year().unwrap_or_default(),
+      runtime_minutes: title
+        .runtime()
+        .map_or(0, |runtime| u32::try_from(runtime.as_secs() / 60).unwrap_or(u32::MAX)),
+      rating: title.rating().map(|rating| (rating.rating(), rating.votes())),
+      genres: title.genres(),
+    }
+  }
+}
+
+/// Opaque handle to the titles found by a search.
+pub struct TvrankResult {
+  titles: Vec<TvrankTitle>,
+}
+
+/// Callback reporting how many bytes of the dumps were read so far, out of their total size.
+pub type TvrankProgressCallback = Option<extern "C" fn(bytes_read: u64, total_bytes: u64)>;
+
+/// Convert a C string argument to a path.
+///
+/// # Safety
+///
+/// `path` must be null or point to a NUL-terminated string.
+unsafe fn path_arg(path: *const c_char) -> Option<PathBuf> {
+  if path.is_null() {
+    return None;
+  }
+
+  CStr::from_ptr(path).to_str().ok().map(PathBuf::from)
+}
+
+/// Hand a string over to the caller, who has to free it with [tvrank_string_free].
+fn owned_string(text: String) -> *mut c_char {
+  CString::new(text).unwrap_or_default().into_raw()
+}
+
+/// Convert a C string argument to a string.
+///
+/// # Safety
+///
+/// `text` must be null or point to a NUL-terminated string.
+unsafe fn str_arg<'a>(text: *const c_char) -> Option<&'a str> {
+  if text.is_null() {
+    return None;
+  }
+
+  CStr::from_ptr(text).to_str().ok()
+}
+
+/// Convert a title argument to a reference, recording an error if it is null.
+///
+/// # Safety
+///
+/// `title` must be null or a title of a result that was not freed.
+unsafe fn title_arg<'a>(title: *const TvrankTitle) -> Option<&'a TvrankTitle> {
+  let title = title.as_ref();
+  if title.is_none() {
+    fail(TvrankStatus::InvalidArgument, "`title` is null");
+  }
+  title
+}
+
+/// Run a search and hand its titles over to the caller in `out`.
+///
+/// # Safety
+///
+/// `out` must be null or point to a writable pointer.
+unsafe fn search_result(
+  out: *mut *mut TvrankResult,
+  search: impl FnOnce() -> Result<Vec<TvrankTitle>, String>,
+) -> TvrankStatus {
+  if out.is_null() {
+    return fail(TvrankStatus::InvalidArgument, "`out` is null");
+  }
+
+  *out = ptr::null_mut();
+
+  match panic::catch_unwind(AssertUnwindSafe(search)) {
+    Ok(Ok(titles)) => {
+      *out = Box::into_raw(Box::new(TvrankResult { titles }));
+      TvrankStatus::Ok
+    }
+    Ok(Err(message)) => fail(TvrankStatus::InvalidArgument, message),
+    Err(payload) => panicked(payload),
+  }
+}
+
+/// The message of the last failed call on the calling thread, or null if no call failed yet.
+///
+/// The message is only replaced by later failing calls, so it has to be read right after the
+/// call that failed. It is valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn tvrank_last_error_message() -> *const c_char {
+  LAST_ERROR.with(|last_error| last_error.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// Build the binary databases in `out_dir` from IMDB dumps that were already downloaded.
+///
+/// The dumps can either be gzip-compressed, as IMDB publishes them, if their names end with
+/// `.gz`, or uncompressed. `progress_cb` can be null.
+///
+/// # Arguments
+///
+/// * `basics_path` - Path to the `title.basics` dump.
+/// * `ratings_path` - Path to the `title.ratings` dump.
+/// * `out_dir` - Directory to write the databases to, which has to exist.
+/// * `progress_cb` - Callback reporting how much of the dumps was read.
+///
+/// # Safety
+///
+/// `basics_path`, `ratings_path` and `out_dir` must be null or point to NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_db_build_from_files(
+  basics_path: *const c_char,
+  ratings_path: *const c_char,
+  out_dir: *const c_char,
+  progress_cb: TvrankProgressCallback,
+) -> TvrankStatus {
+  let Some(basics_path) = path_arg(basics_path) else {
+    return invalid_argument("basics_path");
+  };
+  let Some(ratings_path) = path_arg(ratings_path) else {
+    return invalid_argument("ratings_path");
+  };
+  let Some(out_dir) = path_arg(out_dir) else {
+    return invalid_argument("out_dir");
+  };
+
+  let result = panic::catch_unwind(AssertUnwindSafe(|| {
+    let total = Cell::new(0);
+    let bytes_read = Cell::new(0);
+
+    let progress_fn = |content_len: Option<u64>, delta: u64| {
+      if let Some(content_len) = content_len {
+        total.set(content_len);
+      }
+
+      bytes_read.set(bytes_read.get() + delta);
+
+      if let Some(progress_cb) = progress_cb {
+        progress_cb(bytes_read.get(), total.get());
+      }
+    };
+
+    Imdb::build_from_files(&basics_path, &ratings_path, &out_dir, progress_fn, ImdbCacheCompression::None)
+  }));
+
+  match result {
+    Ok(Ok(())) => TvrankStatus::Ok,
+    Ok(Err(e)) => fail((&e).into(), format!("Cannot build the databases: {e}")),
+    Err(payload) => panicked(payload),
+  }
+}
+
+/// Load the databases from `cache_dir` into a new service, which has to be freed with
+/// `tvrank_service_free`.
+///
+/// The databases are fetched and built first if they are missing or older than a month. If
+/// another process is already rebuilding them, the call waits for it to finish, for up to
+/// an hour before failing with [TvrankStatus::UpdateInProgress].
+///
+/// # Arguments
+///
+/// * `cache_dir` - Directory of the databases.
+/// * `out` - Where to store the new service, or null on failure.
+///
+/// # Safety
+///
+/// `cache_dir` must be null or point to a NUL-terminated string, and `out` must be null or
+/// point to a writable pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_service_new(
+  cache_dir: *const c_char,
+  out: *mut *mut TvrankService,
+) -> TvrankStatus {
+  let Some(cache_dir) = path_arg(cache_dir) else {
+    return invalid_argument("cache_dir");
+  };
+
+  if out.is_null() {
+    return fail(TvrankStatus::InvalidArgument, "`out` is null");
+  }
+
+  *out = ptr::null_mut();
+
+  match panic::catch_unwind(|| Imdb::new(&cache_dir, false, |_, _| {})) {
+    Ok(Ok(imdb)) => {
+      *out = Box::into_raw(Box::new(TvrankService { imdb }));
+      TvrankStatus::Ok
+    }
+    Ok(Err(e)) => fail((&e).into(), format!("Cannot load the databases: {e}")),
+    Err(payload) => panicked(payload),
+  }
+}
+
+/// Free a service created by `tvrank_service_new`. Results of its searches stay valid.
+///
+/// # Safety
+///
+/// `service` must be null or a service created by `tvrank_service_new` that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_service_free(service: *mut TvrankService) {
+  if !service.is_null() {
+    drop(Box::from_raw(service));
+  }
+}
+
+/// Search titles by their exact name, which has to be freed with `tvrank_result_free`.
+///
+/// # Arguments
+///
+/// * `service` - The service to search with.
+/// * `title` - The name of the titles.
+/// * `year` - The release year of the titles, or 0 for any year.
+/// * `query` - Whether movies or series are searched, one of the values of `TvrankQuery`.
+/// * `out` - Where to store the result, or null on failure.
+///
+/// # Safety
+///
+/// `service` must be null or a live service, `title` must be null or point to a
+/// NUL-terminated string, and `out` must be null or point to a writable pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_search_by_title(
+  service: *const TvrankService,
+  title: *const c_char,
+  year: u16,
+  query: u32,
+  out: *mut *mut TvrankResult,
+) -> TvrankStatus {
+  let service = service.as_ref();
+  let title = str_arg(title);
+
+  search_result(out, || {
+    let imdb = &service.ok_or("`service` is null")?.imdb;
+    let query = TvrankQuery::try_from(query)?;
+    let title = title.ok_or_else(|| invalid_argument_message("title"))?;
+    let title = SearchString::try_from(title).map_err(|e| format!("Invalid title `{title}`: {e}"))?;
+    let titles = if year == 0 {
+      imdb.by_title(&title, query.into())
+    } else {
+      imdb.by_title_and_year(&title, year, query.into())
+    };
+
+    Ok(titles.into_iter().map(|title| TvrankTitle::new(title, query.into())).collect())
+  })
+}
+
+/// Search titles by keywords, which has to be freed with `tvrank_result_free`.
+///
+/// # Arguments
+///
+/// * `service` - The service to search with.
+/// * `keywords` - The keywords to search in the names of titles, separated by whitespace.
+/// * `year` - The release year of the titles, or 0 for any year.
+/// * `query` - Whether movies or series are searched, one of the values of `TvrankQuery`.
+/// * `out` - Where to store the result, or null on failure.
+///
+/// # Safety
+///
+/// `service` must be null or a live service, `keywords` must be null or point to a
+/// NUL-terminated string, and `out` must be null or point to a writable pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_search_by_keywords(
+  service: *const TvrankService,
+  keywords: *const c_char,
+  year: u16,
+  query: u32,
+  out: *mut *mut TvrankResult,
+) -> TvrankStatus {
+  let service = service.as_ref();
+  let keywords = str_arg(keywords);
+
+  search_result(out, || {
+    let imdb = &service.ok_or("`service` is null")?.imdb;
+    let query = TvrankQuery::try_from(query)?;
+    let keywords = keywords.ok_or_else(|| invalid_argument_message("keywords"))?;
+    let keywords = keywords
+      .split_whitespace()
+      .map(|keyword| SearchString::try_from(keyword).map_err(|e| format!("Invalid keyword `{keyword}`: {e}")))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    if keywords.is_empty() {
+      return Err("`keywords` is empty".to_owned());
+    }
+    let titles = if year == 0 {
+      imdb.by_keywords(&keywords, query.into())
+    } else {
+      imdb.by_keywords_and_year(&keywords, year, query.into())
+    };
+
+    Ok(titles.into_iter().map(|title| TvrankTitle::new(title, query.into())).collect())
+  })
+}
+
+/// Look up a title by its IMDB ID in both the movies and series databases. The result, which
+/// has to be freed with `tvrank_result_free`, is empty if there is no such title.
+///
+/// # Arguments
+///
+/// * `service` - The service to search with.
+/// * `id` - The IMDB ID of the title, e.g. `tt0133093`.
+/// * `out` - Where to store the result, or null on failure.
+///
+/// # Safety
+///
+/// `service` must be null or a live service, `id` must be null or point to a NUL-terminated
+/// string, and `out` must be null or point to a writable pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_title_by_id(
+  service: *const TvrankService,
+  id: *const c_char,
+  out: *mut *mut TvrankResult,
+) -> TvrankStatus {
+  let service = service.as_ref();
+  let id = str_arg(id);
+
+  search_result(out, || {
+    let imdb = &service.ok_or("`service` is null")?.imdb;
+    let id = id.ok_or_else(|| invalid_argument_message("id"))?.trim();
+    let id = ImdbTitleId::try_from(id).map_err(|e| format!("Invalid IMDB ID `{id}`: {e}"))?;
+    Ok(
+      imdb
+        .by_id_any(&id)
+        .map(|(title, query)| TvrankTitle::new(title, query))
+        .into_iter()
+        .collect(),
+    )
+  })
+}
+
+/// The number of titles of a result, or 0 if `result` is null.
+///
+/// # Safety
+///
+/// `result` must be null or a result that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_result_count(result: *const TvrankResult) -> usize {
+  result.as_ref().map_or(0, |result| result.titles.len())
+}
+
+/// The title at `index` of a result, or null if `index` is out of bounds. The title is owned
+/// by the result and is only valid until the result is freed.
+///
+/// # Safety
+///
+/// `result` must be null or a result that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_result_title_at(
+  result: *const TvrankResult,
+  index: usize,
+) -> *const TvrankTitle {
+  result
+    .as_ref()
+    .and_then(|result| result.titles.get(index))
+    .map_or(ptr::null(), |title| title as *const TvrankTitle)
+}
+
+/// Free a result of a search.
+///
+/// # Safety
+///
+/// `result` must be null or a result that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_result_free(result: *mut TvrankResult) {
+  if !result.is_null() {
+    drop(Box::from_raw(result));
+  }
+}
+
+/// The IMDB ID of a title, e.g. `tt0133093`, which is valid until the result is freed.
+///
+/// # Safety
+///
+/// `title` must be null or a title of a result that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_title_id_str(title: *const TvrankTitle) -> *const c_char {
+  title_arg(title).map_or(ptr::null(), |title| title.id.as_ptr())
+}
+
+/// Whether a title is a movie or a series, as a value of [TvrankQuery], or -1 if `title` is
+/// null.
+///
+/// # Safety
+///
+/// `title` must be null or a title of a result that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_title_kind(title: *const TvrankTitle) -> i32 {
+  title_arg(title).map_or(-1, |title| title.kind as i32)
+}
+
+/// The primary name of a title, which is valid until the result is freed.
+///
+/// # Safety
+///
+/// `title` must be null or a title of a result that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_title_primary_title(title: *const TvrankTitle) -> *const c_char {
+  title_arg(title).map_or(ptr::null(), |title| title.primary_title.as_ptr())
+}
+
+/// The original name of a title, or null if it is the same as the primary name. The name is
+/// valid until the result is freed.
+///
+/// # Safety
+///
+/// `title` must be null or a title of a result that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_title_original_title(title: *const TvrankTitle) -> *const c_char {
+  title_arg(title)
+    .and_then(|title| title.original_title.as_ref())
+    .map_or(ptr::null(), |original_title| original_title.as_ptr())
+}
+
+/// The release year of a title, or 0 if it is unknown.
+///
+/// # Safety
+///
+/// `title` must be null or a title of a result that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_title_year(title: *const TvrankTitle) -> u16 {
+  title_arg(title).map_or(0, |title| title.year)
+}
+
+/// The runtime of a title in minutes, or 0 if it is unknown.
+///
+/// # Safety
+///
+/// `title` must be null or a title of a result that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_title_runtime_minutes(title: *const TvrankTitle) -> u32 {
+  title_arg(title).map_or(0, |title| title.runtime_minutes)
+}
+
+/// The rating of a title out of 100, or -1 if it is not rated.
+///
+/// # Safety
+///
+/// `title` must be null or a title of a result that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_title_rating(title: *const TvrankTitle) -> i32 {
+  title_arg(title)
+    .and_then(|title| title.rating)
+    .map_or(-1, |(rating, _)| i32::from(rating))
+}
+
+/// The number of votes of a title, or 0 if it is not rated.
+///
+/// # Safety
+///
+/// `title` must be null or a title of a result that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_title_votes(title: *const TvrankTitle) -> u32 {
+  title_arg(title).and_then(|title| title.rating).map_or(0, |(_, votes)| votes)
+}
+
+/// The type of a title, e.g. `Movie` or `TV Mini-Series`, which has to be freed with
+/// [tvrank_string_free].
+///
+/// # Safety
+///
+/// `title` must be null or a title of a result that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_title_type(title: *const TvrankTitle) -> *mut c_char {
+  title_arg(title).map_or(ptr::null_mut(), |title| owned_string(title.title_type.to_string()))
+}
+
+/// The genres of a title, separated by commas, e.g. `Action, Sci-Fi`, which have to be freed
+/// with [tvrank_string_free].
+///
+/// # Safety
+///
+/// `title` must be null or a title of a result that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_title_genres(title: *const TvrankTitle) -> *mut c_char {
+  title_arg(title).map_or(ptr::null_mut(), |title| owned_string(title.genres.to_string()))
+}
+
+/// Free a string returned by the C API as `char *`.
+///
+/// # Safety
+///
+/// `text` must be null or a string returned as `char *` that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn tvrank_string_free(text: *mut c_char) {
+  if !text.is_null() {
+    drop(CString::from_raw(text));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  // With the `akas` feature, loading the databases also downloads the akas dump, so the
+  // tests that load them only run without it.
+  #![cfg_attr(feature = "akas", allow(dead_code, unused_imports))]
+
+  use super::*;
+
+  use std::ffi::CString;
+  use std::fs;
+  use std::path::Path;
+  use std::ptr;
+  use std::sync::atomic::{AtomicU64, Ordering};
+
+  const BASICS: &str = "tconst\ttitleType\tprimaryTitle\toriginalTitle\tisAdult\tstartYear\tendYear\t\
+                        runtimeMinutes\tgenres\n\
+                        tt0000001\tmovie\tCarmencita\tCarmencita\t0\t1894\t\\N\t1\tDocumentary\n\
+                        tt0000002\ttvSeries\tLe clown\tLe clown\t0\t1892\t1893\t5\tAnimation\n";
+
+  const RATINGS: &str = "tconst\taverageRating\tnumVotes\n\
+                         tt0000001\t5.7\t1845\n\
+                         tt0000002\t6.0\t236\n";
+
+  static BYTES_READ: AtomicU64 = AtomicU64::new(0);
+  static TOTAL_BYTES: AtomicU64 = AtomicU64::new(0);
+
+  extern "C" fn progress(bytes_read: u64, total_bytes: u64) {
+    BYTES_READ.store(bytes_read, Ordering::SeqCst);
+    TOTAL_BYTES.store(total_bytes, Ordering::SeqCst);
+  }
+
+  fn c_path(path: &Path) -> CString {
+    CString::new(path.to_str().unwrap()).unwrap()
+  }
+
+  #[test]
+  #[cfg(not(feature = "akas"))]
+  fn build_from_files() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let basics = dir.path().join("title.basics.tsv");
+    let ratings = dir.path().join("title.ratings.tsv");
+    fs::write(&basics, BASICS).unwrap();
+    fs::write(&ratings, RATINGS).unwrap();
+
+    let status = unsafe {
+      tvrank_db_build_from_files(
+        c_path(&basics).as_ptr(),
+        c_path(&ratings).as_ptr(),
+        c_path(dir.path()).as_ptr(),
+        Some(progress),
+      )
+    };
+
+    assert_eq!(status, TvrankStatus::Ok);
+    let total = (BASICS.len() + RATINGS.len()) as u64;
+    assert_eq!(TOTAL_BYTES.load(Ordering::SeqCst), total);
+    assert_eq!(BYTES_READ.load(Ordering::SeqCst), total);
+
+    let imdb = Imdb::new(dir.path(), false, |_, _| {}).unwrap();
+    let id = ImdbTitleId::try_from("tt0000001").unwrap();
+    assert_eq!(imdb.by_id(&id, ImdbQuery::Movies).unwrap().primary_title(), "Carmencita");
+    let id = ImdbTitleId::try_from("tt0000002").unwrap();
+    assert!(imdb.by_id(&id, ImdbQuery::Series).is_some());
+  }
+
+  #[test]
+  #[cfg(not(feature = "akas"))]
+  fn failed_build_keeps_databases() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let basics = dir.path().join("title.basics.tsv");
+    let ratings = dir.path().join("title.ratings.tsv");
+    fs::write(&basics, BASICS).unwrap();
+    fs::write(&ratings, RATINGS).unwrap();
+
+    let build = || unsafe {
+      tvrank_db_build_from_files(
+        c_path(&basics).as_ptr(),
+        c_path(&ratings).as_ptr(),
+        c_path(dir.path()).as_ptr(),
+        None,
+      )
+    };
+
+    assert_eq!(build(), TvrankStatus::Ok);
+
+    fs::write(&basics, format!("{BASICS}tt0000003\tmovie\n")).unwrap();
+    assert_eq!(build(), TvrankStatus::Parse);
+    assert!(c_str(tvrank_last_error_message()).starts_with("Cannot build the databases: "));
+
+    let partials = fs::read_dir(dir.path())
+      .unwrap()
+      .filter(|entry| entry.as_ref().unwrap().path().extension().is_some_and(|ext| ext == "partial"))
+      .count();
+    assert_eq!(partials, 0);
+
+    let imdb = Imdb::new(dir.path(), false, |_, _| {}).unwrap();
+    let id = ImdbTitleId::try_from("tt0000001").unwrap();
+    assert!(imdb.by_id(&id, ImdbQuery::Movies).is_some());
+  }
+
+  fn build_service(dir: &Path) -> *mut TvrankService {
+    let basics = dir.join("title.basics.tsv");
+    let ratings = dir.join("title.ratings.tsv");
+    fs::write(&basics, BASICS).unwrap();
+    fs::write(&ratings, RATINGS).unwrap();
+
+    unsafe {
+      let status = tvrank_db_build_from_files(
+        c_path(&basics).as_ptr(),
+        c_path(&ratings).as_ptr(),
+        c_path(dir).as_ptr(),
+        None,
+      );
+      assert_eq!(status, TvrankStatus::Ok);
+
+      let mut service = ptr::null_mut();
+      assert_eq!(tvrank_service_new(c_path(dir).as_ptr(), &mut service), TvrankStatus::Ok);
+      assert!(!service.is_null());
+      service
+    }
+  }
+
+  fn c_str<'a>(text: *const c_char) -> &'a str {
+    unsafe { CStr::from_ptr(text).to_str().unwrap() }
+  }
+
+  #[test]
+  #[cfg(not(feature = "akas"))]
+  fn search() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let service = build_service(dir.path());
+
+    unsafe {
+      let mut result = ptr::null_mut();
+      let title = CString::new("carmencita").unwrap();
+      let status =
+        tvrank_search_by_title(service, title.as_ptr(), 0, TvrankQuery::Movies as u32, &mut result);
+      assert_eq!(status, TvrankStatus::Ok);
+      assert_eq!(tvrank_result_count(result), 1);
+      assert!(tvrank_result_title_at(result, 1).is_null());
+
+      let title = tvrank_result_title_at(result, 0);
+      assert_eq!(c_str(tvrank_title_id_str(title)), "tt0000001");
+      assert_eq!(c_str(tvrank_title_primary_title(title)), "Carmencita");
+      assert!(tvrank_title_original_title(title).is_null());
+      assert_eq!(tvrank_title_kind(title), TvrankQuery::Movies as i32);
+      assert_eq!(tvrank_title_year(title), 1894);
+      assert_eq!(tvrank_title_runtime_minutes(title), 1);
+      assert_eq!(tvrank_title_rating(title), 57);
+      assert_eq!(tvrank_title_votes(title), 1845);
+      let title_type = tvrank_title_type(title);
+      assert_eq!(c_str(title_type), "Movie");
+      tvrank_string_free(title_type);
+      let genres = tvrank_title_genres(title);
+      assert_eq!(c_str(genres), "Documentary");
+      tvrank_string_free(genres);
+      tvrank_string_free(ptr::null_mut());
+      tvrank_result_free(result);
+
+      let title = CString::new("carmencita").unwrap();
+      let status =
+        tvrank_search_by_title(service, title.as_ptr(), 1900, TvrankQuery::Movies as u32, &mut result);
+      assert_eq!(status, TvrankStatus::Ok);
+      assert_eq!(tvrank_result_count(result), 0);
+      tvrank_result_free(result);
+
+      let keywords = CString::new("clown").unwrap();
+      let status =
+        tvrank_search_by_keywords(service, keywords.as_ptr(), 0, TvrankQuery::Series as u32, &mut result);
+      assert_eq!(status, TvrankStatus::Ok);
+      assert_eq!(tvrank_result_count(result), 1);
+
+      // Results outlive the service they were found with.
+      tvrank_service_free(service);
+      let title = tvrank_result_title_at(result, 0);
+      assert_eq!(c_str(tvrank_title_primary_title(title)), "Le clown");
+      assert_eq!(tvrank_title_kind(title), TvrankQuery::Series as i32);
+      tvrank_result_free(result);
+    }
+  }
+
+  #[test]
+  #[cfg(not(feature = "akas"))]
+  fn title_by_id() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let service = build_service(dir.path());
+
+    unsafe {
+      let mut result = ptr::null_mut();
+      let id = CString::new("tt0000002").unwrap();
+      assert_eq!(tvrank_title_by_id(service, id.as_ptr(), &mut result), TvrankStatus::Ok);
+      assert_eq!(tvrank_result_count(result), 1);
+      let title = tvrank_result_title_at(result, 0);
+      assert_eq!(tvrank_title_kind(title), TvrankQuery::Series as i32);
+      tvrank_result_free(result);
+
+      let id = CString::new("tt0000009").unwrap();
+      assert_eq!(tvrank_title_by_id(service, id.as_ptr(), &mut result), TvrankStatus::Ok);
+      assert_eq!(tvrank_result_count(result), 0);
+      tvrank_result_free(result);
+
+      let id = CString::new("not an id").unwrap();
+      assert_eq!(tvrank_title_by_id(service, id.as_ptr(), &mut result), TvrankStatus::InvalidArgument);
+      assert!(result.is_null());
+      assert!(c_str(tvrank_last_error_message()).starts_with("Invalid IMDB ID `not an id`"));
+
+      let keywords = CString::new("  ").unwrap();
+      let status =
+        tvrank_search_by_keywords(service, keywords.as_ptr(), 0, TvrankQuery::Movies as u32, &mut result);
+      assert_eq!(status, TvrankStatus::InvalidArgument);
+      assert_eq!(c_str(tvrank_last_error_message()), "`keywords` is empty");
+
+      let title = CString::new("The Matrix").unwrap();
+      let status = tvrank_search_by_title(service, title.as_ptr(), 0, 2, &mut result);
+      assert_eq!(status, TvrankStatus::InvalidArgument);
+      assert!(result.is_null());
+      assert_eq!(c_str(tvrank_last_error_message()), "Invalid query `2`");
+
+      let status = tvrank_search_by_keywords(service, title.as_ptr(), 0, u32::MAX, &mut result);
+      assert_eq!(status, TvrankStatus::InvalidArgument);
+      assert!(result.is_null());
+
+      let status =
+        tvrank_search_by_title(ptr::null(), id.as_ptr(), 0, TvrankQuery::Movies as u32, &mut result);
+      assert_eq!(status, TvrankStatus::InvalidArgument);
+      assert_eq!(tvrank_result_count(ptr::null()), 0);
+
+      tvrank_service_free(service);
+    }
+  }
+
+  #[test]
+  fn invalid_arguments() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let missing = c_path(&dir.path().join("missing"));
+
+    let status = unsafe { tvrank_db_build_from_files(ptr::null(), missing.as_ptr(), missing.as_ptr(), None) };
+    assert_eq!(status, TvrankStatus::InvalidArgument);
+    assert_eq!(c_str(tvrank_last_error_message()), "`basics_path` is null or not valid UTF-8");
+
+    let status = unsafe {
+      tvrank_db_build_from_files(missing.as_ptr(), missing.as_ptr(), c_path(dir.path()).as_ptr(), None)
+    };
+    assert_eq!(status, TvrankStatus::Io);
+
+    let mut service = ptr::null_mut();
+    let status = unsafe { tvrank_service_new(ptr::null(), &mut service) };
+    assert_eq!(status, TvrankStatus::InvalidArgument);
+    assert_eq!(c_str(tvrank_last_error_message()), "`cache_dir` is null or not valid UTF-8");
+  }
+
+  #[test]
+  fn null_title() {
+    let title = unsafe { tvrank_result_title_at(ptr::null(), 0) };
+    assert!(title.is_null());
+
+    unsafe {
+      assert!(tvrank_title_id_str(title).is_null());
+      assert_eq!(c_str(tvrank_last_error_message()), "`title` is null");
+      assert_eq!(tvrank_title_kind(title), -1);
+      assert!(tvrank_title_primary_title(title).is_null());
+      assert!(tvrank_title_original_title(title).is_null());
+      assert_eq!(tvrank_title_year(title), 0);
+      assert_eq!(tvrank_title_runtime_minutes(title), 0);
+      assert_eq!(tvrank_title_rating(title), -1);
+      assert_eq!(tvrank_title_votes(title), 0);
+      assert!(tvrank_title_type(title).is_null());
+      assert!(tvrank_title_genres(title).is_null());
+    }
+  }
+}
@@ -0,0 +1,261 @@
+#![warn(clippy::all)]
+
+//! Search-quality benchmark: runs the queries of `corpus/queries.tsv` with every keyword
+//! match kind, with and without stemming, reports the precision and recall of the results
+//! against the titles each query is expected to find, then measures the latency of running
+//! the whole corpus with criterion.
+//!
+//! The queries run against the few titles of `corpus/title.basics.tsv` and
+//! `corpus/title.ratings.tsv` (real IMDB IDs, approximate ratings), or against the full
+//! databases in `TVRANK_BENCH_CACHE_DIR` when it is set, which is what changes to the
+//! matching should be evaluated with:
+//!
+//! ```sh
+//! $ TVRANK_BENCH_CACHE_DIR=~/.cache/tvrank cargo bench -p tvrank --bench search
+//! ```
+//!
+//! Keyword queries scan every title name, unless the `trie` feature is enabled, so
+//! running the benchmark with and without it compares both. Criterion keeps the results of
+//! the previous run and reports the change against them:
+//!
+//! ```sh
+//! $ cargo bench -p tvrank --bench search
+//! $ cargo bench -p tvrank --bench search --features trie
+//! ```
+//!
+//! Precision is computed over the first `TOP` results, in the order of
+//! [Imdb::by_keywords_ranked] for keyword queries. Criterion options are accepted as
+//! usual, e.g. a filter such as `search/word-boundary` only measures that match kind.
+
+use std::collections::HashSet;
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use criterion::{BenchmarkId, Criterion};
+use tvrank::imdb::{Imdb, ImdbQuery, ImdbSearch, ImdbTitle};
+use tvrank::utils::search::{KeywordMatchKind, SearchOptions, SearchString};
+
+/// Number of results over which precision is computed.
+const TOP: usize = 10;
+
+/// The keyword match kinds that are compared.
+const MATCH_KINDS: [(&str, KeywordMatchKind); 4] = [
+  ("overlapping", KeywordMatchKind::Overlapping),
+  ("leftmost-first", KeywordMatchKind::LeftmostFirst),
+  ("leftmost-longest", KeywordMatchKind::LeftmostLongest),
+  ("word-boundary", KeywordMatchKind::WordBoundary),
+];
+
+#[derive(Clone, Copy)]
+enum Kind {
+  Exact,
+  Keywords,
+}
+
+struct Entry {
+  kind: Kind,
+  query: ImdbQuery,
+  terms: String,
+  expected: HashSet<String>,
+}
+
+#[derive(Default)]
+struct Quality {
+  precision: f64,
+  recall: f64,
+  misses: Vec<String>,
+}
+
+fn corpus_dir() -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR")).join("benches").join("corpus")
+}
+
+fn load_corpus() -> Result<Vec<Entry>, Box<dyn Error>> {
+  let contents = fs::read_to_string(corpus_dir().join("queries.tsv"))?;
+  let mut entries = Vec::new();
+
+  for line in contents.lines().filter(|line| !line.starts_with('#')).skip(1) {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let [kind, query, terms, expected] = fields[..] else {
+      return Err(format!("Invalid corpus line `{line}`").into());
+    };
+
+    let kind = match kind {
+      "exact" => Kind::Exact,
+      "keywords" => Kind::Keywords,
+      _ => return Err(format!("Invalid query kind `{kind}`").into()),
+    };
+    let query = match query {
+      "movies" => ImdbQuery::Movies,
+      "series" => ImdbQuery::Series,
+      _ => return Err(format!("Invalid query `{query}`").into()),
+    };
+    let expected = expected.split(',').map(str::to_owned).collect();
+    entries.push(Entry { kind, query, terms: terms.to_owned(), expected });
+  }
+
+  Ok(entries)
+}
+
+fn load_service() -> Result<Imdb, Box<dyn Error>> {
+  match env::var_os("TVRANK_BENCH_CACHE_DIR") {
+    Some(cache_dir) => Ok(Imdb::from_cache_only(Path::new(&cache_dir))?),
+    None => {
+      let basics = BufReader::new(File::open(corpus_dir().join("title.basics.tsv"))?);
+      let ratings = BufReader::new(File::open(corpus_dir().join("title.ratings.tsv"))?);
+      Ok(Imdb::from_tsv_readers(basics, ratings)?)
+    }
+  }
+}
+
+fn parse_title_and_year(terms: &str) -> Option<(&str, u16)> {
+  let (title, year) = terms.strip_suffix(')')?.rsplit_once(" (")?;
+  Some((title, year.parse().ok()?))
+}
+
+fn keywords(terms: &str, stem: bool) -> Result<Vec<SearchString>, Box<dyn Error>> {
+  let mut keywords = Vec::new();
+  let mut seen = HashSet::new();
+
+  // Keywords are deduplicated like the command-line tool does.
+  for keyword in terms.split_whitespace().filter(|keyword| seen.insert(*keyword)) {
+    let keyword = match keyword.strip_prefix('-') {
+      Some(keyword) => SearchString::excluded(keyword)?,
+      None => SearchString::try_from(keyword)?,
+    };
+    keywords.push(if stem {
+      keyword.stemmed()
+    } else {
+      keyword
+    });
+  }
+
+  Ok(keywords)
+}
+
+fn search<'a>(
+  imdb: &ImdbSearch<'a>,
+  entry: &Entry,
+  stem: bool,
+) -> Result<Vec<&'a ImdbTitle<'a>>, Box<dyn Error>> {
+  let titles = match entry.kind {
+    Kind::Exact => match parse_title_and_year(&entry.terms) {
+      Some((title, year)) => imdb.by_title_and_year(&SearchString::try_from(title)?, year, entry.query),
+      None => imdb.by_title(&SearchString::try_from(entry.terms.as_str())?, entry.query),
+    },
+    Kind::Keywords => imdb
+      .by_keywords_ranked(&keywords(&entry.terms, stem)?, entry.query)
+      .into_iter()
+      .map(|scored| scored.title())
+      .collect(),
+  };
+
+  Ok(titles)
+}
+
+fn quality(imdb: &ImdbSearch, entry: &Entry, stem: bool) -> Result<Quality, Box<dyn Error>> {
+  let titles = search(imdb, entry, stem)?;
+  let found: HashSet<String> = titles.iter().map(|title| title.title_id().to_string()).collect();
+  let top: Vec<String> = titles.iter().take(TOP).map(|title| title.title_id().to_string()).collect();
+
+  let relevant = top.iter().filter(|id| entry.expected.contains(*id)).count();
+  let precision = if top.is_empty() {
+    0.0
+  } else {
+    relevant as f64 / top.len() as f64
+  };
+  let recall =
+    entry.expected.iter().filter(|id| found.contains(*id)).count() as f64 / entry.expected.len() as f64;
+  let mut misses: Vec<String> = entry.expected.difference(&found).cloned().collect();
+  misses.sort();
+
+  Ok(Quality { precision, recall, misses })
+}
+
+fn report(imdb: &Imdb, corpus: &[Entry]) -> Result<(), Box<dyn Error>> {
+  println!(
+    "{} queries against {} movies and {} series, precision over the top {TOP} results",
+    corpus.len(),
+    imdb.n_titles(ImdbQuery::Movies),
+    imdb.n_titles(ImdbQuery::Series),
+  );
+  println!(
+    "Keyword index: {}",
+    if cfg!(feature = "trie") {
+      "trie"
+    } else {
+      "full scan"
+    }
+  );
+  println!();
+  println!("{:<18} {:<5} {:>10} {:>10}", "match kind", "stem", "precision", "recall");
+
+  for (name, kind) in MATCH_KINDS {
+    let search = imdb.with_options(SearchOptions::new().match_kind(kind));
+
+    for stem in [false, true] {
+      let mut total = Quality::default();
+      for entry in corpus {
+        let quality = quality(&search, entry, stem)?;
+        total.precision += quality.precision;
+        total.recall += quality.recall;
+        total
+          .misses
+          .extend(quality.misses.into_iter().map(|id| format!("{id} for `{}`", entry.terms)));
+      }
+
+      let n = corpus.len().max(1);
+      println!(
+        "{name:<18} {:<5} {:>10.3} {:>10.3}",
+        if stem {
+          "yes"
+        } else {
+          "no"
+        },
+        total.precision / n as f64,
+        total.recall / n as f64,
+      );
+      for miss in total.misses {
+        println!("  missed {miss}");
+      }
+    }
+  }
+
+  println!();
+  Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+  let corpus = load_corpus()?;
+  let imdb = load_service()?;
+  report(&imdb, &corpus)?;
+
+  let mut criterion = Criterion::default().configure_from_args();
+  let mut group = criterion.benchmark_group("search");
+
+  for (name, kind) in MATCH_KINDS {
+    let view = imdb.with_options(SearchOptions::new().match_kind(kind));
+
+    for stem in [false, true] {
+      let parameter = if stem {
+        "stem"
+      } else {
+        "no-stem"
+      };
+      group.bench_with_input(BenchmarkId::new(name, parameter), &stem, |bencher, &stem| {
+        bencher.iter(|| {
+          for entry in &corpus {
+            std::hint::black_box(search(&view, entry, stem).unwrap());
+          }
+        })
+      });
+    }
+  }
+
+  group.finish();
+  criterion.final_summary();
+  Ok(())
+}
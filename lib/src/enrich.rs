@@ -0,0 +1,390 @@
+#![warn(clippy::all)]
+
+//! Enrichment of titles with data that is missing from the IMDB dumps, like plots and
+//! posters, fetched from other providers.
+//!
+//! Enrichment data is cached on disk per provider and title, including titles a provider
+//! knows nothing about, so that each title is only fetched once and can be browsed offline
+//! afterwards.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::imdb::ImdbTitleId as TitleId;
+use crate::tmdb::{self, TmdbDetails, TmdbError};
+
+use fnv::FnvHashMap;
+use reqwest::blocking::Client;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Errors when enriching titles.
+#[derive(Debug, thiserror::Error)]
+#[error("Enrichment error")]
+pub enum Error {
+  /// Networking error.
+  #[error("Networking error: {0}")]
+  Net(#[from] reqwest::Error),
+  /// URL parsing error.
+  #[error("Error parsing URL: {0}")]
+  UrlParsing(#[from] url::ParseError),
+  /// TMDB error.
+  #[error("TMDB error: {0}")]
+  Tmdb(#[from] TmdbError),
+  /// Response or cache parsing error.
+  #[error("Error parsing enrichment data: {0}")]
+  Json(#[from] serde_json::Error),
+  /// IO error.
+  #[error("IO error: {0}")]
+  Io(#[from] io::Error),
+  /// No API key was given for the provider.
+  #[error("Missing API key for {0}")]
+  MissingApiKey(Provider),
+  /// The name of a provider is not known.
+  #[error("Unknown enrichment provider `{0}`")]
+  UnknownProvider(String),
+}
+
+const OMDB_URL: &str = "https://www.omdbapi.com/";
+
+const POSTERS_DIRNAME: &str = "posters";
+
+/// A provider of enrichment data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+  /// The Movie Database (themoviedb.org).
+  Tmdb,
+  /// The Open Movie Database (omdbapi.com).
+  Omdb,
+}
+
+impl Provider {
+  /// All the supported providers.
+  pub const ALL: [Provider; 2] = [Provider::Tmdb, Provider::Omdb];
+
+  /// Short name of the provider, as used on the command-line and in the cache.
+  pub fn name(self) -> &'static str {
+    match self {
+      Provider::Tmdb => "tmdb",
+      Provider::Omdb => "omdb",
+    }
+  }
+}
+
+impl fmt::Display for Provider {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.name())
+  }
+}
+
+impl FromStr for Provider {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Provider::ALL
+      .into_iter()
+      .find(|provider| provider.name().eq_ignore_ascii_case(s.trim()))
+      .ok_or_else(|| Error::UnknownProvider(s.to_owned()))
+  }
+}
+
+/// Data about a title from a provider.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Enrichment {
+  plot: Option<String>,
+  poster_url: Option<String>,
+  popularity: Option<f64>,
+  metacritic: Option<u8>,
+}
+
+impl Enrichment {
+  /// A short summary of the plot.
+  pub fn plot(&self) -> Option<&str> {
+    self.plot.as_deref()
+  }
+
+  /// The URL of the poster image.
+  pub fn poster_url(&self) -> Option<&str> {
+    self.poster_url.as_deref()
+  }
+
+  /// The popularity of the title, only provided by TMDB.
+  pub fn popularity(&self) -> Option<f64> {
+    self.popularity
+  }
+
+  /// The Metacritic score of the title, out of 100, only provided by OMDb.
+  pub fn metacritic(&self) -> Option<u8> {
+    self.metacritic
+  }
+}
+
+impl From<TmdbDetails> for Enrichment {
+  fn from(details: TmdbDetails) -> Self {
+    Self {
+      plot: details.overview().map(str::to_owned),
+      poster_url: details.poster_url().map(str::to_owned),
+      popularity: details.popularity(),
+      metacritic: None,
+    }
+  }
+}
+
+/// Whether a value from a provider is missing, which providers represent as empty or "N/A".
+fn non_empty(value: Option<String>) -> Option<String> {
+  value.filter(|value| !value.is_empty() && value != "N/A")
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawOmdb {
+  response: String,
+  plot: Option<String>,
+  poster: Option<String>,
+  metascore: Option<String>,
+}
+
+impl Enrichment {
+  /// Parse a response of the OMDb API, or `None` if OMDb does not know the title.
+  ///
+  /// # Arguments
+  ///
+  /// * `json` - Body of the response.
+  fn from_omdb_json(json: &str) -> Result<Option<Self>, Error> {
+    let raw: RawOmdb = serde_json::from_str(json)?;
+
+    if raw.response != "True" {
+      return Ok(None);
+    }
+
+    Ok(Some(Self {
+      plot: non_empty(raw.plot),
+      poster_url: non_empty(raw.poster),
+      popularity: None,
+      metacritic: raw.metascore.and_then(|metascore| metascore.parse().ok()),
+    }))
+  }
+}
+
+/// Enrichment of a title by a provider, and where it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lookup {
+  /// The enrichment was read from the cache.
+  Cached(Option<Enrichment>),
+  /// The enrichment was fetched from the provider, and cached.
+  Fetched(Option<Enrichment>),
+}
+
+impl Lookup {
+  /// The enrichment, or `None` if the provider does not know the title.
+  pub fn enrichment(&self) -> Option<&Enrichment> {
+    match self {
+      Lookup::Cached(enrichment) | Lookup::Fetched(enrichment) => enrichment.as_ref(),
+    }
+  }
+}
+
+/// Fetches enrichment data from providers and caches it on disk.
+///
+/// Requests to providers are spaced by a minimum delay, to stay within their rate limits.
+pub struct Enricher {
+  cache_dir: PathBuf,
+  client: Client,
+  api_keys: FnvHashMap<Provider, String>,
+  delay: Duration,
+  last_request: Option<Instant>,
+}
+
+impl Enricher {
+  /// Create an enricher that caches data in the given directory.
+  ///
+  /// # Arguments
+  ///
+  /// * `cache_dir` - Directory to cache enrichment data and posters in.
+  /// * `delay` - Minimum delay between requests.
+  pub fn new(cache_dir: &Path, delay: Duration) -> Result<Self, Error> {
+    Ok(Self {
+      cache_dir: cache_dir.to_owned(),
+      client: Client::builder().build()?,
+      api_keys: FnvHashMap::default(),
+      delay,
+      last_request: None,
+    })
+  }
+
+  /// Use the given API key for requests to a provider.
+  ///
+  /// # Arguments
+  ///
+  /// * `provider` - The provider the key is for.
+  /// * `api_key` - The API key.
+  pub fn with_api_key(mut self, provider: Provider, api_key: String) -> Self {
+    self.api_keys.insert(provider, api_key);
+    self
+  }
+
+  /// Whether an API key was given for the provider.
+  pub fn has_api_key(&self, provider: Provider) -> bool {
+    self.api_keys.contains_key(&provider)
+  }
+
+  fn cache_path(&self, provider: Provider, id: &TitleId) -> PathBuf {
+    self.cache_dir.join(provider.name()).join(format!("{id}.json"))
+  }
+
+  /// The path of the cached poster of a title, which may not exist yet.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - ID of the title.
+  pub fn poster_path(&self, id: &TitleId) -> PathBuf {
+    self.cache_dir.join(POSTERS_DIRNAME).join(format!("{id}.jpg"))
+  }
+
+  /// Return the cached enrichment of a title by a provider, without fetching it.
+  ///
+  /// # Arguments
+  ///
+  /// * `provider` - The provider of the enrichment.
+  /// * `id` - ID of the title.
+  pub fn cached(&self, provider: Provider, id: &TitleId) -> Result<Option<Lookup>, Error> {
+    match fs::read_to_string(self.cache_path(provider, id)) {
+      Ok(json) => Ok(Some(Lookup::Cached(serde_json::from_str(&json)?))),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(Error::Io(e)),
+    }
+  }
+
+  /// Return the enrichment of a title by a provider, fetching and caching it if it is not
+  /// cached yet.
+  ///
+  /// # Arguments
+  ///
+  /// * `provider` - The provider of the enrichment.
+  /// * `id` - ID of the title.
+  pub fn enrich(&mut self, provider: Provider, id: &TitleId) -> Result<Lookup, Error> {
+    if let Some(lookup) = self.cached(provider, id)? {
+      return Ok(lookup);
+    }
+
+    let api_key = self.api_keys.get(&provider).ok_or(Error::MissingApiKey(provider))?;
+
+    let url = match provider {
+      Provider::Tmdb => tmdb::find_url(api_key, id)?,
+      Provider::Omdb => Url::parse_with_params(OMDB_URL, [("i", id.as_str()), ("apikey", api_key)])?,
+    };
+
+    let body = self.get(url)?.text()?;
+    let enrichment = match provider {
+      Provider::Tmdb => TmdbDetails::from_find_json(&body)?.map(Enrichment::from),
+      Provider::Omdb => Enrichment::from_omdb_json(&body)?,
+    };
+
+    write_atomically(&self.cache_path(provider, id), serde_json::to_string(&enrichment)?.as_bytes())?;
+    debug!("Fetched {provider} enrichment of {id}");
+
+    Ok(Lookup::Fetched(enrichment))
+  }
+
+  /// Download the poster of a title to the cache, unless it is already cached. Returns
+  /// whether the poster was downloaded.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - ID of the title.
+  /// * `url` - URL of the poster image.
+  pub fn fetch_poster(&mut self, id: &TitleId, url: &str) -> Result<bool, Error> {
+    let path = self.poster_path(id);
+
+    if path.exists() {
+      return Ok(false);
+    }
+
+    let image = self.get(Url::parse(url)?)?.bytes()?;
+    write_atomically(&path, &image)?;
+    debug!("Fetched poster of {id}");
+
+    Ok(true)
+  }
+
+  /// Send a GET request, after waiting for the delay since the previous request to pass.
+  fn get(&mut self, url: Url) -> Result<reqwest::blocking::Response, Error> {
+    if let Some(last_request) = self.last_request {
+      std::thread::sleep(self.delay.saturating_sub(last_request.elapsed()));
+    }
+
+    self.last_request = Some(Instant::now());
+    Ok(self.client.get(url).send()?.error_for_status()?)
+  }
+}
+
+/// Write a file through a temporary file, so that interrupted writes do not leave partial
+/// files in the cache.
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir)?;
+  }
+
+  let tmp_path = path.with_extension("tmp");
+  fs::write(&tmp_path, contents)?;
+  fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use crate::enrich::{Enricher, Enrichment, Error, Lookup, Provider};
+  use crate::imdb::ImdbTitleId as TitleId;
+
+  #[test]
+  fn test_provider_names() {
+    assert_eq!("tmdb".parse::<Provider>().unwrap(), Provider::Tmdb);
+    assert_eq!(" OMDb".parse::<Provider>().unwrap(), Provider::Omdb);
+    assert!(matches!("imdb".parse::<Provider>(), Err(Error::UnknownProvider(_))));
+    assert_eq!(Provider::Tmdb.to_string(), "tmdb");
+  }
+
+  #[test]
+  fn test_omdb_json() {
+    let json = r#"{"Title":"Fight Club","Plot":"An insomniac office worker.","Poster":"N/A","Metascore":"67","Response":"True"}"#;
+    let enrichment = Enrichment::from_omdb_json(json).unwrap().unwrap();
+    assert_eq!(enrichment.plot(), Some("An insomniac office worker."));
+    assert_eq!(enrichment.poster_url(), None);
+    assert_eq!(enrichment.metacritic(), Some(67));
+
+    let json = r#"{"Plot":"N/A","Metascore":"N/A","Response":"True"}"#;
+    assert_eq!(Enrichment::from_omdb_json(json).unwrap(), Some(Enrichment::default()));
+
+    let json = r#"{"Response":"False","Error":"Incorrect IMDb ID."}"#;
+    assert_eq!(Enrichment::from_omdb_json(json).unwrap(), None);
+  }
+
+  #[test]
+  fn test_cache() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let mut enricher = Enricher::new(dir.path(), Duration::ZERO).unwrap();
+    let id = TitleId::try_from("tt0137523").unwrap();
+
+    assert_eq!(enricher.cached(Provider::Tmdb, &id).unwrap(), None);
+    assert!(matches!(enricher.enrich(Provider::Tmdb, &id), Err(Error::MissingApiKey(Provider::Tmdb))));
+
+    std::fs::create_dir_all(dir.path().join("tmdb")).unwrap();
+    std::fs::write(dir.path().join("tmdb/tt0137523.json"), r#"{"plot":"Soap."}"#).unwrap();
+    std::fs::create_dir_all(dir.path().join("omdb")).unwrap();
+    std::fs::write(dir.path().join("omdb/tt0137523.json"), "null").unwrap();
+
+    let lookup = enricher.enrich(Provider::Tmdb, &id).unwrap();
+    assert!(matches!(lookup, Lookup::Cached(_)));
+    assert_eq!(lookup.enrichment().and_then(Enrichment::plot), Some("Soap."));
+    assert_eq!(enricher.enrich(Provider::Omdb, &id).unwrap(), Lookup::Cached(None));
+    assert_eq!(enricher.poster_path(&id), dir.path().join("posters/tt0137523.jpg"));
+  }
+}
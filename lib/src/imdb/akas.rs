@@ -0,0 +1,419 @@
+#![warn(clippy::all)]
+
+//! Names of titles in non-Latin scripts, imported from the `title.akas` IMDB dump.
+//!
+//! Titles from e.g. Russia, Japan or Egypt are often only known to the movies and series
+//! databases by their English or romanized names. Their names in Cyrillic, CJK or Arabic
+//! scripts are kept in their own database and indexed along with their transliterations,
+//! so that both "Москва слезам не верит" and "moskva slezam ne verit" find the title.
+//! Since this grows the index considerably, it is only done with the `akas` feature.
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+#[cfg(feature = "fetch")]
+use std::time::{Duration, Instant};
+
+use crate::imdb::db_file::{self, Compression};
+#[cfg(feature = "fetch")]
+use crate::imdb::service::IMDB_URL;
+use crate::imdb::title_id::TitleId;
+use crate::imdb::tokens;
+use crate::iter_next;
+#[cfg(feature = "fetch")]
+use crate::utils::cancel::{CancellableReader, Cancellation};
+#[cfg(feature = "fetch")]
+use crate::utils::io::file as io_file;
+use crate::utils::io::net as io_net;
+
+use fnv::FnvHashMap;
+#[cfg(feature = "fetch")]
+use humantime::format_duration;
+use tracing::{debug, info_span};
+#[cfg(feature = "fetch")]
+use url::Url;
+
+/// Errors when importing or loading the names of titles.
+#[derive(Debug, thiserror::Error)]
+#[error("IMDB akas error")]
+pub enum Error {
+  /// File-related error.
+  #[error("File handling error: {0}")]
+  File(#[from] crate::utils::io::file::Error),
+  /// Networking-related error.
+  #[cfg(feature = "fetch")]
+  #[error("Network handling error: {0}")]
+  Net(#[from] crate::utils::io::net::Error),
+  /// URL parsing error.
+  #[error("Error parsing URL: {0}")]
+  UrlParsing(#[from] url::ParseError),
+  /// Database file error.
+  #[error("Akas database error: {0}")]
+  DbFile(#[from] crate::imdb::db_file::Error),
+  /// ID parsing errors.
+  #[error("Error parsing ID: {0}")]
+  IdParsing(#[from] crate::imdb::title_id::Error),
+  /// General parsing errors.
+  #[error("Parsing error: {0}")]
+  Parsing(#[from] crate::utils::tokens::Error),
+  /// A name is not valid UTF-8.
+  #[error("Invalid name: {0}")]
+  Utf8(#[from] std::str::Utf8Error),
+  /// The database ends in the middle of a record.
+  #[error("Akas database is truncated")]
+  Truncated,
+  /// IO errors.
+  #[error("IO error: {0}")]
+  Io(#[from] io::Error),
+  /// The operation was cancelled.
+  #[error("Operation cancelled")]
+  Cancelled,
+}
+
+#[cfg(feature = "fetch")]
+const AKAS_FILENAME: &str = "title.akas.tsv.gz";
+pub(crate) const AKAS_DB_FILENAME: &str = "imdb-akas.tvrankdb";
+
+/// # Name records are composed of:
+///
+/// * 4 bytes: Title ID number (little-endian)
+/// * 2 bytes: Length of the name in bytes (little-endian)
+/// * The name, in UTF-8
+///
+/// Records are sorted by title ID.
+const RECORD_HEADER_LEN: usize = 6;
+
+/// Whether a name has letters of scripts other than Latin, e.g. Cyrillic, CJK or Arabic.
+///
+/// # Arguments
+///
+/// * `name` - The name of a title.
+fn has_non_latin_letters(name: &str) -> bool {
+  name
+    .chars()
+    .any(|c| c.is_alphabetic() && !matches!(c, '\0'..='\u{24F}' | '\u{1E00}'..='\u{1EFF}'))
+}
+
+/// The names of titles in non-Latin scripts.
+pub struct Akas {
+  names: FnvHashMap<u32, Vec<String>>,
+}
+
+impl Akas {
+  /// Returns the akas database in `cache_dir`, or [Error::Cancelled] if `cancellation` is
+  /// cancelled while it is being fetched and built.
+  ///
+  /// # Arguments
+  ///
+  /// * `cache_dir` - Directory path of the database files.
+  /// * `force_db_update` - True if the database should be updated regardless of its age.
+  /// * `progress_fn` - Function that keeps track of the download progress.
+  /// * `cancellation` - Token to cancel fetching and building the database.
+  /// * `compression` - Compression of a newly built database.
+  #[cfg(feature = "fetch")]
+  pub fn new_cancellable(
+    cache_dir: &Path,
+    force_db_update: bool,
+    progress_fn: impl Fn(Option<u64>, u64),
+    cancellation: &Cancellation,
+    compression: Compression,
+  ) -> Result<Self, Error> {
+    let one_month = Duration::from_secs(60 * 60 * 24 * 30);
+    let db_filename = cache_dir.join(AKAS_DB_FILENAME);
+
+    let needs_update = force_db_update
+      || !matches!(db_file::version(&db_filename)?, Some(db_file::VERSION))
+      || io_file::older_than(&io_file::open_existing(&db_filename)?, one_month);
+
+    if needs_update {
+      debug!("IMDB akas database does not exist or is outdated, going to fetch and build");
+      let result = Self::build_db_file(&db_filename, &progress_fn, cancellation, compression);
+
+      if cancellation.is_cancelled() {
+        return Err(Error::Cancelled);
+      }
+
+      result?;
+    }
+
+    let start = Instant::now();
+    let akas = match Self::read_db_file(&db_filename) {
+      Err(Error::DbFile(e)) if e.needs_rebuild() => {
+        debug!("IMDB akas database is damaged ({e}), going to fetch and rebuild");
+        Self::build_db_file(&db_filename, progress_fn, cancellation, compression)?;
+        Self::read_db_file(&db_filename)?
+      }
+      result => result?,
+    };
+    debug!("Read IMDB akas database in {}", format_duration(Instant::now().duration_since(start)));
+
+    Ok(akas)
+  }
+
+  /// Returns the akas database in `cache_dir` if it was already built, without fetching it,
+  /// or `None` if it does not exist or is in an older format.
+  ///
+  /// # Arguments
+  ///
+  /// * `cache_dir` - Directory path of the database files.
+  pub fn load(cache_dir: &Path) -> Result<Option<Self>, Error> {
+    let db_filename = cache_dir.join(AKAS_DB_FILENAME);
+
+    if db_file::version(&db_filename)? != Some(db_file::VERSION) {
+      debug!("IMDB akas database does not exist or is outdated, not loading it");
+      return Ok(None);
+    }
+
+    Self::read_db_file(&db_filename).map(Some)
+  }
+
+  /// Builds the akas database in `cache_dir` from an IMDB dump that was already downloaded,
+  /// instead of fetching it.
+  ///
+  /// The dump can either be gzip-compressed, if its name ends with `.gz`, or uncompressed.
+  ///
+  /// # Arguments
+  ///
+  /// * `akas_filename` - Path to the `title.akas` dump.
+  /// * `cache_dir` - Directory path of the database files.
+  /// * `compression` - Compression of the database.
+  pub fn build_from_files(
+    akas_filename: &Path,
+    cache_dir: &Path,
+    compression: Compression,
+  ) -> Result<(), Error> {
+    let file = fs::File::open(akas_filename)?;
+    let reader: Box<dyn BufRead> = if akas_filename.extension().is_some_and(|extension| extension == "gz") {
+      Box::new(io_net::make_fetcher(file, |_| {}))
+    } else {
+      Box::new(io::BufReader::new(file))
+    };
+
+    Self::import_db_file(reader, &cache_dir.join(AKAS_DB_FILENAME), compression)
+  }
+
+  #[cfg(feature = "fetch")]
+  fn build_db_file(
+    db_filename: &Path,
+    progress_fn: impl Fn(Option<u64>, u64),
+    cancellation: &Cancellation,
+    compression: Compression,
+  ) -> Result<(), Error> {
+    let imdb_url = Url::parse(IMDB_URL)?;
+    let akas_response = io_net::get_response(imdb_url.join(AKAS_FILENAME)?)?;
+    progress_fn(akas_response.content_length(), 0);
+
+    let akas_response = CancellableReader::new(akas_response, cancellation.clone());
+    let akas_fetcher = io_net::make_fetcher(akas_response, |bytes| progress_fn(None, bytes));
+
+    Self::import_db_file(akas_fetcher, db_filename, compression)
+  }
+
+  /// Imports the IMDB dump into the akas database, which only replaces the existing one once
+  /// it was fully written.
+  ///
+  /// # Arguments
+  ///
+  /// * `akas_reader` - Reader of the uncompressed akas dump.
+  /// * `db_filename` - Path to the akas database.
+  /// * `compression` - Compression of the database.
+  fn import_db_file(
+    akas_reader: impl BufRead,
+    db_filename: &Path,
+    compression: Compression,
+  ) -> Result<(), Error> {
+    let _span = info_span!("import", db = "akas").entered();
+    let partial = db_file::partial_filename(db_filename);
+
+    let result = (|| -> Result<(), Error> {
+      let mut writer = db_file::create(&partial, compression)?;
+      akas_import(akas_reader, &mut writer)?;
+      writer.finish()?;
+
+      fs::rename(&partial, db_filename)?;
+      db_file::write_checksum(db_filename)?;
+      Ok(())
+    })();
+
+    if result.is_err() {
+      let _ = fs::remove_file(&partial);
+    }
+
+    result
+  }
+
+  fn read_db_file(db_filename: &Path) -> Result<Self, Error> {
+    let data = db_file::read_verified(db_filename)?;
+    Self::from_records(db_file::titles(data)?.titles())
+  }
+
+  /// Index the records of a database by title.
+  fn from_records(mut records: &[u8]) -> Result<Self, Error> {
+    let mut names: FnvHashMap<u32, Vec<String>> = FnvHashMap::default();
+
+    while !records.is_empty() {
+      let header = records.get(..RECORD_HEADER_LEN).ok_or(Error::Truncated)?;
+      let id = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+      let len = usize::from(u16::from_le_bytes([header[4], header[5]]));
+      let name = records
+        .get(RECORD_HEADER_LEN..RECORD_HEADER_LEN + len)
+        .ok_or(Error::Truncated)?;
+      names.entry(id).or_default().push(std::str::from_utf8(name)?.to_owned());
+      records = &records[RECORD_HEADER_LEN + len..];
+    }
+
+    Ok(Self { names })
+  }
+
+  /// Returns the names of a title in non-Latin scripts.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - IMDB ID of the title.
+  pub fn of_title(&self, id: &TitleId) -> &[String] {
+    u32::try_from(id.as_usize())
+      .ok()
+      .and_then(|id| self.names.get(&id))
+      .map_or(&[], Vec::as_slice)
+  }
+
+  /// Returns the number of names of all titles.
+  pub fn len(&self) -> usize {
+    self.names.values().map(Vec::len).sum()
+  }
+
+  /// Returns true if there are no names.
+  pub fn is_empty(&self) -> bool {
+    self.names.is_empty()
+  }
+
+  /// Returns the number of titles with names in non-Latin scripts.
+  pub fn n_titles(&self) -> usize {
+    self.names.len()
+  }
+}
+
+/// Parse the `title.akas` dump and write the names in non-Latin scripts as records sorted by
+/// title ID, without duplicates.
+///
+/// # Arguments
+///
+/// * `akas_reader` - TSV reader for akas.
+/// * `writer` - Writer of the records.
+fn akas_import(mut akas_reader: impl BufRead, mut writer: impl Write) -> Result<(), Error> {
+  let mut akas = Vec::new();
+  let mut line = String::new();
+
+  // Skip the first line.
+  akas_reader.read_line(&mut line)?;
+  line.clear();
+
+  while akas_reader.read_line(&mut line)? != 0 {
+    let trimmed = line.trim_end();
+
+    if !trimmed.is_empty() {
+      let mut columns = trimmed.split('\t');
+      let id = TitleId::try_from(iter_next!(columns)?.as_bytes())?;
+      let _ordering = iter_next!(columns)?;
+      let name = iter_next!(columns)?;
+
+      if let Ok(id) = u32::try_from(id.as_usize()) {
+        if name.as_bytes() != tokens::NOT_AVAIL
+          && name.len() <= usize::from(u16::MAX)
+          && has_non_latin_letters(name)
+        {
+          akas.push((id, name.to_owned()));
+        }
+      }
+    }
+
+    line.clear();
+  }
+
+  akas.sort_unstable();
+  akas.dedup();
+
+  for (id, name) in akas {
+    writer.write_all(&id.to_le_bytes())?;
+    writer.write_all(&(name.len() as u16).to_le_bytes())?;
+    writer.write_all(name.as_bytes())?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::imdb::akas::{akas_import, has_non_latin_letters, Akas};
+  use crate::imdb::db::Query;
+  use crate::imdb::service::Service;
+  use crate::imdb::testdata::{make_basics_reader, make_ratings_reader};
+  use crate::imdb::title_id::TitleId;
+  use crate::utils::search::SearchString;
+  use indoc::indoc;
+
+  fn make_akas() -> Akas {
+    let akas = indoc! {"
+      titleId\tordering\ttitle\tregion\tlanguage\ttypes\tattributes\tisOriginalTitle
+      tt0000001\t1\tCarmencita\t\\N\t\\N\toriginal\t\\N\t1
+      tt0000001\t2\tКарменсита\tRU\t\\N\timdbDisplay\t\\N\t0
+      tt0000001\t3\tКарменсита\tUA\t\\N\timdbDisplay\t\\N\t0
+      tt0000005\t1\tСцена в кузнице\tRU\t\\N\timdbDisplay\t\\N\t0
+      tt0000005\t2\t铁匠场景\tCN\t\\N\timdbDisplay\t\\N\t0
+      tt0000010\t1\tLa sortie de l'usine Lumière à Lyon\tFR\t\\N\timdbDisplay\t\\N\t0
+      tt0000010\t2\tالخروج من المصنع\tEG\tar\timdbDisplay\t\\N\t0
+    "};
+
+    let mut records = Vec::new();
+    akas_import(akas.as_bytes(), &mut records).unwrap();
+    Akas::from_records(&records).unwrap()
+  }
+
+  #[test]
+  fn test_non_latin_letters() {
+    assert!(!has_non_latin_letters("La sortie de l'usine Lumière à Lyon"));
+    assert!(!has_non_latin_letters("Đorđe Balašević: 1, 2, 3!"));
+    assert!(has_non_latin_letters("Москва слезам не верит"));
+    assert!(has_non_latin_letters("千と千尋の神隠し"));
+    assert!(has_non_latin_letters("Ζορμπάς"));
+  }
+
+  #[test]
+  fn test_import() {
+    let akas = make_akas();
+    assert_eq!(akas.len(), 4);
+    assert_eq!(akas.n_titles(), 3);
+    assert_eq!(akas.of_title(&TitleId::try_from("tt0000001").unwrap()), ["Карменсита"]);
+    assert_eq!(akas.of_title(&TitleId::try_from("tt0000005").unwrap()), ["Сцена в кузнице", "铁匠场景"]);
+    assert!(akas.of_title(&TitleId::try_from("tt0000002").unwrap()).is_empty());
+  }
+
+  #[test]
+  fn test_truncated() {
+    assert!(Akas::from_records(&[1, 0, 0, 0, 5, 0, b'a']).is_err());
+    assert!(Akas::from_records(&[1, 0, 0]).is_err());
+  }
+
+  #[test]
+  fn test_search() {
+    let mut imdb = Service::from_tsv_readers(make_basics_reader(), make_ratings_reader()).unwrap();
+    imdb.store_akas(&make_akas());
+
+    let ids = |name: &str| -> Vec<String> {
+      let name = SearchString::try_from(name).unwrap();
+      imdb
+        .by_title(&name, Query::Movies)
+        .iter()
+        .map(|title| title.title_id().to_string())
+        .collect()
+    };
+    assert_eq!(ids("Карменсита"), ["tt0000001"]);
+    assert_eq!(ids("karmensita"), ["tt0000001"]);
+    assert_eq!(ids("stsena v kuznitse"), ["tt0000005"]);
+    assert_eq!(ids("铁匠场景"), ["tt0000005"]);
+    assert_eq!(ids("Carmencita"), ["tt0000001"]);
+
+    let keywords = [SearchString::try_from("kuznitse").unwrap()];
+    assert_eq!(imdb.by_keywords(&keywords, Query::Movies).len(), 1);
+  }
+}
@@ -0,0 +1,275 @@
+#![warn(clippy::all)]
+
+//! Median ratings and votes of titles per genre and decade, to compare a title against the
+//! titles that are like it.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::imdb::genre::{Genre, Genres};
+use crate::imdb::ratings::Rating;
+use crate::imdb::title::Title;
+
+use fnv::FnvHashMap;
+
+/// Groups with fewer rated titles than this have no baseline, since their medians would not
+/// be meaningful.
+const MIN_TITLES: usize = 10;
+
+/// Whether a title is a series, its genre and the decade it was released in.
+type Key = (bool, Genre, u16);
+
+/// The median rating and votes of a group of titles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Baseline {
+  rating: u8,
+  votes: u32,
+  titles: usize,
+}
+
+impl Baseline {
+  /// The median rating, out of 100.
+  pub fn rating(&self) -> u8 {
+    self.rating
+  }
+
+  /// The median number of votes.
+  pub fn votes(&self) -> u32 {
+    self.votes
+  }
+
+  /// The number of rated titles in the group.
+  pub fn titles(&self) -> usize {
+    self.titles
+  }
+
+  /// Compute the baseline of the given ratings.
+  ///
+  /// # Arguments
+  ///
+  /// * `ratings` - Ratings of the titles in the group.
+  fn from_ratings(ratings: &[Rating]) -> Self {
+    let mut values: Vec<u8> = ratings.iter().map(Rating::rating).collect();
+    let mut votes: Vec<u32> = ratings.iter().map(Rating::votes).collect();
+    let middle = ratings.len() / 2;
+
+    Self {
+      rating: *values.select_nth_unstable(middle).1,
+      votes: *votes.select_nth_unstable(middle).1,
+      titles: ratings.len(),
+    }
+  }
+}
+
+/// How a title compares to the baseline of its genre and decade.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Comparison {
+  genre: Genre,
+  decade: u16,
+  baseline: Baseline,
+  rating: Rating,
+}
+
+impl Comparison {
+  /// The genre of the baseline.
+  pub fn genre(&self) -> Genre {
+    self.genre
+  }
+
+  /// The first year of the decade of the baseline (e.g. 1990).
+  pub fn decade(&self) -> u16 {
+    self.decade
+  }
+
+  /// The baseline the title is compared to.
+  pub fn baseline(&self) -> &Baseline {
+    &self.baseline
+  }
+
+  /// How much higher the rating of the title is than the median, out of 10.
+  pub fn rating_difference(&self) -> f64 {
+    (f64::from(self.rating.rating()) - f64::from(self.baseline.rating)) / 10.0
+  }
+
+  /// How many times more votes the title has than the median.
+  pub fn votes_ratio(&self) -> f64 {
+    f64::from(self.rating.votes()) / f64::from(self.baseline.votes.max(1))
+  }
+}
+
+impl fmt::Display for Comparison {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let difference = self.rating_difference();
+    let group = format!("{}s {}", self.decade, self.genre);
+
+    if difference > 0.0 {
+      write!(f, "+{difference:.1} above {group} median")?;
+    } else if difference < 0.0 {
+      write!(f, "{difference:.1} below {group} median")?;
+    } else {
+      write!(f, "at {group} median")?;
+    }
+
+    write!(f, ", {:.1}x its votes", self.votes_ratio())
+  }
+}
+
+/// Median ratings and votes of movies and series per genre and decade, along with the
+/// average rating of all of them, computed once when the databases are loaded.
+///
+/// Clones share the same baselines.
+#[derive(Debug, Clone, Default)]
+pub struct Baselines {
+  baselines: Arc<FnvHashMap<Key, Baseline>>,
+  mean_rating: f64,
+}
+
+impl Baselines {
+  /// Compute the baselines of the given titles.
+  ///
+  /// # Arguments
+  ///
+  /// * `titles` - Movies and series to compute the baselines of.
+  pub(crate) fn new<'a, 'storage: 'a>(titles: impl Iterator<Item = &'a Title<'storage>>) -> Self {
+    let (mut sum, mut count) = (0u64, 0u64);
+
+    let mut baselines = Self::from_samples(titles.filter_map(|title| {
+      let rating = title.rating()?;
+      sum += u64::from(rating.rating());
+      count += 1;
+      Some((title.title_type().is_series(), title.genres(), title.start_year()?, rating))
+    }));
+
+    if count > 0 {
+      baselines.mean_rating = sum as f64 / count as f64;
+    }
+
+    baselines
+  }
+
+  /// Compute the baselines of titles given as whether they are series, their genres,
+  /// release year and rating.
+  ///
+  /// # Arguments
+  ///
+  /// * `samples` - The titles to compute the baselines of.
+  fn from_samples(samples: impl Iterator<Item = (bool, Genres, u16, Rating)>) -> Self {
+    let mut groups: FnvHashMap<Key, Vec<Rating>> = FnvHashMap::default();
+
+    for (is_series, genres, year, rating) in samples {
+      for genre in genres.iter() {
+        groups.entry((is_series, genre, decade(year))).or_default().push(rating);
+      }
+    }
+
+    let baselines = groups
+      .into_iter()
+      .filter(|(_, ratings)| ratings.len() >= MIN_TITLES)
+      .map(|(key, ratings)| (key, Baseline::from_ratings(&ratings)))
+      .collect();
+
+    Self { baselines: Arc::new(baselines), mean_rating: 0.0 }
+  }
+
+  /// The average rating (out of 100) of all rated titles, including those without a release
+  /// year.
+  pub fn mean_rating(&self) -> f64 {
+    self.mean_rating
+  }
+
+  /// The baseline of a genre and decade.
+  ///
+  /// # Arguments
+  ///
+  /// * `series` - Whether the baseline of series or of movies is requested.
+  /// * `genre` - The genre.
+  /// * `year` - Any year of the decade.
+  pub fn get(&self, series: bool, genre: Genre, year: u16) -> Option<&Baseline> {
+    self.baselines.get(&(series, genre, decade(year)))
+  }
+
+  /// Compare a title to the baseline of the decade it was released in and of the first of
+  /// its genres that has one.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - The title to compare.
+  pub fn compare(&self, title: &Title) -> Option<Comparison> {
+    self.compare_values(title.title_type().is_series(), title.genres(), title.start_year()?, title.rating()?)
+  }
+
+  fn compare_values(&self, series: bool, genres: Genres, year: u16, rating: Rating) -> Option<Comparison> {
+    genres.iter().find_map(|genre| {
+      let baseline = *self.get(series, genre, year)?;
+      Some(Comparison { genre, decade: decade(year), baseline, rating })
+    })
+  }
+}
+
+fn decade(year: u16) -> u16 {
+  year / 10 * 10
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::imdb::baseline::Baselines;
+  use crate::imdb::genre::{Genre, Genres};
+  use crate::imdb::ratings::Rating;
+
+  fn genres(list: &[Genre]) -> Genres {
+    let mut genres = Genres::default();
+    for &genre in list {
+      genres.add(genre);
+    }
+    genres
+  }
+
+  fn baselines() -> Baselines {
+    let thrillers = (0..11).map(|i| {
+      (false, genres(&[Genre::Thriller]), 1990 + i % 10, Rating::new(60 + i as u8, 100 * (u32::from(i) + 1)))
+    });
+    let dramas = (0..5).map(|_| (false, genres(&[Genre::Drama]), 1995, Rating::new(80, 1000)));
+    Baselines::from_samples(thrillers.chain(dramas))
+  }
+
+  #[test]
+  fn test_baselines() {
+    let baselines = baselines();
+
+    let baseline = baselines.get(false, Genre::Thriller, 1999).unwrap();
+    assert_eq!(baseline.rating(), 65);
+    assert_eq!(baseline.votes(), 600);
+    assert_eq!(baseline.titles(), 11);
+
+    assert!(baselines.get(true, Genre::Thriller, 1995).is_none());
+    assert!(baselines.get(false, Genre::Thriller, 2000).is_none());
+    assert!(baselines.get(false, Genre::Drama, 1995).is_none());
+  }
+
+  #[test]
+  fn test_compare() {
+    let baselines = baselines();
+    let title_genres = genres(&[Genre::Drama, Genre::Thriller]);
+
+    let comparison = baselines
+      .compare_values(false, title_genres, 1994, Rating::new(78, 1500))
+      .unwrap();
+    assert_eq!(comparison.genre(), Genre::Thriller);
+    assert_eq!(comparison.decade(), 1990);
+    assert_eq!(comparison.to_string(), "+1.3 above 1990s Thriller median, 2.5x its votes");
+
+    let comparison = baselines
+      .compare_values(false, title_genres, 1994, Rating::new(61, 600))
+      .unwrap();
+    assert_eq!(comparison.to_string(), "-0.4 below 1990s Thriller median, 1.0x its votes");
+
+    let comparison = baselines
+      .compare_values(false, title_genres, 1994, Rating::new(65, 300))
+      .unwrap();
+    assert_eq!(comparison.to_string(), "at 1990s Thriller median, 0.5x its votes");
+
+    assert!(baselines
+      .compare_values(false, genres(&[Genre::Drama]), 1994, Rating::new(78, 1500))
+      .is_none());
+  }
+}
@@ -0,0 +1,688 @@
+#![warn(clippy::all)]
+
+//! Builder of the IMDB service, for the options of loading and updating its databases.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+#[cfg(feature = "fetch")]
+use std::time::Duration;
+use std::time::Instant;
+
+#[cfg(feature = "akas")]
+use crate::imdb::akas::{Akas, Error as AkasError, AKAS_DB_FILENAME};
+use crate::imdb::db_binary::IndexLayout;
+#[cfg(feature = "fetch")]
+use crate::imdb::db_file::Compression;
+use crate::imdb::names::{NameIndex, NAMES_DB_FILENAME};
+use crate::imdb::service::{Error, Service, IMPORT_STATS_FILENAME, MOVIES_DB_FILENAME, SERIES_DB_FILENAME};
+use crate::imdb::tsv_import::ImportStats;
+#[cfg(feature = "fetch")]
+use crate::utils::cancel::Cancellation;
+
+use humantime::format_duration;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use tracing::debug;
+
+/// When the databases are fetched from IMDB and rebuilt.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UpdatePolicy {
+  /// Rebuild the databases if they are missing, damaged, in an older format or older than
+  /// the maximum age. This is the default when the `fetch` feature is enabled.
+  #[cfg(feature = "fetch")]
+  #[default]
+  IfOutdated,
+  /// Always rebuild the databases.
+  #[cfg(feature = "fetch")]
+  Always,
+  /// Rebuild the databases if they are missing, damaged or in an older format, but load
+  /// them as they are if they are only older than the maximum age, so that applications can
+  /// answer right away and rebuild them in the background once [Service::is_outdated] says
+  /// so, e.g. by building another service with [UpdatePolicy::IfOutdated] in a thread.
+  #[cfg(feature = "fetch")]
+  InBackground,
+  /// Never rebuild the databases, and fail if they are missing, damaged or outdated.
+  #[cfg_attr(not(feature = "fetch"), default)]
+  Never,
+}
+
+/// The threads that parse and query the databases.
+enum Threads {
+  /// The global thread pool of rayon.
+  Global,
+  /// A thread pool of the given number of threads, created for the service.
+  Count(usize),
+  /// A thread pool given by the application.
+  Pool(Arc<ThreadPool>),
+}
+
+/// Builder of a [Service], created by [Service::builder].
+pub struct Builder<'a> {
+  cache_dir: Option<PathBuf>,
+  update_policy: UpdatePolicy,
+  threads: Threads,
+  index_layout: IndexLayout,
+  name_index: bool,
+  lazy: bool,
+  #[cfg(feature = "fetch")]
+  max_age: Duration,
+  #[cfg(feature = "fetch")]
+  wait_for_update: bool,
+  #[cfg(feature = "fetch")]
+  update_wait_timeout: Duration,
+  #[cfg(feature = "fetch")]
+  progress_fn: Box<dyn Fn(Option<u64>, u64) + 'a>,
+  #[cfg(feature = "fetch")]
+  cancellation: Cancellation,
+  #[cfg(feature = "fetch")]
+  compression: Compression,
+  #[cfg(not(feature = "fetch"))]
+  _progress_fn: std::marker::PhantomData<&'a ()>,
+}
+
+impl Default for Builder<'_> {
+  fn default() -> Self {
+    Self {
+      cache_dir: None,
+      update_policy: UpdatePolicy::default(),
+      threads: Threads::Global,
+      index_layout: IndexLayout::default(),
+      name_index: false,
+      lazy: false,
+      #[cfg(feature = "fetch")]
+      max_age: Duration::from_secs(60 * 60 * 24 * 30),
+      #[cfg(feature = "fetch")]
+      wait_for_update: true,
+      #[cfg(feature = "fetch")]
+      update_wait_timeout: Duration::from_secs(60 * 60),
+      #[cfg(feature = "fetch")]
+      progress_fn: Box::new(|_, _| {}),
+      #[cfg(feature = "fetch")]
+      cancellation: Cancellation::new(),
+      #[cfg(feature = "fetch")]
+      compression: Compression::None,
+      #[cfg(not(feature = "fetch"))]
+      _progress_fn: std::marker::PhantomData,
+    }
+  }
+}
+
+impl<'a> Builder<'a> {
+  /// Set the directory of the database files, which is required.
+  ///
+  /// # Arguments
+  ///
+  /// * `cache_dir` - Directory path of the database files.
+  pub fn cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+    self.cache_dir = Some(cache_dir.into());
+    self
+  }
+
+  /// Set when the databases are fetched from IMDB and rebuilt.
+  ///
+  /// # Arguments
+  ///
+  /// * `update_policy` - The update policy, which defaults to [UpdatePolicy::IfOutdated].
+  pub fn update_policy(mut self, update_policy: UpdatePolicy) -> Self {
+    self.update_policy = update_policy;
+    self
+  }
+
+  /// Set the number of threads that parse and query the databases, in a thread pool of the
+  /// service instead of the global thread pool of rayon.
+  ///
+  /// # Arguments
+  ///
+  /// * `threads` - The number of threads, which defaults to the size of the global thread
+  ///   pool (the number of CPUs unless configured otherwise).
+  pub fn threads(mut self, threads: usize) -> Self {
+    self.threads = Threads::Count(threads);
+    self
+  }
+
+  /// Set the thread pool that parses and queries the databases, instead of the global
+  /// thread pool of rayon, e.g. to share it with other parts of the application.
+  ///
+  /// # Arguments
+  ///
+  /// * `thread_pool` - The thread pool.
+  pub fn thread_pool(mut self, thread_pool: Arc<ThreadPool>) -> Self {
+    self.threads = Threads::Pool(thread_pool);
+    self
+  }
+
+  /// Set the age after which the databases are rebuilt with [UpdatePolicy::IfOutdated], or
+  /// considered outdated by [Service::is_outdated].
+  ///
+  /// # Arguments
+  ///
+  /// * `max_age` - The maximum age, which defaults to 30 days.
+  #[cfg(feature = "fetch")]
+  pub fn max_age(mut self, max_age: Duration) -> Self {
+    self.max_age = max_age;
+    self
+  }
+
+  /// Set whether to wait for another process that is rebuilding the databases, instead of
+  /// failing with [Error::UpdateInProgress]. Once it is done, the databases are only rebuilt
+  /// again if they are still outdated, even with [UpdatePolicy::Always].
+  ///
+  /// # Arguments
+  ///
+  /// * `wait` - Whether to wait for the other process, which defaults to true.
+  #[cfg(feature = "fetch")]
+  pub fn wait_for_update(mut self, wait: bool) -> Self {
+    self.wait_for_update = wait;
+    self
+  }
+
+  /// Set how long to wait for another process that is rebuilding the databases, after
+  /// which [Builder::build] fails with [Error::UpdateInProgress], e.g. if that process
+  /// stalled while holding its lock.
+  ///
+  /// # Arguments
+  ///
+  /// * `timeout` - How long to wait, which defaults to an hour.
+  #[cfg(feature = "fetch")]
+  pub fn update_wait_timeout(mut self, timeout: Duration) -> Self {
+    self.update_wait_timeout = timeout;
+    self
+  }
+
+  /// Set a function that keeps track of the progress when the databases are rebuilt. The
+  /// IMDB dumps are imported as they are downloaded, so it is called with the Content-Length
+  /// of each dump once its import starts, and then with the number of compressed bytes of
+  /// the dump that the import consumed since its last call.
+  ///
+  /// # Arguments
+  ///
+  /// * `progress_fn` - The progress function.
+  #[cfg(feature = "fetch")]
+  pub fn progress(mut self, progress_fn: impl Fn(Option<u64>, u64) + 'a) -> Self {
+    self.progress_fn = Box::new(progress_fn);
+    self
+  }
+
+  /// Set a token to cancel fetching and building the databases, in which case
+  /// [Builder::build] returns [Error::Cancelled] and leaves no partially built databases
+  /// behind.
+  ///
+  /// # Arguments
+  ///
+  /// * `cancellation` - The cancellation token.
+  #[cfg(feature = "fetch")]
+  pub fn cancellation(mut self, cancellation: &Cancellation) -> Self {
+    self.cancellation = cancellation.clone();
+    self
+  }
+
+  /// Set how the titles are indexed once they are parsed, either in one index per parsing
+  /// thread or in a single merged index that is slower to build but faster to query.
+  ///
+  /// # Arguments
+  ///
+  /// * `index_layout` - The index layout, which defaults to one index per thread.
+  pub fn index_layout(mut self, index_layout: IndexLayout) -> Self {
+    self.index_layout = index_layout;
+    self
+  }
+
+  /// Set whether an index of titles by name is kept alongside the databases and loaded with
+  /// them, instead of indexing the titles by name every time they are loaded, so that
+  /// short-lived applications start sooner. Name queries then go through the sorted names
+  /// of the index rather than hash maps, and keyword queries go through all of them. With
+  /// the `trie` feature, keyword queries only go through the names containing their words
+  /// instead, whose words are indexed on the first keyword query.
+  ///
+  /// The index is written once the databases were loaded without it, and written again
+  /// once it is older than them. With the `akas` feature, the akas database is not loaded
+  /// nor updated while the index is up to date, since the index has its names.
+  ///
+  /// # Arguments
+  ///
+  /// * `name_index` - Whether to keep the index, which defaults to false.
+  pub fn name_index(mut self, name_index: bool) -> Self {
+    self.name_index = name_index;
+    self
+  }
+
+  /// Set whether movies and series are only parsed and indexed once they are first queried,
+  /// instead of when the service is built, so that applications that only query one of
+  /// them start about twice as fast. The rating baselines are then computed once they are
+  /// first needed, which parses both.
+  ///
+  /// Queries cannot fail, so titles that cannot be parsed on first use are logged as an
+  /// error instead of failing [Builder::build], which cannot happen with database files
+  /// since they are verified against their checksums when they are read.
+  ///
+  /// # Arguments
+  ///
+  /// * `lazy` - Whether to parse the titles on first use, which defaults to false.
+  pub fn lazy(mut self, lazy: bool) -> Self {
+    self.lazy = lazy;
+    self
+  }
+
+  /// Set the compression of newly built databases. Databases are read regardless of their
+  /// compression.
+  ///
+  /// # Arguments
+  ///
+  /// * `compression` - The compression, which defaults to none.
+  #[cfg(feature = "fetch")]
+  pub fn compression(mut self, compression: Compression) -> Self {
+    self.compression = compression;
+    self
+  }
+
+  /// Load the databases, fetching and building them first according to the update policy,
+  /// and return the service holding them.
+  pub fn build(self) -> Result<Service, Error> {
+    let cache_dir = self.cache_dir.as_deref().ok_or(Error::NoCacheDir)?;
+    let movies_db_filename = cache_dir.join(MOVIES_DB_FILENAME);
+    let series_db_filename = cache_dir.join(SERIES_DB_FILENAME);
+
+    let thread_pool = match &self.threads {
+      Threads::Global => None,
+      Threads::Count(threads) => Some(Arc::new(ThreadPoolBuilder::new().num_threads(*threads).build()?)),
+      Threads::Pool(thread_pool) => Some(Arc::clone(thread_pool)),
+    };
+
+    let start = Instant::now();
+    let (movies_data, series_data) = match self.update_policy {
+      UpdatePolicy::Never => Service::read_db_files(&movies_db_filename, &series_db_filename)?,
+      // Databases are never too old to be loaded in the background policy.
+      #[cfg(feature = "fetch")]
+      UpdatePolicy::InBackground => {
+        self.fetch_and_read(&movies_db_filename, &series_db_filename, false, Duration::MAX)?
+      }
+      #[cfg(feature = "fetch")]
+      update_policy => self.fetch_and_read(
+        &movies_db_filename,
+        &series_db_filename,
+        update_policy == UpdatePolicy::Always,
+        self.max_age,
+      )?,
+    };
+    debug!("Read IMDB database in {}", format_duration(Instant::now().duration_since(start)));
+
+    let built_at = fs::metadata(&movies_db_filename)?.modified()?;
+
+    let names = self.read_name_index(cache_dir, &movies_db_filename, &series_db_filename);
+    let with_names = names.is_some();
+
+    let start = Instant::now();
+    let mut service = Service::from_data(
+      movies_data,
+      series_data,
+      built_at,
+      thread_pool,
+      self.index_layout,
+      names,
+      self.lazy,
+    )?;
+    debug!("Parsed IMDB database in {}", format_duration(Instant::now().duration_since(start)));
+
+    match ImportStats::load(&cache_dir.join(IMPORT_STATS_FILENAME)) {
+      Ok(stats) => service.set_import_stats(stats),
+      Err(e) => debug!("Could not read the IMDB import statistics: {e}"),
+    }
+
+    #[cfg(feature = "fetch")]
+    service.set_max_age(self.max_age);
+
+    #[cfg(feature = "akas")]
+    let akas = if with_names {
+      None
+    } else {
+      self.akas(cache_dir)?
+    };
+    #[cfg(feature = "akas")]
+    let with_akas = match akas {
+      Some(akas) => {
+        let start = Instant::now();
+        service.store_akas_lazily(akas);
+        debug!("Indexed IMDB akas in {}", format_duration(Instant::now().duration_since(start)));
+        true
+      }
+      None => false,
+    };
+    #[cfg(not(feature = "akas"))]
+    let with_akas = false;
+
+    if self.name_index && !with_names {
+      let start = Instant::now();
+      match service.write_name_index(&cache_dir.join(NAMES_DB_FILENAME), with_akas) {
+        Ok(()) => debug!(
+          "Wrote IMDB index of titles by name in {}",
+          format_duration(Instant::now().duration_since(start))
+        ),
+        Err(e) => debug!("Could not write the IMDB index of titles by name: {e}"),
+      }
+    }
+
+    Ok(service)
+  }
+
+  /// Read the index of titles by name if it is kept and up to date with the databases.
+  ///
+  /// # Arguments
+  ///
+  /// * `cache_dir` - Directory path of the database files.
+  /// * `movies_db_filename` - Path to the movies database.
+  /// * `series_db_filename` - Path to the series database.
+  fn read_name_index(
+    &self,
+    cache_dir: &Path,
+    movies_db_filename: &Path,
+    series_db_filename: &Path,
+  ) -> Option<NameIndex> {
+    if !self.name_index {
+      return None;
+    }
+
+    #[cfg(feature = "akas")]
+    let akas_db_filename = Some(cache_dir.join(AKAS_DB_FILENAME)).filter(|filename| filename.exists());
+    #[cfg(not(feature = "akas"))]
+    let akas_db_filename: Option<PathBuf> = None;
+
+    let filename = cache_dir.join(NAMES_DB_FILENAME);
+    let db_filenames: Vec<&Path> = [movies_db_filename, series_db_filename]
+      .into_iter()
+      .chain(akas_db_filename.as_deref())
+      .collect();
+    if !NameIndex::is_fresh(&filename, &db_filenames, akas_db_filename.is_some()) {
+      debug!("IMDB index of titles by name does not exist or is outdated");
+      return None;
+    }
+
+    let start = Instant::now();
+    match NameIndex::read(&filename) {
+      Ok(names) => {
+        debug!(
+          "Read IMDB index of titles by name in {}",
+          format_duration(Instant::now().duration_since(start))
+        );
+        Some(names)
+      }
+      Err(e) => {
+        debug!("Could not read the IMDB index of titles by name: {e}");
+        None
+      }
+    }
+  }
+
+  /// Load the names of titles in non-Latin scripts, fetching and building their database
+  /// first according to the update policy.
+  ///
+  /// # Arguments
+  ///
+  /// * `cache_dir` - Directory path of the database files.
+  #[cfg(feature = "akas")]
+  fn akas(&self, cache_dir: &std::path::Path) -> Result<Option<Akas>, Error> {
+    let result = match self.update_policy {
+      UpdatePolicy::Never => Akas::load(cache_dir),
+      #[cfg(feature = "fetch")]
+      UpdatePolicy::InBackground => match Akas::load(cache_dir) {
+        Ok(None) => {
+          Akas::new_cancellable(cache_dir, false, &self.progress_fn, &self.cancellation, self.compression)
+            .map(Some)
+        }
+        result => result,
+      },
+      #[cfg(feature = "fetch")]
+      update_policy => Akas::new_cancellable(
+        cache_dir,
+        update_policy == UpdatePolicy::Always,
+        &self.progress_fn,
+        &self.cancellation,
+        self.compression,
+      )
+      .map(Some),
+    };
+
+    match result {
+      Err(AkasError::Cancelled) => Err(Error::Cancelled),
+      result => Ok(result?),
+    }
+  }
+
+  /// Fetch and build the databases if needed, then read them, rebuilding them once more if
+  /// they turn out to be damaged.
+  ///
+  /// # Arguments
+  ///
+  /// * `movies_db_filename` - Path to the movies database.
+  /// * `series_db_filename` - Path to the series database.
+  /// * `force_db_update` - True if the databases should be updated regardless of their age.
+  /// * `max_age` - The age after which the databases are updated.
+  #[cfg(feature = "fetch")]
+  fn fetch_and_read(
+    &self,
+    movies_db_filename: &Path,
+    series_db_filename: &Path,
+    force_db_update: bool,
+    max_age: Duration,
+  ) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let ensure_db_files = |force_db_update| {
+      Service::ensure_db_files(
+        movies_db_filename,
+        series_db_filename,
+        max_age,
+        force_db_update,
+        self.wait_for_update.then_some(self.update_wait_timeout),
+        &self.progress_fn,
+        &self.cancellation,
+        self.compression,
+      )
+    };
+
+    ensure_db_files(force_db_update)?;
+
+    if self.cancellation.is_cancelled() {
+      return Err(Error::Cancelled);
+    }
+
+    match Service::read_db_files(movies_db_filename, series_db_filename) {
+      Err(Error::DbFile(e)) if e.needs_rebuild() => {
+        debug!("IMDB database is damaged ({e}), going to fetch and rebuild");
+        ensure_db_files(true)?;
+        Service::read_db_files(movies_db_filename, series_db_filename)
+      }
+      result => result,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::io::{self, BufRead};
+
+  use crate::imdb::db::Query;
+  use crate::imdb::testdata::{make_basics_reader, make_ratings_reader};
+
+  fn write_dump(path: &std::path::Path, mut reader: impl BufRead) {
+    io::copy(&mut reader, &mut fs::File::create(path).unwrap()).unwrap();
+  }
+
+  #[test]
+  fn build() {
+    assert!(matches!(Service::builder().build(), Err(Error::NoCacheDir)));
+
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let builder = || Service::builder().cache_dir(dir.path()).update_policy(UpdatePolicy::Never);
+    assert!(builder().build().is_err());
+
+    let basics = dir.path().join("title.basics.tsv");
+    let ratings = dir.path().join("title.ratings.tsv");
+    write_dump(&basics, make_basics_reader());
+    write_dump(&ratings, make_ratings_reader());
+    Service::build_from_files(&basics, &ratings, dir.path(), |_, _| {}, Default::default()).unwrap();
+
+    let imdb = builder().threads(2).build().unwrap();
+    assert_eq!(imdb.n_titles(Query::Movies), 11);
+    let stats = imdb.last_import_stats().unwrap();
+    assert_eq!(stats.movies_written(), 11);
+    assert_eq!(stats.series_written(), imdb.n_titles(Query::Series));
+    assert_eq!(stats.parse_warnings(), 0);
+    assert_eq!(imdb.memory_stats().shards().len(), 2);
+
+    let thread_pool = Arc::new(ThreadPoolBuilder::new().num_threads(3).build().unwrap());
+    let imdb = builder().thread_pool(thread_pool).build().unwrap();
+    assert_eq!(imdb.memory_stats().shards().len(), 3);
+
+    let title = crate::utils::search::SearchString::try_from("carmencita").unwrap();
+    assert_eq!(imdb.by_title(&title, Query::Movies).len(), 1);
+
+    let imdb = builder().threads(2).index_layout(IndexLayout::Single).build().unwrap();
+    assert_eq!(imdb.memory_stats().shards().len(), 1);
+    assert_eq!(imdb.n_titles(Query::Movies), 11);
+    assert_eq!(imdb.by_title(&title, Query::Movies).len(), 1);
+
+    let imdb = builder().threads(2).lazy(true).build().unwrap();
+    assert!(imdb.memory_stats().shards().is_empty());
+    assert_eq!(imdb.by_title(&title, Query::Movies).len(), 1);
+    assert_eq!(imdb.memory_stats().shards().len(), 2);
+  }
+
+  #[test]
+  #[cfg(all(feature = "fetch", not(feature = "akas")))]
+  fn in_background() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let basics = dir.path().join("title.basics.tsv");
+    let ratings = dir.path().join("title.ratings.tsv");
+    write_dump(&basics, make_basics_reader());
+    write_dump(&ratings, make_ratings_reader());
+    Service::build_from_files(&basics, &ratings, dir.path(), |_, _| {}, Default::default()).unwrap();
+
+    let imdb = Service::builder()
+      .cache_dir(dir.path())
+      .update_policy(UpdatePolicy::InBackground)
+      .build()
+      .unwrap();
+    assert!(!imdb.is_outdated());
+
+    let built_at = imdb.built_at();
+    let imdb = Service::builder()
+      .cache_dir(dir.path())
+      .update_policy(UpdatePolicy::InBackground)
+      .max_age(Duration::from_secs(0))
+      .build()
+      .unwrap();
+    assert!(imdb.is_outdated());
+    assert_eq!(imdb.built_at(), built_at);
+    assert_eq!(imdb.n_titles(Query::Movies), 11);
+
+    let imdb = Service::from_tsv_readers(make_basics_reader(), make_ratings_reader()).unwrap();
+    assert!(!imdb.is_outdated());
+  }
+
+  #[test]
+  #[cfg(feature = "fetch")]
+  fn update_lock() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let lock = crate::utils::io::file::try_lock(&dir.path().join("imdb-update.lock")).unwrap();
+    assert!(lock.is_some());
+
+    let builder = || Service::builder().cache_dir(dir.path());
+    assert!(matches!(builder().wait_for_update(false).build(), Err(Error::UpdateInProgress)));
+    assert!(matches!(
+      builder().update_wait_timeout(Duration::from_millis(10)).build(),
+      Err(Error::UpdateInProgress)
+    ));
+
+    let cancellation = Cancellation::new();
+    cancellation.cancel();
+    assert!(matches!(builder().cancellation(&cancellation).build(), Err(Error::Cancelled)));
+  }
+
+  #[test]
+  fn name_index() {
+    use crate::utils::search::{SearchOptions, SearchScope, SearchString};
+
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let basics = dir.path().join("title.basics.tsv");
+    let ratings = dir.path().join("title.ratings.tsv");
+    write_dump(&basics, make_basics_reader());
+    write_dump(&ratings, make_ratings_reader());
+    Service::build_from_files(&basics, &ratings, dir.path(), |_, _| {}, Default::default()).unwrap();
+
+    let builder = || {
+      Service::builder()
+        .cache_dir(dir.path())
+        .update_policy(UpdatePolicy::Never)
+        .threads(2)
+    };
+    let plain = builder().build().unwrap();
+    assert!(!dir.path().join(NAMES_DB_FILENAME).exists());
+
+    let imdb = builder().name_index(true).build().unwrap();
+    assert!(dir.path().join(NAMES_DB_FILENAME).exists());
+    assert!(imdb.memory_stats().shards_total().by_title() > 0);
+
+    let ids = |titles: Vec<&crate::imdb::title::Title>| -> Vec<String> {
+      titles.iter().map(|title| title.title_id().to_string()).collect()
+    };
+    let title = |name| SearchString::try_from(name).unwrap();
+    let keywords = [title("kineto")];
+    let regex = regex::Regex::new("^(le|la) ").unwrap();
+
+    for layout in [IndexLayout::Sharded, IndexLayout::Single] {
+      let imdb = builder().name_index(true).index_layout(layout).build().unwrap();
+      assert!(imdb.memory_stats().shards_total().by_title() < plain.memory_stats().shards_total().by_title());
+      assert!(imdb.memory_stats().storage() > plain.memory_stats().storage());
+      assert_eq!(imdb.n_titles(Query::Movies), 11);
+      assert_eq!(imdb.by_keywords(&keywords, Query::Movies).len(), 3);
+      assert_eq!(
+        imdb
+          .by_title_normalized(&title("sortie de l'usine lumiere a lyon"), Query::Movies)
+          .len(),
+        1
+      );
+
+      for scope in [SearchScope::All, SearchScope::PrimaryTitles, SearchScope::OriginalTitles] {
+        let plain = plain.with_options(SearchOptions::new().scope(scope));
+        let imdb = imdb.with_options(SearchOptions::new().scope(scope));
+
+        for name in ["carmencita", "leaving the factory", "la sortie de l'usine lumiere a lyon", "lumiere"] {
+          assert_eq!(
+            ids(imdb.by_title(&title(name), Query::Movies)),
+            ids(plain.by_title(&title(name), Query::Movies))
+          );
+        }
+        assert_eq!(
+          ids(imdb.by_title_and_year(&title("carmencita"), 1894, Query::Movies)),
+          ids(plain.by_title_and_year(&title("carmencita"), 1894, Query::Movies))
+        );
+        assert!(imdb.by_title_and_year(&title("carmencita"), 1895, Query::Movies).is_empty());
+        assert_eq!(
+          ids(imdb.by_title_normalized(&title("sortie de l'usine lumiere a lyon"), Query::Movies)),
+          ids(plain.by_title_normalized(&title("sortie de l'usine lumiere a lyon"), Query::Movies))
+        );
+        assert_eq!(
+          ids(imdb.by_title_regex(&regex, Query::Movies)),
+          ids(plain.by_title_regex(&regex, Query::Movies))
+        );
+        assert_eq!(
+          ids(imdb.by_keywords(&keywords, Query::Movies)),
+          ids(plain.by_keywords(&keywords, Query::Movies))
+        );
+        assert_eq!(
+          ids(imdb.by_keywords_and_year(&keywords, 1894, Query::Movies)),
+          ids(plain.by_keywords_and_year(&keywords, 1894, Query::Movies))
+        );
+
+        let mut streamed = ids(imdb.iter_by_keywords(&keywords, Query::Movies).collect());
+        streamed.sort();
+        assert_eq!(streamed, ids(plain.by_keywords(&keywords, Query::Movies)));
+      }
+    }
+
+    fs::write(dir.path().join(NAMES_DB_FILENAME), b"damaged").unwrap();
+    let imdb = builder().name_index(true).build().unwrap();
+    assert!(imdb.memory_stats().shards_total().by_title() > 0);
+    assert_eq!(imdb.by_title(&title("carmencita"), Query::Movies).len(), 1);
+  }
+}
@@ -0,0 +1,277 @@
+#![warn(clippy::all)]
+
+//! Bundles of pre-built movies and series databases, to bootstrap a cache by downloading
+//! them instead of importing the IMDB dumps.
+//!
+//! A bundle is a gzip stream of [MAGIC], a format version byte, and the movies then the
+//! series database files, each preceded by its length as a little-endian `u64` and its
+//! SHA-256 checksum. The databases have to be in the current version of their format to be
+//! installed.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::imdb::db_file;
+use crate::imdb::service::IMPORT_STATS_FILENAME;
+
+use flate2::write::GzEncoder;
+use ring::digest::{Context, SHA256, SHA256_OUTPUT_LEN};
+
+/// Identifies bundles of pre-built databases.
+const MAGIC: &[u8; 8] = b"TVRANKPB";
+
+/// Version of the bundle format.
+const VERSION: u8 = 1;
+
+/// Errors when publishing or installing bundles.
+#[derive(Debug, thiserror::Error)]
+#[error("Pre-built database bundle error")]
+pub enum Error {
+  /// The data is not a bundle of pre-built databases.
+  #[error("Not a pre-built database bundle")]
+  NotABundle,
+  /// The bundle was published in a format this version does not support.
+  #[error("Unsupported pre-built database bundle version {0} (expected {VERSION})")]
+  UnsupportedVersion(u8),
+  /// A database in the bundle does not match its checksum.
+  #[error("Checksum mismatch for the {0} database in the bundle")]
+  ChecksumMismatch(&'static str),
+  /// A database in the bundle is in another version of the database format.
+  #[error("The {0} database in the bundle was built by an incompatible version")]
+  IncompatibleDatabase(&'static str),
+  /// Database file error.
+  #[error("Database file error: {0}")]
+  DbFile(#[from] crate::imdb::db_file::Error),
+  /// IO error.
+  #[error("IO error: {0}")]
+  Io(#[from] io::Error),
+}
+
+/// A writer that computes the SHA-256 checksum of what is written through it.
+struct HashingWriter<W> {
+  writer: W,
+  context: Context,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let written = self.writer.write(buf)?;
+    self.context.update(&buf[..written]);
+    Ok(written)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.writer.flush()
+  }
+}
+
+/// Write a bundle of the given database files, after verifying them against their checksums.
+///
+/// # Arguments
+///
+/// * `movies_db_filename` - Path to the movies database.
+/// * `series_db_filename` - Path to the series database.
+/// * `writer` - Where to write the bundle to.
+pub(crate) fn publish(
+  movies_db_filename: &Path,
+  series_db_filename: &Path,
+  writer: impl Write,
+) -> Result<(), Error> {
+  let mut encoder = GzEncoder::new(writer, flate2::Compression::best());
+  encoder.write_all(MAGIC)?;
+  encoder.write_all(&[VERSION])?;
+
+  for filename in [movies_db_filename, series_db_filename] {
+    let data = db_file::read_verified(filename)?;
+    encoder.write_all(&(data.len() as u64).to_le_bytes())?;
+    encoder.write_all(ring::digest::digest(&SHA256, &data).as_ref())?;
+    encoder.write_all(&data)?;
+  }
+
+  encoder.finish()?.flush()?;
+  Ok(())
+}
+
+/// Read a bundle and install its databases, replacing the given database files only once
+/// both were read and validated.
+///
+/// # Arguments
+///
+/// * `reader` - Decompressed contents of the bundle.
+/// * `movies_db_filename` - Path to the movies database.
+/// * `series_db_filename` - Path to the series database.
+pub(crate) fn install(
+  mut reader: impl Read,
+  movies_db_filename: &Path,
+  series_db_filename: &Path,
+) -> Result<(), Error> {
+  let mut magic = [0; MAGIC.len()];
+  read_exact(&mut reader, &mut magic)?;
+  if &magic != MAGIC {
+    return Err(Error::NotABundle);
+  }
+
+  let mut version = [0; 1];
+  read_exact(&mut reader, &mut version)?;
+  if version[0] != VERSION {
+    return Err(Error::UnsupportedVersion(version[0]));
+  }
+
+  let movies_partial = db_file::partial_filename(movies_db_filename);
+  let series_partial = db_file::partial_filename(series_db_filename);
+
+  let result = install_db(&mut reader, &movies_partial, "movies")
+    .and_then(|()| install_db(&mut reader, &series_partial, "series"))
+    .and_then(|()| {
+      fs::rename(&movies_partial, movies_db_filename)?;
+      fs::rename(&series_partial, series_db_filename)?;
+      db_file::write_checksum(movies_db_filename)?;
+      db_file::write_checksum(series_db_filename)?;
+      // The statistics of a previous import do not describe the installed databases.
+      let _ = fs::remove_file(movies_db_filename.with_file_name(IMPORT_STATS_FILENAME));
+      Ok(())
+    });
+
+  if result.is_err() {
+    let _ = fs::remove_file(&movies_partial);
+    let _ = fs::remove_file(&series_partial);
+  }
+
+  result
+}
+
+/// Copy one database from a bundle to a file, validating its checksum.
+///
+/// # Arguments
+///
+/// * `reader` - Bundle positioned at the length of the database.
+/// * `filename` - Path of the file to write the database to.
+/// * `name` - Name of the database, for error reporting.
+fn install_db(reader: &mut impl Read, filename: &Path, name: &'static str) -> Result<(), Error> {
+  let mut len = [0; 8];
+  read_exact(reader, &mut len)?;
+  let len = u64::from_le_bytes(len);
+
+  let mut checksum = [0; SHA256_OUTPUT_LEN];
+  read_exact(reader, &mut checksum)?;
+
+  let file = BufWriter::new(File::create(filename)?);
+  let mut writer = HashingWriter { writer: file, context: Context::new(&SHA256) };
+
+  if io::copy(&mut reader.take(len), &mut writer)? != len {
+    return Err(Error::NotABundle);
+  }
+
+  writer.flush()?;
+
+  if writer.context.finish().as_ref() != checksum {
+    return Err(Error::ChecksumMismatch(name));
+  }
+
+  if db_file::version(filename)? != Some(db_file::VERSION) {
+    return Err(Error::IncompatibleDatabase(name));
+  }
+
+  Ok(())
+}
+
+/// Like [Read::read_exact], but reports a truncated bundle as an invalid one.
+fn read_exact(reader: &mut impl Read, buf: &mut [u8]) -> Result<(), Error> {
+  reader.read_exact(buf).map_err(|e| match e.kind() {
+    io::ErrorKind::UnexpectedEof => Error::NotABundle,
+    _ => Error::Io(e),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use flate2::read::GzDecoder;
+
+  fn db(titles: &[u8]) -> Vec<u8> {
+    [b"TVRANKDB", &[db_file::VERSION, 0][..], titles].concat()
+  }
+
+  fn setup() -> (tempfile::TempDir, std::path::PathBuf, std::path::PathBuf) {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let movies = dir.path().join("movies");
+    let series = dir.path().join("series");
+    (dir, movies, series)
+  }
+
+  #[test]
+  fn roundtrip() {
+    let (_src_dir, src_movies, src_series) = setup();
+    fs::write(&src_movies, db(b"movies data")).unwrap();
+    db_file::write_checksum(&src_movies).unwrap();
+    fs::write(&src_series, db(b"series data")).unwrap();
+    db_file::write_checksum(&src_series).unwrap();
+
+    let mut bundle = Vec::new();
+    publish(&src_movies, &src_series, &mut bundle).unwrap();
+
+    let (_dst_dir, dst_movies, dst_series) = setup();
+    install(GzDecoder::new(bundle.as_slice()), &dst_movies, &dst_series).unwrap();
+    assert_eq!(fs::read(&dst_movies).unwrap(), db(b"movies data"));
+    assert_eq!(fs::read(&dst_series).unwrap(), db(b"series data"));
+    assert!(!db_file::partial_filename(&dst_movies).exists());
+    assert_eq!(db_file::read_verified(&dst_series).unwrap(), db(b"series data"));
+  }
+
+  #[test]
+  fn not_a_bundle() {
+    let (_dir, movies, series) = setup();
+    assert!(matches!(install(&b"garbage"[..], &movies, &series), Err(Error::NotABundle)));
+    assert!(matches!(install(&b"TVRANKPB\x09"[..], &movies, &series), Err(Error::UnsupportedVersion(9))));
+  }
+
+  #[test]
+  fn incompatible() {
+    let (_src_dir, src_movies, src_series) = setup();
+    fs::write(&src_movies, b"movies data").unwrap();
+    db_file::write_checksum(&src_movies).unwrap();
+    fs::write(&src_series, db(b"series data")).unwrap();
+    db_file::write_checksum(&src_series).unwrap();
+
+    let mut bundle = Vec::new();
+    publish(&src_movies, &src_series, &mut bundle).unwrap();
+
+    let (_dst_dir, dst_movies, dst_series) = setup();
+    let result = install(GzDecoder::new(bundle.as_slice()), &dst_movies, &dst_series);
+    assert!(matches!(result, Err(Error::IncompatibleDatabase("movies"))));
+    assert!(!dst_movies.exists());
+  }
+
+  #[test]
+  fn corrupted() {
+    let (_src_dir, src_movies, src_series) = setup();
+    fs::write(&src_movies, db(b"movies data")).unwrap();
+    db_file::write_checksum(&src_movies).unwrap();
+    fs::write(&src_series, db(b"series data")).unwrap();
+    db_file::write_checksum(&src_series).unwrap();
+
+    let mut bundle = Vec::new();
+    publish(&src_movies, &src_series, &mut bundle).unwrap();
+
+    let mut decompressed = Vec::new();
+    GzDecoder::new(bundle.as_slice()).read_to_end(&mut decompressed).unwrap();
+    let last = decompressed.len() - 1;
+    decompressed[last] ^= 0xff;
+
+    let (_dst_dir, dst_movies, dst_series) = setup();
+    fs::write(&dst_movies, b"old movies").unwrap();
+
+    let result = install(decompressed.as_slice(), &dst_movies, &dst_series);
+    assert!(matches!(result, Err(Error::ChecksumMismatch("series"))));
+    assert_eq!(fs::read(&dst_movies).unwrap(), b"old movies");
+    assert!(!dst_series.exists());
+    assert!(!db_file::partial_filename(&dst_movies).exists());
+    assert!(!db_file::partial_filename(&dst_series).exists());
+
+    decompressed.truncate(last);
+    let result = install(decompressed.as_slice(), &dst_movies, &dst_series);
+    assert!(matches!(result, Err(Error::NotABundle)));
+  }
+}
@@ -0,0 +1,366 @@
+#![warn(clippy::all)]
+
+//! User-defined columns computed from titles, for custom metrics to be displayed and sorted
+//! by alongside the fields of titles.
+//!
+//! ```
+//! use tvrank::imdb::{ImdbColumn, ImdbColumnValue, ImdbColumns};
+//!
+//! let mut columns = ImdbColumns::new();
+//! columns.register(ImdbColumn::age());
+//! columns.register(ImdbColumn::new("Decade", |title| {
+//!   title.start_year().map(|year| ImdbColumnValue::Integer(i64::from(year / 10 * 10)))
+//! }));
+//! assert!(columns.get("decade").is_some());
+//! ```
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::imdb::filter::current_year;
+use crate::imdb::ranked;
+use crate::imdb::title::Title;
+use crate::utils::search::SearchString;
+
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+
+/// A value computed for a title.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+  /// An integer, e.g. a number of years.
+  Integer(i64),
+  /// A floating point number, e.g. a ratio.
+  Float(f64),
+  /// Free text.
+  Text(String),
+  /// A size in bytes, displayed in binary units.
+  Bytes(u64),
+}
+
+impl Value {
+  /// Compare two values, numbers by their magnitude and before any text.
+  ///
+  /// # Arguments
+  ///
+  /// * `other` - The value to compare against.
+  pub fn compare(&self, other: &Self) -> Ordering {
+    match (self.as_f64(), other.as_f64()) {
+      (Some(a), Some(b)) => a.total_cmp(&b),
+      (Some(_), None) => Ordering::Less,
+      (None, Some(_)) => Ordering::Greater,
+      (None, None) => match (self, other) {
+        (Value::Text(a), Value::Text(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+      },
+    }
+  }
+
+  fn as_f64(&self) -> Option<f64> {
+    match self {
+      Value::Integer(value) => Some(*value as f64),
+      Value::Float(value) => Some(*value),
+      Value::Bytes(value) => Some(*value as f64),
+      Value::Text(_) => None,
+    }
+  }
+}
+
+impl fmt::Display for Value {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Value::Integer(value) => write!(f, "{value}"),
+      Value::Float(value) => write!(f, "{value:.2}"),
+      Value::Text(value) => write!(f, "{value}"),
+      Value::Bytes(value) => {
+        const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+        if *value < 1024 {
+          return write!(f, "{value} B");
+        }
+
+        let mut size = *value as f64 / 1024.0;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+          size /= 1024.0;
+          unit += 1;
+        }
+
+        write!(f, "{size:.1} {}", UNITS[unit])
+      }
+    }
+  }
+}
+
+type ComputeFn = dyn Fn(&Title) -> Option<Value> + Send + Sync;
+
+/// A named column whose values are computed from titles.
+///
+/// Columns are cheap to clone, clones share the same function.
+#[derive(Clone)]
+pub struct Column {
+  name: String,
+  compute: Arc<ComputeFn>,
+}
+
+impl Column {
+  /// Create a column.
+  ///
+  /// # Arguments
+  ///
+  /// * `name` - Name of the column, used as its header and to look it up.
+  /// * `compute` - Function computing the value of the column for a title, if any.
+  pub fn new(
+    name: impl Into<String>,
+    compute: impl Fn(&Title) -> Option<Value> + Send + Sync + 'static,
+  ) -> Self {
+    Self { name: name.into(), compute: Arc::new(compute) }
+  }
+
+  /// Column with the number of years since a title was released.
+  pub fn age() -> Self {
+    Self::new("Age", |title| {
+      title
+        .start_year()
+        .map(|year| Value::Integer(i64::from(current_year()) - i64::from(year)))
+    })
+  }
+
+  /// Column with the relevance of titles to the keywords they were found with, the same
+  /// score as [Service::by_keywords_ranked](crate::imdb::Imdb::by_keywords_ranked).
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - The keywords the titles were found with.
+  pub fn relevance(keywords: &[SearchString]) -> Self {
+    let keywords: Vec<String> = ranked::included(keywords).into_iter().map(String::from).collect();
+    Self::new("Relevance", move |title| {
+      let keywords: Vec<&str> = keywords.iter().map(String::as_str).collect();
+      Some(Value::Float(ranked::relevance(title, &keywords)))
+    })
+  }
+
+  /// The name of the column.
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  /// The value of the column for the given title, if any.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - The title to compute the value for.
+  pub fn value(&self, title: &Title) -> Option<Value> {
+    (self.compute)(title)
+  }
+
+  /// Compare two titles by their values in this column, titles without a value being less
+  /// than titles with one.
+  ///
+  /// # Arguments
+  ///
+  /// * `a` - The first title.
+  /// * `b` - The second title.
+  pub fn compare(&self, a: &Title, b: &Title) -> Ordering {
+    match (self.value(a), self.value(b)) {
+      (Some(a), Some(b)) => a.compare(&b),
+      (Some(_), None) => Ordering::Greater,
+      (None, Some(_)) => Ordering::Less,
+      (None, None) => Ordering::Equal,
+    }
+  }
+}
+
+impl fmt::Debug for Column {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Column").field("name", &self.name).finish_non_exhaustive()
+  }
+}
+
+/// A set of columns, looked up by their case-insensitive names.
+#[derive(Debug, Clone, Default)]
+pub struct Columns {
+  columns: Vec<Column>,
+}
+
+impl Columns {
+  /// Construct an empty set of columns.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Add a column, replacing any column with the same name.
+  ///
+  /// # Arguments
+  ///
+  /// * `column` - The column to add.
+  pub fn register(&mut self, column: Column) {
+    match self.columns.iter_mut().find(|c| c.name.eq_ignore_ascii_case(&column.name)) {
+      Some(existing) => *existing = column,
+      None => self.columns.push(column),
+    }
+  }
+
+  /// Find a column by its case-insensitive name.
+  ///
+  /// # Arguments
+  ///
+  /// * `name` - Name of the column.
+  pub fn get(&self, name: &str) -> Option<&Column> {
+    self.columns.iter().find(|column| column.name.eq_ignore_ascii_case(name))
+  }
+
+  /// Iterate over the columns in the order they were added.
+  pub fn iter(&self) -> impl Iterator<Item = &Column> {
+    self.columns.iter()
+  }
+
+  /// Whether there are no columns.
+  pub fn is_empty(&self) -> bool {
+    self.columns.is_empty()
+  }
+
+  /// The values of all columns for the given title, which serialize as a map from column
+  /// names to values.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - The title to compute the values for.
+  pub fn values(&self, title: &Title) -> Values<'_> {
+    Values { values: self.columns.iter().map(|column| (column.name(), column.value(title))).collect() }
+  }
+}
+
+/// Values of a set of columns for a title, in the order of the columns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Values<'columns> {
+  values: Vec<(&'columns str, Option<Value>)>,
+}
+
+impl<'columns> Values<'columns> {
+  /// Iterate over the column names and their values.
+  pub fn iter(&self) -> impl Iterator<Item = (&'columns str, Option<&Value>)> + '_ {
+    self.values.iter().map(|(name, value)| (*name, value.as_ref()))
+  }
+
+  /// Whether there are no values.
+  pub fn is_empty(&self) -> bool {
+    self.values.is_empty()
+  }
+}
+
+impl Serialize for Values<'_> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(self.values.len()))?;
+    for (name, value) in &self.values {
+      map.serialize_entry(name, value)?;
+    }
+    map.end()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::BufRead;
+
+  use super::*;
+  use crate::imdb::ratings::Ratings;
+  use crate::imdb::testdata::{make_basics_reader, make_ratings_reader};
+  use crate::imdb::title::TsvAction;
+  use crate::imdb::tsv_import::ImportStats;
+
+  fn make_titles() -> Vec<Title<'static>> {
+    let ratings = Ratings::from_tsv(make_ratings_reader()).unwrap();
+    make_basics_reader()
+      .lines()
+      .skip(1)
+      .filter_map(|line| {
+        let line = Box::leak(line.unwrap().into_boxed_str());
+        match Title::from_tsv(line.as_bytes(), &ratings, &mut ImportStats::default()).unwrap() {
+          TsvAction::Movie(title) | TsvAction::Series(title) => Some(title),
+          TsvAction::Skip => None,
+        }
+      })
+      .collect()
+  }
+
+  fn votes() -> Column {
+    Column::new("Votes", |title| title.rating().map(|rating| Value::Integer(i64::from(rating.votes()))))
+  }
+
+  #[test]
+  fn age() {
+    let titles = make_titles();
+    let title = titles.iter().find(|title| title.start_year() == Some(1894)).unwrap();
+    let age = i64::from(current_year()) - 1894;
+    assert_eq!(Column::age().value(title), Some(Value::Integer(age)));
+  }
+
+  #[test]
+  fn relevance() {
+    let titles = make_titles();
+    let keywords = [SearchString::try_from("carmencita").unwrap()];
+    let relevance = Column::relevance(&keywords);
+    let best = titles.iter().max_by(|a, b| relevance.compare(a, b)).unwrap();
+    assert_eq!(best.primary_title(), "Carmencita");
+    assert_eq!(relevance.name(), "Relevance");
+  }
+
+  #[test]
+  fn compare_values() {
+    assert_eq!(Value::Integer(2).compare(&Value::Float(1.5)), Ordering::Greater);
+    assert_eq!(Value::Float(-1.0).compare(&Value::Integer(0)), Ordering::Less);
+    assert_eq!(Value::Integer(100).compare(&Value::Text(String::from("a"))), Ordering::Less);
+    assert_eq!(Value::Text(String::from("a")).compare(&Value::Text(String::from("b"))), Ordering::Less);
+    assert_eq!(Value::Bytes(2048).compare(&Value::Integer(2000)), Ordering::Greater);
+  }
+
+  #[test]
+  fn display_bytes() {
+    assert_eq!(Value::Bytes(1023).to_string(), "1023 B");
+    assert_eq!(Value::Bytes(1536).to_string(), "1.5 KiB");
+    assert_eq!(Value::Bytes(5 * 1024 * 1024 * 1024).to_string(), "5.0 GiB");
+    assert_eq!(serde_json::to_string(&Value::Bytes(1536)).unwrap(), "1536");
+  }
+
+  #[test]
+  fn compare_titles() {
+    let mut titles = make_titles();
+    let votes = votes();
+    titles.sort_by(|a, b| votes.compare(b, a));
+
+    assert_eq!(titles[0].primary_title(), "Leaving the Factory");
+    assert!(titles
+      .windows(2)
+      .all(|pair| votes.compare(&pair[0], &pair[1]) != Ordering::Less));
+  }
+
+  #[test]
+  fn register() {
+    let mut columns = Columns::new();
+    assert!(columns.is_empty());
+
+    columns.register(Column::age());
+    columns.register(votes());
+    columns.register(Column::new("AGE", |_| None));
+
+    let names: Vec<_> = columns.iter().map(Column::name).collect();
+    assert_eq!(names, ["AGE", "Votes"]);
+    assert!(columns.get("age").is_some());
+    assert!(columns.get("missing").is_none());
+  }
+
+  #[test]
+  fn values() {
+    let titles = make_titles();
+    let mut columns = Columns::new();
+    columns.register(votes());
+    columns.register(Column::new("Name", |title| Some(Value::Text(title.primary_title().to_owned()))));
+
+    let values = columns.values(&titles[0]);
+    assert!(!values.is_empty());
+    assert_eq!(serde_json::to_string(&values).unwrap(), r#"{"Votes":1845,"Name":"Carmencita"}"#);
+  }
+}
@@ -1,15 +1,20 @@
 #![warn(clippy::all)]
 
+#[cfg(feature = "akas")]
+use crate::imdb::akas::Akas;
 use crate::imdb::db_impl::DbImpl;
+use crate::imdb::memory::ShardMemory;
+#[cfg(feature = "fs")]
+use crate::imdb::names::Posting;
 use crate::imdb::title::Title;
 use crate::imdb::title_id::TitleId;
-use crate::utils::search::SearchString;
+use crate::utils::search::{KeywordMatchKind, SearchScope, SearchString};
 
 use derive_more::{Display, From, Into};
+use regex::Regex;
 
 /// Specifies the type of title a query is for. E.g. Movies or Series.
-#[derive(Clone, Copy, Display)]
-#[display(fmt = "{}")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
 pub enum Query {
   /// Query the database of Movies.
   #[display(fmt = "movie")]
@@ -29,12 +34,12 @@ struct MoviesCookie(usize);
 struct SeriesCookie(usize);
 
 /// The primary API for access the movies and series database.
-pub struct Db {
-  movies: DbImpl<MoviesCookie>,
-  series: DbImpl<SeriesCookie>,
+pub struct Db<'storage> {
+  movies: DbImpl<'storage, MoviesCookie>,
+  series: DbImpl<'storage, SeriesCookie>,
 }
 
-impl Db {
+impl<'storage> Db<'storage> {
   /// Construct a database for movies and series, with a starting capacity for each.
   ///
   /// # Arguments
@@ -57,9 +62,57 @@ impl Db {
     self.series.n_titles()
   }
 
-  /// The total number of titles in the database.
-  pub fn n_entries(&self) -> usize {
-    self.n_movies() + self.n_series()
+  /// The number of titles in the movies or series database.
+  ///
+  /// # Arguments
+  ///
+  /// * `query` - Whether to count movies or series.
+  pub(crate) fn n_titles(&self, query: Query) -> usize {
+    match query {
+      Query::Movies => self.n_movies(),
+      Query::Series => self.n_series(),
+    }
+  }
+
+  /// Estimate the bytes used by the titles and indexes of the movies and series databases.
+  pub(crate) fn memory(&self) -> ShardMemory {
+    let mut memory = self.movies.memory();
+    memory += self.series.memory();
+    memory
+  }
+
+  /// Iterate over all titles in the database.
+  ///
+  /// # Arguments
+  ///
+  /// * `query` - Whether to iterate over movies or series.
+  pub(crate) fn titles(&self, query: Query) -> Box<dyn Iterator<Item = &Title<'storage>> + '_> {
+    match query {
+      Query::Movies => Box::new(self.movies.titles()),
+      Query::Series => Box::new(self.series.titles()),
+    }
+  }
+
+  /// Stop indexing the movies and series stored from now on by name, for when names are
+  /// looked up in an index stored alongside the databases.
+  pub(crate) fn skip_names(&mut self) {
+    self.movies.skip_names();
+    self.series.skip_names();
+  }
+
+  /// Iterate over the names the movies and series are indexed by, with the posting of each
+  /// of their titles.
+  #[cfg(feature = "fs")]
+  pub(crate) fn names(&self) -> impl Iterator<Item = (&str, Posting)> {
+    let movies = self
+      .movies
+      .names()
+      .map(|(name, field, stripped, id)| (name, Posting::new(id, Query::Movies, field, stripped)));
+    let series = self
+      .series
+      .names()
+      .map(|(name, field, stripped, id)| (name, Posting::new(id, Query::Series, field, stripped)));
+    movies.chain(series)
   }
 
   /// Insert a given title into the movies database.
@@ -67,7 +120,7 @@ impl Db {
   /// # Arguments
   ///
   /// * `title` - The title to be inserted.
-  pub(crate) fn store_movie(&mut self, title: Title<'static>) {
+  pub(crate) fn store_movie(&mut self, title: Title<'storage>) {
     self.movies.store_title(title)
   }
 
@@ -76,37 +129,63 @@ impl Db {
   /// # Arguments
   ///
   /// * `title` - The title to be inserted.
-  pub(crate) fn store_series(&mut self, title: Title<'static>) {
+  pub(crate) fn store_series(&mut self, title: Title<'storage>) {
     self.series.store_title(title)
   }
 
+  /// Index the movies and series by their names in non-Latin scripts as well.
+  ///
+  /// # Arguments
+  ///
+  /// * `akas` - The names of titles in non-Latin scripts.
+  #[cfg(feature = "akas")]
+  pub(crate) fn store_akas(&mut self, akas: &Akas) {
+    self.movies.store_akas(akas);
+    self.series.store_akas(akas);
+  }
+
   /// Return the title with the given ID from the database.
   ///
   /// # Arguments
   ///
   /// * `id` - Title ID to lookup.
   /// * `query` - Whether to query movies or series.
-  pub(crate) fn by_id(&self, id: &TitleId, query: Query) -> Option<&Title> {
+  pub(crate) fn by_id(&self, id: &TitleId, query: Query) -> Option<&Title<'_>> {
     match query {
       Query::Movies => self.movies.by_id(id),
       Query::Series => self.series.by_id(id),
     }
   }
 
+  /// Return the title with the given numeric part of its ID from the database.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - Numeric part of the title ID to lookup.
+  /// * `query` - Whether to query movies or series.
+  pub(crate) fn by_id_number(&self, id: usize, query: Query) -> Option<&Title<'_>> {
+    match query {
+      Query::Movies => self.movies.by_id_number(id),
+      Query::Series => self.series.by_id_number(id),
+    }
+  }
+
   /// Search for titles by name.
   ///
   /// # Arguments
   ///
   /// * `title` - The title name to search for.
+  /// * `scope` - Which names of the titles are searched.
   /// * `query` - Whether to query movies or series.
   pub(crate) fn by_title<'a>(
     &'a self,
     title: &SearchString,
+    scope: SearchScope,
     query: Query,
-  ) -> Box<dyn Iterator<Item = &'a Title> + 'a> {
+  ) -> Box<dyn Iterator<Item = &'a Title<'a>> + 'a> {
     match query {
-      Query::Movies => self.movies.by_title(title),
-      Query::Series => self.series.by_title(title),
+      Query::Movies => Box::new(self.movies.by_title(title, scope)),
+      Query::Series => Box::new(self.series.by_title(title, scope)),
     }
   }
 
@@ -116,16 +195,62 @@ impl Db {
   ///
   /// * `title` - The title name to search for.
   /// * `year` - The year to search for titles in.
+  /// * `scope` - Which names of the titles are searched.
   /// * `query` - Whether to query movies or series.
   pub(crate) fn by_title_and_year<'a>(
     &'a self,
     title: &SearchString,
     year: u16,
+    scope: SearchScope,
+    query: Query,
+  ) -> Box<dyn Iterator<Item = &'a Title<'a>> + 'a> {
+    match query {
+      Query::Movies => Box::new(self.movies.by_title_and_year(title, year, scope)),
+      Query::Series => Box::new(self.series.by_title_and_year(title, year, scope)),
+    }
+  }
+
+  /// Search for titles whose names match the given name once their leading articles are
+  /// stripped.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - The title name to search for.
+  /// * `year` - The year to search for titles in, if any.
+  /// * `scope` - Which names of the titles are searched.
+  /// * `query` - Whether to query movies or series.
+  pub(crate) fn by_stripped_title<'a>(
+    &'a self,
+    title: &SearchString,
+    year: Option<u16>,
+    scope: SearchScope,
+    query: Query,
+  ) -> Box<dyn Iterator<Item = &'a Title<'a>> + 'a> {
+    match query {
+      Query::Movies => Box::new(self.movies.by_stripped_title(title, year, scope)),
+      Query::Series => Box::new(self.series.by_stripped_title(title, year, scope)),
+    }
+  }
+
+  /// Search for titles whose names match a regular expression.
+  ///
+  /// # Arguments
+  ///
+  /// * `regex` - The regular expression to match lowercase title names with.
+  /// * `scope` - Which names of the titles are searched.
+  /// * `query` - Whether to query movies or series.
+  pub(crate) fn by_title_regex<'a, 'r>(
+    &'a self,
+    regex: &'r Regex,
+    scope: SearchScope,
     query: Query,
-  ) -> Box<dyn Iterator<Item = &'a Title> + 'a> {
+  ) -> Box<dyn Iterator<Item = &'a Title<'a>> + 'r>
+  where
+    'a: 'r,
+  {
     match query {
-      Query::Movies => Box::new(self.movies.by_title_and_year(title, year)),
-      Query::Series => Box::new(self.series.by_title_and_year(title, year)),
+      Query::Movies => Box::new(self.movies.by_title_regex(regex, scope)),
+      Query::Series => Box::new(self.series.by_title_regex(regex, scope)),
     }
   }
 
@@ -134,15 +259,19 @@ impl Db {
   /// # Arguments
   ///
   /// * `keywords` - Keywords to search for in title names.
+  /// * `kind` - How keywords are matched against title names.
+  /// * `scope` - Which names of the titles are searched.
   /// * `query` - Whether to query movies or series.
   pub(crate) fn by_keywords<'a, 'k>(
     &'a self,
     keywords: &'k [SearchString],
+    kind: KeywordMatchKind,
+    scope: SearchScope,
     query: Query,
-  ) -> Box<dyn Iterator<Item = &'a Title> + 'a> {
+  ) -> Box<dyn Iterator<Item = &'a Title<'a>> + 'a> {
     match query {
-      Query::Movies => Box::new(self.movies.by_keywords(keywords)),
-      Query::Series => Box::new(self.series.by_keywords(keywords)),
+      Query::Movies => Box::new(self.movies.by_keywords(keywords, kind, scope)),
+      Query::Series => Box::new(self.series.by_keywords(keywords, kind, scope)),
     }
   }
 
@@ -152,16 +281,20 @@ impl Db {
   ///
   /// * `keywords` - Keywords to search for in title names.
   /// * `year` - The year to search for titles in.
+  /// * `kind` - How keywords are matched against title names.
+  /// * `scope` - Which names of the titles are searched.
   /// * `query` - Whether to query movies or series.
   pub(crate) fn by_keywords_and_year<'a, 'k>(
     &'a self,
     keywords: &'k [SearchString],
     year: u16,
+    kind: KeywordMatchKind,
+    scope: SearchScope,
     query: Query,
-  ) -> Box<dyn Iterator<Item = &'a Title> + 'a> {
+  ) -> Box<dyn Iterator<Item = &'a Title<'a>> + 'a> {
     match query {
-      Query::Movies => Box::new(self.movies.by_keywords_and_year(keywords, year)),
-      Query::Series => Box::new(self.series.by_keywords_and_year(keywords, year)),
+      Query::Movies => Box::new(self.movies.by_keywords_and_year(keywords, year, kind, scope)),
+      Query::Series => Box::new(self.series.by_keywords_and_year(keywords, year, kind, scope)),
     }
   }
 }
@@ -174,6 +307,7 @@ mod test_db {
   use crate::imdb::testdata::{make_basics_reader, make_ratings_reader};
   use crate::imdb::title::Title;
   use crate::imdb::tsv_import::tsv_import;
+  use crate::imdb::tsv_import::ImportStats;
 
   #[test]
   fn test_to_binary() {
@@ -200,7 +334,7 @@ mod test_db {
     tsv_lines_iter.next();
 
     for line in tsv_lines_iter {
-      let title = Title::from_tsv(line.as_bytes(), &ratings).unwrap();
+      let title = Title::from_tsv(line.as_bytes(), &ratings, &mut ImportStats::default()).unwrap();
       let title: Option<Title> = title.into();
       let title = title.unwrap();
       titles_from_tsv.push(title);
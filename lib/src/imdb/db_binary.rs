@@ -1,13 +1,27 @@
 #![warn(clippy::all)]
 
+#[cfg(feature = "fs")]
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock};
+
+#[cfg(feature = "akas")]
+use crate::imdb::akas::Akas;
 use crate::imdb::db::{Db, Query};
+use crate::imdb::db_file::{self, Contents};
+use crate::imdb::memory::{MemoryStats, ShardMemory};
+use crate::imdb::names::{NameIndex, Posting};
 use crate::imdb::title::Title;
 use crate::imdb::title_id::TitleId;
-use crate::utils::search::SearchString;
+use crate::utils::search::{self, KeywordMatchKind, KeywordMatcher, Language, SearchScope, SearchString};
 
-use log::debug;
+use fnv::FnvHashSet;
 use parking_lot::{const_mutex, Mutex};
+use rayon::iter::Either;
 use rayon::prelude::*;
+use rayon::ThreadPool;
+use regex::Regex;
+use self_cell::self_cell;
+use tracing::{debug, error};
 
 /// Errors when loading database.
 #[derive(Debug, thiserror::Error)]
@@ -16,47 +30,281 @@ pub enum Error {
   /// Loading title error.
   #[error("Error loading title: {0}")]
   LoadingTitle(#[from] crate::imdb::title::Error),
+  /// Database file error.
+  #[error("Error reading database file: {0}")]
+  File(#[from] crate::imdb::db_file::Error),
+}
+
+/// How the titles of the databases are indexed once they are parsed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum IndexLayout {
+  /// One index per parsing thread, which is the fastest to build but has every query fan
+  /// out to all the indexes and merge their results.
+  #[default]
+  Sharded,
+  /// A single index merged from the ones of the parsing threads, which takes longer to
+  /// build but makes queries faster, e.g. for long-running servers.
+  Single,
+}
+
+/// The thread-handled databases that the titles of one database are parsed into.
+type Dbs<'storage> = Vec<Db<'storage>>;
+
+self_cell!(
+  /// The thread-handled databases of one database, movies or series, along with the
+  /// contents of the database whose titles they borrow.
+  struct Parsed {
+    owner: Arc<Contents>,
+    #[covariant]
+    dependent: Dbs,
+  }
+);
+
+/// The titles of one database, movies or series, and the thread-handled databases they are
+/// parsed into once they are needed.
+struct Titles {
+  /// The titles as stored in the database file, once decompressed.
+  contents: Arc<Contents>,
+  /// The thread-handled databases, which only hold titles of this database.
+  dbs: OnceLock<Parsed>,
+}
+
+impl Titles {
+  fn new(contents: Contents) -> Self {
+    Self { contents: Arc::new(contents), dbs: OnceLock::new() }
+  }
+
+  /// The thread-handled databases, if the titles were parsed already.
+  fn parsed(&self) -> Option<&[Db<'_>]> {
+    self.dbs.get().map(|parsed| parsed.borrow_dependent().as_slice())
+  }
+
+  /// Run a function on each of the thread-handled databases in parallel, if the titles were
+  /// parsed already.
+  ///
+  /// # Arguments
+  ///
+  /// * `f` - The function.
+  #[cfg(feature = "akas")]
+  fn for_each_db(&mut self, f: impl Fn(&mut Db<'_>) + Send + Sync) {
+    if let Some(parsed) = self.dbs.get_mut() {
+      parsed.with_dependent_mut(|_, dbs| dbs.par_iter_mut().for_each(f));
+    }
+  }
 }
 
 pub struct ServiceDbFromBinary {
-  dbs: Vec<Db>,
+  movies: Titles,
+  series: Titles,
+  layout: IndexLayout,
+  /// Thread pool that parses the titles, instead of the global thread pool.
+  thread_pool: Option<Arc<ThreadPool>>,
+  /// The names of titles in non-Latin scripts, kept to index the titles that are not parsed
+  /// yet by them.
+  #[cfg(feature = "akas")]
+  akas: Option<Akas>,
+  /// The index of titles by name stored alongside the databases, if it was loaded, in which
+  /// case names are looked up there and the shards do not index titles by name.
+  names: Option<NameIndex>,
+  /// Bytes of the titles of the database files, decompressed if they were compressed, and
+  /// of the index of titles by name.
+  storage: usize,
 }
 
 impl ServiceDbFromBinary {
   /// Load titles from the given binary data.
   ///
-  /// Loads titles from the provided binary content buffers (`movies_data` and
-  /// `series_data`) into one of the thread-handled databases.
+  /// Decompresses the provided binary content buffers (`movies_data` and `series_data`) if
+  /// needed, and parses their titles into the thread-handled databases, or only prepares
+  /// them to be parsed when they are first queried if `lazy` is set.
+  ///
+  /// # Arguments
+  ///
+  /// * `movies_data` - Contents of the movies database file.
+  /// * `series_data` - Contents of the series database file.
+  /// * `layout` - Whether the titles are kept in the indexes of the parsing threads or
+  ///   merged into a single one.
+  /// * `names` - The index of titles by name stored alongside the databases, if any, which
+  ///   spares indexing the titles by name while loading them.
+  /// * `thread_pool` - Thread pool that parses the titles, instead of the global thread pool.
+  /// * `lazy` - Whether movies and series are only parsed once they are first queried.
+  pub(crate) fn new(
+    movies_data: Vec<u8>,
+    series_data: Vec<u8>,
+    layout: IndexLayout,
+    names: Option<NameIndex>,
+    thread_pool: Option<Arc<ThreadPool>>,
+    lazy: bool,
+  ) -> Result<Self, Error> {
+    let movies_titles = db_file::titles(movies_data)?;
+    let series_titles = db_file::titles(series_data)?;
+    let storage = movies_titles.bytes() + series_titles.bytes() + names.as_ref().map_or(0, NameIndex::bytes);
+
+    let service_db = Self {
+      movies: Titles::new(movies_titles),
+      series: Titles::new(series_titles),
+      layout,
+      thread_pool,
+      #[cfg(feature = "akas")]
+      akas: None,
+      names,
+      storage,
+    };
+
+    if !lazy {
+      for (titles, query) in [(&service_db.movies, Query::Movies), (&service_db.series, Query::Series)] {
+        let parsed = Parsed::try_new(Arc::clone(&titles.contents), |contents| {
+          service_db.parse(contents.titles(), query)
+        })?;
+        let _ = titles.dbs.set(parsed);
+      }
+    }
+
+    Ok(service_db)
+  }
+
+  /// The thread-handled databases of movies or series, parsing their titles first if they
+  /// were not parsed yet. Since queries cannot fail, titles that cannot be parsed are
+  /// logged as an error and leave the databases empty, which cannot happen with database
+  /// files that were verified against their checksums.
+  ///
+  /// # Arguments
+  ///
+  /// * `query` - Whether the databases of movies or series are needed.
+  fn dbs(&self, query: Query) -> &[Db<'_>] {
+    let titles = match query {
+      Query::Movies => &self.movies,
+      Query::Series => &self.series,
+    };
+
+    let parsed = titles.dbs.get_or_init(|| {
+      debug!("Parsing IMDB {query} database on first use");
+
+      Parsed::new(Arc::clone(&titles.contents), |contents| {
+        let dbs = match self.parse(contents.titles(), query) {
+          Ok(dbs) => dbs,
+          Err(e) => {
+            error!("Could not parse IMDB {query} database: {e}");
+            Vec::new()
+          }
+        };
+
+        #[cfg(feature = "akas")]
+        let dbs = self.index_akas(dbs);
+
+        dbs
+      })
+    });
+
+    parsed.borrow_dependent()
+  }
+
+  /// Index the titles of newly parsed thread-handled databases by the names in non-Latin
+  /// scripts that were stored for them, if any.
   ///
   /// # Arguments
   ///
-  /// * `movies_data` - Binary movies data.
-  /// * `series_data` - Binary series data.
-  pub(crate) fn new(mut movies_data: &'static [u8], mut series_data: &'static [u8]) -> Result<Self, Error> {
+  /// * `dbs` - The thread-handled databases.
+  #[cfg(feature = "akas")]
+  fn index_akas<'storage>(&self, mut dbs: Vec<Db<'storage>>) -> Vec<Db<'storage>> {
+    if let Some(akas) = &self.akas {
+      self.in_thread_pool(|| dbs.par_iter_mut().for_each(|db| db.store_akas(akas)));
+    }
+
+    dbs
+  }
+
+  /// Parse titles into thread-handled databases.
+  ///
+  /// # Arguments
+  ///
+  /// * `data` - The titles as stored in the database file.
+  /// * `query` - Whether the titles are movies or series.
+  fn parse<'storage>(&self, data: &'storage [u8], query: Query) -> Result<Vec<Db<'storage>>, Error> {
+    self.in_thread_pool(|| match query {
+      Query::Movies => Self::titles_from_binary::<true>(data, self.layout, self.names.is_some()),
+      Query::Series => Self::titles_from_binary::<false>(data, self.layout, self.names.is_some()),
+    })
+  }
+
+  /// Run a function in the thread pool of the service, if it has one.
+  ///
+  /// # Arguments
+  ///
+  /// * `f` - The function.
+  fn in_thread_pool<R: Send>(&self, f: impl FnOnce() -> R + Send) -> R {
+    match &self.thread_pool {
+      Some(thread_pool) => thread_pool.install(f),
+      None => f(),
+    }
+  }
+
+  /// Merge the thread-handled databases into a single one.
+  ///
+  /// # Arguments
+  ///
+  /// * `dbs` - The thread-handled databases.
+  /// * `skip_names` - Whether the merged database does not index titles by name.
+  fn merge(dbs: Vec<Db<'_>>, skip_names: bool) -> Db<'_> {
+    let movies = dbs.iter().map(Db::n_movies).sum();
+    let series = dbs.iter().map(Db::n_series).sum();
+    let mut merged = Db::with_capacities(movies, series);
+    if skip_names {
+      merged.skip_names();
+    }
+
+    for db in &dbs {
+      for &title in db.titles(Query::Movies) {
+        merged.store_movie(title);
+      }
+
+      for &title in db.titles(Query::Series) {
+        merged.store_series(title);
+      }
+    }
+
+    merged
+  }
+
+  /// Loads titles from the provided binary content buffers into thread-handled databases.
+  ///
+  /// # Const Arguments
+  ///
+  /// * `IS_MOVIE` - Whether the titles are movies or series.
+  ///
+  /// # Arguments
+  ///
+  /// * `data` - The titles as stored in the database file.
+  /// * `layout` - Whether the titles are kept in the indexes of the parsing threads or
+  ///   merged into a single one.
+  /// * `skip_names` - Whether the databases do not index titles by name.
+  fn titles_from_binary<const IS_MOVIE: bool>(
+    mut data: &[u8],
+    layout: IndexLayout,
+    skip_names: bool,
+  ) -> Result<Vec<Db<'_>>, Error> {
     let nthreads = rayon::current_num_threads();
     let dbs = const_mutex(Vec::with_capacity(nthreads));
-    let movies_cursor: Mutex<&mut &'static [u8]> = const_mutex(&mut movies_data);
-    let series_cursor: Mutex<&mut &'static [u8]> = const_mutex(&mut series_data);
+    let cursor: Mutex<&mut &[u8]> = const_mutex(&mut data);
     let error: Mutex<Option<Error>> = const_mutex(None);
 
     rayon::scope(|scope| {
       for _ in 0..nthreads {
         let dbs = &dbs;
-        let movies_cursor = &movies_cursor;
-        let series_cursor = &series_cursor;
+        let cursor = &cursor;
         let error_mutex = &error;
 
         scope.spawn(move |_| {
-          let mut db = Db::with_capacities(1_900_000 / nthreads, 270_000 / nthreads);
-          let mut titles = Vec::with_capacity(100);
-          if let Err(err) = Self::movies_from_binary(movies_cursor, &mut titles, &mut db) {
-            let mut error_guard = error_mutex.lock();
-            if error_guard.is_none() {
-              *error_guard = Some(err);
-            }
-            return;
+          let mut db = if IS_MOVIE {
+            Db::with_capacities(1_900_000 / nthreads, 0)
+          } else {
+            Db::with_capacities(0, 270_000 / nthreads)
+          };
+          if skip_names {
+            db.skip_names();
           }
-          if let Err(err) = Self::series_from_binary(series_cursor, &mut titles, &mut db) {
+          let mut titles = Vec::with_capacity(100);
+          if let Err(err) = Self::store_from_binary::<IS_MOVIE>(cursor, &mut titles, &mut db) {
             let mut error_guard = error_mutex.lock();
             if error_guard.is_none() {
               *error_guard = Some(err);
@@ -69,44 +317,17 @@ impl ServiceDbFromBinary {
     });
 
     if let Some(err) = error.into_inner() {
-      Err(err)
-    } else {
-      Ok(Self { dbs: dbs.into_inner() })
+      return Err(err);
     }
-  }
-
-  /// Loads movies from the provided binary content buffers.
-  ///
-  /// # Arguments
-  ///
-  /// * `cursor` - Cursor at the binary to read the titles from.
-  /// * `titles` - Vector to store the titles temporarily before writing to the database.
-  /// * `db` - Database to store movies in.
-  fn movies_from_binary(
-    cursor: &Mutex<&mut &'static [u8]>,
-    titles: &mut Vec<Title<'static>>,
-    db: &mut Db,
-  ) -> Result<(), Error> {
-    Self::titles_from_binary::<true>(cursor, titles, db)
-  }
 
-  /// Loads series from the provided binary content buffers.
-  ///
-  /// # Arguments
-  ///
-  /// * `cursor` - Cursor at the binary to read the titles from.
-  /// * `titles` - Vector to store the titles temporarily before writing to the database.
-  /// * `db` - Database to store series in.
-  fn series_from_binary(
-    cursor: &Mutex<&mut &'static [u8]>,
-    titles: &mut Vec<Title<'static>>,
-    db: &mut Db,
-  ) -> Result<(), Error> {
-    Self::titles_from_binary::<false>(cursor, titles, db)
+    Ok(match layout {
+      IndexLayout::Sharded => dbs.into_inner(),
+      IndexLayout::Single => vec![Self::merge(dbs.into_inner(), skip_names)],
+    })
   }
 
-  /// Loads titles from the provided binary content buffers into the thread-handled
-  /// databases.
+  /// Stores the titles read from the provided binary content buffers into a thread-handled
+  /// database.
   ///
   /// # Const Arguments
   ///
@@ -117,10 +338,10 @@ impl ServiceDbFromBinary {
   /// * `cursor` - Cursor at the binary to read the titles from.
   /// * `titles` - Vector to store the titles temporarily before writing to the database.
   /// * `db` - Database to store movies or series.
-  fn titles_from_binary<const IS_MOVIE: bool>(
-    cursor: &Mutex<&mut &'static [u8]>,
-    titles: &mut Vec<Title<'static>>,
-    db: &mut Db,
+  fn store_from_binary<'storage, const IS_MOVIE: bool>(
+    cursor: &Mutex<&mut &'storage [u8]>,
+    titles: &mut Vec<Title<'storage>>,
+    db: &mut Db<'storage>,
   ) -> Result<(), Error> {
     loop {
       let mut cursor_guard = cursor.lock();
@@ -159,97 +380,610 @@ impl ServiceDbFromBinary {
   /// Returns a tuple containing two numbers, the first one is the number of movies and
   /// the second on the number of series contained in the database.
   pub(crate) fn n_entries(&self) -> (usize, usize) {
-    let mut total_movies = 0;
-    let mut total_series = 0;
+    let movies: Vec<usize> = self.dbs(Query::Movies).iter().map(Db::n_movies).collect();
+    let series: Vec<usize> = self.dbs(Query::Series).iter().map(Db::n_series).collect();
+
+    for (i, n) in movies.iter().enumerate() {
+      debug!("IMDB movies database (thread {i}) contains {n} movies");
+    }
+    for (i, n) in series.iter().enumerate() {
+      debug!("IMDB series database (thread {i}) contains {n} series");
+    }
 
-    for (i, db) in self.dbs.iter().enumerate() {
-      let movies = db.n_movies();
-      let series = db.n_series();
-      let entries = db.n_entries();
+    (movies.iter().sum(), series.iter().sum())
+  }
+
+  /// The number of movies or series across all thread-handled databases, parsing them first
+  /// if they were not parsed yet.
+  ///
+  /// # Arguments
+  ///
+  /// * `query` - Whether to count movies or series.
+  pub(crate) fn n_titles(&self, query: Query) -> usize {
+    self.dbs(query).iter().map(|db| db.n_titles(query)).sum()
+  }
 
-      total_movies += movies;
-      total_series += series;
+  /// Estimate the bytes used by the database files and by the titles and indexes of each
+  /// thread-handled database, with the movies and series parsed by each thread counted as
+  /// one shard. Titles that were not parsed yet are not counted.
+  pub(crate) fn memory_stats(&self) -> MemoryStats {
+    let memory = |titles: &Titles| -> Vec<ShardMemory> {
+      titles
+        .parsed()
+        .map_or_else(Vec::new, |dbs| dbs.iter().map(Db::memory).collect())
+    };
 
-      debug!("IMDB database (thread {i}) contains {movies} movies and {series} series ({entries} entries)");
+    let mut shards = memory(&self.movies);
+    let series = memory(&self.series);
+    for (i, memory) in series.into_iter().enumerate() {
+      match shards.get_mut(i) {
+        Some(shard) => *shard += memory,
+        None => shards.push(memory),
+      }
     }
 
-    (total_movies, total_series)
+    MemoryStats::new(self.storage, shards)
   }
 
-  pub(crate) fn by_id(&self, id: &TitleId, query: Query) -> Option<&Title> {
-    let res = self
-      .dbs
-      .par_iter()
-      .map(|db| db.by_id(id, query))
-      .filter(|res| res.is_some())
-      .flatten()
-      .collect::<Vec<_>>();
-
-    if res.is_empty() {
-      None
-    } else {
-      Some(unsafe { res.get_unchecked(0) })
+  /// Iterate over all titles across all thread-handled databases.
+  ///
+  /// # Arguments
+  ///
+  /// * `query` - Whether to iterate over movies or series.
+  pub(crate) fn titles(&self, query: Query) -> impl Iterator<Item = &Title<'_>> {
+    self.dbs(query).iter().flat_map(move |db| db.titles(query))
+  }
+
+  /// Index the titles of all the thread-handled databases by their names in non-Latin
+  /// scripts as well, parsing the titles that were not parsed yet first.
+  ///
+  /// # Arguments
+  ///
+  /// * `akas` - The names of titles in non-Latin scripts.
+  #[cfg(feature = "akas")]
+  pub(crate) fn store_akas(&mut self, akas: &Akas) {
+    self.dbs(Query::Movies);
+    self.dbs(Query::Series);
+
+    for titles in [&mut self.movies, &mut self.series] {
+      titles.for_each_db(|db| db.store_akas(akas));
+    }
+  }
+
+  /// Index the titles of all the thread-handled databases by their names in non-Latin
+  /// scripts as well, keeping the names to index the titles that are not parsed yet once
+  /// they are.
+  ///
+  /// # Arguments
+  ///
+  /// * `akas` - The names of titles in non-Latin scripts.
+  #[cfg(feature = "akas")]
+  pub(crate) fn store_akas_lazily(&mut self, akas: Akas) {
+    for titles in [&mut self.movies, &mut self.series] {
+      titles.for_each_db(|db| db.store_akas(&akas));
+    }
+
+    if self.movies.parsed().is_none() || self.series.parsed().is_none() {
+      self.akas = Some(akas);
     }
   }
 
-  pub(crate) fn by_title(&self, title: &SearchString, query: Query) -> Vec<&Title> {
+  pub(crate) fn by_id(&self, id: &TitleId, query: Query) -> Option<&Title<'_>> {
+    // IDs are unique across shards, so the lookup stops as soon as any shard has the title.
+    self.dbs(query).par_iter().find_map_any(|db| db.by_id(id, query))
+  }
+
+  pub(crate) fn by_id_any(&self, id: &TitleId) -> Option<(&Title<'_>, Query)> {
     self
-      .dbs
-      .par_iter()
-      .flat_map(|db| db.by_title(title, query).collect::<Vec<_>>())
+      .by_id(id, Query::Movies)
+      .map(|title| (title, Query::Movies))
+      .or_else(|| self.by_id(id, Query::Series).map(|title| (title, Query::Series)))
+  }
+
+  /// Collect the names the titles are indexed by in all thread-handled databases, with the
+  /// postings of their titles, to store them as an index of titles by name. There are none
+  /// if the names are already looked up in such an index.
+  #[cfg(feature = "fs")]
+  pub(crate) fn names(&self) -> BTreeMap<String, Vec<Posting>> {
+    let mut names: BTreeMap<String, Vec<Posting>> = BTreeMap::new();
+
+    let dbs = self.dbs(Query::Movies).iter().chain(self.dbs(Query::Series));
+    for (name, posting) in dbs.flat_map(Db::names) {
+      match names.get_mut(name) {
+        Some(postings) => postings.push(posting),
+        None => {
+          names.insert(name.to_owned(), vec![posting]);
+        }
+      }
+    }
+
+    names
+  }
+
+  /// Find a title by the numeric part of its ID, for the postings of the index of titles by
+  /// name. Unlike [ServiceDbFromBinary::by_id], shards are searched one after the other
+  /// since lookups are already done for many postings in parallel.
+  fn by_id_number(&self, id: usize, query: Query) -> Option<&Title<'_>> {
+    self.dbs(query).iter().find_map(|db| db.by_id_number(id, query))
+  }
+
+  /// The titles of postings of the index of titles by name that are of the given query and
+  /// search scope.
+  ///
+  /// # Arguments
+  ///
+  /// * `postings` - The postings of a name.
+  /// * `scope` - Which names of the titles are searched.
+  /// * `query` - Whether to query movies or series.
+  /// * `stripped` - Whether the name is one without its leading article.
+  fn indexed<'a>(
+    &'a self,
+    postings: impl Iterator<Item = Posting> + 'a,
+    scope: SearchScope,
+    query: Query,
+    stripped: bool,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+    postings
+      .filter(move |posting| posting.matches(query, scope, stripped))
+      .filter_map(move |posting| self.by_id_number(posting.id(), query))
+  }
+
+  /// Search the index of titles by name for the titles whose names are accepted by a
+  /// predicate and that were released in a year, if any, going through the names in
+  /// parallel in as many chunks as there are threads and stopping the search in each chunk
+  /// once `limit` titles were found there.
+  ///
+  /// # Arguments
+  ///
+  /// * `names` - The index of titles by name.
+  /// * `positions` - The positions of the names to go through in increasing order, or
+  ///   `None` for all of them.
+  /// * `accept` - The predicate on names.
+  /// * `year` - The release year of the titles, if any.
+  /// * `scope` - Which names of the titles are searched.
+  /// * `query` - Whether to query movies or series.
+  /// * `limit` - The maximum number of distinct titles to find in each chunk, if any.
+  #[allow(clippy::too_many_arguments)]
+  fn scan<'a>(
+    &'a self,
+    names: &'a NameIndex,
+    positions: Option<&[usize]>,
+    accept: impl Fn(&str) -> bool + Sync + Send,
+    year: Option<u16>,
+    scope: SearchScope,
+    query: Query,
+    limit: Option<usize>,
+  ) -> Vec<&'a Title<'a>> {
+    let len = positions.map_or(names.len(), <[usize]>::len);
+    let chunk_len = len.div_ceil(rayon::current_num_threads()).max(1);
+
+    (0..len.div_ceil(chunk_len))
+      .into_par_iter()
+      .flat_map_iter(|chunk| {
+        let start = chunk * chunk_len;
+        let titles = (start..len.min(start + chunk_len))
+          .map(|index| names.entry(positions.map_or(index, |positions| positions[index])))
+          .filter(|(name, _)| accept(name))
+          .flat_map(|(_, postings)| self.indexed(postings, scope, query, false))
+          .filter(|title| year.is_none_or(|year| title.start_year().unwrap_or(0) == year));
+        collect_limited(titles, limit)
+      })
       .collect()
   }
 
-  pub(crate) fn by_title_and_year(&self, title: &SearchString, year: u16, query: Query) -> Vec<&Title> {
-    self
-      .dbs
+  /// Search the index of titles by name for the titles whose names match the given
+  /// keywords and that were released in a year, if any, narrowing the names down by the
+  /// words they contain with the `trie` feature.
+  ///
+  /// # Arguments
+  ///
+  /// * `names` - The index of titles by name.
+  /// * `keywords` - The keywords to match.
+  /// * `year` - The release year of the titles, if any.
+  /// * `kind` - How the keywords are matched.
+  /// * `scope` - Which names of the titles are searched.
+  /// * `query` - Whether to query movies or series.
+  /// * `limit` - The maximum number of distinct titles to find in each chunk of names, if
+  ///   any.
+  #[allow(clippy::too_many_arguments)]
+  fn scan_keywords<'a>(
+    &'a self,
+    names: &'a NameIndex,
+    keywords: &[SearchString],
+    year: Option<u16>,
+    kind: KeywordMatchKind,
+    scope: SearchScope,
+    query: Query,
+    limit: Option<usize>,
+  ) -> Vec<&'a Title<'a>> {
+    let matcher = KeywordMatcher::new(keywords, kind);
+
+    #[cfg(feature = "trie")]
+    let positions = names.candidates(keywords);
+    #[cfg(not(feature = "trie"))]
+    let positions: Option<Vec<usize>> = None;
+
+    self.scan(names, positions.as_deref(), |name| matcher.matches_all(name), year, scope, query, limit)
+  }
+
+  /// Iterate lazily over the titles of the index of titles by name whose names are
+  /// accepted by a predicate.
+  ///
+  /// # Arguments
+  ///
+  /// * `names` - The index of titles by name.
+  /// * `accept` - The predicate on names.
+  /// * `scope` - Which names of the titles are searched.
+  /// * `query` - Whether to query movies or series.
+  fn iter_scan<'a>(
+    &'a self,
+    names: &'a NameIndex,
+    accept: impl Fn(&str) -> bool + 'a,
+    scope: SearchScope,
+    query: Query,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+    (0..names.len())
+      .map(|index| names.entry(index))
+      .filter(move |(name, _)| accept(name))
+      .flat_map(move |(_, postings)| self.indexed(postings, scope, query, false))
+  }
+
+  pub(crate) fn by_title(&self, title: &SearchString, scope: SearchScope, query: Query) -> Vec<&Title<'_>> {
+    if let Some(names) = &self.names {
+      return sorted_unique(self.indexed(names.get(title.as_str()), scope, query, false).collect());
+    }
+
+    let titles = self
+      .dbs(query)
       .par_iter()
-      .flat_map(|db| db.by_title_and_year(title, year, query).collect::<Vec<_>>())
-      .collect()
+      .flat_map(|db| db.by_title(title, scope, query).collect::<Vec<_>>())
+      .collect();
+
+    sorted_unique(titles)
   }
 
-  pub(crate) fn by_keywords<'a>(&'a self, keywords: &[SearchString], query: Query) -> Vec<&'a Title> {
-    self
-      .dbs
+  pub(crate) fn by_title_and_year(
+    &self,
+    title: &SearchString,
+    year: u16,
+    scope: SearchScope,
+    query: Query,
+  ) -> Vec<&Title<'_>> {
+    if let Some(names) = &self.names {
+      let titles = self.indexed(names.get(title.as_str()), scope, query, false);
+      return sorted_unique(titles.filter(|title| title.start_year().unwrap_or(0) == year).collect());
+    }
+
+    let titles = self
+      .dbs(query)
       .par_iter()
-      .flat_map(|db| db.by_keywords(keywords, query).collect::<Vec<_>>())
-      .collect()
+      .flat_map(|db| db.by_title_and_year(title, year, scope, query).collect::<Vec<_>>())
+      .collect();
+
+    sorted_unique(titles)
   }
 
+  /// Search for titles whose names match the given name once their leading articles are
+  /// stripped.
+  pub(crate) fn by_stripped_title(
+    &self,
+    title: &SearchString,
+    year: Option<u16>,
+    scope: SearchScope,
+    query: Query,
+  ) -> Vec<&Title<'_>> {
+    if let Some(names) = &self.names {
+      let stripped = search::strip_article(title.as_str(), Language::ALL);
+      let with_article = self.indexed(names.get(stripped), scope, query, true);
+      let without_article = (stripped.len() != title.as_str().len())
+        .then(|| self.indexed(names.get(stripped), scope, query, false));
+      let titles = with_article
+        .chain(without_article.into_iter().flatten())
+        .filter(|title| year.is_none_or(|year| title.start_year() == Some(year)));
+      return sorted_unique(titles.collect());
+    }
+
+    let titles = self
+      .dbs(query)
+      .par_iter()
+      .flat_map(|db| db.by_stripped_title(title, year, scope, query).collect::<Vec<_>>())
+      .collect();
+
+    sorted_unique(titles)
+  }
+
+  /// Search for titles whose names match a regular expression.
+  pub(crate) fn by_title_regex(&self, regex: &Regex, scope: SearchScope, query: Query) -> Vec<&Title<'_>> {
+    if let Some(names) = &self.names {
+      return sorted_unique(self.scan(names, None, |name| regex.is_match(name), None, scope, query, None));
+    }
+
+    let titles = self
+      .dbs(query)
+      .par_iter()
+      .flat_map(|db| db.by_title_regex(regex, scope, query).collect::<Vec<_>>())
+      .collect();
+
+    sorted_unique(titles)
+  }
+
+  /// Iterate lazily over the titles with the given name, in no particular order.
+  pub(crate) fn iter_by_title<'a>(
+    &'a self,
+    title: &'a SearchString,
+    scope: SearchScope,
+    query: Query,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+    match &self.names {
+      Some(names) => Either::Left(unique(self.indexed(names.get(title.as_str()), scope, query, false))),
+      None => Either::Right(stream(self.dbs(query), move |db| db.by_title(title, scope, query))),
+    }
+  }
+
+  /// Iterate lazily over the titles with the given name and year, in no particular order.
+  pub(crate) fn iter_by_title_and_year<'a>(
+    &'a self,
+    title: &'a SearchString,
+    year: u16,
+    scope: SearchScope,
+    query: Query,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+    match &self.names {
+      Some(names) => Either::Left(unique(
+        self
+          .indexed(names.get(title.as_str()), scope, query, false)
+          .filter(move |title| title.start_year().unwrap_or(0) == year),
+      )),
+      None => {
+        Either::Right(stream(self.dbs(query), move |db| db.by_title_and_year(title, year, scope, query)))
+      }
+    }
+  }
+
+  /// Iterate lazily over the titles matching the given keywords, in no particular order.
+  pub(crate) fn iter_by_keywords<'a>(
+    &'a self,
+    keywords: &'a [SearchString],
+    kind: KeywordMatchKind,
+    scope: SearchScope,
+    query: Query,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+    match &self.names {
+      Some(names) => {
+        let matcher = KeywordMatcher::new(keywords, kind);
+        Either::Left(unique(self.iter_scan(names, move |name| matcher.matches_all(name), scope, query)))
+      }
+      None => Either::Right(stream(self.dbs(query), move |db| db.by_keywords(keywords, kind, scope, query))),
+    }
+  }
+
+  /// Iterate lazily over the titles matching the given keywords and year, in no particular
+  /// order.
+  pub(crate) fn iter_by_keywords_and_year<'a>(
+    &'a self,
+    keywords: &'a [SearchString],
+    year: u16,
+    kind: KeywordMatchKind,
+    scope: SearchScope,
+    query: Query,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+    match &self.names {
+      Some(names) => {
+        let matcher = KeywordMatcher::new(keywords, kind);
+        let titles = self
+          .iter_scan(names, move |name| matcher.matches_all(name), scope, query)
+          .filter(move |title| title.start_year().unwrap_or(0) == year);
+        Either::Left(unique(titles))
+      }
+      None => Either::Right(stream(self.dbs(query), move |db| {
+        db.by_keywords_and_year(keywords, year, kind, scope, query)
+      })),
+    }
+  }
+
+  /// Search for titles by keywords, stopping the search in each thread-handled database,
+  /// or in each chunk of the index of titles by name, once `limit` titles were found there.
+  pub(crate) fn by_keywords<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    kind: KeywordMatchKind,
+    scope: SearchScope,
+    query: Query,
+    limit: Option<usize>,
+  ) -> Vec<&'a Title<'a>> {
+    if let Some(names) = &self.names {
+      let titles = self.scan_keywords(names, keywords, None, kind, scope, query, limit);
+      return limited(sorted_unique(titles), limit);
+    }
+
+    let titles = self
+      .dbs(query)
+      .par_iter()
+      .flat_map(|db| collect_limited(db.by_keywords(keywords, kind, scope, query), limit))
+      .collect();
+
+    limited(sorted_unique(titles), limit)
+  }
+
+  /// Search for titles by keywords and year, stopping the search in each thread-handled
+  /// database, or in each chunk of the index of titles by name, once `limit` titles were
+  /// found there.
   pub(crate) fn by_keywords_and_year<'a>(
     &'a self,
     keywords: &[SearchString],
     year: u16,
+    kind: KeywordMatchKind,
+    scope: SearchScope,
     query: Query,
-  ) -> Vec<&'a Title> {
-    self
-      .dbs
+    limit: Option<usize>,
+  ) -> Vec<&'a Title<'a>> {
+    if let Some(names) = &self.names {
+      let titles = self.scan_keywords(names, keywords, Some(year), kind, scope, query, limit);
+      return limited(sorted_unique(titles), limit);
+    }
+
+    let titles = self
+      .dbs(query)
       .par_iter()
-      .flat_map(|db| db.by_keywords_and_year(keywords, year, query).collect::<Vec<_>>())
-      .collect()
+      .flat_map(|db| collect_limited(db.by_keywords_and_year(keywords, year, kind, scope, query), limit))
+      .collect();
+
+    limited(sorted_unique(titles), limit)
+  }
+}
+
+/// Iterate lazily over the titles found in all shards, one shard after the other, skipping
+/// titles already found under another of their names.
+///
+/// # Arguments
+///
+/// * `dbs` - The shards.
+/// * `search` - The search in a single shard.
+fn stream<'a>(
+  dbs: &'a [Db],
+  search: impl Fn(&'a Db) -> Box<dyn Iterator<Item = &'a Title<'a>> + 'a> + 'a,
+) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+  unique(dbs.iter().flat_map(search))
+}
+
+/// Skip titles already found under another of their names.
+///
+/// # Arguments
+///
+/// * `titles` - The titles found, lazily searched for.
+fn unique<'a>(titles: impl Iterator<Item = &'a Title<'a>> + 'a) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+  let mut seen = FnvHashSet::default();
+  titles.filter(move |title| seen.insert(title.title_id().as_usize()))
+}
+
+/// Collect the titles found in a shard, stopping once `limit` distinct titles were found.
+///
+/// # Arguments
+///
+/// * `titles` - The titles found in the shard, lazily searched for.
+/// * `limit` - The maximum number of distinct titles to collect, if any.
+fn collect_limited<'a, 'storage>(
+  titles: impl Iterator<Item = &'a Title<'storage>>,
+  limit: Option<usize>,
+) -> Vec<&'a Title<'storage>>
+where
+  'storage: 'a,
+{
+  match limit {
+    Some(limit) => {
+      let mut seen = FnvHashSet::default();
+      titles
+        .filter(|title| seen.insert(title.title_id().as_usize()))
+        .take(limit)
+        .collect()
+    }
+    None => titles.collect(),
   }
 }
 
+/// Keep at most `limit` titles, if there is a limit.
+///
+/// # Arguments
+///
+/// * `titles` - The titles.
+/// * `limit` - The maximum number of titles to keep, if any.
+fn limited<'a, 'storage>(
+  mut titles: Vec<&'a Title<'storage>>,
+  limit: Option<usize>,
+) -> Vec<&'a Title<'storage>> {
+  if let Some(limit) = limit {
+    titles.truncate(limit);
+  }
+
+  titles
+}
+
+/// Sort titles found in several shards by their IDs and remove duplicates, since a title can
+/// be found under several of its names and the shards hold different titles every time the
+/// databases are loaded.
+///
+/// # Arguments
+///
+/// * `titles` - The titles found in all shards.
+fn sorted_unique<'a, 'storage>(mut titles: Vec<&'a Title<'storage>>) -> Vec<&'a Title<'storage>> {
+  titles.sort_unstable_by_key(|title| title.title_id().as_usize());
+  titles.dedup_by_key(|title| title.title_id().as_usize());
+  titles
+}
+
 #[cfg(test)]
 mod tests {
   use crate::imdb::db::Query;
-  use crate::imdb::db_binary::ServiceDbFromBinary;
+  use crate::imdb::db_binary::{IndexLayout, ServiceDbFromBinary};
+  use crate::imdb::db_file::{Compression, Writer};
+  #[cfg(feature = "fs")]
+  use crate::imdb::names::{NameIndex, NAMES_DB_FILENAME};
   use crate::imdb::testdata::{make_basics_reader, make_ratings_reader};
+  use crate::imdb::title::Title;
   use crate::imdb::title_id::TitleId;
   use crate::imdb::tsv_import::tsv_import;
-  use crate::utils::search::SearchString;
+  use crate::utils::search::{KeywordMatchKind, SearchScope, SearchString};
 
-  fn make_service_db_from_binary() -> ServiceDbFromBinary {
+  fn make_storage() -> (Vec<u8>, Vec<u8>) {
     let basics_reader = make_basics_reader();
     let ratings_reader = make_ratings_reader();
 
     let mut movies_storage = Vec::new();
     let mut series_storage = Vec::new();
-    tsv_import(ratings_reader, basics_reader, &mut movies_storage, &mut series_storage).unwrap();
+    let mut movies_writer = Writer::new(&mut movies_storage, Compression::None).unwrap();
+    let mut series_writer = Writer::new(&mut series_storage, Compression::None).unwrap();
+    tsv_import(ratings_reader, basics_reader, &mut movies_writer, &mut series_writer).unwrap();
+    movies_writer.finish().unwrap();
+    series_writer.finish().unwrap();
+
+    (movies_storage, series_storage)
+  }
+
+  fn make_service_db_from_binary() -> ServiceDbFromBinary {
+    let (movies_storage, series_storage) = make_storage();
+    ServiceDbFromBinary::new(movies_storage, series_storage, IndexLayout::Sharded, None, None, false).unwrap()
+  }
+
+  #[test]
+  fn test_truncated() {
+    let (mut movies_storage, series_storage) = make_storage();
+    movies_storage.truncate(movies_storage.len() - 5);
+
+    assert!(ServiceDbFromBinary::new(
+      movies_storage,
+      series_storage,
+      IndexLayout::Sharded,
+      None,
+      None,
+      false
+    )
+    .is_err());
+  }
+
+  #[test]
+  fn test_lazy() {
+    let (movies_storage, series_storage) = make_storage();
+    let service_db =
+      ServiceDbFromBinary::new(movies_storage, series_storage, IndexLayout::Sharded, None, None, true)
+        .unwrap();
+    assert!(service_db.memory_stats().shards().is_empty());
+
+    let title = SearchString::try_from("carmencita").unwrap();
+    assert_eq!(service_db.by_title(&title, SearchScope::All, Query::Movies).len(), 1);
+    assert!(service_db.movies.dbs.get().is_some());
+    assert!(service_db.series.dbs.get().is_none());
+    assert_eq!(service_db.memory_stats().shards().len(), rayon::current_num_threads());
+
+    assert!(service_db.by_title(&title, SearchScope::All, Query::Series).is_empty());
+    assert!(service_db.series.dbs.get().is_some());
+    assert_eq!(service_db.n_entries(), make_service_db_from_binary().n_entries());
 
-    let movies_storage = Box::leak(movies_storage.into_boxed_slice());
-    let series_storage = Box::leak(series_storage.into_boxed_slice());
-    ServiceDbFromBinary::new(movies_storage, series_storage).unwrap()
+    let (mut movies_storage, series_storage) = make_storage();
+    movies_storage.truncate(movies_storage.len() - 5);
+    let service_db =
+      ServiceDbFromBinary::new(movies_storage, series_storage, IndexLayout::Sharded, None, None, true)
+        .unwrap();
+    assert!(service_db.by_title(&title, SearchScope::All, Query::Movies).is_empty());
   }
 
   #[test]
@@ -258,6 +992,53 @@ mod tests {
     assert_eq!(service_db.n_entries(), (11, 0));
   }
 
+  #[test]
+  fn test_n_titles() {
+    let service_db = make_service_db_from_binary();
+    assert_eq!(service_db.n_titles(Query::Movies), 11);
+    assert_eq!(service_db.n_titles(Query::Series), 0);
+    assert_eq!(service_db.n_titles(Query::Movies), service_db.titles(Query::Movies).count());
+  }
+
+  #[test]
+  fn test_memory_stats() {
+    let (movies_storage, series_storage) = make_storage();
+    let storage = movies_storage.len() + series_storage.len();
+
+    let service_db = make_service_db_from_binary();
+    let stats = service_db.memory_stats();
+    assert_eq!(stats.storage(), storage);
+    assert_eq!(stats.shards().len(), rayon::current_num_threads());
+
+    let shards = stats.shards_total();
+    assert!(shards.titles() > 0);
+    assert!(shards.by_id() >= 11 * std::mem::size_of::<(usize, usize)>());
+    assert!(shards.by_title() > 0);
+    assert_eq!(stats.total(), storage + shards.total());
+  }
+
+  #[test]
+  fn test_single_index() {
+    let (movies_storage, series_storage) = make_storage();
+    let single =
+      ServiceDbFromBinary::new(movies_storage, series_storage, IndexLayout::Single, None, None, false)
+        .unwrap();
+    let sharded = make_service_db_from_binary();
+
+    assert_eq!(single.memory_stats().shards().len(), 1);
+    assert_eq!(single.n_entries(), sharded.n_entries());
+
+    let id = TitleId::try_from("tt0000007").unwrap();
+    assert_eq!(single.by_id(&id, Query::Movies), sharded.by_id(&id, Query::Movies));
+
+    let keywords = [SearchString::try_from("c").unwrap()];
+    let kind = KeywordMatchKind::default();
+    assert_eq!(
+      single.by_keywords(&keywords, kind, SearchScope::All, Query::Movies, None),
+      sharded.by_keywords(&keywords, kind, SearchScope::All, Query::Movies, None)
+    );
+  }
+
   #[test]
   fn test_by_id() {
     let service_db = make_service_db_from_binary();
@@ -268,22 +1049,48 @@ mod tests {
     assert_eq!(title.primary_title(), "Corbett and Courtney Before the Kinetograph");
   }
 
+  #[test]
+  fn test_by_id_any() {
+    let service_db = make_service_db_from_binary();
+    let (title, query) = service_db.by_id_any(&TitleId::try_from("tt0000007").unwrap()).unwrap();
+    assert!(matches!(query, Query::Movies));
+    assert_eq!(title.primary_title(), "Corbett and Courtney Before the Kinetograph");
+    assert!(service_db.by_id_any(&TitleId::try_from("tt9999999").unwrap()).is_none());
+  }
+
   #[test]
   fn test_by_title() {
     let service_db = make_service_db_from_binary();
     let title = SearchString::try_from("Corbett and Courtney Before the Kinetograph").unwrap();
-    let titles = service_db.by_title(&title, Query::Movies);
+    let titles = service_db.by_title(&title, SearchScope::All, Query::Movies);
     assert_eq!(titles.len(), 1);
     let title = titles[0];
     assert_eq!(title.title_id(), &TitleId::try_from("tt0000007").unwrap());
     assert_eq!(title.primary_title(), "Corbett and Courtney Before the Kinetograph");
   }
 
+  #[test]
+  fn test_by_title_regex() {
+    let service_db = make_service_db_from_binary();
+    let regex = regex::Regex::new(r"^c.* (and|of) ").unwrap();
+    let titles = service_db.by_title_regex(&regex, SearchScope::All, Query::Movies);
+    assert_eq!(titles.len(), 1);
+    assert_eq!(titles[0].title_id(), &TitleId::try_from("tt0000007").unwrap());
+
+    let regex = regex::Regex::new(r"lumiere").unwrap();
+    let titles = service_db.by_title_regex(&regex, SearchScope::All, Query::Movies);
+    assert_eq!(titles.len(), 1);
+    assert_eq!(titles[0].title_id(), &TitleId::try_from("tt0000010").unwrap());
+
+    let regex = regex::Regex::new(r"^C").unwrap();
+    assert!(service_db.by_title_regex(&regex, SearchScope::All, Query::Movies).is_empty());
+  }
+
   #[test]
   fn test_by_title_and_year() {
     let service_db = make_service_db_from_binary();
     let title = SearchString::try_from("Corbett and Courtney Before the Kinetograph").unwrap();
-    let titles = service_db.by_title_and_year(&title, 1894, Query::Movies);
+    let titles = service_db.by_title_and_year(&title, 1894, SearchScope::All, Query::Movies);
     assert_eq!(titles.len(), 1);
     let title = titles[0];
     assert_eq!(title.title_id(), &TitleId::try_from("tt0000007").unwrap());
@@ -293,7 +1100,13 @@ mod tests {
   #[test]
   fn test_by_keywords() {
     let service_db = make_service_db_from_binary();
-    let titles = service_db.by_keywords(&[SearchString::try_from("Corbett").unwrap()], Query::Movies);
+    let titles = service_db.by_keywords(
+      &[SearchString::try_from("Corbett").unwrap()],
+      KeywordMatchKind::default(),
+      SearchScope::All,
+      Query::Movies,
+      None,
+    );
     assert_eq!(titles.len(), 1);
     let title = titles[0];
     assert_eq!(title.title_id(), &TitleId::try_from("tt0000007").unwrap());
@@ -301,10 +1114,170 @@ mod tests {
   }
 
   #[test]
-  fn test_by_keywords_and_year() {
+  fn test_results_sorted_and_unique() {
     let service_db = make_service_db_from_binary();
+    let ids =
+      |titles: Vec<&Title>| titles.iter().map(|title| title.title_id().as_usize()).collect::<Vec<_>>();
+
+    let keywords = [SearchString::try_from("ine").unwrap()];
+    let titles = ids(service_db.by_keywords(
+      &keywords,
+      KeywordMatchKind::default(),
+      SearchScope::All,
+      Query::Movies,
+      None,
+    ));
+    assert!(titles.len() > 1);
+    assert!(titles.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(titles.contains(&10));
+
+    let again = ids(service_db.by_keywords(
+      &keywords,
+      KeywordMatchKind::default(),
+      SearchScope::All,
+      Query::Movies,
+      None,
+    ));
+    assert_eq!(titles, again);
+  }
+
+  #[test]
+  fn test_by_keywords_limited() {
+    let service_db = make_service_db_from_binary();
+    let keywords = [SearchString::try_from("ine").unwrap()];
+    let all =
+      service_db.by_keywords(&keywords, KeywordMatchKind::default(), SearchScope::All, Query::Movies, None);
+    assert!(all.len() > 3);
+
+    let titles = service_db.by_keywords(
+      &keywords,
+      KeywordMatchKind::default(),
+      SearchScope::All,
+      Query::Movies,
+      Some(3),
+    );
+    assert_eq!(titles.len(), 3);
+    assert!(titles
+      .windows(2)
+      .all(|pair| pair[0].title_id().as_usize() < pair[1].title_id().as_usize()));
+    assert!(titles.iter().all(|title| all.iter().any(|t| t.title_id() == title.title_id())));
+
+    let titles = service_db.by_keywords(
+      &keywords,
+      KeywordMatchKind::default(),
+      SearchScope::All,
+      Query::Movies,
+      Some(0),
+    );
+    assert!(titles.is_empty());
+
+    let titles = service_db.by_keywords_and_year(
+      &keywords,
+      1894,
+      KeywordMatchKind::default(),
+      SearchScope::All,
+      Query::Movies,
+      Some(1),
+    );
+    assert_eq!(titles.len(), 1);
+    assert_eq!(titles[0].start_year(), Some(1894));
+  }
+
+  #[test]
+  #[cfg(feature = "fs")]
+  fn test_by_keywords_name_index() {
+    let plain = make_service_db_from_binary();
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let filename = dir.path().join(NAMES_DB_FILENAME);
+    NameIndex::write(&filename, &plain.names(), false).unwrap();
+
+    let (movies_storage, series_storage) = make_storage();
+    let names = NameIndex::read(&filename).unwrap();
+    let indexed = ServiceDbFromBinary::new(
+      movies_storage,
+      series_storage,
+      IndexLayout::Sharded,
+      Some(names),
+      None,
+      false,
+    )
+    .unwrap();
+
+    let ids =
+      |titles: Vec<&Title>| titles.iter().map(|title| title.title_id().as_usize()).collect::<Vec<_>>();
+    let kind = KeywordMatchKind::default();
+    for keyword in ["ine", "kineto", "lumiere", "a"] {
+      let keywords = [SearchString::try_from(keyword).unwrap()];
+      let all = ids(plain.by_keywords(&keywords, kind, SearchScope::All, Query::Movies, None));
+      assert_eq!(ids(indexed.by_keywords(&keywords, kind, SearchScope::All, Query::Movies, None)), all);
+
+      let limited = ids(indexed.by_keywords(&keywords, kind, SearchScope::All, Query::Movies, Some(2)));
+      assert_eq!(limited.len(), all.len().min(2));
+      assert!(limited.iter().all(|id| all.contains(id)));
+    }
+
+    let keywords = [SearchString::try_from("ine").unwrap()];
     let titles =
-      service_db.by_keywords_and_year(&[SearchString::try_from("Kineto").unwrap()], 1915, Query::Movies);
+      indexed.by_keywords_and_year(&keywords, 1894, kind, SearchScope::All, Query::Movies, Some(1));
+    assert_eq!(titles.len(), 1);
+    assert_eq!(titles[0].start_year(), Some(1894));
+  }
+
+  #[test]
+  fn test_iter() {
+    let service_db = make_service_db_from_binary();
+    let ids = |titles: &mut dyn Iterator<Item = &Title>| {
+      let mut ids = titles.map(|title| title.title_id().as_usize()).collect::<Vec<_>>();
+      ids.sort_unstable();
+      ids
+    };
+
+    let keywords = [SearchString::try_from("ine").unwrap()];
+    let kind = KeywordMatchKind::default();
+    assert_eq!(
+      ids(&mut service_db.iter_by_keywords(&keywords, kind, SearchScope::All, Query::Movies)),
+      ids(
+        &mut service_db
+          .by_keywords(&keywords, kind, SearchScope::All, Query::Movies, None)
+          .into_iter()
+      )
+    );
+    assert_eq!(
+      ids(&mut service_db.iter_by_keywords_and_year(&keywords, 1894, kind, SearchScope::All, Query::Movies)),
+      ids(
+        &mut service_db
+          .by_keywords_and_year(&keywords, 1894, kind, SearchScope::All, Query::Movies, None)
+          .into_iter()
+      )
+    );
+
+    let title = SearchString::try_from("Carmencita").unwrap();
+    assert_eq!(service_db.iter_by_title(&title, SearchScope::All, Query::Movies).count(), 1);
+    assert_eq!(
+      service_db
+        .iter_by_title_and_year(&title, 1894, SearchScope::All, Query::Movies)
+        .count(),
+      1
+    );
+    assert_eq!(
+      service_db
+        .iter_by_title_and_year(&title, 1895, SearchScope::All, Query::Movies)
+        .count(),
+      0
+    );
+  }
+
+  #[test]
+  fn test_by_keywords_and_year() {
+    let service_db = make_service_db_from_binary();
+    let titles = service_db.by_keywords_and_year(
+      &[SearchString::try_from("Kineto").unwrap()],
+      1915,
+      KeywordMatchKind::default(),
+      SearchScope::All,
+      Query::Movies,
+      None,
+    );
     assert_eq!(titles.len(), 1);
     let title = titles[0];
     assert_eq!(title.title_id(), &TitleId::try_from("tt0212278").unwrap());
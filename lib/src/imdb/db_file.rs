@@ -0,0 +1,423 @@
+#![warn(clippy::all)]
+
+//! Header and compression of the binary database files.
+//!
+//! Database files start with [MAGIC], followed by a byte with the [VERSION] of the format of
+//! the titles and a byte flagging how the titles that follow are compressed.
+//!
+//! Databases in older formats, including ones written before the header was introduced, are
+//! rejected rather than misread, so they can be rebuilt.
+//!
+//! Each database file has a SHA-256 checksum file alongside it, in the format of `sha256sum`,
+//! which is verified when the database is read to detect truncated or corrupted files.
+
+#[cfg(feature = "fs")]
+use std::fmt::Write as _;
+#[cfg(feature = "fs")]
+use std::fs::{self, File};
+#[cfg(feature = "fs")]
+use std::io::BufWriter;
+use std::io::{self, Read, Write};
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::path::PathBuf;
+
+#[cfg(feature = "fs")]
+use ring::digest::{digest, SHA256};
+use zstd::stream::read::Decoder as ZstdDecoder;
+use zstd::stream::write::Encoder as ZstdEncoder;
+
+/// Identifies binary database files that start with a header.
+const MAGIC: &[u8; 8] = b"TVRANKDB";
+
+/// Version of the format of the database files.
+///
+/// Has to be incremented whenever the header or the binary encoding of titles changes, so that
+/// existing databases get rebuilt instead of being misread.
+pub(crate) const VERSION: u8 = 6;
+
+/// Bytes of the header: the magic, the version and the compression flag.
+pub(crate) const HEADER_LEN: usize = MAGIC.len() + 2;
+
+/// Compression level of zstd-compressed databases, which compresses them several times
+/// while keeping updates fast.
+const ZSTD_LEVEL: i32 = 3;
+
+/// Errors when reading or writing database files.
+#[derive(Debug, thiserror::Error)]
+#[error("Database file error")]
+pub enum Error {
+  /// The data does not start with a database header.
+  #[error("Not a database file, or a database from an older version")]
+  NotADatabase,
+  /// The database was written in another version of the format.
+  #[error("Database format version {0} is not supported (expected {VERSION})")]
+  UnsupportedVersion(u8),
+  /// The compression flag in the header is not known.
+  #[error("Unknown database compression flag {0}")]
+  UnknownCompression(u8),
+  /// The header is truncated.
+  #[error("Database header is truncated")]
+  TruncatedHeader,
+  /// The checksum file of the database is missing or unreadable.
+  #[error("Checksum of `{}` is missing", .0.display())]
+  MissingChecksum(PathBuf),
+  /// The database does not match its checksum.
+  #[error("`{}` does not match its checksum", .0.display())]
+  ChecksumMismatch(PathBuf),
+  /// IO error.
+  #[error("IO error: {0}")]
+  Io(#[from] io::Error),
+}
+
+impl Error {
+  /// Whether the error means that the database file is damaged or outdated, and can be fixed
+  /// by rebuilding it.
+  #[cfg_attr(not(feature = "fetch"), allow(dead_code))]
+  pub(crate) fn needs_rebuild(&self) -> bool {
+    !matches!(self, Error::Io(_))
+  }
+}
+
+/// Compression of the binary database files.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+  /// Titles are stored as they are, which is the fastest to load.
+  #[default]
+  None,
+  /// Titles are zstd-compressed, which is several times smaller on disk and quick to
+  /// decompress.
+  Zstd,
+}
+
+impl Compression {
+  /// The flag of the compression in the header. Flag 1 was used by the gzip-compressed
+  /// databases of version 4 of the format.
+  fn flag(self) -> u8 {
+    match self {
+      Compression::None => 0,
+      Compression::Zstd => 2,
+    }
+  }
+
+  fn from_flag(flag: u8) -> Result<Self, Error> {
+    match flag {
+      0 => Ok(Compression::None),
+      2 => Ok(Compression::Zstd),
+      flag => Err(Error::UnknownCompression(flag)),
+    }
+  }
+}
+
+/// Writes titles to a database file, compressing them if needed.
+pub(crate) enum Writer<W: Write> {
+  Plain(W),
+  Zstd(ZstdEncoder<'static, W>),
+}
+
+impl<W: Write> Writer<W> {
+  /// Write the header to the given writer and return a writer for the titles.
+  ///
+  /// # Arguments
+  ///
+  /// * `writer` - Writer of the database file.
+  /// * `compression` - How the titles are compressed.
+  pub(crate) fn new(mut writer: W, compression: Compression) -> Result<Self, Error> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION, compression.flag()])?;
+
+    Ok(match compression {
+      Compression::None => Writer::Plain(writer),
+      Compression::Zstd => Writer::Zstd(ZstdEncoder::new(writer, ZSTD_LEVEL)?),
+    })
+  }
+
+  /// Finish compressing and flush the titles, returning the writer of the database file.
+  pub(crate) fn finish(self) -> Result<W, Error> {
+    let mut writer = match self {
+      Writer::Plain(writer) => writer,
+      Writer::Zstd(encoder) => encoder.finish()?,
+    };
+
+    writer.flush()?;
+    Ok(writer)
+  }
+}
+
+impl<W: Write> Write for Writer<W> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    match self {
+      Writer::Plain(writer) => writer.write(buf),
+      Writer::Zstd(encoder) => encoder.write(buf),
+    }
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    match self {
+      Writer::Plain(writer) => writer.flush(),
+      Writer::Zstd(encoder) => encoder.flush(),
+    }
+  }
+}
+
+/// Create a database file and return a writer for its titles.
+///
+/// # Arguments
+///
+/// * `filename` - Path of the database file.
+/// * `compression` - How the titles are compressed.
+#[cfg(feature = "fs")]
+pub(crate) fn create(filename: &Path, compression: Compression) -> Result<Writer<BufWriter<File>>, Error> {
+  Writer::new(BufWriter::new(File::create(filename)?), compression)
+}
+
+/// Return the format version of a database file, or `None` if it does not exist or does not
+/// start with a header.
+///
+/// # Arguments
+///
+/// * `filename` - Path of the database file.
+#[cfg(feature = "fs")]
+pub(crate) fn version(filename: &Path) -> Result<Option<u8>, Error> {
+  let file = match File::open(filename) {
+    Ok(file) => file,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+    Err(e) => return Err(Error::Io(e)),
+  };
+
+  let mut header = Vec::with_capacity(MAGIC.len() + 1);
+  file.take(MAGIC.len() as u64 + 1).read_to_end(&mut header)?;
+
+  match header.strip_prefix(MAGIC) {
+    Some(&[version]) => Ok(Some(version)),
+    _ => Ok(None),
+  }
+}
+
+/// The path of the checksum file of a database file.
+///
+/// # Arguments
+///
+/// * `filename` - Path of the database file.
+#[cfg(feature = "fs")]
+pub(crate) fn checksum_filename(filename: &Path) -> PathBuf {
+  let mut checksum_filename = filename.as_os_str().to_owned();
+  checksum_filename.push(".sha256");
+  PathBuf::from(checksum_filename)
+}
+
+/// The path a database file is written to before it replaces the existing one.
+///
+/// # Arguments
+///
+/// * `filename` - Path of the database file.
+#[cfg(feature = "fs")]
+pub(crate) fn partial_filename(filename: &Path) -> PathBuf {
+  let mut partial_filename = filename.as_os_str().to_owned();
+  partial_filename.push(".partial");
+  PathBuf::from(partial_filename)
+}
+
+/// Hexadecimal SHA-256 checksum of the given data.
+#[cfg(feature = "fs")]
+fn checksum(data: &[u8]) -> String {
+  digest(&SHA256, data).as_ref().iter().fold(String::new(), |mut hex, byte| {
+    let _ = write!(hex, "{byte:02x}");
+    hex
+  })
+}
+
+/// Compute the checksum of a database file and write it to its checksum file.
+///
+/// # Arguments
+///
+/// * `filename` - Path of the database file.
+#[cfg(feature = "fs")]
+pub(crate) fn write_checksum(filename: &Path) -> Result<(), Error> {
+  let checksum = checksum(&fs::read(filename)?);
+  let name = filename.file_name().unwrap_or_default().to_string_lossy();
+  fs::write(checksum_filename(filename), format!("{checksum}  {name}\n"))?;
+  Ok(())
+}
+
+/// Read a database file, after verifying it against its checksum file.
+///
+/// # Arguments
+///
+/// * `filename` - Path of the database file.
+#[cfg(feature = "fs")]
+pub(crate) fn read_verified(filename: &Path) -> Result<Vec<u8>, Error> {
+  if !checksum_filename(filename).exists() {
+    return Err(Error::MissingChecksum(filename.to_owned()));
+  }
+
+  let data = fs::read(filename)?;
+  verify(filename, &data)?;
+  Ok(data)
+}
+
+/// Verify the contents of a database file against its checksum file.
+///
+/// # Arguments
+///
+/// * `filename` - Path of the database file.
+/// * `data` - Contents of the database file.
+#[cfg(feature = "fs")]
+pub(crate) fn verify(filename: &Path, data: &[u8]) -> Result<(), Error> {
+  let expected = match fs::read_to_string(checksum_filename(filename)) {
+    Ok(contents) => contents.split_whitespace().next().map(str::to_owned),
+    Err(_) => None,
+  };
+
+  match expected {
+    Some(expected) if checksum(data) == expected => Ok(()),
+    Some(_) => Err(Error::ChecksumMismatch(filename.to_owned())),
+    None => Err(Error::MissingChecksum(filename.to_owned())),
+  }
+}
+
+/// The titles stored in a database file, which own either the contents of the file or, if
+/// the titles were compressed, the decompressed titles.
+pub(crate) struct Contents {
+  buffer: Vec<u8>,
+  /// Where the titles start in the buffer.
+  start: usize,
+}
+
+impl Contents {
+  /// The titles as stored in the database file, once decompressed.
+  pub(crate) fn titles(&self) -> &[u8] {
+    &self.buffer[self.start..]
+  }
+
+  /// Bytes used by the contents.
+  pub(crate) fn bytes(&self) -> usize {
+    self.buffer.len()
+  }
+}
+
+impl From<Vec<u8>> for Contents {
+  fn from(titles: Vec<u8>) -> Self {
+    Self { buffer: titles, start: 0 }
+  }
+}
+
+/// Return how the titles of a database file are compressed, after checking its header.
+///
+/// # Arguments
+///
+/// * `data` - Contents of the database file.
+pub(crate) fn compression(data: &[u8]) -> Result<Compression, Error> {
+  let header = data.strip_prefix(MAGIC).ok_or(Error::NotADatabase)?;
+
+  let (&version, header) = header.split_first().ok_or(Error::TruncatedHeader)?;
+  if version != VERSION {
+    return Err(Error::UnsupportedVersion(version));
+  }
+
+  let &flag = header.first().ok_or(Error::TruncatedHeader)?;
+  Compression::from_flag(flag)
+}
+
+/// Return the titles stored in the contents of a database file, decompressing them if
+/// needed.
+///
+/// Uncompressed titles are returned without copying them, and the contents of files with
+/// compressed titles are dropped once they are decompressed.
+///
+/// # Arguments
+///
+/// * `data` - Contents of the database file.
+pub(crate) fn titles(data: Vec<u8>) -> Result<Contents, Error> {
+  match compression(&data)? {
+    Compression::None => Ok(Contents { buffer: data, start: HEADER_LEN }),
+    Compression::Zstd => {
+      let titles = &data[HEADER_LEN..];
+      let mut decompressed = Vec::with_capacity(titles.len() * 4);
+      ZstdDecoder::new(titles)?.read_to_end(&mut decompressed)?;
+      Ok(Contents { buffer: decompressed, start: 0 })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn write(compression: Compression, titles: &[u8]) -> Vec<u8> {
+    let mut writer = Writer::new(Vec::new(), compression).unwrap();
+    writer.write_all(titles).unwrap();
+    writer.finish().unwrap()
+  }
+
+  fn titles(data: &[u8]) -> Result<Vec<u8>, Error> {
+    super::titles(data.to_vec()).map(|contents| contents.titles().to_vec())
+  }
+
+  #[test]
+  fn uncompressed() {
+    let data = write(Compression::None, b"titles");
+    assert!(data.starts_with(MAGIC));
+    assert_eq!(titles(&data).unwrap(), b"titles");
+  }
+
+  #[test]
+  fn zstd() {
+    let contents = b"titles titles titles titles titles titles titles titles".repeat(100);
+    let data = write(Compression::Zstd, &contents);
+    assert!(data.len() < contents.len());
+    assert_eq!(titles(&data).unwrap(), contents);
+  }
+
+  #[test]
+  fn older_versions() {
+    assert!(matches!(titles(b"titles"), Err(Error::NotADatabase)));
+    assert!(matches!(titles(b""), Err(Error::NotADatabase)));
+    assert!(matches!(titles(b"TVRANKDB\x01titles"), Err(Error::UnsupportedVersion(1))));
+    assert!(matches!(titles(b"TVRANKDB\x02titles"), Err(Error::UnsupportedVersion(2))));
+    assert!(matches!(titles(b"TVRANKDB\x03titles"), Err(Error::UnsupportedVersion(3))));
+    assert!(matches!(titles(b"TVRANKDB\x04titles"), Err(Error::UnsupportedVersion(4))));
+    assert!(matches!(titles(b"TVRANKDB\x05titles"), Err(Error::UnsupportedVersion(5))));
+  }
+
+  #[test]
+  fn bad_header() {
+    assert!(matches!(titles(b"TVRANKDB"), Err(Error::TruncatedHeader)));
+    assert!(matches!(titles(b"TVRANKDB\x06"), Err(Error::TruncatedHeader)));
+    assert!(matches!(titles(b"TVRANKDB\x06\x07titles"), Err(Error::UnknownCompression(7))));
+    assert!(matches!(titles(b"TVRANKDB\x06\x01titles"), Err(Error::UnknownCompression(1))));
+  }
+
+  #[test]
+  #[cfg(feature = "fs")]
+  fn file_version() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let filename = dir.path().join("db");
+    assert_eq!(version(&filename).unwrap(), None);
+
+    std::fs::write(&filename, b"titles").unwrap();
+    assert_eq!(version(&filename).unwrap(), None);
+
+    create(&filename, Compression::Zstd).unwrap().finish().unwrap();
+    assert_eq!(version(&filename).unwrap(), Some(VERSION));
+  }
+
+  #[test]
+  #[cfg(feature = "fs")]
+  fn checksums() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let filename = dir.path().join("db");
+    fs::write(&filename, b"titles").unwrap();
+    assert!(matches!(read_verified(&filename), Err(Error::MissingChecksum(_))));
+
+    write_checksum(&filename).unwrap();
+    let contents = fs::read_to_string(checksum_filename(&filename)).unwrap();
+    assert_eq!(contents, "d9ad71b7e31032045152913ad66df1acc62ffe47298740649de7e55ada48aa10  db\n");
+    assert_eq!(read_verified(&filename).unwrap(), b"titles");
+
+    fs::write(&filename, b"titlez").unwrap();
+    let result = read_verified(&filename);
+    assert!(matches!(&result, Err(Error::ChecksumMismatch(_))));
+    assert!(result.unwrap_err().needs_rebuild());
+  }
+}
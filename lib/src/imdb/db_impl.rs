@@ -1,80 +1,242 @@
 #![warn(clippy::all)]
 
 use std::collections::HashMap;
+use std::mem::size_of;
 use std::ops::Index;
 
+#[cfg(feature = "akas")]
+use crate::imdb::akas::Akas;
+use crate::imdb::memory::{hash_map_bytes, ShardMemory};
 use crate::imdb::title::Title;
 use crate::imdb::title_id::TitleId;
-use crate::utils::search::SearchString;
+#[cfg(feature = "trie")]
+use crate::imdb::trie::WordIndex;
+use crate::utils::search::{self, KeywordMatchKind, KeywordMatcher, Language, SearchScope, SearchString};
 
-use aho_corasick::AhoCorasickBuilder;
-use aho_corasick::MatchKind as ACMatchKind;
-use deunicode::deunicode;
-use fnv::{FnvHashMap, FnvHashSet};
+use fnv::FnvHashMap;
+#[cfg(feature = "trie")]
+use rayon::iter::Either;
+use regex::Regex;
 
 type ById<C> = FnvHashMap<usize, C>;
 type ByYear<C> = FnvHashMap<u16, Vec<C>>;
-type ByTitle<C> = HashMap<String, ByYear<C>>;
+type ByTitle<C> = HashMap<String, ByField<C>>;
+
+/// Which field of the titles a name was indexed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Field {
+  /// The primary title, which differs from the original title.
+  Primary,
+  /// The original title, which differs from the primary title.
+  Original,
+  /// The primary title of a title without a different original title.
+  Same,
+}
+
+impl Field {
+  /// Whether names indexed from this field are searched in a search scope.
+  ///
+  /// # Arguments
+  ///
+  /// * `scope` - The search scope.
+  pub(crate) fn in_scope(self, scope: SearchScope) -> bool {
+    match self {
+      Field::Same => true,
+      Field::Primary => scope != SearchScope::OriginalTitles,
+      Field::Original => scope != SearchScope::PrimaryTitles,
+    }
+  }
+}
+
+/// Titles with a name, by the field the name was indexed from.
+struct ByField<C> {
+  primary: ByYear<C>,
+  original: ByYear<C>,
+  same: ByYear<C>,
+}
+
+impl<C> Default for ByField<C> {
+  fn default() -> Self {
+    Self { primary: Default::default(), original: Default::default(), same: Default::default() }
+  }
+}
+
+impl<C> ByField<C> {
+  /// The titles indexed from a field.
+  ///
+  /// # Arguments
+  ///
+  /// * `field` - The field the titles were indexed from.
+  fn field_mut(&mut self, field: Field) -> &mut ByYear<C> {
+    match field {
+      Field::Primary => &mut self.primary,
+      Field::Original => &mut self.original,
+      Field::Same => &mut self.same,
+    }
+  }
+
+  /// The titles indexed from a field.
+  ///
+  /// # Arguments
+  ///
+  /// * `field` - The field the titles were indexed from.
+  #[cfg(feature = "fs")]
+  fn field(&self, field: Field) -> &ByYear<C> {
+    match field {
+      Field::Primary => &self.primary,
+      Field::Original => &self.original,
+      Field::Same => &self.same,
+    }
+  }
+
+  /// The titles indexed from the fields in a search scope.
+  ///
+  /// # Arguments
+  ///
+  /// * `scope` - The search scope.
+  fn in_scope(&self, scope: SearchScope) -> impl Iterator<Item = &ByYear<C>> {
+    let (primary, original) = match scope {
+      SearchScope::All => (Some(&self.primary), Some(&self.original)),
+      SearchScope::PrimaryTitles => (Some(&self.primary), None),
+      SearchScope::OriginalTitles => (None, Some(&self.original)),
+    };
+
+    std::iter::once(&self.same).chain(primary).chain(original)
+  }
 
-pub(crate) struct DbImpl<C> {
+  /// Iterate over all the years and the matching titles in a search scope.
+  fn values(&self, scope: SearchScope) -> impl Iterator<Item = &Vec<C>> {
+    self.in_scope(scope).flat_map(|by_year| by_year.values())
+  }
+
+  /// The titles released in a year in a search scope.
+  fn get(&self, year: u16, scope: SearchScope) -> impl Iterator<Item = &C> {
+    self.in_scope(scope).filter_map(move |by_year| by_year.get(&year)).flatten()
+  }
+}
+
+pub(crate) struct DbImpl<'storage, C> {
   /// The actual storage of title information.
-  titles: Vec<Title<'static>>,
+  titles: Vec<Title<'storage>>,
   /// Map from title IDs to Titles.
   by_id: ById<C>,
-  /// Map from years to title names to Titles.
+  /// Map from title names to the fields they were indexed from to years to Titles.
   by_title: ByTitle<C>,
+  /// Like `by_title`, for the normalized names that start with an article, without it.
+  by_stripped_title: ByTitle<C>,
+  /// The names of `by_title` by the words they contain, for keyword queries.
+  #[cfg(feature = "trie")]
+  words: WordIndex,
+  /// Whether titles are indexed by name, which is not needed when names are looked up in
+  /// an index stored alongside the databases.
+  index_names: bool,
 }
 
-impl<C: Into<usize>> Index<C> for DbImpl<C> {
-  type Output = Title<'static>;
+impl<'storage, C: Into<usize>> Index<C> for DbImpl<'storage, C> {
+  type Output = Title<'storage>;
 
   fn index(&self, index: C) -> &Self::Output {
     unsafe { self.titles.get_unchecked(index.into()) }
   }
 }
 
-impl<C: From<usize>> DbImpl<C> {
+impl<'storage, C: From<usize>> DbImpl<'storage, C> {
   fn next_cookie(&self) -> C {
     C::from(self.n_titles())
   }
 }
 
-impl<C: From<usize> + Into<usize> + Copy> DbImpl<C> {
+impl<'storage, C: From<usize> + Into<usize> + Copy> DbImpl<'storage, C> {
   /// Insert a given title into the database.
   ///
   /// # Arguments
   ///
   /// * `title` - The title to be inserted.
-  pub(crate) fn store_title(&mut self, title: Title<'static>) {
+  pub(crate) fn store_title(&mut self, title: Title<'storage>) {
     let cookie = self.next_cookie();
 
     self.insert_by_id(title.title_id(), cookie);
 
-    let lc_primary_title = title.primary_title().to_lowercase();
+    let primary_field = if title.original_title().is_some() {
+      Field::Primary
+    } else {
+      Field::Same
+    };
+    self.insert_name(title.primary_title(), primary_field, title.start_year(), cookie);
 
-    let deunicoded_primary_title = deunicode(&lc_primary_title);
-    if deunicoded_primary_title != lc_primary_title {
-      self.insert_by_title_and_year(deunicoded_primary_title, title.start_year(), cookie);
+    if let Some(original_title) = title.original_title() {
+      self.insert_name(original_title, Field::Original, title.start_year(), cookie);
     }
 
-    self.insert_by_title_and_year(lc_primary_title, title.start_year(), cookie);
-
-    if let Some(original_title) = title.original_title() {
-      let lc_original_title = original_title.to_lowercase();
+    self.store(title);
+  }
 
-      let deunicoded_original_title = deunicode(&lc_original_title);
-      if deunicoded_original_title != lc_original_title {
-        self.insert_by_title_and_year(deunicoded_original_title, title.start_year(), cookie);
+  /// Index the titles by their names in non-Latin scripts as well, as if they were original
+  /// titles, in addition to the names [DbImpl::store_title] indexed them by.
+  ///
+  /// # Arguments
+  ///
+  /// * `akas` - The names of titles in non-Latin scripts.
+  #[cfg(feature = "akas")]
+  pub(crate) fn store_akas(&mut self, akas: &Akas) {
+    for index in 0..self.titles.len() {
+      let title = &self.titles[index];
+      let year = title.start_year();
+
+      for name in akas.of_title(title.title_id()) {
+        self.insert_name(name, Field::Original, year, C::from(index));
       }
+    }
+  }
+
+  /// Insert cookie for the title with the given name, in lowercase with its accents, and
+  /// normalized as search strings are if that differs, so that both match.
+  ///
+  /// # Arguments
+  ///
+  /// * `name` - Name of the title to be inserted.
+  /// * `field` - The field of the title the name is from.
+  /// * `year` - Release year of the title to be inserted.
+  /// * `cookie` - Cookie to be inserted.
+  fn insert_name(&mut self, name: &str, field: Field, year: Option<u16>, cookie: C) {
+    if !self.index_names {
+      return;
+    }
+
+    let lc_name = search::lowercase(name);
+    let normalized_name = search::normalize(name);
 
-      self.insert_by_title_and_year(lc_original_title, title.start_year(), cookie);
+    let stripped_name = search::strip_article(&normalized_name, Language::ALL);
+    if stripped_name.len() != normalized_name.len() {
+      insert_by_title_and_year(&mut self.by_stripped_title, stripped_name.to_owned(), field, year, cookie);
     }
 
-    self.store(title);
+    if normalized_name != lc_name {
+      self.index_words(&normalized_name);
+      insert_by_title_and_year(&mut self.by_title, normalized_name, field, year, cookie);
+    }
+
+    self.index_words(&lc_name);
+    insert_by_title_and_year(&mut self.by_title, lc_name, field, year, cookie);
+  }
+
+  /// Index the words of a name for keyword queries, unless the name was already indexed.
+  ///
+  /// # Arguments
+  ///
+  /// * `name` - The name, as a key of the index by title.
+  #[cfg(feature = "trie")]
+  fn index_words(&mut self, name: &str) {
+    if !self.by_title.contains_key(name) {
+      self.words.insert(name);
+    }
   }
+
+  #[cfg(not(feature = "trie"))]
+  fn index_words(&mut self, _name: &str) {}
 }
 
-impl<C> DbImpl<C> {
+impl<'storage, C> DbImpl<'storage, C> {
   /// Construct a database with a starting capacity.
   ///
   /// # Arguments
@@ -84,7 +246,21 @@ impl<C> DbImpl<C> {
     let titles = Vec::with_capacity(cap);
     let by_id = Default::default();
     let by_title = Default::default();
-    Self { titles, by_id, by_title }
+    let by_stripped_title = Default::default();
+    Self {
+      titles,
+      by_id,
+      by_title,
+      by_stripped_title,
+      #[cfg(feature = "trie")]
+      words: Default::default(),
+      index_names: true,
+    }
+  }
+
+  /// Stop indexing the titles stored from now on by name.
+  pub(crate) fn skip_names(&mut self) {
+    self.index_names = false;
   }
 
   /// Insert a title into the database.
@@ -92,7 +268,7 @@ impl<C> DbImpl<C> {
   /// # Arguments
   ///
   /// * `title` - The title to be stored.
-  fn store(&mut self, title: Title<'static>) {
+  fn store(&mut self, title: Title<'storage>) {
     self.titles.push(title);
   }
 
@@ -101,6 +277,33 @@ impl<C> DbImpl<C> {
     self.titles.len()
   }
 
+  /// Estimate the bytes used by the titles and indexes of the database.
+  pub(crate) fn memory(&self) -> ShardMemory {
+    let titles = self.titles.capacity() * size_of::<Title>();
+    let by_id = hash_map_bytes::<usize, C>(self.by_id.capacity());
+
+    let by_title =
+      by_title_bytes(&self.by_title) + by_title_bytes(&self.by_stripped_title) + self.words_bytes();
+
+    ShardMemory::new(titles, by_id, by_title)
+  }
+
+  /// Estimate the bytes used by the index of the words of names.
+  #[cfg(feature = "trie")]
+  fn words_bytes(&self) -> usize {
+    self.words.bytes()
+  }
+
+  #[cfg(not(feature = "trie"))]
+  fn words_bytes(&self) -> usize {
+    0
+  }
+
+  /// Iterate over all titles stored in the database.
+  pub(crate) fn titles(&self) -> impl Iterator<Item = &Title<'storage>> {
+    self.titles.iter()
+  }
+
   /// Return a cookie for the given title ID.
   ///
   /// # Arguments
@@ -115,8 +318,18 @@ impl<C> DbImpl<C> {
   /// # Arguments
   ///
   /// * `title` - Title name to search for.
-  pub(crate) fn cookies_by_title(&self, title: &SearchString) -> Option<impl Iterator<Item = &C>> {
-    self.by_title.get(title.as_str()).map(|by_year| by_year.values().flatten())
+  /// * `scope` - Which names of the titles are searched.
+  pub(crate) fn cookies_by_title(
+    &self,
+    title: &SearchString,
+    scope: SearchScope,
+  ) -> impl Iterator<Item = &C> {
+    self
+      .by_title
+      .get(title.as_str())
+      .into_iter()
+      .flat_map(move |by_field| by_field.values(scope))
+      .flatten()
   }
 
   /// Search for titles with the given title and year.
@@ -125,14 +338,18 @@ impl<C> DbImpl<C> {
   ///
   /// * `title` - The title name to search for.
   /// * `year` - The year to search for titles in.
-  fn cookies_by_title_and_year(&self, title: &SearchString, year: u16) -> impl Iterator<Item = &C> {
-    if let Some(by_year) = self.by_title.get(title.as_str()) {
-      if let Some(cookies) = by_year.get(&year) {
-        return cookies.iter();
-      }
-    }
-
-    [].iter()
+  /// * `scope` - Which names of the titles are searched.
+  fn cookies_by_title_and_year(
+    &self,
+    title: &SearchString,
+    year: u16,
+    scope: SearchScope,
+  ) -> impl Iterator<Item = &C> {
+    self
+      .by_title
+      .get(title.as_str())
+      .into_iter()
+      .flat_map(move |by_field| by_field.get(year, scope))
   }
 
   /// Search for titles by keywords.
@@ -140,17 +357,19 @@ impl<C> DbImpl<C> {
   /// # Arguments
   ///
   /// * `keywords` - Keywords to search for in title names.
-  fn cookies_by_keywords<'a>(&'a self, keywords: &[SearchString]) -> impl Iterator<Item = &'a C> {
-    let searcher = AhoCorasickBuilder::new().match_kind(ACMatchKind::LeftmostFirst).build(keywords);
-    let keywords_len = keywords.len();
+  /// * `kind` - How keywords are matched against title names.
+  /// * `scope` - Which names of the titles are searched.
+  fn cookies_by_keywords<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    kind: KeywordMatchKind,
+    scope: SearchScope,
+  ) -> impl Iterator<Item = &'a C> {
+    let matcher = KeywordMatcher::new(keywords, kind);
     self
-      .by_title
-      .iter()
-      .filter(move |&(title, _)| {
-        let matches: FnvHashSet<_> = searcher.find_iter(title).map(|mat| mat.pattern()).collect();
-        matches.len() == keywords_len
-      })
-      .flat_map(|(_, by_year)| by_year.values())
+      .names_by_keywords(keywords)
+      .filter(move |&(title, _)| matcher.matches_all(title))
+      .flat_map(move |(_, by_field)| by_field.values(scope))
       .flatten()
   }
 
@@ -160,22 +379,54 @@ impl<C> DbImpl<C> {
   ///
   /// * `keywords` - Keywords to search for in title names.
   /// * `year` - The year to search for titles in.
+  /// * `kind` - How keywords are matched against title names.
+  /// * `scope` - Which names of the titles are searched.
   fn cookies_by_keywords_and_year<'a>(
     &'a self,
     keywords: &[SearchString],
     year: u16,
+    kind: KeywordMatchKind,
+    scope: SearchScope,
   ) -> impl Iterator<Item = &'a C> {
-    let searcher = AhoCorasickBuilder::new().match_kind(ACMatchKind::LeftmostFirst).build(keywords);
-    let keywords_len = keywords.len();
+    let matcher = KeywordMatcher::new(keywords, kind);
     self
-      .by_title
-      .iter()
-      .filter(move |&(title, _)| {
-        let matches: FnvHashSet<_> = searcher.find_iter(title).map(|mat| mat.pattern()).collect();
-        matches.len() == keywords_len
-      })
-      .filter_map(move |(_, by_year)| by_year.get(&year))
-      .flatten()
+      .names_by_keywords(keywords)
+      .filter(move |&(title, _)| matcher.matches_all(title))
+      .flat_map(move |(_, by_field)| by_field.get(year, scope))
+  }
+
+  /// The names that keyword queries match against, with their titles: the ones the index of
+  /// words finds for the keywords, or all of them if it cannot narrow them down.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - Keywords to search for in title names.
+  #[cfg(feature = "trie")]
+  fn names_by_keywords<'a>(
+    &'a self,
+    keywords: &[SearchString],
+  ) -> impl Iterator<Item = (&'a str, &'a ByField<C>)> {
+    match self.words.candidates(keywords) {
+      Some(names) => Either::Left(
+        names
+          .into_iter()
+          .filter_map(move |name| self.by_title.get(name).map(|by_field| (name, by_field))),
+      ),
+      None => Either::Right(self.by_title.iter().map(|(name, by_field)| (name.as_str(), by_field))),
+    }
+  }
+
+  /// The names that keyword queries match against, with their titles.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - Keywords to search for in title names.
+  #[cfg(not(feature = "trie"))]
+  fn names_by_keywords<'a>(
+    &'a self,
+    _keywords: &[SearchString],
+  ) -> impl Iterator<Item = (&'a str, &'a ByField<C>)> {
+    self.by_title.iter().map(|(name, by_field)| (name.as_str(), by_field))
   }
 
   /// Insert a cookie with the given title ID.
@@ -187,44 +438,114 @@ impl<C> DbImpl<C> {
   fn insert_by_id(&mut self, id: &TitleId, cookie: C) -> bool {
     self.by_id.insert(id.as_usize(), cookie).is_none()
   }
+}
 
-  /// Insert cookie for the title with the given name and year.
-  ///
-  /// # Arguments
-  ///
-  /// * `title` - Name of the title to be inserted.
-  /// * `year` - Release year of the title to be inserted.
-  /// * `cookie` - Cookie to be inserted.
-  fn insert_by_title_and_year(&mut self, title: String, year: Option<u16>, cookie: C) {
-    if let Some(year) = year {
-      self.by_title.entry(title).or_default().entry(year).or_default().push(cookie);
-    } else {
-      self.by_title.entry(title).or_default().entry(0).or_default().push(cookie);
+/// Insert cookie for the title with the given name and year.
+///
+/// # Arguments
+///
+/// * `by_title` - The index to insert the cookie into.
+/// * `title` - Name of the title to be inserted.
+/// * `field` - The field of the title the name is from.
+/// * `year` - Release year of the title to be inserted.
+/// * `cookie` - Cookie to be inserted.
+fn insert_by_title_and_year<C>(
+  by_title: &mut ByTitle<C>,
+  title: String,
+  field: Field,
+  year: Option<u16>,
+  cookie: C,
+) {
+  let by_year = by_title.entry(title).or_default().field_mut(field);
+  by_year.entry(year.unwrap_or(0)).or_default().push(cookie);
+}
+
+/// Estimate the bytes used by an index of titles by name.
+///
+/// # Arguments
+///
+/// * `by_title` - The index.
+fn by_title_bytes<C>(by_title: &ByTitle<C>) -> usize {
+  let mut bytes = hash_map_bytes::<String, ByField<C>>(by_title.capacity());
+  for (name, by_field) in by_title {
+    bytes += name.capacity();
+    for by_year in by_field.in_scope(SearchScope::All) {
+      bytes += hash_map_bytes::<u16, Vec<C>>(by_year.capacity());
+      bytes += by_year
+        .values()
+        .map(|cookies| cookies.capacity() * size_of::<C>())
+        .sum::<usize>();
     }
   }
+  bytes
 }
 
-impl<C: Into<usize> + Copy> DbImpl<C> {
+impl<'storage, C: Into<usize> + Copy> DbImpl<'storage, C> {
   /// Find title by IMDB ID.
   ///
   /// # Arguments
   ///
   /// * `id` - Title ID to find.
-  pub(crate) fn by_id(&self, id: &TitleId) -> Option<&Title> {
+  pub(crate) fn by_id(&self, id: &TitleId) -> Option<&Title<'_>> {
     self.cookie_by_id(id).map(|&cookie| &self[cookie])
   }
 
+  /// Find title by the numeric part of its IMDB ID.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - Numeric part of the title ID to find.
+  pub(crate) fn by_id_number(&self, id: usize) -> Option<&Title<'_>> {
+    self.by_id.get(&id).map(|&cookie| &self[cookie])
+  }
+
+  /// Iterate over the indexed names with the field each was indexed from, whether it is a
+  /// name without its leading article, and the numeric part of the ID of each title
+  /// indexed by it.
+  #[cfg(feature = "fs")]
+  pub(crate) fn names(&self) -> impl Iterator<Item = (&str, Field, bool, usize)> {
+    self
+      .names_of(&self.by_title, false)
+      .chain(self.names_of(&self.by_stripped_title, true))
+  }
+
+  /// Iterate over the names of an index of titles by name, like [DbImpl::names].
+  ///
+  /// # Arguments
+  ///
+  /// * `by_title` - The index.
+  /// * `stripped` - Whether the index is of names without their leading article.
+  #[cfg(feature = "fs")]
+  fn names_of<'a>(
+    &'a self,
+    by_title: &'a ByTitle<C>,
+    stripped: bool,
+  ) -> impl Iterator<Item = (&'a str, Field, bool, usize)> {
+    by_title.iter().flat_map(move |(name, by_field)| {
+      [Field::Primary, Field::Original, Field::Same]
+        .into_iter()
+        .flat_map(move |field| {
+          by_field
+            .field(field)
+            .values()
+            .flatten()
+            .map(move |&cookie| (name.as_str(), field, stripped, self[cookie].title_id().as_usize()))
+        })
+    })
+  }
+
   /// Find titles by name.
   ///
   /// # Arguments
   ///
   /// * `title` - Title name to search for.
-  pub(crate) fn by_title<'a>(&'a self, title: &SearchString) -> Box<dyn Iterator<Item = &Title> + 'a> {
-    if let Some(cookies) = self.cookies_by_title(title) {
-      return Box::new(cookies.map(|&cookie| &self[cookie]));
-    }
-
-    Box::new(std::iter::empty())
+  /// * `scope` - Which names of the titles are searched.
+  pub(crate) fn by_title(
+    &self,
+    title: &SearchString,
+    scope: SearchScope,
+  ) -> impl Iterator<Item = &Title<'_>> {
+    self.cookies_by_title(title, scope).map(|&cookie| &self[cookie])
   }
 
   /// Find titles by name and year.
@@ -233,8 +554,70 @@ impl<C: Into<usize> + Copy> DbImpl<C> {
   ///
   /// * `title` - Title name to search for.
   /// * `year` - The year to search for titles in.
-  pub(crate) fn by_title_and_year(&self, title: &SearchString, year: u16) -> impl Iterator<Item = &Title> {
-    self.cookies_by_title_and_year(title, year).map(|&cookie| &self[cookie])
+  /// * `scope` - Which names of the titles are searched.
+  pub(crate) fn by_title_and_year(
+    &self,
+    title: &SearchString,
+    year: u16,
+    scope: SearchScope,
+  ) -> impl Iterator<Item = &Title<'_>> {
+    self.cookies_by_title_and_year(title, year, scope).map(|&cookie| &self[cookie])
+  }
+
+  /// Find titles whose names match the given name once their leading articles are stripped,
+  /// e.g. "The Matrix" for "matrix" and "Matrix" for "the matrix", in any of the known
+  /// languages. Titles matching the name exactly may be found as well.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - Title name to search for.
+  /// * `year` - The year to search for titles in, if any.
+  /// * `scope` - Which names of the titles are searched.
+  pub(crate) fn by_stripped_title(
+    &self,
+    title: &SearchString,
+    year: Option<u16>,
+    scope: SearchScope,
+  ) -> impl Iterator<Item = &Title<'_>> {
+    let stripped = search::strip_article(title.as_str(), Language::ALL);
+    let with_article = self.by_stripped_title.get(stripped);
+    let without_article = if stripped.len() != title.as_str().len() {
+      self.by_title.get(stripped)
+    } else {
+      None
+    };
+
+    with_article
+      .into_iter()
+      .chain(without_article)
+      .flat_map(move |by_field| by_field.values(scope))
+      .flatten()
+      .map(|&cookie| &self[cookie])
+      .filter(move |title| year.is_none_or(|year| title.start_year() == Some(year)))
+  }
+
+  /// Search for titles whose lowercase names, or their ASCII transliterations, match a
+  /// regular expression.
+  ///
+  /// # Arguments
+  ///
+  /// * `regex` - The regular expression to match title names with.
+  /// * `scope` - Which names of the titles are searched.
+  pub(crate) fn by_title_regex<'a, 'r>(
+    &'a self,
+    regex: &'r Regex,
+    scope: SearchScope,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'r
+  where
+    'a: 'r,
+  {
+    self
+      .by_title
+      .iter()
+      .filter(|&(title, _)| regex.is_match(title))
+      .flat_map(move |(_, by_field)| by_field.values(scope))
+      .flatten()
+      .map(|&cookie| &self[cookie])
   }
 
   /// Search for titles by keywords.
@@ -242,8 +625,15 @@ impl<C: Into<usize> + Copy> DbImpl<C> {
   /// # Arguments
   ///
   /// * `keywords` - Keywords to search for.
-  pub(crate) fn by_keywords<'a>(&'a self, keywords: &[SearchString]) -> impl Iterator<Item = &'a Title> {
-    self.cookies_by_keywords(keywords).map(|&cookie| &self[cookie])
+  /// * `kind` - How keywords are matched against title names.
+  /// * `scope` - Which names of the titles are searched.
+  pub(crate) fn by_keywords<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    kind: KeywordMatchKind,
+    scope: SearchScope,
+  ) -> impl Iterator<Item = &'a Title<'a>> {
+    self.cookies_by_keywords(keywords, kind, scope).map(|&cookie| &self[cookie])
   }
 
   /// Search for titles by keywords and year.
@@ -252,12 +642,18 @@ impl<C: Into<usize> + Copy> DbImpl<C> {
   ///
   /// * `keywords` - Keywords to search for.
   /// * `year` - The year to search for titles in.
+  /// * `kind` - How keywords are matched against title names.
+  /// * `scope` - Which names of the titles are searched.
   pub(crate) fn by_keywords_and_year<'a>(
     &'a self,
     keywords: &[SearchString],
     year: u16,
-  ) -> impl Iterator<Item = &'a Title> {
-    self.cookies_by_keywords_and_year(keywords, year).map(|&cookie| &self[cookie])
+    kind: KeywordMatchKind,
+    scope: SearchScope,
+  ) -> impl Iterator<Item = &'a Title<'a>> {
+    self
+      .cookies_by_keywords_and_year(keywords, year, kind, scope)
+      .map(|&cookie| &self[cookie])
   }
 }
 
@@ -270,14 +666,17 @@ mod test_db_impl {
   use crate::imdb::testdata::{make_basics_reader, make_ratings_reader};
   use crate::imdb::title::{Title, TsvAction};
   use crate::imdb::title_id::TitleId;
-  use crate::utils::search::SearchString;
+  use crate::imdb::tsv_import::ImportStats;
+  use crate::utils::search::{KeywordMatchKind, SearchScope, SearchString};
+
+  use regex::Regex;
 
-  fn make_db_impl() -> DbImpl<usize> {
+  fn make_db_impl() -> DbImpl<'static, usize> {
     let mut db_impl = DbImpl::with_capacity(10);
     let ratings = Ratings::from_tsv(make_ratings_reader()).unwrap();
     for line in make_basics_reader().lines().skip(1) {
       let line = Box::leak(line.unwrap().into_boxed_str());
-      match Title::from_tsv(line.as_bytes(), &ratings).unwrap() {
+      match Title::from_tsv(line.as_bytes(), &ratings, &mut ImportStats::default()).unwrap() {
         TsvAction::Skip => {}
         TsvAction::Movie(title) => db_impl.store_title(title),
         TsvAction::Series(_) => panic!("Invalid test contents"),
@@ -290,7 +689,7 @@ mod test_db_impl {
   fn test_by_title() {
     let db_impl = make_db_impl();
     let title = SearchString::try_from("Corbett and Courtney Before the Kinetograph").unwrap();
-    let titles: Vec<_> = db_impl.by_title(&title).collect();
+    let titles: Vec<_> = db_impl.by_title(&title, SearchScope::All).collect();
     assert_eq!(titles.len(), 1);
     let title = titles[0];
     assert_eq!(title.title_id(), &TitleId::try_from("tt0000007").unwrap());
@@ -301,7 +700,7 @@ mod test_db_impl {
   fn test_by_title_and_year() {
     let db_impl = make_db_impl();
     let title = SearchString::try_from("Corbett and Courtney Before the Kinetograph").unwrap();
-    let titles: Vec<_> = db_impl.by_title_and_year(&title, 1894).collect();
+    let titles: Vec<_> = db_impl.by_title_and_year(&title, 1894, SearchScope::All).collect();
     assert_eq!(titles.len(), 1);
     let title = titles[0];
     assert_eq!(title.title_id(), &TitleId::try_from("tt0000007").unwrap());
@@ -312,7 +711,11 @@ mod test_db_impl {
   fn test_by_keywords() {
     let db_impl = make_db_impl();
     let titles: Vec<_> = db_impl
-      .by_keywords(&[SearchString::try_from("Corbett").unwrap(), SearchString::try_from("Courtney").unwrap()])
+      .by_keywords(
+        &[SearchString::try_from("Corbett").unwrap(), SearchString::try_from("Courtney").unwrap()],
+        KeywordMatchKind::default(),
+        SearchScope::All,
+      )
       .collect();
     assert_eq!(titles.len(), 1);
     let title = titles[0];
@@ -327,6 +730,8 @@ mod test_db_impl {
       .by_keywords_and_year(
         &[SearchString::try_from("Corbett").unwrap(), SearchString::try_from("Courtney").unwrap()],
         1894,
+        KeywordMatchKind::default(),
+        SearchScope::All,
       )
       .collect();
     assert_eq!(titles.len(), 1);
@@ -334,4 +739,100 @@ mod test_db_impl {
     assert_eq!(title.title_id(), &TitleId::try_from("tt0000007").unwrap());
     assert_eq!(title.primary_title(), "Corbett and Courtney Before the Kinetograph");
   }
+
+  #[test]
+  fn test_by_overlapping_keywords() {
+    let db_impl = make_db_impl();
+    let keywords = [SearchString::try_from("Corb").unwrap(), SearchString::try_from("Corbett").unwrap()];
+
+    let titles: Vec<_> = db_impl
+      .by_keywords(&keywords, KeywordMatchKind::Overlapping, SearchScope::All)
+      .collect();
+    assert_eq!(titles.len(), 1);
+    assert_eq!(titles[0].title_id(), &TitleId::try_from("tt0000007").unwrap());
+
+    let titles: Vec<_> = db_impl
+      .by_keywords(&keywords, KeywordMatchKind::LeftmostFirst, SearchScope::All)
+      .collect();
+    assert!(titles.is_empty());
+  }
+
+  #[test]
+  fn test_search_scope() {
+    let db_impl = make_db_impl();
+    let ids =
+      |titles: Vec<&Title>| titles.iter().map(|title| title.title_id().as_usize()).collect::<Vec<_>>();
+
+    let original = SearchString::try_from("La sortie de l'usine Lumière à Lyon").unwrap();
+    let primary = SearchString::try_from("Leaving the Factory").unwrap();
+    let same = SearchString::try_from("Corbett and Courtney Before the Kinetograph").unwrap();
+    for (title, year, scope, expected) in [
+      (&original, 1895, SearchScope::All, vec![10]),
+      (&original, 1895, SearchScope::OriginalTitles, vec![10]),
+      (&original, 1895, SearchScope::PrimaryTitles, vec![]),
+      (&primary, 1895, SearchScope::All, vec![10]),
+      (&primary, 1895, SearchScope::OriginalTitles, vec![]),
+      (&primary, 1895, SearchScope::PrimaryTitles, vec![10]),
+      (&same, 1894, SearchScope::OriginalTitles, vec![7]),
+      (&same, 1894, SearchScope::PrimaryTitles, vec![7]),
+    ] {
+      assert_eq!(ids(db_impl.by_title(title, scope).collect()), expected);
+      assert_eq!(ids(db_impl.by_title_and_year(title, year, scope).collect()), expected);
+    }
+
+    let keywords = [SearchString::try_from("lumiere").unwrap()];
+    let kind = KeywordMatchKind::default();
+    assert_eq!(ids(db_impl.by_keywords(&keywords, kind, SearchScope::OriginalTitles).collect()), [10]);
+    assert!(db_impl
+      .by_keywords(&keywords, kind, SearchScope::PrimaryTitles)
+      .next()
+      .is_none());
+
+    let keywords = [SearchString::try_from("LUMIÈRE").unwrap(), SearchString::try_from("Usine").unwrap()];
+    assert_eq!(ids(db_impl.by_keywords(&keywords, kind, SearchScope::All).collect()), [10]);
+  }
+
+  #[test]
+  fn test_accents() {
+    let mut db_impl = DbImpl::<usize>::with_capacity(4);
+    let ratings = Ratings::from_tsv(make_ratings_reader()).unwrap();
+    for line in [
+      "tt0211915\tmovie\tAmélie\tLe Fabuleux Destin d'Amélie Poulain\t0\t2001\t\\N\t122\tComedy,Romance",
+      "tt0052654\tmovie\tThe Bridge\tDie Bru\u{308}cke\t0\t1959\t\\N\t103\tDrama,War",
+      "tt0074360\tmovie\tRaise Ravens\tCría cuervos\t0\t1976\t\\N\t110\tDrama",
+      "tt9999998\tmovie\tAmelie\tAmelie\t0\t2001\t\\N\t90\tDrama",
+    ] {
+      let line = Box::leak(line.to_owned().into_boxed_str());
+      match Title::from_tsv(line.as_bytes(), &ratings, &mut ImportStats::default()).unwrap() {
+        TsvAction::Movie(title) => db_impl.store_title(title),
+        _ => panic!("Invalid test contents"),
+      }
+    }
+
+    let ids = |query: &str| {
+      let mut ids: Vec<_> = db_impl
+        .by_title(&SearchString::try_from(query).unwrap(), SearchScope::All)
+        .map(|title| title.title_id().as_usize())
+        .collect();
+      ids.sort_unstable();
+      ids
+    };
+    assert_eq!(ids("Amélie"), [211915, 9999998]);
+    assert_eq!(ids("AMELIE"), [211915, 9999998]);
+    assert_eq!(ids("Ame\u{301}lie"), [211915, 9999998]);
+    assert_eq!(ids("die brücke"), [52654]);
+    assert_eq!(ids("DIE BRUCKE"), [52654]);
+    assert_eq!(ids("cria cuervos"), [74360]);
+    assert_eq!(ids("Cría Cuervos"), [74360]);
+
+    let keywords = [SearchString::try_from("destin").unwrap(), SearchString::try_from("amélie").unwrap()];
+    let titles: Vec<_> = db_impl
+      .by_keywords(&keywords, KeywordMatchKind::default(), SearchScope::OriginalTitles)
+      .collect();
+    assert_eq!(titles.len(), 1);
+    assert_eq!(titles[0].original_title(), Some("Le Fabuleux Destin d'Amélie Poulain"));
+
+    let regex = Regex::new("brücke").unwrap();
+    assert_eq!(db_impl.by_title_regex(&regex, SearchScope::All).count(), 1);
+  }
 }
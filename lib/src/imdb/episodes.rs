@@ -0,0 +1,709 @@
+#![warn(clippy::all)]
+
+//! Episodes of series, imported from the `title.episode` IMDB dump.
+//!
+//! Episodes are kept in their own database next to the movies and series databases, since
+//! they are only needed by a few queries and are several times more numerous than all the
+//! other titles together. The database is only fetched and loaded when it is asked for.
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+#[cfg(feature = "fetch")]
+use std::time::{Duration, Instant};
+
+use crate::imdb::column::{Column, Value as ColumnValue};
+use crate::imdb::db_file::{self, Compression, Contents};
+use crate::imdb::ratings::{Rating, Ratings};
+#[cfg(feature = "fetch")]
+use crate::imdb::service::{IMDB_URL, RATINGS_FILENAME};
+use crate::imdb::title_id::TitleId;
+use crate::imdb::tokens;
+use crate::iter_next;
+#[cfg(feature = "fetch")]
+use crate::utils::cancel::{CancellableReader, Cancellation};
+#[cfg(feature = "fetch")]
+use crate::utils::io::file as io_file;
+use crate::utils::io::net as io_net;
+
+use atoi::atoi;
+use fnv::FnvHashMap;
+#[cfg(feature = "fetch")]
+use humantime::format_duration;
+use serde::Serialize;
+use tracing::{debug, info_span};
+#[cfg(feature = "fetch")]
+use url::Url;
+
+/// Errors when importing or loading episodes.
+#[derive(Debug, thiserror::Error)]
+#[error("IMDB episodes error")]
+pub enum Error {
+  /// File-related error.
+  #[error("File handling error: {0}")]
+  File(#[from] crate::utils::io::file::Error),
+  /// Networking-related error.
+  #[cfg(feature = "fetch")]
+  #[error("Network handling error: {0}")]
+  Net(#[from] crate::utils::io::net::Error),
+  /// URL parsing error.
+  #[error("Error parsing URL: {0}")]
+  UrlParsing(#[from] url::ParseError),
+  /// Database file error.
+  #[error("Episodes database error: {0}")]
+  DbFile(#[from] crate::imdb::db_file::Error),
+  /// Ratings parsing error.
+  #[error("Ratings parsing error: {0}")]
+  RatingsParsing(#[from] crate::imdb::ratings::Error),
+  /// ID parsing errors.
+  #[error("Error parsing ID: {0}")]
+  IdParsing(#[from] crate::imdb::title_id::Error),
+  /// General parsing errors.
+  #[error("Parsing error: {0}")]
+  Parsing(#[from] crate::utils::tokens::Error),
+  /// Season or episode number is not a valid number.
+  #[error("Invalid season or episode number `{0}`")]
+  Number(String),
+  /// The size of the database is not a multiple of the record size.
+  #[error("Episodes database is truncated")]
+  Truncated,
+  /// IO errors.
+  #[error("IO error: {0}")]
+  Io(#[from] io::Error),
+  /// The operation was cancelled.
+  #[error("Operation cancelled")]
+  Cancelled,
+}
+
+#[cfg(feature = "fetch")]
+const EPISODES_FILENAME: &str = "title.episode.tsv.gz";
+const EPISODES_DB_FILENAME: &str = "imdb-episodes.tvrankdb";
+
+/// # Episode records are 17 bytes composed of:
+///
+/// * 4 bytes: Series ID number (little-endian)
+/// * 4 bytes: Episode ID number (little-endian)
+/// * 2 bytes: Season number (little-endian)
+/// * 2 bytes: Episode number (little-endian)
+/// * 1 byte:  Rating
+/// * 4 bytes: Number of votes (little-endian), zero if the episode is not rated
+///
+/// Records are sorted by series, season and episode number.
+const RECORD_LEN: usize = 17;
+
+/// An episode of a series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Episode {
+  #[serde(serialize_with = "serialize_id")]
+  id: u32,
+  season: u16,
+  episode: u16,
+  rating: Option<Rating>,
+}
+
+fn serialize_id<S: serde::Serializer>(id: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+  serializer.serialize_str(&format_id(*id))
+}
+
+/// Format an ID number the way IMDB does, e.g. `tt0000001`.
+fn format_id(id: u32) -> String {
+  format!("tt{id:07}")
+}
+
+impl Episode {
+  /// Returns the IMDB ID of the episode, e.g. `tt0959621`.
+  pub fn id(&self) -> String {
+    format_id(self.id)
+  }
+
+  /// Returns the season the episode belongs to.
+  pub fn season(&self) -> u16 {
+    self.season
+  }
+
+  /// Returns the number of the episode within its season.
+  pub fn episode(&self) -> u16 {
+    self.episode
+  }
+
+  /// Returns the rating of the episode, if it was rated.
+  pub fn rating(&self) -> Option<&Rating> {
+    self.rating.as_ref()
+  }
+
+  fn from_record(record: &[u8]) -> Self {
+    let u32_at = |at: usize| u32::from_le_bytes([record[at], record[at + 1], record[at + 2], record[at + 3]]);
+    let u16_at = |at: usize| u16::from_le_bytes([record[at], record[at + 1]]);
+
+    let votes = u32_at(13);
+    let rating = if votes == 0 {
+      None
+    } else {
+      Some(Rating::new(record[12], votes))
+    };
+
+    Self { id: u32_at(4), season: u16_at(8), episode: u16_at(10), rating }
+  }
+}
+
+/// The episodes of a season of a series.
+#[derive(Debug, Clone, Serialize)]
+pub struct Season {
+  number: u16,
+  episodes: Vec<Episode>,
+}
+
+impl Season {
+  /// Returns the number of the season.
+  pub fn number(&self) -> u16 {
+    self.number
+  }
+
+  /// Returns the episodes of the season, in order.
+  pub fn episodes(&self) -> &[Episode] {
+    &self.episodes
+  }
+
+  /// Returns the average rating of the rated episodes of the season, out of 100, or `None`
+  /// if none of them are rated.
+  pub fn average(&self) -> Option<f64> {
+    average(self.episodes.iter())
+  }
+}
+
+/// The average rating of the rated episodes, out of 100.
+fn average<'a>(episodes: impl Iterator<Item = &'a Episode>) -> Option<f64> {
+  let (sum, count) = episodes
+    .filter_map(Episode::rating)
+    .fold((0u32, 0u32), |(sum, count), rating| (sum + u32::from(rating.rating()), count + 1));
+
+  if count == 0 {
+    None
+  } else {
+    Some(f64::from(sum) / f64::from(count))
+  }
+}
+
+/// The episodes of a series, grouped by season.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SeriesEpisodes {
+  seasons: Vec<Season>,
+}
+
+impl SeriesEpisodes {
+  fn new(episodes: impl Iterator<Item = Episode>) -> Self {
+    let mut seasons: Vec<Season> = Vec::new();
+
+    for episode in episodes {
+      match seasons.last_mut() {
+        Some(season) if season.number == episode.season => season.episodes.push(episode),
+        _ => seasons.push(Season { number: episode.season, episodes: vec![episode] }),
+      }
+    }
+
+    Self { seasons }
+  }
+
+  /// Returns the seasons of the series, in order.
+  pub fn seasons(&self) -> &[Season] {
+    &self.seasons
+  }
+
+  /// Returns all the episodes of the series, in order.
+  pub fn episodes(&self) -> impl Iterator<Item = &Episode> {
+    self.seasons.iter().flat_map(|season| season.episodes.iter())
+  }
+
+  /// Returns the number of episodes of the series.
+  pub fn len(&self) -> usize {
+    self.seasons.iter().map(|season| season.episodes.len()).sum()
+  }
+
+  /// Returns true if the series has no known episodes.
+  pub fn is_empty(&self) -> bool {
+    self.seasons.is_empty()
+  }
+
+  /// Returns the average rating of all the rated episodes, out of 100.
+  pub fn average(&self) -> Option<f64> {
+    average(self.episodes())
+  }
+
+  /// Returns the best rated episode, the one with the most votes among equally rated ones.
+  pub fn best(&self) -> Option<&Episode> {
+    self
+      .episodes()
+      .filter(|episode| episode.rating.is_some())
+      .max_by_key(|episode| episode.rating)
+  }
+
+  /// Returns the worst rated episode, the one with the fewest votes among equally rated
+  /// ones.
+  pub fn worst(&self) -> Option<&Episode> {
+    self
+      .episodes()
+      .filter(|episode| episode.rating.is_some())
+      .min_by_key(|episode| episode.rating)
+  }
+}
+
+/// The number of seasons and episodes of a series, and the average rating of its episodes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Summary {
+  seasons: usize,
+  episodes: usize,
+  average: Option<f64>,
+}
+
+impl Summary {
+  /// Returns the number of seasons.
+  pub fn seasons(&self) -> usize {
+    self.seasons
+  }
+
+  /// Returns the number of episodes.
+  pub fn episodes(&self) -> usize {
+    self.episodes
+  }
+
+  /// Returns the average rating of the rated episodes, out of 100.
+  pub fn average(&self) -> Option<f64> {
+    self.average
+  }
+}
+
+/// The episodes of all series.
+pub struct Episodes {
+  records: Contents,
+  series: FnvHashMap<u32, Range<usize>>,
+}
+
+impl Episodes {
+  /// Returns the episodes database in `cache_dir`, fetching and building it first if it does
+  /// not exist or is outdated.
+  ///
+  /// # Arguments
+  ///
+  /// * `cache_dir` - Directory path of the database files.
+  /// * `force_db_update` - True if the database should be updated regardless of its age.
+  /// * `progress_fn` - Function that keeps track of the download progress.
+  #[cfg(feature = "fetch")]
+  pub fn new(
+    cache_dir: &Path,
+    force_db_update: bool,
+    progress_fn: impl Fn(Option<u64>, u64),
+  ) -> Result<Self, Error> {
+    Self::new_cancellable(cache_dir, force_db_update, progress_fn, &Cancellation::new(), Compression::None)
+  }
+
+  /// Returns the episodes database in `cache_dir`, or [Error::Cancelled] if `cancellation`
+  /// is cancelled while it is being fetched and built.
+  ///
+  /// # Arguments
+  ///
+  /// * `cache_dir` - Directory path of the database files.
+  /// * `force_db_update` - True if the database should be updated regardless of its age.
+  /// * `progress_fn` - Function that keeps track of the download progress.
+  /// * `cancellation` - Token to cancel fetching and building the database.
+  /// * `compression` - Compression of a newly built database.
+  #[cfg(feature = "fetch")]
+  pub fn new_cancellable(
+    cache_dir: &Path,
+    force_db_update: bool,
+    progress_fn: impl Fn(Option<u64>, u64),
+    cancellation: &Cancellation,
+    compression: Compression,
+  ) -> Result<Self, Error> {
+    let one_month = Duration::from_secs(60 * 60 * 24 * 30);
+    let db_filename = cache_dir.join(EPISODES_DB_FILENAME);
+
+    let needs_update = force_db_update
+      || !matches!(db_file::version(&db_filename)?, Some(db_file::VERSION))
+      || io_file::older_than(&io_file::open_existing(&db_filename)?, one_month);
+
+    if needs_update {
+      debug!("IMDB episodes database does not exist or is outdated, going to fetch and build");
+      let result = Self::build_db_file(&db_filename, &progress_fn, cancellation, compression);
+
+      if cancellation.is_cancelled() {
+        return Err(Error::Cancelled);
+      }
+
+      result?;
+    }
+
+    let start = Instant::now();
+    let episodes = match Self::read_db_file(&db_filename) {
+      Err(Error::DbFile(e)) if e.needs_rebuild() => {
+        debug!("IMDB episodes database is damaged ({e}), going to fetch and rebuild");
+        Self::build_db_file(&db_filename, progress_fn, cancellation, compression)?;
+        Self::read_db_file(&db_filename)?
+      }
+      result => result?,
+    };
+    debug!("Read IMDB episodes database in {}", format_duration(Instant::now().duration_since(start)));
+
+    Ok(episodes)
+  }
+
+  /// Returns the episodes database in `cache_dir` if it was already built, without fetching
+  /// it, or `None` if it does not exist or is in an older format.
+  ///
+  /// # Arguments
+  ///
+  /// * `cache_dir` - Directory path of the database files.
+  pub fn load(cache_dir: &Path) -> Result<Option<Self>, Error> {
+    let db_filename = cache_dir.join(EPISODES_DB_FILENAME);
+
+    if db_file::version(&db_filename)? != Some(db_file::VERSION) {
+      debug!("IMDB episodes database does not exist or is outdated, not loading it");
+      return Ok(None);
+    }
+
+    Self::read_db_file(&db_filename).map(Some)
+  }
+
+  /// Builds the episodes database in `cache_dir` from IMDB dumps that were already
+  /// downloaded, instead of fetching them.
+  ///
+  /// The dumps can either be gzip-compressed, if their names end with `.gz`, or
+  /// uncompressed.
+  ///
+  /// # Arguments
+  ///
+  /// * `episodes_filename` - Path to the `title.episode` dump.
+  /// * `ratings_filename` - Path to the `title.ratings` dump.
+  /// * `cache_dir` - Directory path of the database files.
+  /// * `compression` - Compression of the database.
+  pub fn build_from_files(
+    episodes_filename: &Path,
+    ratings_filename: &Path,
+    cache_dir: &Path,
+    compression: Compression,
+  ) -> Result<(), Error> {
+    let reader = |filename: &Path| -> Result<Box<dyn BufRead>, Error> {
+      let file = fs::File::open(filename)?;
+      if filename.extension().is_some_and(|extension| extension == "gz") {
+        Ok(Box::new(io_net::make_fetcher(file, |_| {})))
+      } else {
+        Ok(Box::new(io::BufReader::new(file)))
+      }
+    };
+
+    Self::import_db_file(
+      reader(episodes_filename)?,
+      reader(ratings_filename)?,
+      &cache_dir.join(EPISODES_DB_FILENAME),
+      compression,
+    )
+  }
+
+  #[cfg(feature = "fetch")]
+  fn build_db_file(
+    db_filename: &Path,
+    progress_fn: impl Fn(Option<u64>, u64),
+    cancellation: &Cancellation,
+    compression: Compression,
+  ) -> Result<(), Error> {
+    let imdb_url = Url::parse(IMDB_URL)?;
+    let episodes_response = io_net::get_response(imdb_url.join(EPISODES_FILENAME)?)?;
+    let ratings_response = io_net::get_response(imdb_url.join(RATINGS_FILENAME)?)?;
+
+    let content_length = episodes_response
+      .content_length()
+      .zip(ratings_response.content_length())
+      .map(|(episodes, ratings)| episodes + ratings);
+    progress_fn(content_length, 0);
+
+    let episodes_response = CancellableReader::new(episodes_response, cancellation.clone());
+    let ratings_response = CancellableReader::new(ratings_response, cancellation.clone());
+
+    let episodes_fetcher = io_net::make_fetcher(episodes_response, |bytes| progress_fn(None, bytes));
+    let ratings_fetcher = io_net::make_fetcher(ratings_response, |bytes| progress_fn(None, bytes));
+
+    Self::import_db_file(episodes_fetcher, ratings_fetcher, db_filename, compression)
+  }
+
+  /// Imports the IMDB dumps into the episodes database, which only replaces the existing one
+  /// once it was fully written.
+  ///
+  /// # Arguments
+  ///
+  /// * `episodes_reader` - Reader of the uncompressed episodes dump.
+  /// * `ratings_reader` - Reader of the uncompressed ratings dump.
+  /// * `db_filename` - Path to the episodes database.
+  /// * `compression` - Compression of the database.
+  fn import_db_file(
+    episodes_reader: impl BufRead,
+    ratings_reader: impl BufRead,
+    db_filename: &Path,
+    compression: Compression,
+  ) -> Result<(), Error> {
+    let _span = info_span!("import", db = "episodes").entered();
+    let partial = db_file::partial_filename(db_filename);
+
+    let result = (|| -> Result<(), Error> {
+      let mut writer = db_file::create(&partial, compression)?;
+      episodes_import(episodes_reader, ratings_reader, &mut writer)?;
+      writer.finish()?;
+
+      fs::rename(&partial, db_filename)?;
+      db_file::write_checksum(db_filename)?;
+      Ok(())
+    })();
+
+    if result.is_err() {
+      let _ = fs::remove_file(&partial);
+    }
+
+    result
+  }
+
+  fn read_db_file(db_filename: &Path) -> Result<Self, Error> {
+    let data = db_file::read_verified(db_filename)?;
+    Self::from_records(db_file::titles(data)?)
+  }
+
+  /// Index the records of a database by series.
+  fn from_records(records: Contents) -> Result<Self, Error> {
+    if !records.titles().len().is_multiple_of(RECORD_LEN) {
+      return Err(Error::Truncated);
+    }
+
+    let mut series: FnvHashMap<u32, Range<usize>> = FnvHashMap::default();
+    for (index, record) in records.titles().chunks_exact(RECORD_LEN).enumerate() {
+      let id = u32::from_le_bytes([record[0], record[1], record[2], record[3]]);
+      series.entry(id).or_insert(index..index).end = index + 1;
+    }
+
+    Ok(Self { records, series })
+  }
+
+  /// Returns the episodes of a series, or `None` if it has no known episodes.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - IMDB ID of the series.
+  pub fn of_series(&self, id: &TitleId) -> Option<SeriesEpisodes> {
+    let id = u32::try_from(id.as_usize()).ok()?;
+    let range = self.series.get(&id)?;
+    let records = &self.records.titles()[range.start * RECORD_LEN..range.end * RECORD_LEN];
+    Some(SeriesEpisodes::new(records.chunks_exact(RECORD_LEN).map(Episode::from_record)))
+  }
+
+  /// Returns the number of seasons and episodes of a series and the average rating of its
+  /// episodes, or `None` if it has no known episodes.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - IMDB ID of the series.
+  pub fn summary(&self, id: &TitleId) -> Option<Summary> {
+    let id = u32::try_from(id.as_usize()).ok()?;
+    let range = self.series.get(&id)?;
+    let records = &self.records.titles()[range.start * RECORD_LEN..range.end * RECORD_LEN];
+    let episodes: Vec<_> = records.chunks_exact(RECORD_LEN).map(Episode::from_record).collect();
+
+    let mut seasons: Vec<_> = episodes.iter().map(Episode::season).collect();
+    seasons.dedup();
+
+    Some(Summary { seasons: seasons.len(), episodes: episodes.len(), average: average(episodes.iter()) })
+  }
+
+  /// Columns with the number of seasons and episodes of series and the average rating of
+  /// their episodes, named "Seasons", "Episodes" and "Episode Rating".
+  ///
+  /// # Arguments
+  ///
+  /// * `episodes` - The episodes of all series.
+  pub fn columns(episodes: Arc<Self>) -> Vec<Column> {
+    type Field = fn(&Summary) -> Option<ColumnValue>;
+
+    let fields: [(&str, Field); 3] = [
+      ("Seasons", |summary| Some(ColumnValue::Integer(summary.seasons as i64))),
+      ("Episodes", |summary| Some(ColumnValue::Integer(summary.episodes as i64))),
+      ("Episode Rating", |summary| summary.average.map(ColumnValue::Float)),
+    ];
+
+    fields
+      .into_iter()
+      .map(|(name, field)| {
+        let episodes = episodes.clone();
+        Column::new(name, move |title| field(&episodes.summary(title.title_id())?))
+      })
+      .collect()
+  }
+
+  /// Returns the number of episodes of all series.
+  pub fn len(&self) -> usize {
+    self.records.titles().len() / RECORD_LEN
+  }
+
+  /// Returns true if there are no episodes.
+  pub fn is_empty(&self) -> bool {
+    self.records.titles().is_empty()
+  }
+
+  /// Returns the number of series with episodes.
+  pub fn n_series(&self) -> usize {
+    self.series.len()
+  }
+}
+
+/// Parse the `title.episode` dump and write the episodes as records sorted by series, season
+/// and episode number.
+///
+/// Episodes without a season or episode number are skipped, they cannot be placed.
+///
+/// # Arguments
+///
+/// * `episodes_reader` - TSV reader for episodes.
+/// * `ratings_reader` - TSV reader for ratings.
+/// * `writer` - Writer of the records.
+fn episodes_import(
+  mut episodes_reader: impl BufRead,
+  ratings_reader: impl BufRead,
+  mut writer: impl Write,
+) -> Result<(), Error> {
+  let ratings = Ratings::from_tsv(ratings_reader)?;
+  let mut episodes = Vec::new();
+  let mut line = String::new();
+
+  // Skip the first line.
+  episodes_reader.read_line(&mut line)?;
+  line.clear();
+
+  while episodes_reader.read_line(&mut line)? != 0 {
+    let trimmed = line.trim_end();
+
+    if !trimmed.is_empty() {
+      let mut columns = trimmed.as_bytes().split(|&b| b == tokens::TAB);
+      let id = TitleId::try_from(iter_next!(columns)?)?;
+      let series_id = TitleId::try_from(iter_next!(columns)?)?;
+      let season = parse_number(iter_next!(columns)?)?;
+      let episode = parse_number(iter_next!(columns)?)?;
+
+      let rating = ratings.get(&id.as_usize()).copied();
+
+      if let (Some(season), Some(episode), Ok(id), Ok(series_id)) =
+        (season, episode, u32::try_from(id.as_usize()), u32::try_from(series_id.as_usize()))
+      {
+        episodes.push((series_id, Episode { id, season, episode, rating }));
+      }
+    }
+
+    line.clear();
+  }
+
+  episodes
+    .sort_unstable_by_key(|(series_id, episode)| (*series_id, episode.season, episode.episode, episode.id));
+
+  for (series_id, episode) in episodes {
+    let (rating, votes) = episode.rating.map_or((0, 0), |rating| (rating.rating(), rating.votes()));
+    writer.write_all(&series_id.to_le_bytes())?;
+    writer.write_all(&episode.id.to_le_bytes())?;
+    writer.write_all(&episode.season.to_le_bytes())?;
+    writer.write_all(&episode.episode.to_le_bytes())?;
+    writer.write_all(&[rating])?;
+    writer.write_all(&votes.to_le_bytes())?;
+  }
+
+  Ok(())
+}
+
+/// Parse a season or episode number, which is `None` when it is not available.
+fn parse_number(number: &[u8]) -> Result<Option<u16>, Error> {
+  match number {
+    tokens::NOT_AVAIL => Ok(None),
+    number => match atoi::<u16>(number) {
+      Some(number) => Ok(Some(number)),
+      None => Err(Error::Number(String::from_utf8_lossy(number).into_owned())),
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::imdb::db_file::Contents;
+  use crate::imdb::episodes::{episodes_import, Episodes};
+  use crate::imdb::title_id::TitleId;
+  use indoc::indoc;
+
+  fn make_episodes() -> Episodes {
+    let episodes = indoc! {"
+      tconst\tparentTconst\tseasonNumber\tepisodeNumber
+      tt0000012\ttt0000010\t2\t1
+      tt0000011\ttt0000010\t1\t2
+      tt0000013\ttt0000010\t1\t1
+      tt0000014\ttt0000010\t\\N\t\\N
+      tt0000015\ttt0000010\t2\t2
+      tt0000021\ttt0000020\t1\t1
+    "};
+
+    let ratings = indoc! {"
+      tconst\taverageRating\tnumVotes
+      tt0000011\t8.5\t100
+      tt0000012\t9.1\t200
+      tt0000013\t7.9\t150
+      tt0000015\t9.1\t300
+    "};
+
+    let mut records = Vec::new();
+    episodes_import(episodes.as_bytes(), ratings.as_bytes(), &mut records).unwrap();
+    Episodes::from_records(Contents::from(records)).unwrap()
+  }
+
+  #[test]
+  fn test_import() {
+    let episodes = make_episodes();
+    assert_eq!(episodes.len(), 5);
+    assert_eq!(episodes.n_series(), 2);
+
+    let id = TitleId::try_from("tt0000030").unwrap();
+    assert!(episodes.of_series(&id).is_none());
+  }
+
+  #[test]
+  fn test_seasons() {
+    let episodes = make_episodes();
+    let series = episodes.of_series(&TitleId::try_from("tt0000010").unwrap()).unwrap();
+    assert_eq!(series.len(), 4);
+
+    let seasons = series.seasons();
+    assert_eq!(seasons.len(), 2);
+    assert_eq!(seasons[0].number(), 1);
+    let ids: Vec<_> = seasons[0].episodes().iter().map(|episode| episode.id()).collect();
+    assert_eq!(ids, ["tt0000013", "tt0000011"]);
+    assert_eq!(seasons[0].average(), Some(82.0));
+    assert_eq!(seasons[1].average(), Some(91.0));
+    assert_eq!(series.average(), Some(86.5));
+
+    assert_eq!(series.best().map(|episode| episode.id()).as_deref(), Some("tt0000015"));
+    assert_eq!(series.worst().map(|episode| episode.id()).as_deref(), Some("tt0000013"));
+
+    let unrated = episodes.of_series(&TitleId::try_from("tt0000020").unwrap()).unwrap();
+    assert_eq!(unrated.len(), 1);
+    assert_eq!(unrated.seasons()[0].episodes()[0].rating(), None);
+    assert_eq!(unrated.average(), None);
+    assert!(unrated.best().is_none());
+  }
+
+  #[test]
+  fn test_summary() {
+    let episodes = make_episodes();
+    let summary = episodes.summary(&TitleId::try_from("tt0000010").unwrap()).unwrap();
+    assert_eq!(summary.seasons(), 2);
+    assert_eq!(summary.episodes(), 4);
+    assert_eq!(summary.average(), Some(86.5));
+
+    let columns = Episodes::columns(std::sync::Arc::new(episodes));
+    let names: Vec<_> = columns.iter().map(|column| column.name()).collect();
+    assert_eq!(names, ["Seasons", "Episodes", "Episode Rating"]);
+  }
+
+  #[test]
+  fn test_truncated() {
+    assert!(Episodes::from_records(Contents::from(vec![0; 18])).is_err());
+  }
+}
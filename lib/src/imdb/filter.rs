@@ -0,0 +1,411 @@
+#![warn(clippy::all)]
+
+//! Filters narrowing down titles by their properties, shared by all the ways of querying
+//! titles.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::imdb::genre::{Genre, Genres};
+use crate::imdb::title::Title;
+
+/// Whether series are still running or have ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeriesStatus {
+  /// Series without an end year.
+  Running,
+  /// Series with an end year.
+  Ended,
+}
+
+/// Bands of numbers of votes, to tell hidden gems apart from the most popular titles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Popularity {
+  /// Titles with fewer than 1,000 votes, including those without any.
+  Obscure,
+  /// Titles with 1,000 to 9,999 votes.
+  Niche,
+  /// Titles with 10,000 to 99,999 votes.
+  Popular,
+  /// Titles with 100,000 votes or more.
+  Blockbuster,
+}
+
+impl Popularity {
+  /// The smallest and largest numbers of votes of the titles in the band, if any.
+  pub fn votes(self) -> (Option<u32>, Option<u32>) {
+    match self {
+      Popularity::Obscure => (None, Some(999)),
+      Popularity::Niche => (Some(1_000), Some(9_999)),
+      Popularity::Popular => (Some(10_000), Some(99_999)),
+      Popularity::Blockbuster => (Some(100_000), None),
+    }
+  }
+}
+
+/// Criteria that titles have to match, all of which are optional.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Filter {
+  released_since: Option<u16>,
+  released_until: Option<u16>,
+  min_votes: Option<u32>,
+  max_votes: Option<u32>,
+  genres: Genres,
+  status: Option<SeriesStatus>,
+}
+
+impl Filter {
+  /// Construct a filter that matches all titles.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Only match titles released in or after the given year.
+  ///
+  /// # Arguments
+  ///
+  /// * `year` - The earliest release year.
+  pub fn released_since(mut self, year: u16) -> Self {
+    self.released_since = Some(year);
+    self
+  }
+
+  /// Only match titles released in or before the given year.
+  ///
+  /// # Arguments
+  ///
+  /// * `year` - The latest release year.
+  pub fn released_until(mut self, year: u16) -> Self {
+    self.released_until = Some(year);
+    self
+  }
+
+  /// Only match titles released between two years, including both of them (e.g. 1960 and
+  /// 1975 for the titles of an era).
+  ///
+  /// # Arguments
+  ///
+  /// * `start` - The earliest release year.
+  /// * `end` - The latest release year.
+  pub fn released_between(self, start: u16, end: u16) -> Self {
+    self.released_since(start).released_until(end)
+  }
+
+  /// Only match titles released in the decade of the given year (e.g. 1990 to 1999 for
+  /// 1990 or 1994).
+  ///
+  /// # Arguments
+  ///
+  /// * `year` - Any year of the decade.
+  pub fn decade(self, year: u16) -> Self {
+    let start = year - year % 10;
+    self.released_between(start, start.saturating_add(9))
+  }
+
+  /// Only match titles released in the last `years` years, including the current one.
+  ///
+  /// # Arguments
+  ///
+  /// * `years` - Number of years, where 1 means the current year only.
+  pub fn last_n_years(self, years: u16) -> Self {
+    self.released_since(current_year().saturating_sub(years.saturating_sub(1)))
+  }
+
+  /// Only match titles with at least the given number of votes. Titles without ratings
+  /// never match.
+  ///
+  /// # Arguments
+  ///
+  /// * `votes` - The smallest number of votes.
+  pub fn min_votes(mut self, votes: u32) -> Self {
+    self.min_votes = Some(votes);
+    self
+  }
+
+  /// Only match titles with at most the given number of votes. Titles without ratings are
+  /// counted as having none.
+  ///
+  /// # Arguments
+  ///
+  /// * `votes` - The largest number of votes.
+  pub fn max_votes(mut self, votes: u32) -> Self {
+    self.max_votes = Some(votes);
+    self
+  }
+
+  /// Only match titles whose number of votes is in the given band.
+  ///
+  /// # Arguments
+  ///
+  /// * `popularity` - The band of numbers of votes.
+  pub fn popularity(mut self, popularity: Popularity) -> Self {
+    let (min_votes, max_votes) = popularity.votes();
+    self.min_votes = min_votes;
+    self.max_votes = max_votes;
+    self
+  }
+
+  /// Only match titles associated with the given genre, in addition to any previously
+  /// given genres.
+  ///
+  /// # Arguments
+  ///
+  /// * `genre` - Genre titles have to be associated with.
+  pub fn genre(mut self, genre: Genre) -> Self {
+    self.genres.add(genre);
+    self
+  }
+
+  /// Only match series that are still running or that have ended. Movies never match.
+  ///
+  /// # Arguments
+  ///
+  /// * `status` - Whether series have to be running or have ended.
+  pub fn status(mut self, status: SeriesStatus) -> Self {
+    self.status = Some(status);
+    self
+  }
+
+  /// The earliest release year of matching titles, if any.
+  pub fn get_released_since(&self) -> Option<u16> {
+    self.released_since
+  }
+
+  /// The latest release year of matching titles, if any.
+  pub fn get_released_until(&self) -> Option<u16> {
+    self.released_until
+  }
+
+  /// The smallest number of votes of matching titles, if any.
+  pub fn get_min_votes(&self) -> Option<u32> {
+    self.min_votes
+  }
+
+  /// The largest number of votes of matching titles, if any.
+  pub fn get_max_votes(&self) -> Option<u32> {
+    self.max_votes
+  }
+
+  /// Whether the filter matches all titles.
+  pub fn is_empty(&self) -> bool {
+    *self == Self::default()
+  }
+
+  /// Whether the given title matches the filter.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - The title to check.
+  pub fn matches(&self, title: &Title) -> bool {
+    if let Some(released_since) = self.released_since {
+      match title.start_year() {
+        Some(year) if year >= released_since => {}
+        _ => return false,
+      }
+    }
+
+    if let Some(released_until) = self.released_until {
+      match title.start_year() {
+        Some(year) if year <= released_until => {}
+        _ => return false,
+      }
+    }
+
+    let votes = title.rating().map(|rating| rating.votes());
+    if self
+      .min_votes
+      .is_some_and(|min_votes| votes.is_none_or(|votes| votes < min_votes))
+    {
+      return false;
+    }
+    if self.max_votes.is_some_and(|max_votes| votes.unwrap_or(0) > max_votes) {
+      return false;
+    }
+
+    if let Some(status) = self.status {
+      let ended = title.end_year().is_some();
+      match status {
+        SeriesStatus::Running if title.title_type().is_series() && !ended => {}
+        SeriesStatus::Ended if ended => {}
+        _ => return false,
+      }
+    }
+
+    let genres = title.genres();
+    self.genres.iter().all(|genre| genres.contains(genre))
+  }
+}
+
+/// The current year in UTC.
+pub(crate) fn current_year() -> u16 {
+  let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+  year_from_unix_secs(secs)
+}
+
+/// The year in UTC of the given number of seconds since the Unix epoch.
+///
+/// # Arguments
+///
+/// * `secs` - Seconds since the Unix epoch.
+fn year_from_unix_secs(secs: u64) -> u16 {
+  // Converts days since the epoch to a civil date, from Howard Hinnant's date algorithms.
+  let days = secs / 86400 + 719_468;
+  let era = days / 146_097;
+  let doe = days - era * 146_097;
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let year = yoe + era * 400 + u64::from(mp >= 10);
+  u16::try_from(year).unwrap_or(u16::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::BufRead;
+
+  use super::*;
+  use crate::imdb::ratings::Ratings;
+  use crate::imdb::testdata::{make_basics_reader, make_ratings_reader};
+  use crate::imdb::title::TsvAction;
+  use crate::imdb::title_id::TitleId;
+  use crate::imdb::title_type::TitleType;
+  use crate::imdb::tsv_import::ImportStats;
+
+  fn make_titles() -> Vec<Title<'static>> {
+    let ratings = Ratings::from_tsv(make_ratings_reader()).unwrap();
+    make_basics_reader()
+      .lines()
+      .skip(1)
+      .filter_map(|line| {
+        let line = Box::leak(line.unwrap().into_boxed_str());
+        match Title::from_tsv(line.as_bytes(), &ratings, &mut ImportStats::default()).unwrap() {
+          TsvAction::Movie(title) | TsvAction::Series(title) => Some(title),
+          TsvAction::Skip => None,
+        }
+      })
+      .collect()
+  }
+
+  #[test]
+  fn year() {
+    assert_eq!(year_from_unix_secs(0), 1970);
+    assert_eq!(year_from_unix_secs(951_782_400), 2000);
+    assert_eq!(year_from_unix_secs(1_704_067_199), 2023);
+    assert_eq!(year_from_unix_secs(1_704_067_200), 2024);
+  }
+
+  #[test]
+  fn empty() {
+    let filter = Filter::new();
+    assert!(filter.is_empty());
+    assert!(make_titles().iter().all(|title| filter.matches(title)));
+  }
+
+  #[test]
+  fn released_since() {
+    let filter = Filter::new().released_since(1894);
+    assert!(!filter.is_empty());
+    assert!(make_titles().iter().any(|title| filter.matches(title)));
+
+    for title in make_titles() {
+      assert_eq!(filter.matches(&title), title.start_year().is_some_and(|year| year >= 1894));
+    }
+  }
+
+  #[test]
+  fn released_between() {
+    let filter = Filter::new().released_between(1894, 1895);
+    assert!(!filter.is_empty());
+    assert!(make_titles().iter().any(|title| filter.matches(title)));
+
+    for title in make_titles() {
+      let expected = title.start_year().is_some_and(|year| (1894..=1895).contains(&year));
+      assert_eq!(filter.matches(&title), expected);
+    }
+
+    let filter = Filter::new().released_until(1893);
+    for title in make_titles() {
+      assert_eq!(filter.matches(&title), title.start_year().is_some_and(|year| year <= 1893));
+    }
+  }
+
+  #[test]
+  fn votes() {
+    let titles = make_titles();
+    let votes = |title: &Title| title.rating().map_or(0, |rating| rating.votes());
+
+    let filter = Filter::new().min_votes(2000);
+    assert!(!filter.is_empty());
+    assert!(titles.iter().any(|title| filter.matches(title)));
+    for title in &titles {
+      assert_eq!(filter.matches(title), title.rating().is_some_and(|rating| rating.votes() >= 2000));
+    }
+
+    let filter = Filter::new().max_votes(2000);
+    assert!(titles.iter().any(|title| filter.matches(title)));
+    for title in &titles {
+      assert_eq!(filter.matches(title), votes(title) <= 2000);
+    }
+  }
+
+  #[test]
+  fn popularity() {
+    let tiers = [Popularity::Obscure, Popularity::Niche, Popularity::Popular, Popularity::Blockbuster];
+
+    for title in make_titles() {
+      let matches = tiers
+        .iter()
+        .filter(|&&tier| Filter::new().popularity(tier).matches(&title))
+        .count();
+      assert_eq!(matches, 1);
+
+      if title.primary_title() == "Leaving the Factory" {
+        assert!(Filter::new().popularity(Popularity::Niche).matches(&title));
+      }
+    }
+  }
+
+  #[test]
+  fn decade() {
+    let filter = Filter::new().decade(1994);
+    assert_eq!(filter.get_released_since(), Some(1990));
+    assert_eq!(filter.get_released_until(), Some(1999));
+    assert_eq!(filter, Filter::new().decade(1990));
+  }
+
+  #[test]
+  fn last_n_years() {
+    let this_year = current_year();
+    assert_eq!(Filter::new().last_n_years(1).get_released_since(), Some(this_year));
+    assert_eq!(Filter::new().last_n_years(2).get_released_since(), Some(this_year - 1));
+  }
+
+  #[test]
+  fn genres() {
+    let titles = make_titles();
+    let filter = Filter::new().genre(Genre::Short);
+    assert!(titles.iter().any(|title| filter.matches(title)));
+
+    let filter = filter.genre(Genre::Sport);
+    let matches: Vec<_> = titles.iter().filter(|title| filter.matches(title)).collect();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].primary_title(), "Corbett and Courtney Before the Kinetograph");
+  }
+  #[test]
+  fn status() {
+    let id = |id| TitleId::try_from(id).unwrap();
+    let movie = Title::new(id("tt0000001"), TitleType::Movie, "Movie").with_start_year(2008);
+    let running = Title::new(id("tt0000002"), TitleType::TvSeries, "Running").with_start_year(2008);
+    let ended = running.with_end_year(2013);
+
+    let filter = Filter::new().status(SeriesStatus::Running);
+    assert!(!filter.is_empty());
+    assert!(!filter.matches(&movie));
+    assert!(filter.matches(&running));
+    assert!(!filter.matches(&ended));
+
+    let filter = Filter::new().status(SeriesStatus::Ended);
+    assert!(!filter.matches(&movie));
+    assert!(!filter.matches(&running));
+    assert!(filter.matches(&ended));
+  }
+}
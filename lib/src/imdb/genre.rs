@@ -6,9 +6,8 @@ use serde::{Serialize, Serializer};
 use std::fmt;
 use std::str::FromStr;
 
-/// 27 genres a title can be associated with
+/// 29 genres a title can be associated with, and a bucket for genres that are not known
 #[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy, Serialize)]
-#[display(fmt = "{}")]
 pub enum Genre {
   /// Action
   Action = 0,
@@ -78,6 +77,8 @@ pub enum Genre {
   Western = 27,
   /// Experimental
   Experimental = 28,
+  /// Genres added to the IMDB dumps after this version, which are not known.
+  Other = 29,
 }
 
 impl FromStr for Genre {
@@ -120,9 +121,43 @@ impl FromStr for Genre {
 }
 
 impl Genre {
+  /// Look up a genre by its name, ignoring case (e.g. "sci-fi" for [Genre::SciFi]).
+  ///
+  /// # Arguments
+  ///
+  /// * `name` - Name of the genre, as displayed.
+  pub fn from_name(name: &str) -> Option<Self> {
+    (0..=Self::max())
+      .map(|value| unsafe { Self::from(value) })
+      .find(|genre| genre.to_string().eq_ignore_ascii_case(name))
+  }
+
+  /// Iterate over the known genres, i.e. all of them except [Genre::Other].
+  pub fn all() -> impl Iterator<Item = Self> {
+    (0..Self::Other as u8).map(|value| unsafe { Self::from(value) })
+  }
+
+  /// The known genre whose name is the closest to the given one, ignoring case, to suggest
+  /// a genre for misspelled names (e.g. [Genre::Documentary] for "documentry"). Names that
+  /// are too different from all the genres have no suggestion.
+  ///
+  /// # Arguments
+  ///
+  /// * `name` - Name of the genre, possibly misspelled.
+  pub fn closest(name: &str) -> Option<Self> {
+    let name = name.to_lowercase();
+    let max_distance = 2.max(name.chars().count() / 3);
+
+    Self::all()
+      .map(|genre| (edit_distance(&name, &genre.to_string().to_lowercase()), genre))
+      .filter(|&(distance, _)| distance <= max_distance)
+      .min_by_key(|&(distance, _)| distance)
+      .map(|(_, genre)| genre)
+  }
+
   /// Returns the largest-valued [Genre] enum variant enum as [u8].
   pub(crate) const fn max() -> u8 {
-    Self::Experimental as u8
+    Self::Other as u8
   }
 
   /// Converts a number into its corresponding Genre item.
@@ -135,6 +170,25 @@ impl Genre {
   }
 }
 
+/// The number of characters to insert, delete or substitute to turn one string into another.
+fn edit_distance(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  let mut row: Vec<usize> = (0..=b.len()).collect();
+
+  for (i, ca) in a.chars().enumerate() {
+    let mut diagonal = row[0];
+    row[0] = i + 1;
+
+    for (j, &cb) in b.iter().enumerate() {
+      let substitution = diagonal + usize::from(ca != cb);
+      diagonal = row[j + 1];
+      row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+    }
+  }
+
+  row[b.len()]
+}
+
 /// Represents the set of genres a title is associated with
 #[derive(PartialEq, Eq, Default, Clone, Copy)]
 pub struct Genres(u32);
@@ -150,6 +204,15 @@ impl Genres {
     self.0 |= 1 << index;
   }
 
+  /// Whether the given genre is one of the genres.
+  ///
+  /// # Arguments
+  ///
+  /// * `genre` - Genre to look for.
+  pub fn contains(&self, genre: Genre) -> bool {
+    (self.0 >> genre as u8) & 1 == 1
+  }
+
   /// Returns an iterator for the genres.
   pub fn iter(&self) -> GenresIter {
     GenresIter::new(*self)
@@ -294,6 +357,7 @@ mod tests {
     assert_eq!(Genre::War as u8, 26);
     assert_eq!(Genre::Western as u8, 27);
     assert_eq!(Genre::Experimental as u8, 28);
+    assert_eq!(Genre::Other as u8, 29);
   }
 
   #[test]
@@ -327,11 +391,12 @@ mod tests {
     assert_eq!(Genre::War, unsafe { Genre::from(26) });
     assert_eq!(Genre::Western, unsafe { Genre::from(27) });
     assert_eq!(Genre::Experimental, unsafe { Genre::from(28) });
+    assert_eq!(Genre::Other, unsafe { Genre::from(29) });
   }
 
   #[test]
   fn test_genre_max() {
-    assert_eq!(Genre::max(), Genre::Experimental as u8);
+    assert_eq!(Genre::max(), Genre::Other as u8);
   }
 
   #[test]
@@ -368,4 +433,39 @@ mod tests {
     assert_eq!(iter.next(), Some(Genre::War));
     assert_eq!(iter.next(), None);
   }
+
+  #[test]
+  fn test_genre_from_name() {
+    assert_eq!(Genre::from_name("Drama"), Some(Genre::Drama));
+    assert_eq!(Genre::from_name("sci-fi"), Some(Genre::SciFi));
+    assert_eq!(Genre::from_name("REALITY-TV"), Some(Genre::RealityTv));
+    assert_eq!(Genre::from_name("SciFi"), None);
+  }
+
+  #[test]
+  fn test_genre_all() {
+    let genres: Vec<Genre> = Genre::all().collect();
+    assert_eq!(genres.len(), 29);
+    assert_eq!(genres.first(), Some(&Genre::Action));
+    assert_eq!(genres.last(), Some(&Genre::Experimental));
+    assert!(!genres.contains(&Genre::Other));
+  }
+
+  #[test]
+  fn test_genre_closest() {
+    assert_eq!(Genre::closest("Drama"), Some(Genre::Drama));
+    assert_eq!(Genre::closest("dramma"), Some(Genre::Drama));
+    assert_eq!(Genre::closest("SciFi"), Some(Genre::SciFi));
+    assert_eq!(Genre::closest("documentry"), Some(Genre::Documentary));
+    assert_eq!(Genre::closest("realitytv"), Some(Genre::RealityTv));
+    assert_eq!(Genre::closest("other"), None);
+    assert_eq!(Genre::closest("xyzzy"), None);
+  }
+
+  #[test]
+  fn test_genres_contains() {
+    let genres = make_genres();
+    assert!(genres.contains(Genre::Adventure));
+    assert!(!genres.contains(Genre::Western));
+  }
 }
@@ -0,0 +1,545 @@
+#![warn(clippy::all)]
+
+use std::cmp::Ordering;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::imdb::db::Query;
+use crate::imdb::ratings::Rating;
+use crate::imdb::service::Service;
+use crate::imdb::title::Title;
+use crate::imdb::title_id::TitleId;
+
+use fnv::FnvHashMap;
+use serde::Serialize;
+use tracing::debug;
+
+/// Errors when handling the rating history.
+#[derive(Debug, thiserror::Error)]
+#[error("Rating history error")]
+pub enum Error {
+  /// IO errors.
+  #[error("IO error: {0}")]
+  Io(#[from] io::Error),
+  /// Database timestamp is before the UNIX epoch.
+  #[error("Invalid database timestamp: {0}")]
+  Timestamp(#[from] std::time::SystemTimeError),
+  /// A snapshot file has a size that is not a multiple of the record size.
+  #[error("Rating snapshot `{}` is truncated", .0.display())]
+  Truncated(PathBuf),
+  /// There are no snapshots older than the current database.
+  #[error("Not enough rating history, at least one earlier database update is required")]
+  NotEnoughHistory,
+}
+
+const SNAPSHOT_PREFIX: &str = "ratings-";
+const SNAPSHOT_EXT: &str = "tvrankhist";
+
+/// # Snapshot records are 9 bytes composed of:
+///
+/// * 4 bytes: Title ID number (little-endian)
+/// * 1 byte:  Rating
+/// * 4 bytes: Number of votes (little-endian)
+const RECORD_LEN: usize = 9;
+
+/// Titles with fewer votes are not recorded, which keeps snapshots small.
+const MIN_VOTES: u32 = 100;
+
+/// Only the newest snapshots are kept, older ones are removed when a new one is recorded.
+/// With monthly database updates this is about a year, which also bounds how far back
+/// [History::trending] and [History::title_history] can look.
+const MAX_SNAPSHOTS: usize = 12;
+
+const SECS_PER_DAY: f64 = 60.0 * 60.0 * 24.0;
+
+/// The ratings of all movies and series at the time a database was built.
+pub struct Snapshot {
+  taken: u64,
+  ratings: FnvHashMap<usize, Rating>,
+}
+
+impl Snapshot {
+  /// Returns the time at which the snapshot's database was built.
+  pub fn taken(&self) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(self.taken)
+  }
+
+  /// Returns the rating of a title at the time of the snapshot.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - ID of the title to lookup.
+  pub fn rating(&self, id: &TitleId) -> Option<Rating> {
+    self.ratings.get(&id.as_usize()).copied()
+  }
+
+  /// Returns the number of titles recorded in the snapshot.
+  pub fn len(&self) -> usize {
+    self.ratings.len()
+  }
+
+  /// Returns true if the snapshot contains no titles.
+  pub fn is_empty(&self) -> bool {
+    self.ratings.is_empty()
+  }
+
+  /// Reads a snapshot from its binary representation.
+  ///
+  /// # Arguments
+  ///
+  /// * `taken` - Time at which the snapshot was taken, in seconds since the UNIX epoch.
+  /// * `source` - Binary snapshot records.
+  fn from_binary(taken: u64, source: &[u8]) -> Option<Self> {
+    if !source.len().is_multiple_of(RECORD_LEN) {
+      return None;
+    }
+
+    let mut ratings = FnvHashMap::default();
+    ratings.reserve(source.len() / RECORD_LEN);
+
+    for record in source.chunks_exact(RECORD_LEN) {
+      let id = u32::from_le_bytes([record[0], record[1], record[2], record[3]]);
+      let rating = record[4];
+      let votes = u32::from_le_bytes([record[5], record[6], record[7], record[8]]);
+      ratings.insert(id as usize, Rating::new(rating, votes));
+    }
+
+    Some(Self { taken, ratings })
+  }
+
+  /// Finds the rating of a title in binary snapshot records, without loading all of them.
+  ///
+  /// # Arguments
+  ///
+  /// * `source` - Binary snapshot records.
+  /// * `id` - Title ID number to lookup.
+  fn find_in_binary(source: &[u8], id: u32) -> Option<Rating> {
+    let id = id.to_le_bytes();
+    let record = source.chunks_exact(RECORD_LEN).find(|record| record[0..4] == id)?;
+    let votes = u32::from_le_bytes([record[5], record[6], record[7], record[8]]);
+    Some(Rating::new(record[4], votes))
+  }
+
+  /// Writes the ratings of the given titles in binary format.
+  ///
+  /// # Arguments
+  ///
+  /// * `titles` - Titles to record the ratings of.
+  /// * `writer` - Writer to write the records to.
+  fn write_binary<'a, W: Write>(
+    titles: impl Iterator<Item = &'a Title<'a>>,
+    writer: &mut W,
+  ) -> Result<usize, io::Error> {
+    let mut written = 0;
+
+    for title in titles {
+      let Some(rating) = title.rating() else {
+        continue;
+      };
+
+      if rating.votes() < MIN_VOTES {
+        continue;
+      }
+
+      let Ok(id) = u32::try_from(title.title_id().as_usize()) else {
+        continue;
+      };
+
+      writer.write_all(&id.to_le_bytes())?;
+      writer.write_all(&[rating.rating()])?;
+      writer.write_all(&rating.votes().to_le_bytes())?;
+      written += 1;
+    }
+
+    Ok(written)
+  }
+}
+
+/// The rating of a title at the time a snapshot was taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RatingPoint {
+  taken: u64,
+  rating: Rating,
+}
+
+impl RatingPoint {
+  /// Returns the time at which the snapshot's database was built.
+  pub fn taken(&self) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(self.taken)
+  }
+
+  /// Returns the rating of the title at the time of the snapshot.
+  pub fn rating(&self) -> Rating {
+    self.rating
+  }
+}
+
+/// A title together with the rate at which it gained votes.
+#[derive(Serialize)]
+pub struct Trending<'a> {
+  #[serde(flatten)]
+  title: &'a Title<'a>,
+  votes_per_day: f64,
+}
+
+impl<'a> Trending<'a> {
+  /// Returns the trending title.
+  pub fn title(&self) -> &'a Title<'a> {
+    self.title
+  }
+
+  /// Returns the average number of votes gained per day.
+  pub fn votes_per_day(&self) -> f64 {
+    self.votes_per_day
+  }
+}
+
+/// A store of rating snapshots taken each time the IMDB database is rebuilt.
+///
+/// Snapshots are only ever appended, so comparing the current database against an older
+/// snapshot gives the change in ratings and votes over time without any network access.
+pub struct History {
+  dir: PathBuf,
+}
+
+impl History {
+  /// Create a history store in the given directory.
+  ///
+  /// # Arguments
+  ///
+  /// * `dir` - Directory to store snapshots in.
+  pub fn new(dir: &Path) -> Self {
+    Self { dir: dir.to_owned() }
+  }
+
+  fn snapshot_path(&self, taken: u64) -> PathBuf {
+    self.dir.join(format!("{SNAPSHOT_PREFIX}{taken}.{SNAPSHOT_EXT}"))
+  }
+
+  /// Record a snapshot of the ratings in the given database.
+  ///
+  /// Nothing is written if a snapshot of the same database build already exists. Only the
+  /// newest snapshots are kept, so the oldest ones are removed once a new one is recorded.
+  /// Returns true if a new snapshot was recorded.
+  ///
+  /// # Arguments
+  ///
+  /// * `imdb` - The database to record the ratings of.
+  pub fn record(&self, imdb: &Service) -> Result<bool, Error> {
+    let taken = imdb.built_at().duration_since(UNIX_EPOCH)?.as_secs();
+    let path = self.snapshot_path(taken);
+
+    if path.exists() {
+      return Ok(false);
+    }
+
+    fs::create_dir_all(&self.dir)?;
+
+    let tmp_path = path.with_extension("tmp");
+    let mut writer = BufWriter::new(File::create(&tmp_path)?);
+    let titles = imdb.titles(Query::Movies).chain(imdb.titles(Query::Series));
+    let written = Snapshot::write_binary(titles, &mut writer)?;
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    fs::rename(&tmp_path, &path)?;
+
+    debug!("Recorded rating snapshot of {written} titles in `{}`", path.display());
+
+    self.prune()?;
+    Ok(true)
+  }
+
+  /// Remove the oldest snapshots beyond the retention limit.
+  fn prune(&self) -> Result<(), Error> {
+    let times = self.snapshot_secs()?;
+    let excess = times.len().saturating_sub(MAX_SNAPSHOTS);
+
+    for &taken in &times[..excess] {
+      let path = self.snapshot_path(taken);
+      fs::remove_file(&path)?;
+      debug!("Removed old rating snapshot `{}`", path.display());
+    }
+
+    Ok(())
+  }
+
+  /// Returns the times at which the recorded snapshots were taken, oldest first.
+  pub fn snapshot_times(&self) -> Result<Vec<SystemTime>, Error> {
+    Ok(
+      self
+        .snapshot_secs()?
+        .into_iter()
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+        .collect(),
+    )
+  }
+
+  fn snapshot_secs(&self) -> Result<Vec<u64>, Error> {
+    let entries = match fs::read_dir(&self.dir) {
+      Ok(entries) => entries,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+      Err(e) => return Err(Error::Io(e)),
+    };
+
+    let mut times = Vec::new();
+
+    for entry in entries {
+      let entry = entry?;
+      let filename = entry.file_name();
+      let filename = filename.to_string_lossy();
+
+      let taken = filename
+        .strip_prefix(SNAPSHOT_PREFIX)
+        .and_then(|rest| rest.strip_suffix(SNAPSHOT_EXT))
+        .and_then(|rest| rest.strip_suffix('.'))
+        .and_then(|secs| secs.parse::<u64>().ok());
+
+      if let Some(taken) = taken {
+        times.push(taken);
+      }
+    }
+
+    times.sort_unstable();
+    Ok(times)
+  }
+
+  fn load_secs(&self, taken: u64) -> Result<Snapshot, Error> {
+    let path = self.snapshot_path(taken);
+    let mut data = Vec::new();
+    BufReader::new(File::open(&path)?).read_to_end(&mut data)?;
+    Snapshot::from_binary(taken, &data).ok_or(Error::Truncated(path))
+  }
+
+  /// Load the snapshot that was taken at the given time.
+  ///
+  /// # Arguments
+  ///
+  /// * `taken` - One of the times returned by [History::snapshot_times].
+  pub fn load(&self, taken: SystemTime) -> Result<Snapshot, Error> {
+    self.load_secs(taken.duration_since(UNIX_EPOCH)?.as_secs())
+  }
+
+  /// Returns the ratings of a title across the recorded snapshots, oldest first.
+  ///
+  /// Snapshots in which the title was not recorded, e.g. because it had too few votes at
+  /// the time, are skipped. Only the 12 newest snapshots are kept, so the history does not
+  /// go back further than the 12 previous database updates.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - ID of the title to lookup.
+  pub fn title_history(&self, id: &TitleId) -> Result<Vec<RatingPoint>, Error> {
+    let Ok(number) = u32::try_from(id.as_usize()) else {
+      return Ok(Vec::new());
+    };
+
+    let mut points = Vec::new();
+
+    for taken in self.snapshot_secs()? {
+      let path = self.snapshot_path(taken);
+      let mut data = Vec::new();
+      BufReader::new(File::open(&path)?).read_to_end(&mut data)?;
+
+      if !data.len().is_multiple_of(RECORD_LEN) {
+        return Err(Error::Truncated(path));
+      }
+
+      if let Some(rating) = Snapshot::find_in_binary(&data, number) {
+        points.push(RatingPoint { taken, rating });
+      }
+    }
+
+    Ok(points)
+  }
+
+  /// Rank titles by the number of votes they gained per day over a time window.
+  ///
+  /// The current database is compared against the newest snapshot taken at least
+  /// `window` before it was built. If the history does not go back that far, the oldest
+  /// available snapshot is used instead. Only titles that were recorded in that snapshot
+  /// and gained votes since are returned, ordered by votes gained per day.
+  ///
+  /// # Arguments
+  ///
+  /// * `imdb` - The current database.
+  /// * `window` - How far back to compare against.
+  /// * `query` - Specifies if movies or series are ranked.
+  pub fn trending<'a>(
+    &self,
+    imdb: &'a Service,
+    window: Duration,
+    query: Query,
+  ) -> Result<Vec<Trending<'a>>, Error> {
+    let now = imdb.built_at().duration_since(UNIX_EPOCH)?.as_secs();
+    let target = now.saturating_sub(window.as_secs());
+
+    let earlier: Vec<u64> = self.snapshot_secs()?.into_iter().filter(|&taken| taken < now).collect();
+    let baseline = match earlier.iter().rev().find(|&&taken| taken <= target) {
+      Some(&taken) => taken,
+      None => *earlier.first().ok_or(Error::NotEnoughHistory)?,
+    };
+
+    let baseline = self.load_secs(baseline)?;
+    let days = (now - baseline.taken) as f64 / SECS_PER_DAY;
+    debug!("Comparing against rating snapshot taken {days:.1} days before the current database");
+
+    Ok(trending_since(imdb.titles(query), &baseline, days))
+  }
+}
+
+/// Rank titles by the number of votes they gained per day since a snapshot.
+///
+/// Titles that are not in the snapshot are skipped, since their votes before it are not
+/// known and counting all of them as gained would put every new title on top.
+///
+/// # Arguments
+///
+/// * `titles` - Titles in their current state.
+/// * `baseline` - Snapshot to compare against.
+/// * `days` - Number of days between the snapshot and the current state.
+fn trending_since<'a>(
+  titles: impl Iterator<Item = &'a Title<'a>>,
+  baseline: &Snapshot,
+  days: f64,
+) -> Vec<Trending<'a>> {
+  let days = days.max(1.0);
+
+  let mut res: Vec<Trending> = titles
+    .filter_map(|title| {
+      let votes = title.rating()?.votes();
+      let before = baseline.rating(title.title_id())?.votes();
+      let gained = votes.checked_sub(before).filter(|&gained| gained > 0)?;
+      Some(Trending { title, votes_per_day: f64::from(gained) / days })
+    })
+    .collect();
+
+  res.sort_unstable_by(|a, b| {
+    match b.votes_per_day.partial_cmp(&a.votes_per_day) {
+      Some(Ordering::Equal) | None => {}
+      Some(ord) => return ord,
+    }
+
+    a.title.primary_title().cmp(b.title.primary_title())
+  });
+
+  res
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+  use std::io::BufRead;
+  use std::time::{Duration, UNIX_EPOCH};
+
+  use crate::imdb::history::{trending_since, History, Snapshot, MAX_SNAPSHOTS, RECORD_LEN};
+  use crate::imdb::ratings::{Rating, Ratings};
+  use crate::imdb::testdata::{make_basics_reader, make_ratings_reader};
+  use crate::imdb::title::Title;
+  use crate::imdb::title_id::TitleId;
+  use crate::imdb::tsv_import::ImportStats;
+
+  fn make_titles() -> Vec<Title<'static>> {
+    let ratings = Ratings::from_tsv(make_ratings_reader()).unwrap();
+    make_basics_reader()
+      .lines()
+      .skip(1)
+      .filter_map(|line| {
+        let line = Box::leak(line.unwrap().into_boxed_str());
+        Title::from_tsv(line.as_bytes(), &ratings, &mut ImportStats::default())
+          .unwrap()
+          .into()
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_snapshot_roundtrip() {
+    let titles = make_titles();
+    let mut binary = Vec::new();
+    let written = Snapshot::write_binary(titles.iter(), &mut binary).unwrap();
+
+    // tt0212278 has no rating and is not recorded.
+    assert_eq!(written, 10);
+    assert_eq!(binary.len(), 10 * RECORD_LEN);
+
+    let snapshot = Snapshot::from_binary(42, &binary).unwrap();
+    assert_eq!(snapshot.len(), 10);
+    let id = TitleId::try_from("tt0000001").unwrap();
+    assert_eq!(snapshot.rating(&id), Some(Rating::new(57, 1845)));
+    let id = TitleId::try_from("tt0212278").unwrap();
+    assert_eq!(snapshot.rating(&id), None);
+  }
+
+  #[test]
+  fn test_title_history() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let history = History::new(dir.path());
+    let id = TitleId::try_from("tt0000001").unwrap();
+    assert!(history.title_history(&id).unwrap().is_empty());
+
+    let mut titles = make_titles();
+    for (taken, votes) in [(20, 1900), (10, 1845)] {
+      titles[0] = titles[0].with_rating(58, votes);
+      let mut binary = Vec::new();
+      Snapshot::write_binary(titles.iter(), &mut binary).unwrap();
+      fs::write(history.snapshot_path(taken), binary).unwrap();
+    }
+
+    let points = history.title_history(&id).unwrap();
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[0].taken(), UNIX_EPOCH + Duration::from_secs(10));
+    assert_eq!(points[0].rating(), Rating::new(58, 1845));
+    assert_eq!(points[1].rating(), Rating::new(58, 1900));
+
+    let id = TitleId::try_from("tt0212278").unwrap();
+    assert!(history.title_history(&id).unwrap().is_empty());
+  }
+
+  #[test]
+  fn test_snapshot_truncated() {
+    assert!(Snapshot::from_binary(42, &[0; RECORD_LEN + 1]).is_none());
+  }
+
+  #[test]
+  fn test_trending_since() {
+    let titles = make_titles();
+    let mut baseline = Snapshot { taken: 0, ratings: Default::default() };
+    let carmencita = TitleId::try_from("tt0000001").unwrap();
+    let factory = TitleId::try_from("tt0000010").unwrap();
+    let opium_den = TitleId::try_from("tt0000006").unwrap();
+
+    for title in &titles {
+      baseline
+        .ratings
+        .insert(title.title_id().as_usize(), title.rating().unwrap_or(Rating::new(0, 0)));
+    }
+
+    baseline.ratings.insert(carmencita.as_usize(), Rating::new(57, 1745));
+    baseline.ratings.insert(factory.as_usize(), Rating::new(69, 6036));
+    baseline.ratings.remove(&opium_den.as_usize());
+
+    let trending = trending_since(titles.iter(), &baseline, 10.0);
+    assert_eq!(trending.len(), 2);
+    assert_eq!(trending[0].title().title_id(), &factory);
+    assert_eq!(trending[0].votes_per_day(), 60.0);
+    assert_eq!(trending[1].title().title_id(), &carmencita);
+    assert_eq!(trending[1].votes_per_day(), 10.0);
+    assert!(trending.iter().all(|trending| trending.title().title_id() != &opium_den));
+  }
+
+  #[test]
+  fn test_prune() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let history = History::new(dir.path());
+
+    for taken in 0..MAX_SNAPSHOTS as u64 + 3 {
+      fs::write(history.snapshot_path(taken), []).unwrap();
+    }
+
+    history.prune().unwrap();
+    let times = history.snapshot_secs().unwrap();
+    assert_eq!(times.len(), MAX_SNAPSHOTS);
+    assert_eq!(times[0], 3);
+  }
+}
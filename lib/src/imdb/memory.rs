@@ -0,0 +1,142 @@
+#![warn(clippy::all)]
+
+//! Estimates of the memory used by the databases once they are loaded.
+
+use std::mem::size_of;
+use std::ops::AddAssign;
+
+/// Estimated bytes used by the titles and indexes of one shard of the database.
+///
+/// The database is split into one shard per thread that loaded it, each holding a part of
+/// the movies and series.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShardMemory {
+  titles: usize,
+  by_id: usize,
+  by_title: usize,
+}
+
+impl ShardMemory {
+  pub(crate) fn new(titles: usize, by_id: usize, by_title: usize) -> Self {
+    Self { titles, by_id, by_title }
+  }
+
+  /// Bytes used by the titles, not including the names they point into the storage.
+  pub fn titles(&self) -> usize {
+    self.titles
+  }
+
+  /// Bytes used by the index of titles by ID.
+  pub fn by_id(&self) -> usize {
+    self.by_id
+  }
+
+  /// Bytes used by the index of titles by name and year.
+  pub fn by_title(&self) -> usize {
+    self.by_title
+  }
+
+  /// Bytes used by the titles and indexes of the shard.
+  pub fn total(&self) -> usize {
+    self.titles + self.by_id + self.by_title
+  }
+}
+
+impl AddAssign for ShardMemory {
+  fn add_assign(&mut self, other: Self) {
+    self.titles += other.titles;
+    self.by_id += other.by_id;
+    self.by_title += other.by_title;
+  }
+}
+
+/// Estimated bytes used by the loaded databases.
+///
+/// Sizes are estimated from the capacities of the containers, so they include reserved but
+/// unused space and exclude the overhead of the allocator.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+  storage: usize,
+  shards: Vec<ShardMemory>,
+}
+
+impl MemoryStats {
+  pub(crate) fn new(storage: usize, shards: Vec<ShardMemory>) -> Self {
+    Self { storage, shards }
+  }
+
+  /// Bytes used by the contents of the database files, which titles point into for their
+  /// names and IDs. Compressed databases are kept both compressed and decompressed.
+  pub fn storage(&self) -> usize {
+    self.storage
+  }
+
+  /// The memory used by each shard.
+  pub fn shards(&self) -> &[ShardMemory] {
+    &self.shards
+  }
+
+  /// The memory used by all the shards together.
+  pub fn shards_total(&self) -> ShardMemory {
+    let mut total = ShardMemory::default();
+    for &shard in &self.shards {
+      total += shard;
+    }
+    total
+  }
+
+  /// Bytes used by the storage, titles and indexes.
+  pub fn total(&self) -> usize {
+    self.storage + self.shards_total().total()
+  }
+}
+
+/// Estimated bytes used by the buckets of a hash map with the given capacity, following the
+/// layout of the standard library's hash maps.
+///
+/// # Arguments
+///
+/// * `capacity` - Capacity of the hash map.
+pub(crate) fn hash_map_bytes<K, V>(capacity: usize) -> usize {
+  if capacity == 0 {
+    return 0;
+  }
+
+  // Maps are at most 7/8 full, and small maps have at least one free bucket.
+  let buckets = if capacity < 8 {
+    (capacity + 1).next_power_of_two()
+  } else {
+    (capacity * 8 / 7).next_power_of_two()
+  };
+
+  // One control byte per bucket, plus one group of control bytes for probing.
+  const GROUP_WIDTH: usize = 16;
+  buckets * (size_of::<(K, V)>() + 1) + GROUP_WIDTH
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use crate::imdb::memory::{hash_map_bytes, MemoryStats, ShardMemory};
+
+  #[test]
+  fn test_hash_map_bytes() {
+    assert_eq!(hash_map_bytes::<usize, usize>(0), 0);
+    assert_eq!(hash_map_bytes::<usize, usize>(3), 4 * 17 + 16);
+    assert_eq!(hash_map_bytes::<usize, usize>(7), 8 * 17 + 16);
+    assert_eq!(hash_map_bytes::<usize, usize>(14), 16 * 17 + 16);
+
+    let map: HashMap<usize, usize> = (0..1000).map(|i| (i, i)).collect();
+    assert!(hash_map_bytes::<usize, usize>(map.capacity()) >= 1000 * 16);
+  }
+
+  #[test]
+  fn test_totals() {
+    let stats = MemoryStats::new(1000, vec![ShardMemory::new(10, 20, 30), ShardMemory::new(1, 2, 3)]);
+    assert_eq!(stats.shards().len(), 2);
+    assert_eq!(stats.shards()[0].total(), 60);
+    assert_eq!(stats.shards_total(), ShardMemory::new(11, 22, 33));
+    assert_eq!(stats.total(), 1066);
+  }
+}
@@ -2,28 +2,137 @@
 #![warn(missing_docs)]
 
 //! Module for TVrank to use the IMDB dataset (TSV dumps) as a source.
+//!
+//! Each part of the module has its own error type, re-exported with an `Imdb` prefix, so
+//! that callers can tell file errors, corrupted databases and parsing errors apart. Some
+//! variants only exist with some features (e.g. network failures with `fetch`, or missing
+//! cache directories with `fs`), so matches need a wildcard arm, or to match on the
+//! [ImdbErrorKind] of the errors instead:
+//!
+//! ```
+//! use tvrank::imdb::ImdbError;
+//!
+//! fn describe(e: &ImdbError) -> &'static str {
+//!   match e {
+//!     ImdbError::Io(_) => "file error",
+//!     ImdbError::CorruptCache(_) | ImdbError::DbFile(_) => "corrupted database",
+//!     ImdbError::TsvImport(_) => "invalid IMDB dump",
+//!     _ => "other error",
+//!   }
+//! }
+//! ```
 
+#[cfg(feature = "akas")]
+mod akas;
+mod baseline;
+#[cfg(feature = "fs")]
+mod builder;
+// Bundles are only installed once they were downloaded.
+#[cfg(feature = "fs")]
+#[cfg_attr(not(feature = "fetch"), allow(dead_code))]
+mod bundle;
+mod column;
 mod db;
 mod db_binary;
+mod db_file;
 mod db_impl;
+#[cfg(feature = "fs")]
+mod episodes;
+mod filter;
 mod genre;
+#[cfg(feature = "fs")]
+mod history;
+mod memory;
+#[cfg_attr(not(feature = "fs"), allow(dead_code))]
+mod names;
+mod ranked;
 mod ratings;
 mod service;
+mod stats;
+#[cfg(feature = "fetch")]
+mod suggest;
 mod title;
 mod title_header;
 mod title_id;
 mod title_type;
 mod tokens;
+#[cfg(feature = "fs")]
+mod tombstones;
+#[cfg(feature = "trie")]
+mod trie;
 mod tsv_import;
+#[cfg(feature = "fs")]
+mod watchlist;
 
 #[cfg(test)]
 mod testdata;
 
+#[cfg(feature = "akas")]
+pub use akas::{Akas as ImdbAkas, Error as ImdbAkasError};
+pub use baseline::{Baseline as ImdbBaseline, Baselines as ImdbBaselines, Comparison as ImdbComparison};
+#[cfg(feature = "fs")]
+pub use builder::{Builder as ImdbBuilder, UpdatePolicy as ImdbUpdatePolicy};
+#[cfg(feature = "fs")]
+pub use bundle::Error as ImdbBundleError;
+pub use column::Values as ImdbColumnValues;
+pub use column::{Column as ImdbColumn, Columns as ImdbColumns, Value as ImdbColumnValue};
 pub use db::Query as ImdbQuery;
+pub use db_binary::Error as ImdbLoadError;
+pub use db_binary::IndexLayout as ImdbIndexLayout;
+pub use db_file::Compression as ImdbCacheCompression;
+pub use db_file::Error as ImdbDbFileError;
+#[cfg(feature = "fs")]
+pub use episodes::Error as ImdbEpisodesError;
+#[cfg(feature = "fs")]
+pub use episodes::{
+  Episode as ImdbEpisode, Episodes as ImdbEpisodes, Season as ImdbSeason,
+  SeriesEpisodes as ImdbSeriesEpisodes, Summary as ImdbEpisodesSummary,
+};
+pub use filter::Filter as ImdbFilter;
+pub use filter::Popularity as ImdbPopularity;
+pub use filter::SeriesStatus as ImdbSeriesStatus;
 pub use genre::{Genre as ImdbGenre, Genres as ImdbGenres};
+#[cfg(feature = "fs")]
+pub use history::Error as ImdbHistoryError;
+#[cfg(feature = "fs")]
+pub use history::History as ImdbHistory;
+#[cfg(feature = "fs")]
+pub use history::RatingPoint as ImdbRatingPoint;
+#[cfg(feature = "fs")]
+pub use history::Snapshot as ImdbSnapshot;
+#[cfg(feature = "fs")]
+pub use history::Trending as ImdbTrending;
+pub use memory::{MemoryStats as ImdbMemoryStats, ShardMemory as ImdbShardMemory};
+pub use names::Error as ImdbNameIndexError;
+pub use ranked::ScoredTitle as ImdbScoredTitle;
+pub use ratings::Error as ImdbRatingsError;
+pub use ratings::WeightedRating as ImdbWeightedRating;
 pub use service::Error as ImdbError;
+pub use service::ErrorKind as ImdbErrorKind;
+pub use service::Search as ImdbSearch;
 pub use service::Service as Imdb;
+pub use stats::Stats as ImdbStats;
+#[cfg(feature = "fetch")]
+pub use suggest::Error as ImdbSuggestionError;
+#[cfg(feature = "fetch")]
+pub use suggest::Suggestion as ImdbSuggestion;
+pub use title::Error as ImdbTitleError;
 pub use title::Title as ImdbTitle;
 pub use title_id::Error as ImdbTitleIdError;
 pub use title_id::TitleId as ImdbTitleId;
 pub use title_type::TitleType as ImdbTitleType;
+#[cfg(feature = "fs")]
+pub use tombstones::Error as ImdbTombstonesError;
+#[cfg(feature = "fs")]
+pub use tombstones::{Retired as ImdbRetired, Tombstones as ImdbTombstones};
+pub use tsv_import::Error as ImdbTsvImportError;
+pub use tsv_import::ImportStats as ImdbImportStats;
+#[cfg(feature = "fs")]
+pub use watchlist::Error as ImdbWatchlistError;
+#[cfg(feature = "fs")]
+pub use watchlist::{RatingChange as ImdbRatingChange, Watchlist as ImdbWatchlist};
+
+/// Deprecated name of [ImdbError].
+#[cfg(feature = "compat")]
+#[deprecated(since = "0.10.0", note = "use `ImdbError` instead")]
+pub type ImdbErr = ImdbError;
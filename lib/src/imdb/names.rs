@@ -0,0 +1,580 @@
+#![warn(clippy::all)]
+
+//! An index of titles by name stored alongside the databases, so that loading them does
+//! not rebuild the in-memory indexes of titles by name every time.
+//!
+//! The index is a database file (see [db_file](crate::imdb::db_file)) whose contents are
+//! never compressed, so that it is memory-mapped and used as it is without being parsed:
+//!
+//! * A byte flagging whether names of titles in non-Latin scripts were indexed.
+//! * The number of names, as a little-endian `u32`.
+//! * The length of the [fst] map, as a little-endian `u64`.
+//! * The [fst] map of each name to its position in the names, which are in byte order.
+//! * The offset of each name from the start of the names, as little-endian `u32`s.
+//! * The names, each as its length as a little-endian `u16`, its bytes, its number of titles
+//!   as a little-endian `u32` and the [Posting] of each title.
+//!
+//! Exact names are found through the map, while keyword and regular expression queries go
+//! through all the names by their positions, unless keyword queries are narrowed down by the
+//! words of the names with the `trie` feature.
+
+#[cfg(feature = "fs")]
+use std::collections::BTreeMap;
+#[cfg(feature = "fs")]
+use std::fs::{self, File};
+#[cfg(feature = "fs")]
+use std::io::{Read, Write};
+use std::ops::Range;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::sync::Arc;
+#[cfg(feature = "trie")]
+use std::sync::OnceLock;
+
+use crate::imdb::db::Query;
+#[cfg(feature = "fs")]
+use crate::imdb::db_file::{self, Compression};
+use crate::imdb::db_impl::Field;
+#[cfg(feature = "trie")]
+use crate::imdb::trie::WordIndex;
+use crate::utils::search::SearchScope;
+#[cfg(feature = "trie")]
+use crate::utils::search::SearchString;
+
+use fst::Map;
+#[cfg(feature = "fs")]
+use fst::MapBuilder;
+#[cfg(feature = "fs")]
+use memmap2::Mmap;
+
+/// Without the `fs` feature indexes are never read from files, so nothing is mapped.
+#[cfg(not(feature = "fs"))]
+type Mmap = Vec<u8>;
+
+/// File name of the index of titles by name.
+pub(crate) const NAMES_DB_FILENAME: &str = "imdb-names.tvrankdb";
+
+/// Bytes of the flags, of the number of names and of the length of the map at the start of
+/// the index.
+const HEADER_LEN: usize = 13;
+
+/// Bytes of each [Posting].
+const POSTING_LEN: usize = 5;
+
+/// Errors when reading or writing the index of titles by name.
+#[derive(Debug, thiserror::Error)]
+#[error("Index of titles by name error")]
+pub enum Error {
+  /// Database file error.
+  #[error("Database file error: {0}")]
+  DbFile(#[from] crate::imdb::db_file::Error),
+  /// The index is compressed, so it cannot be mapped.
+  #[error("Index of titles by name is compressed")]
+  Compressed,
+  /// The index is truncated.
+  #[error("Index of titles by name is truncated")]
+  Truncated,
+  /// The names or their offsets are not consistent.
+  #[error("Index of titles by name is corrupted")]
+  Corrupted,
+  /// A name is too long to be indexed.
+  #[error("Name of {0} bytes is too long to be indexed")]
+  NameTooLong(usize),
+  /// There are too many names or titles for the offsets of the names.
+  #[error("Index of titles by name is too large")]
+  TooLarge,
+  /// Error with the map of names.
+  #[error("Map of names error: {0}")]
+  Map(#[from] fst::Error),
+  /// IO error.
+  #[error("IO error: {0}")]
+  Io(#[from] std::io::Error),
+}
+
+/// A title that a name was indexed for: its ID, whether it is a movie or a series, which of
+/// its fields the name is from, and whether the name is the one of the field without its
+/// leading article.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Posting {
+  id: u32,
+  query: Query,
+  field: Field,
+  stripped: bool,
+}
+
+impl Posting {
+  pub(crate) fn new(id: usize, query: Query, field: Field, stripped: bool) -> Self {
+    Self { id: id as u32, query, field, stripped }
+  }
+
+  /// The numeric part of the IMDB ID of the title.
+  pub(crate) fn id(&self) -> usize {
+    self.id as usize
+  }
+
+  /// Whether the posting is a title of the given query, indexed from a field of the given
+  /// search scope, and by its name with or without its leading article.
+  ///
+  /// # Arguments
+  ///
+  /// * `query` - Whether movies or series are searched for.
+  /// * `scope` - Which names of the titles are searched.
+  /// * `stripped` - Whether names without their leading article are searched for.
+  pub(crate) fn matches(&self, query: Query, scope: SearchScope, stripped: bool) -> bool {
+    self.query == query && self.field.in_scope(scope) && self.stripped == stripped
+  }
+
+  fn encode(&self) -> [u8; POSTING_LEN] {
+    let query = match self.query {
+      Query::Movies => 0,
+      Query::Series => 1,
+    };
+    let field = match self.field {
+      Field::Primary => 0,
+      Field::Original => 1,
+      Field::Same => 2,
+    };
+    let [a, b, c, d] = self.id.to_le_bytes();
+    [a, b, c, d, query | field << 1 | u8::from(self.stripped) << 3]
+  }
+
+  fn decode(bytes: &[u8]) -> Self {
+    let id = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let flags = bytes[4];
+    let query = if flags & 1 == 0 {
+      Query::Movies
+    } else {
+      Query::Series
+    };
+    let field = match (flags >> 1) & 3 {
+      0 => Field::Primary,
+      1 => Field::Original,
+      _ => Field::Same,
+    };
+    Self { id, query, field, stripped: flags & 8 != 0 }
+  }
+}
+
+/// A region of the memory-mapped index.
+struct Region {
+  mmap: Arc<Mmap>,
+  range: Range<usize>,
+}
+
+impl AsRef<[u8]> for Region {
+  fn as_ref(&self) -> &[u8] {
+    &self.mmap[self.range.clone()]
+  }
+}
+
+/// The index of titles by name, as mapped from its file.
+pub(crate) struct NameIndex {
+  /// The contents of the index file.
+  mmap: Arc<Mmap>,
+  /// The positions of the names.
+  map: Map<Region>,
+  len: usize,
+  /// Where the offsets of the names start in the file.
+  offsets_at: usize,
+  /// The positions of the names by the words they contain, indexed on the first keyword
+  /// query so that loading the index stays fast.
+  #[cfg(feature = "trie")]
+  words: OnceLock<WordIndex>,
+}
+
+impl NameIndex {
+  /// Write the index of the given names to a file, which only replaces the existing one
+  /// once it was fully written.
+  ///
+  /// # Arguments
+  ///
+  /// * `filename` - Path of the index.
+  /// * `names` - The titles of each name.
+  /// * `with_akas` - Whether the names of titles in non-Latin scripts are included.
+  #[cfg(feature = "fs")]
+  pub(crate) fn write(
+    filename: &Path,
+    names: &BTreeMap<String, Vec<Posting>>,
+    with_akas: bool,
+  ) -> Result<(), Error> {
+    let partial = db_file::partial_filename(filename);
+
+    let result = (|| -> Result<(), Error> {
+      let mut map = MapBuilder::memory();
+      for (position, name) in names.keys().enumerate() {
+        map.insert(name, position as u64)?;
+      }
+      let map = map.into_inner()?;
+
+      let mut writer = db_file::create(&partial, Compression::None)?;
+      writer.write_all(&[u8::from(with_akas)])?;
+      let len = u32::try_from(names.len()).map_err(|_| Error::TooLarge)?;
+      writer.write_all(&len.to_le_bytes())?;
+      writer.write_all(&(map.len() as u64).to_le_bytes())?;
+      writer.write_all(&map)?;
+
+      let mut offset = 0u32;
+      for (name, postings) in names {
+        writer.write_all(&offset.to_le_bytes())?;
+        let len =
+          u32::try_from(2 + name.len() + 4 + postings.len() * POSTING_LEN).map_err(|_| Error::TooLarge)?;
+        offset = offset.checked_add(len).ok_or(Error::TooLarge)?;
+      }
+
+      for (name, postings) in names {
+        let len = u16::try_from(name.len()).map_err(|_| Error::NameTooLong(name.len()))?;
+        writer.write_all(&len.to_le_bytes())?;
+        writer.write_all(name.as_bytes())?;
+        let count = u32::try_from(postings.len()).map_err(|_| Error::TooLarge)?;
+        writer.write_all(&count.to_le_bytes())?;
+        for posting in postings {
+          writer.write_all(&posting.encode())?;
+        }
+      }
+
+      writer.finish()?;
+      fs::rename(&partial, filename)?;
+      db_file::write_checksum(filename)?;
+      Ok(())
+    })();
+
+    if result.is_err() {
+      let _ = fs::remove_file(&partial);
+    }
+
+    result
+  }
+
+  /// Map the index from a file, after checking that the offsets of its names are consistent
+  /// and that the names are valid UTF-8. The index is not verified against its checksum,
+  /// which would read all of it again on every load: it was written along with the
+  /// databases it was built from, which were verified.
+  ///
+  /// # Arguments
+  ///
+  /// * `filename` - Path of the index.
+  #[cfg(feature = "fs")]
+  pub(crate) fn read(filename: &Path) -> Result<Self, Error> {
+    let file = File::open(filename)?;
+    // SAFETY: Index files are never modified in place: they are written to a partial file
+    // that then replaces them, which leaves the mapped file as it is until it is unmapped.
+    let mmap = Arc::new(unsafe { Mmap::map(&file)? });
+    if !matches!(db_file::compression(&mmap)?, Compression::None) {
+      return Err(Error::Compressed);
+    }
+
+    let start = db_file::HEADER_LEN;
+    let header = mmap.get(start..start + HEADER_LEN).ok_or(Error::Truncated)?;
+    let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    let map_len = u64::from_le_bytes(header[5..13].try_into().unwrap_or_default()) as usize;
+
+    let map_at = start + HEADER_LEN;
+    let offsets_at = map_at.checked_add(map_len).ok_or(Error::Truncated)?;
+    if mmap.len() < offsets_at + len * 4 {
+      return Err(Error::Truncated);
+    }
+
+    let map = Map::new(Region { mmap: Arc::clone(&mmap), range: map_at..offsets_at })?;
+    if map.len() != len {
+      return Err(Error::Truncated);
+    }
+
+    let index = Self {
+      mmap,
+      map,
+      len,
+      offsets_at,
+      #[cfg(feature = "trie")]
+      words: OnceLock::new(),
+    };
+    let names = index.names();
+    let mut end = 0;
+    for position in 0..len {
+      if index.offset(position) != end {
+        return Err(Error::Corrupted);
+      }
+      end = Self::check_entry(names, end)?;
+    }
+    if end != names.len() {
+      return Err(Error::Corrupted);
+    }
+
+    Ok(index)
+  }
+
+  /// Check that a name and its postings are within the names and that the name is valid
+  /// UTF-8, and return where the next name starts.
+  ///
+  /// # Arguments
+  ///
+  /// * `names` - The names and their postings.
+  /// * `offset` - Where the name starts.
+  #[cfg(feature = "fs")]
+  fn check_entry(names: &[u8], offset: usize) -> Result<usize, Error> {
+    let len = names.get(offset..offset + 2).ok_or(Error::Corrupted)?;
+    let name_at = offset + 2;
+    let count_at = name_at + usize::from(u16::from_le_bytes([len[0], len[1]]));
+    let name = names.get(name_at..count_at).ok_or(Error::Corrupted)?;
+    std::str::from_utf8(name).map_err(|_| Error::Corrupted)?;
+
+    let count = names.get(count_at..count_at + 4).ok_or(Error::Corrupted)?;
+    let count = u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize;
+    let end = count
+      .checked_mul(POSTING_LEN)
+      .and_then(|postings| (count_at + 4).checked_add(postings))
+      .ok_or(Error::Corrupted)?;
+    if end > names.len() {
+      return Err(Error::Corrupted);
+    }
+
+    Ok(end)
+  }
+
+  /// Whether the index in a file can be used with the given databases: it has to be newer
+  /// than them and to include the names of titles in non-Latin scripts if and only if they
+  /// are indexed.
+  ///
+  /// # Arguments
+  ///
+  /// * `filename` - Path of the index.
+  /// * `db_filenames` - Paths of the databases the index has to be newer than.
+  /// * `with_akas` - Whether the names of titles in non-Latin scripts are indexed.
+  #[cfg(feature = "fs")]
+  pub(crate) fn is_fresh(filename: &Path, db_filenames: &[&Path], with_akas: bool) -> bool {
+    let modified = |filename: &Path| fs::metadata(filename).and_then(|metadata| metadata.modified()).ok();
+
+    let Some(index_modified) = modified(filename) else {
+      return false;
+    };
+
+    let newer = db_filenames
+      .iter()
+      .all(|db_filename| modified(db_filename).is_some_and(|db_modified| db_modified <= index_modified));
+
+    newer && Self::flags(filename).is_some_and(|flags| (flags & 1 != 0) == with_akas)
+  }
+
+  /// The flags at the start of the contents of the index in a file, if it has them.
+  #[cfg(feature = "fs")]
+  fn flags(filename: &Path) -> Option<u8> {
+    if db_file::version(filename).ok()? != Some(db_file::VERSION) {
+      return None;
+    }
+
+    let mut header = Vec::with_capacity(db_file::HEADER_LEN + 1);
+    File::open(filename)
+      .ok()?
+      .take(db_file::HEADER_LEN as u64 + 1)
+      .read_to_end(&mut header)
+      .ok()?;
+    header.get(db_file::HEADER_LEN).copied()
+  }
+
+  /// The number of names in the index.
+  pub(crate) fn len(&self) -> usize {
+    self.len
+  }
+
+  /// The bytes of the index, which are mapped rather than read.
+  pub(crate) fn bytes(&self) -> usize {
+    self.mmap.len()
+  }
+
+  /// The names and their postings, which follow the offsets of the names.
+  fn names(&self) -> &[u8] {
+    &self.mmap[self.offsets_at + self.len * 4..]
+  }
+
+  fn offset(&self, index: usize) -> usize {
+    let at = self.offsets_at + index * 4;
+    let bytes = &self.mmap[at..at + 4];
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
+  }
+
+  /// The name at a position of the index and its postings.
+  ///
+  /// # Arguments
+  ///
+  /// * `index` - The position of the name, less than [NameIndex::len].
+  pub(crate) fn entry(&self, index: usize) -> (&str, impl Iterator<Item = Posting> + '_) {
+    let names = self.names();
+    let offset = self.offset(index);
+    let len = usize::from(u16::from_le_bytes([names[offset], names[offset + 1]]));
+    let name = &names[offset + 2..offset + 2 + len];
+    let count_at = offset + 2 + len;
+    let count =
+      u32::from_le_bytes([names[count_at], names[count_at + 1], names[count_at + 2], names[count_at + 3]])
+        as usize;
+    let postings = &names[count_at + 4..count_at + 4 + count * POSTING_LEN];
+
+    // SAFETY: The offset of every name and its bytes were checked when the index was read
+    // (see NameIndex::check_entry), and the index is never modified once it is mapped.
+    let name = unsafe { std::str::from_utf8_unchecked(name) };
+    (name, postings.chunks_exact(POSTING_LEN).map(Posting::decode))
+  }
+
+  /// The positions of the names that may contain all the keywords in increasing order, or
+  /// `None` if the keywords cannot narrow down the names (see [WordIndex::candidates]).
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - The keywords of a query.
+  #[cfg(feature = "trie")]
+  pub(crate) fn candidates(&self, keywords: &[SearchString]) -> Option<Vec<usize>> {
+    let words = self.words.get_or_init(|| {
+      let mut words = WordIndex::default();
+      for index in 0..self.len {
+        words.insert_words(self.entry(index).0, index as u32);
+      }
+      words
+    });
+
+    let mut positions: Vec<usize> =
+      words.candidate_ids(keywords)?.into_iter().map(|id| id as usize).collect();
+    positions.sort_unstable();
+    Some(positions)
+  }
+
+  /// The postings of a name, if it was indexed.
+  ///
+  /// # Arguments
+  ///
+  /// * `name` - The name.
+  pub(crate) fn get(&self, name: &str) -> impl Iterator<Item = Posting> + '_ {
+    let position = self.map.get(name).map(|position| position as usize);
+    position.into_iter().flat_map(|position| self.entry(position).1)
+  }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+  use super::*;
+
+  fn names() -> BTreeMap<String, Vec<Posting>> {
+    let mut names = BTreeMap::new();
+    names
+      .insert("leaving the factory".to_owned(), vec![Posting::new(10, Query::Movies, Field::Primary, false)]);
+    names.insert(
+      "la sortie de l'usine lumiere a lyon".to_owned(),
+      vec![Posting::new(10, Query::Movies, Field::Original, false)],
+    );
+    names.insert(
+      "sortie de l'usine lumiere a lyon".to_owned(),
+      vec![Posting::new(10, Query::Movies, Field::Original, true)],
+    );
+    names.insert(
+      "carmencita".to_owned(),
+      vec![
+        Posting::new(1, Query::Movies, Field::Same, false),
+        Posting::new(123, Query::Series, Field::Same, false),
+      ],
+    );
+    names
+  }
+
+  #[test]
+  fn write_and_read() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let filename = dir.path().join(NAMES_DB_FILENAME);
+    NameIndex::write(&filename, &names(), false).unwrap();
+
+    let index = NameIndex::read(&filename).unwrap();
+    assert_eq!(index.len(), 4);
+    assert_eq!(index.entry(0).0, "carmencita");
+
+    let postings: Vec<_> = index.get("carmencita").collect();
+    assert_eq!(postings.len(), 2);
+    assert!(postings[0].matches(Query::Movies, SearchScope::All, false));
+    assert_eq!(postings[1].id(), 123);
+    assert!(postings[1].matches(Query::Series, SearchScope::OriginalTitles, false));
+
+    let posting = index.get("leaving the factory").next().unwrap();
+    assert!(posting.matches(Query::Movies, SearchScope::PrimaryTitles, false));
+    assert!(!posting.matches(Query::Movies, SearchScope::OriginalTitles, false));
+    assert!(!posting.matches(Query::Series, SearchScope::All, false));
+    assert!(index.get("sortie de l'usine lumiere a lyon").next().unwrap().matches(
+      Query::Movies,
+      SearchScope::All,
+      true
+    ));
+
+    assert_eq!(index.get("leaving").count(), 0);
+    assert_eq!(index.get("zzz").count(), 0);
+    assert_eq!(index.get("").count(), 0);
+  }
+
+  #[cfg(feature = "trie")]
+  #[test]
+  fn candidates() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let filename = dir.path().join(NAMES_DB_FILENAME);
+    NameIndex::write(&filename, &names(), false).unwrap();
+    let index = NameIndex::read(&filename).unwrap();
+
+    let keywords = |keywords: &[&str]| -> Vec<SearchString> {
+      keywords
+        .iter()
+        .map(|&keyword| SearchString::try_from(keyword).unwrap())
+        .collect()
+    };
+    assert_eq!(index.candidates(&keywords(&["usine", "lyon"])).unwrap(), [1, 3]);
+    assert_eq!(index.candidates(&keywords(&["factory"])).unwrap(), [2]);
+    assert!(index.candidates(&keywords(&["predator"])).unwrap().is_empty());
+    assert!(index.candidates(&keywords(&["a"])).is_none());
+  }
+
+  #[test]
+  fn freshness() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let filename = dir.path().join(NAMES_DB_FILENAME);
+    let db_filename = dir.path().join("db");
+    fs::write(&db_filename, b"").unwrap();
+
+    assert!(!NameIndex::is_fresh(&filename, &[&db_filename], false));
+
+    NameIndex::write(&filename, &names(), false).unwrap();
+    assert!(NameIndex::is_fresh(&filename, &[&db_filename], false));
+    assert!(!NameIndex::is_fresh(&filename, &[&db_filename], true));
+    assert!(!NameIndex::is_fresh(&filename, &[&dir.path().join("missing")], false));
+  }
+
+  #[test]
+  fn damaged() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let filename = dir.path().join(NAMES_DB_FILENAME);
+    NameIndex::write(&filename, &names(), false).unwrap();
+
+    let mut data = fs::read(&filename).unwrap();
+    data.truncate(data.len() - 1);
+    fs::write(&filename, &data).unwrap();
+    assert!(matches!(NameIndex::read(&filename), Err(Error::Corrupted)));
+  }
+
+  #[test]
+  fn invalid_utf8() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let filename = dir.path().join(NAMES_DB_FILENAME);
+    NameIndex::write(&filename, &names(), false).unwrap();
+
+    let mut data = fs::read(&filename).unwrap();
+    let name = data.windows(10).rposition(|window| window == b"carmencita").unwrap();
+    data[name] = 0xff;
+    fs::write(&filename, &data).unwrap();
+    assert!(matches!(NameIndex::read(&filename), Err(Error::Corrupted)));
+  }
+
+  #[test]
+  fn inconsistent_offsets() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let filename = dir.path().join(NAMES_DB_FILENAME);
+    NameIndex::write(&filename, &names(), false).unwrap();
+
+    let mut data = fs::read(&filename).unwrap();
+    let index = NameIndex::read(&filename).unwrap();
+    let at = index.offsets_at + 4;
+    drop(index);
+    data[at] = data[at].wrapping_add(1);
+    fs::write(&filename, &data).unwrap();
+    assert!(matches!(NameIndex::read(&filename), Err(Error::Corrupted)));
+  }
+}
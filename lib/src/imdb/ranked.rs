@@ -0,0 +1,196 @@
+#![warn(clippy::all)]
+
+//! Relevance of titles to the keywords they were found with, for keyword searches to
+//! present the most relevant titles first.
+//!
+//! A title whose name is made of exactly the keywords is more relevant than one where every keyword
+//! starts a word of its name, which is more relevant than one that only contains the
+//! keywords. Titles of the same kind of match are ordered by their number of votes.
+
+use std::cmp::Ordering;
+
+use crate::imdb::title::Title;
+use crate::imdb::title_header::MAX_VOTES;
+use crate::utils::search::{self, SearchString};
+
+/// How closely the name of a title matches the keywords, from the least to the most
+/// relevant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum MatchClass {
+  /// The keywords appear somewhere in the name.
+  Substring = 1,
+  /// Every keyword is the start of a word of the name.
+  Prefix = 2,
+  /// The words of the name are the keywords, in any order.
+  Exact = 3,
+}
+
+impl MatchClass {
+  /// Classify the match of a title name against keywords.
+  ///
+  /// # Arguments
+  ///
+  /// * `name` - The title name.
+  /// * `keywords` - The keywords the title was found with, which may be phrases.
+  fn of(name: &str, keywords: &[&str]) -> Self {
+    let name = search::normalize(name);
+    let words = split_words(&name);
+    let keywords: Vec<&str> = keywords.iter().flat_map(|keyword| split_words(keyword)).collect();
+
+    let mut sorted_words = words.clone();
+    sorted_words.sort_unstable();
+    let mut sorted_keywords = keywords.clone();
+    sorted_keywords.sort_unstable();
+
+    if sorted_words == sorted_keywords {
+      MatchClass::Exact
+    } else if keywords
+      .iter()
+      .all(|keyword| words.iter().any(|word| word.starts_with(keyword)))
+    {
+      MatchClass::Prefix
+    } else {
+      MatchClass::Substring
+    }
+  }
+}
+
+/// Split a name or a keyword, which may be a phrase of several words, into its words.
+///
+/// # Arguments
+///
+/// * `name` - The lowercase name or keyword.
+fn split_words(name: &str) -> Vec<&str> {
+  name
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|word| !word.is_empty())
+    .collect()
+}
+
+/// Compute the relevance score of a title to the keywords it was found with.
+///
+/// The integral part of the score is the kind of match (3 for an exact match of the primary
+/// or original title, 2 when every keyword starts a word, 1 otherwise) and the fractional
+/// part grows with the number of votes of the title.
+///
+/// # Arguments
+///
+/// * `title` - The title to score.
+/// * `keywords` - The lowercase keywords the title was found with.
+pub(crate) fn relevance(title: &Title, keywords: &[&str]) -> f64 {
+  let class = std::iter::once(title.primary_title())
+    .chain(title.original_title())
+    .map(|name| MatchClass::of(name, keywords))
+    .max()
+    .unwrap_or(MatchClass::Substring);
+
+  let votes = title.rating().map_or(0, |rating| rating.votes());
+  let weight = (1.0 + f64::from(votes)).ln() / (1.0 + f64::from(MAX_VOTES)).ln();
+
+  f64::from(class as u8) + weight.min(0.999)
+}
+
+/// A title found by keywords, together with its relevance score.
+#[derive(Debug, Clone, Copy)]
+pub struct ScoredTitle<'a> {
+  title: &'a Title<'a>,
+  score: f64,
+}
+
+impl<'a> ScoredTitle<'a> {
+  /// Score a title against the keywords it was found with.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - The title.
+  /// * `keywords` - The lowercase keywords the title was found with.
+  pub(crate) fn new(title: &'a Title<'a>, keywords: &[&str]) -> Self {
+    Self { title, score: relevance(title, keywords) }
+  }
+
+  /// The title.
+  pub fn title(&self) -> &'a Title<'a> {
+    self.title
+  }
+
+  /// The relevance score of the title, higher for more relevant titles.
+  pub fn score(&self) -> f64 {
+    self.score
+  }
+}
+
+/// The keywords that titles were found with, leaving out the excluded ones.
+///
+/// # Arguments
+///
+/// * `keywords` - The keywords of a query.
+pub(crate) fn included(keywords: &[SearchString]) -> Vec<&str> {
+  keywords
+    .iter()
+    .filter(|keyword| !keyword.is_excluded())
+    .map(SearchString::as_str)
+    .collect()
+}
+
+/// Score titles against the keywords they were found with and order them by decreasing
+/// score, keeping the order of the given titles among titles of the same score.
+///
+/// # Arguments
+///
+/// * `titles` - The titles found with the keywords.
+/// * `keywords` - The keywords.
+pub(crate) fn rank<'a>(titles: Vec<&'a Title<'a>>, keywords: &[SearchString]) -> Vec<ScoredTitle<'a>> {
+  let keywords = included(keywords);
+  let mut scored: Vec<_> = titles.into_iter().map(|title| ScoredTitle::new(title, &keywords)).collect();
+  scored.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+  scored
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::imdb::title_id::TitleId;
+  use crate::imdb::title_type::TitleType;
+
+  fn keywords(keywords: &[&str]) -> Vec<SearchString> {
+    keywords.iter().map(|&kw| SearchString::try_from(kw).unwrap()).collect()
+  }
+
+  #[test]
+  fn match_class() {
+    assert_eq!(MatchClass::of("The Matrix", &["the", "matrix"]), MatchClass::Exact);
+    assert_eq!(MatchClass::of("The Matrix Reloaded", &["matrix", "rel"]), MatchClass::Prefix);
+    assert_eq!(MatchClass::of("The Matrix", &["matrix", "the"]), MatchClass::Exact);
+    assert_eq!(MatchClass::of("New York Stories", &["new york", "stories"]), MatchClass::Exact);
+    assert_eq!(MatchClass::of("Thematrix", &["matrix"]), MatchClass::Substring);
+    assert_eq!(MatchClass::of("Matrix: Resurrections", &["matrix", "resurrections"]), MatchClass::Exact);
+  }
+
+  #[test]
+  fn rank_titles() {
+    let id = |id| TitleId::try_from(id).unwrap();
+    let exact = Title::new(id("tt0000001"), TitleType::Movie, "Matrix").with_rating(50, 10);
+    let prefix = Title::new(id("tt0000002"), TitleType::Movie, "Matrix Reloaded").with_rating(70, 100_000);
+    let substring = Title::new(id("tt0000003"), TitleType::Movie, "Antimatrix").with_rating(90, 1_000_000);
+    let popular =
+      Title::new(id("tt0000004"), TitleType::Movie, "The Matrix Revolutions").with_rating(60, 500_000);
+
+    let mut keywords = keywords(&["matrix"]);
+    keywords.push(SearchString::excluded("reloaded").unwrap());
+    let ranked = rank(vec![&substring, &prefix, &exact, &popular], &keywords);
+    let ids: Vec<_> = ranked.iter().map(|scored| scored.title().title_id().to_string()).collect();
+    assert_eq!(ids, ["tt0000001", "tt0000004", "tt0000002", "tt0000003"]);
+    assert!(ranked.windows(2).all(|pair| pair[0].score() >= pair[1].score()));
+    assert!(ranked[0].score() >= 3.0 && ranked[0].score() < 4.0);
+    assert!(ranked[3].score() >= 1.0 && ranked[3].score() < 2.0);
+  }
+
+  #[test]
+  fn original_title() {
+    let title = Title::new(TitleId::try_from("tt0000010").unwrap(), TitleType::Movie, "Leaving")
+      .with_original_title("La sortie de l'usine");
+    assert!(relevance(&title, &["la", "sortie", "de", "l", "usine"]) >= 3.0);
+    assert!(relevance(&title, &["usi"]) < 3.0);
+  }
+}
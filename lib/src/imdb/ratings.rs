@@ -1,5 +1,6 @@
 #![warn(clippy::all)]
 
+use crate::imdb::title::Title;
 use crate::imdb::title_id::TitleId;
 use crate::imdb::tokens;
 use crate::iter_next;
@@ -96,6 +97,96 @@ impl Rating {
   }
 }
 
+/// IMDB-style weighted ("Bayesian") rating, which pulls the ratings of titles with few votes
+/// towards a prior mean so that they do not outrank titles rated as highly by many more
+/// people. The weighted rating of a title is `(v * R + m * C) / (v + m)`, where `R` is its
+/// rating, `v` its number of votes, `C` the prior mean and `m` the minimum number of votes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeightedRating {
+  mean: f64,
+  min_votes: u32,
+}
+
+impl WeightedRating {
+  /// The minimum number of votes of the IMDB Top 250.
+  pub const DEFAULT_MIN_VOTES: u32 = 25_000;
+
+  /// Create a weighted rating.
+  ///
+  /// # Arguments
+  ///
+  /// * `mean` - The prior mean, on the scale of [Rating::rating] (0 to 100).
+  /// * `min_votes` - The number of votes at which the rating of a title and the prior mean
+  ///   weigh the same.
+  pub fn new(mean: f64, min_votes: u32) -> Self {
+    Self { mean, min_votes }
+  }
+
+  /// Create a weighted rating whose prior mean is the average rating of the given titles,
+  /// as for the IMDB Top 250. Titles without ratings are not counted.
+  ///
+  /// # Arguments
+  ///
+  /// * `titles` - Titles to average the ratings of.
+  /// * `min_votes` - The number of votes at which the rating of a title and the prior mean
+  ///   weigh the same.
+  pub fn from_titles<'a, 'storage: 'a>(
+    titles: impl IntoIterator<Item = &'a Title<'storage>>,
+    min_votes: u32,
+  ) -> Self {
+    let (sum, count) = titles
+      .into_iter()
+      .filter_map(|title| title.rating())
+      .fold((0u64, 0u64), |(sum, count), rating| (sum + u64::from(rating.rating), count + 1));
+    let mean = if count == 0 {
+      0.0
+    } else {
+      sum as f64 / count as f64
+    };
+    Self::new(mean, min_votes)
+  }
+
+  /// The prior mean, on the scale of [Rating::rating].
+  pub fn mean(&self) -> f64 {
+    self.mean
+  }
+
+  /// The number of votes at which the rating of a title and the prior mean weigh the same.
+  pub fn min_votes(&self) -> u32 {
+    self.min_votes
+  }
+
+  /// The weighted rating of a title, on the scale of [Rating::rating].
+  ///
+  /// # Arguments
+  ///
+  /// * `rating` - The rating of the title.
+  pub fn score(&self, rating: &Rating) -> f64 {
+    let votes = f64::from(rating.votes);
+    let min_votes = f64::from(self.min_votes);
+
+    if votes + min_votes == 0.0 {
+      return self.mean;
+    }
+
+    (votes * f64::from(rating.rating) + min_votes * self.mean) / (votes + min_votes)
+  }
+
+  /// Compare the ratings of two titles by their weighted ratings, then by their numbers of
+  /// votes. Titles without ratings are lower than all the others.
+  ///
+  /// # Arguments
+  ///
+  /// * `a` - The rating of the first title, if any.
+  /// * `b` - The rating of the second title, if any.
+  pub fn compare(&self, a: Option<&Rating>, b: Option<&Rating>) -> Ordering {
+    match (a, b) {
+      (Some(a), Some(b)) => self.score(a).total_cmp(&self.score(b)).then(a.votes.cmp(&b.votes)),
+      (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+  }
+}
+
 /// Maps a set of title IDs to their corresponding ratings
 #[derive(Default)]
 pub(crate) struct Ratings {
@@ -182,8 +273,12 @@ impl Ratings {
 mod tests_ratings {
   use crate::imdb::ratings::Rating;
   use crate::imdb::ratings::Ratings;
+  use crate::imdb::ratings::WeightedRating;
+  use crate::imdb::title::Title;
   use crate::imdb::title_id::TitleId;
+  use crate::imdb::title_type::TitleType;
   use indoc::indoc;
+  use std::cmp::Ordering;
   use std::io::BufRead;
 
   fn make_ratings_reader() -> impl BufRead {
@@ -218,4 +313,39 @@ mod tests_ratings {
     let id = TitleId::try_from("tt0000011".as_bytes()).unwrap();
     assert_eq!(ratings.get(&id.as_usize()), None);
   }
+
+  #[test]
+  fn test_weighted_rating() {
+    let weighted = WeightedRating::new(70.0, 25_000);
+    assert_eq!(weighted.score(&Rating::new(90, 0)), 70.0);
+    assert_eq!(weighted.score(&Rating::new(90, 25_000)), 80.0);
+    assert_eq!(WeightedRating::new(70.0, 0).score(&Rating::new(90, 600)), 90.0);
+    assert_eq!(WeightedRating::new(70.0, 0).score(&Rating::new(90, 0)), 70.0);
+
+    // A title rated highly by few people ranks below one rated almost as highly by many.
+    let few = Rating::new(90, 600);
+    let many = Rating::new(85, 100_000);
+    assert_eq!(few.cmp(&many), Ordering::Greater);
+    assert_eq!(weighted.compare(Some(&few), Some(&many)), Ordering::Less);
+
+    assert_eq!(weighted.compare(None, Some(&few)), Ordering::Less);
+    assert_eq!(weighted.compare(Some(&few), None), Ordering::Greater);
+    assert_eq!(weighted.compare(None, None), Ordering::Equal);
+  }
+
+  #[test]
+  fn test_weighted_rating_from_titles() {
+    let id = |id| TitleId::try_from(id).unwrap();
+    let titles = [
+      Title::new(id("tt0000001"), TitleType::Movie, "A").with_rating(60, 10),
+      Title::new(id("tt0000002"), TitleType::Movie, "B").with_rating(80, 20),
+      Title::new(id("tt0000003"), TitleType::Movie, "C"),
+    ];
+
+    let weighted = WeightedRating::from_titles(&titles, 100);
+    assert_eq!(weighted.mean(), 70.0);
+    assert_eq!(weighted.min_votes(), 100);
+
+    assert_eq!(WeightedRating::from_titles([], 100).mean(), 0.0);
+  }
 }
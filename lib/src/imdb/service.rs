@@ -1,55 +1,210 @@
 #![warn(clippy::all)]
 
+#[cfg(feature = "fetch")]
+use std::cell::Cell;
+#[cfg(feature = "fs")]
+use std::fs;
+use std::io::BufRead;
+#[cfg(feature = "fs")]
+use std::io::{BufReader, Write};
+#[cfg(feature = "fs")]
 use std::path::Path;
-use std::time::{Duration, Instant};
+use std::sync::{Arc, OnceLock};
+#[cfg(feature = "fetch")]
+use std::thread;
+use std::time::Duration;
+#[cfg(feature = "fetch")]
+use std::time::Instant;
+use std::time::SystemTime;
 
+#[cfg(feature = "akas")]
+use crate::imdb::akas::Akas;
+use crate::imdb::baseline::Baselines;
+#[cfg(feature = "fs")]
+use crate::imdb::builder::{Builder, UpdatePolicy};
+#[cfg(feature = "fs")]
+use crate::imdb::bundle;
 use crate::imdb::db::Query;
-use crate::imdb::db_binary::ServiceDbFromBinary;
+use crate::imdb::db_binary::{IndexLayout, ServiceDbFromBinary};
+use crate::imdb::db_file::{self, Compression};
+use crate::imdb::filter::Filter;
+use crate::imdb::memory::MemoryStats;
+use crate::imdb::names::NameIndex;
+use crate::imdb::ranked::{self, ScoredTitle};
+use crate::imdb::ratings::WeightedRating;
 use crate::imdb::title::Title;
 use crate::imdb::title_id::TitleId;
-use crate::imdb::tsv_import::tsv_import;
+use crate::imdb::tsv_import::{tsv_import, ImportStats};
+#[cfg(feature = "fetch")]
+use crate::utils::cancel::{CancellableReader, Cancellation};
+#[cfg(feature = "fetch")]
 use crate::utils::io::file as io_file;
+#[cfg(feature = "fs")]
 use crate::utils::io::net as io_net;
-use crate::utils::search::SearchString;
+#[cfg(feature = "fs")]
+use crate::utils::io::progress::ProgressPipe;
+use crate::utils::sample::Sampler;
+use crate::utils::search::{self, SearchOptions, SearchScope, SearchString};
 
+#[cfg(feature = "fetch")]
 use humantime::format_duration;
-use log::{debug, log_enabled};
-use reqwest::Url;
+use rayon::ThreadPool;
+use regex::Regex;
+#[cfg(feature = "fs")]
+use tracing::info_span;
+use tracing::{debug, enabled, info, warn, Level};
+#[cfg(feature = "fetch")]
+use url::Url;
 
 /// Errors when creating service.
+///
+/// Some of the variants only exist with some features, so matching on errors needs a
+/// wildcard arm, or matching on their [ErrorKind] instead.
 #[derive(Debug, thiserror::Error)]
 #[error("Error creating IMDB service")]
+#[non_exhaustive]
 pub enum Error {
   /// File-related error.
+  #[cfg(feature = "fs")]
   #[error("File handling error: {0}")]
   File(#[from] crate::utils::io::file::Error),
   /// Networking-related error.
+  #[cfg(feature = "fetch")]
   #[error("Network handling error: {0}")]
   Net(#[from] crate::utils::io::net::Error),
-  /// Database loading error.
-  #[error("Error loading database: {0}")]
-  Db(#[from] crate::imdb::db_binary::Error),
+  /// The database could not be loaded because it is corrupted.
+  #[error("IMDB database is corrupted: {0}")]
+  CorruptCache(#[from] crate::imdb::db_binary::Error),
+  /// Database writing error.
+  #[error("Error writing database: {0}")]
+  DbFile(#[from] crate::imdb::db_file::Error),
   /// URL parsing error.
   #[error("Error parsing URL: {0}")]
   UrlParsing(#[from] url::ParseError),
   /// TSV conversion error.
   #[error("Error importing from TSV to binary database: {0}")]
   TsvImport(#[from] crate::imdb::tsv_import::Error),
+  /// IO errors.
+  #[error("IO error: {0}")]
+  Io(#[from] std::io::Error),
+  /// The operation was cancelled.
+  #[error("Operation cancelled")]
+  Cancelled,
+  /// Pre-built database bundle error.
+  #[cfg(feature = "fs")]
+  #[error("Error with pre-built databases: {0}")]
+  Bundle(#[from] crate::imdb::bundle::Error),
+  /// No cache directory was given to the builder.
+  #[cfg(feature = "fs")]
+  #[error("No cache directory was given")]
+  NoCacheDir,
+  /// The thread pool to parse the databases could not be created.
+  #[error("Error creating thread pool: {0}")]
+  ThreadPool(#[from] rayon::ThreadPoolBuildError),
+  /// Error with the names of titles in non-Latin scripts.
+  #[cfg(feature = "akas")]
+  #[error("Error with the names of titles in non-Latin scripts: {0}")]
+  Akas(#[from] crate::imdb::akas::Error),
+  /// Error with the index of titles by name.
+  #[error("Error with the index of titles by name: {0}")]
+  NameIndex(#[from] crate::imdb::names::Error),
+  /// Another process is rebuilding the databases, and the builder was set not to wait for
+  /// it, or it did not finish in time.
+  #[cfg(feature = "fetch")]
+  #[error("Another tvrank process is updating the database")]
+  UpdateInProgress,
+}
+
+/// The kind of an [Error], to handle errors by kind without a wildcard arm for the variants
+/// that only exist with some features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+  /// The IMDB dumps or pre-built databases could not be downloaded.
+  Network,
+  /// The databases could not be read or written, or are corrupted.
+  Cache,
+  /// The IMDB dumps could not be parsed.
+  Parse,
+  /// A file could not be read or written, or the system ran out of resources.
+  Io,
+  /// The operation was cancelled.
+  Cancelled,
+  /// The builder was not given what it needs.
+  InvalidInput,
+  /// Another process is updating the databases.
+  UpdateInProgress,
+}
+
+impl Error {
+  /// Returns the kind of the error.
+  pub fn kind(&self) -> ErrorKind {
+    match self {
+      Error::Io(_) | Error::ThreadPool(_) => ErrorKind::Io,
+      #[cfg(feature = "fs")]
+      Error::File(_) => ErrorKind::Io,
+      #[cfg(feature = "fetch")]
+      Error::Net(_) => ErrorKind::Network,
+      Error::UrlParsing(_) => ErrorKind::Network,
+      Error::CorruptCache(_) | Error::DbFile(_) | Error::NameIndex(_) => ErrorKind::Cache,
+      #[cfg(feature = "fs")]
+      Error::Bundle(_) => ErrorKind::Cache,
+      #[cfg(feature = "akas")]
+      Error::Akas(_) => ErrorKind::Cache,
+      Error::TsvImport(_) => ErrorKind::Parse,
+      Error::Cancelled => ErrorKind::Cancelled,
+      #[cfg(feature = "fs")]
+      Error::NoCacheDir => ErrorKind::InvalidInput,
+      #[cfg(feature = "fetch")]
+      Error::UpdateInProgress => ErrorKind::UpdateInProgress,
+    }
+  }
 }
 
 /// Struct providing the movies and series databases and the related services.
+///
+/// Queries by title or keywords return their titles sorted by ID and without duplicates.
+/// Their `iter_` variants instead stream the titles as they are found, in no particular
+/// order, so that large results can be processed with bounded memory. Keyword queries with
+/// a limit stop searching once enough titles were found, and return
+/// any of the matching titles rather than the ones with the lowest IDs.
 pub struct Service {
   service_db: ServiceDbFromBinary,
+  built_at: SystemTime,
+  max_age: Option<Duration>,
+  import_stats: Option<ImportStats>,
+  baselines: OnceLock<Baselines>,
+  thread_pool: Option<Arc<ThreadPool>>,
 }
 
-const IMDB_URL: &str = "https://datasets.imdbws.com/";
+#[cfg(feature = "fetch")]
+pub(crate) const IMDB_URL: &str = "https://datasets.imdbws.com/";
+#[cfg(feature = "fetch")]
 const BASICS_FILENAME: &str = "title.basics.tsv.gz";
-const RATINGS_FILENAME: &str = "title.ratings.tsv.gz";
+#[cfg(feature = "fetch")]
+pub(crate) const RATINGS_FILENAME: &str = "title.ratings.tsv.gz";
 
-const MOVIES_DB_FILENAME: &str = "imdb-movies.tvrankdb";
-const SERIES_DB_FILENAME: &str = "imdb-series.tvrankdb";
+#[cfg(feature = "fs")]
+pub(crate) const MOVIES_DB_FILENAME: &str = "imdb-movies.tvrankdb";
+#[cfg(feature = "fs")]
+pub(crate) const SERIES_DB_FILENAME: &str = "imdb-series.tvrankdb";
+#[cfg(feature = "fs")]
+pub(crate) const IMPORT_STATS_FILENAME: &str = "imdb-import-stats.json";
+#[cfg(feature = "fetch")]
+const UPDATE_LOCK_FILENAME: &str = "imdb-update.lock";
+
+/// How often the lock of another process rebuilding the databases is checked while waiting
+/// for it.
+#[cfg(feature = "fetch")]
+const UPDATE_LOCK_POLL_INTERVAL: Duration = Duration::from_millis(500);
 
 impl Service {
+  /// Returns a builder of a Service struct, to set the options of loading and updating its
+  /// databases.
+  #[cfg(feature = "fs")]
+  pub fn builder<'a>() -> Builder<'a> {
+    Builder::default()
+  }
+
   /// Returns a Service struct holding movies/series databases
   ///
   /// # Arguments
@@ -57,147 +212,1470 @@ impl Service {
   /// * `cache_dir` - Directory path of the database files.
   /// * `force_db_update` - True if the databases should be updated regardless of their age.
   /// * `progress_fn` - Function that keeps track of the download progress.
+  #[cfg(feature = "fetch")]
   pub fn new(
     cache_dir: &Path,
     force_db_update: bool,
     progress_fn: impl Fn(Option<u64>, u64),
   ) -> Result<Self, Error> {
-    let one_month = Duration::from_secs(60 * 60 * 24 * 30);
+    Self::new_cancellable(cache_dir, force_db_update, progress_fn, &Cancellation::new(), Compression::None)
+  }
 
-    let movies_db_filename = cache_dir.join(MOVIES_DB_FILENAME);
-    let series_db_filename = cache_dir.join(SERIES_DB_FILENAME);
-    Self::ensure_db_files(&movies_db_filename, &series_db_filename, one_month, force_db_update, progress_fn)?;
+  /// Returns a Service struct holding movies/series databases, or [Error::Cancelled] if
+  /// `cancellation` is cancelled while the databases are being fetched and built.
+  ///
+  /// Cancelling leaves no partially built databases behind.
+  ///
+  /// Databases are read regardless of their compression, `compression` only applies when
+  /// they are (re)built.
+  ///
+  /// # Arguments
+  ///
+  /// * `cache_dir` - Directory path of the database files.
+  /// * `force_db_update` - True if the databases should be updated regardless of their age.
+  /// * `progress_fn` - Function that keeps track of the download progress.
+  /// * `cancellation` - Token to cancel fetching and building the databases.
+  /// * `compression` - Compression of newly built databases.
+  #[cfg(feature = "fetch")]
+  pub fn new_cancellable(
+    cache_dir: &Path,
+    force_db_update: bool,
+    progress_fn: impl Fn(Option<u64>, u64),
+    cancellation: &Cancellation,
+    compression: Compression,
+  ) -> Result<Self, Error> {
+    let update_policy = if force_db_update {
+      UpdatePolicy::Always
+    } else {
+      UpdatePolicy::IfOutdated
+    };
 
-    let start = Instant::now();
-    let movies_data = io_file::read_static(&movies_db_filename)?;
-    let series_data = io_file::read_static(&series_db_filename)?;
-    debug!("Read IMDB database in {}", format_duration(Instant::now().duration_since(start)));
+    Self::builder()
+      .cache_dir(cache_dir)
+      .update_policy(update_policy)
+      .progress(&progress_fn)
+      .cancellation(cancellation)
+      .compression(compression)
+      .build()
+  }
 
-    let start = Instant::now();
-    let service = Self { service_db: ServiceDbFromBinary::new(movies_data, series_data)? };
-    debug!("Parsed IMDB database in {}", format_duration(Instant::now().duration_since(start)));
+  /// Returns a Service struct holding the movies/series databases in `cache_dir`, without
+  /// fetching or updating them, for applications that manage the databases themselves.
+  ///
+  /// Fails if the databases are missing, damaged or in an older format.
+  ///
+  /// # Arguments
+  ///
+  /// * `cache_dir` - Directory path of the database files.
+  #[cfg(feature = "fs")]
+  pub fn from_cache_only(cache_dir: &Path) -> Result<Self, Error> {
+    Self::builder().cache_dir(cache_dir).update_policy(UpdatePolicy::Never).build()
+  }
+
+  /// Returns a Service struct holding the movies/series databases given as buffers, e.g.
+  /// fetched by a browser, without touching the file system or the network.
+  ///
+  /// The buffers are the contents of the database files written by
+  /// [Service::build_from_files]. Since the time at which they were built is not known,
+  /// [Service::built_at] returns the Unix epoch.
+  ///
+  /// # Arguments
+  ///
+  /// * `movies_data` - Contents of the movies database file.
+  /// * `series_data` - Contents of the series database file.
+  pub fn from_buffers(movies_data: Vec<u8>, series_data: Vec<u8>) -> Result<Self, Error> {
+    Self::from_data(movies_data, series_data, SystemTime::UNIX_EPOCH, None, IndexLayout::Sharded, None, false)
+  }
+
+  /// Returns a Service struct holding movies/series databases built in memory from the
+  /// uncompressed contents of the IMDB dumps, without touching the file system or the
+  /// network.
+  ///
+  /// Since the time at which the dumps were published is not known, [Service::built_at]
+  /// returns the Unix epoch.
+  ///
+  /// # Arguments
+  ///
+  /// * `basics_reader` - Reader of the uncompressed `title.basics` dump.
+  /// * `ratings_reader` - Reader of the uncompressed `title.ratings` dump.
+  pub fn from_tsv_readers(basics_reader: impl BufRead, ratings_reader: impl BufRead) -> Result<Self, Error> {
+    let mut movies_db_writer = db_file::Writer::new(Vec::new(), Compression::None)?;
+    let mut series_db_writer = db_file::Writer::new(Vec::new(), Compression::None)?;
+
+    let stats = tsv_import(ratings_reader, basics_reader, &mut movies_db_writer, &mut series_db_writer)?;
+    log_import_stats(&stats);
+
+    let mut service = Self::from_buffers(movies_db_writer.finish()?, series_db_writer.finish()?)?;
+    service.set_import_stats(Some(stats));
+    Ok(service)
+  }
+
+  /// Parses the contents of the movies and series databases and computes the rating
+  /// baselines of their titles.
+  ///
+  /// Does not measure how long this takes, since clocks are not available on all targets.
+  ///
+  /// # Arguments
+  ///
+  /// * `movies_data` - Contents of the movies database file.
+  /// * `series_data` - Contents of the series database file.
+  /// * `built_at` - The time at which the databases were built.
+  /// * `thread_pool` - Thread pool that parses and queries the databases, instead of the
+  ///   global thread pool.
+  /// * `layout` - How the titles are indexed once they are parsed.
+  /// * `names` - The index of titles by name stored alongside the databases, if any.
+  /// * `lazy` - Whether movies and series are only parsed once they are first queried, in
+  ///   which case the baselines are only computed once they are first needed as well.
+  pub(crate) fn from_data(
+    movies_data: Vec<u8>,
+    series_data: Vec<u8>,
+    built_at: SystemTime,
+    thread_pool: Option<Arc<ThreadPool>>,
+    layout: IndexLayout,
+    names: Option<NameIndex>,
+    lazy: bool,
+  ) -> Result<Self, Error> {
+    let service_db =
+      ServiceDbFromBinary::new(movies_data, series_data, layout, names, thread_pool.clone(), lazy)?;
 
-    if log_enabled!(log::Level::Debug) {
-      let (total_movies, total_series) = service.service_db.n_entries();
-      let total_entries = total_movies + total_series;
-      debug!("IMDB: {total_movies} movies and {total_series} series ({total_entries} entries)");
+    let service = Self {
+      service_db,
+      built_at,
+      max_age: None,
+      import_stats: None,
+      baselines: OnceLock::new(),
+      thread_pool,
+    };
+
+    if !lazy {
+      service.baselines();
+
+      if enabled!(Level::DEBUG) {
+        let (total_movies, total_series) = service.service_db.n_entries();
+        let total_entries = total_movies + total_series;
+        debug!("IMDB: {total_movies} movies and {total_series} series ({total_entries} entries)");
+      }
     }
 
     Ok(service)
   }
 
+  /// Stores the names the titles are indexed by as an index of titles by name, which can be
+  /// loaded along with the databases to spare indexing the titles by name again.
+  ///
+  /// # Arguments
+  ///
+  /// * `filename` - Path of the index.
+  /// * `with_akas` - Whether the titles were indexed by their names in non-Latin scripts.
+  #[cfg(feature = "fs")]
+  pub(crate) fn write_name_index(&self, filename: &Path, with_akas: bool) -> Result<(), Error> {
+    Ok(NameIndex::write(filename, &self.service_db.names(), with_akas)?)
+  }
+
+  /// Reads the movies and series databases, verifying them against their checksums.
+  ///
+  /// # Arguments
+  ///
+  /// * `movies_db_filename` - Path to the movies database.
+  /// * `series_db_filename` - Path to the series database.
+  #[cfg(feature = "fs")]
+  pub(crate) fn read_db_files(
+    movies_db_filename: &Path,
+    series_db_filename: &Path,
+  ) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let movies_data = db_file::read_verified(movies_db_filename)?;
+    let series_data = db_file::read_verified(series_db_filename)?;
+    Ok((movies_data, series_data))
+  }
+
   /// Ensures that the movies and series databases exist and are up-to-date.
   ///
   /// The databases are created if they don't exist, and updated if they are outdated or
   /// if the force_db_update parameter is set to true.
   ///
+  /// Processes updating the databases hold an advisory lock on a file next to them, so that
+  /// they do not write the same files. When another process holds it, the databases are
+  /// only updated once it is released if they are still outdated, since that process most
+  /// likely updated them already.
+  ///
   /// # Arguments
   ///
   /// * `movies_db_filename` - Path to the movies database.
   /// * `series_db_filename` - Path to the series database.
+  /// * `max_age` - The age after which the databases are updated.
   /// * `force_db_update` - True if the databases should be updated regardless of their age.
+  /// * `wait_timeout` - How long to wait for another process updating the databases before
+  ///   failing with [Error::UpdateInProgress], or `None` to fail right away.
   /// * `progress_fn` - Function that keeps track of the download progress.
-  fn ensure_db_files(
+  /// * `cancellation` - Token to cancel fetching and building the databases.
+  /// * `compression` - Compression of the databases if they are built.
+  #[cfg(feature = "fetch")]
+  #[allow(clippy::too_many_arguments)]
+  pub(crate) fn ensure_db_files(
     movies_db_filename: &Path,
     series_db_filename: &Path,
     max_age: Duration,
     force_db_update: bool,
+    wait_timeout: Option<Duration>,
     progress_fn: impl Fn(Option<u64>, u64),
+    cancellation: &Cancellation,
+    compression: Compression,
   ) -> Result<(), Error> {
-    let needs_update = {
-      force_db_update
-        || io_file::older_than(&io_file::open_existing(movies_db_filename)?, max_age)
-        || io_file::older_than(&io_file::open_existing(series_db_filename)?, max_age)
+    if !Self::db_files_need_update(movies_db_filename, series_db_filename, max_age, force_db_update)? {
+      debug!("IMDB database exists and is less than a month old");
+      return Ok(());
+    }
+
+    let lock_filename = movies_db_filename.with_file_name(UPDATE_LOCK_FILENAME);
+    let _lock = match (io_file::try_lock(&lock_filename)?, wait_timeout) {
+      (Some(lock), _) => lock,
+      (None, None) => return Err(Error::UpdateInProgress),
+      (None, Some(wait_timeout)) => {
+        info!("Another process is updating the IMDB database, waiting for it to finish");
+        let lock = Self::wait_for_update_lock(&lock_filename, wait_timeout, cancellation)?;
+
+        if !Self::db_files_need_update(movies_db_filename, series_db_filename, max_age, false)? {
+          debug!("IMDB database was updated by another process");
+          return Ok(());
+        }
+
+        lock
+      }
     };
 
-    if needs_update {
-      if force_db_update {
-        debug!("Force-update is enabled, IMDB database is going to be re-fetched and built");
-      } else {
-        debug!("IMDB database does not exist or is more than a month old, going to fetch and build");
+    let result =
+      Self::build_db_files(movies_db_filename, series_db_filename, progress_fn, cancellation, compression);
+
+    if cancellation.is_cancelled() {
+      debug!("Fetching and building the IMDB database was cancelled");
+      return Err(Error::Cancelled);
+    }
+
+    result
+  }
+
+  /// Whether the movies and series databases need to be updated, logging why they do.
+  ///
+  /// # Arguments
+  ///
+  /// * `movies_db_filename` - Path to the movies database.
+  /// * `series_db_filename` - Path to the series database.
+  /// * `max_age` - The age after which the databases are updated.
+  /// * `force_db_update` - True if the databases should be updated regardless of their age.
+  #[cfg(feature = "fetch")]
+  fn db_files_need_update(
+    movies_db_filename: &Path,
+    series_db_filename: &Path,
+    max_age: Duration,
+    force_db_update: bool,
+  ) -> Result<bool, Error> {
+    let outdated_version = [movies_db_filename, series_db_filename]
+      .into_iter()
+      .map(db_file::version)
+      .find(|version| !matches!(version, Ok(Some(db_file::VERSION))));
+
+    if force_db_update {
+      debug!("Force-update is enabled, IMDB database is going to be re-fetched and built");
+    } else if let Some(Ok(Some(version))) = outdated_version {
+      debug!(
+        "IMDB database has format version {version} instead of {}, going to fetch and rebuild",
+        db_file::VERSION
+      );
+    } else if outdated_version.is_some()
+      || io_file::older_than(&io_file::open_existing(movies_db_filename)?, max_age)
+      || io_file::older_than(&io_file::open_existing(series_db_filename)?, max_age)
+    {
+      debug!("IMDB database does not exist or is more than a month old, going to fetch and build");
+    } else {
+      return Ok(false);
+    }
+
+    Ok(true)
+  }
+
+  /// Wait for another process updating the databases to release its lock, and take it, or
+  /// fail with [Error::UpdateInProgress] if it is still held after `timeout`, e.g. because
+  /// the other process stalled.
+  ///
+  /// # Arguments
+  ///
+  /// * `lock_filename` - Path to the lock file.
+  /// * `timeout` - How long to wait for the lock.
+  /// * `cancellation` - Token to stop waiting.
+  #[cfg(feature = "fetch")]
+  fn wait_for_update_lock(
+    lock_filename: &Path,
+    timeout: Duration,
+    cancellation: &Cancellation,
+  ) -> Result<fs::File, Error> {
+    let start = Instant::now();
+
+    loop {
+      if cancellation.is_cancelled() {
+        return Err(Error::Cancelled);
       }
 
-      let movies_db_writer = io_file::create_buffered(movies_db_filename)?;
-      let series_db_writer = io_file::create_buffered(series_db_filename)?;
+      let remaining = timeout.saturating_sub(start.elapsed());
+      if remaining.is_zero() {
+        warn!("Another process has been updating the IMDB database for {}", format_duration(timeout));
+        return Err(Error::UpdateInProgress);
+      }
 
-      let imdb_url = Url::parse(IMDB_URL)?;
-      let basics_response = io_net::get_response(imdb_url.join(BASICS_FILENAME)?)?;
-      let ratings_response = io_net::get_response(imdb_url.join(RATINGS_FILENAME)?)?;
+      thread::sleep(UPDATE_LOCK_POLL_INTERVAL.min(remaining));
 
-      let content_length = match (basics_response.content_length(), ratings_response.content_length()) {
-        (None, _) | (_, None) => None,
-        (Some(basics_content_length), Some(ratings_content_length)) => {
-          Some(basics_content_length + ratings_content_length)
-        }
-      };
+      if let Some(lock) = io_file::try_lock(lock_filename)? {
+        return Ok(lock);
+      }
+    }
+  }
+
+  /// Fetches the IMDB dumps and builds the movies and series databases from them.
+  ///
+  /// The dumps are imported as they are downloaded, so their import reports its progress:
+  /// the Content-Length of each dump once its import starts, then the compressed bytes of the
+  /// dump that it consumed.
+  ///
+  /// # Arguments
+  ///
+  /// * `movies_db_filename` - Path to the movies database.
+  /// * `series_db_filename` - Path to the series database.
+  /// * `progress_fn` - Function that keeps track of the import of each dump.
+  /// * `cancellation` - Token to cancel fetching and building the databases.
+  /// * `compression` - Compression of the databases.
+  #[cfg(feature = "fetch")]
+  fn build_db_files(
+    movies_db_filename: &Path,
+    series_db_filename: &Path,
+    progress_fn: impl Fn(Option<u64>, u64),
+    cancellation: &Cancellation,
+    compression: Compression,
+  ) -> Result<(), Error> {
+    let imdb_url = Url::parse(IMDB_URL)?;
+    let basics_response = io_net::get_response(imdb_url.join(BASICS_FILENAME)?)?;
+    let ratings_response = io_net::get_response(imdb_url.join(RATINGS_FILENAME)?)?;
+
+    let basics_content_length = basics_response.content_length();
+    let ratings_content_length = ratings_response.content_length();
+
+    // The import of a dump starts once its first bytes are consumed.
+    let basics_started = Cell::new(false);
+    let ratings_started = Cell::new(false);
+    let consumed = |started: &Cell<bool>, content_length: Option<u64>, bytes: u64| {
+      if !started.replace(true) {
+        progress_fn(content_length, 0);
+      }
+      progress_fn(None, bytes);
+    };
 
-      progress_fn(content_length, 0);
+    let basics_response = CancellableReader::new(basics_response, cancellation.clone());
+    let ratings_response = CancellableReader::new(ratings_response, cancellation.clone());
 
-      let basics_fetcher = io_net::make_fetcher(basics_response, |bytes| progress_fn(None, bytes));
-      let ratings_fetcher = io_net::make_fetcher(ratings_response, |bytes| progress_fn(None, bytes));
+    let basics_fetcher =
+      io_net::make_fetcher(basics_response, |bytes| consumed(&basics_started, basics_content_length, bytes));
+    let ratings_fetcher = io_net::make_fetcher(ratings_response, |bytes| {
+      consumed(&ratings_started, ratings_content_length, bytes)
+    });
 
-      tsv_import(ratings_fetcher, basics_fetcher, movies_db_writer, series_db_writer)?;
+    Self::import_db_files(
+      ratings_fetcher,
+      basics_fetcher,
+      movies_db_filename,
+      series_db_filename,
+      compression,
+    )
+  }
+
+  /// Imports the IMDB dumps into the movies and series databases.
+  ///
+  /// The databases are written to temporary files which only replace the existing databases
+  /// once both were fully imported, so an interrupted import does not leave a corrupted cache
+  /// behind.
+  ///
+  /// # Arguments
+  ///
+  /// * `ratings_reader` - Reader of the uncompressed ratings dump.
+  /// * `basics_reader` - Reader of the uncompressed basics dump.
+  /// * `movies_db_filename` - Path to the movies database.
+  /// * `series_db_filename` - Path to the series database.
+  /// * `compression` - Compression of the databases.
+  #[cfg(feature = "fs")]
+  fn import_db_files(
+    ratings_reader: impl BufRead,
+    basics_reader: impl BufRead,
+    movies_db_filename: &Path,
+    series_db_filename: &Path,
+    compression: Compression,
+  ) -> Result<(), Error> {
+    let _span = info_span!("import", db = "titles").entered();
+    let movies_partial = db_file::partial_filename(movies_db_filename);
+    let series_partial = db_file::partial_filename(series_db_filename);
+
+    let result = (|| -> Result<(), Error> {
+      let mut movies_db_writer = db_file::create(&movies_partial, compression)?;
+      let mut series_db_writer = db_file::create(&series_partial, compression)?;
+
+      let stats = tsv_import(ratings_reader, basics_reader, &mut movies_db_writer, &mut series_db_writer)?;
+      log_import_stats(&stats);
+
+      movies_db_writer.finish()?;
+      series_db_writer.finish()?;
+
+      fs::rename(&movies_partial, movies_db_filename)?;
+      fs::rename(&series_partial, series_db_filename)?;
+
+      db_file::write_checksum(movies_db_filename)?;
+      db_file::write_checksum(series_db_filename)?;
+
+      stats.save(&movies_db_filename.with_file_name(IMPORT_STATS_FILENAME))?;
+
+      Ok(())
+    })();
+
+    if result.is_err() {
+      let _ = fs::remove_file(&movies_partial);
+      let _ = fs::remove_file(&series_partial);
+    }
+
+    result
+  }
+
+  /// Builds the databases in `cache_dir` from IMDB dumps that were already downloaded, instead
+  /// of fetching them.
+  ///
+  /// The dumps can either be gzip-compressed, as they are published, if their names end with
+  /// `.gz`, or uncompressed.
+  ///
+  /// # Arguments
+  ///
+  /// * `basics_filename` - Path to the `title.basics` dump.
+  /// * `ratings_filename` - Path to the `title.ratings` dump.
+  /// * `cache_dir` - Directory path of the database files.
+  /// * `progress_fn` - Function that keeps track of how much of the dumps was read.
+  /// * `compression` - Compression of the databases.
+  #[cfg(feature = "fs")]
+  pub fn build_from_files(
+    basics_filename: &Path,
+    ratings_filename: &Path,
+    cache_dir: &Path,
+    progress_fn: impl Fn(Option<u64>, u64),
+    compression: Compression,
+  ) -> Result<(), Error> {
+    let movies_db_filename = cache_dir.join(MOVIES_DB_FILENAME);
+    let series_db_filename = cache_dir.join(SERIES_DB_FILENAME);
+
+    let basics_file = fs::File::open(basics_filename)?;
+    let ratings_file = fs::File::open(ratings_filename)?;
+    progress_fn(Some(basics_file.metadata()?.len() + ratings_file.metadata()?.len()), 0);
+
+    let basics_reader = Self::dump_reader(basics_filename, basics_file, |bytes| progress_fn(None, bytes));
+    let ratings_reader = Self::dump_reader(ratings_filename, ratings_file, |bytes| progress_fn(None, bytes));
+
+    Self::import_db_files(
+      ratings_reader,
+      basics_reader,
+      &movies_db_filename,
+      &series_db_filename,
+      compression,
+    )
+  }
+
+  /// Returns a reader of the uncompressed contents of a dump file.
+  ///
+  /// # Arguments
+  ///
+  /// * `filename` - Path of the dump, which is gzip-compressed if it ends with `.gz`.
+  /// * `file` - The opened dump.
+  /// * `progress_fn` - Function that keeps track of how much of the file was read.
+  #[cfg(feature = "fs")]
+  fn dump_reader<'a>(
+    filename: &Path,
+    file: fs::File,
+    progress_fn: impl Fn(u64) + 'a,
+  ) -> Box<dyn BufRead + 'a> {
+    if filename.extension().is_some_and(|extension| extension == "gz") {
+      Box::new(io_net::make_fetcher(file, progress_fn))
     } else {
-      debug!("IMDB database exists and is less than a month old");
+      Box::new(BufReader::new(ProgressPipe::new(file, progress_fn)))
     }
+  }
+
+  /// Replaces the databases in `cache_dir` with pre-built ones downloaded from `url`, as
+  /// written by [Service::publish_prebuilt].
+  ///
+  /// The existing databases are only replaced once the downloaded ones were validated.
+  ///
+  /// # Arguments
+  ///
+  /// * `cache_dir` - Directory path of the database files.
+  /// * `url` - URL of the bundle of pre-built databases.
+  /// * `progress_fn` - Function that keeps track of the download progress.
+  #[cfg(feature = "fetch")]
+  pub fn fetch_prebuilt(
+    cache_dir: &Path,
+    url: Url,
+    progress_fn: impl Fn(Option<u64>, u64),
+  ) -> Result<(), Error> {
+    let response = io_net::get_response(url)?.error_for_status().map_err(io_net::Error::from)?;
+    progress_fn(response.content_length(), 0);
+
+    let fetcher = io_net::make_fetcher(response, |bytes| progress_fn(None, bytes));
+    bundle::install(fetcher, &cache_dir.join(MOVIES_DB_FILENAME), &cache_dir.join(SERIES_DB_FILENAME))?;
 
     Ok(())
   }
 
-  /// Query titles by ID.
+  /// Writes the databases in `cache_dir` as a bundle of pre-built databases that can be
+  /// installed with [Service::fetch_prebuilt].
   ///
   /// # Arguments
   ///
-  /// * `id` - ID of the title to be queried.
-  /// * `query` - Specifies if movies or series are queried.
-  pub fn by_id(&self, id: &TitleId, query: Query) -> Option<&Title> {
-    self.service_db.by_id(id, query)
+  /// * `cache_dir` - Directory path of the database files.
+  /// * `writer` - Where to write the bundle to.
+  #[cfg(feature = "fs")]
+  pub fn publish_prebuilt(cache_dir: &Path, writer: impl Write) -> Result<(), Error> {
+    bundle::publish(&cache_dir.join(MOVIES_DB_FILENAME), &cache_dir.join(SERIES_DB_FILENAME), writer)?;
+    Ok(())
   }
 
-  /// Query titles by title.
+  /// The version of the format of the database files.
+  ///
+  /// Databases in other versions of the format are rebuilt when the service is created.
+  pub fn cache_version() -> u8 {
+    db_file::VERSION
+  }
+
+  /// The time at which the databases were last built from the IMDB dumps.
+  pub fn built_at(&self) -> SystemTime {
+    self.built_at
+  }
+
+  /// Whether the databases are older than the maximum age of the [Builder] that loaded
+  /// them, and would be rebuilt by loading them again with [UpdatePolicy::IfOutdated]. The
+  /// databases age while the service is used, so applications loading them with
+  /// [UpdatePolicy::InBackground] can check this again to know when to rebuild them.
+  ///
+  /// Services created from dumps or without the `fetch` feature are never outdated.
+  pub fn is_outdated(&self) -> bool {
+    self
+      .max_age
+      .is_some_and(|max_age| self.built_at.elapsed().map_or(true, |age| age >= max_age))
+  }
+
+  /// The median ratings and votes of movies and series per genre and decade.
+  pub fn baselines(&self) -> &Baselines {
+    self
+      .baselines
+      .get_or_init(|| Baselines::new(self.titles(Query::Movies).chain(self.titles(Query::Series))))
+  }
+
+  /// The average rating (out of 100) of all movies and series with a rating, which is
+  /// computed along with the [baselines](Service::baselines).
+  pub fn mean_rating(&self) -> f64 {
+    self.baselines().mean_rating()
+  }
+
+  /// A weighted rating whose prior mean is the average rating of all movies and series (see
+  /// [Service::mean_rating]).
   ///
   /// # Arguments
   ///
-  /// * `title` - Title to be queried.
-  /// * `query` - Specifies if movies or series are queried.
-  pub fn by_title(&self, title: &SearchString, query: Query) -> Vec<&Title> {
-    self.service_db.by_title(title, query)
+  /// * `min_votes` - The number of votes at which the rating of a title and the prior mean
+  ///   weigh the same.
+  pub fn weighted_rating(&self, min_votes: u32) -> WeightedRating {
+    WeightedRating::new(self.mean_rating(), min_votes)
   }
 
-  /// Query titles by title and year.
+  /// The number of titles of the given kind.
   ///
   /// # Arguments
   ///
-  /// * `title` - Title to be queried.
-  /// * `year` - Release year of the title.
-  /// * `query` - Specifies if movies or series are queried.
-  pub fn by_title_and_year(&self, title: &SearchString, year: u16, query: Query) -> Vec<&Title> {
-    self.service_db.by_title_and_year(title, year, query)
+  /// * `query` - Specifies if movies or series are counted.
+  pub fn n_titles(&self, query: Query) -> usize {
+    self.service_db.n_titles(query)
   }
 
-  /// Query titles by keywords.
+  /// Estimate the memory used by the databases: the contents of the database files, and the
+  /// titles and indexes of each shard of the database. Titles that were not parsed yet (see
+  /// [Builder::lazy]) are not counted.
+  pub fn memory_stats(&self) -> MemoryStats {
+    self.service_db.memory_stats()
+  }
+
+  /// The statistics of the import that built the databases, to verify what they contain, or
+  /// `None` if they are not known, e.g. for databases given as buffers or built by an older
+  /// version.
+  pub fn last_import_stats(&self) -> Option<&ImportStats> {
+    self.import_stats.as_ref()
+  }
+
+  /// Set the statistics of the import that built the databases.
   ///
   /// # Arguments
   ///
-  /// * `keywords` - List of keywords to search in titles.
-  /// * `query` - Specifies if movies or series are queried.
-  pub fn by_keywords<'a>(&'a self, keywords: &[SearchString], query: Query) -> Vec<&'a Title> {
-    self.service_db.by_keywords(keywords, query)
+  /// * `stats` - The import statistics, if they are known.
+  pub(crate) fn set_import_stats(&mut self, stats: Option<ImportStats>) {
+    self.import_stats = stats;
   }
 
-  /// Query titles by keywords and year.
+  /// Set the age after which the databases are outdated.
   ///
   /// # Arguments
   ///
-  /// * `keywords` - List of keywords to search in titles.
-  /// * `year` - Release year of the title.
+  /// * `max_age` - The maximum age of the builder that loaded the databases.
+  #[cfg(feature = "fetch")]
+  pub(crate) fn set_max_age(&mut self, max_age: Duration) {
+    self.max_age = Some(max_age);
+  }
+
+  /// Index the titles by their names in non-Latin scripts and the transliterations of those
+  /// names as well, which are searched as original titles. The builder does this with the
+  /// akas database when the `akas` feature is enabled.
+  ///
+  /// Titles loaded along with an index of titles by name (see [Builder::name_index]) are
+  /// looked up by the names of the index only, which include these names if they were
+  /// indexed when the index was written. Titles that were not parsed yet (see
+  /// [Builder::lazy]) are parsed first.
+  ///
+  /// # Arguments
+  ///
+  /// * `akas` - The names of titles in non-Latin scripts.
+  #[cfg(feature = "akas")]
+  pub fn store_akas(&mut self, akas: &Akas) {
+    let service_db = &mut self.service_db;
+    match &self.thread_pool {
+      Some(thread_pool) => thread_pool.install(|| service_db.store_akas(akas)),
+      None => service_db.store_akas(akas),
+    }
+  }
+
+  /// Index the titles by their names in non-Latin scripts as well, like
+  /// [Service::store_akas], without parsing the titles that were not parsed yet: they are
+  /// indexed by these names once they are.
+  ///
+  /// # Arguments
+  ///
+  /// * `akas` - The names of titles in non-Latin scripts.
+  #[cfg(feature = "akas")]
+  pub(crate) fn store_akas_lazily(&mut self, akas: Akas) {
+    let service_db = &mut self.service_db;
+    match &self.thread_pool {
+      Some(thread_pool) => thread_pool.install(|| service_db.store_akas_lazily(akas)),
+      None => service_db.store_akas_lazily(akas),
+    }
+  }
+
+  /// Query titles with the given search options instead of the default ones, e.g. to match
+  /// keywords as whole words or to only search the original names of titles.
+  ///
+  /// # Arguments
+  ///
+  /// * `options` - How titles are searched by name, regular expression or keywords.
+  pub fn with_options(&self, options: SearchOptions) -> Search<'_> {
+    Search { service: self, options }
+  }
+
+  /// Iterate over all titles of the given kind, in no particular order.
+  ///
+  /// # Arguments
+  ///
+  /// * `query` - Specifies if movies or series are iterated over.
+  pub fn titles(&self, query: Query) -> impl Iterator<Item = &Title<'_>> + '_ {
+    self.service_db.titles(query)
+  }
+
+  /// Iterate over all movies across the shards of the database, in no particular order, e.g.
+  /// to build custom analytics, exports or indexes.
+  pub fn iter_movies(&self) -> impl Iterator<Item = &Title<'_>> + '_ {
+    self.titles(Query::Movies)
+  }
+
+  /// Iterate over all series across the shards of the database, in no particular order.
+  pub fn iter_series(&self) -> impl Iterator<Item = &Title<'_>> + '_ {
+    self.titles(Query::Series)
+  }
+
+  /// Query titles by ID.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - ID of the title to be queried.
   /// * `query` - Specifies if movies or series are queried.
-  pub fn by_keywords_and_year<'a>(
-    &'a self,
-    keywords: &[SearchString],
-    year: u16,
-    query: Query,
-  ) -> Vec<&'a Title> {
-    self.service_db.by_keywords_and_year(keywords, year, query)
+  pub fn by_id(&self, id: &TitleId, query: Query) -> Option<&Title<'_>> {
+    self.in_thread_pool(|| self.service_db.by_id(id, query))
+  }
+
+  /// Query titles by ID in both the movies and series databases.
+  ///
+  /// Returns the title together with the database it was found in.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - ID of the title to be queried.
+  pub fn by_id_any(&self, id: &TitleId) -> Option<(&Title<'_>, Query)> {
+    self.in_thread_pool(|| self.service_db.by_id_any(id))
+  }
+
+  /// Query titles by title.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - Title to be queried.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_title(&self, title: &SearchString, query: Query) -> Vec<&Title<'_>> {
+    self.with_options(SearchOptions::default()).by_title(title, query)
+  }
+
+  /// Query titles by title and year.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - Title to be queried.
+  /// * `year` - Release year of the title.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_title_and_year(&self, title: &SearchString, year: u16, query: Query) -> Vec<&Title<'_>> {
+    self
+      .with_options(SearchOptions::default())
+      .by_title_and_year(title, year, query)
+  }
+
+  /// Query titles whose names only match the given title once their leading articles are
+  /// stripped, e.g. "The Matrix" for "Matrix" or "Le fabuleux destin d'Amélie Poulain" for
+  /// "Fabuleux destin d'Amélie Poulain", for when [Service::by_title] finds nothing because
+  /// the article was left out or added. Titles matching exactly are not returned.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - Title to be queried.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_title_normalized(&self, title: &SearchString, query: Query) -> Vec<&Title<'_>> {
+    self.with_options(SearchOptions::default()).by_title_normalized(title, query)
+  }
+
+  /// Query titles released in the given year whose names only match the given title once
+  /// their leading articles are stripped, like [Service::by_title_normalized].
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - Title to be queried.
+  /// * `year` - Release year of the title.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_title_and_year_normalized(
+    &self,
+    title: &SearchString,
+    year: u16,
+    query: Query,
+  ) -> Vec<&Title<'_>> {
+    self
+      .with_options(SearchOptions::default())
+      .by_title_and_year_normalized(title, year, query)
+  }
+
+  /// Query titles whose names match a regular expression.
+  ///
+  /// Names are matched in lowercase and also as their ASCII transliterations (e.g.
+  /// "lumiere" for "Lumière"), so patterns should be lowercase. Every name in the database
+  /// is matched against the regular expression, which makes this query much slower than
+  /// the others, especially with patterns that are not anchored.
+  ///
+  /// # Arguments
+  ///
+  /// * `regex` - The regular expression to match title names with.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_title_regex(&self, regex: &Regex, query: Query) -> Vec<&Title<'_>> {
+    self.with_options(SearchOptions::default()).by_title_regex(regex, query)
+  }
+
+  /// Query all titles matching a filter.
+  ///
+  /// # Arguments
+  ///
+  /// * `filter` - Criteria that the titles have to match.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_filter(&self, filter: &Filter, query: Query) -> Vec<&Title<'_>> {
+    self.titles(query).filter(|title| filter.matches(title)).collect()
+  }
+
+  /// Iterate over the titles matching a filter as they are found, in no particular order.
+  ///
+  /// # Arguments
+  ///
+  /// * `filter` - Criteria that the titles have to match.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn iter_by_filter<'a>(
+    &'a self,
+    filter: &'a Filter,
+    query: Query,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+    self.titles(query).filter(move |title| filter.matches(title))
+  }
+
+  /// Iterate over the titles with the given name as they are found, in no particular order
+  /// and without duplicates.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - Title to be queried.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn iter_by_title<'a>(
+    &'a self,
+    title: &'a SearchString,
+    query: Query,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+    self.with_options(SearchOptions::default()).iter_by_title(title, query)
+  }
+
+  /// Iterate over the titles with the given name and release year as they are found, in no
+  /// particular order and without duplicates.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - Title to be queried.
+  /// * `year` - Release year of the title.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn iter_by_title_and_year<'a>(
+    &'a self,
+    title: &'a SearchString,
+    year: u16,
+    query: Query,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+    self
+      .with_options(SearchOptions::default())
+      .iter_by_title_and_year(title, year, query)
+  }
+
+  /// Iterate over the titles matching keywords as they are found, in no particular order and
+  /// without duplicates. Unlike [Service::by_keywords], the search runs on the calling thread
+  /// and only goes as far as the iterator is consumed.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - List of keywords to search in titles.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn iter_by_keywords<'a>(
+    &'a self,
+    keywords: &'a [SearchString],
+    query: Query,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+    self.with_options(SearchOptions::default()).iter_by_keywords(keywords, query)
+  }
+
+  /// Iterate over the titles matching keywords and released in the given year as they are
+  /// found, in no particular order and without duplicates.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - List of keywords to search in titles.
+  /// * `year` - Release year of the title.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn iter_by_keywords_and_year<'a>(
+    &'a self,
+    keywords: &'a [SearchString],
+    year: u16,
+    query: Query,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+    self
+      .with_options(SearchOptions::default())
+      .iter_by_keywords_and_year(keywords, year, query)
+  }
+
+  /// Draw a random sample of the titles matching a filter.
+  ///
+  /// Titles are drawn in the order of their IDs, so that a sampler with the same seed draws
+  /// the same titles as long as they are in the database, regardless of how it was built.
+  ///
+  /// # Arguments
+  ///
+  /// * `filter` - Criteria that the titles have to match.
+  /// * `query` - Specifies if movies or series are queried.
+  /// * `count` - Maximum number of titles to draw.
+  /// * `sampler` - Random number generator drawing the titles.
+  pub fn sample(
+    &self,
+    filter: &Filter,
+    query: Query,
+    count: usize,
+    sampler: &mut Sampler,
+  ) -> Vec<&Title<'_>> {
+    let mut titles = self.by_filter(filter, query);
+    titles.sort_unstable_by_key(|title| title.title_id().as_usize());
+    sampler.sample(titles, count)
+  }
+
+  /// Query titles by keywords.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - List of keywords to search in titles.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_keywords<'a>(&'a self, keywords: &[SearchString], query: Query) -> Vec<&'a Title<'a>> {
+    self.with_options(SearchOptions::default()).by_keywords(keywords, query)
+  }
+
+  /// Query at most `limit` titles by keywords, which is much faster than querying all of
+  /// them when few keywords match many titles.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - List of keywords to search in titles.
+  /// * `limit` - Maximum number of titles to return.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_keywords_limited<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    limit: usize,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    self
+      .with_options(SearchOptions::default())
+      .by_keywords_limited(keywords, limit, query)
+  }
+
+  /// Query titles by keywords, most relevant first: titles named exactly after the keywords
+  /// come before titles where every keyword starts a word, which come before titles only
+  /// containing the keywords, and titles of the same kind of match are ordered by their
+  /// number of votes.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - List of keywords to search in titles.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_keywords_ranked<'a>(&'a self, keywords: &[SearchString], query: Query) -> Vec<ScoredTitle<'a>> {
+    self.with_options(SearchOptions::default()).by_keywords_ranked(keywords, query)
+  }
+
+  /// Query titles by keywords and year.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - List of keywords to search in titles.
+  /// * `year` - Release year of the title.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_keywords_and_year<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    year: u16,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    self
+      .with_options(SearchOptions::default())
+      .by_keywords_and_year(keywords, year, query)
+  }
+
+  /// Query at most `limit` titles by keywords and year.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - List of keywords to search in titles.
+  /// * `year` - Release year of the title.
+  /// * `limit` - Maximum number of titles to return.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_keywords_and_year_limited<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    year: u16,
+    limit: usize,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    self
+      .with_options(SearchOptions::default())
+      .by_keywords_and_year_limited(keywords, year, limit, query)
+  }
+
+  /// Run a query in the thread pool of the service, or in the global thread pool if the
+  /// service was not given one.
+  ///
+  /// # Arguments
+  ///
+  /// * `query_fn` - The query to run.
+  fn in_thread_pool<R: Send>(&self, query_fn: impl FnOnce() -> R + Send) -> R {
+    match &self.thread_pool {
+      Some(thread_pool) => thread_pool.install(query_fn),
+      None => query_fn(),
+    }
+  }
+}
+
+/// Queries of the movies and series databases with search options other than the default
+/// ones of the [Service] queries, returned by [Service::with_options].
+pub struct Search<'a> {
+  service: &'a Service,
+  options: SearchOptions,
+}
+
+impl<'a> Search<'a> {
+  /// Returns the search options of the queries.
+  pub fn options(&self) -> &SearchOptions {
+    &self.options
+  }
+
+  /// Query titles by ID, which does not depend on the search options, like [Service::by_id].
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - ID of the title to be queried.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_id(&self, id: &TitleId, query: Query) -> Option<&'a Title<'a>> {
+    self.service.by_id(id, query)
+  }
+
+  /// Query titles by ID in both the movies and series databases, like [Service::by_id_any].
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - ID of the title to be queried.
+  pub fn by_id_any(&self, id: &TitleId) -> Option<(&'a Title<'a>, Query)> {
+    self.service.by_id_any(id)
+  }
+
+  /// Query titles by title, like [Service::by_title].
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - Title to be queried.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_title(&self, title: &SearchString, query: Query) -> Vec<&'a Title<'a>> {
+    let service = self.service;
+    service.in_thread_pool(|| service.service_db.by_title(title, self.options.scope, query))
+  }
+
+  /// Query titles by title and year, like [Service::by_title_and_year].
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - Title to be queried.
+  /// * `year` - Release year of the title.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_title_and_year(&self, title: &SearchString, year: u16, query: Query) -> Vec<&'a Title<'a>> {
+    let service = self.service;
+    service.in_thread_pool(|| service.service_db.by_title_and_year(title, year, self.options.scope, query))
+  }
+
+  /// Query titles whose names only match the given title once the leading articles of the
+  /// stop words are stripped, like [Service::by_title_normalized].
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - Title to be queried.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_title_normalized(&self, title: &SearchString, query: Query) -> Vec<&'a Title<'a>> {
+    let service = self.service;
+    let mut titles =
+      service.in_thread_pool(|| service.service_db.by_stripped_title(title, None, self.options.scope, query));
+    titles.retain(|candidate| self.matches_without_articles(candidate, title));
+    titles
+  }
+
+  /// Query titles released in the given year whose names only match the given title once
+  /// the leading articles of the stop words are stripped, like
+  /// [Service::by_title_and_year_normalized].
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - Title to be queried.
+  /// * `year` - Release year of the title.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_title_and_year_normalized(
+    &self,
+    title: &SearchString,
+    year: u16,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    let service = self.service;
+    let mut titles = service.in_thread_pool(|| {
+      service
+        .service_db
+        .by_stripped_title(title, Some(year), self.options.scope, query)
+    });
+    titles.retain(|candidate| self.matches_without_articles(candidate, title));
+    titles
+  }
+
+  /// Whether none of the names of a title in the search scope is the given title, and one
+  /// of them is once the configured leading articles are stripped from both.
+  ///
+  /// # Arguments
+  ///
+  /// * `candidate` - The title.
+  /// * `title` - The title searched for.
+  fn matches_without_articles(&self, candidate: &Title, title: &SearchString) -> bool {
+    let (primary, original) = match (self.options.scope, candidate.original_title()) {
+      (SearchScope::All, original) => (Some(candidate.primary_title()), original),
+      (SearchScope::PrimaryTitles, _) => (Some(candidate.primary_title()), None),
+      (SearchScope::OriginalTitles, None) => (Some(candidate.primary_title()), None),
+      (SearchScope::OriginalTitles, Some(original)) => (None, Some(original)),
+    };
+    let names: Vec<String> = primary.into_iter().chain(original).map(search::normalize).collect();
+
+    let stripped = self.options.stop_words.strip(title.as_str());
+    !names.iter().any(|name| name == title.as_str())
+      && names.iter().any(|name| self.options.stop_words.strip(name) == stripped)
+  }
+
+  /// Query titles whose names match a regular expression, like [Service::by_title_regex].
+  ///
+  /// # Arguments
+  ///
+  /// * `regex` - The regular expression to match title names with.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_title_regex(&self, regex: &Regex, query: Query) -> Vec<&'a Title<'a>> {
+    let service = self.service;
+    service.in_thread_pool(|| service.service_db.by_title_regex(regex, self.options.scope, query))
+  }
+
+  /// Iterate over the titles with the given name as they are found, like
+  /// [Service::iter_by_title].
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - Title to be queried.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn iter_by_title(
+    &self,
+    title: &'a SearchString,
+    query: Query,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+    self.service.service_db.iter_by_title(title, self.options.scope, query)
+  }
+
+  /// Iterate over the titles with the given name and release year as they are found, like
+  /// [Service::iter_by_title_and_year].
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - Title to be queried.
+  /// * `year` - Release year of the title.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn iter_by_title_and_year(
+    &self,
+    title: &'a SearchString,
+    year: u16,
+    query: Query,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+    self
+      .service
+      .service_db
+      .iter_by_title_and_year(title, year, self.options.scope, query)
+  }
+
+  /// Iterate over the titles matching keywords as they are found, like
+  /// [Service::iter_by_keywords].
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - List of keywords to search in titles.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn iter_by_keywords(
+    &self,
+    keywords: &'a [SearchString],
+    query: Query,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+    let options = &self.options;
+    self
+      .service
+      .service_db
+      .iter_by_keywords(keywords, options.match_kind, options.scope, query)
+  }
+
+  /// Iterate over the titles matching keywords and released in the given year as they are
+  /// found, like [Service::iter_by_keywords_and_year].
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - List of keywords to search in titles.
+  /// * `year` - Release year of the title.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn iter_by_keywords_and_year(
+    &self,
+    keywords: &'a [SearchString],
+    year: u16,
+    query: Query,
+  ) -> impl Iterator<Item = &'a Title<'a>> + 'a {
+    let options = &self.options;
+    self.service.service_db.iter_by_keywords_and_year(
+      keywords,
+      year,
+      options.match_kind,
+      options.scope,
+      query,
+    )
+  }
+
+  /// Query titles by keywords, like [Service::by_keywords].
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - List of keywords to search in titles.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_keywords(&self, keywords: &[SearchString], query: Query) -> Vec<&'a Title<'a>> {
+    self.keywords(keywords, None, None, query)
+  }
+
+  /// Query at most `limit` titles by keywords, like [Service::by_keywords_limited].
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - List of keywords to search in titles.
+  /// * `limit` - Maximum number of titles to return.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_keywords_limited(
+    &self,
+    keywords: &[SearchString],
+    limit: usize,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    self.keywords(keywords, None, Some(limit), query)
+  }
+
+  /// Query titles by keywords, most relevant first, like [Service::by_keywords_ranked].
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - List of keywords to search in titles.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_keywords_ranked(&self, keywords: &[SearchString], query: Query) -> Vec<ScoredTitle<'a>> {
+    ranked::rank(self.by_keywords(keywords, query), keywords)
+  }
+
+  /// Query titles by keywords and year, like [Service::by_keywords_and_year].
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - List of keywords to search in titles.
+  /// * `year` - Release year of the title.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_keywords_and_year(
+    &self,
+    keywords: &[SearchString],
+    year: u16,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    self.keywords(keywords, Some(year), None, query)
+  }
+
+  /// Query at most `limit` titles by keywords and year, like
+  /// [Service::by_keywords_and_year_limited].
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - List of keywords to search in titles.
+  /// * `year` - Release year of the title.
+  /// * `limit` - Maximum number of titles to return.
+  /// * `query` - Specifies if movies or series are queried.
+  pub fn by_keywords_and_year_limited(
+    &self,
+    keywords: &[SearchString],
+    year: u16,
+    limit: usize,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    self.keywords(keywords, Some(year), Some(limit), query)
+  }
+
+  /// Query titles by keywords in the thread pool of the service, optionally only the ones
+  /// released in the given year and at most `limit` of them.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - List of keywords to search in titles.
+  /// * `year` - Release year of the titles, if any.
+  /// * `limit` - Maximum number of titles to return, if any.
+  /// * `query` - Specifies if movies or series are queried.
+  fn keywords(
+    &self,
+    keywords: &[SearchString],
+    year: Option<u16>,
+    limit: Option<usize>,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    let service = self.service;
+    let (kind, scope) = (self.options.match_kind, self.options.scope);
+    service.in_thread_pool(|| match year {
+      Some(year) => service
+        .service_db
+        .by_keywords_and_year(keywords, year, kind, scope, query, limit),
+      None => service.service_db.by_keywords(keywords, kind, scope, query, limit),
+    })
+  }
+}
+
+/// Log the statistics of an import, warning about values of the dumps that are not known.
+///
+/// # Arguments
+///
+/// * `stats` - The import statistics.
+fn log_import_stats(stats: &ImportStats) {
+  info!("Imported the IMDB dumps: {stats}");
+  if stats.parse_warnings() > 0 {
+    warn!("The IMDB dumps contain {}", stats.describe_warnings());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::imdb::testdata::{make_basics_reader, make_ratings_reader};
+  use crate::utils::search::{Language, StopWords};
+
+  #[test]
+  #[cfg(feature = "fs")]
+  fn from_buffers() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let movies_db_filename = dir.path().join(MOVIES_DB_FILENAME);
+    let series_db_filename = dir.path().join(SERIES_DB_FILENAME);
+    Service::import_db_files(
+      make_ratings_reader(),
+      make_basics_reader(),
+      &movies_db_filename,
+      &series_db_filename,
+      Compression::Zstd,
+    )
+    .unwrap();
+
+    let imdb =
+      Service::from_buffers(fs::read(&movies_db_filename).unwrap(), fs::read(&series_db_filename).unwrap())
+        .unwrap();
+    assert_eq!(imdb.built_at(), SystemTime::UNIX_EPOCH);
+    assert_eq!(imdb.n_titles(Query::Movies), 11);
+
+    let id = TitleId::try_from("tt0000001").unwrap();
+    assert_eq!(imdb.by_id(&id, Query::Movies).unwrap().primary_title(), "Carmencita");
+
+    let title = SearchString::try_from("le clown et ses chiens").unwrap();
+    assert_eq!(imdb.by_title(&title, Query::Movies).len(), 1);
+
+    assert!(Service::from_buffers(b"not a database".to_vec(), Vec::new()).is_err());
+  }
+
+  #[test]
+  fn from_tsv_readers() {
+    let imdb = Service::from_tsv_readers(make_basics_reader(), make_ratings_reader()).unwrap();
+    assert_eq!(imdb.n_titles(Query::Movies), 11);
+    assert_eq!(imdb.n_titles(Query::Series), 0);
+
+    let id = TitleId::try_from("tt0000010").unwrap();
+    let title = imdb.by_id(&id, Query::Movies).unwrap();
+    assert_eq!(title.primary_title(), "Leaving the Factory");
+    assert_eq!(title.rating().unwrap().votes(), 6636);
+
+    let basics = "tconst\ttitleType\nnot a title\n".as_bytes();
+    assert!(Service::from_tsv_readers(basics, make_ratings_reader()).is_err());
+  }
+
+  #[test]
+  fn mean_rating() {
+    let imdb = Service::from_tsv_readers(make_basics_reader(), make_ratings_reader()).unwrap();
+    let expected =
+      WeightedRating::from_titles(imdb.titles(Query::Movies).chain(imdb.titles(Query::Series)), 0);
+    assert_eq!(imdb.mean_rating(), expected.mean());
+    assert!(imdb.mean_rating() > 0.0);
+
+    let weighted = imdb.weighted_rating(1000);
+    assert_eq!(weighted.mean(), imdb.mean_rating());
+    assert_eq!(weighted.min_votes(), 1000);
+  }
+
+  #[test]
+  fn by_title_normalized() {
+    let imdb = Service::from_tsv_readers(make_basics_reader(), make_ratings_reader()).unwrap();
+    let ids =
+      |titles: Vec<&Title>| titles.iter().map(|title| title.title_id().as_usize()).collect::<Vec<_>>();
+    let title = |title: &str| SearchString::try_from(title).unwrap();
+
+    assert!(imdb.by_title(&title("Clown et ses chiens"), Query::Movies).is_empty());
+    assert_eq!(ids(imdb.by_title_normalized(&title("Clown et ses chiens"), Query::Movies)), [2]);
+    assert_eq!(ids(imdb.by_title_normalized(&title("The clown et ses chiens"), Query::Movies)), [2]);
+    assert_eq!(ids(imdb.by_title_normalized(&title("bon bock"), Query::Movies)), [4]);
+    assert_eq!(
+      ids(imdb.by_title_normalized(&title("sortie de l'usine lumiere a lyon"), Query::Movies)),
+      [10]
+    );
+    assert!(imdb
+      .by_title_normalized(&title("Le clown et ses chiens"), Query::Movies)
+      .is_empty());
+
+    let year = |name: &str, year| ids(imdb.by_title_and_year_normalized(&title(name), year, Query::Movies));
+    assert_eq!(year("clown et ses chiens", 1892), [2]);
+    assert!(year("clown et ses chiens", 1893).is_empty());
+
+    let primary = imdb.with_options(SearchOptions::new().scope(SearchScope::PrimaryTitles));
+    assert!(primary
+      .by_title_normalized(&title("sortie de l'usine lumiere a lyon"), Query::Movies)
+      .is_empty());
+
+    let english = imdb.with_options(SearchOptions::new().stop_words(StopWords::new(&[Language::English])));
+    assert!(english
+      .by_title_normalized(&title("Clown et ses chiens"), Query::Movies)
+      .is_empty());
+    assert_eq!(ids(english.by_title_normalized(&title("The Le clown et ses chiens"), Query::Movies)), [2]);
+  }
+
+  #[test]
+  fn by_keywords_ranked() {
+    let imdb = Service::from_tsv_readers(make_basics_reader(), make_ratings_reader()).unwrap();
+    let keywords = ["le", "clown"].map(|kw| SearchString::try_from(kw).unwrap());
+    let ranked = imdb.by_keywords_ranked(&keywords, Query::Movies);
+    assert_eq!(ranked.len(), 1);
+    assert_eq!(ranked[0].title().primary_title(), "Le clown et ses chiens");
+    assert!(ranked[0].score() >= 2.0 && ranked[0].score() < 3.0);
+
+    let keywords = [SearchString::try_from("c").unwrap()];
+    let ranked = imdb.by_keywords_ranked(&keywords, Query::Movies);
+    assert_eq!(ranked.len(), imdb.by_keywords(&keywords, Query::Movies).len());
+    assert!(ranked.windows(2).all(|pair| pair[0].score() >= pair[1].score()));
+  }
+
+  #[test]
+  fn iter_queries() {
+    let imdb = Service::from_tsv_readers(make_basics_reader(), make_ratings_reader()).unwrap();
+    let keywords = [SearchString::try_from("ine").unwrap()];
+    let mut ids: Vec<_> = imdb.iter_by_keywords(&keywords, Query::Movies).map(Title::title_id).collect();
+    ids.sort_unstable_by_key(|id| id.as_usize());
+    let expected: Vec<_> = imdb
+      .by_keywords(&keywords, Query::Movies)
+      .into_iter()
+      .map(Title::title_id)
+      .collect();
+    assert_eq!(ids, expected);
+
+    assert_eq!(imdb.iter_by_keywords(&keywords, Query::Movies).take(2).count(), 2);
+    assert_eq!(imdb.iter_by_keywords_and_year(&keywords, 1894, Query::Movies).count(), 3);
+
+    let title = SearchString::try_from("carmencita").unwrap();
+    assert_eq!(imdb.iter_by_title(&title, Query::Movies).count(), 1);
+    assert_eq!(imdb.iter_by_title_and_year(&title, 1894, Query::Movies).count(), 1);
+
+    let filter = Filter::new().released_since(1895);
+    assert_eq!(
+      imdb.iter_by_filter(&filter, Query::Movies).count(),
+      imdb.by_filter(&filter, Query::Movies).len()
+    );
+
+    assert_eq!(imdb.iter_movies().count(), imdb.n_titles(Query::Movies));
+    assert_eq!(imdb.iter_series().count(), 0);
+    assert!(imdb.iter_movies().any(|title| title.primary_title() == "Carmencita"));
+  }
+
+  #[test]
+  #[cfg(feature = "fs")]
+  fn from_cache_only() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    assert!(Service::from_cache_only(dir.path()).is_err());
+
+    Service::import_db_files(
+      make_ratings_reader(),
+      make_basics_reader(),
+      &dir.path().join(MOVIES_DB_FILENAME),
+      &dir.path().join(SERIES_DB_FILENAME),
+      Compression::None,
+    )
+    .unwrap();
+
+    let imdb = Service::from_cache_only(dir.path()).unwrap();
+    assert_ne!(imdb.built_at(), SystemTime::UNIX_EPOCH);
+    assert_eq!(imdb.n_titles(Query::Movies), 11);
+
+    fs::write(dir.path().join(SERIES_DB_FILENAME), b"damaged").unwrap();
+    assert!(matches!(Service::from_cache_only(dir.path()), Err(Error::DbFile(_))));
   }
 }
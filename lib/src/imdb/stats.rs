@@ -0,0 +1,215 @@
+#![warn(clippy::all)]
+
+//! Aggregated statistics of a collection of titles, e.g. of the titles of a scanned library.
+
+use std::collections::BTreeMap;
+
+use crate::imdb::genre::Genre;
+use crate::imdb::title::Title;
+
+use fnv::{FnvHashMap, FnvHashSet};
+use serde::{Serialize, Serializer};
+
+/// Number of titles per tenth of the rating scale, e.g. ratings from 70 to 79 out of 100 count
+/// towards 7. The perfect rating of 100 counts towards 9.
+type RatingHistogram = [usize; 10];
+
+/// Serialize the genre counts as a map from genre to count, keeping their order.
+fn serialize_genres<S: Serializer>(genres: &[(Genre, usize)], serializer: S) -> Result<S::Ok, S::Error> {
+  serializer.collect_map(genres.iter().map(|(genre, count)| (genre.to_string(), count)))
+}
+
+/// Statistics of a collection of titles.
+#[derive(Debug, Clone, Serialize)]
+pub struct Stats<'a, 'storage> {
+  titles: usize,
+  rated: usize,
+  average_rating: Option<f64>,
+  ratings: RatingHistogram,
+  #[serde(serialize_with = "serialize_genres")]
+  genres: Vec<(Genre, usize)>,
+  runtime_minutes: u64,
+  decades: BTreeMap<u16, usize>,
+  best: Vec<&'a Title<'storage>>,
+  worst: Vec<&'a Title<'storage>>,
+}
+
+impl<'a, 'storage> Stats<'a, 'storage> {
+  /// Compute the statistics of a collection of titles. Titles that appear several times are
+  /// only counted once.
+  ///
+  /// # Arguments
+  ///
+  /// * `titles` - The titles of the collection.
+  /// * `n` - The number of best and worst rated titles to keep.
+  pub fn new(titles: impl IntoIterator<Item = &'a Title<'storage>>, n: usize) -> Self {
+    let mut seen = FnvHashSet::default();
+    let titles: Vec<&Title> = titles
+      .into_iter()
+      .filter(|title| seen.insert(title.title_id().as_usize()))
+      .collect();
+
+    let mut ratings = RatingHistogram::default();
+    let mut genres: FnvHashMap<Genre, usize> = FnvHashMap::default();
+    let mut decades = BTreeMap::new();
+    let mut runtime_minutes = 0;
+    let mut rated = Vec::new();
+
+    for &title in &titles {
+      if let Some(rating) = title.rating() {
+        ratings[usize::from(rating.rating() / 10).min(9)] += 1;
+        rated.push(title);
+      }
+
+      for genre in title.genres().iter() {
+        *genres.entry(genre).or_default() += 1;
+      }
+
+      if let Some(year) = title.start_year() {
+        *decades.entry(year / 10 * 10).or_default() += 1;
+      }
+
+      if let Some(runtime) = title.runtime() {
+        runtime_minutes += runtime.as_secs() / 60;
+      }
+    }
+
+    let average_rating = if rated.is_empty() {
+      None
+    } else {
+      let sum: u64 = rated
+        .iter()
+        .filter_map(|title| title.rating())
+        .map(|rating| u64::from(rating.rating()))
+        .sum();
+      Some(sum as f64 / rated.len() as f64)
+    };
+
+    // Best first, more votes breaking ties.
+    rated.sort_by_key(|title| {
+      title
+        .rating()
+        .map(|rating| std::cmp::Reverse((rating.rating(), rating.votes())))
+    });
+    let best = rated.iter().take(n).copied().collect();
+    let worst = rated.iter().rev().take(n).copied().collect();
+
+    let mut genres: Vec<(Genre, usize)> = genres.into_iter().collect();
+    genres.sort_by(|(genre1, count1), (genre2, count2)| {
+      count2.cmp(count1).then_with(|| genre1.to_string().cmp(&genre2.to_string()))
+    });
+
+    Self {
+      titles: titles.len(),
+      rated: rated.len(),
+      average_rating,
+      ratings,
+      genres,
+      runtime_minutes,
+      decades,
+      best,
+      worst,
+    }
+  }
+
+  /// The number of titles.
+  pub fn titles(&self) -> usize {
+    self.titles
+  }
+
+  /// The number of titles that have a rating.
+  pub fn rated(&self) -> usize {
+    self.rated
+  }
+
+  /// The average rating of the rated titles, out of 100.
+  pub fn average_rating(&self) -> Option<f64> {
+    self.average_rating
+  }
+
+  /// The number of titles per tenth of the rating scale, from 0 (ratings from 0 to 9 out of
+  /// 100) to 9 (ratings from 90 to 100).
+  pub fn ratings(&self) -> &[usize; 10] {
+    &self.ratings
+  }
+
+  /// The number of titles per genre, most common first.
+  pub fn genres(&self) -> &[(Genre, usize)] {
+    &self.genres
+  }
+
+  /// The total runtime of the titles, in minutes.
+  pub fn runtime_minutes(&self) -> u64 {
+    self.runtime_minutes
+  }
+
+  /// The number of titles per decade, by the first year of the decade (e.g. 1990).
+  pub fn decades(&self) -> &BTreeMap<u16, usize> {
+    &self.decades
+  }
+
+  /// The best rated titles, best first.
+  pub fn best(&self) -> &[&'a Title<'storage>] {
+    &self.best
+  }
+
+  /// The worst rated titles, worst first.
+  pub fn worst(&self) -> &[&'a Title<'storage>] {
+    &self.worst
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::imdb::genre::{Genre, Genres};
+  use crate::imdb::stats::Stats;
+  use crate::imdb::title::Title;
+  use crate::imdb::title_id::TitleId;
+  use crate::imdb::title_type::TitleType;
+
+  fn title(id: &'static str, year: u16, rating: Option<u8>, genre: Genre) -> Title<'static> {
+    let mut genres = Genres::default();
+    genres.add(genre);
+
+    let title = Title::new(TitleId::try_from(id).unwrap(), TitleType::Movie, id)
+      .with_start_year(year)
+      .with_runtime_minutes(100)
+      .with_genres(genres);
+
+    match rating {
+      Some(rating) => title.with_rating(rating, 1000),
+      None => title,
+    }
+  }
+
+  #[test]
+  fn test_stats() {
+    let titles = [
+      title("tt0000001", 1994, Some(85), Genre::Drama),
+      title("tt0000002", 1999, Some(72), Genre::Action),
+      title("tt0000003", 2008, Some(100), Genre::Drama),
+      title("tt0000004", 2010, None, Genre::Comedy),
+    ];
+
+    let stats = Stats::new(titles.iter().chain(&titles[..1]), 2);
+    assert_eq!(stats.titles(), 4);
+    assert_eq!(stats.rated(), 3);
+    assert_eq!(stats.average_rating(), Some(257.0 / 3.0));
+    assert_eq!(stats.ratings(), &[0, 0, 0, 0, 0, 0, 0, 1, 1, 1]);
+    assert_eq!(stats.genres(), &[(Genre::Drama, 2), (Genre::Action, 1), (Genre::Comedy, 1)]);
+    assert_eq!(stats.runtime_minutes(), 400);
+    assert_eq!(stats.decades().iter().collect::<Vec<_>>(), [(&1990, &2), (&2000, &1), (&2010, &1)]);
+
+    let ids = |titles: &[&Title]| titles.iter().map(|title| title.title_id().to_string()).collect::<Vec<_>>();
+    assert_eq!(ids(stats.best()), ["tt0000003", "tt0000001"]);
+    assert_eq!(ids(stats.worst()), ["tt0000002", "tt0000001"]);
+  }
+
+  #[test]
+  fn test_empty() {
+    let stats = Stats::new([], 10);
+    assert_eq!(stats.titles(), 0);
+    assert_eq!(stats.average_rating(), None);
+    assert!(stats.best().is_empty());
+  }
+}
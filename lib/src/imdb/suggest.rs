@@ -0,0 +1,165 @@
+#![warn(clippy::all)]
+
+//! Online title suggestions from IMDB, for titles that are missing from the local database
+//! (e.g. titles released after the last IMDB dump).
+
+use crate::imdb::title_type::TitleType;
+
+use reqwest::blocking::Client;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+/// Errors when fetching suggestions.
+#[derive(Debug, thiserror::Error)]
+#[error("Error fetching IMDB suggestions")]
+pub enum Error {
+  /// Networking error.
+  #[error("Networking error: {0}")]
+  Net(#[from] reqwest::Error),
+  /// URL parsing error.
+  #[error("Error parsing URL: {0}")]
+  UrlParsing(#[from] url::ParseError),
+  /// The suggestion URL cannot have path segments.
+  #[error("Invalid suggestion URL")]
+  Url,
+  /// Response parsing error.
+  #[error("Error parsing suggestions: {0}")]
+  Json(#[from] serde_json::Error),
+}
+
+const SUGGESTION_URL: &str = "https://v3.sg.media-imdb.com/suggestion/x";
+
+#[derive(Deserialize)]
+struct RawSuggestions {
+  #[serde(default)]
+  d: Vec<RawSuggestion>,
+}
+
+#[derive(Deserialize)]
+struct RawSuggestion {
+  id: String,
+  l: Option<String>,
+  y: Option<u16>,
+  qid: Option<String>,
+}
+
+/// A title suggested by IMDB.
+///
+/// Suggestions do not carry ratings, so they cannot be ranked against titles from the local
+/// database.
+#[derive(Debug, Clone, Serialize)]
+pub struct Suggestion {
+  title_id: String,
+  primary_title: String,
+  start_year: Option<u16>,
+  title_type: Option<TitleType>,
+}
+
+impl Suggestion {
+  /// The IMDB ID of the title.
+  pub fn title_id(&self) -> &str {
+    &self.title_id
+  }
+
+  /// The title name.
+  pub fn primary_title(&self) -> &str {
+    &self.primary_title
+  }
+
+  /// The year the title was released or started airing in, if known.
+  pub fn start_year(&self) -> Option<u16> {
+    self.start_year
+  }
+
+  /// The type of the title, if known.
+  pub fn title_type(&self) -> Option<TitleType> {
+    self.title_type
+  }
+
+  /// Fetch suggestions for the given search terms.
+  ///
+  /// Only movies and series are returned, other suggestions (e.g. people) are dropped.
+  ///
+  /// # Arguments
+  ///
+  /// * `terms` - Title name or IMDB ID to search for.
+  pub fn fetch(terms: &str) -> Result<Vec<Self>, Error> {
+    let mut url = Url::parse(SUGGESTION_URL)?;
+    url
+      .path_segments_mut()
+      .map_err(|_| Error::Url)?
+      .push(&format!("{}.json", terms.to_lowercase()));
+
+    let client = Client::builder().build()?;
+    let body = client.get(url).send()?.error_for_status()?.text()?;
+    Self::from_json(&body)
+  }
+
+  /// Parse a suggestions response.
+  ///
+  /// # Arguments
+  ///
+  /// * `json` - Body of the suggestions response.
+  fn from_json(json: &str) -> Result<Vec<Self>, Error> {
+    let raw: RawSuggestions = serde_json::from_str(json)?;
+
+    let suggestions = raw
+      .d
+      .into_iter()
+      .filter(|raw| raw.id.starts_with("tt"))
+      .filter_map(|raw| {
+        let title_type = raw.qid.as_deref().and_then(|qid| qid.parse::<TitleType>().ok());
+
+        if let Some(title_type) = title_type {
+          if !title_type.is_movie() && !title_type.is_series() {
+            return None;
+          }
+        }
+
+        Some(Self { title_id: raw.id, primary_title: raw.l?, start_year: raw.y, title_type })
+      })
+      .collect();
+
+    Ok(suggestions)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use indoc::indoc;
+
+  #[test]
+  fn from_json() {
+    let json = indoc! {r#"
+      {"d": [
+        {"id": "tt0317248", "l": "City of God", "y": 2002, "q": "feature", "qid": "movie"},
+        {"id": "nm0000138", "l": "Leonardo DiCaprio", "s": "Actor"},
+        {"id": "tt0903747", "l": "Breaking Bad", "y": 2008, "q": "TV series", "qid": "tvSeries"},
+        {"id": "tt0959621", "l": "Pilot", "y": 2008, "q": "TV episode", "qid": "tvEpisode"},
+        {"id": "tt9999999", "l": "Unknown Kind"}
+      ], "q": "query", "v": 1}
+    "#};
+
+    let suggestions = Suggestion::from_json(json).unwrap();
+    assert_eq!(suggestions.len(), 3);
+
+    assert_eq!(suggestions[0].title_id(), "tt0317248");
+    assert_eq!(suggestions[0].primary_title(), "City of God");
+    assert_eq!(suggestions[0].start_year(), Some(2002));
+    assert_eq!(suggestions[0].title_type(), Some(TitleType::Movie));
+
+    assert_eq!(suggestions[1].title_id(), "tt0903747");
+    assert_eq!(suggestions[1].title_type(), Some(TitleType::TvSeries));
+
+    assert_eq!(suggestions[2].title_id(), "tt9999999");
+    assert_eq!(suggestions[2].start_year(), None);
+    assert_eq!(suggestions[2].title_type(), None);
+  }
+
+  #[test]
+  fn empty() {
+    assert!(Suggestion::from_json(r#"{"q": "query", "v": 1}"#).unwrap().is_empty());
+  }
+}
@@ -2,10 +2,11 @@
 
 use crate::imdb::genre::{Genre, Genres};
 use crate::imdb::ratings::{Rating, Ratings};
-use crate::imdb::title_header::TitleHeader;
+use crate::imdb::title_header::{TitleHeader, MAX_RUNTIME_MINUTES, MAX_VOTES};
 use crate::imdb::title_id::TitleId;
 use crate::imdb::title_type::TitleType;
 use crate::imdb::tokens;
+use crate::imdb::tsv_import::ImportStats;
 use crate::iter_next;
 
 use std::array::TryFromSliceError;
@@ -101,7 +102,7 @@ impl Hash for Title<'_> {
 
 impl<'storage> Title<'storage> {
   /// Returns the id of the title.
-  pub fn title_id(&self) -> &TitleId {
+  pub fn title_id(&self) -> &TitleId<'_> {
     &self.title_id
   }
 
@@ -130,6 +131,11 @@ impl<'storage> Title<'storage> {
     self.header.start_year()
   }
 
+  /// Returns the year the title ended, for series that are no longer running.
+  pub fn end_year(&self) -> Option<u16> {
+    self.header.end_year()
+  }
+
   /// Returns the duration of the title.
   pub fn runtime(&self) -> Option<Duration> {
     self
@@ -148,13 +154,137 @@ impl<'storage> Title<'storage> {
     self.header.rating()
   }
 
+  /// Create a title without a release year, runtime, genres or rating, for providers of
+  /// titles other than the IMDB dataset (see [TitleProvider](crate::provider::TitleProvider)).
+  ///
+  /// # Arguments
+  ///
+  /// * `title_id` - IMDB ID of the title.
+  /// * `title_type` - Type of the title.
+  /// * `primary_title` - The primary title in English.
+  pub fn new(title_id: TitleId<'storage>, title_type: TitleType, primary_title: &'storage str) -> Self {
+    let header =
+      TitleHeader::new_version_2(false, false, None, None, None, None, title_type, Genres::default());
+    Self { header, title_id, primary_title, original_title: None }
+  }
+
+  /// Rebuild the header of the title with the given values, keeping the others.
+  fn with_header(
+    mut self,
+    runtime_minutes: Option<u32>,
+    start_year: Option<u16>,
+    rating: Option<Rating>,
+    genres: Genres,
+  ) -> Self {
+    self.header = TitleHeader::new_version_2(
+      self.original_title.is_some(),
+      self.is_adult(),
+      runtime_minutes,
+      start_year,
+      self.end_year(),
+      rating,
+      self.title_type(),
+      genres,
+    );
+    self
+  }
+
+  /// Set the title in its original language.
+  ///
+  /// # Arguments
+  ///
+  /// * `original_title` - The original title, ignored if it is the same as the primary one.
+  pub fn with_original_title(mut self, original_title: &'storage str) -> Self {
+    if original_title.to_lowercase() != self.primary_title.to_lowercase() {
+      self.original_title = Some(original_title);
+    }
+
+    let header = &self.header;
+    self.with_header(header.runtime_minutes(), header.start_year(), header.rating(), header.genres())
+  }
+
+  /// Set the release year.
+  ///
+  /// # Arguments
+  ///
+  /// * `year` - The release year, ignored if it is before 1801 or after 2311, which cannot
+  ///   be stored.
+  pub fn with_start_year(self, year: u16) -> Self {
+    let year = (1801..=2311).contains(&year).then_some(year);
+    let header = &self.header;
+    self.with_header(header.runtime_minutes(), year, header.rating(), header.genres())
+  }
+
+  /// Set the year the title ended.
+  ///
+  /// # Arguments
+  ///
+  /// * `year` - The end year, ignored if it is before 1801 or after 2311, which cannot be
+  ///   stored.
+  pub fn with_end_year(mut self, year: u16) -> Self {
+    let year = (1801..=2311).contains(&year).then_some(year);
+    let header = &self.header;
+    self.header = TitleHeader::new_version_2(
+      self.original_title.is_some(),
+      header.is_adult(),
+      header.runtime_minutes(),
+      header.start_year(),
+      year,
+      header.rating(),
+      header.title_type(),
+      header.genres(),
+    );
+    self
+  }
+
+  /// Set the runtime.
+  ///
+  /// # Arguments
+  ///
+  /// * `minutes` - The runtime in minutes, capped at 1048575 which is the most that can be
+  ///   stored.
+  pub fn with_runtime_minutes(self, minutes: u32) -> Self {
+    let header = &self.header;
+    let minutes = minutes.min(MAX_RUNTIME_MINUTES);
+    self.with_header(Some(minutes), header.start_year(), header.rating(), header.genres())
+  }
+
+  /// Set the genres.
+  ///
+  /// # Arguments
+  ///
+  /// * `genres` - The genres associated with the title.
+  pub fn with_genres(self, genres: Genres) -> Self {
+    let header = &self.header;
+    self.with_header(header.runtime_minutes(), header.start_year(), header.rating(), genres)
+  }
+
+  /// Set the rating.
+  ///
+  /// # Arguments
+  ///
+  /// * `rating` - The average rating out of 100, capped at 100.
+  /// * `votes` - The number of votes, capped at 268435455 which is the most that can be
+  ///   stored.
+  pub fn with_rating(self, rating: u8, votes: u32) -> Self {
+    let rating = Rating::new(rating.min(100), votes.min(MAX_VOTES));
+    let header = &self.header;
+    self.with_header(header.runtime_minutes(), header.start_year(), Some(rating), header.genres())
+  }
+
   /// Reads a title from tab separated values and returns it inside a TsvAction struct
   ///
   /// # Arguments
   ///
   /// * `line` - A title as tab separated values.
   /// * `ratings` - Ratings struct containing the ratings of the titles.
-  pub(crate) fn from_tsv(line: &'storage [u8], ratings: &Ratings) -> Result<TsvAction<Self>, Error> {
+  /// * `stats` - Statistics of the import, counting skipped titles and the genres and title
+  ///   types that are not known, which are read as [Genre::Other] and [TitleType::Other].
+  pub(crate) fn from_tsv(
+    line: &'storage [u8],
+    ratings: &Ratings,
+    stats: &mut ImportStats,
+  ) -> Result<TsvAction<Self>, Error> {
     let mut columns = line.split(|&b| b == tokens::TAB);
 
     let title_id = TitleId::try_from(iter_next!(columns)?)?;
@@ -162,13 +292,17 @@ impl<'storage> Title<'storage> {
     let title_type = {
       let title_type = iter_next!(columns)?;
       let title_type = unsafe { std::str::from_utf8_unchecked(title_type) };
-      TitleType::from_str(title_type).map_err(|_| Error::TitleType)?
+      TitleType::from_str(title_type).unwrap_or_else(|()| {
+        stats.unknown_title_type(title_type);
+        TitleType::Other
+      })
     };
 
     let is_movie = title_type.is_movie();
     let is_series = title_type.is_series();
 
     if !is_movie && !is_series {
+      stats.skip_type();
       return Ok(TsvAction::Skip);
     }
 
@@ -190,6 +324,7 @@ impl<'storage> Title<'storage> {
     };
 
     if is_adult {
+      stats.skip_adult();
       return Ok(TsvAction::Skip);
     }
 
@@ -201,7 +336,7 @@ impl<'storage> Title<'storage> {
       }
     };
 
-    let _end_year = {
+    let end_year = {
       let end_year = iter_next!(columns)?;
       match end_year {
         tokens::NOT_AVAIL => None,
@@ -213,7 +348,11 @@ impl<'storage> Title<'storage> {
       let runtime_minutes = iter_next!(columns)?;
       match runtime_minutes {
         tokens::NOT_AVAIL => None,
-        runtime_minutes => Some(atoi::<u16>(runtime_minutes).ok_or(Error::RuntimeMinutes)?),
+        runtime_minutes => Some(
+          atoi::<u32>(runtime_minutes)
+            .ok_or(Error::RuntimeMinutes)?
+            .min(MAX_RUNTIME_MINUTES),
+        ),
       }
     };
 
@@ -225,7 +364,10 @@ impl<'storage> Title<'storage> {
         let genres = genres.split(|&b| b == tokens::COMMA);
         for genre in genres {
           let genre = unsafe { std::str::from_utf8_unchecked(genre) };
-          let genre = Genre::from_str(genre).map_err(|_| Error::Genre(genre.to_owned()))?;
+          let genre = Genre::from_str(genre).unwrap_or_else(|()| {
+            stats.unknown_genre(genre);
+            Genre::Other
+          });
           result.add(genre);
         }
       }
@@ -233,13 +375,16 @@ impl<'storage> Title<'storage> {
       result
     };
 
-    let rating = ratings.get(&title_id.as_usize()).copied();
+    let rating = ratings
+      .get(&title_id.as_usize())
+      .map(|rating| Rating::new(rating.rating(), rating.votes().min(MAX_VOTES)));
 
-    let header = TitleHeader::new_version_0(
+    let header = TitleHeader::new_version_2(
       original_title.is_some(),
       is_adult,
       runtime_minutes,
       start_year,
+      end_year,
       rating,
       title_type,
       genres,
@@ -281,7 +426,12 @@ impl<'storage> Title<'storage> {
   }
 
   /// Reads a title from its binary representation and returns it inside a Result
+  ///
+  /// Headers of all versions are decoded, older versions lacking the values they did not
+  /// have room for.
+  ///
   /// # Arguments
+  ///
   /// * `source` - Title to be read as binary
   pub(crate) fn from_binary(source: &mut &'storage [u8]) -> Result<Self, Error> {
     if (*source).len() < 23 {
@@ -296,42 +446,28 @@ impl<'storage> Title<'storage> {
       return Err(crate::utils::tokens::Error::Eof)?;
     }
 
-    let header: [u8; 16] = source[..16].try_into()?;
+    let header: [u8; 16] = Self::take(source, 16)?.try_into()?;
     let header = TitleHeader::from(header);
 
-    *source = &source[16..];
-
-    let title_id_len: [u8; 1] = source[..1].try_into()?;
+    let title_id_len: [u8; 1] = Self::take(source, 1)?.try_into()?;
     let title_id_len = u8::from_le_bytes(title_id_len) as usize;
 
-    *source = &source[1..];
-
-    let title_id = &source[..title_id_len];
+    let title_id = Self::take(source, title_id_len)?;
     let title_id = TitleId::try_from(title_id)?;
 
-    *source = &source[title_id_len..];
-
-    let primary_title_len: [u8; 2] = source[..2].try_into()?;
+    let primary_title_len: [u8; 2] = Self::take(source, 2)?.try_into()?;
     let primary_title_len = u16::from_le_bytes(primary_title_len) as usize;
 
-    *source = &source[2..];
-
-    let primary_title = &source[..primary_title_len];
+    let primary_title = Self::take(source, primary_title_len)?;
     let primary_title = unsafe { std::str::from_utf8_unchecked(primary_title) };
 
-    *source = &source[primary_title_len..];
-
     let original_title = if header.has_original_title() {
-      let original_title_len: [u8; 2] = source[..2].try_into()?;
+      let original_title_len: [u8; 2] = Self::take(source, 2)?.try_into()?;
       let original_title_len = u16::from_le_bytes(original_title_len) as usize;
 
-      *source = &source[2..];
-
-      let original_title = &source[..original_title_len];
+      let original_title = Self::take(source, original_title_len)?;
       let original_title = unsafe { std::str::from_utf8_unchecked(original_title) };
 
-      *source = &source[original_title_len..];
-
       Some(original_title)
     } else {
       None
@@ -339,6 +475,21 @@ impl<'storage> Title<'storage> {
 
     Ok(Self { header, title_id, primary_title, original_title })
   }
+
+  /// Splits `len` bytes off the front of `source`, or fails if it is truncated.
+  ///
+  /// # Arguments
+  ///
+  /// * `source` - Binary to split the bytes off.
+  /// * `len` - Number of bytes to split off.
+  fn take(source: &mut &'storage [u8], len: usize) -> Result<&'storage [u8], Error> {
+    let Some((bytes, rest)) = source.split_at_checked(len) else {
+      return Err(crate::utils::tokens::Error::Eof)?;
+    };
+
+    *source = rest;
+    Ok(bytes)
+  }
 }
 
 #[cfg(test)]
@@ -348,6 +499,7 @@ mod test_title {
   use crate::imdb::ratings::Ratings;
   use crate::imdb::title::Title;
   use crate::imdb::title_type::TitleType;
+  use crate::imdb::tsv_import::ImportStats;
 
   #[test]
   fn test_title() {
@@ -357,6 +509,7 @@ mod test_title {
     let title = Title::from_tsv(
       b"tt0000001\tshort\tCarmencita\tCarmencita\t0\t1894\t\\N\t1\tDocumentary,Short",
       &ratings,
+      &mut ImportStats::default(),
     )
     .unwrap();
     let title: Option<Title> = title.into();
@@ -383,5 +536,31 @@ mod test_title {
     let title_parsed = Title::from_binary(&mut binary.as_ref()).unwrap();
 
     assert_eq!(title, title_parsed);
+
+    for len in [20, 24, binary.len() - 1] {
+      assert!(Title::from_binary(&mut &binary[..len]).is_err());
+    }
+  }
+  #[test]
+  fn test_end_year() {
+    let ratings = Ratings::default();
+    let title = Title::from_tsv(
+      b"tt0903747\ttvSeries\tBreaking Bad\tBreaking Bad\t0\t2008\t2013\t49\tCrime,Drama,Thriller",
+      &ratings,
+      &mut ImportStats::default(),
+    )
+    .unwrap();
+    let title: Option<Title> = title.into();
+    let title = title.unwrap();
+    assert_eq!(title.start_year(), Some(2008));
+    assert_eq!(title.end_year(), Some(2013));
+
+    let mut binary = Vec::new();
+    title.write_binary(&mut binary).unwrap();
+    assert_eq!(Title::from_binary(&mut binary.as_ref()).unwrap().end_year(), Some(2013));
+
+    let title = title.with_rating(95, 2_000_000).with_start_year(2009);
+    assert_eq!(title.end_year(), Some(2013));
+    assert_eq!(title.with_end_year(1700).end_year(), None);
   }
 }
@@ -29,17 +29,39 @@ use std::ops::Deref;
 ///   * Genres:                 32 bits
 ///
 /// * 3 bytes are reserved for later version use.
+///
+/// # Header version 1 uses 9 bits of the reserved bytes of version 0 for:
+///
+/// * End Year:                 9  bits (same encoding as the Year; 0 means unknown or that
+///   the title is still running, only series have one)
+///
+/// Headers of version 0 are read as titles without an end year.
+///
+/// # Header version 2 uses 9 more bits of the reserved bytes for the high bits of:
+///
+/// * Number of Rating Votes:   5  bits (above the 23 bits of version 0, for 28 bits)
+/// * Runtime in Minutes:       4  bits (above the 16 bits of version 0, for 20 bits)
+///
+/// Headers of older versions are read with the high bits as 0. Titles are always written
+/// with the latest version.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub(crate) struct TitleHeader(u128);
 
+/// The most votes a header can hold.
+pub(crate) const MAX_VOTES: u32 = (1 << 28) - 1;
+
+/// The longest runtime in minutes a header can hold.
+pub(crate) const MAX_RUNTIME_MINUTES: u32 = (1 << 20) - 1;
+
 impl Serialize for TitleHeader {
   fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
   where
     S: Serializer,
   {
-    let mut state = serializer.serialize_struct("TitleHeader", 5)?;
+    let mut state = serializer.serialize_struct("TitleHeader", 6)?;
     state.serialize_field("rating", &self.rating())?;
     state.serialize_field("start_year", &self.start_year())?;
+    state.serialize_field("end_year", &self.end_year())?;
     state.serialize_field("runtime", &self.runtime_minutes())?;
     state.serialize_field("genres", &self.genres())?;
     state.serialize_field("title_type", &self.title_type())?;
@@ -63,32 +85,32 @@ impl From<[u8; 16]> for TitleHeader {
 
 impl TitleHeader {
   /// Create a new TitleHeader with the given values
-  pub(crate) fn new_version_0(
+  #[allow(clippy::too_many_arguments)]
+  pub(crate) fn new_version_2(
     has_original_title: bool,
     is_adult: bool,
-    runtime_minutes: Option<u16>,
+    runtime_minutes: Option<u32>,
     start_year: Option<u16>,
+    end_year: Option<u16>,
     rating: Option<Rating>,
     title_type: TitleType,
     genres: Genres,
   ) -> Self {
-    let version = 0;
+    let version = 2;
     let has_original_title = u128::from(has_original_title);
     let is_adult = u128::from(is_adult);
 
     let runtime = if let Some(runtime) = runtime_minutes {
+      assert!(runtime <= MAX_RUNTIME_MINUTES);
       u128::from(runtime)
     } else {
       0
     };
 
-    let year = if let Some(year) = start_year {
-      assert!(year > 1800);
-      u128::from(year - 1800)
-    } else {
-      0
-    };
+    let year = encode_year(start_year);
+    let end_year = encode_year(end_year);
     let (rating, votes) = if let Some(rating) = rating {
+      assert!(rating.votes() <= MAX_VOTES);
       (u128::from(rating.rating()), u128::from(rating.votes()))
     } else {
       (0, 0)
@@ -101,20 +123,24 @@ impl TitleHeader {
     let header = version
       | (has_original_title << 6)
       | (is_adult << 7)
-      | (runtime << 8)
+      | ((runtime & 0xFFFF) << 8)
       | (year << 24)
       | (rating << 33)
-      | (votes << 40)
+      | ((votes & 0x7F_FFFF) << 40)
       | (title_type << 63)
-      | (genres << 68);
+      | (genres << 68)
+      | (end_year << 100)
+      | ((votes >> 23) << 109)
+      | ((runtime >> 16) << 114);
 
     Self(header)
   }
 
-  // fn version(&self) -> u8 {
-  //   let mask = 2_u128.pow(6) - 1;
-  //   (self.0 & mask) as u8
-  // }
+  /// Returns the version of the header
+  pub(crate) fn version(&self) -> u8 {
+    let mask = 2_u128.pow(6) - 1;
+    (self.0 & mask) as u8
+  }
 
   /// Returns true if the title has an original title
   pub(crate) fn has_original_title(&self) -> bool {
@@ -129,13 +155,13 @@ impl TitleHeader {
   }
 
   /// Returns an Option containing the duration of the title in minutes
-  pub(crate) fn runtime_minutes(&self) -> Option<u16> {
+  pub(crate) fn runtime_minutes(&self) -> Option<u32> {
     let mask = (2_u128.pow(16) - 1) << 8;
-    let value = (self.0 & mask) >> 8;
+    let value = ((self.0 & mask) >> 8) | (self.high_bits(114, 4) << 16);
     if value == 0 {
       None
     } else {
-      Some(value as u16)
+      Some(value as u32)
     }
   }
 
@@ -150,12 +176,28 @@ impl TitleHeader {
     }
   }
 
+  /// Returns the year the title ended inside an Option, which is None for titles that are
+  /// still running and for headers of version 0
+  pub(crate) fn end_year(&self) -> Option<u16> {
+    if self.version() < 1 {
+      return None;
+    }
+
+    let mask = (2_u128.pow(9) - 1) << 100;
+    let value = (self.0 & mask) >> 100;
+    if value == 0 {
+      None
+    } else {
+      Some(1800 + value as u16)
+    }
+  }
+
   /// Returns the rating of the title inside an Option
   pub(crate) fn rating(&self) -> Option<Rating> {
     let mask = (2_u128.pow(7) - 1) << 33;
     let rating = (self.0 & mask) >> 33;
     let mask = (2_u128.pow(23) - 1) << 40;
-    let votes = (self.0 & mask) >> 40;
+    let votes = ((self.0 & mask) >> 40) | (self.high_bits(109, 5) << 23);
 
     if votes == 0 {
       None
@@ -164,6 +206,21 @@ impl TitleHeader {
     }
   }
 
+  /// Returns the high bits that version 2 added to a value, which are 0 in older versions
+  ///
+  /// # Arguments
+  ///
+  /// * `offset` - The position of the high bits in the header.
+  /// * `len` - The number of high bits.
+  fn high_bits(&self, offset: u32, len: u32) -> u128 {
+    if self.version() < 2 {
+      return 0;
+    }
+
+    let mask = (2_u128.pow(len) - 1) << offset;
+    (self.0 & mask) >> offset
+  }
+
   /// Returns the type of the title
   pub(crate) fn title_type(&self) -> TitleType {
     let mask = (2_u128.pow(5) - 1) << 63;
@@ -179,12 +236,26 @@ impl TitleHeader {
   }
 }
 
+/// Encode a year as the number of years since 1800, or 0 if it is unknown.
+///
+/// # Arguments
+///
+/// * `year` - The year, which has to be after 1800.
+fn encode_year(year: Option<u16>) -> u128 {
+  if let Some(year) = year {
+    assert!(year > 1800);
+    u128::from(year - 1800)
+  } else {
+    0
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use crate::imdb::genre::Genre;
   use crate::imdb::genre::Genres;
   use crate::imdb::ratings::Rating;
-  use crate::imdb::title_header::TitleHeader;
+  use crate::imdb::title_header::{TitleHeader, MAX_RUNTIME_MINUTES, MAX_VOTES};
   use crate::imdb::title_type::TitleType;
 
   #[test]
@@ -194,11 +265,12 @@ mod tests {
     genres.add(Genre::Music);
     genres.add(Genre::War);
 
-    let header = TitleHeader::new_version_0(
+    let header = TitleHeader::new_version_2(
       true,
       true,
       Some(150),
       Some(1995),
+      Some(2003),
       Some(Rating::new(68, 1364)),
       TitleType::Movie,
       genres,
@@ -208,6 +280,8 @@ mod tests {
     assert!(header.is_adult());
     assert_eq!(header.runtime_minutes(), Some(150));
     assert_eq!(header.start_year(), Some(1995));
+    assert_eq!(header.end_year(), Some(2003));
+    assert_eq!(header.version(), 2);
     assert_eq!(header.rating().unwrap().rating(), 68);
     assert_eq!(header.rating().unwrap().votes(), 1364);
     assert_eq!(header.title_type(), TitleType::Movie);
@@ -217,4 +291,54 @@ mod tests {
     assert_eq!(genres_iter.next().unwrap(), Genre::Music);
     assert_eq!(genres_iter.next().unwrap(), Genre::War);
   }
+  #[test]
+  fn version_0() {
+    let header = TitleHeader::new_version_2(
+      false,
+      false,
+      None,
+      Some(2008),
+      Some(2013),
+      None,
+      TitleType::TvSeries,
+      Genres::default(),
+    );
+    let version_0 = TitleHeader(*header & !(2_u128.pow(6) - 1));
+    assert_eq!(version_0.version(), 0);
+    assert_eq!(version_0.start_year(), Some(2008));
+    assert_eq!(version_0.end_year(), None);
+  }
+
+  #[test]
+  fn version_2() {
+    let header = TitleHeader::new_version_2(
+      false,
+      false,
+      Some(MAX_RUNTIME_MINUTES),
+      Some(1994),
+      None,
+      Some(Rating::new(93, MAX_VOTES)),
+      TitleType::Movie,
+      Genres::default(),
+    );
+    assert_eq!(header.runtime_minutes(), Some(MAX_RUNTIME_MINUTES));
+    assert_eq!(header.rating().unwrap().votes(), MAX_VOTES);
+    assert_eq!(header.rating().unwrap().rating(), 93);
+    assert_eq!(header.start_year(), Some(1994));
+    assert_eq!(header.title_type(), TitleType::Movie);
+
+    let header = TitleHeader::new_version_2(
+      false,
+      false,
+      Some(70_000),
+      None,
+      None,
+      Some(Rating::new(88, 9_000_000)),
+      TitleType::Movie,
+      Genres::default(),
+    );
+    let version_1 = TitleHeader((*header & !(2_u128.pow(6) - 1)) | 1);
+    assert_eq!(version_1.runtime_minutes(), Some(70_000 & 0xFFFF));
+    assert_eq!(version_1.rating().unwrap().votes(), 9_000_000 & 0x7F_FFFF);
+  }
 }
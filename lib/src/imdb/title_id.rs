@@ -71,14 +71,14 @@ impl<'storage> TryFrom<&'storage [u8]> for TitleId<'storage> {
   type Error = Error;
 
   fn try_from(bytes: &'storage [u8]) -> Result<Self, Self::Error> {
-    if &bytes[0..2] != tokens::TT {
-      return Err(Error::Id(unsafe { std::str::from_utf8_unchecked(bytes) }.to_owned()));
+    if !bytes.starts_with(tokens::TT) {
+      return Err(Error::Id(String::from_utf8_lossy(bytes).into_owned()));
     }
 
     let num = &bytes[2..];
     let num_len = num.len();
     let num = match usize::from_radix_10(num) {
-      (val, len) if len == num_len => val,
+      (val, len) if len == num_len && len > 0 => val,
       _ => return Err(Error::IdNumber(unsafe { std::str::from_utf8_unchecked(bytes) }.to_owned())),
     };
 
@@ -119,6 +119,18 @@ mod tests {
     assert_eq!(id, Err(Error::IdNumber("ttabc".to_owned())));
   }
 
+  #[test]
+  fn short() {
+    let id = TitleId::try_from("t".as_bytes());
+    assert_eq!(id, Err(Error::Id("t".to_owned())));
+  }
+
+  #[test]
+  fn no_number() {
+    let id = TitleId::try_from("tt".as_bytes());
+    assert_eq!(id, Err(Error::IdNumber("tt".to_owned())));
+  }
+
   #[test]
   fn trailing_non_numeric() {
     let id = TitleId::try_from("tt0000001abc".as_bytes());
@@ -4,9 +4,8 @@ use derive_more::Display;
 use serde::Serialize;
 use std::{hash::Hash, str::FromStr};
 
-/// Encodes the 13 types of a title.
+/// Encodes the 13 types of a title, and a bucket for types that are not known.
 #[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy, Serialize)]
-#[display(fmt = "{}")]
 pub enum TitleType {
   // Games
   /// VideoGame.
@@ -50,6 +49,11 @@ pub enum TitleType {
   /// RadioSeries.
   #[display(fmt = "Radio Series")]
   RadioSeries = 12,
+
+  // Unknown
+  /// Title types added to the IMDB dumps after this version, which are neither movies nor
+  /// series.
+  Other = 13,
 }
 
 impl FromStr for TitleType {
@@ -86,7 +90,7 @@ impl TitleType {
   }
 
   /// Whether the title type refers to a movie.
-  pub(crate) fn is_movie(&self) -> bool {
+  pub fn is_movie(&self) -> bool {
     match self {
       // Games
       TitleType::VideoGame => false,
@@ -107,11 +111,14 @@ impl TitleType {
 
       // Radio
       TitleType::RadioSeries => false,
+
+      // Unknown
+      TitleType::Other => false,
     }
   }
 
   /// Whether the title type refers to a series.
-  pub(crate) fn is_series(&self) -> bool {
+  pub fn is_series(&self) -> bool {
     match self {
       // Games
       TitleType::VideoGame => false,
@@ -132,6 +139,9 @@ impl TitleType {
 
       // Radio
       TitleType::RadioSeries => false,
+
+      // Unknown
+      TitleType::Other => false,
     }
   }
 }
@@ -155,6 +165,7 @@ mod tests {
     assert_eq!(TitleType::TvSeries as u8, 10);
     assert_eq!(TitleType::TvMiniSeries as u8, 11);
     assert_eq!(TitleType::RadioSeries as u8, 12);
+    assert_eq!(TitleType::Other as u8, 13);
   }
 
   #[test]
@@ -172,6 +183,7 @@ mod tests {
     assert_eq!(TitleType::TvSeries, unsafe { TitleType::from(10) });
     assert_eq!(TitleType::TvMiniSeries, unsafe { TitleType::from(11) });
     assert_eq!(TitleType::RadioSeries, unsafe { TitleType::from(12) });
+    assert_eq!(TitleType::Other, unsafe { TitleType::from(13) });
   }
 
   #[test]
@@ -189,6 +201,7 @@ mod tests {
     assert!(unsafe { !TitleType::from(10).is_movie() });
     assert!(unsafe { !TitleType::from(11).is_movie() });
     assert!(unsafe { !TitleType::from(12).is_movie() });
+    assert!(unsafe { !TitleType::from(13).is_movie() });
   }
 
   #[test]
@@ -206,5 +219,6 @@ mod tests {
     assert!(unsafe { TitleType::from(10).is_series() });
     assert!(unsafe { TitleType::from(11).is_series() });
     assert!(unsafe { !TitleType::from(12).is_series() });
+    assert!(unsafe { !TitleType::from(13).is_series() });
   }
 }
@@ -0,0 +1,280 @@
+#![warn(clippy::all)]
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::imdb::db::Query;
+use crate::imdb::service::Service;
+use crate::imdb::title_id::TitleId;
+
+use fnv::FnvHashMap;
+use tracing::debug;
+
+/// Errors when handling the tombstones of retired title IDs.
+#[derive(Debug, thiserror::Error)]
+#[error("Retired title IDs error")]
+pub enum Error {
+  /// IO errors.
+  #[error("IO error: {0}")]
+  Io(#[from] io::Error),
+  /// Database timestamp is before the UNIX epoch.
+  #[error("Invalid database timestamp: {0}")]
+  Timestamp(#[from] std::time::SystemTimeError),
+  /// The file of known title IDs is truncated.
+  #[error("Known title IDs file `{}` is truncated", .0.display())]
+  Truncated(PathBuf),
+  /// A line of the file of retired title IDs is invalid.
+  #[error("Invalid line {1} in retired title IDs file `{}`", .0.display())]
+  InvalidLine(PathBuf, usize),
+}
+
+/// # The known IDs file is composed of:
+///
+/// * 8 bytes: Time the database was built, in seconds since the UNIX epoch (little-endian)
+/// * 4 bytes per title: Sorted title ID numbers (little-endian)
+const KNOWN_IDS_FILENAME: &str = "known-ids.bin";
+
+/// Lines of `ID<TAB>SECS`, where `SECS` is when the first database without the ID was built.
+const RETIRED_FILENAME: &str = "retired.tsv";
+
+/// Title IDs that were retired from the IMDB dataset, along with when they were retired.
+#[derive(Debug, Default)]
+pub struct Retired {
+  ids: FnvHashMap<u32, u64>,
+}
+
+impl Retired {
+  /// Returns when the given ID was retired, if it was.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - ID of the title to lookup.
+  pub fn retired_at(&self, id: &TitleId) -> Option<SystemTime> {
+    let id = u32::try_from(id.as_usize()).ok()?;
+    self.ids.get(&id).map(|&secs| UNIX_EPOCH + Duration::from_secs(secs))
+  }
+
+  /// Returns the number of retired IDs.
+  pub fn len(&self) -> usize {
+    self.ids.len()
+  }
+
+  /// Returns true if no IDs were retired.
+  pub fn is_empty(&self) -> bool {
+    self.ids.is_empty()
+  }
+
+  /// Reads retired IDs from their textual representation.
+  ///
+  /// # Arguments
+  ///
+  /// * `path` - Path of the file, for error reporting.
+  /// * `source` - Lines of retired IDs.
+  fn from_text(path: &Path, source: &str) -> Result<Self, Error> {
+    let mut ids = FnvHashMap::default();
+
+    for (index, line) in source.lines().enumerate().filter(|(_, line)| !line.is_empty()) {
+      let invalid = || Error::InvalidLine(path.to_owned(), index + 1);
+      let (id, secs) = line.split_once('\t').ok_or_else(invalid)?;
+      let id = id.parse().map_err(|_| invalid())?;
+      let secs = secs.parse().map_err(|_| invalid())?;
+      ids.insert(id, secs);
+    }
+
+    Ok(Self { ids })
+  }
+
+  /// Writes the retired IDs in their textual representation, sorted by ID.
+  fn to_text(&self) -> String {
+    let mut ids: Vec<_> = self.ids.iter().collect();
+    ids.sort_unstable();
+    ids.into_iter().map(|(id, secs)| format!("{id}\t{secs}\n")).collect()
+  }
+
+  /// Retires the IDs that were known but are missing from the current IDs, and revives the
+  /// retired IDs that are back in the current IDs. Returns the number of newly retired IDs.
+  ///
+  /// # Arguments
+  ///
+  /// * `known` - Sorted IDs of the previous database.
+  /// * `current` - Sorted IDs of the current database.
+  /// * `taken` - Time at which the current database was built.
+  fn update(&mut self, known: &[u32], current: &[u32], taken: u64) -> usize {
+    self.ids.retain(|id, _| current.binary_search(id).is_err());
+
+    let mut retired = 0;
+    for &id in known {
+      if current.binary_search(&id).is_err() && self.ids.insert(id, taken).is_none() {
+        retired += 1;
+      }
+    }
+
+    retired
+  }
+}
+
+/// A store of the title IDs that disappeared from the IMDB dataset between database builds.
+///
+/// IMDB occasionally retires or merges title IDs, which leaves title information files
+/// pointing at IDs that no longer exist. Keeping track of the retired IDs distinguishes them
+/// from IDs that were never valid.
+pub struct Tombstones {
+  dir: PathBuf,
+}
+
+impl Tombstones {
+  /// Create a store of retired title IDs in the given directory.
+  ///
+  /// # Arguments
+  ///
+  /// * `dir` - Directory to store the retired title IDs in.
+  pub fn new(dir: &Path) -> Self {
+    Self { dir: dir.to_owned() }
+  }
+
+  /// Record the title IDs in the given database, retiring the IDs of the previously recorded
+  /// database that are missing from it.
+  ///
+  /// Nothing is done if the same database build was already recorded. Returns the number of
+  /// newly retired IDs.
+  ///
+  /// # Arguments
+  ///
+  /// * `imdb` - The database to record the title IDs of.
+  pub fn record(&self, imdb: &Service) -> Result<usize, Error> {
+    let taken = imdb.built_at().duration_since(UNIX_EPOCH)?.as_secs();
+    let known_path = self.dir.join(KNOWN_IDS_FILENAME);
+
+    let known = match fs::read(&known_path) {
+      Ok(data) => Some(Self::known_from_binary(&known_path, &data)?),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+      Err(e) => return Err(Error::Io(e)),
+    };
+
+    if known.as_ref().is_some_and(|(known_taken, _)| *known_taken == taken) {
+      return Ok(0);
+    }
+
+    let mut current: Vec<u32> = imdb
+      .titles(Query::Movies)
+      .chain(imdb.titles(Query::Series))
+      .filter_map(|title| u32::try_from(title.title_id().as_usize()).ok())
+      .collect();
+    current.sort_unstable();
+    current.dedup();
+
+    fs::create_dir_all(&self.dir)?;
+
+    let mut retired_ids = self.load()?;
+    let retired = match &known {
+      Some((_, known)) => retired_ids.update(known, &current, taken),
+      None => 0,
+    };
+
+    let retired_path = self.dir.join(RETIRED_FILENAME);
+    let tmp_path = retired_path.with_extension("tmp");
+    fs::write(&tmp_path, retired_ids.to_text())?;
+    fs::rename(&tmp_path, &retired_path)?;
+
+    let tmp_path = known_path.with_extension("tmp");
+    let mut writer = BufWriter::new(File::create(&tmp_path)?);
+    writer.write_all(&taken.to_le_bytes())?;
+    for id in &current {
+      writer.write_all(&id.to_le_bytes())?;
+    }
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    fs::rename(&tmp_path, &known_path)?;
+
+    debug!("Recorded {} title IDs, {retired} of the previous ones were retired", current.len());
+    Ok(retired)
+  }
+
+  /// Load the retired title IDs.
+  pub fn load(&self) -> Result<Retired, Error> {
+    let path = self.dir.join(RETIRED_FILENAME);
+
+    match fs::read_to_string(&path) {
+      Ok(source) => Retired::from_text(&path, &source),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Retired::default()),
+      Err(e) => Err(Error::Io(e)),
+    }
+  }
+
+  /// Reads the known IDs file.
+  ///
+  /// # Arguments
+  ///
+  /// * `path` - Path of the file, for error reporting.
+  /// * `source` - Contents of the file.
+  fn known_from_binary(path: &Path, source: &[u8]) -> Result<(u64, Vec<u32>), Error> {
+    let truncated = || Error::Truncated(path.to_owned());
+    let (taken, ids) = source.split_first_chunk::<8>().ok_or_else(truncated)?;
+
+    if ids.len() % 4 != 0 {
+      return Err(truncated());
+    }
+
+    let ids = ids
+      .chunks_exact(4)
+      .map(|id| u32::from_le_bytes([id[0], id[1], id[2], id[3]]))
+      .collect();
+
+    Ok((u64::from_le_bytes(*taken), ids))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use crate::imdb::title_id::TitleId;
+  use crate::imdb::tombstones::{Retired, Tombstones};
+
+  #[test]
+  fn test_update() {
+    let mut retired = Retired::default();
+    assert_eq!(retired.update(&[1, 2, 3, 4], &[1, 3, 5], 42), 2);
+    assert_eq!(retired.update(&[1, 3, 5], &[1, 3, 5], 43), 0);
+    assert_eq!(retired.update(&[1, 3, 5], &[2, 3, 5], 44), 1);
+
+    assert_eq!(retired.len(), 2);
+    let id = TitleId::try_from("tt0000004").unwrap();
+    assert_eq!(
+      retired
+        .retired_at(&id)
+        .unwrap()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs(),
+      42
+    );
+    assert!(retired.retired_at(&TitleId::try_from("tt0000002").unwrap()).is_none());
+    assert!(retired.retired_at(&TitleId::try_from("tt0000001").unwrap()).is_some());
+  }
+
+  #[test]
+  fn test_text_roundtrip() {
+    let mut retired = Retired::default();
+    retired.update(&[7, 10, 300], &[10], 42);
+
+    let text = retired.to_text();
+    assert_eq!(text, "7\t42\n300\t42\n");
+
+    let parsed = Retired::from_text(Path::new("retired.tsv"), &text).unwrap();
+    assert_eq!(parsed.ids, retired.ids);
+    assert!(Retired::from_text(Path::new("retired.tsv"), "7 42\n").is_err());
+  }
+
+  #[test]
+  fn test_known_truncated() {
+    let path = Path::new("known-ids.bin");
+    assert!(Tombstones::known_from_binary(path, &[0; 7]).is_err());
+    assert!(Tombstones::known_from_binary(path, &[0; 10]).is_err());
+
+    let (taken, ids) = Tombstones::known_from_binary(path, &[42, 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0]).unwrap();
+    assert_eq!(taken, 42);
+    assert_eq!(ids, [7]);
+  }
+}
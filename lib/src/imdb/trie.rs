@@ -0,0 +1,276 @@
+#![warn(clippy::all)]
+
+//! An index of the words of title names for keyword queries, so that they only match the
+//! names containing all the keywords instead of scanning every name.
+
+use std::mem::size_of;
+
+use crate::utils::search::SearchString;
+
+use fnv::FnvHashSet;
+
+/// Suffixes of words are indexed up to this many bytes, which bounds the size of the trie.
+/// Longer keywords are looked up by their first bytes, and the names found are matched
+/// against the whole keywords afterwards.
+const MAX_SUFFIX_LEN: usize = 8;
+
+/// Keywords shorter than this many bytes match too many words to narrow queries down.
+const MIN_KEYWORD_LEN: usize = 3;
+
+/// A node of a [Trie], with its children sorted by byte.
+struct Node<V> {
+  children: Vec<(u8, u32)>,
+  values: Vec<V>,
+}
+
+impl<V> Default for Node<V> {
+  fn default() -> Self {
+    Self { children: Vec::new(), values: Vec::new() }
+  }
+}
+
+/// A trie of byte strings, with values at the end of the keys they were inserted with.
+pub(crate) struct Trie<V> {
+  nodes: Vec<Node<V>>,
+}
+
+impl<V> Default for Trie<V> {
+  fn default() -> Self {
+    Self { nodes: vec![Node::default()] }
+  }
+}
+
+impl<V: PartialEq + Copy> Trie<V> {
+  /// Insert a value at the end of a key, unless the value was the last one inserted there.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key to insert the value at.
+  /// * `value` - The value to insert.
+  pub(crate) fn insert(&mut self, key: &[u8], value: V) {
+    let mut index = 0;
+
+    for &byte in key {
+      let children = &self.nodes[index].children;
+      index = match children.binary_search_by_key(&byte, |&(child_byte, _)| child_byte) {
+        Ok(position) => children[position].1 as usize,
+        Err(position) => {
+          let child = self.nodes.len();
+          self.nodes.push(Node::default());
+          self.nodes[index].children.insert(position, (byte, child as u32));
+          child
+        }
+      };
+    }
+
+    let values = &mut self.nodes[index].values;
+    if values.last() != Some(&value) {
+      values.push(value);
+    }
+  }
+
+  /// Iterate over the values of all the keys starting with a prefix.
+  ///
+  /// # Arguments
+  ///
+  /// * `prefix` - The prefix of the keys.
+  pub(crate) fn prefixed(&self, prefix: &[u8]) -> impl Iterator<Item = &V> {
+    let mut stack = Vec::from_iter(self.find(prefix));
+
+    std::iter::from_fn(move || {
+      let index = stack.pop()?;
+      let node = &self.nodes[index];
+      stack.extend(node.children.iter().map(|&(_, child)| child as usize));
+      Some(node.values.iter())
+    })
+    .flatten()
+  }
+
+  /// The node at the end of a key, if any key starts with it.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key.
+  fn find(&self, key: &[u8]) -> Option<usize> {
+    key.iter().try_fold(0, |index, &byte| {
+      let children = &self.nodes[index].children;
+      children
+        .binary_search_by_key(&byte, |&(child_byte, _)| child_byte)
+        .ok()
+        .map(|position| children[position].1 as usize)
+    })
+  }
+
+  /// Estimate the bytes used by the trie.
+  pub(crate) fn bytes(&self) -> usize {
+    self.nodes.capacity() * size_of::<Node<V>>()
+      + self
+        .nodes
+        .iter()
+        .map(|node| {
+          node.children.capacity() * size_of::<(u8, u32)>() + node.values.capacity() * size_of::<V>()
+        })
+        .sum::<usize>()
+  }
+}
+
+/// The names of titles, by the suffixes of their words.
+#[derive(Default)]
+pub(crate) struct WordIndex {
+  names: Vec<String>,
+  by_suffix: Trie<u32>,
+}
+
+impl WordIndex {
+  /// Index a name by the suffixes of its words, so that keywords occurring anywhere in its
+  /// words find it.
+  ///
+  /// # Arguments
+  ///
+  /// * `name` - The lowercase name, which must not have been indexed before.
+  pub(crate) fn insert(&mut self, name: &str) {
+    self.insert_words(name, self.names.len() as u32);
+    self.names.push(name.to_owned());
+  }
+
+  /// Index a name by the suffixes of its words under an ID, without keeping the name. Its
+  /// ID is then returned by [WordIndex::candidate_ids] instead of the name itself.
+  ///
+  /// # Arguments
+  ///
+  /// * `name` - The lowercase name.
+  /// * `id` - The ID of the name, e.g. its position in a list of names kept elsewhere.
+  pub(crate) fn insert_words(&mut self, name: &str, id: u32) {
+    for word in name.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()) {
+      for (start, _) in word.char_indices() {
+        let suffix = &word.as_bytes()[start..];
+        self.by_suffix.insert(&suffix[..suffix.len().min(MAX_SUFFIX_LEN)], id);
+      }
+    }
+  }
+
+  /// The names that may contain all the keywords, which still have to be matched against
+  /// them, or `None` if the keywords cannot narrow down the names and all of them have to be
+  /// matched.
+  ///
+  /// Only keywords that are single words of at least [MIN_KEYWORD_LEN] bytes narrow down
+  /// the names, any of their forms finding a name. Excluded keywords never do.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - The keywords of a query.
+  pub(crate) fn candidates(&self, keywords: &[SearchString]) -> Option<Vec<&str>> {
+    let candidates = self.candidate_ids(keywords)?;
+    Some(candidates.into_iter().map(|id| self.names[id as usize].as_str()).collect())
+  }
+
+  /// The IDs of the names that may contain all the keywords, like [WordIndex::candidates].
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - The keywords of a query.
+  pub(crate) fn candidate_ids(&self, keywords: &[SearchString]) -> Option<FnvHashSet<u32>> {
+    let mut candidates: Option<FnvHashSet<u32>> = None;
+
+    for keyword in keywords.iter().filter(|keyword| !keyword.is_excluded()) {
+      let forms: Vec<&str> = keyword.forms().collect();
+      if forms
+        .iter()
+        .any(|form| form.len() < MIN_KEYWORD_LEN || !form.chars().all(char::is_alphanumeric))
+      {
+        continue;
+      }
+
+      let ids: FnvHashSet<u32> = forms
+        .iter()
+        .flat_map(|form| self.by_suffix.prefixed(&form.as_bytes()[..form.len().min(MAX_SUFFIX_LEN)]))
+        .copied()
+        .filter(|id| candidates.as_ref().is_none_or(|candidates| candidates.contains(id)))
+        .collect();
+      candidates = Some(ids);
+    }
+
+    candidates
+  }
+
+  /// Estimate the bytes used by the index.
+  pub(crate) fn bytes(&self) -> usize {
+    self.names.capacity() * size_of::<String>()
+      + self.names.iter().map(String::capacity).sum::<usize>()
+      + self.by_suffix.bytes()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn keywords(values: &[&str]) -> Vec<SearchString> {
+    values
+      .iter()
+      .map(|&value| match value.strip_prefix('-') {
+        Some(value) => SearchString::excluded(value).unwrap(),
+        None => SearchString::try_from(value).unwrap(),
+      })
+      .collect()
+  }
+
+  fn candidates(index: &WordIndex, values: &[&str]) -> Option<Vec<String>> {
+    let mut names: Vec<String> =
+      index.candidates(&keywords(values))?.into_iter().map(str::to_owned).collect();
+    names.sort();
+    Some(names)
+  }
+
+  #[test]
+  fn trie() {
+    let mut trie = Trie::default();
+    trie.insert(b"alien", 1);
+    trie.insert(b"aliens", 2);
+    trie.insert(b"alien", 1);
+    trie.insert(b"alibi", 3);
+
+    let mut values: Vec<_> = trie.prefixed(b"ali").copied().collect();
+    values.sort();
+    assert_eq!(values, [1, 2, 3]);
+    assert_eq!(trie.prefixed(b"alien").count(), 2);
+    assert_eq!(trie.prefixed(b"aliens").count(), 1);
+    assert_eq!(trie.prefixed(b"alienz").count(), 0);
+    assert_eq!(trie.prefixed(b"").count(), 3);
+  }
+
+  #[test]
+  fn word_index() {
+    let mut index = WordIndex::default();
+    for name in ["alien", "aliens", "the lego batman movie", "batman begins", "kinetoscopic record"] {
+      index.insert(name);
+    }
+
+    assert_eq!(candidates(&index, &["alien"]).unwrap(), ["alien", "aliens"]);
+    assert_eq!(candidates(&index, &["lien"]).unwrap(), ["alien", "aliens"]);
+    assert_eq!(candidates(&index, &["batman", "movie"]).unwrap(), ["the lego batman movie"]);
+    assert_eq!(candidates(&index, &["batman", "-lego"]).unwrap(), ["batman begins", "the lego batman movie"]);
+    assert_eq!(candidates(&index, &["kinetoscopic"]).unwrap(), ["kinetoscopic record"]);
+    assert_eq!(candidates(&index, &["kinetoscopy"]).unwrap(), ["kinetoscopic record"]);
+    assert!(candidates(&index, &["predator"]).unwrap().is_empty());
+
+    assert!(candidates(&index, &["up"]).is_none());
+    assert!(candidates(&index, &["-lego"]).is_none());
+    assert!(candidates(&index, &["lego batman"]).is_none());
+    assert_eq!(candidates(&index, &["up", "begins"]).unwrap(), ["batman begins"]);
+  }
+
+  #[test]
+  fn word_index_ids() {
+    let mut index = WordIndex::default();
+    for (id, name) in ["alien", "aliens", "batman begins"].into_iter().enumerate() {
+      index.insert_words(name, id as u32);
+    }
+
+    let mut ids: Vec<u32> = index.candidate_ids(&keywords(&["alien"])).unwrap().into_iter().collect();
+    ids.sort();
+    assert_eq!(ids, [0, 1]);
+    assert!(index.candidate_ids(&keywords(&["up"])).is_none());
+    assert_eq!(index.bytes(), index.by_suffix.bytes());
+  }
+}
@@ -1,6 +1,14 @@
 #![warn(clippy::all)]
 
+use std::collections::BTreeMap;
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs;
 use std::io::{self, BufRead, Write};
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
 
 use crate::imdb::ratings::Ratings;
 use crate::imdb::title::Title;
@@ -19,13 +27,170 @@ pub enum Error {
   /// Ratings parsing error.
   #[error("Ratings parsing error: {0}")]
   RatingsParsing(#[from] crate::imdb::ratings::Error),
+  /// Import statistics (de)serialization error.
+  #[error("Import statistics error: {0}")]
+  Stats(#[from] serde_json::Error),
+}
+
+/// Statistics of an import of the IMDB dumps, to verify what the databases contain.
+///
+/// Values of the dumps that are not known to this version are imported as
+/// [Genre::Other](crate::imdb::genre::Genre::Other) or
+/// [TitleType::Other](crate::imdb::title_type::TitleType::Other) instead of failing the
+/// import, and are counted as parse warnings.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportStats {
+  rows_read: usize,
+  movies_written: usize,
+  series_written: usize,
+  skipped_adult: usize,
+  skipped_types: usize,
+  unknown_genres: BTreeMap<String, usize>,
+  unknown_title_types: BTreeMap<String, usize>,
+}
+
+impl ImportStats {
+  /// The number of titles read from the dumps.
+  pub fn rows_read(&self) -> usize {
+    self.rows_read
+  }
+
+  /// The number of movies written to the movies database.
+  pub fn movies_written(&self) -> usize {
+    self.movies_written
+  }
+
+  /// The number of series written to the series database.
+  pub fn series_written(&self) -> usize {
+    self.series_written
+  }
+
+  /// The number of adult titles that were skipped.
+  pub fn skipped_adult(&self) -> usize {
+    self.skipped_adult
+  }
+
+  /// The number of titles that were skipped for being neither movies nor series, e.g.
+  /// episodes, video games or titles of unknown types.
+  pub fn skipped_types(&self) -> usize {
+    self.skipped_types
+  }
+
+  /// The number of occurrences of genres and title types that are not known.
+  pub fn parse_warnings(&self) -> usize {
+    self.unknown_genres.values().chain(self.unknown_title_types.values()).sum()
+  }
+
+  /// The genres that are not known, with the number of titles associated with each.
+  pub fn unknown_genres(&self) -> &BTreeMap<String, usize> {
+    &self.unknown_genres
+  }
+
+  /// The title types that are not known, with the number of titles of each.
+  pub fn unknown_title_types(&self) -> &BTreeMap<String, usize> {
+    &self.unknown_title_types
+  }
+
+  /// Count an occurrence of a genre that is not known.
+  ///
+  /// # Arguments
+  ///
+  /// * `genre` - The name of the genre in the dumps.
+  pub(crate) fn unknown_genre(&mut self, genre: &str) {
+    *self.unknown_genres.entry(genre.to_owned()).or_default() += 1;
+  }
+
+  /// Count an occurrence of a title type that is not known.
+  ///
+  /// # Arguments
+  ///
+  /// * `title_type` - The name of the title type in the dumps.
+  pub(crate) fn unknown_title_type(&mut self, title_type: &str) {
+    *self.unknown_title_types.entry(title_type.to_owned()).or_default() += 1;
+  }
+
+  /// Count a title skipped for being an adult title.
+  pub(crate) fn skip_adult(&mut self) {
+    self.skipped_adult += 1;
+  }
+
+  /// Count a title skipped for being neither a movie nor a series.
+  pub(crate) fn skip_type(&mut self) {
+    self.skipped_types += 1;
+  }
+
+  /// Describe the values that are not known, e.g. "unknown genres `Lyrical` (2 titles)".
+  pub(crate) fn describe_warnings(&self) -> String {
+    let describe = |values: &BTreeMap<String, usize>| {
+      values
+        .iter()
+        .map(|(value, count)| format!("`{value}` ({count} titles)"))
+        .collect::<Vec<_>>()
+        .join(", ")
+    };
+
+    let mut parts = Vec::new();
+    if !self.unknown_genres.is_empty() {
+      parts.push(format!("unknown genres {}", describe(&self.unknown_genres)));
+    }
+    if !self.unknown_title_types.is_empty() {
+      parts.push(format!("unknown title types {}", describe(&self.unknown_title_types)));
+    }
+
+    parts.join("; ")
+  }
+
+  /// Read the statistics of the last import, if they were saved.
+  ///
+  /// # Arguments
+  ///
+  /// * `path` - File the statistics were saved to.
+  #[cfg(feature = "fs")]
+  pub(crate) fn load(path: &Path) -> Result<Option<Self>, Error> {
+    match fs::read_to_string(path) {
+      Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(Error::Io(e)),
+    }
+  }
+
+  /// Write the statistics to a file, replacing it.
+  ///
+  /// # Arguments
+  ///
+  /// * `path` - File to write to.
+  #[cfg(feature = "fs")]
+  pub(crate) fn save(&self, path: &Path) -> Result<(), Error> {
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, serde_json::to_string(self)?)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+  }
+}
+
+impl fmt::Display for ImportStats {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} titles read, {} movies and {} series written, {} adult titles and {} titles of other \
+       types skipped, {} parse warnings",
+      self.rows_read,
+      self.movies_written,
+      self.series_written,
+      self.skipped_adult,
+      self.skipped_types,
+      self.parse_warnings()
+    )
+  }
 }
 
 /// Import title data from tab separated values (TSVs).
 ///
 /// This parses TSV data from the provided `ratings_reader` and `basics_reader` and
 /// write them out in binary to the provided writers `movies_db_writer` and
-/// `series_db_writer`.
+/// `series_db_writer`, and returns the statistics of the import. Unknown genres and title
+/// types are counted in the statistics rather than failing the import; titles of unknown
+/// types are skipped.
 ///
 /// # Arguments
 ///
@@ -38,9 +203,10 @@ pub(crate) fn tsv_import<R1: BufRead, R2: BufRead, W1: Write, W2: Write>(
   mut basics_reader: R2,
   mut movies_db_writer: W1,
   mut series_db_writer: W2,
-) -> Result<(), Error> {
+) -> Result<ImportStats, Error> {
   let ratings = Ratings::from_tsv(ratings_reader)?;
 
+  let mut stats = ImportStats::default();
   let mut line = String::new();
 
   // Skip the first line.
@@ -60,9 +226,17 @@ pub(crate) fn tsv_import<R1: BufRead, R2: BufRead, W1: Write, W2: Write>(
       continue;
     }
 
-    match Title::from_tsv(trimmed.as_bytes(), &ratings)? {
-      TsvAction::Movie(title) => title.write_binary(&mut movies_db_writer)?,
-      TsvAction::Series(title) => title.write_binary(&mut series_db_writer)?,
+    stats.rows_read += 1;
+
+    match Title::from_tsv(trimmed.as_bytes(), &ratings, &mut stats)? {
+      TsvAction::Movie(title) => {
+        title.write_binary(&mut movies_db_writer)?;
+        stats.movies_written += 1;
+      }
+      TsvAction::Series(title) => {
+        title.write_binary(&mut series_db_writer)?;
+        stats.series_written += 1;
+      }
       TsvAction::Skip => {
         line.clear();
         continue;
@@ -72,5 +246,59 @@ pub(crate) fn tsv_import<R1: BufRead, R2: BufRead, W1: Write, W2: Write>(
     line.clear();
   }
 
-  Ok(())
+  Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+
+  use super::*;
+
+  #[test]
+  fn unknown_values() {
+    let basics =
+      "tconst\ttitleType\tprimaryTitle\toriginalTitle\tisAdult\tstartYear\tendYear\truntimeMinutes\tgenres
+tt0000001\tmovie\tFirst\tFirst\t0\t1894\t\\N\t1\tDocumentary,Lyrical
+tt0000002\tmovie\tSecond\tSecond\t0\t1894\t\\N\t1\tLyrical
+tt0000003\tholoDrama\tThird\tThird\t0\t1894\t\\N\t1\tDrama
+tt0000004\tmovie\tFourth\tFourth\t1\t1894\t\\N\t1\tDrama
+tt0000005\ttvSeries\tFifth\tFifth\t0\t1894\t\\N\t1\tDrama
+tt0000006\ttvEpisode\tSixth\tSixth\t0\t1894\t\\N\t1\tDrama
+";
+    let ratings = "tconst\taverageRating\tnumVotes\n";
+
+    let mut movies = Vec::new();
+    let mut series = Vec::new();
+    let stats = tsv_import(Cursor::new(ratings), Cursor::new(basics), &mut movies, &mut series).unwrap();
+    assert_eq!(stats.parse_warnings(), 3);
+    assert_eq!(stats.unknown_genres().get("Lyrical"), Some(&2));
+    assert_eq!(stats.unknown_title_types().get("holoDrama"), Some(&1));
+    assert_eq!(
+      stats.describe_warnings(),
+      "unknown genres `Lyrical` (2 titles); unknown title types `holoDrama` (1 titles)"
+    );
+    assert_eq!(stats.rows_read(), 6);
+    assert_eq!(stats.movies_written(), 2);
+    assert_eq!(stats.series_written(), 1);
+    assert_eq!(stats.skipped_adult(), 1);
+    assert_eq!(stats.skipped_types(), 2);
+
+    #[cfg(feature = "fs")]
+    {
+      let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+      let path = dir.path().join("stats.json");
+      assert_eq!(ImportStats::load(&path).unwrap(), None);
+      stats.save(&path).unwrap();
+      assert_eq!(ImportStats::load(&path).unwrap(), Some(stats));
+    }
+
+    let mut source = movies.as_slice();
+    let first = Title::from_binary(&mut source).unwrap();
+    assert!(first.genres().contains(crate::imdb::genre::Genre::Other));
+    assert!(first.genres().contains(crate::imdb::genre::Genre::Documentary));
+    assert_eq!(Title::from_binary(&mut source).unwrap().primary_title(), "Second");
+    assert!(source.is_empty());
+    assert!(!series.is_empty());
+  }
 }
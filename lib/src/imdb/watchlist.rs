@@ -0,0 +1,246 @@
+#![warn(clippy::all)]
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::imdb::ratings::Rating;
+use crate::imdb::service::Service;
+use crate::imdb::title_id::TitleId;
+
+use serde::{Deserialize, Serialize};
+
+/// Errors when handling the watchlist.
+#[derive(Debug, thiserror::Error)]
+#[error("Watchlist error")]
+pub enum Error {
+  /// IO errors.
+  #[error("IO error: {0}")]
+  Io(#[from] io::Error),
+  /// The watchlist file is not valid JSON.
+  #[error("Invalid watchlist `{}`: {1}", .0.display())]
+  Json(PathBuf, #[source] serde_json::Error),
+}
+
+/// The rating and votes of a title the last time they were seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Seen {
+  rating: u8,
+  votes: u32,
+}
+
+impl From<Rating> for Seen {
+  fn from(rating: Rating) -> Self {
+    Self { rating: rating.rating(), votes: rating.votes() }
+  }
+}
+
+impl From<Seen> for Rating {
+  fn from(seen: Seen) -> Self {
+    Rating::new(seen.rating, seen.votes)
+  }
+}
+
+/// A change in the rating or votes of a title of the watchlist between database updates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RatingChange {
+  title_id: String,
+  primary_title: String,
+  before: Option<Rating>,
+  after: Option<Rating>,
+}
+
+impl RatingChange {
+  /// Returns the ID of the title.
+  pub fn title_id(&self) -> &str {
+    &self.title_id
+  }
+
+  /// Returns the primary name of the title.
+  pub fn primary_title(&self) -> &str {
+    &self.primary_title
+  }
+
+  /// Returns the rating of the title the last time it was seen, if it had one.
+  pub fn before(&self) -> Option<Rating> {
+    self.before
+  }
+
+  /// Returns the current rating of the title, if it has one.
+  pub fn after(&self) -> Option<Rating> {
+    self.after
+  }
+}
+
+impl fmt::Display for RatingChange {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fn rating(f: &mut fmt::Formatter, rating: Option<Rating>) -> fmt::Result {
+      match rating {
+        Some(rating) => {
+          write!(f, "{}.{} ({} votes)", rating.rating() / 10, rating.rating() % 10, rating.votes())
+        }
+        None => write!(f, "no rating"),
+      }
+    }
+
+    write!(f, "{}: ", self.primary_title)?;
+    rating(f, self.before)?;
+    write!(f, " -> ")?;
+    rating(f, self.after)
+  }
+}
+
+/// Titles a user keeps an eye on, along with their ratings the last time they were seen so
+/// that changes can be reported after database updates.
+#[derive(Debug, Default)]
+pub struct Watchlist {
+  path: PathBuf,
+  entries: BTreeMap<String, Option<Seen>>,
+}
+
+impl Watchlist {
+  /// Load the watchlist from the given file, which is empty if the file does not exist.
+  ///
+  /// # Arguments
+  ///
+  /// * `path` - File the watchlist is stored in.
+  pub fn load(path: &Path) -> Result<Self, Error> {
+    let entries = match fs::read(path) {
+      Ok(data) => serde_json::from_slice(&data).map_err(|e| Error::Json(path.to_owned(), e))?,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+      Err(e) => return Err(Error::Io(e)),
+    };
+
+    Ok(Self { path: path.to_owned(), entries })
+  }
+
+  /// Write the watchlist to its file.
+  pub fn save(&self) -> Result<(), Error> {
+    if let Some(dir) = self.path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+
+    let json = serde_json::to_vec_pretty(&self.entries).map_err(|e| Error::Json(self.path.clone(), e))?;
+    let tmp_path = self.path.with_extension("tmp");
+    fs::write(&tmp_path, json)?;
+    fs::rename(&tmp_path, &self.path)?;
+    Ok(())
+  }
+
+  /// Add a title to the watchlist, returns false if it was already in it.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - ID of the title.
+  /// * `rating` - Current rating of the title, changes are reported relative to it.
+  pub fn add(&mut self, id: &TitleId, rating: Option<Rating>) -> bool {
+    let id = id.to_string();
+
+    if self.entries.contains_key(&id) {
+      return false;
+    }
+
+    self.entries.insert(id, rating.map(Seen::from));
+    true
+  }
+
+  /// Remove a title from the watchlist, returns false if it was not in it.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - ID of the title.
+  pub fn remove(&mut self, id: &TitleId) -> bool {
+    self.entries.remove(&id.to_string()).is_some()
+  }
+
+  /// Returns the IDs of the titles of the watchlist, sorted.
+  pub fn ids(&self) -> impl Iterator<Item = &str> {
+    self.entries.keys().map(String::as_str)
+  }
+
+  /// Returns the number of titles in the watchlist.
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// Returns true if the watchlist has no titles.
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Compare the ratings of the titles of the watchlist in the given database against the
+  /// ratings they had the last time they were seen, and remember the current ones. Titles
+  /// that are not in the database are left alone.
+  ///
+  /// # Arguments
+  ///
+  /// * `imdb` - The current database.
+  pub fn update(&mut self, imdb: &Service) -> Vec<RatingChange> {
+    let mut changes = Vec::new();
+
+    for (id, seen) in &mut self.entries {
+      let Some((title, _)) = TitleId::try_from(id.as_str()).ok().and_then(|id| imdb.by_id_any(&id)) else {
+        continue;
+      };
+
+      let current = title.rating().map(Seen::from);
+      if current != *seen {
+        changes.push(RatingChange {
+          title_id: id.clone(),
+          primary_title: title.primary_title().to_owned(),
+          before: seen.map(Rating::from),
+          after: title.rating(),
+        });
+        *seen = current;
+      }
+    }
+
+    changes
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::imdb::testdata::{make_basics_reader, make_ratings_reader};
+
+  #[test]
+  fn update() {
+    let imdb = Service::from_tsv_readers(make_basics_reader(), make_ratings_reader()).unwrap();
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let path = dir.path().join("watchlist.json");
+
+    let mut watchlist = Watchlist::load(&path).unwrap();
+    assert!(watchlist.is_empty());
+
+    let carmencita = TitleId::try_from("tt0000001").unwrap();
+    let factory = TitleId::try_from("tt0000010").unwrap();
+    let missing = TitleId::try_from("tt9999999").unwrap();
+    assert!(watchlist.add(&carmencita, Some(Rating::new(57, 1800))));
+    assert!(!watchlist.add(&carmencita, None));
+    assert!(watchlist.add(&factory, imdb.by_id_any(&factory).unwrap().0.rating()));
+    assert!(watchlist.add(&missing, None));
+    watchlist.save().unwrap();
+
+    let mut watchlist = Watchlist::load(&path).unwrap();
+    assert_eq!(watchlist.ids().collect::<Vec<_>>(), ["tt0000001", "tt0000010", "tt9999999"]);
+
+    let changes = watchlist.update(&imdb);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].title_id(), "tt0000001");
+    assert_eq!(changes[0].before(), Some(Rating::new(57, 1800)));
+    assert_eq!(changes[0].after(), Some(Rating::new(57, 1845)));
+    assert_eq!(changes[0].to_string(), "Carmencita: 5.7 (1800 votes) -> 5.7 (1845 votes)");
+    assert!(watchlist.update(&imdb).is_empty());
+
+    assert!(watchlist.remove(&missing));
+    assert!(!watchlist.remove(&missing));
+    assert_eq!(watchlist.len(), 2);
+
+    fs::write(&path, "not json").unwrap();
+    assert!(matches!(Watchlist::load(&path), Err(Error::Json(_, _))));
+  }
+}
@@ -3,6 +3,18 @@
 
 //! TVrank is a library for querying and ranking information about movies and series.
 
+#[cfg(feature = "fetch")]
+pub mod enrich;
 pub mod imdb;
+pub mod nfo;
+#[cfg(feature = "fs")]
+pub mod paths;
+pub mod prelude;
+pub mod provider;
+pub mod scores;
 pub mod title_info;
+#[cfg(feature = "fetch")]
+pub mod tmdb;
+#[cfg(feature = "fetch")]
+pub mod trakt;
 pub mod utils;
@@ -0,0 +1,218 @@
+#![warn(clippy::all)]
+
+//! Module for writing NFO files, the XML metadata files that media centers like Kodi and
+//! Jellyfin read from the directories of movies and series.
+//!
+//! Movies are described by a `movie.nfo` file and series by a `tvshow.nfo` file, holding the
+//! title, release year, IMDB rating, runtime, genres and IMDB ID of the title.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::imdb::ImdbTitle;
+
+/// Errors when writing NFO files.
+#[derive(Debug, thiserror::Error)]
+#[error("Error writing NFO file")]
+pub enum Error {
+  /// IO errors.
+  #[error("IO error writing `{}`: {1}", .0.display())]
+  Io(PathBuf, #[source] io::Error),
+}
+
+/// What happened when writing an NFO file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Written {
+  /// The file did not exist and was created.
+  Created,
+  /// The file existed and was replaced.
+  Overwritten,
+  /// The file existed and was left untouched.
+  Skipped,
+}
+
+/// The name of the NFO file of a title, `tvshow.nfo` for series and `movie.nfo` otherwise.
+///
+/// # Arguments
+///
+/// * `title` - The title to describe.
+pub fn filename(title: &ImdbTitle) -> &'static str {
+  if title.title_type().is_series() {
+    "tvshow.nfo"
+  } else {
+    "movie.nfo"
+  }
+}
+
+/// Escape text for XML element contents.
+fn escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&apos;"),
+      c => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+/// The contents of the NFO file of a title.
+///
+/// # Arguments
+///
+/// * `title` - The title to describe.
+pub fn to_xml(title: &ImdbTitle) -> String {
+  let root = if title.title_type().is_series() {
+    "tvshow"
+  } else {
+    "movie"
+  };
+
+  // Writing to a String cannot fail.
+  let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+  let _ = writeln!(xml, "<{root}>");
+  let _ = writeln!(xml, "  <title>{}</title>", escape(title.primary_title()));
+
+  if let Some(original_title) = title.original_title() {
+    let _ = writeln!(xml, "  <originaltitle>{}</originaltitle>", escape(original_title));
+  }
+
+  if let Some(year) = title.start_year() {
+    let _ = writeln!(xml, "  <year>{year}</year>");
+  }
+
+  if let Some(rating) = title.rating() {
+    let _ = writeln!(xml, "  <ratings>");
+    let _ = writeln!(xml, "    <rating name=\"imdb\" max=\"10\" default=\"true\">");
+    let _ = writeln!(xml, "      <value>{:.1}</value>", f32::from(rating.rating()) / 10.0);
+    let _ = writeln!(xml, "      <votes>{}</votes>", rating.votes());
+    let _ = writeln!(xml, "    </rating>");
+    let _ = writeln!(xml, "  </ratings>");
+  }
+
+  if let Some(runtime) = title.runtime() {
+    let _ = writeln!(xml, "  <runtime>{}</runtime>", runtime.as_secs() / 60);
+  }
+
+  for genre in title.genres().iter() {
+    let _ = writeln!(xml, "  <genre>{}</genre>", escape(&genre.to_string()));
+  }
+
+  let _ = writeln!(xml, "  <uniqueid type=\"imdb\" default=\"true\">{}</uniqueid>", title.title_id());
+  let _ = writeln!(xml, "</{root}>");
+  xml
+}
+
+/// Write the NFO file of a title into its directory.
+///
+/// Existing NFO files may have been edited by hand or written by another tool, so they are
+/// only replaced when `overwrite` is set.
+///
+/// # Arguments
+///
+/// * `dir` - The directory of the title.
+/// * `title` - The title to describe.
+/// * `overwrite` - Whether to replace an existing NFO file.
+pub fn write(dir: &Path, title: &ImdbTitle, overwrite: bool) -> Result<Written, Error> {
+  let path = dir.join(filename(title));
+  let exists = path.exists();
+
+  if exists && !overwrite {
+    return Ok(Written::Skipped);
+  }
+
+  fs::write(&path, to_xml(title)).map_err(|e| Error::Io(path.clone(), e))?;
+
+  if exists {
+    Ok(Written::Overwritten)
+  } else {
+    Ok(Written::Created)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::imdb::{ImdbGenre, ImdbGenres, ImdbTitle, ImdbTitleId, ImdbTitleType};
+  use crate::nfo::{self, Written};
+  use indoc::indoc;
+
+  fn movie() -> ImdbTitle<'static> {
+    let mut genres = ImdbGenres::default();
+    genres.add(ImdbGenre::Action);
+    genres.add(ImdbGenre::Thriller);
+
+    ImdbTitle::new(ImdbTitleId::try_from("tt0117060").unwrap(), ImdbTitleType::Movie, "Mission: Impossible")
+      .with_original_title("Mission <Impossible> & Co")
+      .with_start_year(1996)
+      .with_runtime_minutes(110)
+      .with_rating(71, 450000)
+      .with_genres(genres)
+  }
+
+  #[test]
+  fn test_movie_xml() {
+    let title = movie();
+    assert_eq!(nfo::filename(&title), "movie.nfo");
+    assert_eq!(
+      nfo::to_xml(&title),
+      indoc! {r#"
+        <?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+        <movie>
+          <title>Mission: Impossible</title>
+          <originaltitle>Mission &lt;Impossible&gt; &amp; Co</originaltitle>
+          <year>1996</year>
+          <ratings>
+            <rating name="imdb" max="10" default="true">
+              <value>7.1</value>
+              <votes>450000</votes>
+            </rating>
+          </ratings>
+          <runtime>110</runtime>
+          <genre>Action</genre>
+          <genre>Thriller</genre>
+          <uniqueid type="imdb" default="true">tt0117060</uniqueid>
+        </movie>
+      "#}
+    );
+  }
+
+  #[test]
+  fn test_series_xml() {
+    let id = ImdbTitleId::try_from("tt0903747").unwrap();
+    let title = ImdbTitle::new(id, ImdbTitleType::TvSeries, "Breaking Bad");
+    assert_eq!(nfo::filename(&title), "tvshow.nfo");
+    assert_eq!(
+      nfo::to_xml(&title),
+      indoc! {r#"
+        <?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+        <tvshow>
+          <title>Breaking Bad</title>
+          <uniqueid type="imdb" default="true">tt0903747</uniqueid>
+        </tvshow>
+      "#}
+    );
+  }
+
+  #[test]
+  fn test_write() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let title = movie();
+    let path = dir.path().join("movie.nfo");
+
+    assert_eq!(nfo::write(dir.path(), &title, false).unwrap(), Written::Created);
+    assert!(std::fs::read_to_string(&path).unwrap().contains("<year>1996</year>"));
+
+    std::fs::write(&path, "edited").unwrap();
+    assert_eq!(nfo::write(dir.path(), &title, false).unwrap(), Written::Skipped);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "edited");
+
+    assert_eq!(nfo::write(dir.path(), &title, true).unwrap(), Written::Overwritten);
+    assert!(std::fs::read_to_string(&path).unwrap().contains("<movie>"));
+  }
+}
@@ -0,0 +1,215 @@
+#![warn(clippy::all)]
+
+//! Standard locations of the files used by TVrank.
+//!
+//! Sharing these locations ensures that the command-line interface, the server and any
+//! other front-end agree on where databases and runtime state are kept.
+//!
+//! Personal state, like the rating history, can be kept per profile so that several people
+//! sharing a machine keep it separate, while the databases are shared by all profiles.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+
+/// Errors when determining standard locations.
+#[derive(Debug, thiserror::Error)]
+#[error("Error determining standard locations")]
+pub enum Error {
+  /// Thrown if no home directory could be found for the current user.
+  #[error("Cannot find home directory")]
+  NoHome,
+  /// Thrown if a profile name is empty or contains characters other than ASCII letters,
+  /// digits, `-` and `_`.
+  #[error("Invalid profile name `{0}`")]
+  InvalidProfileName(String),
+  /// IO errors.
+  #[error("IO error: {0}")]
+  Io(#[from] io::Error),
+}
+
+const QUALIFIER: &str = "com.fredmorcos";
+const ORGANIZATION: &str = "Fred Morcos";
+const APPLICATION: &str = "tvrank";
+
+const HISTORY_DIRNAME: &str = "history";
+const TOMBSTONES_DIRNAME: &str = "tombstones";
+const PROFILES_DIRNAME: &str = "profiles";
+const ENRICHMENT_DIRNAME: &str = "enrichment";
+const TRAKT_DIRNAME: &str = "trakt";
+const WATCHLIST_FILENAME: &str = "watchlist.json";
+const LOG_FILENAME: &str = "tvrank.log";
+
+/// Standard locations of the files used by TVrank.
+#[derive(Debug, Clone)]
+pub struct Paths {
+  cache_dir: PathBuf,
+  state_dir: PathBuf,
+  profile: Option<String>,
+}
+
+impl Paths {
+  /// Determine the standard locations for the current user.
+  ///
+  /// On Linux, the state directory respects `XDG_STATE_HOME`. Platforms without a notion
+  /// of a state directory use the local data directory instead.
+  pub fn new() -> Result<Self, Error> {
+    let project = ProjectDirs::from(QUALIFIER, ORGANIZATION, APPLICATION).ok_or(Error::NoHome)?;
+    let state_dir = project.state_dir().unwrap_or_else(|| project.data_local_dir());
+    Ok(Self::with_dirs(project.cache_dir(), state_dir))
+  }
+
+  /// Use custom locations instead of the standard ones.
+  ///
+  /// # Arguments
+  ///
+  /// * `cache_dir` - Directory for the databases, which can be rebuilt at any time.
+  /// * `state_dir` - Directory for mutable runtime state, like logs and rating history.
+  pub fn with_dirs(cache_dir: &Path, state_dir: &Path) -> Self {
+    Self { cache_dir: cache_dir.to_path_buf(), state_dir: state_dir.to_path_buf(), profile: None }
+  }
+
+  /// Use the locations of the given profile for personal state.
+  ///
+  /// # Arguments
+  ///
+  /// * `profile` - Name of the profile, made of ASCII letters, digits, `-` and `_`.
+  pub fn with_profile(mut self, profile: &str) -> Result<Self, Error> {
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    if profile.is_empty() || !profile.chars().all(valid) {
+      return Err(Error::InvalidProfileName(profile.to_owned()));
+    }
+
+    self.profile = Some(profile.to_owned());
+    Ok(self)
+  }
+
+  /// The selected profile, if any.
+  pub fn profile(&self) -> Option<&str> {
+    self.profile.as_deref()
+  }
+
+  /// Directory for the databases.
+  pub fn cache_dir(&self) -> &Path {
+    &self.cache_dir
+  }
+
+  /// Directory for mutable runtime state.
+  pub fn state_dir(&self) -> &Path {
+    &self.state_dir
+  }
+
+  /// Directory for the personal state of the selected profile, or the state directory if no
+  /// profile is selected.
+  pub fn profile_dir(&self) -> PathBuf {
+    match &self.profile {
+      Some(profile) => self.profiles_dir().join(profile),
+      None => self.state_dir.clone(),
+    }
+  }
+
+  /// Directory containing the directories of all profiles.
+  pub fn profiles_dir(&self) -> PathBuf {
+    self.state_dir.join(PROFILES_DIRNAME)
+  }
+
+  /// Names of the existing profiles, sorted.
+  pub fn profiles(&self) -> Result<Vec<String>, Error> {
+    let entries = match fs::read_dir(self.profiles_dir()) {
+      Ok(entries) => entries,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+      Err(e) => return Err(Error::Io(e)),
+    };
+
+    let mut profiles = Vec::new();
+    for entry in entries {
+      let entry = entry?;
+      if entry.file_type()?.is_dir() {
+        profiles.push(entry.file_name().to_string_lossy().into_owned());
+      }
+    }
+
+    profiles.sort_unstable();
+    Ok(profiles)
+  }
+
+  /// Directory for the rating history snapshots of the selected profile.
+  pub fn history_dir(&self) -> PathBuf {
+    self.profile_dir().join(HISTORY_DIRNAME)
+  }
+
+  /// Directory for the title IDs that were retired from the IMDB dataset.
+  pub fn tombstones_dir(&self) -> PathBuf {
+    self.state_dir.join(TOMBSTONES_DIRNAME)
+  }
+
+  /// Directory for the cached enrichment data of titles, like plots and posters.
+  pub fn enrichment_dir(&self) -> PathBuf {
+    self.cache_dir.join(ENRICHMENT_DIRNAME)
+  }
+
+  /// Directory for the Trakt token and watched titles of the selected profile.
+  pub fn trakt_dir(&self) -> PathBuf {
+    self.profile_dir().join(TRAKT_DIRNAME)
+  }
+
+  /// File of the watchlist of the selected profile.
+  pub fn watchlist_file(&self) -> PathBuf {
+    self.profile_dir().join(WATCHLIST_FILENAME)
+  }
+
+  /// File to write logs to when logging to a file.
+  pub fn log_file(&self) -> PathBuf {
+    self.state_dir.join(LOG_FILENAME)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn with_dirs() {
+    let paths = Paths::with_dirs(Path::new("/cache"), Path::new("/state"));
+    assert_eq!(paths.cache_dir(), Path::new("/cache"));
+    assert_eq!(paths.state_dir(), Path::new("/state"));
+    assert_eq!(paths.history_dir(), Path::new("/state/history"));
+    assert_eq!(paths.tombstones_dir(), Path::new("/state/tombstones"));
+    assert_eq!(paths.enrichment_dir(), Path::new("/cache/enrichment"));
+    assert_eq!(paths.trakt_dir(), Path::new("/state/trakt"));
+    assert_eq!(paths.watchlist_file(), Path::new("/state/watchlist.json"));
+    assert_eq!(paths.log_file(), Path::new("/state/tvrank.log"));
+  }
+
+  #[test]
+  fn with_profile() {
+    let paths = Paths::with_dirs(Path::new("/cache"), Path::new("/state"))
+      .with_profile("kids")
+      .unwrap();
+    assert_eq!(paths.profile(), Some("kids"));
+    assert_eq!(paths.cache_dir(), Path::new("/cache"));
+    assert_eq!(paths.profile_dir(), Path::new("/state/profiles/kids"));
+    assert_eq!(paths.history_dir(), Path::new("/state/profiles/kids/history"));
+    assert_eq!(paths.trakt_dir(), Path::new("/state/profiles/kids/trakt"));
+    assert_eq!(paths.watchlist_file(), Path::new("/state/profiles/kids/watchlist.json"));
+    assert_eq!(paths.tombstones_dir(), Path::new("/state/tombstones"));
+
+    for name in ["", "..", "a/b", "a b"] {
+      let paths = Paths::with_dirs(Path::new("/cache"), Path::new("/state"));
+      assert!(matches!(paths.with_profile(name), Err(Error::InvalidProfileName(_))));
+    }
+  }
+
+  #[test]
+  fn profiles() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let paths = Paths::with_dirs(dir.path(), dir.path());
+    assert!(paths.profiles().unwrap().is_empty());
+
+    fs::create_dir_all(paths.profiles_dir().join("kids")).unwrap();
+    fs::create_dir_all(paths.profiles_dir().join("adults")).unwrap();
+    assert_eq!(paths.profiles().unwrap(), ["adults", "kids"]);
+  }
+}
@@ -0,0 +1,32 @@
+#![warn(clippy::all)]
+
+//! Commonly used types, re-exported for convenience.
+//!
+//! Glob-importing this module is the recommended way to use the library, since the items
+//! it contains are kept stable across releases even when the modules they live in are
+//! reorganized:
+//!
+//! ```
+//! use tvrank::prelude::*;
+//! ```
+
+pub use crate::imdb::{
+  Imdb, ImdbCacheCompression, ImdbColumn, ImdbColumnValue, ImdbColumns, ImdbError, ImdbErrorKind, ImdbFilter,
+  ImdbGenre, ImdbGenres, ImdbQuery, ImdbScoredTitle, ImdbSearch, ImdbSeriesStatus, ImdbTitle, ImdbTitleId,
+  ImdbTitleIdError, ImdbTitleType,
+};
+#[cfg(feature = "fs")]
+pub use crate::imdb::{
+  ImdbBuilder, ImdbHistory, ImdbHistoryError, ImdbTombstones, ImdbTombstonesError, ImdbTrending,
+  ImdbUpdatePolicy,
+};
+#[cfg(feature = "fetch")]
+pub use crate::imdb::{ImdbSuggestion, ImdbSuggestionError};
+#[cfg(feature = "fs")]
+pub use crate::paths::{Error as PathsError, Paths};
+pub use crate::provider::{TitleProvider, TitleProviders};
+pub use crate::title_info::TitleInfo;
+pub use crate::utils::cancel::Cancellation;
+pub use crate::utils::search::{
+  KeywordMatchKind, SearchOptions, SearchScope, SearchString, SearchStringError,
+};
@@ -0,0 +1,548 @@
+#![warn(clippy::all)]
+
+//! Providers of titles, so that other sources than the IMDB dataset can be queried together
+//! with it.
+//!
+//! [Imdb] is a provider itself, and so are its queries with other search options,
+//! [ImdbSearch]. Downstream crates can add their own sources by implementing
+//! [TitleProvider], building their titles with [ImdbTitle::new](crate::imdb::ImdbTitle::new),
+//! and aggregate them with [TitleProviders]:
+//!
+//! ```
+//! use tvrank::imdb::{ImdbQuery, ImdbTitle, ImdbTitleId, ImdbTitleType};
+//! use tvrank::provider::{TitleProvider, TitleProviders};
+//! use tvrank::utils::search::SearchString;
+//!
+//! /// Home videos, which are not on IMDB.
+//! struct HomeVideos {
+//!   titles: Vec<ImdbTitle<'static>>,
+//! }
+//!
+//! impl TitleProvider for HomeVideos {
+//!   fn name(&self) -> &str {
+//!     "home videos"
+//!   }
+//!
+//!   fn by_id(&self, id: &ImdbTitleId, _query: ImdbQuery) -> Option<&ImdbTitle<'_>> {
+//!     self.titles.iter().find(|title| title.title_id() == id)
+//!   }
+//!
+//!   fn by_title(&self, title: &SearchString, _query: ImdbQuery) -> Vec<&ImdbTitle<'_>> {
+//!     let title = title.as_str();
+//!     self.titles.iter().filter(|t| t.primary_title().to_lowercase() == title).collect()
+//!   }
+//!
+//!   fn by_keywords<'a>(&'a self, keywords: &[SearchString], _query: ImdbQuery) -> Vec<&'a ImdbTitle<'a>> {
+//!     let matches = |title: &ImdbTitle| {
+//!       let name = title.primary_title().to_lowercase();
+//!       keywords.iter().all(|keyword| name.contains(keyword.as_str()))
+//!     };
+//!     self.titles.iter().filter(|title| matches(title)).collect()
+//!   }
+//! }
+//!
+//! let id = ImdbTitleId::try_from("tt9999999").unwrap();
+//! let title = ImdbTitle::new(id, ImdbTitleType::Movie, "Summer Holidays").with_start_year(2021);
+//! let home_videos = HomeVideos { titles: vec![title] };
+//!
+//! let providers = TitleProviders::new().with(&home_videos);
+//! let keywords = [SearchString::try_from("holidays").unwrap()];
+//! let results = providers.by_keywords(&keywords, ImdbQuery::Movies);
+//! assert_eq!(results[0].start_year(), Some(2021));
+//! ```
+
+use crate::imdb::{Imdb, ImdbQuery as Query, ImdbSearch, ImdbTitle as Title, ImdbTitleId as TitleId};
+use crate::utils::search::SearchString;
+
+use fnv::FnvHashSet;
+
+/// A source of movies and series that can be searched.
+pub trait TitleProvider: Send + Sync {
+  /// The name of the provider, e.g. for logging.
+  fn name(&self) -> &str;
+
+  /// Find a title by its IMDB ID.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - IMDB ID of the title.
+  /// * `query` - Whether a movie or a series is searched for.
+  fn by_id(&self, id: &TitleId, query: Query) -> Option<&Title<'_>>;
+
+  /// Find a title by its IMDB ID among both movies and series, together with whether it is
+  /// a movie or a series.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - IMDB ID of the title.
+  fn by_id_any(&self, id: &TitleId) -> Option<(&Title<'_>, Query)> {
+    self
+      .by_id(id, Query::Movies)
+      .map(|title| (title, Query::Movies))
+      .or_else(|| self.by_id(id, Query::Series).map(|title| (title, Query::Series)))
+  }
+
+  /// Find the titles with the given name.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - The name of the titles, in lowercase.
+  /// * `query` - Whether movies or series are searched for.
+  fn by_title(&self, title: &SearchString, query: Query) -> Vec<&Title<'_>>;
+
+  /// Find the titles with the given name released in the given year.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - The name of the titles, in lowercase.
+  /// * `year` - The release year of the titles.
+  /// * `query` - Whether movies or series are searched for.
+  fn by_title_and_year(&self, title: &SearchString, year: u16, query: Query) -> Vec<&Title<'_>> {
+    let mut titles = self.by_title(title, query);
+    titles.retain(|title| title.start_year() == Some(year));
+    titles
+  }
+
+  /// Find the titles whose names only match the given name once their leading articles are
+  /// ignored, for when [TitleProvider::by_title] finds nothing. Providers without such an
+  /// index find nothing by default.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - The name of the titles, in lowercase.
+  /// * `query` - Whether movies or series are searched for.
+  fn by_title_normalized(&self, _title: &SearchString, _query: Query) -> Vec<&Title<'_>> {
+    Vec::new()
+  }
+
+  /// Find the titles released in the given year whose names only match the given name once
+  /// their leading articles are ignored. Providers without such an index find nothing by
+  /// default.
+  ///
+  /// # Arguments
+  ///
+  /// * `title` - The name of the titles, in lowercase.
+  /// * `year` - The release year of the titles.
+  /// * `query` - Whether movies or series are searched for.
+  fn by_title_and_year_normalized(
+    &self,
+    _title: &SearchString,
+    _year: u16,
+    _query: Query,
+  ) -> Vec<&Title<'_>> {
+    Vec::new()
+  }
+
+  /// Find the titles whose names match the given keywords.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - The keywords, in lowercase.
+  /// * `query` - Whether movies or series are searched for.
+  fn by_keywords<'a>(&'a self, keywords: &[SearchString], query: Query) -> Vec<&'a Title<'a>>;
+
+  /// Find the titles whose names match the given keywords, released in the given year.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - The keywords, in lowercase.
+  /// * `year` - The release year of the titles.
+  /// * `query` - Whether movies or series are searched for.
+  fn by_keywords_and_year<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    year: u16,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    let mut titles = self.by_keywords(keywords, query);
+    titles.retain(|title| title.start_year() == Some(year));
+    titles
+  }
+
+  /// Find at most `limit` titles whose names match the given keywords. Providers that can
+  /// stop searching early should override this, by default all titles are found first.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - The keywords, in lowercase.
+  /// * `limit` - The maximum number of titles.
+  /// * `query` - Whether movies or series are searched for.
+  fn by_keywords_limited<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    limit: usize,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    let mut titles = self.by_keywords(keywords, query);
+    titles.truncate(limit);
+    titles
+  }
+
+  /// Find at most `limit` titles whose names match the given keywords, released in the
+  /// given year.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - The keywords, in lowercase.
+  /// * `year` - The release year of the titles.
+  /// * `limit` - The maximum number of titles.
+  /// * `query` - Whether movies or series are searched for.
+  fn by_keywords_and_year_limited<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    year: u16,
+    limit: usize,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    let mut titles = self.by_keywords_and_year(keywords, year, query);
+    titles.truncate(limit);
+    titles
+  }
+}
+
+impl TitleProvider for Imdb {
+  fn name(&self) -> &str {
+    "IMDB"
+  }
+
+  fn by_id(&self, id: &TitleId, query: Query) -> Option<&Title<'_>> {
+    Imdb::by_id(self, id, query)
+  }
+
+  fn by_id_any(&self, id: &TitleId) -> Option<(&Title<'_>, Query)> {
+    Imdb::by_id_any(self, id)
+  }
+
+  fn by_title(&self, title: &SearchString, query: Query) -> Vec<&Title<'_>> {
+    Imdb::by_title(self, title, query)
+  }
+
+  fn by_title_and_year(&self, title: &SearchString, year: u16, query: Query) -> Vec<&Title<'_>> {
+    Imdb::by_title_and_year(self, title, year, query)
+  }
+
+  fn by_title_normalized(&self, title: &SearchString, query: Query) -> Vec<&Title<'_>> {
+    Imdb::by_title_normalized(self, title, query)
+  }
+
+  fn by_title_and_year_normalized(&self, title: &SearchString, year: u16, query: Query) -> Vec<&Title<'_>> {
+    Imdb::by_title_and_year_normalized(self, title, year, query)
+  }
+
+  fn by_keywords<'a>(&'a self, keywords: &[SearchString], query: Query) -> Vec<&'a Title<'a>> {
+    Imdb::by_keywords(self, keywords, query)
+  }
+
+  fn by_keywords_and_year<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    year: u16,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    Imdb::by_keywords_and_year(self, keywords, year, query)
+  }
+
+  fn by_keywords_limited<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    limit: usize,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    Imdb::by_keywords_limited(self, keywords, limit, query)
+  }
+
+  fn by_keywords_and_year_limited<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    year: u16,
+    limit: usize,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    Imdb::by_keywords_and_year_limited(self, keywords, year, limit, query)
+  }
+}
+
+impl TitleProvider for ImdbSearch<'_> {
+  fn name(&self) -> &str {
+    "IMDB"
+  }
+
+  fn by_id(&self, id: &TitleId, query: Query) -> Option<&Title<'_>> {
+    ImdbSearch::by_id(self, id, query)
+  }
+
+  fn by_id_any(&self, id: &TitleId) -> Option<(&Title<'_>, Query)> {
+    ImdbSearch::by_id_any(self, id)
+  }
+
+  fn by_title(&self, title: &SearchString, query: Query) -> Vec<&Title<'_>> {
+    ImdbSearch::by_title(self, title, query)
+  }
+
+  fn by_title_and_year(&self, title: &SearchString, year: u16, query: Query) -> Vec<&Title<'_>> {
+    ImdbSearch::by_title_and_year(self, title, year, query)
+  }
+
+  fn by_title_normalized(&self, title: &SearchString, query: Query) -> Vec<&Title<'_>> {
+    ImdbSearch::by_title_normalized(self, title, query)
+  }
+
+  fn by_title_and_year_normalized(&self, title: &SearchString, year: u16, query: Query) -> Vec<&Title<'_>> {
+    ImdbSearch::by_title_and_year_normalized(self, title, year, query)
+  }
+
+  fn by_keywords<'a>(&'a self, keywords: &[SearchString], query: Query) -> Vec<&'a Title<'a>> {
+    ImdbSearch::by_keywords(self, keywords, query)
+  }
+
+  fn by_keywords_and_year<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    year: u16,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    ImdbSearch::by_keywords_and_year(self, keywords, year, query)
+  }
+
+  fn by_keywords_limited<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    limit: usize,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    ImdbSearch::by_keywords_limited(self, keywords, limit, query)
+  }
+
+  fn by_keywords_and_year_limited<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    year: u16,
+    limit: usize,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    ImdbSearch::by_keywords_and_year_limited(self, keywords, year, limit, query)
+  }
+}
+
+/// Several providers queried together.
+///
+/// Results are aggregated in the order the providers were added. When several providers
+/// have a title with the same ID, only the title of the first one is kept, so sources that
+/// should take precedence are added first.
+#[derive(Default, Clone)]
+pub struct TitleProviders<'p> {
+  providers: Vec<&'p dyn TitleProvider>,
+}
+
+impl<'p> TitleProviders<'p> {
+  /// Construct an empty set of providers.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Add a provider, after the previously added ones.
+  ///
+  /// # Arguments
+  ///
+  /// * `provider` - The provider to add.
+  pub fn with(mut self, provider: &'p dyn TitleProvider) -> Self {
+    self.providers.push(provider);
+    self
+  }
+
+  /// The names of the providers, in the order they were added.
+  pub fn names(&self) -> impl Iterator<Item = &str> {
+    self.providers.iter().map(|provider| provider.name())
+  }
+
+  /// Aggregate the results of all providers, keeping the first title with each ID.
+  fn aggregate<'a>(
+    &'a self,
+    query: impl Fn(&'a dyn TitleProvider) -> Vec<&'a Title<'a>>,
+  ) -> Vec<&'a Title<'a>> {
+    if let [provider] = self.providers[..] {
+      return query(provider);
+    }
+
+    let mut seen = FnvHashSet::default();
+    let mut titles = Vec::new();
+
+    for &provider in &self.providers {
+      for title in query(provider) {
+        if seen.insert(title.title_id().as_usize()) {
+          titles.push(title);
+        }
+      }
+    }
+
+    titles
+  }
+}
+
+impl TitleProvider for TitleProviders<'_> {
+  fn name(&self) -> &str {
+    "aggregate"
+  }
+
+  fn by_id(&self, id: &TitleId, query: Query) -> Option<&Title<'_>> {
+    self.providers.iter().find_map(|provider| provider.by_id(id, query))
+  }
+
+  fn by_id_any(&self, id: &TitleId) -> Option<(&Title<'_>, Query)> {
+    self.providers.iter().find_map(|provider| provider.by_id_any(id))
+  }
+
+  fn by_title(&self, title: &SearchString, query: Query) -> Vec<&Title<'_>> {
+    self.aggregate(|provider| provider.by_title(title, query))
+  }
+
+  fn by_title_and_year(&self, title: &SearchString, year: u16, query: Query) -> Vec<&Title<'_>> {
+    self.aggregate(|provider| provider.by_title_and_year(title, year, query))
+  }
+
+  fn by_title_normalized(&self, title: &SearchString, query: Query) -> Vec<&Title<'_>> {
+    self.aggregate(|provider| provider.by_title_normalized(title, query))
+  }
+
+  fn by_title_and_year_normalized(&self, title: &SearchString, year: u16, query: Query) -> Vec<&Title<'_>> {
+    self.aggregate(|provider| provider.by_title_and_year_normalized(title, year, query))
+  }
+
+  fn by_keywords<'a>(&'a self, keywords: &[SearchString], query: Query) -> Vec<&'a Title<'a>> {
+    self.aggregate(|provider| provider.by_keywords(keywords, query))
+  }
+
+  fn by_keywords_and_year<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    year: u16,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    self.aggregate(|provider| provider.by_keywords_and_year(keywords, year, query))
+  }
+
+  fn by_keywords_limited<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    limit: usize,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    let mut titles = self.aggregate(|provider| provider.by_keywords_limited(keywords, limit, query));
+    titles.truncate(limit);
+    titles
+  }
+
+  fn by_keywords_and_year_limited<'a>(
+    &'a self,
+    keywords: &[SearchString],
+    year: u16,
+    limit: usize,
+    query: Query,
+  ) -> Vec<&'a Title<'a>> {
+    let mut titles =
+      self.aggregate(|provider| provider.by_keywords_and_year_limited(keywords, year, limit, query));
+    titles.truncate(limit);
+    titles
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::imdb::ImdbTitleType as TitleType;
+  use crate::imdb::{ImdbGenre, ImdbGenres, ImdbQuery as Query, ImdbTitle as Title, ImdbTitleId as TitleId};
+  use crate::provider::{TitleProvider, TitleProviders};
+  use crate::utils::search::SearchString;
+
+  struct Fixed {
+    name: &'static str,
+    titles: Vec<Title<'static>>,
+  }
+
+  impl TitleProvider for Fixed {
+    fn name(&self) -> &str {
+      self.name
+    }
+
+    fn by_id(&self, id: &TitleId, query: Query) -> Option<&Title<'_>> {
+      self
+        .by_title(&SearchString::try_from("any").unwrap(), query)
+        .into_iter()
+        .find(|t| t.title_id() == id)
+    }
+
+    fn by_title(&self, _title: &SearchString, query: Query) -> Vec<&Title<'_>> {
+      let series = matches!(query, Query::Series);
+      self
+        .titles
+        .iter()
+        .filter(|title| title.title_type().is_series() == series)
+        .collect()
+    }
+
+    fn by_keywords<'a>(&'a self, keywords: &[SearchString], query: Query) -> Vec<&'a Title<'a>> {
+      self.by_title(&keywords[0], query)
+    }
+  }
+
+  fn title(id: &'static str, name: &'static str, year: u16) -> Title<'static> {
+    Title::new(TitleId::try_from(id).unwrap(), TitleType::Movie, name).with_start_year(year)
+  }
+
+  #[test]
+  fn test_title_builder() {
+    let mut genres = ImdbGenres::default();
+    genres.add(ImdbGenre::Drama);
+
+    let title = title("tt0000001", "Summer", 2021)
+      .with_rating(123, 10)
+      .with_genres(genres)
+      .with_runtime_minutes(95)
+      .with_original_title("Été");
+    assert_eq!(title.primary_title(), "Summer");
+    assert_eq!(title.original_title(), Some("Été"));
+    assert_eq!(title.start_year(), Some(2021));
+    assert_eq!(title.rating().map(|rating| (rating.rating(), rating.votes())), Some((100, 10)));
+    assert!(title.genres().contains(ImdbGenre::Drama));
+    assert_eq!(title.runtime().map(|runtime| runtime.as_secs()), Some(95 * 60));
+    assert_eq!(title.title_type(), TitleType::Movie);
+
+    assert_eq!(title.with_original_title("summer").original_title(), Some("Été"));
+    assert_eq!(title.with_start_year(1700).start_year(), None);
+  }
+
+  #[test]
+  fn test_aggregate() {
+    let first =
+      Fixed { name: "first", titles: vec![title("tt0000001", "A", 2000), title("tt0000002", "B", 2001)] };
+    let second =
+      Fixed { name: "second", titles: vec![title("tt0000002", "B2", 2001), title("tt0000003", "C", 2001)] };
+    let providers = TitleProviders::new().with(&first).with(&second);
+    assert_eq!(providers.names().collect::<Vec<_>>(), ["first", "second"]);
+
+    let keywords = [SearchString::try_from("any").unwrap()];
+    let names: Vec<_> = providers
+      .by_keywords(&keywords, Query::Movies)
+      .iter()
+      .map(|t| t.primary_title())
+      .collect();
+    assert_eq!(names, ["A", "B", "C"]);
+
+    let names: Vec<_> = providers
+      .by_keywords_and_year(&keywords, 2001, Query::Movies)
+      .iter()
+      .map(|t| t.primary_title())
+      .collect();
+    assert_eq!(names, ["B", "C"]);
+
+    let names: Vec<_> = providers
+      .by_keywords_limited(&keywords, 2, Query::Movies)
+      .iter()
+      .map(|t| t.primary_title())
+      .collect();
+    assert_eq!(names, ["A", "B"]);
+
+    let id = TitleId::try_from("tt0000003").unwrap();
+    assert_eq!(providers.by_id_any(&id).map(|(title, _)| title.primary_title()), Some("C"));
+    assert!(providers.by_keywords(&keywords, Query::Series).is_empty());
+  }
+}
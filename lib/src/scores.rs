@@ -0,0 +1,381 @@
+#![warn(clippy::all)]
+
+//! Critic scores of titles, like Rotten Tomatoes and Metacritic scores, which are missing
+//! from the IMDB dumps.
+//!
+//! Scores come from a [ScoreProvider]. [ScoreDataset] provides scores from a local file
+//! supplied by the user, and other sources can be plugged in by implementing the trait:
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use tvrank::imdb::ImdbTitleId;
+//! use tvrank::scores::{self, ScoreKind, ScoreProvider};
+//!
+//! /// Every title is a masterpiece.
+//! struct Generous;
+//!
+//! impl ScoreProvider for Generous {
+//!   fn kinds(&self) -> &[ScoreKind] {
+//!     &[ScoreKind::Metacritic]
+//!   }
+//!
+//!   fn score(&self, _id: &ImdbTitleId, kind: ScoreKind) -> Option<u8> {
+//!     (kind == ScoreKind::Metacritic).then_some(100)
+//!   }
+//! }
+//!
+//! let columns = scores::columns(Arc::new(Generous));
+//! assert_eq!(columns[0].name(), "Metacritic");
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::imdb::{ImdbColumn as Column, ImdbColumnValue as ColumnValue, ImdbTitleId as TitleId};
+
+use fnv::FnvHashMap;
+use serde::Deserialize;
+
+/// Errors when loading score datasets.
+#[derive(Debug, thiserror::Error)]
+#[error("Error loading scores")]
+pub enum Error {
+  /// IO error.
+  #[error("IO error: {0}")]
+  Io(#[from] io::Error),
+  /// JSON parsing error.
+  #[error("Error parsing JSON scores: {0}")]
+  Json(#[from] serde_json::Error),
+  /// CSV parsing error.
+  #[error("Error parsing CSV scores on line {0}: {1}")]
+  Csv(usize, String),
+  /// Invalid title ID or score in a JSON dataset.
+  #[error("Invalid JSON scores: {0}")]
+  InvalidJson(String),
+  /// The file is neither a CSV nor a JSON file.
+  #[error("Unknown format of scores file `{}`, expected a .csv or .json file", .0.display())]
+  UnknownFormat(PathBuf),
+}
+
+/// A kind of critic score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScoreKind {
+  /// The Rotten Tomatoes Tomatometer, the percentage of positive reviews.
+  RottenTomatoes,
+  /// The Metascore, the weighted average of reviews on Metacritic.
+  Metacritic,
+}
+
+impl ScoreKind {
+  /// All the kinds of scores.
+  pub const ALL: [ScoreKind; 2] = [ScoreKind::RottenTomatoes, ScoreKind::Metacritic];
+
+  /// The name of the score, as displayed in column headers.
+  pub fn name(self) -> &'static str {
+    match self {
+      ScoreKind::RottenTomatoes => "Rotten Tomatoes",
+      ScoreKind::Metacritic => "Metacritic",
+    }
+  }
+
+  /// The name of the score in dataset files.
+  fn key(self) -> &'static str {
+    match self {
+      ScoreKind::RottenTomatoes => "rotten_tomatoes",
+      ScoreKind::Metacritic => "metacritic",
+    }
+  }
+}
+
+impl fmt::Display for ScoreKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.name())
+  }
+}
+
+/// A source of critic scores of titles.
+pub trait ScoreProvider: Send + Sync {
+  /// The kinds of scores the provider has, which are displayed as columns.
+  fn kinds(&self) -> &[ScoreKind];
+
+  /// The score of a title, out of 100, or `None` if the provider has no such score for the
+  /// title.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - IMDB ID of the title.
+  /// * `kind` - The kind of score.
+  fn score(&self, id: &TitleId, kind: ScoreKind) -> Option<u8>;
+}
+
+/// Columns with the scores of titles from a provider, one per kind of score it has.
+///
+/// # Arguments
+///
+/// * `provider` - The provider of the scores.
+pub fn columns(provider: Arc<dyn ScoreProvider>) -> Vec<Column> {
+  provider
+    .kinds()
+    .iter()
+    .map(|&kind| {
+      let provider = provider.clone();
+      Column::new(kind.name(), move |title| {
+        provider
+          .score(title.title_id(), kind)
+          .map(|score| ColumnValue::Integer(i64::from(score)))
+      })
+    })
+    .collect()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Scores {
+  rotten_tomatoes: Option<u8>,
+  metacritic: Option<u8>,
+}
+
+impl Scores {
+  fn get(&self, kind: ScoreKind) -> Option<u8> {
+    match kind {
+      ScoreKind::RottenTomatoes => self.rotten_tomatoes,
+      ScoreKind::Metacritic => self.metacritic,
+    }
+  }
+
+  fn set(&mut self, kind: ScoreKind, score: u8) {
+    match kind {
+      ScoreKind::RottenTomatoes => self.rotten_tomatoes = Some(score),
+      ScoreKind::Metacritic => self.metacritic = Some(score),
+    }
+  }
+}
+
+/// Parse a score out of 100, written as a number with an optional `%` sign.
+fn parse_score(value: &str) -> Result<Option<u8>, String> {
+  let value = value.trim();
+  let number = value.strip_suffix('%').unwrap_or(value).trim();
+
+  if number.is_empty() || number.eq_ignore_ascii_case("N/A") {
+    return Ok(None);
+  }
+
+  match number.parse::<u8>() {
+    Ok(score) if score <= 100 => Ok(Some(score)),
+    _ => Err(format!("invalid score `{value}`, expected a number between 0 and 100")),
+  }
+}
+
+#[derive(Deserialize)]
+struct RawScores {
+  imdb_id: String,
+  rotten_tomatoes: Option<serde_json::Value>,
+  metacritic: Option<serde_json::Value>,
+}
+
+/// Parse a score of a JSON dataset, given as a number or as a string like in CSV datasets.
+fn json_score(value: Option<serde_json::Value>, id: &str) -> Result<Option<u8>, Error> {
+  let text = match value {
+    None | Some(serde_json::Value::Null) => return Ok(None),
+    Some(serde_json::Value::String(text)) => text,
+    Some(value) => value.to_string(),
+  };
+
+  parse_score(&text).map_err(|e| Error::InvalidJson(format!("{id}: {e}")))
+}
+
+/// Scores of titles from a local dataset, usually exported from another tool.
+///
+/// Datasets are CSV or JSON files with an `imdb_id` and optional `rotten_tomatoes` and
+/// `metacritic` scores out of 100 for each title, for example:
+///
+/// ```text
+/// imdb_id,rotten_tomatoes,metacritic
+/// tt0137523,79%,67
+/// tt0903747,96,
+/// ```
+///
+/// ```text
+/// [{"imdb_id": "tt0137523", "rotten_tomatoes": 79, "metacritic": 67}]
+/// ```
+///
+/// CSV fields are separated by commas and cannot be quoted, and empty or `N/A` scores are
+/// missing.
+#[derive(Debug, Clone, Default)]
+pub struct ScoreDataset {
+  kinds: Vec<ScoreKind>,
+  scores: FnvHashMap<String, Scores>,
+}
+
+impl ScoreDataset {
+  /// Load a dataset from a `.csv` or `.json` file.
+  ///
+  /// # Arguments
+  ///
+  /// * `path` - Path of the dataset.
+  pub fn load(path: &Path) -> Result<Self, Error> {
+    let extension = path.extension().map(|extension| extension.to_string_lossy().to_lowercase());
+
+    match extension.as_deref() {
+      Some("csv") => Self::from_csv(&fs::read_to_string(path)?),
+      Some("json") => Self::from_json(&fs::read_to_string(path)?),
+      _ => Err(Error::UnknownFormat(path.to_owned())),
+    }
+  }
+
+  /// Parse a CSV dataset, whose first line names the columns.
+  ///
+  /// # Arguments
+  ///
+  /// * `csv` - Contents of the dataset.
+  pub fn from_csv(csv: &str) -> Result<Self, Error> {
+    let mut lines = csv.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+    let header = lines.next().map_or("", |(_, line)| line);
+    let columns: Vec<_> = header.split(',').map(|name| name.trim().to_lowercase()).collect();
+
+    let id_column = columns
+      .iter()
+      .position(|name| name == "imdb_id")
+      .ok_or_else(|| Error::Csv(1, String::from("missing `imdb_id` column")))?;
+    let score_columns: Vec<_> = ScoreKind::ALL
+      .into_iter()
+      .filter_map(|kind| Some((kind, columns.iter().position(|name| name == kind.key())?)))
+      .collect();
+
+    let mut dataset =
+      Self { kinds: score_columns.iter().map(|&(kind, _)| kind).collect(), ..Self::default() };
+
+    for (index, line) in lines {
+      let fields: Vec<_> = line.split(',').collect();
+      let field = |column: usize| fields.get(column).copied().unwrap_or_default();
+
+      let mut scores = Scores::default();
+      for &(kind, column) in &score_columns {
+        if let Some(score) = parse_score(field(column)).map_err(|e| Error::Csv(index + 1, e))? {
+          scores.set(kind, score);
+        }
+      }
+
+      dataset
+        .insert(field(id_column).trim(), scores)
+        .map_err(|e| Error::Csv(index + 1, e))?;
+    }
+
+    Ok(dataset)
+  }
+
+  /// Parse a JSON dataset, an array of objects with an `imdb_id` and the scores.
+  ///
+  /// # Arguments
+  ///
+  /// * `json` - Contents of the dataset.
+  pub fn from_json(json: &str) -> Result<Self, Error> {
+    let mut dataset = Self::default();
+
+    for raw in serde_json::from_str::<Vec<RawScores>>(json)? {
+      let scores = Scores {
+        rotten_tomatoes: json_score(raw.rotten_tomatoes, &raw.imdb_id)?,
+        metacritic: json_score(raw.metacritic, &raw.imdb_id)?,
+      };
+
+      dataset.insert(raw.imdb_id.trim(), scores).map_err(Error::InvalidJson)?;
+    }
+
+    // Only the scores that some titles have are displayed.
+    dataset.kinds = ScoreKind::ALL
+      .into_iter()
+      .filter(|&kind| dataset.scores.values().any(|scores| scores.get(kind).is_some()))
+      .collect();
+
+    Ok(dataset)
+  }
+
+  fn insert(&mut self, id: &str, scores: Scores) -> Result<(), String> {
+    let id = TitleId::try_from(id).map_err(|e| e.to_string())?;
+    self.scores.insert(id.to_string(), scores);
+    Ok(())
+  }
+
+  /// The number of titles in the dataset.
+  pub fn len(&self) -> usize {
+    self.scores.len()
+  }
+
+  /// Whether the dataset has no titles.
+  pub fn is_empty(&self) -> bool {
+    self.scores.is_empty()
+  }
+}
+
+impl ScoreProvider for ScoreDataset {
+  fn kinds(&self) -> &[ScoreKind] {
+    &self.kinds
+  }
+
+  fn score(&self, id: &TitleId, kind: ScoreKind) -> Option<u8> {
+    self.scores.get(&id.to_string())?.get(kind)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use crate::imdb::ImdbTitleId as TitleId;
+  use crate::scores::{Error, ScoreDataset, ScoreKind, ScoreProvider};
+
+  fn id(id: &str) -> TitleId<'_> {
+    TitleId::try_from(id).unwrap()
+  }
+
+  #[test]
+  fn test_csv() {
+    let csv = "imdb_id, Metacritic ,rotten_tomatoes\ntt0137523,67,79%\n\ntt0903747,,96\ntt0000001,N/A\n";
+    let dataset = ScoreDataset::from_csv(csv).unwrap();
+    assert_eq!(dataset.len(), 3);
+    assert_eq!(dataset.kinds(), [ScoreKind::RottenTomatoes, ScoreKind::Metacritic]);
+    assert_eq!(dataset.score(&id("tt0137523"), ScoreKind::RottenTomatoes), Some(79));
+    assert_eq!(dataset.score(&id("tt0137523"), ScoreKind::Metacritic), Some(67));
+    assert_eq!(dataset.score(&id("tt0903747"), ScoreKind::Metacritic), None);
+    assert_eq!(dataset.score(&id("tt0000001"), ScoreKind::RottenTomatoes), None);
+    assert_eq!(dataset.score(&id("tt0000002"), ScoreKind::Metacritic), None);
+
+    let dataset = ScoreDataset::from_csv("imdb_id,metacritic\ntt1,50\n").unwrap();
+    assert_eq!(dataset.kinds(), [ScoreKind::Metacritic]);
+
+    assert!(matches!(ScoreDataset::from_csv("id,metacritic\n"), Err(Error::Csv(1, _))));
+    assert!(matches!(ScoreDataset::from_csv("imdb_id,metacritic\ntt1,101"), Err(Error::Csv(2, _))));
+    assert!(matches!(ScoreDataset::from_csv("imdb_id,metacritic\n\nnope,1"), Err(Error::Csv(3, _))));
+  }
+
+  #[test]
+  fn test_json() {
+    let json = r#"[{"imdb_id":"tt0137523","rotten_tomatoes":79,"metacritic":"67"},{"imdb_id":"tt0903747","rotten_tomatoes":"96%"}]"#;
+    let dataset = ScoreDataset::from_json(json).unwrap();
+    assert_eq!(dataset.len(), 2);
+    assert_eq!(dataset.kinds(), [ScoreKind::RottenTomatoes, ScoreKind::Metacritic]);
+    assert_eq!(dataset.score(&id("tt0137523"), ScoreKind::Metacritic), Some(67));
+    assert_eq!(dataset.score(&id("tt0903747"), ScoreKind::RottenTomatoes), Some(96));
+
+    let dataset = ScoreDataset::from_json(r#"[{"imdb_id":"tt1","metacritic":null}]"#).unwrap();
+    assert!(dataset.kinds().is_empty());
+
+    let json = r#"[{"imdb_id":"tt1","metacritic":200}]"#;
+    assert!(matches!(ScoreDataset::from_json(json), Err(Error::InvalidJson(_))));
+    assert!(matches!(ScoreDataset::from_json(r#"[{"imdb_id":"x1"}]"#), Err(Error::InvalidJson(_))));
+    assert!(matches!(ScoreDataset::from_json("{}"), Err(Error::Json(_))));
+  }
+
+  #[test]
+  fn test_load() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let path = dir.path().join("scores.CSV");
+    std::fs::write(&path, "imdb_id,metacritic\ntt0137523,67\n").unwrap();
+    assert_eq!(ScoreDataset::load(&path).unwrap().len(), 1);
+
+    assert!(matches!(ScoreDataset::load(Path::new("scores.txt")), Err(Error::UnknownFormat(_))));
+  }
+}
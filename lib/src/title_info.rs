@@ -2,14 +2,15 @@
 
 //! Module for handling title information objects.
 
+use std::fmt;
 use std::fs;
 use std::io::{self, BufReader};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::imdb::ImdbTitleId;
 
-use log::warn;
 use serde::{Deserialize, Deserializer, Serialize};
+use tracing::warn;
 
 /// Errors when parsing title information.
 #[derive(Debug, thiserror::Error)]
@@ -21,6 +22,34 @@ pub enum Error {
   /// IO errors.
   #[error("IO error: {0}")]
   Io(#[from] io::Error),
+  /// No title information or media center metadata with an IMDB ID was found.
+  #[error("No title information found")]
+  NotFound,
+}
+
+/// Where the title information of a directory was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+  /// A `tvrank.json` title information file.
+  TitleInfo,
+  /// A Kodi or Jellyfin NFO file.
+  Nfo(PathBuf),
+  /// A Plex `.plexmatch` file.
+  PlexMatch,
+  /// An `[imdbid-ttXXXXXXX]` or `{imdb-ttXXXXXXX}` tag in the directory name, as used by
+  /// Jellyfin and Plex.
+  DirName,
+}
+
+impl fmt::Display for Source {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Source::TitleInfo => write!(f, "`tvrank.json` file"),
+      Source::Nfo(path) => write!(f, "`{}` file", path.display()),
+      Source::PlexMatch => write!(f, "`.plexmatch` file"),
+      Source::DirName => write!(f, "IMDB ID in directory name"),
+    }
+  }
 }
 
 /// The IMDB section of title information files.
@@ -37,7 +66,7 @@ impl<'a> ImdbTitleInfo<'a> {
   }
 
   /// Get the IMDB ID of an IMDB title information object.
-  pub fn id(&self) -> &ImdbTitleId {
+  pub fn id(&self) -> &ImdbTitleId<'_> {
     &self.id
   }
 }
@@ -69,7 +98,7 @@ impl<'a> TitleInfo<'a> {
   }
 
   /// Load a title information object from a file.
-  pub fn from_path(path: &Path) -> Result<TitleInfo, Error> {
+  pub fn from_path(path: &Path) -> Result<TitleInfo<'static>, Error> {
     let title_info_path = path.join("tvrank.json");
     let title_info_file = fs::File::open(&title_info_path)?;
     let title_info_file_reader = BufReader::new(title_info_file);
@@ -86,8 +115,173 @@ impl<'a> TitleInfo<'a> {
     Ok(title_info)
   }
 
+  /// Find the title information of a directory, from its `tvrank.json` file or otherwise
+  /// from the metadata media centers keep for it: Kodi and Jellyfin NFO files, Plex
+  /// `.plexmatch` files and IMDB ID tags in the directory name.
+  ///
+  /// # Arguments
+  ///
+  /// * `path` - The directory of the title.
+  pub fn discover(path: &Path) -> Result<(TitleInfo<'static>, Source), Error> {
+    if let Ok(title_info) = TitleInfo::from_path(path) {
+      return Ok((title_info, Source::TitleInfo));
+    }
+
+    if let Some(id) = plexmatch_id(path) {
+      return Ok((TitleInfo::new(id), Source::PlexMatch));
+    }
+
+    if let Some(id) = path.file_name().and_then(|name| dir_name_id(&name.to_string_lossy())) {
+      return Ok((TitleInfo::new(id), Source::DirName));
+    }
+
+    if let Some((id, nfo_path)) = nfo_id(path)? {
+      return Ok((TitleInfo::new(id), Source::Nfo(nfo_path)));
+    }
+
+    Err(Error::NotFound)
+  }
+
   /// Get the IMDB title information object from a top-level title information object.
-  pub fn imdb(&self) -> &ImdbTitleInfo {
+  pub fn imdb(&self) -> &ImdbTitleInfo<'_> {
     &self.imdb
   }
 }
+
+/// Find the first IMDB ID ("tt" followed by at least 7 digits) that is not part of a longer
+/// word in `text`.
+fn find_id(text: &str) -> Option<ImdbTitleId<'static>> {
+  let bytes = text.as_bytes();
+  let mut start = 0;
+
+  while let Some(offset) = text[start..].find("tt") {
+    let begin = start + offset;
+    let digits = bytes[begin + 2..].iter().take_while(|b| b.is_ascii_digit()).count();
+    let end = begin + 2 + digits;
+    let bounded_before = begin == 0 || !bytes[begin - 1].is_ascii_alphanumeric();
+    let bounded_after = end == bytes.len() || !bytes[end].is_ascii_alphanumeric();
+
+    if digits >= 7 && bounded_before && bounded_after {
+      // Title IDs borrow their storage, like the ones deserialized from `tvrank.json` files.
+      let id = Box::leak(text[begin..end].to_owned().into_boxed_str());
+      if let Ok(id) = ImdbTitleId::try_from(&*id) {
+        return Some(id);
+      }
+    }
+
+    start = begin + 2;
+  }
+
+  None
+}
+
+/// The IMDB ID in a `[imdbid-ttXXXXXXX]`, `[imdb-ttXXXXXXX]` or `{imdb-ttXXXXXXX}` tag of a
+/// directory name.
+fn dir_name_id(name: &str) -> Option<ImdbTitleId<'static>> {
+  const TAGS: [&str; 4] = ["[imdbid-", "{imdbid-", "[imdb-", "{imdb-"];
+
+  let lowercase = name.to_ascii_lowercase();
+  TAGS.iter().find_map(|tag| {
+    let start = lowercase.find(tag)? + tag.len();
+    let end = start + name[start..].find([']', '}'])?;
+    find_id(&name[start..end])
+  })
+}
+
+/// The IMDB ID in the `imdbid: ttXXXXXXX` or `guid: imdb://ttXXXXXXX` line of a `.plexmatch`
+/// file.
+fn plexmatch_id(path: &Path) -> Option<ImdbTitleId<'static>> {
+  let contents = fs::read_to_string(path.join(".plexmatch")).ok()?;
+
+  contents.lines().find_map(|line| {
+    let (key, value) = line.split_once(':')?;
+    let value = value.trim();
+
+    match key.trim().to_ascii_lowercase().as_str() {
+      "imdbid" => find_id(value),
+      "guid" => find_id(value.strip_prefix("imdb://")?),
+      _ => None,
+    }
+  })
+}
+
+/// The IMDB ID in the NFO files of a directory, trying `movie.nfo` and `tvshow.nfo` before
+/// the NFO files named after the media files.
+fn nfo_id(path: &Path) -> Result<Option<(ImdbTitleId<'static>, PathBuf)>, Error> {
+  let mut nfo_paths = Vec::new();
+
+  for entry in fs::read_dir(path)? {
+    let entry_path = entry?.path();
+    if entry_path.is_file() && entry_path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("nfo")) {
+      nfo_paths.push(entry_path);
+    }
+  }
+
+  nfo_paths.sort_by_key(|nfo_path| {
+    let is_title_nfo = nfo_path
+      .file_name()
+      .is_some_and(|name| name == "movie.nfo" || name == "tvshow.nfo");
+    (!is_title_nfo, nfo_path.clone())
+  });
+
+  for nfo_path in nfo_paths {
+    let Ok(contents) = fs::read_to_string(&nfo_path) else {
+      warn!("Ignoring `{}`, it is not a text file", nfo_path.display());
+      continue;
+    };
+
+    if let Some(id) = find_id(&contents) {
+      return Ok(Some((id, nfo_path)));
+    }
+  }
+
+  Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+
+  use crate::title_info::{dir_name_id, find_id, Source, TitleInfo};
+
+  #[test]
+  fn test_find_id() {
+    assert_eq!(find_id("https://www.imdb.com/title/tt0117060/").unwrap().as_str(), "tt0117060");
+    assert_eq!(find_id("<id>tt10872600</id>").unwrap().as_str(), "tt10872600");
+    assert!(find_id("tt011706").is_none());
+    assert!(find_id("ttt0117060").is_none());
+    assert!(find_id("tt0117060a").is_none());
+  }
+
+  #[test]
+  fn test_dir_name_id() {
+    assert_eq!(dir_name_id("Heat (1995) [imdbid-tt0113277]").unwrap().as_str(), "tt0113277");
+    assert_eq!(dir_name_id("Heat (1995) {imdb-tt0113277}").unwrap().as_str(), "tt0113277");
+    assert!(dir_name_id("Heat (1995)").is_none());
+    assert!(dir_name_id("Heat (1995) [tt0113277]").is_none());
+  }
+
+  #[test]
+  fn test_discover() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let path = dir.path();
+    assert!(TitleInfo::discover(path).is_err());
+
+    fs::write(path.join("Heat.1995.nfo"), "https://www.imdb.com/title/tt0000001/").unwrap();
+    fs::write(path.join("movie.nfo"), r#"<movie><uniqueid type="imdb">tt0113277</uniqueid></movie>"#)
+      .unwrap();
+    let (title_info, source) = TitleInfo::discover(path).unwrap();
+    assert_eq!(title_info.imdb().id().as_str(), "tt0113277");
+    assert_eq!(source, Source::Nfo(path.join("movie.nfo")));
+
+    fs::write(path.join(".plexmatch"), "title: Heat\nyear: 1995\nguid: imdb://tt0000002\n").unwrap();
+    let (title_info, source) = TitleInfo::discover(path).unwrap();
+    assert_eq!(title_info.imdb().id().as_str(), "tt0000002");
+    assert_eq!(source, Source::PlexMatch);
+
+    fs::write(path.join("tvrank.json"), r#"{"imdb":{"id":"tt0000003"}}"#).unwrap();
+    let (title_info, source) = TitleInfo::discover(path).unwrap();
+    assert_eq!(title_info.imdb().id().as_str(), "tt0000003");
+    assert_eq!(source, Source::TitleInfo);
+  }
+}
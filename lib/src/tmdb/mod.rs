@@ -0,0 +1,16 @@
+#![warn(clippy::all)]
+#![warn(missing_docs)]
+
+//! Module for TVrank to use The Movie Database (TMDB) API as an auxiliary source.
+//!
+//! TMDB provides data that is missing from the IMDB dataset, like overviews, posters and
+//! popularity. Titles are looked up by their IMDB ID, so that the data can be merged into
+//! the results of IMDB queries.
+
+mod service;
+
+pub(crate) use service::find_url;
+
+pub use service::Details as TmdbDetails;
+pub use service::Error as TmdbError;
+pub use service::Service as Tmdb;
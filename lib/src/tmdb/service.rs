@@ -0,0 +1,152 @@
+#![warn(clippy::all)]
+
+use crate::imdb::ImdbTitleId;
+
+use reqwest::blocking::Client;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+/// Errors when querying TMDB.
+#[derive(Debug, thiserror::Error)]
+#[error("Error querying TMDB")]
+pub enum Error {
+  /// Networking error.
+  #[error("Networking error: {0}")]
+  Net(#[from] reqwest::Error),
+  /// URL parsing error.
+  #[error("Error parsing URL: {0}")]
+  UrlParsing(#[from] url::ParseError),
+  /// Response parsing error.
+  #[error("Error parsing TMDB response: {0}")]
+  Json(#[from] serde_json::Error),
+}
+
+const FIND_URL: &str = "https://api.themoviedb.org/3/find/";
+const POSTER_URL: &str = "https://image.tmdb.org/t/p/w500";
+
+#[derive(Deserialize)]
+struct RawFind {
+  #[serde(default)]
+  movie_results: Vec<RawResult>,
+  #[serde(default)]
+  tv_results: Vec<RawResult>,
+}
+
+#[derive(Deserialize)]
+struct RawResult {
+  overview: Option<String>,
+  poster_path: Option<String>,
+  popularity: Option<f64>,
+}
+
+/// Data about a title from TMDB.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Details {
+  overview: Option<String>,
+  poster_url: Option<String>,
+  popularity: Option<f64>,
+}
+
+impl Details {
+  /// A short summary of the plot.
+  pub fn overview(&self) -> Option<&str> {
+    self.overview.as_deref()
+  }
+
+  /// The URL of the poster image.
+  pub fn poster_url(&self) -> Option<&str> {
+    self.poster_url.as_deref()
+  }
+
+  /// The popularity of the title on TMDB, which grows with recent activity (e.g. views,
+  /// votes and watchlist additions).
+  pub fn popularity(&self) -> Option<f64> {
+    self.popularity
+  }
+
+  /// Parse a response of the find API, or `None` if TMDB does not know the title.
+  ///
+  /// # Arguments
+  ///
+  /// * `json` - Body of the response.
+  pub(crate) fn from_find_json(json: &str) -> Result<Option<Self>, Error> {
+    let raw: RawFind = serde_json::from_str(json)?;
+    let non_empty = |value: Option<String>| value.filter(|value| !value.is_empty());
+
+    Ok(raw.movie_results.into_iter().chain(raw.tv_results).next().map(|result| Self {
+      overview: non_empty(result.overview),
+      poster_url: non_empty(result.poster_path).map(|path| format!("{POSTER_URL}{path}")),
+      popularity: result.popularity,
+    }))
+  }
+}
+
+/// The URL to find a title by its IMDB ID.
+///
+/// # Arguments
+///
+/// * `api_key` - TMDB API key.
+/// * `id` - IMDB ID of the title.
+pub(crate) fn find_url(api_key: &str, id: &ImdbTitleId) -> Result<Url, Error> {
+  Ok(Url::parse_with_params(
+    &format!("{FIND_URL}{id}"),
+    [("api_key", api_key), ("external_source", "imdb_id")],
+  )?)
+}
+
+/// Client of the TMDB API.
+pub struct Service {
+  client: Client,
+  api_key: String,
+}
+
+impl Service {
+  /// Create a client of the TMDB API.
+  ///
+  /// # Arguments
+  ///
+  /// * `api_key` - TMDB API key, see <https://www.themoviedb.org/settings/api>.
+  pub fn new(api_key: &str) -> Result<Self, Error> {
+    Ok(Self { client: Client::builder().build()?, api_key: api_key.to_owned() })
+  }
+
+  /// Find a title by its IMDB ID, or `None` if TMDB does not know the title.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - IMDB ID of the title.
+  pub fn by_imdb_id(&self, id: &ImdbTitleId) -> Result<Option<Details>, Error> {
+    let url = find_url(&self.api_key, id)?;
+    let body = self.client.get(url).send()?.error_for_status()?.text()?;
+    Details::from_find_json(&body)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::imdb::ImdbTitleId;
+  use crate::tmdb::service::{find_url, Details};
+
+  #[test]
+  fn test_find_json() {
+    let json = r#"{"movie_results":[],"tv_results":[{"overview":"A chemistry teacher.","poster_path":"/ggFHVNu6.jpg","popularity":512.25}]}"#;
+    let details = Details::from_find_json(json).unwrap().unwrap();
+    assert_eq!(details.overview(), Some("A chemistry teacher."));
+    assert_eq!(details.poster_url(), Some("https://image.tmdb.org/t/p/w500/ggFHVNu6.jpg"));
+    assert_eq!(details.popularity(), Some(512.25));
+
+    let json = r#"{"movie_results":[{"overview":"","poster_path":null}]}"#;
+    assert_eq!(Details::from_find_json(json).unwrap(), Some(Details::default()));
+    assert_eq!(Details::from_find_json(r#"{"movie_results":[],"tv_results":[]}"#).unwrap(), None);
+  }
+
+  #[test]
+  fn test_find_url() {
+    let id = ImdbTitleId::try_from("tt0903747").unwrap();
+    let url = find_url("KEY", &id).unwrap();
+    assert_eq!(
+      url.as_str(),
+      "https://api.themoviedb.org/3/find/tt0903747?api_key=KEY&external_source=imdb_id"
+    );
+  }
+}
@@ -0,0 +1,19 @@
+#![warn(clippy::all)]
+#![warn(missing_docs)]
+
+//! Module for TVrank to sync the watch status of titles from Trakt (trakt.tv).
+//!
+//! Trakt accounts are linked with the OAuth device-code flow: the user enters a short code
+//! on the Trakt website, while TVrank polls for the resulting token. The watched history and
+//! ratings are then pulled into a [TraktWatched] list, kept on disk so that results can be
+//! filtered and annotated offline.
+
+mod service;
+mod watched;
+
+pub use service::DeviceCode as TraktDeviceCode;
+pub use service::Error as TraktError;
+pub use service::Service as Trakt;
+pub use service::Token as TraktToken;
+pub use watched::Error as TraktWatchedError;
+pub use watched::{Watched as TraktWatched, WatchedTitle as TraktWatchedTitle};
@@ -0,0 +1,281 @@
+#![warn(clippy::all)]
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::trakt::watched::{Error as WatchedError, Watched};
+
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Errors when querying Trakt.
+#[derive(Debug, thiserror::Error)]
+#[error("Error querying Trakt")]
+pub enum Error {
+  /// Networking error.
+  #[error("Networking error: {0}")]
+  Net(#[from] reqwest::Error),
+  /// Response or token parsing error.
+  #[error("Error parsing Trakt response: {0}")]
+  Json(#[from] serde_json::Error),
+  /// IO error.
+  #[error("IO error: {0}")]
+  Io(#[from] io::Error),
+  /// Error reading the watched titles.
+  #[error("{0}")]
+  Watched(#[from] WatchedError),
+  /// The device code expired before the user entered it.
+  #[error("The code expired before it was entered, try again")]
+  Expired,
+  /// The user denied access to their account.
+  #[error("Access to the Trakt account was denied")]
+  Denied,
+  /// The device code is invalid or was already used.
+  #[error("The code is invalid or was already used, try again")]
+  InvalidCode,
+}
+
+const API_URL: &str = "https://api.trakt.tv";
+const API_VERSION: &str = "2";
+
+/// The redirect URI of applications without a web server, as registered on Trakt.
+const REDIRECT_URI: &str = "urn:ietf:wg:oauth:2.0:oob";
+
+/// Tokens are refreshed when they expire within this margin, so that they do not expire
+/// in the middle of a sync.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn unix_now() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs())
+}
+
+/// A code to enter on the Trakt website to link an account.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceCode {
+  device_code: String,
+  user_code: String,
+  verification_url: String,
+  expires_in: u64,
+  interval: u64,
+}
+
+impl DeviceCode {
+  /// The code the user has to enter.
+  pub fn user_code(&self) -> &str {
+    &self.user_code
+  }
+
+  /// The address of the page where the user enters the code.
+  pub fn verification_url(&self) -> &str {
+    &self.verification_url
+  }
+}
+
+/// An access token to a Trakt account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Token {
+  access_token: String,
+  refresh_token: String,
+  created_at: u64,
+  expires_in: u64,
+}
+
+impl Token {
+  /// Whether the token has expired, or is about to.
+  pub fn is_expired(&self) -> bool {
+    self.expires_within(unix_now(), EXPIRY_MARGIN)
+  }
+
+  fn expires_within(&self, now: u64, margin: Duration) -> bool {
+    now.saturating_add(margin.as_secs()) >= self.created_at.saturating_add(self.expires_in)
+  }
+
+  /// Read a token from a file, or `None` if it does not exist.
+  ///
+  /// # Arguments
+  ///
+  /// * `path` - File written by [Token::save].
+  pub fn load(path: &Path) -> Result<Option<Self>, Error> {
+    match fs::read_to_string(path) {
+      Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(Error::Io(e)),
+    }
+  }
+
+  /// Write the token to a file only readable by the current user, replacing it.
+  ///
+  /// # Arguments
+  ///
+  /// * `path` - File to write to.
+  pub fn save(&self, path: &Path) -> Result<(), Error> {
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+
+    let tmp_path = path.with_extension("tmp");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    options.open(&tmp_path)?.write_all(serde_json::to_string(self)?.as_bytes())?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+  }
+}
+
+/// Client of the Trakt API.
+pub struct Service {
+  client: Client,
+  client_id: String,
+  client_secret: String,
+}
+
+impl Service {
+  /// Create a client of the Trakt API.
+  ///
+  /// # Arguments
+  ///
+  /// * `client_id` - Client ID of the Trakt application, see
+  ///   <https://trakt.tv/oauth/applications>.
+  /// * `client_secret` - Client secret of the Trakt application.
+  pub fn new(client_id: &str, client_secret: &str) -> Result<Self, Error> {
+    Ok(Self {
+      client: Client::builder().build()?,
+      client_id: client_id.to_owned(),
+      client_secret: client_secret.to_owned(),
+    })
+  }
+
+  fn post(&self, path: &str, body: serde_json::Value) -> Result<Response, Error> {
+    Ok(
+      self
+        .client
+        .post(format!("{API_URL}{path}"))
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.to_string())
+        .send()?,
+    )
+  }
+
+  fn get(&self, token: &Token, path: &str) -> RequestBuilder {
+    self
+      .client
+      .get(format!("{API_URL}{path}"))
+      .bearer_auth(&token.access_token)
+      .header("trakt-api-version", API_VERSION)
+      .header("trakt-api-key", &self.client_id)
+  }
+
+  /// Request a code for the user to enter on the Trakt website.
+  pub fn device_code(&self) -> Result<DeviceCode, Error> {
+    let body = serde_json::json!({ "client_id": self.client_id });
+    let response = self.post("/oauth/device/code", body)?.error_for_status()?;
+    Ok(serde_json::from_str(&response.text()?)?)
+  }
+
+  /// Wait for the user to enter a code on the Trakt website, and return the resulting token.
+  ///
+  /// # Arguments
+  ///
+  /// * `code` - The code returned by [Service::device_code].
+  pub fn wait_for_token(&self, code: &DeviceCode) -> Result<Token, Error> {
+    let deadline = Instant::now() + Duration::from_secs(code.expires_in);
+    let mut interval = Duration::from_secs(code.interval.max(1));
+    let body = serde_json::json!({
+      "code": code.device_code,
+      "client_id": self.client_id,
+      "client_secret": self.client_secret,
+    });
+
+    while Instant::now() < deadline {
+      std::thread::sleep(interval);
+
+      let response = self.post("/oauth/device/token", body.clone())?;
+      match response.status() {
+        StatusCode::OK => return Ok(serde_json::from_str(&response.text()?)?),
+        // Pending, the user has not entered the code yet.
+        StatusCode::BAD_REQUEST => {}
+        StatusCode::TOO_MANY_REQUESTS => interval += Duration::from_secs(1),
+        StatusCode::NOT_FOUND | StatusCode::CONFLICT => return Err(Error::InvalidCode),
+        StatusCode::GONE => return Err(Error::Expired),
+        StatusCode::IM_A_TEAPOT => return Err(Error::Denied),
+        _ => {
+          response.error_for_status()?;
+        }
+      }
+    }
+
+    Err(Error::Expired)
+  }
+
+  /// Exchange a token for a new one, since tokens expire after a few months.
+  ///
+  /// # Arguments
+  ///
+  /// * `token` - The token to refresh.
+  pub fn refresh(&self, token: &Token) -> Result<Token, Error> {
+    let body = serde_json::json!({
+      "refresh_token": token.refresh_token,
+      "client_id": self.client_id,
+      "client_secret": self.client_secret,
+      "redirect_uri": REDIRECT_URI,
+      "grant_type": "refresh_token",
+    });
+
+    let response = self.post("/oauth/token", body)?.error_for_status()?;
+    Ok(serde_json::from_str(&response.text()?)?)
+  }
+
+  /// Pull the watched history and ratings of movies and shows of the account.
+  ///
+  /// # Arguments
+  ///
+  /// * `token` - Access token to the account.
+  pub fn watched(&self, token: &Token) -> Result<Watched, Error> {
+    let fetch = |path: &str| -> Result<String, Error> {
+      debug!("Fetching {path} from Trakt");
+      Ok(self.get(token, path).send()?.error_for_status()?.text()?)
+    };
+
+    let watched = [fetch("/sync/watched/movies")?, fetch("/sync/watched/shows")?];
+    let ratings = [fetch("/sync/ratings/movies")?, fetch("/sync/ratings/shows")?];
+
+    Ok(Watched::from_json(&[&watched[0], &watched[1]], &[&ratings[0], &ratings[1]])?)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use crate::trakt::service::Token;
+
+  #[test]
+  fn test_token() {
+    let json = r#"{"access_token":"a","token_type":"bearer","expires_in":7776000,"refresh_token":"r","scope":"public","created_at":1700000000}"#;
+    let token: Token = serde_json::from_str(json).unwrap();
+    assert!(!token.expires_within(1700000000, Duration::from_secs(86400)));
+    assert!(token.expires_within(1707700000, Duration::from_secs(86400)));
+    assert!(token.expires_within(1707776000, Duration::ZERO));
+
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let path = dir.path().join("trakt/token.json");
+    assert_eq!(Token::load(&path).unwrap(), None);
+    token.save(&path).unwrap();
+    assert_eq!(Token::load(&path).unwrap(), Some(token));
+
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+      assert_eq!(mode & 0o777, 0o600);
+    }
+  }
+}
@@ -0,0 +1,252 @@
+#![warn(clippy::all)]
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::imdb::ImdbTitleId as TitleId;
+
+use fnv::FnvHashMap;
+use serde::{Deserialize, Serialize};
+
+/// Errors when reading or writing the list of watched titles.
+#[derive(Debug, thiserror::Error)]
+#[error("Error reading or writing watched titles")]
+pub enum Error {
+  /// Parsing error.
+  #[error("Error parsing watched titles: {0}")]
+  Json(#[from] serde_json::Error),
+  /// IO error.
+  #[error("IO error: {0}")]
+  Io(#[from] io::Error),
+}
+
+#[derive(Deserialize)]
+struct RawIds {
+  imdb: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawItem {
+  ids: RawIds,
+}
+
+#[derive(Deserialize)]
+struct RawWatched {
+  plays: u32,
+  last_watched_at: Option<String>,
+  movie: Option<RawItem>,
+  show: Option<RawItem>,
+}
+
+#[derive(Deserialize)]
+struct RawRating {
+  rating: u8,
+  movie: Option<RawItem>,
+  show: Option<RawItem>,
+}
+
+/// The IMDB ID of a movie or show from Trakt, if Trakt knows it.
+fn imdb_id(movie: Option<RawItem>, show: Option<RawItem>) -> Option<String> {
+  movie.or(show)?.ids.imdb.filter(|id| TitleId::try_from(id.as_str()).is_ok())
+}
+
+/// The watch status of a title.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchedTitle {
+  plays: u32,
+  last_watched_at: Option<String>,
+  rating: Option<u8>,
+}
+
+impl WatchedTitle {
+  /// How many times the title was watched, or for series, how many episodes were watched.
+  pub fn plays(&self) -> u32 {
+    self.plays
+  }
+
+  /// When the title was last watched, as an ISO 8601 timestamp.
+  pub fn last_watched_at(&self) -> Option<&str> {
+    self.last_watched_at.as_deref()
+  }
+
+  /// The date the title was last watched, as YYYY-MM-DD.
+  pub fn last_watched_date(&self) -> Option<&str> {
+    self.last_watched_at().map(|at| at.get(..10).unwrap_or(at))
+  }
+
+  /// The rating given to the title on Trakt, out of 10.
+  pub fn rating(&self) -> Option<u8> {
+    self.rating
+  }
+}
+
+impl fmt::Display for WatchedTitle {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match (self.last_watched_date(), self.rating) {
+      (Some(date), Some(rating)) => write!(f, "{date}, rated {rating}/10"),
+      (Some(date), None) => write!(f, "{date}"),
+      (None, Some(rating)) => write!(f, "rated {rating}/10"),
+      (None, None) => write!(f, "yes"),
+    }
+  }
+}
+
+/// Titles that were watched or rated on Trakt, by their IMDB ID.
+///
+/// Rated titles count as watched even when they are missing from the watched history, since
+/// titles are often rated without being marked as watched.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Watched {
+  titles: FnvHashMap<String, WatchedTitle>,
+}
+
+impl Watched {
+  /// Read the list of watched titles from a file, or `None` if it does not exist.
+  ///
+  /// # Arguments
+  ///
+  /// * `path` - File written by [Watched::save].
+  pub fn load(path: &Path) -> Result<Option<Self>, Error> {
+    match fs::read_to_string(path) {
+      Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(Error::Io(e)),
+    }
+  }
+
+  /// Write the list of watched titles to a file, replacing it.
+  ///
+  /// # Arguments
+  ///
+  /// * `path` - File to write to.
+  pub fn save(&self, path: &Path) -> Result<(), Error> {
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, serde_json::to_string(self)?)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+  }
+
+  /// Build the list from responses of the Trakt sync API. Titles without an IMDB ID are
+  /// ignored.
+  ///
+  /// # Arguments
+  ///
+  /// * `watched` - Bodies of the watched movies and shows responses.
+  /// * `ratings` - Bodies of the movie and show ratings responses.
+  pub(crate) fn from_json(watched: &[&str], ratings: &[&str]) -> Result<Self, Error> {
+    let mut titles: FnvHashMap<String, WatchedTitle> = FnvHashMap::default();
+
+    for json in watched {
+      for raw in serde_json::from_str::<Vec<RawWatched>>(json)? {
+        if let Some(id) = imdb_id(raw.movie, raw.show) {
+          let title = titles.entry(id).or_default();
+          title.plays = raw.plays;
+          title.last_watched_at = raw.last_watched_at;
+        }
+      }
+    }
+
+    for json in ratings {
+      for raw in serde_json::from_str::<Vec<RawRating>>(json)? {
+        if let Some(id) = imdb_id(raw.movie, raw.show) {
+          titles.entry(id).or_default().rating = Some(raw.rating);
+        }
+      }
+    }
+
+    Ok(Self { titles })
+  }
+
+  /// The watch status of a title, or `None` if it was not watched.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - IMDB ID of the title.
+  pub fn get(&self, id: &TitleId) -> Option<&WatchedTitle> {
+    self.titles.get(&id.to_string())
+  }
+
+  /// Whether a title was watched.
+  ///
+  /// # Arguments
+  ///
+  /// * `id` - IMDB ID of the title.
+  pub fn contains(&self, id: &TitleId) -> bool {
+    self.get(id).is_some()
+  }
+
+  /// The number of watched titles.
+  pub fn len(&self) -> usize {
+    self.titles.len()
+  }
+
+  /// Whether no titles were watched.
+  pub fn is_empty(&self) -> bool {
+    self.titles.is_empty()
+  }
+
+  /// The number of rated titles.
+  pub fn rated(&self) -> usize {
+    self.titles.values().filter(|title| title.rating.is_some()).count()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::imdb::ImdbTitleId as TitleId;
+  use crate::trakt::watched::Watched;
+
+  const MOVIES: &str = r#"[
+    {"plays":2,"last_watched_at":"2023-05-01T20:00:00.000Z","movie":{"title":"Fight Club","year":1999,"ids":{"trakt":432,"imdb":"tt0137523"}}},
+    {"plays":1,"last_watched_at":"2022-01-01T20:00:00.000Z","movie":{"title":"Unknown","ids":{"trakt":1,"imdb":null}}}
+  ]"#;
+
+  const SHOWS: &str = r#"[
+    {"plays":62,"last_watched_at":"2021-03-04T21:00:00.000Z","show":{"title":"Breaking Bad","ids":{"imdb":"tt0903747"}},"seasons":[]}
+  ]"#;
+
+  const RATINGS: &str = r#"[
+    {"rated_at":"2023-05-02T10:00:00.000Z","rating":9,"type":"movie","movie":{"ids":{"imdb":"tt0137523"}}},
+    {"rated_at":"2020-01-01T10:00:00.000Z","rating":6,"type":"movie","movie":{"ids":{"imdb":"tt0000001"}}}
+  ]"#;
+
+  #[test]
+  fn test_from_json() {
+    let watched = Watched::from_json(&[MOVIES, SHOWS], &[RATINGS, "[]"]).unwrap();
+    assert_eq!(watched.len(), 3);
+    assert_eq!(watched.rated(), 2);
+
+    let fight_club = watched.get(&TitleId::try_from("tt0137523").unwrap()).unwrap();
+    assert_eq!(fight_club.plays(), 2);
+    assert_eq!(fight_club.last_watched_date(), Some("2023-05-01"));
+    assert_eq!(fight_club.rating(), Some(9));
+    assert_eq!(fight_club.to_string(), "2023-05-01, rated 9/10");
+
+    let breaking_bad = watched.get(&TitleId::try_from("tt0903747").unwrap()).unwrap();
+    assert_eq!(breaking_bad.to_string(), "2021-03-04");
+
+    let rated_only = watched.get(&TitleId::try_from("tt0000001").unwrap()).unwrap();
+    assert_eq!(rated_only.plays(), 0);
+    assert_eq!(rated_only.to_string(), "rated 6/10");
+
+    assert!(!watched.contains(&TitleId::try_from("tt0000002").unwrap()));
+  }
+
+  #[test]
+  fn test_save_load() {
+    let dir = tempfile::Builder::new().prefix("tvrank_").tempdir().unwrap();
+    let path = dir.path().join("trakt/watched.json");
+    assert_eq!(Watched::load(&path).unwrap(), None);
+
+    let watched = Watched::from_json(&[MOVIES], &[RATINGS]).unwrap();
+    watched.save(&path).unwrap();
+    assert_eq!(Watched::load(&path).unwrap(), Some(watched));
+  }
+}
@@ -0,0 +1,86 @@
+#![warn(clippy::all)]
+
+//! Cooperative cancellation of long-running operations like database downloads.
+
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A token that can be used to cancel a long-running operation from another thread.
+///
+/// Clones of a token share their state, so cancelling any clone cancels all of them.
+#[derive(Debug, Clone, Default)]
+pub struct Cancellation {
+  cancelled: Arc<AtomicBool>,
+}
+
+impl Cancellation {
+  /// Construct a token that is not cancelled.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Request cancellation of the operations using this token.
+  pub fn cancel(&self) {
+    self.cancelled.store(true, Ordering::Relaxed);
+  }
+
+  /// Whether cancellation was requested.
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::Relaxed)
+  }
+}
+
+/// A reader that fails once its cancellation token is cancelled.
+pub struct CancellableReader<R> {
+  source: R,
+  cancellation: Cancellation,
+}
+
+impl<R: Read> CancellableReader<R> {
+  /// Construct a new `CancellableReader` object.
+  ///
+  /// # Arguments
+  ///
+  /// * `source` - The source (read stream) object.
+  /// * `cancellation` - The token that stops reading when cancelled.
+  pub fn new(source: R, cancellation: Cancellation) -> Self {
+    Self { source, cancellation }
+  }
+}
+
+impl<R: Read> Read for CancellableReader<R> {
+  fn read(&mut self, destination: &mut [u8]) -> io::Result<usize> {
+    if self.cancellation.is_cancelled() {
+      return Err(io::Error::other("Operation cancelled"));
+    }
+
+    self.source.read(destination)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn clones_share_state() {
+    let cancellation = Cancellation::new();
+    let clone = cancellation.clone();
+    assert!(!clone.is_cancelled());
+    cancellation.cancel();
+    assert!(clone.is_cancelled());
+  }
+
+  #[test]
+  fn reader() {
+    let cancellation = Cancellation::new();
+    let mut reader = CancellableReader::new(&b"abcdef"[..], cancellation.clone());
+
+    let mut buf = [0; 3];
+    assert_eq!(reader.read(&mut buf).unwrap(), 3);
+
+    cancellation.cancel();
+    assert!(reader.read(&mut buf).is_err());
+  }
+}
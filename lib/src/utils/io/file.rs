@@ -55,6 +55,23 @@ pub fn older_than(file: &Option<File>, duration: Duration) -> bool {
   true
 }
 
+/// Opens the given lock file, creating it if needed, and takes an exclusive advisory lock on
+/// it that is released once the returned file is closed, or returns `None` if another
+/// process holds the lock.
+///
+/// # Arguments
+///
+/// * `path` - Path of the lock file.
+pub fn try_lock(path: &Path) -> Result<Option<File>, Error> {
+  let file = File::options().create(true).truncate(false).write(true).open(path)?;
+
+  match file.try_lock() {
+    Ok(()) => Ok(Some(file)),
+    Err(fs::TryLockError::WouldBlock) => Ok(None),
+    Err(fs::TryLockError::Error(e)) => Err(Error::Io(e)),
+  }
+}
+
 /// Reads the contents of a file into a leaked static buffer.
 ///
 /// # Arguments
@@ -3,6 +3,7 @@
 
 //! Common utilities for things like networking and IO.
 
+#[cfg(feature = "fs")]
 pub mod file;
 pub mod net;
 pub mod progress;
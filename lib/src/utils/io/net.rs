@@ -1,16 +1,22 @@
 #![warn(clippy::all)]
 
 //! Helpers for networking.
+//!
+//! Only [make_fetcher] is available without the `fetch` feature, since it also reads dumps that
+//! were already downloaded.
 
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read};
 
 use crate::utils::io::progress::ProgressPipe;
 
 use flate2::bufread::GzDecoder;
+#[cfg(feature = "fetch")]
 use reqwest::blocking::{Client, Response};
+#[cfg(feature = "fetch")]
 use reqwest::Url;
 
 /// Errors when doing networking.
+#[cfg(feature = "fetch")]
 #[derive(Debug, thiserror::Error)]
 #[error("Networking error")]
 pub enum Error {
@@ -24,6 +30,7 @@ pub enum Error {
 /// # Arguments
 ///
 /// * `url` - The URL to send the GET request to.
+#[cfg(feature = "fetch")]
 pub fn get_response(url: Url) -> Result<Response, Error> {
   let client = Client::builder().build()?;
   let resp = client.get(url).send()?;
@@ -34,9 +41,9 @@ pub fn get_response(url: Url) -> Result<Response, Error> {
 ///
 /// # Arguments
 ///
-/// * `resp` - Response returned for the GET request.
+/// * `resp` - Response returned for the GET request, or a reader wrapping it.
 /// * `progress_fn` - Function to keep track of the download progress.
-pub fn make_fetcher(resp: Response, progress_fn: impl Fn(u64)) -> impl BufRead {
+pub fn make_fetcher(resp: impl Read, progress_fn: impl Fn(u64)) -> impl BufRead {
   let progress = ProgressPipe::new(resp, progress_fn);
   let reader = BufReader::new(progress);
   let decoder = GzDecoder::new(reader);
@@ -3,6 +3,10 @@
 
 //! Common utilities for things like parsing and IO.
 
+pub mod cancel;
 pub mod io;
+#[cfg(feature = "compat")]
+pub mod result;
+pub mod sample;
 pub mod search;
 pub mod tokens;
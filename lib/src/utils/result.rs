@@ -0,0 +1,7 @@
+#![warn(clippy::all)]
+
+//! Deprecated result type kept for compatibility with older versions of the library.
+
+/// A result with a boxed error.
+#[deprecated(since = "0.10.0", note = "use the error types of the individual modules instead")]
+pub type Res<T> = Result<T, Box<dyn std::error::Error>>;
@@ -0,0 +1,111 @@
+#![warn(clippy::all)]
+
+//! Reproducible random sampling.
+//!
+//! Samples only depend on the seed and the items they are drawn from, across platforms and
+//! versions of TVrank, so that a seed can be shared to reproduce a sample.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A seedable pseudo-random number generator (SplitMix64) drawing samples.
+///
+/// ```
+/// use tvrank::utils::sample::Sampler;
+///
+/// let a = Sampler::new(42).sample((0..100).collect(), 5);
+/// let b = Sampler::new(42).sample((0..100).collect(), 5);
+/// assert_eq!(a, b);
+/// assert_eq!(a.len(), 5);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Sampler {
+  state: u64,
+}
+
+impl Sampler {
+  /// Create a sampler from a seed.
+  ///
+  /// # Arguments
+  ///
+  /// * `seed` - The seed, the same seed always draws the same samples.
+  pub fn new(seed: u64) -> Self {
+    Self { state: seed }
+  }
+
+  /// A seed that differs between calls, for samples that do not need to be reproduced.
+  pub fn random_seed() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    hasher.write_u128(now.as_nanos());
+    hasher.write_u32(std::process::id());
+    hasher.finish()
+  }
+
+  fn next_u64(&mut self) -> u64 {
+    self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = self.state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+  }
+
+  /// A uniformly distributed number in `0..bound`.
+  fn below(&mut self, bound: u64) -> u64 {
+    // Reject the values of the last incomplete range to avoid modulo bias.
+    let zone = u64::MAX - u64::MAX % bound;
+    loop {
+      let value = self.next_u64();
+      if value < zone {
+        return value % bound;
+      }
+    }
+  }
+
+  /// Draw up to `count` items without replacement, in random order.
+  ///
+  /// # Arguments
+  ///
+  /// * `items` - The items to draw from.
+  /// * `count` - The number of items to draw.
+  pub fn sample<T>(&mut self, mut items: Vec<T>, count: usize) -> Vec<T> {
+    let count = count.min(items.len());
+
+    for i in 0..count {
+      let j = i + self.below((items.len() - i) as u64) as usize;
+      items.swap(i, j);
+    }
+
+    items.truncate(count);
+    items
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::utils::sample::Sampler;
+
+  #[test]
+  fn test_reproducible() {
+    let items: Vec<u32> = (0..1000).collect();
+    let sample = Sampler::new(7).sample(items.clone(), 10);
+    assert_eq!(sample, Sampler::new(7).sample(items.clone(), 10));
+    assert_ne!(sample, Sampler::new(8).sample(items.clone(), 10));
+
+    // Samples must not change across versions, or shared seeds would draw other titles.
+    assert_eq!(Sampler::new(7).sample(items, 3), [487, 727, 212]);
+  }
+
+  #[test]
+  fn test_sample() {
+    let mut sampler = Sampler::new(1);
+    let mut sample = sampler.sample((0..10).collect::<Vec<u32>>(), 10);
+    sample.sort_unstable();
+    assert_eq!(sample, (0..10).collect::<Vec<_>>());
+
+    assert_eq!(sampler.sample(vec![1, 2, 3], 5).len(), 3);
+    assert!(sampler.sample(Vec::<u32>::new(), 5).is_empty());
+    assert!(sampler.sample(vec![1, 2, 3], 0).is_empty());
+  }
+}
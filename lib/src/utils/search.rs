@@ -1,7 +1,16 @@
-//! A string type used to ensure that search keywords are lowercase and non-empty.
+//! A string type used to ensure that search keywords are normalized and non-empty, and
+//! keyword matching against title names.
 
 pub use self::Error as SearchStringError;
 
+use std::borrow::Cow;
+
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, Match, MatchKind as ACMatchKind};
+use deunicode::deunicode;
+use fnv::FnvHashSet;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
 /// Error type for search string construction.
 #[derive(Debug, thiserror::Error)]
 #[error("Search string error")]
@@ -11,16 +20,71 @@ pub enum Error {
   IsEmpty,
 }
 
-/// A string type used to ensure that search keywords are lowercase and non-empty.
+/// Lowercase a title name in its canonical composed form (NFC), so that names with accents
+/// are indexed the same regardless of how their accents were encoded.
+///
+/// # Arguments
+///
+/// * `value` - The title name.
+pub(crate) fn lowercase(value: &str) -> String {
+  value.nfc().collect::<String>().to_lowercase()
+}
+
+/// Normalize a title name or keyword for searching, by folding its diacritics (NFKD
+/// decomposition without the combining marks), transliterating what is left to ASCII and
+/// lowercasing it, so that e.g. "Lumière", "LUMIERE" and "Lumie\u{300}re" are the same.
+///
+/// # Arguments
+///
+/// * `value` - The title name or keyword.
+pub(crate) fn normalize(value: &str) -> String {
+  let folded: String = value.nfkd().filter(|&c| !is_combining_mark(c)).collect();
+  deunicode(&folded).to_lowercase()
+}
+
+/// A string type used to ensure that search keywords are normalized and non-empty, which
+/// is the only way titles can be looked up by name.
 pub struct SearchString {
   contents: String,
+  excluded: bool,
+  /// Other forms of the keyword that keyword queries also accept, e.g. "aliens" for "alien".
+  variants: Vec<String>,
 }
 
 impl SearchString {
+  /// Create an excluded keyword: keyword queries leave out the titles that contain it.
+  /// Queries by title ignore whether a search string is excluded.
+  ///
+  /// # Arguments
+  ///
+  /// * `value` - The keyword, without any leading `-`.
+  pub fn excluded(value: &str) -> Result<Self, Error> {
+    Ok(Self { excluded: true, ..Self::try_from(value)? })
+  }
+
   /// Return the search string as a string slice.
   pub fn as_str(&self) -> &str {
     &self.contents
   }
+
+  /// Whether titles containing this keyword are left out of keyword queries.
+  pub fn is_excluded(&self) -> bool {
+    self.excluded
+  }
+
+  /// Expand the keyword to its light English stemming variants (singular and plural, "-ing"
+  /// and "-ed" forms), so that keyword queries accept any of them, e.g. "alien" also finds
+  /// "Aliens" when keywords are matched as whole words and "aliens" also finds "Alien".
+  /// Queries by title ignore the variants of a search string.
+  pub fn stemmed(self) -> Self {
+    let variants = stem_variants(&self.contents);
+    Self { variants, ..self }
+  }
+
+  /// The keyword followed by its variants, all of which keyword queries accept.
+  pub(crate) fn forms(&self) -> impl Iterator<Item = &str> {
+    std::iter::once(self.as_str()).chain(self.variants.iter().map(String::as_str))
+  }
 }
 
 impl AsRef<[u8]> for SearchString {
@@ -43,7 +107,438 @@ impl TryFrom<&str> for SearchString {
       return Err(Error::IsEmpty);
     }
 
-    Ok(Self { contents: value.to_lowercase() })
+    Ok(Self { contents: normalize(value), excluded: false, variants: Vec::new() })
+  }
+}
+
+/// Words shorter than this many letters are not stemmed, since too few of their letters are
+/// left to tell them apart.
+const MIN_STEM_LEN: usize = 3;
+
+fn is_vowel(c: char) -> bool {
+  matches!(c, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+/// Strip the plural, "-ing" and "-ed" suffixes of an English word, e.g. "aliens" and
+/// "aliened" become "alien", "stories" becomes "story" and "running" becomes "run".
+///
+/// # Arguments
+///
+/// * `word` - The lowercase word.
+fn stem(word: &str) -> String {
+  // Consonants doubled after a single vowel are undoubled, e.g. "runn" and "stopp" but not
+  // "add" or "fall".
+  let undouble = |stem: &str| {
+    let mut chars = stem.chars().rev();
+    match (chars.next(), chars.next(), chars.next(), chars.next()) {
+      (Some(a), Some(b), Some(vowel), Some(consonant))
+        if a == b && !matches!(a, 'l' | 's' | 'z') && is_vowel(vowel) && !is_vowel(consonant) =>
+      {
+        stem[..stem.len() - 1].to_owned()
+      }
+      _ => stem.to_owned(),
+    }
+  };
+  let long_enough = |stem: &str| stem.len() >= MIN_STEM_LEN;
+
+  if let Some(stem) = word.strip_suffix("ies").filter(|stem| long_enough(stem)) {
+    format!("{stem}y")
+  } else if let Some(stem) = word.strip_suffix("ied").filter(|stem| long_enough(stem)) {
+    format!("{stem}y")
+  } else if let Some(stem) = word.strip_suffix("sses") {
+    format!("{stem}ss")
+  } else if let Some(stem) = word
+    .strip_suffix("es")
+    .filter(|stem| long_enough(stem) && ["x", "z", "ch", "sh"].iter().any(|end| stem.ends_with(end)))
+  {
+    stem.to_owned()
+  } else if let Some(stem) = word
+    .strip_suffix('s')
+    .filter(|stem| long_enough(stem) && !["s", "u", "i"].iter().any(|end| stem.ends_with(end)))
+  {
+    stem.to_owned()
+  } else if let Some(stem) = word.strip_suffix("ing").filter(|stem| long_enough(stem)) {
+    undouble(stem)
+  } else if let Some(stem) = word.strip_suffix("ed").filter(|stem| long_enough(stem)) {
+    undouble(stem)
+  } else {
+    word.to_owned()
+  }
+}
+
+/// The forms of a keyword other than itself that keyword queries accept when stemming: its
+/// stem followed by the plural, "-ing" and "-ed" forms of the stem. Only keywords made of
+/// ASCII letters are stemmed.
+///
+/// # Arguments
+///
+/// * `keyword` - The normalized keyword.
+fn stem_variants(keyword: &str) -> Vec<String> {
+  if keyword.len() < MIN_STEM_LEN || !keyword.chars().all(|c| c.is_ascii_lowercase()) {
+    return Vec::new();
+  }
+
+  let stem = stem(keyword);
+  let mut forms = vec![stem.clone()];
+
+  if let Some(base) = stem.strip_suffix('y') {
+    forms.extend([format!("{base}ies"), format!("{base}ied"), format!("{stem}ing")]);
+  } else if let Some(base) = stem.strip_suffix('e') {
+    forms.extend([format!("{stem}s"), format!("{stem}d"), format!("{base}ing")]);
+  } else if ["s", "x", "z", "ch", "sh"].iter().any(|end| stem.ends_with(end)) {
+    forms.extend([format!("{stem}es"), format!("{stem}ed"), format!("{stem}ing")]);
+  } else {
+    forms.extend([format!("{stem}s"), format!("{stem}ed"), format!("{stem}ing")]);
+
+    // Short words ending with a consonant after a vowel double it, e.g. "running".
+    let mut chars = stem.chars().rev();
+    if let (Some(last), Some(before)) = (chars.next(), chars.next()) {
+      if stem.len() <= 4 && !is_vowel(last) && !matches!(last, 'w' | 'x' | 'y') && is_vowel(before) {
+        forms.extend([format!("{stem}{last}ed"), format!("{stem}{last}ing")]);
+      }
+    }
+  }
+
+  let mut seen = FnvHashSet::default();
+  seen.insert(keyword.to_owned());
+  forms.retain(|form| seen.insert(form.clone()));
+  forms
+}
+
+/// How keywords are matched against title names when searching by keywords.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum KeywordMatchKind {
+  /// Report every keyword occurrence, including ones overlapping other keywords.
+  ///
+  /// With keywords "man" and "mandala", the title "Mandala" matches both keywords.
+  #[default]
+  Overlapping,
+  /// Report non-overlapping occurrences, preferring the keyword that was given first.
+  ///
+  /// With keywords "man" and "mandala", the title "Mandala" only matches "man".
+  LeftmostFirst,
+  /// Report non-overlapping occurrences, preferring the longest keyword.
+  ///
+  /// With keywords "man" and "mandala", the title "Mandala" only matches "mandala".
+  LeftmostLongest,
+  /// Report occurrences of keywords as whole words, delimited by whitespace, punctuation or
+  /// the ends of the title name.
+  ///
+  /// With keyword "up", the title "Up" matches but the title "Supernova" does not.
+  WordBoundary,
+}
+
+/// Languages whose leading articles exact searches by title can do without, so that e.g.
+/// "Matrix" finds "The Matrix" and "Fabuleux destin d'Amelie Poulain" finds "Le fabuleux
+/// destin d'Amélie Poulain".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+  /// "the", "a", "an"
+  English,
+  /// "le", "la", "les", "l'", "un", "une"
+  French,
+  /// "der", "die", "das", "ein", "eine"
+  German,
+  /// "el", "la", "los", "las", "un", "una"
+  Spanish,
+  /// "il", "lo", "la", "i", "gli", "le", "l'", "un", "uno", "una"
+  Italian,
+  /// "o", "a", "os", "as", "um", "uma"
+  Portuguese,
+  /// "de", "het", "een"
+  Dutch,
+}
+
+impl Language {
+  /// All the languages whose leading articles are known.
+  pub const ALL: &'static [Language] = &[
+    Language::English,
+    Language::French,
+    Language::German,
+    Language::Spanish,
+    Language::Italian,
+    Language::Portuguese,
+    Language::Dutch,
+  ];
+
+  /// The articles of the language, normalized like search strings. Elided articles end
+  /// with an apostrophe and are directly followed by the next word.
+  fn articles(self) -> &'static [&'static str] {
+    match self {
+      Language::English => &["the", "a", "an"],
+      Language::French => &["le", "la", "les", "l'", "un", "une"],
+      Language::German => &["der", "die", "das", "ein", "eine"],
+      Language::Spanish => &["el", "la", "los", "las", "un", "una"],
+      Language::Italian => &["il", "lo", "la", "i", "gli", "le", "l'", "un", "uno", "una"],
+      Language::Portuguese => &["o", "a", "os", "as", "um", "uma"],
+      Language::Dutch => &["de", "het", "een"],
+    }
+  }
+}
+
+/// Strip the leading article of a normalized title name or search string, if it has one
+/// of the articles of the given languages and more than the article.
+///
+/// # Arguments
+///
+/// * `name` - The normalized title name or search string.
+/// * `languages` - The languages of the articles to strip.
+pub(crate) fn strip_article<'a>(name: &'a str, languages: &[Language]) -> &'a str {
+  for article in languages.iter().flat_map(|language| language.articles()) {
+    let Some(rest) = name.strip_prefix(article) else {
+      continue;
+    };
+
+    let rest = if article.ends_with('\'') {
+      rest
+    } else if let Some(rest) = rest.strip_prefix(' ') {
+      rest
+    } else {
+      continue;
+    };
+
+    let rest = rest.trim_start();
+    if !rest.is_empty() {
+      return rest;
+    }
+  }
+
+  name
+}
+
+/// The leading articles that exact searches by title can do without, as the languages
+/// they are from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StopWords {
+  languages: Cow<'static, [Language]>,
+}
+
+impl Default for StopWords {
+  /// The articles of all known languages.
+  fn default() -> Self {
+    Self { languages: Cow::Borrowed(Language::ALL) }
+  }
+}
+
+impl StopWords {
+  /// The articles of the given languages.
+  ///
+  /// # Arguments
+  ///
+  /// * `languages` - The languages of the articles.
+  pub fn new(languages: &[Language]) -> Self {
+    Self { languages: Cow::Owned(languages.to_vec()) }
+  }
+
+  /// Returns the languages of the articles.
+  pub fn languages(&self) -> &[Language] {
+    &self.languages
+  }
+
+  /// Strip the leading article of a normalized title name or search string, if it has one.
+  ///
+  /// # Arguments
+  ///
+  /// * `name` - The normalized title name or search string.
+  pub(crate) fn strip<'a>(&self, name: &'a str) -> &'a str {
+    strip_article(name, &self.languages)
+  }
+}
+
+/// Which names of titles are searched.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SearchScope {
+  /// Search both the primary and the original names of titles.
+  #[default]
+  All,
+  /// Search only the primary names of titles, which are usually the English names.
+  PrimaryTitles,
+  /// Search only the original names of titles, in their original language.
+  ///
+  /// The primary name of a title without a different original name is its original name.
+  OriginalTitles,
+}
+
+/// How titles are searched by name, regular expression or keywords, given per query with
+/// [Imdb::with_options](crate::imdb::Imdb::with_options).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SearchOptions {
+  pub(crate) match_kind: KeywordMatchKind,
+  pub(crate) scope: SearchScope,
+  pub(crate) stop_words: StopWords,
+}
+
+impl SearchOptions {
+  /// Construct the default search options: overlapping keyword matches, all names of titles
+  /// and the articles of all known languages.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Set how keywords are matched against title names in keyword queries.
+  ///
+  /// # Arguments
+  ///
+  /// * `kind` - The keyword match kind.
+  pub fn match_kind(mut self, kind: KeywordMatchKind) -> Self {
+    self.match_kind = kind;
+    self
+  }
+
+  /// Set which names of titles are searched in queries by title, regular expression or
+  /// keywords, e.g. to only search original names when localized names collide with
+  /// English ones.
+  ///
+  /// # Arguments
+  ///
+  /// * `scope` - The search scope.
+  pub fn scope(mut self, scope: SearchScope) -> Self {
+    self.scope = scope;
+    self
+  }
+
+  /// Set the leading articles that normalized queries by title do without.
+  ///
+  /// # Arguments
+  ///
+  /// * `stop_words` - The articles.
+  pub fn stop_words(mut self, stop_words: StopWords) -> Self {
+    self.stop_words = stop_words;
+    self
+  }
+}
+
+/// Keywords of at most this many characters are matched as whole words regardless of the
+/// match kind, since they occur inside far too many unrelated words.
+const SHORT_KEYWORD_LEN: usize = 2;
+
+/// Checks whether a title name contains all of a set of keywords and none of a set of
+/// excluded keywords.
+pub(crate) struct KeywordMatcher {
+  /// Searcher of the keywords that can occur anywhere in title names.
+  searcher: AhoCorasick,
+  /// The keyword of each pattern of the searcher, since keywords have a pattern per form.
+  keyword_of: Vec<usize>,
+  kind: KeywordMatchKind,
+  keywords_len: usize,
+  /// Searcher of the keywords that have to occur as whole words.
+  word_searcher: AhoCorasick,
+  word_of: Vec<usize>,
+  words_len: usize,
+  /// Searcher of the excluded keywords.
+  excluded_searcher: AhoCorasick,
+  /// Whether the excluded keyword of each pattern only excludes titles containing it as a
+  /// whole word.
+  excluded_words: Vec<bool>,
+}
+
+/// The patterns of the forms of the given keywords, and the keyword of each pattern.
+///
+/// # Arguments
+///
+/// * `keywords` - The keywords to search for.
+fn patterns<'a>(keywords: &[&'a SearchString]) -> (Vec<&'a str>, Vec<usize>) {
+  keywords
+    .iter()
+    .enumerate()
+    .flat_map(|(index, keyword)| keyword.forms().map(move |form| (form, index)))
+    .unzip()
+}
+
+impl KeywordMatcher {
+  /// Create a matcher for the given keywords.
+  ///
+  /// # Arguments
+  ///
+  /// * `keywords` - Keywords that must all be present in a title name, except the excluded
+  ///   ones which must all be absent.
+  /// * `kind` - How keyword occurrences are matched.
+  pub(crate) fn new(keywords: &[SearchString], kind: KeywordMatchKind) -> Self {
+    let match_kind = match kind {
+      KeywordMatchKind::Overlapping | KeywordMatchKind::WordBoundary => ACMatchKind::Standard,
+      KeywordMatchKind::LeftmostFirst => ACMatchKind::LeftmostFirst,
+      KeywordMatchKind::LeftmostLongest => ACMatchKind::LeftmostLongest,
+    };
+
+    let is_word = |keyword: &SearchString| {
+      kind == KeywordMatchKind::WordBoundary || keyword.as_str().chars().count() <= SHORT_KEYWORD_LEN
+    };
+
+    let (excluded, keywords): (Vec<_>, Vec<_>) = keywords.iter().partition(|keyword| keyword.is_excluded());
+    let (words, keywords): (Vec<_>, Vec<_>) = keywords.into_iter().partition(|keyword| is_word(keyword));
+
+    let (keyword_patterns, keyword_of) = patterns(&keywords);
+    let (word_patterns, word_of) = patterns(&words);
+    let (excluded_patterns, excluded_of) = patterns(&excluded);
+
+    let searcher = AhoCorasickBuilder::new().match_kind(match_kind).build(keyword_patterns);
+    let word_searcher = AhoCorasickBuilder::new().build(word_patterns);
+    let excluded_searcher = AhoCorasickBuilder::new().build(excluded_patterns);
+    let excluded_words = excluded_of.into_iter().map(|index| is_word(excluded[index])).collect();
+
+    Self {
+      searcher,
+      keyword_of,
+      kind,
+      keywords_len: keywords.len(),
+      word_searcher,
+      word_of,
+      words_len: words.len(),
+      excluded_searcher,
+      excluded_words,
+    }
+  }
+
+  /// Whether all the keywords and none of the excluded keywords were found in the given
+  /// title name.
+  ///
+  /// # Arguments
+  ///
+  /// * `name` - Lowercase title name to search in.
+  pub(crate) fn matches_all(&self, name: &str) -> bool {
+    let matches: FnvHashSet<_> = match self.kind {
+      KeywordMatchKind::Overlapping | KeywordMatchKind::WordBoundary => self
+        .searcher
+        .find_overlapping_iter(name)
+        .map(|mat| self.keyword_of[mat.pattern()])
+        .collect(),
+      KeywordMatchKind::LeftmostFirst | KeywordMatchKind::LeftmostLongest => self
+        .searcher
+        .find_iter(name)
+        .map(|mat| self.keyword_of[mat.pattern()])
+        .collect(),
+    };
+
+    if matches.len() != self.keywords_len {
+      return false;
+    }
+
+    let is_word = |mat: &Match| {
+      let is_boundary = |c: Option<char>| c.is_none_or(|c| !c.is_alphanumeric());
+      is_boundary(name[..mat.start()].chars().next_back()) && is_boundary(name[mat.end()..].chars().next())
+    };
+
+    if !self.excluded_words.is_empty()
+      && self
+        .excluded_searcher
+        .find_overlapping_iter(name)
+        .any(|mat| !self.excluded_words[mat.pattern()] || is_word(&mat))
+    {
+      return false;
+    }
+
+    if self.words_len == 0 {
+      return true;
+    }
+
+    let words: FnvHashSet<_> = self
+      .word_searcher
+      .find_overlapping_iter(name)
+      .filter(is_word)
+      .map(|mat| self.word_of[mat.pattern()])
+      .collect();
+
+    words.len() == self.words_len
   }
 }
 
@@ -51,6 +546,13 @@ impl TryFrom<&str> for SearchString {
 mod tests {
   use super::*;
 
+  fn keywords(keywords: &[&str]) -> Vec<SearchString> {
+    keywords
+      .iter()
+      .map(|&keyword| SearchString::try_from(keyword).unwrap())
+      .collect()
+  }
+
   #[test]
   #[should_panic]
   fn empty() {
@@ -70,4 +572,194 @@ mod tests {
     let search_string = SearchString::try_from(value).unwrap();
     assert_eq!(value.to_lowercase(), search_string.as_str());
   }
+
+  #[test]
+  fn non_ascii() {
+    let search_string = SearchString::try_from("La Sortie de l'Usine LUMIÈRE").unwrap();
+    assert_eq!("la sortie de l'usine lumiere", search_string.as_str());
+
+    let search_string = SearchString::excluded("Amélie").unwrap();
+    assert_eq!("amelie", search_string.as_str());
+    assert!(search_string.is_excluded());
+
+    for (value, expected) in [
+      ("Ame\u{301}lie", "amelie"),
+      ("Die Brücke", "die brucke"),
+      ("STRAẞE", "strasse"),
+      ("Cría Cuervos", "cria cuervos"),
+      ("Señora", "senora"),
+      ("ﬁn", "fin"),
+    ] {
+      assert_eq!(SearchString::try_from(value).unwrap().as_str(), expected);
+    }
+
+    assert_eq!(super::lowercase("Ame\u{301}lie"), super::lowercase("AMÉLIE"));
+  }
+
+  #[test]
+  fn overlapping_keywords() {
+    let matcher = KeywordMatcher::new(&keywords(&["man", "mandala"]), KeywordMatchKind::Overlapping);
+    assert!(matcher.matches_all("mandala"));
+    assert!(!matcher.matches_all("batman"));
+  }
+
+  #[test]
+  fn leftmost_first_misses_overlapping_keywords() {
+    let matcher = KeywordMatcher::new(&keywords(&["man", "mandala"]), KeywordMatchKind::LeftmostFirst);
+    assert!(!matcher.matches_all("mandala"));
+    assert!(!matcher.matches_all("mandala man"));
+
+    let matcher = KeywordMatcher::new(&keywords(&["mandala", "man"]), KeywordMatchKind::LeftmostFirst);
+    assert!(!matcher.matches_all("mandala"));
+    assert!(matcher.matches_all("mandala man"));
+  }
+
+  #[test]
+  fn leftmost_longest_misses_overlapping_keywords() {
+    let matcher = KeywordMatcher::new(&keywords(&["man", "mandala"]), KeywordMatchKind::LeftmostLongest);
+    assert!(!matcher.matches_all("mandala"));
+    assert!(matcher.matches_all("a man and a mandala"));
+  }
+
+  #[test]
+  fn word_boundary() {
+    let matcher = KeywordMatcher::new(&keywords(&["up", "man"]), KeywordMatchKind::WordBoundary);
+    assert!(matcher.matches_all("up, man!"));
+    assert!(matcher.matches_all("man up"));
+    assert!(!matcher.matches_all("supernova man"));
+    assert!(!matcher.matches_all("up batman"));
+
+    let matcher = KeywordMatcher::new(&keywords(&["l'usine"]), KeywordMatchKind::WordBoundary);
+    assert!(matcher.matches_all("la sortie de l'usine"));
+  }
+
+  #[test]
+  fn stop_words() {
+    let all = StopWords::default();
+    assert_eq!(all.strip("the matrix"), "matrix");
+    assert_eq!(all.strip("l'usine"), "usine");
+    assert_eq!(all.strip("le fabuleux destin d'amelie poulain"), "fabuleux destin d'amelie poulain");
+    assert_eq!(all.strip("das boot"), "boot");
+    assert_eq!(all.strip("theodora"), "theodora");
+    assert_eq!(all.strip("the"), "the");
+    assert_eq!(all.strip("matrix"), "matrix");
+
+    let english = StopWords::new(&[Language::English]);
+    assert_eq!(english.strip("the matrix"), "matrix");
+    assert_eq!(english.strip("das boot"), "das boot");
+    assert_eq!(english.languages(), [Language::English]);
+  }
+
+  #[test]
+  fn excluded_keywords() {
+    let mut keywords = keywords(&["batman"]);
+    keywords.push(SearchString::excluded("LEGO").unwrap());
+    keywords.push(SearchString::excluded("of").unwrap());
+    assert!(keywords[1].is_excluded());
+    assert_eq!(keywords[1].as_str(), "lego");
+
+    for kind in
+      [KeywordMatchKind::Overlapping, KeywordMatchKind::LeftmostFirst, KeywordMatchKind::WordBoundary]
+    {
+      let matcher = KeywordMatcher::new(&keywords, kind);
+      assert!(matcher.matches_all("batman begins"));
+      assert!(!matcher.matches_all("the lego batman movie"));
+      assert!(!matcher.matches_all("batman: mask of the phantasm"));
+      assert!(matcher.matches_all("batman: the offering"));
+      assert!(!matcher.matches_all("superman"));
+    }
+
+    let matcher = KeywordMatcher::new(&keywords, KeywordMatchKind::Overlapping);
+    assert!(!matcher.matches_all("batman and legolas"));
+  }
+
+  #[test]
+  fn phrases() {
+    let matcher = KeywordMatcher::new(&keywords(&["new york", "stories"]), KeywordMatchKind::Overlapping);
+    assert!(matcher.matches_all("new york stories"));
+    assert!(!matcher.matches_all("new stories of york"));
+
+    let matcher = KeywordMatcher::new(&keywords(&["new york"]), KeywordMatchKind::WordBoundary);
+    assert!(matcher.matches_all("escape from new york"));
+    assert!(!matcher.matches_all("new yorker"));
+  }
+
+  #[test]
+  fn short_keywords_match_whole_words() {
+    for kind in
+      [KeywordMatchKind::Overlapping, KeywordMatchKind::LeftmostFirst, KeywordMatchKind::LeftmostLongest]
+    {
+      let matcher = KeywordMatcher::new(&keywords(&["up", "nova"]), kind);
+      assert!(matcher.matches_all("up and supernova"));
+      assert!(!matcher.matches_all("supernova"));
+      assert!(!matcher.matches_all("upside supernova"));
+    }
+  }
+
+  #[test]
+  fn non_overlapping_keywords() {
+    for kind in [
+      KeywordMatchKind::Overlapping,
+      KeywordMatchKind::LeftmostFirst,
+      KeywordMatchKind::LeftmostLongest,
+      KeywordMatchKind::WordBoundary,
+    ] {
+      let matcher = KeywordMatcher::new(&keywords(&["corbett", "courtney"]), kind);
+      assert!(matcher.matches_all("corbett and courtney before the kinetograph"));
+      assert!(!matcher.matches_all("corbett"));
+    }
+  }
+
+  #[test]
+  fn stems() {
+    for (word, expected) in [
+      ("aliens", "alien"),
+      ("alien", "alien"),
+      ("stories", "story"),
+      ("married", "marry"),
+      ("boxes", "box"),
+      ("glasses", "glass"),
+      ("running", "run"),
+      ("stopped", "stop"),
+      ("added", "add"),
+      ("falling", "fall"),
+      ("walked", "walk"),
+      ("virus", "virus"),
+      ("analysis", "analysis"),
+      ("bus", "bus"),
+    ] {
+      assert_eq!(stem(word), expected, "{word}");
+    }
+  }
+
+  #[test]
+  fn stemmed_keywords() {
+    let stemmed = |values: &[&str]| -> Vec<SearchString> {
+      values
+        .iter()
+        .map(|&value| SearchString::try_from(value).unwrap().stemmed())
+        .collect()
+    };
+
+    let matcher = KeywordMatcher::new(&stemmed(&["aliens"]), KeywordMatchKind::WordBoundary);
+    assert!(matcher.matches_all("alien"));
+    assert!(matcher.matches_all("aliens"));
+    assert!(!matcher.matches_all("alienated"));
+
+    let matcher = KeywordMatcher::new(&stemmed(&["alien"]), KeywordMatchKind::WordBoundary);
+    assert!(matcher.matches_all("aliens"));
+
+    let matcher = KeywordMatcher::new(&keywords(&["alien"]), KeywordMatchKind::WordBoundary);
+    assert!(!matcher.matches_all("aliens"));
+
+    let matcher = KeywordMatcher::new(&stemmed(&["story", "toy"]), KeywordMatchKind::Overlapping);
+    assert!(matcher.matches_all("toy stories"));
+
+    let matcher = KeywordMatcher::new(&stemmed(&["running"]), KeywordMatchKind::LeftmostLongest);
+    assert!(matcher.matches_all("the running man"));
+    assert!(matcher.matches_all("run lola run"));
+
+    let matcher = KeywordMatcher::new(&stemmed(&["batman", "-legos"]), KeywordMatchKind::Overlapping);
+    assert!(!matcher.matches_all("the lego batman movie"));
+  }
 }
@@ -0,0 +1,255 @@
+#![warn(clippy::all)]
+
+//! Python bindings of TVrank, for notebooks and scripts that rank libraries of movies and
+//! series.
+//!
+//! The module is built with maturin, see `pyproject.toml`.
+
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+
+use tvrank::imdb::{Imdb as ImdbService, ImdbCacheCompression, ImdbQuery, ImdbTitle, ImdbTitleId};
+use tvrank::utils::cancel::Cancellation;
+use tvrank::utils::search::SearchString;
+
+create_exception!(tvrank, TvrankError, PyException, "Error raised by TVrank.");
+
+/// Whether movies or series are queried.
+#[pyclass(module = "tvrank")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Query {
+  Movies,
+  Series,
+}
+
+impl From<Query> for ImdbQuery {
+  fn from(query: Query) -> Self {
+    match query {
+      Query::Movies => ImdbQuery::Movies,
+      Query::Series => ImdbQuery::Series,
+    }
+  }
+}
+
+/// A movie or series of the IMDB databases.
+///
+/// Titles are not copied out of the databases, they keep the databases they were found in
+/// alive instead.
+#[pyclass(module = "tvrank", frozen)]
+struct Title {
+  _imdb: Arc<ImdbService>,
+  title: &'static ImdbTitle<'static>,
+}
+
+impl Title {
+  fn new(imdb: &Arc<ImdbService>, title: &ImdbTitle) -> Self {
+    // SAFETY: `title` was found in `imdb` and borrows from it: titles are parsed once into
+    // vectors owned by the service, and their fields borrow the contents of the database
+    // files, also owned by the service. Neither is modified, moved or freed before the
+    // service is dropped, and the service cannot be mutated once it is shared through the
+    // `Arc`. This object keeps a
+    // reference to that `Arc` for as long as it lives, and only hands out borrows of the
+    // title tied to `&self`, so the title outlives every use of it.
+    let title = unsafe { std::mem::transmute::<&ImdbTitle, &'static ImdbTitle<'static>>(title) };
+    Self { _imdb: Arc::clone(imdb), title }
+  }
+}
+
+#[pymethods]
+impl Title {
+  /// The IMDB ID, e.g. "tt0133093".
+  #[getter]
+  fn id(&self) -> String {
+    self.title.title_id().to_string()
+  }
+
+  /// The type, e.g. "Movie" or "TV Series".
+  #[getter]
+  fn title_type(&self) -> String {
+    self.title.title_type().to_string()
+  }
+
+  #[getter]
+  fn primary_title(&self) -> &str {
+    self.title.primary_title()
+  }
+
+  /// The title in its original language, if it differs from the primary title.
+  #[getter]
+  fn original_title(&self) -> Option<&str> {
+    self.title.original_title()
+  }
+
+  #[getter]
+  fn is_adult(&self) -> bool {
+    self.title.is_adult()
+  }
+
+  #[getter]
+  fn year(&self) -> Option<u16> {
+    self.title.start_year()
+  }
+
+  #[getter]
+  fn runtime_minutes(&self) -> Option<u64> {
+    self.title.runtime().map(|runtime| runtime.as_secs() / 60)
+  }
+
+  /// The rating out of 100.
+  #[getter]
+  fn rating(&self) -> Option<u8> {
+    self.title.rating().map(|rating| rating.rating())
+  }
+
+  #[getter]
+  fn votes(&self) -> Option<u32> {
+    self.title.rating().map(|rating| rating.votes())
+  }
+
+  #[getter]
+  fn genres(&self) -> Vec<String> {
+    self.title.genres().iter().map(|genre| genre.to_string()).collect()
+  }
+
+  fn __repr__(&self) -> String {
+    match self.title.start_year() {
+      Some(year) => format!("<Title {} {} ({year})>", self.title.title_id(), self.title.primary_title()),
+      None => format!("<Title {} {}>", self.title.title_id(), self.title.primary_title()),
+    }
+  }
+}
+
+/// Parse the keywords of a keyword search.
+fn keywords_arg(keywords: &str) -> PyResult<Vec<SearchString>> {
+  let keywords = keywords
+    .split_whitespace()
+    .map(SearchString::try_from)
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| TvrankError::new_err(e.to_string()))?;
+
+  if keywords.is_empty() {
+    return Err(TvrankError::new_err("No keywords to search for"));
+  }
+
+  Ok(keywords)
+}
+
+/// The movies and series databases.
+#[pyclass(module = "tvrank", frozen)]
+struct Imdb {
+  imdb: Arc<ImdbService>,
+}
+
+impl Imdb {
+  fn titles(&self, titles: Vec<&ImdbTitle>) -> Vec<Title> {
+    titles.into_iter().map(|title| Title::new(&self.imdb, title)).collect()
+  }
+}
+
+#[pymethods]
+impl Imdb {
+  /// Load the databases from `cache_dir`, fetching and building them first if they are
+  /// missing, older than a month, or if `force_update` is set.
+  ///
+  /// `progress` is called with the number of bytes downloaded so far and the total number of
+  /// bytes, which is 0 until it is known. An exception raised by it cancels loading, leaving
+  /// the databases as they were, and is raised again. Other threads can run Python code while
+  /// the databases are loaded.
+  #[new]
+  #[pyo3(signature = (cache_dir, force_update = false, progress = None))]
+  fn new(
+    py: Python<'_>,
+    cache_dir: PathBuf,
+    force_update: bool,
+    progress: Option<PyObject>,
+  ) -> PyResult<Self> {
+    let (imdb, progress_err) = py.allow_threads(|| {
+      let total = Cell::new(0);
+      let bytes_read = Cell::new(0);
+      let progress_err = RefCell::new(None);
+      let cancellation = Cancellation::new();
+
+      let progress_fn = |content_len: Option<u64>, delta: u64| {
+        if let Some(content_len) = content_len {
+          total.set(content_len);
+        }
+
+        bytes_read.set(bytes_read.get() + delta);
+
+        if let Some(progress) = &progress {
+          if progress_err.borrow().is_none() {
+            let called = Python::with_gil(|py| progress.call1(py, (bytes_read.get(), total.get())).map(drop));
+            if let Err(e) = called {
+              progress_err.replace(Some(e));
+              cancellation.cancel();
+            }
+          }
+        }
+      };
+
+      let imdb = ImdbService::new_cancellable(
+        &cache_dir,
+        force_update,
+        progress_fn,
+        &cancellation,
+        ImdbCacheCompression::None,
+      );
+      (imdb, progress_err.into_inner())
+    });
+
+    if let Some(e) = progress_err {
+      return Err(e);
+    }
+
+    let imdb = imdb.map_err(|e| TvrankError::new_err(format!("Cannot load the databases: {e}")))?;
+    Ok(Self { imdb: Arc::new(imdb) })
+  }
+
+  /// The number of titles of the given kind.
+  fn n_titles(&self, query: Query) -> usize {
+    self.imdb.n_titles(query.into())
+  }
+
+  /// Look up a title by its IMDB ID in both the movies and series databases.
+  fn by_id(&self, id: &str) -> PyResult<Option<Title>> {
+    let id = ImdbTitleId::try_from(id.trim()).map_err(|e| TvrankError::new_err(e.to_string()))?;
+    Ok(self.imdb.by_id_any(&id).map(|(title, _)| Title::new(&self.imdb, title)))
+  }
+
+  /// Search titles by their exact name, and optionally their release year.
+  #[pyo3(signature = (title, query, year = None))]
+  fn by_title(&self, title: &str, query: Query, year: Option<u16>) -> PyResult<Vec<Title>> {
+    let title = SearchString::try_from(title).map_err(|e| TvrankError::new_err(e.to_string()))?;
+
+    Ok(match year {
+      Some(year) => self.titles(self.imdb.by_title_and_year(&title, year, query.into())),
+      None => self.titles(self.imdb.by_title(&title, query.into())),
+    })
+  }
+
+  /// Search titles by keywords separated by whitespace, and optionally their release year.
+  #[pyo3(signature = (keywords, query, year = None))]
+  fn by_keywords(&self, keywords: &str, query: Query, year: Option<u16>) -> PyResult<Vec<Title>> {
+    let keywords = keywords_arg(keywords)?;
+
+    Ok(match year {
+      Some(year) => self.titles(self.imdb.by_keywords_and_year(&keywords, year, query.into())),
+      None => self.titles(self.imdb.by_keywords(&keywords, query.into())),
+    })
+  }
+}
+
+#[pymodule]
+#[pyo3(name = "tvrank")]
+fn tvrank_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+  m.add("TvrankError", m.py().get_type_bound::<TvrankError>())?;
+  m.add_class::<Query>()?;
+  m.add_class::<Title>()?;
+  m.add_class::<Imdb>()?;
+  Ok(())
+}